@@ -7,6 +7,7 @@ use napi::{
     JsFunction, Status,
 };
 use next_api::{
+    build_stats::build_stats_json,
     entrypoints::Entrypoints,
     operation::{
         EntrypointsOperation, InstrumentationOperation, MiddlewareOperation, OptionEndpoint,
@@ -40,7 +41,9 @@ use turbopack_core::{
     diagnostics::PlainDiagnostic,
     error::PrettyPrintError,
     issue::PlainIssue,
+    module_graph::export_usage::{export_usage_json, merged_export_usage_json},
     output::{OutputAsset, OutputAssets},
+    reference::all_assets_from_entries,
     source_map::{OptionSourceMap, OptionStringifiedSourceMap, SourceMap, Token},
     version::{PartialUpdate, TotalUpdate, Update, VersionState},
     PROJECT_FILESYSTEM_NAME, SOURCE_URL_PROTOCOL,
@@ -148,6 +151,10 @@ pub struct NapiProjectOptions {
     /// The browserslist query to use for targeting browsers.
     pub browserslist_query: String,
 
+    /// The browserslist query of legacy browsers, from the `legacy` environment of the
+    /// browserslist config. Builds emit ES5 chunks for them when it's set.
+    pub legacy_browserslist_query: Option<String>,
+
     /// When the code is minified, this opts out of the default mangling of
     /// local names for variables, functions etc., which can be useful for
     /// debugging/profiling purposes.
@@ -254,6 +261,7 @@ impl From<NapiProjectOptions> for ProjectOptions {
             build_id: val.build_id.into(),
             preview_props: val.preview_props.into(),
             browserslist_query: val.browserslist_query.into(),
+            legacy_browserslist_query: val.legacy_browserslist_query.map(From::from),
             no_mangling: val.no_mangling,
         }
     }
@@ -1474,3 +1482,100 @@ pub fn project_get_source_map_sync(
         tokio::runtime::Handle::current().block_on(project_get_source_map(project, file_path))
     })
 }
+
+#[turbo_tasks::function(operation)]
+async fn export_usage_json_operation(container: ResolvedVc<ProjectContainer>) -> Result<Vc<RcStr>> {
+    let module_graphs = container.project().whole_app_module_graphs().await?;
+    Ok(export_usage_json(*module_graphs.full))
+}
+
+/// Returns the export usage of all modules of the app as JSON, keyed by module ident. This lists
+/// the used exports of each module and the references that use them.
+#[napi]
+pub async fn project_get_export_usage_json(
+    #[napi(ts_arg_type = "{ __napiType: \"Project\" }")] project: External<ProjectInstance>,
+) -> napi::Result<String> {
+    let turbo_tasks = project.turbo_tasks.clone();
+    let container = project.container;
+
+    let json = turbo_tasks
+        .run_once(async move {
+            let json = export_usage_json_operation(container)
+                .read_strongly_consistent()
+                .await?;
+            Ok(json.to_string())
+        })
+        .await
+        .map_err(|e| napi::Error::from_reason(PrettyPrintError(&e).to_string()))?;
+
+    Ok(json)
+}
+
+#[turbo_tasks::function(operation)]
+async fn merged_export_usage_json_operation(
+    container: ResolvedVc<ProjectContainer>,
+) -> Result<Vc<RcStr>> {
+    let module_graphs = container.project().whole_app_module_graphs().await?;
+    Ok(merged_export_usage_json(*module_graphs.full))
+}
+
+/// Returns the export usage of all source files of the app as JSON, keyed by path. The modules
+/// that are created from the same file for the server and the client are merged, and each used
+/// export lists the layers that use it.
+#[napi]
+pub async fn project_get_merged_export_usage_json(
+    #[napi(ts_arg_type = "{ __napiType: \"Project\" }")] project: External<ProjectInstance>,
+) -> napi::Result<String> {
+    let turbo_tasks = project.turbo_tasks.clone();
+    let container = project.container;
+
+    let json = turbo_tasks
+        .run_once(async move {
+            let json = merged_export_usage_json_operation(container)
+                .read_strongly_consistent()
+                .await?;
+            Ok(json.to_string())
+        })
+        .await
+        .map_err(|e| napi::Error::from_reason(PrettyPrintError(&e).to_string()))?;
+
+    Ok(json)
+}
+
+#[turbo_tasks::function(operation)]
+async fn build_stats_json_operation(
+    container: ResolvedVc<ProjectContainer>,
+    app_dir_only: bool,
+) -> Result<Vc<RcStr>> {
+    let project = container.project();
+    let module_graphs = project.whole_app_module_graphs().await?;
+    let output_assets = output_assets_operation(container, app_dir_only).connect();
+    Ok(build_stats_json(
+        all_assets_from_entries(output_assets),
+        *module_graphs.full,
+        project.node_root(),
+    ))
+}
+
+/// Returns a report of the build in the format of webpack's `stats.json` with the emitted assets,
+/// the client chunks and their modules, so bundle analyzer tools can consume it.
+#[napi]
+pub async fn project_get_build_stats_json(
+    #[napi(ts_arg_type = "{ __napiType: \"Project\" }")] project: External<ProjectInstance>,
+    app_dir_only: bool,
+) -> napi::Result<String> {
+    let turbo_tasks = project.turbo_tasks.clone();
+    let container = project.container;
+
+    let json = turbo_tasks
+        .run_once(async move {
+            let json = build_stats_json_operation(container, app_dir_only)
+                .read_strongly_consistent()
+                .await?;
+            Ok(json.to_string())
+        })
+        .await
+        .map_err(|e| napi::Error::from_reason(PrettyPrintError(&e).to_string()))?;
+
+    Ok(json)
+}
@@ -23,9 +23,9 @@ use next_core::{
     next_dynamic::NextDynamicTransition,
     next_edge::route_regex::get_named_middleware_regex,
     next_manifests::{
-        client_reference_manifest::ClientReferenceManifestOptions, AppBuildManifest,
-        AppPathsManifest, BuildManifest, ClientReferenceManifest, EdgeFunctionDefinition,
-        MiddlewareMatcher, MiddlewaresManifestV2, PagesManifest, Regions,
+        client_reference_manifest::ClientReferenceManifestOptions, dynamic_import_chunks,
+        AppBuildManifest, AppPathsManifest, BuildManifest, ClientReferenceManifest,
+        EdgeFunctionDefinition, MiddlewareMatcher, MiddlewaresManifestV2, PagesManifest, Regions,
     },
     next_server::{
         get_server_module_options_context, get_server_resolve_options_context,
@@ -39,8 +39,8 @@ use serde::{Deserialize, Serialize};
 use tracing::Instrument;
 use turbo_rcstr::RcStr;
 use turbo_tasks::{
-    fxindexmap, fxindexset, trace::TraceRawVcs, Completion, FxIndexSet, NonLocalValue, ResolvedVc,
-    TryJoinIterExt, Value, ValueToString, Vc,
+    fxindexmap, fxindexset, trace::TraceRawVcs, Completion, FxIndexMap, FxIndexSet, NonLocalValue,
+    ResolvedVc, TryJoinIterExt, Value, ValueToString, Vc,
 };
 use turbo_tasks_env::{CustomProcessEnv, ProcessEnv};
 use turbo_tasks_fs::{File, FileContent, FileSystemPath};
@@ -201,6 +201,7 @@ impl AppProject {
             self.project().next_config(),
             self.project().encryption_key(),
             self.project().no_mangling(),
+            false,
         ))
     }
 
@@ -1347,9 +1348,23 @@ impl AppEndpoint {
                 server_assets.insert(ResolvedVc::upcast(stats_output));
             }
 
+            let prefetch_files = if *project
+                .next_config()
+                .prefetch_dynamic_imports_enabled()
+                .await?
+            {
+                let client_chunks = Vc::cell(client_assets.iter().copied().collect());
+                fxindexmap!(
+                    app_entry.original_name.clone() =>
+                        dynamic_import_chunks(client_chunks).to_resolved().await?
+                )
+            } else {
+                FxIndexMap::default()
+            };
             let build_manifest = BuildManifest {
                 root_main_files: client_shared_chunks,
                 polyfill_files: vec![polyfill_output_asset],
+                prefetch_files,
                 ..Default::default()
             };
             let build_manifest_output = ResolvedVc::upcast(
@@ -0,0 +1,261 @@
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexMap, FxIndexSet, ResolvedVc, TryJoinIterExt, ValueToString, Vc};
+use turbo_tasks_fs::FileSystemPath;
+use turbopack_browser::ecmascript::EcmascriptBrowserChunk;
+use turbopack_core::{
+    chunk::{Chunk, ChunkItem, ChunkingContext},
+    module::Module,
+    module_graph::{
+        export_usage::{ExportUsage, ModuleExportUsageInfo},
+        ModuleGraph,
+    },
+    output::{OutputAsset, OutputAssets},
+    reference::primary_chunkable_referenced_modules_with_export_usage,
+};
+use turbopack_ecmascript::chunk::EcmascriptChunkItem;
+
+/// Serializes a report of the build in the format of webpack's `stats.json`, so bundle analyzer
+/// tools can consume it. It lists the emitted assets, the client chunks and the modules in them
+/// with their sizes, the modules that import them and their used exports.
+///
+/// Modules are minified as part of their chunks, so the minified size of a module is estimated
+/// from the size of its code relative to the other modules in the chunk.
+#[turbo_tasks::function]
+pub async fn build_stats_json(
+    output_assets: Vc<OutputAssets>,
+    module_graph: Vc<ModuleGraph>,
+    output_root: Vc<FileSystemPath>,
+) -> Result<Vc<RcStr>> {
+    let output_root = output_root.await?;
+
+    let mut assets = vec![];
+    let mut chunks = vec![];
+    let mut modules = FxIndexMap::<ResolvedVc<Box<dyn Module>>, BuildStatsModule>::default();
+    for &asset in output_assets.await?.iter() {
+        let asset_path = asset.path().await?;
+        let name: RcStr = output_root
+            .get_path_to(&asset_path)
+            .unwrap_or(asset_path.path.as_str())
+            .into();
+        let Some(size) = *asset.size_bytes().await? else {
+            continue;
+        };
+
+        let mut asset_chunks = vec![];
+        if let Some(chunk) = ResolvedVc::try_downcast_type::<EcmascriptBrowserChunk>(asset) {
+            let chunk_items = chunk
+                .chunk()
+                .chunk_items()
+                .await?
+                .iter()
+                .map(async |&chunk_item| {
+                    let module = chunk_item.module().to_resolved().await?;
+                    let id = chunk_item
+                        .chunking_context()
+                        .chunk_item_id(*chunk_item)
+                        .to_string()
+                        .owned()
+                        .await?;
+                    Ok((module, id, chunk_item_size(chunk_item).await?))
+                })
+                .try_join()
+                .await?;
+
+            let total_size = chunk_items.iter().map(|(_, _, size)| size).sum::<u64>();
+            for (module, id, module_size) in chunk_items {
+                if !modules.contains_key(&module) {
+                    let ident = module.ident().to_string().owned().await?;
+                    let module_path = module.ident().path().await?;
+                    modules.insert(
+                        module,
+                        BuildStatsModule {
+                            id,
+                            identifier: ident,
+                            name: output_root
+                                .get_path_to(&module_path)
+                                .unwrap_or(module_path.path.as_str())
+                                .into(),
+                            size: module_size,
+                            minified_size: if total_size == 0 {
+                                0
+                            } else {
+                                size * module_size / total_size
+                            },
+                            chunks: vec![],
+                            reasons: vec![],
+                            used_exports: BuildStatsUsedExports::All(true),
+                        },
+                    );
+                }
+                modules[&module].chunks.push(name.clone());
+            }
+
+            chunks.push(BuildStatsChunk {
+                id: name.clone(),
+                files: vec![name.clone()],
+                size,
+                modules: vec![],
+            });
+            asset_chunks.push(name.clone());
+        }
+
+        assets.push(BuildStatsAsset {
+            name,
+            size,
+            chunks: asset_chunks,
+        });
+    }
+
+    for chunk in &mut chunks {
+        chunk.modules = modules
+            .values()
+            .filter(|module| module.chunks.contains(&chunk.id))
+            .map(|module| module.id.clone())
+            .collect();
+    }
+
+    let export_usage_info = module_graph.export_usage_info().await?;
+    for (module, stats) in &mut modules {
+        stats.used_exports = match export_usage_info.used_exports(*module) {
+            ModuleExportUsageInfo::Evaluation => BuildStatsUsedExports::Exports(vec![]),
+            ModuleExportUsageInfo::Exports(exports) => {
+                let mut exports = exports.into_iter().collect::<Vec<_>>();
+                exports.sort();
+                BuildStatsUsedExports::Exports(exports)
+            }
+            ModuleExportUsageInfo::All => BuildStatsUsedExports::All(true),
+        };
+    }
+
+    // The importers of the modules are the reasons for including them
+    let graphs = module_graph.await?.get_graphs().await?;
+    let importers = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|node| node.module)
+        .collect::<FxIndexSet<_>>();
+    let references = importers
+        .into_iter()
+        .map(async |importer| {
+            let references =
+                primary_chunkable_referenced_modules_with_export_usage(*importer).await?;
+            Ok((importer, references))
+        })
+        .try_join()
+        .await?;
+    let mut importer_idents = FxHashMap::default();
+    for (importer, references) in &references {
+        for (usage, targets) in references.iter() {
+            for target in targets {
+                let Some(stats) = modules.get_mut(target) else {
+                    continue;
+                };
+                let module_identifier = match importer_idents.get(importer) {
+                    Some(ident) => ident.clone(),
+                    None => {
+                        let ident = importer.ident().to_string().owned().await?;
+                        importer_idents.insert(*importer, ident.clone());
+                        ident
+                    }
+                };
+                stats.reasons.push(BuildStatsReason {
+                    module_identifier,
+                    ty: reason_type(usage).into(),
+                });
+            }
+        }
+    }
+
+    let stats = BuildStats {
+        assets,
+        chunks,
+        modules: modules.into_values().collect(),
+    };
+    Ok(Vc::cell(serde_json::to_string_pretty(&stats)?.into()))
+}
+
+/// The size of the code of a chunk item before minification.
+async fn chunk_item_size(chunk_item: ResolvedVc<Box<dyn ChunkItem>>) -> Result<u64> {
+    let Some(chunk_item) = ResolvedVc::try_downcast::<Box<dyn EcmascriptChunkItem>>(chunk_item)
+    else {
+        return Ok(0);
+    };
+    Ok(chunk_item
+        .content()
+        .await
+        .map_or(0, |content| content.inner_code.len() as u64))
+}
+
+fn reason_type(usage: &ExportUsage) -> &'static str {
+    match usage {
+        ExportUsage::Evaluation => "side effect import",
+        ExportUsage::Named(_) => "named import",
+        ExportUsage::DefaultExportProperties(_) => "default import",
+        ExportUsage::ReExport { .. } => "named re-export",
+        ExportUsage::ReExportNamespace(_) => "namespace re-export",
+        ExportUsage::ReExportAll => "star re-export",
+        ExportUsage::NamespaceProperties(_) => "namespace import",
+        ExportUsage::All => "import",
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildStats {
+    assets: Vec<BuildStatsAsset>,
+    chunks: Vec<BuildStatsChunk>,
+    modules: Vec<BuildStatsModule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildStatsAsset {
+    name: RcStr,
+    size: u64,
+    chunks: Vec<RcStr>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildStatsChunk {
+    id: RcStr,
+    files: Vec<RcStr>,
+    size: u64,
+    /// The ids of the modules in the chunk.
+    modules: Vec<RcStr>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildStatsModule {
+    id: RcStr,
+    identifier: RcStr,
+    name: RcStr,
+    /// The size of the generated code before minification.
+    size: u64,
+    /// The estimated size of the generated code after minification.
+    minified_size: u64,
+    chunks: Vec<RcStr>,
+    reasons: Vec<BuildStatsReason>,
+    used_exports: BuildStatsUsedExports,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildStatsReason {
+    module_identifier: RcStr,
+    #[serde(rename = "type")]
+    ty: RcStr,
+}
+
+/// Like in webpack's stats, `true` when all exports might be used, and the used exports
+/// otherwise.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BuildStatsUsedExports {
+    All(bool),
+    Exports(Vec<RcStr>),
+}
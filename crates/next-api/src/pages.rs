@@ -12,8 +12,8 @@ use next_core::{
     next_dynamic::NextDynamicTransition,
     next_edge::route_regex::get_named_middleware_regex,
     next_manifests::{
-        BuildManifest, EdgeFunctionDefinition, MiddlewareMatcher, MiddlewaresManifestV2,
-        PagesManifest,
+        dynamic_import_chunks, BuildManifest, EdgeFunctionDefinition, MiddlewareMatcher,
+        MiddlewaresManifestV2, PagesManifest,
     },
     next_pages::create_page_ssr_entry_module,
     next_server::{
@@ -338,12 +338,22 @@ impl PagesProject {
         Vc::upcast(FullContextTransition::new(self.client_module_context()))
     }
 
+    /// The module options of the client modules. `legacy` compiles them to ES5 for the legacy
+    /// `nomodule` chunks.
     #[turbo_tasks::function]
-    async fn client_module_options_context(self: Vc<Self>) -> Result<Vc<ModuleOptionsContext>> {
+    async fn client_module_options_context(
+        self: Vc<Self>,
+        legacy: bool,
+    ) -> Result<Vc<ModuleOptionsContext>> {
+        let compile_time_info = if legacy {
+            self.project().legacy_client_compile_time_info()
+        } else {
+            self.project().client_compile_time_info()
+        };
         Ok(get_client_module_options_context(
             self.project().project_path(),
             self.project().execution_context(),
-            self.project().client_compile_time_info().environment(),
+            compile_time_info.environment(),
             Value::new(ClientContextType::Pages {
                 pages_dir: self.pages_dir().to_resolved().await?,
             }),
@@ -351,6 +361,7 @@ impl PagesProject {
             self.project().next_config(),
             self.project().encryption_key(),
             self.project().no_mangling(),
+            legacy,
         ))
     }
 
@@ -372,12 +383,33 @@ impl PagesProject {
         ModuleAssetContext::new(
             self.client_transitions(),
             self.project().client_compile_time_info(),
-            self.client_module_options_context(),
+            self.client_module_options_context(false),
+            self.client_resolve_options_context(),
+            client_layer(),
+        )
+    }
+
+    /// The module context of the legacy `nomodule` chunks, which targets the legacy browserslist.
+    #[turbo_tasks::function]
+    fn legacy_client_module_context(self: Vc<Self>) -> Vc<ModuleAssetContext> {
+        ModuleAssetContext::new(
+            self.client_transitions(),
+            self.project().legacy_client_compile_time_info(),
+            self.client_module_options_context(true),
             self.client_resolve_options_context(),
             client_layer(),
         )
     }
 
+    #[turbo_tasks::function]
+    fn client_module_context_for(self: Vc<Self>, legacy: bool) -> Vc<ModuleAssetContext> {
+        if legacy {
+            self.legacy_client_module_context()
+        } else {
+            self.client_module_context()
+        }
+    }
+
     #[turbo_tasks::function]
     pub(super) fn ssr_module_context(self: Vc<Self>) -> Vc<ModuleAssetContext> {
         ModuleAssetContext::new(
@@ -571,7 +603,7 @@ impl PagesProject {
     }
 
     #[turbo_tasks::function]
-    async fn client_runtime_entries(self: Vc<Self>) -> Result<Vc<EvaluatableAssets>> {
+    async fn client_runtime_entries(self: Vc<Self>, legacy: bool) -> Result<Vc<EvaluatableAssets>> {
         let client_runtime_entries = get_client_runtime_entries(
             self.project().project_path(),
             Value::new(ClientContextType::Pages {
@@ -581,7 +613,8 @@ impl PagesProject {
             self.project().next_config(),
             self.project().execution_context(),
         );
-        Ok(client_runtime_entries.resolve_entries(Vc::upcast(self.client_module_context())))
+        Ok(client_runtime_entries
+            .resolve_entries(Vc::upcast(self.client_module_context_for(legacy))))
     }
 
     #[turbo_tasks::function]
@@ -629,8 +662,8 @@ impl PagesProject {
     }
 
     #[turbo_tasks::function]
-    pub async fn client_main_module(self: Vc<Self>) -> Result<Vc<Box<dyn Module>>> {
-        let client_module_context = Vc::upcast(self.client_module_context());
+    pub async fn client_main_module(self: Vc<Self>, legacy: bool) -> Result<Vc<Box<dyn Module>>> {
+        let client_module_context = Vc::upcast(self.client_module_context_for(legacy));
 
         let client_main_module = esm_resolve(
             Vc::upcast(PlainResolveOrigin::new(
@@ -734,10 +767,10 @@ impl PageEndpoint {
     }
 
     #[turbo_tasks::function]
-    async fn client_module(self: Vc<Self>) -> Result<Vc<Box<dyn Module>>> {
+    async fn client_module(self: Vc<Self>, legacy: bool) -> Result<Vc<Box<dyn Module>>> {
         let this = self.await?;
         let page_loader = create_page_loader_entry_module(
-            Vc::upcast(this.pages_project.client_module_context()),
+            Vc::upcast(this.pages_project.client_module_context_for(legacy)),
             self.source(),
             *this.pathname,
         );
@@ -756,11 +789,14 @@ impl PageEndpoint {
     }
 
     #[turbo_tasks::function]
-    async fn client_evaluatable_assets(self: Vc<Self>) -> Result<Vc<EvaluatableAssets>> {
+    async fn client_evaluatable_assets(
+        self: Vc<Self>,
+        legacy: bool,
+    ) -> Result<Vc<EvaluatableAssets>> {
         let this = self.await?;
 
-        let client_module = self.client_module();
-        let client_main_module = this.pages_project.client_main_module();
+        let client_module = self.client_module(legacy);
+        let client_main_module = this.pages_project.client_main_module(legacy);
 
         let Some(client_module) =
             Vc::try_resolve_sidecast::<Box<dyn EvaluatableAsset>>(client_module).await?
@@ -776,7 +812,7 @@ impl PageEndpoint {
 
         let evaluatable_assets = this
             .pages_project
-            .client_runtime_entries()
+            .client_runtime_entries(legacy)
             .with_entry(client_main_module)
             .with_entry(client_module);
         Ok(evaluatable_assets)
@@ -786,7 +822,7 @@ impl PageEndpoint {
     async fn client_module_graph(self: Vc<Self>) -> Result<Vc<ModuleGraph>> {
         let this = self.await?;
         let project = this.pages_project.project();
-        let evaluatable_assets = self.client_evaluatable_assets();
+        let evaluatable_assets = self.client_evaluatable_assets(false);
         Ok(project.module_graph_for_modules(evaluatable_assets))
     }
 
@@ -839,7 +875,7 @@ impl PageEndpoint {
             let module_graph = self.client_module_graph();
 
             let evaluatable_assets = self
-                .client_evaluatable_assets()
+                .client_evaluatable_assets(false)
                 .await?
                 .iter()
                 .map(|m| ResolvedVc::upcast(*m))
@@ -857,6 +893,39 @@ impl PageEndpoint {
         .await
     }
 
+    /// The ES5 chunks of the page for browsers without module support, which are loaded with
+    /// `nomodule` next to the modern chunks. They have a module graph of their own, as the modules
+    /// are compiled for the legacy browserslist.
+    #[turbo_tasks::function]
+    async fn legacy_client_chunks(self: Vc<Self>) -> Result<Vc<ChunkGroupResult>> {
+        async move {
+            let this = self.await?;
+
+            let project = this.pages_project.project();
+            let legacy_client_chunking_context = project.legacy_client_chunking_context();
+
+            let evaluatable_assets: Vec<_> = self
+                .client_evaluatable_assets(true)
+                .await?
+                .iter()
+                .map(|m| ResolvedVc::upcast(*m))
+                .collect();
+            let module_graph = ModuleGraph::from_modules(Vc::cell(vec![ChunkGroupEntry::Entry(
+                evaluatable_assets.clone(),
+            )]));
+            let legacy_client_chunk_group = legacy_client_chunking_context.evaluated_chunk_group(
+                AssetIdent::from_path(*this.page.await?.base_path),
+                ChunkGroup::Entry(evaluatable_assets),
+                module_graph,
+                Value::new(AvailabilityInfo::Root),
+            );
+
+            Ok(legacy_client_chunk_group)
+        }
+        .instrument(tracing::info_span!("page legacy client side rendering"))
+        .await
+    }
+
     #[turbo_tasks::function]
     async fn page_loader(
         self: Vc<Self>,
@@ -997,7 +1066,7 @@ impl PageEndpoint {
                     *project.per_page_module_graph().await?,
                 );
                 let next_dynamic_imports = reduced_graphs
-                    .get_next_dynamic_imports_for_endpoint(self.client_module())
+                    .get_next_dynamic_imports_for_endpoint(self.client_module(false))
                     .await?;
                 Some((next_dynamic_imports, client_availability_info))
             } else {
@@ -1236,11 +1305,30 @@ impl PageEndpoint {
     async fn build_manifest(
         &self,
         client_chunks: ResolvedVc<OutputAssets>,
+        legacy_client_chunks: Option<ResolvedVc<OutputAssets>>,
     ) -> Result<Vc<Box<dyn OutputAsset>>> {
-        let node_root = self.pages_project.project().node_root();
-        let client_relative_path = self.pages_project.project().client_relative_path();
+        let project = self.pages_project.project();
+        let node_root = project.node_root();
+        let client_relative_path = project.client_relative_path();
+        let pathname = self.pathname.owned().await?;
+        let prefetch_files = if *project
+            .next_config()
+            .prefetch_dynamic_imports_enabled()
+            .await?
+        {
+            fxindexmap!(
+                pathname.clone() => dynamic_import_chunks(*client_chunks).to_resolved().await?
+            )
+        } else {
+            FxIndexMap::default()
+        };
+        let legacy_pages = legacy_client_chunks
+            .map(|legacy_client_chunks| fxindexmap!(pathname.clone() => legacy_client_chunks))
+            .unwrap_or_default();
         let build_manifest = BuildManifest {
-            pages: fxindexmap!(self.pathname.owned().await? => client_chunks),
+            pages: fxindexmap!(pathname => client_chunks),
+            prefetch_files,
+            legacy_pages,
             ..Default::default()
         };
         let manifest_path_prefix = get_asset_prefix_from_pathname(&self.pathname.await?);
@@ -1267,7 +1355,23 @@ impl PageEndpoint {
             PageEndpointType::Html => {
                 let client_chunks = *self.client_chunks().await?.assets;
                 client_assets.extend(client_chunks.await?.iter().map(|asset| **asset));
-                let build_manifest = self.build_manifest(client_chunks).to_resolved().await?;
+                let legacy_client_chunks = if this
+                    .pages_project
+                    .project()
+                    .legacy_browserslist_query()
+                    .await?
+                    .is_some()
+                {
+                    let legacy_client_chunks = self.legacy_client_chunks().await?.assets;
+                    client_assets.extend(legacy_client_chunks.await?.iter().map(|asset| **asset));
+                    Some(legacy_client_chunks)
+                } else {
+                    None
+                };
+                let build_manifest = self
+                    .build_manifest(client_chunks, legacy_client_chunks)
+                    .to_resolved()
+                    .await?;
                 let page_loader = self.page_loader(client_chunks);
                 client_assets.push(page_loader);
                 server_assets.push(build_manifest);
@@ -1581,7 +1685,7 @@ impl Endpoint for PageEndpoint {
             ])))
             .chain(if this.ty == PageEndpointType::Html {
                 Some(ChunkGroupEntry::Entry(
-                    self.client_evaluatable_assets()
+                    self.client_evaluatable_assets(false)
                         .await?
                         .iter()
                         .map(|m| ResolvedVc::upcast(*m))
@@ -10,7 +10,7 @@ use next_core::{
     instrumentation::instrumentation_files,
     middleware::middleware_files,
     mode::NextMode,
-    next_client::{get_client_chunking_context, get_client_compile_time_info},
+    next_client::{get_client_chunking_context, get_client_compile_time_info, ClientOutput},
     next_config::{JsConfig, ModuleIds as ModuleIdStrategyConfig, NextConfig},
     next_server::{
         get_server_chunking_context, get_server_chunking_context_with_client_assets,
@@ -35,13 +35,18 @@ use turbo_tasks::{
 use turbo_tasks_env::{EnvMap, ProcessEnv};
 use turbo_tasks_fs::{invalidation, DiskFileSystem, FileSystem, FileSystemPath, VirtualFileSystem};
 use turbopack::{
-    evaluate_context::node_build_environment, global_module_ids::get_global_module_id_strategy,
-    transition::TransitionOptions, ModuleAssetContext,
+    evaluate_context::node_build_environment,
+    global_module_ids::{
+        get_global_module_id_strategy, get_hashed_module_id_strategy, get_hashed_module_ids,
+        HashedModuleIds,
+    },
+    transition::TransitionOptions,
+    ModuleAssetContext,
 };
 use turbopack_core::{
     changed::content_changed,
     chunk::{
-        module_id_strategies::{DevModuleIdStrategy, ModuleIdStrategy},
+        module_id_strategies::{DevModuleIdStrategy, HashedModuleIdStrategy, ModuleIdStrategy},
         ChunkingContext, EvaluatableAssets, SourceMapsType,
     },
     compile_time_info::CompileTimeInfo,
@@ -178,6 +183,10 @@ pub struct ProjectOptions {
     /// The browserslist query to use for targeting browsers.
     pub browserslist_query: RcStr,
 
+    /// The browserslist query of legacy browsers. When it's set, production builds also emit the
+    /// client chunks of pages compiled to ES5, which are loaded with `<script nomodule>`.
+    pub legacy_browserslist_query: Option<RcStr>,
+
     /// When the code is minified, this opts out of the default mangling of
     /// local names for variables, functions etc., which can be useful for
     /// debugging/profiling purposes.
@@ -437,6 +446,7 @@ impl ProjectContainer {
         let build_id;
         let preview_props;
         let browserslist_query;
+        let legacy_browserslist_query;
         let no_mangling;
         {
             let options = self.options_state.get();
@@ -460,6 +470,7 @@ impl ProjectContainer {
             build_id = options.build_id.clone();
             preview_props = options.preview_props.clone();
             browserslist_query = options.browserslist_query.clone();
+            legacy_browserslist_query = options.legacy_browserslist_query.clone();
             no_mangling = options.no_mangling
         }
 
@@ -479,6 +490,7 @@ impl ProjectContainer {
             env: ResolvedVc::upcast(env_map.to_resolved().await?),
             define_env: define_env.to_resolved().await?,
             browserslist_query,
+            legacy_browserslist_query,
             mode: if dev {
                 NextMode::Development.resolved_cell()
             } else {
@@ -552,6 +564,9 @@ pub struct Project {
     /// The browserslist query to use for targeting browsers.
     browserslist_query: RcStr,
 
+    /// The browserslist query of legacy browsers, see [ProjectOptions::legacy_browserslist_query].
+    legacy_browserslist_query: Option<RcStr>,
+
     mode: ResolvedVc<NextMode>,
 
     versioned_content_map: Option<ResolvedVc<VersionedContentMap>>,
@@ -801,6 +816,28 @@ impl Project {
         get_client_compile_time_info(self.browserslist_query.clone(), self.define_env.client())
     }
 
+    /// The browserslist query of the legacy client output. It's only emitted in production builds.
+    #[turbo_tasks::function]
+    pub(super) async fn legacy_browserslist_query(&self) -> Result<Vc<Option<RcStr>>> {
+        Ok(Vc::cell(match *self.mode.await? {
+            NextMode::Build => self.legacy_browserslist_query.clone(),
+            NextMode::Development => None,
+        }))
+    }
+
+    #[turbo_tasks::function]
+    pub(super) async fn legacy_client_compile_time_info(
+        self: Vc<Self>,
+    ) -> Result<Vc<CompileTimeInfo>> {
+        let Some(browserslist_query) = self.legacy_browserslist_query().owned().await? else {
+            bail!("The legacy client output requires a legacy browserslist query");
+        };
+        Ok(get_client_compile_time_info(
+            browserslist_query,
+            self.await?.define_env.client(),
+        ))
+    }
+
     #[turbo_tasks::function]
     pub async fn get_all_endpoints(self: Vc<Self>, app_dir_only: bool) -> Result<Vc<Endpoints>> {
         let mut endpoints = Vec::new();
@@ -986,8 +1023,15 @@ impl Project {
     }
 
     #[turbo_tasks::function]
-    pub(super) fn client_chunking_context(self: Vc<Self>) -> Vc<Box<dyn ChunkingContext>> {
-        get_client_chunking_context(
+    pub(super) async fn client_chunking_context(
+        self: Vc<Self>,
+    ) -> Result<Vc<Box<dyn ChunkingContext>>> {
+        let output = if self.legacy_browserslist_query().await?.is_some() {
+            ClientOutput::Modern
+        } else {
+            ClientOutput::Default
+        };
+        Ok(get_client_chunking_context(
             self.project_root_path(),
             self.client_relative_path(),
             Vc::cell("/ROOT".into()),
@@ -999,6 +1043,48 @@ impl Project {
             self.next_config().turbo_minify(self.next_mode()),
             self.next_config().client_source_maps(self.next_mode()),
             self.no_mangling(),
+            self.next_config().turbo_mangle_properties(),
+            self.next_config().turbo_client_chunking_config(),
+            self.next_config().runtime_chunk_enabled(),
+            self.next_config().turbo_client_content_hashing(),
+            self.next_config().scope_hoisting_enabled(self.next_mode()),
+            self.next_config()
+                .json_tree_shaking_enabled(self.next_mode()),
+            Value::new(output),
+        ))
+    }
+
+    /// The chunking context of the legacy client output. The chunks of each page are computed
+    /// from a module graph of the page only, so the module ids are hashes of the module idents,
+    /// which are the same in all pages, and optimizations that depend on the whole module graph
+    /// are disabled.
+    #[turbo_tasks::function]
+    pub(super) fn legacy_client_chunking_context(self: Vc<Self>) -> Vc<Box<dyn ChunkingContext>> {
+        get_client_chunking_context(
+            self.project_root_path(),
+            self.client_relative_path(),
+            Vc::cell("/ROOT".into()),
+            self.next_config().computed_asset_prefix(),
+            self.next_config().chunk_suffix_path(),
+            self.legacy_client_compile_time_info().environment(),
+            self.next_mode(),
+            Vc::upcast(
+                HashedModuleIdStrategy {
+                    hash_length: 16,
+                    collision_ids: Default::default(),
+                }
+                .cell(),
+            ),
+            self.next_config().turbo_minify(self.next_mode()),
+            self.next_config().client_source_maps(self.next_mode()),
+            self.no_mangling(),
+            Vc::cell(None),
+            self.next_config().turbo_client_chunking_config(),
+            self.next_config().runtime_chunk_enabled(),
+            self.next_config().turbo_client_content_hashing(),
+            Vc::cell(false),
+            Vc::cell(false),
+            Value::new(ClientOutput::Legacy),
         )
     }
 
@@ -1020,6 +1106,9 @@ impl Project {
                 self.next_config().turbo_minify(self.next_mode()),
                 self.next_config().server_source_maps(),
                 self.no_mangling(),
+                self.next_config().scope_hoisting_enabled(self.next_mode()),
+                self.next_config()
+                    .json_tree_shaking_enabled(self.next_mode()),
             )
         } else {
             get_server_chunking_context(
@@ -1032,6 +1121,9 @@ impl Project {
                 self.next_config().turbo_minify(self.next_mode()),
                 self.next_config().server_source_maps(),
                 self.no_mangling(),
+                self.next_config().scope_hoisting_enabled(self.next_mode()),
+                self.next_config()
+                    .json_tree_shaking_enabled(self.next_mode()),
             )
         }
     }
@@ -1054,6 +1146,9 @@ impl Project {
                 self.next_config().turbo_minify(self.next_mode()),
                 self.next_config().server_source_maps(),
                 self.no_mangling(),
+                self.next_config().scope_hoisting_enabled(self.next_mode()),
+                self.next_config()
+                    .json_tree_shaking_enabled(self.next_mode()),
             )
         } else {
             get_edge_chunking_context(
@@ -1066,6 +1161,9 @@ impl Project {
                 self.next_config().turbo_minify(self.next_mode()),
                 self.next_config().server_source_maps(),
                 self.no_mangling(),
+                self.next_config().scope_hoisting_enabled(self.next_mode()),
+                self.next_config()
+                    .json_tree_shaking_enabled(self.next_mode()),
             )
         }
     }
@@ -1578,6 +1676,16 @@ impl Project {
             let client_relative_path = self.client_relative_path();
             let node_root = self.node_root();
 
+            // The ids are written next to the output, for the next build to keep them
+            if matches!(
+                *self.module_id_strategy_config().await?,
+                ModuleIdStrategyConfig::Hashed
+            ) {
+                self.hashed_module_ids()
+                    .write(self.hashed_module_ids_path())
+                    .await?;
+            }
+
             if let Some(map) = self.await?.versioned_content_map {
                 let _ = map
                     .insert_output_assets(
@@ -1703,7 +1811,10 @@ impl Project {
     pub async fn client_main_modules(self: Vc<Self>) -> Result<Vc<GraphEntries>> {
         let pages_project = self.pages_project();
         let mut modules = vec![ChunkGroupEntry::Entry(vec![
-            pages_project.client_main_module().to_resolved().await?,
+            pages_project
+                .client_main_module(false)
+                .to_resolved()
+                .await?,
         ])];
 
         if let Some(app_project) = *self.app_project().await? {
@@ -1715,10 +1826,10 @@ impl Project {
         Ok(Vc::cell(modules))
     }
 
-    /// Gets the module id strategy for the project.
+    /// The configured module id strategy, or the default one of the mode.
     #[turbo_tasks::function]
-    pub async fn module_ids(self: Vc<Self>) -> Result<Vc<Box<dyn ModuleIdStrategy>>> {
-        let module_id_strategy =
+    async fn module_id_strategy_config(self: Vc<Self>) -> Result<Vc<ModuleIdStrategyConfig>> {
+        Ok(
             if let Some(module_id_strategy) = &*self.next_config().module_ids().await? {
                 *module_id_strategy
             } else {
@@ -1726,9 +1837,15 @@ impl Project {
                     NextMode::Development => ModuleIdStrategyConfig::Named,
                     NextMode::Build => ModuleIdStrategyConfig::Deterministic,
                 }
-            };
+            }
+            .cell(),
+        )
+    }
 
-        match module_id_strategy {
+    /// Gets the module id strategy for the project.
+    #[turbo_tasks::function]
+    pub async fn module_ids(self: Vc<Self>) -> Result<Vc<Box<dyn ModuleIdStrategy>>> {
+        match *self.module_id_strategy_config().await? {
             ModuleIdStrategyConfig::Named => Ok(Vc::upcast(DevModuleIdStrategy::new())),
             ModuleIdStrategyConfig::Deterministic => {
                 let module_graphs = self.whole_app_module_graphs().await?;
@@ -1736,8 +1853,37 @@ impl Project {
                     *module_graphs.full,
                 )))
             }
+            ModuleIdStrategyConfig::Hashed => {
+                let module_graphs = self.whole_app_module_graphs().await?;
+                Ok(Vc::upcast(get_hashed_module_id_strategy(
+                    *module_graphs.full,
+                    *self.next_config().module_id_hash_length().await?,
+                    self.hashed_module_ids(),
+                )))
+            }
         }
     }
+
+    /// The file that records the ids of the hashed module id strategy, see
+    /// [Project::hashed_module_ids].
+    #[turbo_tasks::function]
+    fn hashed_module_ids_path(self: Vc<Self>) -> Vc<FileSystemPath> {
+        self.node_root()
+            .join("cache/turbopack/hashed-module-ids.json".into())
+    }
+
+    /// The ids of the hashed module id strategy. The ids of the previous build are kept, so that
+    /// adding a module whose id collides with an existing one only changes the id of the new
+    /// module. They are written by [Project::emit_all_output_assets] for the next build.
+    #[turbo_tasks::function]
+    async fn hashed_module_ids(self: Vc<Self>) -> Result<Vc<HashedModuleIds>> {
+        let module_graphs = self.whole_app_module_graphs().await?;
+        Ok(get_hashed_module_ids(
+            *module_graphs.full,
+            *self.next_config().module_id_hash_length().await?,
+            HashedModuleIds::read(self.hashed_module_ids_path()),
+        ))
+    }
 }
 
 // This is a performance optimization. This function is a root aggregation function that
@@ -165,6 +165,7 @@ fn main() {
                 browserslist_query: "last 1 Chrome versions, last 1 Firefox versions, last 1 \
                                      Safari versions, last 1 Edge versions"
                     .into(),
+                legacy_browserslist_query: None,
                 no_mangling: false,
             };
 
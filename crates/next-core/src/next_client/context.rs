@@ -14,13 +14,12 @@ use turbopack::{
     resolve_options_context::ResolveOptionsContext,
 };
 use turbopack_browser::{
-    react_refresh::assert_can_resolve_react_refresh, BrowserChunkingContext, ContentHashing,
-    CurrentChunkMethod,
+    react_refresh::assert_can_resolve_react_refresh, BrowserChunkingContext, CurrentChunkMethod,
 };
 use turbopack_core::{
     chunk::{
         module_id_strategies::ModuleIdStrategy, ChunkingConfig, ChunkingContext, MangleType,
-        MinifyType, SourceMapsType,
+        MinifyOptions, MinifyType, SourceMapsType,
     },
     compile_time_info::{
         CompileTimeDefineValue, CompileTimeDefines, CompileTimeInfo, DefineableNameSegment,
@@ -41,7 +40,9 @@ use crate::{
     mode::NextMode,
     next_build::get_postcss_package_mapping,
     next_client::runtime_entry::{RuntimeEntries, RuntimeEntry},
-    next_config::NextConfig,
+    next_config::{
+        ClientChunkingConfig, ClientContentHashing, NextConfig, OptionManglePropertiesOptions,
+    },
     next_font::local::NextFontLocalResolvePlugin,
     next_import_map::{
         get_next_client_fallback_import_map, get_next_client_import_map,
@@ -60,7 +61,7 @@ use crate::{
             styled_jsx::get_styled_jsx_transform_rule,
             swc_ecma_transform_plugins::get_swc_ecma_transform_plugin_rule,
         },
-        webpack_rules::webpack_loader_options,
+        webpack_rules::{issuer_resolve_conditions, webpack_loader_options},
     },
     transform_options::{
         get_decorators_transform_options, get_jsx_transform_options,
@@ -221,6 +222,12 @@ pub async fn get_client_resolve_options_context(
             foreign_code_context_condition(next_config, project_path).await?,
             resolve_options_context.clone().resolved_cell(),
         )],
+        issuer_conditions: issuer_resolve_conditions(
+            project_path,
+            next_config,
+            vec!["browser".into(), mode.await?.condition().into()],
+        )
+        .await?,
         ..resolve_options_context
     }
     .cell())
@@ -236,6 +243,7 @@ pub async fn get_client_module_options_context(
     next_config: Vc<NextConfig>,
     encryption_key: ResolvedVc<RcStr>,
     no_mangling: Vc<bool>,
+    legacy: bool,
 ) -> Result<Vc<ModuleOptionsContext>> {
     let next_mode = mode.await?;
     let resolve_options_context = get_client_resolve_options_context(
@@ -345,6 +353,7 @@ pub async fn get_client_module_options_context(
             ..Default::default()
         },
         preset_env_versions: Some(env),
+        preset_env_es5: legacy,
         execution_context: Some(execution_context),
         tree_shaking_mode: tree_shaking_mode_for_user_code,
         enable_postcss_transform,
@@ -393,9 +402,10 @@ pub async fn get_client_module_options_context(
         enable_mdx_rs,
         css: CssOptionsContext {
             minify_type: if *next_config.turbo_minify(mode).await? {
-                MinifyType::Minify {
+                MinifyType::Minify(MinifyOptions {
                     mangle: (!*no_mangling.await?).then_some(MangleType::OptimalSize),
-                }
+                    mangle_properties: None,
+                })
             } else {
                 MinifyType::NoMinify
             },
@@ -419,6 +429,20 @@ pub async fn get_client_module_options_context(
     Ok(module_options_context)
 }
 
+/// The variant of the client output of a chunking context. With a legacy browserslist query, a
+/// modern variant, which is loaded with `<script type="module">`, and a legacy variant compiled to
+/// ES5, which is loaded with `<script nomodule>`, are emitted.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash)]
+pub enum ClientOutput {
+    /// The only output, loaded with classic scripts.
+    Default,
+    /// The output for browsers that support module scripts.
+    Modern,
+    /// The ES5 output in `static/chunks/legacy` for browsers without module scripts.
+    Legacy,
+}
+
 #[turbo_tasks::function]
 pub async fn get_client_chunking_context(
     root_path: ResolvedVc<FileSystemPath>,
@@ -432,15 +456,29 @@ pub async fn get_client_chunking_context(
     minify: Vc<bool>,
     source_maps: Vc<bool>,
     no_mangling: Vc<bool>,
+    mangle_properties: Vc<OptionManglePropertiesOptions>,
+    chunking_config: Vc<ClientChunkingConfig>,
+    separate_runtime_chunk: Vc<bool>,
+    content_hashing: Vc<ClientContentHashing>,
+    scope_hoisting: Vc<bool>,
+    json_tree_shaking: Vc<bool>,
+    output: Value<ClientOutput>,
 ) -> Result<Vc<Box<dyn ChunkingContext>>> {
     let next_mode = mode.await?;
+    let output = output.into_value();
     let mut builder = BrowserChunkingContext::builder(
         root_path,
         client_root,
         client_root_to_root_path,
         client_root,
         client_root
-            .join("static/chunks".into())
+            .join(
+                match output {
+                    ClientOutput::Legacy => "static/chunks/legacy",
+                    ClientOutput::Default | ClientOutput::Modern => "static/chunks",
+                }
+                .into(),
+            )
             .to_resolved()
             .await?,
         get_client_assets_path(*client_root).to_resolved().await?,
@@ -450,9 +488,10 @@ pub async fn get_client_chunking_context(
     .chunk_base_path(asset_prefix)
     .chunk_suffix_path(chunk_suffix_path)
     .minify_type(if *minify.await? {
-        MinifyType::Minify {
+        MinifyType::Minify(MinifyOptions {
             mangle: (!*no_mangling.await?).then_some(MangleType::OptimalSize),
-        }
+            mangle_properties: mangle_properties.owned().await?,
+        })
     } else {
         MinifyType::NoMinify
     })
@@ -462,20 +501,23 @@ pub async fn get_client_chunking_context(
         SourceMapsType::None
     })
     .asset_base_path(asset_prefix)
-    .current_chunk_method(CurrentChunkMethod::DocumentCurrentScript)
-    .module_id_strategy(module_id_strategy);
+    .current_chunk_method(match output {
+        // Module scripts have no `document.currentScript`
+        ClientOutput::Modern => CurrentChunkMethod::StringLiteral,
+        ClientOutput::Default | ClientOutput::Legacy => CurrentChunkMethod::DocumentCurrentScript,
+    })
+    .legacy_output(matches!(output, ClientOutput::Legacy))
+    .module_id_strategy(module_id_strategy)
+    .separate_runtime_chunk(*separate_runtime_chunk.await?)
+    .scope_hoisting(*scope_hoisting.await?)
+    .json_tree_shaking(*json_tree_shaking.await?);
 
     if next_mode.is_development() {
         builder = builder.hot_module_replacement().use_file_source_map_uris();
     } else {
         builder = builder.chunking_config(
             Vc::<EcmascriptChunkType>::default().to_resolved().await?,
-            ChunkingConfig {
-                min_chunk_size: 50_000,
-                max_chunk_count_per_group: 40,
-                max_merge_chunk_size: 200_000,
-                ..Default::default()
-            },
+            chunking_config.owned().await?,
         );
         builder = builder.chunking_config(
             Vc::<CssChunkType>::default().to_resolved().await?,
@@ -484,7 +526,7 @@ pub async fn get_client_chunking_context(
                 ..Default::default()
             },
         );
-        builder = builder.use_content_hashing(ContentHashing::Direct { length: 16 })
+        builder = builder.use_content_hashing(*content_hashing.await?)
     }
 
     Ok(Vc::upcast(builder.build()))
@@ -5,5 +5,6 @@ pub(crate) mod transforms;
 pub use context::{
     get_client_chunking_context, get_client_compile_time_info, get_client_module_options_context,
     get_client_resolve_options_context, get_client_runtime_entries, ClientContextType,
+    ClientOutput,
 };
 pub use runtime_entry::{RuntimeEntries, RuntimeEntry};
@@ -12,7 +12,9 @@ use turbo_tasks_fs::FileSystemPath;
 use turbopack::module_options::{
     module_options_context::MdxTransformOptions, LoaderRuleItem, OptionWebpackRules,
 };
+use turbopack_browser::ContentHashing;
 use turbopack_core::{
+    chunk::{ChunkCacheGroup, ChunkingConfig, ManglePropertiesOptions},
     issue::{Issue, IssueSeverity, IssueStage, OptionStyledString, StyledString},
     resolve::ResolveAliasMap,
 };
@@ -544,6 +546,8 @@ pub struct TurbopackConfig {
     pub resolve_alias: Option<FxIndexMap<RcStr, JsonValue>>,
     pub resolve_extensions: Option<Vec<RcStr>>,
     pub module_ids: Option<ModuleIds>,
+    /// The number of hex digits of the module ids with [ModuleIds::Hashed].
+    pub module_id_hash_length: Option<u8>,
 }
 
 #[derive(
@@ -554,6 +558,10 @@ pub struct RuleConfigItemOptions {
     pub loaders: Vec<LoaderItem>,
     #[serde(default, alias = "as")]
     pub rename_as: Option<RcStr>,
+    /// Resolve conditions to set for requests issued by matched files, e.g. `react-server`.
+    /// Conditions prefixed with `!` are unset instead.
+    #[serde(default)]
+    pub resolve_conditions: Option<Vec<RcStr>>,
 }
 
 #[derive(
@@ -584,12 +592,54 @@ pub enum LoaderItem {
     LoaderOptions(WebpackLoaderItem),
 }
 
+enum FindRuleResult<'a> {
+    Found(&'a RuleConfigItemOptions),
+    NotFound,
+    Break,
+}
+
+fn find_rule<'a>(
+    rule: &'a RuleConfigItem,
+    active_conditions: &FxHashSet<RcStr>,
+) -> FindRuleResult<'a> {
+    match rule {
+        RuleConfigItem::Options(rule) => FindRuleResult::Found(rule),
+        RuleConfigItem::Conditional(map) => {
+            for (condition, rule) in map.iter() {
+                if condition == "default" || active_conditions.contains(condition) {
+                    match find_rule(rule, active_conditions) {
+                        FindRuleResult::Found(rule) => {
+                            return FindRuleResult::Found(rule);
+                        }
+                        FindRuleResult::Break => {
+                            return FindRuleResult::Break;
+                        }
+                        FindRuleResult::NotFound => {}
+                    }
+                }
+            }
+            FindRuleResult::NotFound
+        }
+        RuleConfigItem::Boolean(_) => FindRuleResult::Break,
+    }
+}
+
+/// Resolve conditions of turbopack rules, keyed by the glob matching the issuing files.
+#[turbo_tasks::value(transparent)]
+pub struct ResolveConditionsRules(FxIndexMap<RcStr, Vec<RcStr>>);
+
+/// The number of hex digits of the module ids with [ModuleIds::Hashed] by default.
+const DEFAULT_MODULE_ID_HASH_LENGTH: u8 = 8;
+
 #[turbo_tasks::value(operation)]
 #[derive(Copy, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub enum ModuleIds {
     Named,
     Deterministic,
+    /// Hashes of the module idents with a fixed length, which don't change when other modules are
+    /// added or removed.
+    Hashed,
 }
 
 #[turbo_tasks::value(transparent)]
@@ -747,11 +797,96 @@ pub struct ExperimentalConfig {
     worker_threads: Option<bool>,
 
     turbopack_minify: Option<bool>,
+    turbopack_mangle_properties: Option<TurbopackManglePropertiesConfig>,
+    turbopack_chunking: Option<TurbopackChunkingConfig>,
+    turbopack_content_hashing: Option<TurbopackContentHashingConfig>,
+    turbopack_json_tree_shaking: Option<bool>,
     turbopack_persistent_caching: Option<bool>,
+    turbopack_prefetch_dynamic_imports: Option<bool>,
+    turbopack_runtime_chunk: Option<bool>,
+    turbopack_scope_hoisting: Option<bool>,
     turbopack_source_maps: Option<bool>,
     turbopack_tree_shaking: Option<bool>,
 }
 
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue, OperationValue,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct TurbopackManglePropertiesConfig {
+    /// Only properties matching this regex are mangled, e.g. `^_`.
+    pub regex: RcStr,
+    /// Property names that are never mangled.
+    #[serde(default)]
+    pub reserved: Vec<RcStr>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionManglePropertiesOptions(Option<ManglePropertiesOptions>);
+
+/// Overrides of the chunking of client code in production. Sizes are in bytes.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+    NonLocalValue,
+    OperationValue,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct TurbopackChunkingConfig {
+    pub min_chunk_size: Option<usize>,
+    pub max_chunk_size: Option<usize>,
+    pub max_chunk_count_per_group: Option<usize>,
+    pub max_merge_chunk_size: Option<usize>,
+    #[serde(default)]
+    pub cache_groups: Vec<TurbopackChunkCacheGroup>,
+    /// Modules in `node_modules` that are needed by at least this number of chunk groups are put
+    /// into vendor chunks.
+    pub vendor_min_chunk_groups: Option<usize>,
+}
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue, OperationValue,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct TurbopackChunkCacheGroup {
+    pub name: RcStr,
+    /// A regex that is matched against the module idents.
+    pub test: RcStr,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ClientChunkingConfig(ChunkingConfig);
+
+/// How the file names of client chunks are derived from their content in production.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+    NonLocalValue,
+    OperationValue,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct TurbopackContentHashingConfig {
+    /// The length of the content hash in hex chars.
+    pub hash_length: Option<u8>,
+    /// Whether the file names start with the readable name of the chunk.
+    pub include_name: Option<bool>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ClientContentHashing(ContentHashing);
+
 #[derive(
     Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs, NonLocalValue, OperationValue,
 )]
@@ -1169,36 +1304,6 @@ impl NextConfig {
                         .collect(),
                 )
             }
-            enum FindRuleResult<'a> {
-                Found(&'a RuleConfigItemOptions),
-                NotFound,
-                Break,
-            }
-            fn find_rule<'a>(
-                rule: &'a RuleConfigItem,
-                active_conditions: &FxHashSet<RcStr>,
-            ) -> FindRuleResult<'a> {
-                match rule {
-                    RuleConfigItem::Options(rule) => FindRuleResult::Found(rule),
-                    RuleConfigItem::Conditional(map) => {
-                        for (condition, rule) in map.iter() {
-                            if condition == "default" || active_conditions.contains(condition) {
-                                match find_rule(rule, active_conditions) {
-                                    FindRuleResult::Found(rule) => {
-                                        return FindRuleResult::Found(rule);
-                                    }
-                                    FindRuleResult::Break => {
-                                        return FindRuleResult::Break;
-                                    }
-                                    FindRuleResult::NotFound => {}
-                                }
-                            }
-                        }
-                        FindRuleResult::NotFound
-                    }
-                    RuleConfigItem::Boolean(_) => FindRuleResult::Break,
-                }
-            }
             match rule {
                 RuleConfigItemOrShortcut::Loaders(loaders) => {
                     rules.insert(
@@ -1210,9 +1315,17 @@ impl NextConfig {
                     );
                 }
                 RuleConfigItemOrShortcut::Advanced(rule) => {
-                    if let FindRuleResult::Found(RuleConfigItemOptions { loaders, rename_as }) =
-                        find_rule(rule, &active_conditions)
+                    if let FindRuleResult::Found(RuleConfigItemOptions {
+                        loaders,
+                        rename_as,
+                        resolve_conditions,
+                    }) = find_rule(rule, &active_conditions)
                     {
+                        // Rules that only change resolve conditions don't need a loader run.
+                        if loaders.is_empty() && rename_as.is_none() && resolve_conditions.is_some()
+                        {
+                            continue;
+                        }
                         rules.insert(
                             ext.clone(),
                             LoaderRuleItem {
@@ -1227,6 +1340,33 @@ impl NextConfig {
         Vc::cell(Some(ResolvedVc::cell(rules)))
     }
 
+    /// Returns the resolve conditions of the turbopack rules that are active for the given
+    /// conditions, keyed by the glob of the rule.
+    #[turbo_tasks::function]
+    pub fn resolve_conditions_rules(
+        &self,
+        active_conditions: Vec<RcStr>,
+    ) -> Vc<ResolveConditionsRules> {
+        let Some(turbo_rules) = self.turbopack.as_ref().and_then(|t| t.rules.as_ref()) else {
+            return Vc::cell(Default::default());
+        };
+        let active_conditions = active_conditions.into_iter().collect::<FxHashSet<_>>();
+        let mut rules = FxIndexMap::default();
+        for (glob, rule) in turbo_rules.iter() {
+            let RuleConfigItemOrShortcut::Advanced(rule) = rule else {
+                continue;
+            };
+            if let FindRuleResult::Found(RuleConfigItemOptions {
+                resolve_conditions: Some(resolve_conditions),
+                ..
+            }) = find_rule(rule, &active_conditions)
+            {
+                rules.insert(glob.clone(), resolve_conditions.clone());
+            }
+        }
+        Vc::cell(rules)
+    }
+
     #[turbo_tasks::function]
     pub fn persistent_caching_enabled(&self) -> Result<Vc<bool>> {
         Ok(Vc::cell(
@@ -1236,6 +1376,51 @@ impl NextConfig {
         ))
     }
 
+    /// Returns whether the chunks of the dynamic imports of a route are listed in its build
+    /// manifest, so they can be prefetched when the route is rendered.
+    #[turbo_tasks::function]
+    pub fn prefetch_dynamic_imports_enabled(&self) -> Vc<bool> {
+        Vc::cell(
+            self.experimental
+                .turbopack_prefetch_dynamic_imports
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns whether the top level keys of JSON modules that are never read by their importers
+    /// are removed. This only applies to production builds.
+    #[turbo_tasks::function]
+    pub async fn json_tree_shaking_enabled(&self, mode: Vc<NextMode>) -> Result<Vc<bool>> {
+        Ok(Vc::cell(
+            self.experimental
+                .turbopack_json_tree_shaking
+                .unwrap_or_default()
+                && mode.await?.is_production(),
+        ))
+    }
+
+    /// Returns whether modules with a single ESM importer are merged into the module factory of
+    /// their importer. This only applies to production builds.
+    #[turbo_tasks::function]
+    pub async fn scope_hoisting_enabled(&self, mode: Vc<NextMode>) -> Result<Vc<bool>> {
+        Ok(Vc::cell(
+            self.experimental
+                .turbopack_scope_hoisting
+                .unwrap_or_default()
+                && mode.await?.is_production(),
+        ))
+    }
+
+    /// Returns whether the client runtime is emitted as a chunk of its own.
+    #[turbo_tasks::function]
+    pub fn runtime_chunk_enabled(&self) -> Vc<bool> {
+        Vc::cell(
+            self.experimental
+                .turbopack_runtime_chunk
+                .unwrap_or_default(),
+        )
+    }
+
     #[turbo_tasks::function]
     pub fn resolve_alias_options(&self) -> Result<Vc<ResolveAliasMap>> {
         let Some(resolve_alias) = self
@@ -1495,6 +1680,16 @@ impl NextConfig {
         Vc::cell(Some(module_ids))
     }
 
+    #[turbo_tasks::function]
+    pub fn module_id_hash_length(&self) -> Vc<u8> {
+        Vc::cell(
+            self.turbopack
+                .as_ref()
+                .and_then(|t| t.module_id_hash_length)
+                .unwrap_or(DEFAULT_MODULE_ID_HASH_LENGTH),
+        )
+    }
+
     #[turbo_tasks::function]
     pub async fn turbo_minify(&self, mode: Vc<NextMode>) -> Result<Vc<bool>> {
         let minify = self.experimental.turbopack_minify;
@@ -1503,6 +1698,76 @@ impl NextConfig {
         ))
     }
 
+    /// Returns the config for chunking client code in production, with the defaults overridden by
+    /// `experimental.turbopackChunking`.
+    #[turbo_tasks::function]
+    pub fn turbo_client_chunking_config(&self) -> Vc<ClientChunkingConfig> {
+        let config = self
+            .experimental
+            .turbopack_chunking
+            .clone()
+            .unwrap_or_default();
+        Vc::cell(ChunkingConfig {
+            min_chunk_size: config.min_chunk_size.unwrap_or(50_000),
+            max_chunk_count_per_group: config.max_chunk_count_per_group.unwrap_or(40),
+            max_merge_chunk_size: config.max_merge_chunk_size.unwrap_or(200_000),
+            max_chunk_size: config.max_chunk_size.unwrap_or(0),
+            cache_groups: config
+                .cache_groups
+                .into_iter()
+                .map(|cache_group| ChunkCacheGroup {
+                    name: cache_group.name,
+                    test: cache_group.test,
+                })
+                .collect(),
+            vendor_min_chunk_groups: config.vendor_min_chunk_groups.unwrap_or(0),
+            ..Default::default()
+        })
+    }
+
+    /// Returns how the file names of client chunks are derived from their content in production.
+    #[turbo_tasks::function]
+    pub fn turbo_client_content_hashing(&self) -> Vc<ClientContentHashing> {
+        let config = self
+            .experimental
+            .turbopack_content_hashing
+            .clone()
+            .unwrap_or_default();
+        let length = config.hash_length.unwrap_or(16);
+        let turbopack = self.turbopack.as_ref();
+        let hashed_ids = matches!(
+            turbopack.and_then(|t| t.module_ids),
+            Some(ModuleIds::Hashed)
+        );
+        Vc::cell(if hashed_ids {
+            // Like the module ids, the chunk names are hashes of the module paths
+            ContentHashing::HashedName {
+                name_length: turbopack
+                    .and_then(|t| t.module_id_hash_length)
+                    .unwrap_or(DEFAULT_MODULE_ID_HASH_LENGTH),
+                length,
+            }
+        } else if config.include_name.unwrap_or_default() {
+            ContentHashing::Named { length }
+        } else {
+            ContentHashing::Direct { length }
+        })
+    }
+
+    /// Returns the options for mangling property names in client code, which is opt-in.
+    #[turbo_tasks::function]
+    pub fn turbo_mangle_properties(&self) -> Vc<OptionManglePropertiesOptions> {
+        Vc::cell(
+            self.experimental
+                .turbopack_mangle_properties
+                .as_ref()
+                .map(|config| ManglePropertiesOptions {
+                    reserved: config.reserved.clone(),
+                    regex: config.regex.clone(),
+                }),
+        )
+    }
+
     #[turbo_tasks::function]
     pub async fn client_source_maps(&self, _mode: Vc<NextMode>) -> Result<Vc<bool>> {
         // Temporarily always enable client source maps as tests regress.
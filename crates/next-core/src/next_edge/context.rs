@@ -8,7 +8,7 @@ use turbopack_browser::BrowserChunkingContext;
 use turbopack_core::{
     chunk::{
         module_id_strategies::ModuleIdStrategy, ChunkingConfig, ChunkingContext, MangleType,
-        MinifyType, SourceMapsType,
+        MinifyOptions, MinifyType, SourceMapsType,
     },
     compile_time_info::{
         CompileTimeDefineValue, CompileTimeDefines, CompileTimeInfo, DefineableNameSegment,
@@ -26,9 +26,12 @@ use crate::{
     next_font::local::NextFontLocalResolvePlugin,
     next_import_map::get_next_edge_import_map,
     next_server::context::ServerContextType,
-    next_shared::resolve::{
-        get_invalid_client_only_resolve_plugin, get_invalid_styled_jsx_resolve_plugin,
-        ModuleFeatureReportResolvePlugin, NextSharedRuntimeResolvePlugin,
+    next_shared::{
+        resolve::{
+            get_invalid_client_only_resolve_plugin, get_invalid_styled_jsx_resolve_plugin,
+            ModuleFeatureReportResolvePlugin, NextSharedRuntimeResolvePlugin,
+        },
+        webpack_rules::issuer_resolve_conditions,
     },
     util::{foreign_code_context_condition, NextRuntime},
 };
@@ -170,6 +173,9 @@ pub async fn get_edge_resolve_options_context(
             .map(RcStr::from),
     );
 
+    let issuer_conditions =
+        issuer_resolve_conditions(project_path, next_config, custom_conditions.clone()).await?;
+
     if ty.supports_react_server() {
         custom_conditions.push("react-server".into());
     };
@@ -204,6 +210,7 @@ pub async fn get_edge_resolve_options_context(
             foreign_code_context_condition(next_config, project_path).await?,
             resolve_options_context.clone().resolved_cell(),
         )],
+        issuer_conditions,
         ..resolve_options_context
     }
     .cell())
@@ -222,6 +229,8 @@ pub async fn get_edge_chunking_context_with_client_assets(
     turbo_minify: Vc<bool>,
     turbo_source_maps: Vc<bool>,
     no_mangling: Vc<bool>,
+    scope_hoisting: Vc<bool>,
+    json_tree_shaking: Vc<bool>,
 ) -> Result<Vc<Box<dyn ChunkingContext>>> {
     let output_root = node_root.join("server/edge".into()).to_resolved().await?;
     let next_mode = mode.await?;
@@ -240,10 +249,11 @@ pub async fn get_edge_chunking_context_with_client_assets(
     )
     .asset_base_path(asset_prefix)
     .minify_type(if *turbo_minify.await? {
-        MinifyType::Minify {
+        MinifyType::Minify(MinifyOptions {
             // React needs deterministic function names to work correctly.
             mangle: (!*no_mangling.await?).then_some(MangleType::Deterministic),
-        }
+            mangle_properties: None,
+        })
     } else {
         MinifyType::NoMinify
     })
@@ -252,7 +262,9 @@ pub async fn get_edge_chunking_context_with_client_assets(
     } else {
         SourceMapsType::None
     })
-    .module_id_strategy(module_id_strategy);
+    .module_id_strategy(module_id_strategy)
+    .scope_hoisting(*scope_hoisting.await?)
+    .json_tree_shaking(*json_tree_shaking.await?);
 
     if !next_mode.is_development() {
         builder = builder.chunking_config(
@@ -285,6 +297,8 @@ pub async fn get_edge_chunking_context(
     turbo_minify: Vc<bool>,
     turbo_source_maps: Vc<bool>,
     no_mangling: Vc<bool>,
+    scope_hoisting: Vc<bool>,
+    json_tree_shaking: Vc<bool>,
 ) -> Result<Vc<Box<dyn ChunkingContext>>> {
     let output_root = node_root.join("server/edge".into()).to_resolved().await?;
     let next_mode = mode.await?;
@@ -304,9 +318,10 @@ pub async fn get_edge_chunking_context(
     // asset from the output directory.
     .asset_base_path(ResolvedVc::cell(Some("blob:server/edge/".into())))
     .minify_type(if *turbo_minify.await? {
-        MinifyType::Minify {
+        MinifyType::Minify(MinifyOptions {
             mangle: (!*no_mangling.await?).then_some(MangleType::OptimalSize),
-        }
+            mangle_properties: None,
+        })
     } else {
         MinifyType::NoMinify
     })
@@ -315,7 +330,9 @@ pub async fn get_edge_chunking_context(
     } else {
         SourceMapsType::None
     })
-    .module_id_strategy(module_id_strategy);
+    .module_id_strategy(module_id_strategy)
+    .scope_hoisting(*scope_hoisting.await?)
+    .json_tree_shaking(*json_tree_shaking.await?);
 
     if !next_mode.is_development() {
         builder = builder.chunking_config(
@@ -30,6 +30,12 @@ pub struct BuildManifest {
     pub polyfill_files: Vec<ResolvedVc<Box<dyn OutputAsset>>>,
     pub root_main_files: Vec<ResolvedVc<Box<dyn OutputAsset>>>,
     pub pages: FxIndexMap<RcStr, ResolvedVc<OutputAssets>>,
+    /// The chunks of the dynamic imports of each route, which can be prefetched when the route is
+    /// rendered. See [dynamic_import_chunks].
+    pub prefetch_files: FxIndexMap<RcStr, ResolvedVc<OutputAssets>>,
+    /// The ES5 chunks of each route for browsers without module support. When a route has them,
+    /// its chunks in [BuildManifest::pages] are loaded as modules and these with `nomodule`.
+    pub legacy_pages: FxIndexMap<RcStr, ResolvedVc<OutputAssets>>,
 }
 
 impl BuildManifest {
@@ -50,6 +56,10 @@ impl BuildManifest {
             pub root_main_files: Vec<RcStr>,
             pub pages: FxIndexMap<RcStr, Vec<RcStr>>,
             pub amp_first_pages: Vec<RcStr>,
+            #[serde(skip_serializing_if = "FxIndexMap::is_empty")]
+            pub prefetch_files: FxIndexMap<RcStr, Vec<RcStr>>,
+            #[serde(skip_serializing_if = "FxIndexMap::is_empty")]
+            pub legacy_pages: FxIndexMap<RcStr, Vec<RcStr>>,
         }
 
         let pages: Vec<(RcStr, Vec<RcStr>)> = self
@@ -76,6 +86,56 @@ impl BuildManifest {
             .try_join()
             .await?;
 
+        let prefetch_files: Vec<(RcStr, Vec<RcStr>)> = self
+            .prefetch_files
+            .iter()
+            .map(|(k, chunks)| async move {
+                Ok((
+                    k.clone(),
+                    chunks
+                        .await?
+                        .iter()
+                        .copied()
+                        .map(|chunk| async move {
+                            let chunk_path = chunk.path().await?;
+                            Ok(client_relative_path_ref
+                                .get_path_to(&chunk_path)
+                                .context(
+                                    "dynamic import chunk path must be inside the client root",
+                                )?
+                                .into())
+                        })
+                        .try_join()
+                        .await?,
+                ))
+            })
+            .try_join()
+            .await?;
+
+        let legacy_pages: Vec<(RcStr, Vec<RcStr>)> = self
+            .legacy_pages
+            .iter()
+            .map(|(k, chunks)| async move {
+                Ok((
+                    k.clone(),
+                    chunks
+                        .await?
+                        .iter()
+                        .copied()
+                        .map(|chunk| async move {
+                            let chunk_path = chunk.path().await?;
+                            Ok(client_relative_path_ref
+                                .get_path_to(&chunk_path)
+                                .context("legacy chunk path must be inside the client root")?
+                                .into())
+                        })
+                        .try_join()
+                        .await?,
+                ))
+            })
+            .try_join()
+            .await?;
+
         let polyfill_files: Vec<RcStr> = self
             .polyfill_files
             .iter()
@@ -108,10 +168,17 @@ impl BuildManifest {
             pages: FxIndexMap::from_iter(pages.into_iter()),
             polyfill_files,
             root_main_files,
+            prefetch_files: FxIndexMap::from_iter(prefetch_files.into_iter()),
+            legacy_pages: FxIndexMap::from_iter(legacy_pages.into_iter()),
             ..Default::default()
         };
 
-        let chunks: Vec<ReadRef<OutputAssets>> = self.pages.values().try_join().await?;
+        let chunks: Vec<ReadRef<OutputAssets>> = self
+            .pages
+            .values()
+            .chain(self.legacy_pages.values())
+            .try_join()
+            .await?;
 
         let references = chunks
             .into_iter()
@@ -128,6 +195,29 @@ impl BuildManifest {
     }
 }
 
+/// The chunks that are loaded by the dynamic imports of the modules in the given chunks, i.e. the
+/// chunks they reference that aren't part of them. Nested dynamic imports are not followed.
+#[turbo_tasks::function]
+pub async fn dynamic_import_chunks(chunks: Vc<OutputAssets>) -> Result<Vc<OutputAssets>> {
+    let chunks = chunks.await?;
+    let own_chunks = chunks.iter().copied().collect::<FxIndexSet<_>>();
+    let mut dynamic_import_chunks = FxIndexSet::default();
+    for chunk in chunks.iter() {
+        for &reference in chunk.references().await?.iter() {
+            if own_chunks.contains(&reference) {
+                continue;
+            }
+            // Only scripts and stylesheets are loaded as chunks, the other references are e.g.
+            // source maps and static assets.
+            let path = reference.path().await?;
+            if matches!(path.extension_ref(), Some("js" | "css")) {
+                dynamic_import_chunks.insert(reference);
+            }
+        }
+    }
+    Ok(Vc::cell(dynamic_import_chunks.into_iter().collect()))
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase", tag = "version")]
 #[allow(clippy::large_enum_variant)]
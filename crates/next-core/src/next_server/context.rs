@@ -16,8 +16,8 @@ use turbopack::{
 };
 use turbopack_core::{
     chunk::{
-        module_id_strategies::ModuleIdStrategy, ChunkingConfig, MangleType, MinifyType,
-        SourceMapsType,
+        module_id_strategies::ModuleIdStrategy, ChunkingConfig, MangleType, MinifyOptions,
+        MinifyType, SourceMapsType,
     },
     compile_time_info::{
         CompileTimeDefineValue, CompileTimeDefines, CompileTimeInfo, DefineableNameSegment,
@@ -66,7 +66,7 @@ use crate::{
             styled_jsx::get_styled_jsx_transform_rule,
             swc_ecma_transform_plugins::get_swc_ecma_transform_plugin_rule,
         },
-        webpack_rules::webpack_loader_options,
+        webpack_rules::{issuer_resolve_conditions, webpack_loader_options},
     },
     transform_options::{
         get_decorators_transform_options, get_jsx_transform_options,
@@ -208,6 +208,9 @@ pub async fn get_server_resolve_options_context(
             .map(RcStr::from),
     );
 
+    let issuer_conditions =
+        issuer_resolve_conditions(project_path, next_config, custom_conditions.clone()).await?;
+
     if ty.supports_react_server() {
         custom_conditions.push("react-server".into());
     };
@@ -338,6 +341,7 @@ pub async fn get_server_resolve_options_context(
             foreign_code_context_condition,
             resolve_options_context.clone().resolved_cell(),
         )],
+        issuer_conditions,
         ..resolve_options_context
     }
     .cell())
@@ -991,6 +995,8 @@ pub async fn get_server_chunking_context_with_client_assets(
     turbo_minify: Vc<bool>,
     turbo_source_maps: Vc<bool>,
     no_mangling: Vc<bool>,
+    scope_hoisting: Vc<bool>,
+    json_tree_shaking: Vc<bool>,
 ) -> Result<Vc<NodeJsChunkingContext>> {
     let next_mode = mode.await?;
     // TODO(alexkirsz) This should return a trait that can be implemented by the
@@ -1014,10 +1020,11 @@ pub async fn get_server_chunking_context_with_client_assets(
     )
     .asset_prefix(asset_prefix)
     .minify_type(if *turbo_minify.await? {
-        MinifyType::Minify {
+        MinifyType::Minify(MinifyOptions {
             // React needs deterministic function names to work correctly.
             mangle: (!*no_mangling.await?).then_some(MangleType::Deterministic),
-        }
+            mangle_properties: None,
+        })
     } else {
         MinifyType::NoMinify
     })
@@ -1027,7 +1034,9 @@ pub async fn get_server_chunking_context_with_client_assets(
         SourceMapsType::None
     })
     .module_id_strategy(module_id_strategy)
-    .file_tracing(next_mode.is_production());
+    .file_tracing(next_mode.is_production())
+    .scope_hoisting(*scope_hoisting.await?)
+    .json_tree_shaking(*json_tree_shaking.await?);
 
     if next_mode.is_development() {
         builder = builder.use_file_source_map_uris();
@@ -1064,6 +1073,8 @@ pub async fn get_server_chunking_context(
     turbo_minify: Vc<bool>,
     turbo_source_maps: Vc<bool>,
     no_mangling: Vc<bool>,
+    scope_hoisting: Vc<bool>,
+    json_tree_shaking: Vc<bool>,
 ) -> Result<Vc<NodeJsChunkingContext>> {
     let next_mode = mode.await?;
     // TODO(alexkirsz) This should return a trait that can be implemented by the
@@ -1080,9 +1091,10 @@ pub async fn get_server_chunking_context(
         next_mode.runtime_type(),
     )
     .minify_type(if *turbo_minify.await? {
-        MinifyType::Minify {
+        MinifyType::Minify(MinifyOptions {
             mangle: (!*no_mangling.await?).then_some(MangleType::OptimalSize),
-        }
+            mangle_properties: None,
+        })
     } else {
         MinifyType::NoMinify
     })
@@ -1092,7 +1104,9 @@ pub async fn get_server_chunking_context(
         SourceMapsType::None
     })
     .module_id_strategy(module_id_strategy)
-    .file_tracing(next_mode.is_production());
+    .file_tracing(next_mode.is_production())
+    .scope_hoisting(*scope_hoisting.await?)
+    .json_tree_shaking(*json_tree_shaking.await?);
 
     if next_mode.is_development() {
         builder = builder.use_file_source_map_uris()
@@ -1,9 +1,12 @@
 use anyhow::Result;
 use turbo_rcstr::RcStr;
 use turbo_tasks::{ResolvedVc, Vc};
-use turbo_tasks_fs::FileSystemPath;
+use turbo_tasks_fs::{glob::Glob, FileSystemPath};
 use turbopack::module_options::WebpackLoadersOptions;
-use turbopack_core::resolve::{options::ImportMapping, ExternalTraced, ExternalType};
+use turbopack_core::{
+    condition::ContextCondition,
+    resolve::{options::ImportMapping, ExternalTraced, ExternalType},
+};
 
 use self::{babel::maybe_add_babel_loader, sass::maybe_add_sass_loader};
 use crate::next_config::NextConfig;
@@ -37,6 +40,31 @@ pub async fn webpack_loader_options(
     })
 }
 
+/// Returns the resolve conditions configured by turbopack rules as conditions on the issuing
+/// file. Like for loaders, globs without a `/` match the file name, and all other globs match the
+/// path relative to the project.
+pub async fn issuer_resolve_conditions(
+    project_path: ResolvedVc<FileSystemPath>,
+    next_config: Vc<NextConfig>,
+    conditions: Vec<RcStr>,
+) -> Result<Vec<(ContextCondition, Vec<RcStr>)>> {
+    let rules = next_config.resolve_conditions_rules(conditions).await?;
+    let mut issuer_conditions = Vec::with_capacity(rules.len());
+    for (glob, resolve_conditions) in rules.iter() {
+        let glob_vc = Glob::new(glob.clone()).to_resolved().await?;
+        let condition = if glob.contains('/') {
+            ContextCondition::PathGlob {
+                base: project_path,
+                glob: glob_vc,
+            }
+        } else {
+            ContextCondition::NameGlob(glob_vc)
+        };
+        issuer_conditions.push((condition, resolve_conditions.clone()));
+    }
+    Ok(issuer_conditions)
+}
+
 #[turbo_tasks::function]
 async fn loader_runner_package_mapping() -> Result<Vc<ImportMapping>> {
     Ok(ImportMapping::Alternatives(vec![ImportMapping::External(
@@ -129,6 +129,11 @@ export interface NapiProjectOptions {
   previewProps: NapiDraftModeOptions
   /** The browserslist query to use for targeting browsers. */
   browserslistQuery: string
+  /**
+   * The browserslist query of legacy browsers, from the `legacy` environment of the
+   * browserslist config. Builds emit ES5 chunks for them when it's set.
+   */
+  legacyBrowserslistQuery?: string
   /**
    * When the code is minified, this opts out of the default mangling of
    * local names for variables, functions etc., which can be useful for
@@ -326,6 +331,29 @@ export declare function projectGetSourceMapSync(
   project: { __napiType: 'Project' },
   filePath: string
 ): string | null
+/**
+ * Returns the export usage of all modules of the app as JSON, keyed by module ident. This lists
+ * the used exports of each module and the references that use them.
+ */
+export declare function projectGetExportUsageJson(project: {
+  __napiType: 'Project'
+}): Promise<string>
+/**
+ * Returns the export usage of all source files of the app as JSON, keyed by path. The modules
+ * that are created from the same file for the server and the client are merged, and each used
+ * export lists the layers that use it.
+ */
+export declare function projectGetMergedExportUsageJson(project: {
+  __napiType: 'Project'
+}): Promise<string>
+/**
+ * Returns a report of the build in the format of webpack's `stats.json` with the emitted assets,
+ * the client chunks and their modules, so bundle analyzer tools can consume it.
+ */
+export declare function projectGetBuildStatsJson(
+  project: { __napiType: 'Project' },
+  appDirOnly: boolean
+): Promise<string>
 export declare function rootTaskDispose(rootTask: {
   __napiType: 'RootTask'
 }): void
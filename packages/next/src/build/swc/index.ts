@@ -702,6 +702,18 @@ function bindingToApi(
       return binding.projectGetSourceMapSync(this._nativeProject, filePath)
     }
 
+    getExportUsageJson(): Promise<string> {
+      return binding.projectGetExportUsageJson(this._nativeProject)
+    }
+
+    getMergedExportUsageJson(): Promise<string> {
+      return binding.projectGetMergedExportUsageJson(this._nativeProject)
+    }
+
+    getBuildStatsJson(appDirOnly: boolean): Promise<string> {
+      return binding.projectGetBuildStatsJson(this._nativeProject, appDirOnly)
+    }
+
     updateInfoSubscribe(aggregationMs: number) {
       return subscribe<TurbopackResult<UpdateMessage>>(true, async (callback) =>
         binding.projectUpdateInfoSubscribe(
@@ -207,6 +207,12 @@ export interface Project {
   getSourceMap(filePath: string): Promise<string | null>
   getSourceMapSync(filePath: string): string | null
 
+  getExportUsageJson(): Promise<string>
+
+  getMergedExportUsageJson(): Promise<string>
+
+  getBuildStatsJson(appDirOnly: boolean): Promise<string>
+
   traceSource(
     stackFrame: TurbopackStackFrame,
     currentDirectoryFileUrl: string
@@ -389,6 +395,13 @@ export interface ProjectOptions {
    */
   browserslistQuery: string
 
+  /**
+   * The browserslist query of legacy browsers. When it's set, builds also emit
+   * the client chunks of pages compiled to ES5, which are loaded with
+   * `<script nomodule>`.
+   */
+  legacyBrowserslistQuery?: string
+
   /**
    * When the code is minified, this opts out of the default mangling of local
    * names for variables, functions etc., which can be useful for
@@ -19,6 +19,7 @@ import loadConfig from '../../server/config'
 import { hasCustomExportOutput } from '../../export/utils'
 import { Telemetry } from '../../telemetry/storage'
 import { setGlobal } from '../../trace'
+import { getLegacyBrowsers } from '../utils'
 
 export async function turbopackBuild(): Promise<{
   duration: number
@@ -78,6 +79,7 @@ export async function turbopackBuild(): Promise<{
       encryptionKey,
       previewProps,
       browserslistQuery: supportedBrowsers.join(', '),
+      legacyBrowserslistQuery: getLegacyBrowsers(dir)?.join(', '),
       noMangling,
     },
     {
@@ -194,6 +196,13 @@ export async function turbopackBuild(): Promise<{
       entrypoints: currentEntrypoints,
     })
 
+    if (config.experimental.turbopackBuildStats) {
+      await fs.writeFile(
+        path.join(distDir, 'turbopack-stats.json'),
+        await project.getBuildStatsJson(appDirOnly)
+      )
+    }
+
     const shutdownPromise = project.shutdown()
 
     const time = process.hrtime(startTime)
@@ -1840,6 +1840,24 @@ export function getSupportedBrowsers(
   return MODERN_BROWSERSLIST_TARGET
 }
 
+/**
+ * The browsers of the `legacy` environment of the browserslist config, if it
+ * has one. Builds emit ES5 chunks loaded with `<script nomodule>` for them.
+ */
+export function getLegacyBrowsers(dir: string): string[] | undefined {
+  try {
+    const legacyConfig = browserslist.findConfig(dir)?.legacy
+    if (legacyConfig && legacyConfig.length > 0) {
+      const browsers = browserslist(legacyConfig)
+      if (browsers.length > 0) {
+        return browsers
+      }
+    }
+  } catch {}
+
+  return undefined
+}
+
 export function isWebpackServerOnlyLayer(
   layer: WebpackLayerName | null | undefined
 ): boolean {
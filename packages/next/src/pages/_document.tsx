@@ -146,6 +146,19 @@ function AmpStyles({
   )
 }
 
+/**
+ * The ES5 chunks of the page for browsers without module support. When the
+ * page has them, its other chunks are loaded as modules, which those browsers
+ * skip.
+ */
+function getLegacyScripts(context: HtmlProps): string[] {
+  const { buildManifest, __NEXT_DATA__ } = context
+
+  return (buildManifest.legacyPages?.[__NEXT_DATA__.page] || []).filter(
+    (file) => file.endsWith('.js')
+  )
+}
+
 function getDynamicChunks(
   context: HtmlProps,
   props: OriginProps,
@@ -159,12 +172,14 @@ function getDynamicChunks(
     disableOptimizedLoading,
     crossOrigin,
   } = context
+  const hasLegacyScripts = getLegacyScripts(context).length > 0
 
   return dynamicImports.map((file) => {
     if (!file.endsWith('.js') || files.allFiles.includes(file)) return null
 
     return (
       <script
+        type={hasLegacyScripts ? 'module' : undefined}
         async={!isDevelopment && disableOptimizedLoading}
         defer={!disableOptimizedLoading}
         key={file}
@@ -194,11 +209,14 @@ function getScripts(
   const lowPriorityScripts = buildManifest.lowPriorityFiles?.filter((file) =>
     file.endsWith('.js')
   )
+  const legacyScripts = getLegacyScripts(context)
+  const moduleScripts = new Set(legacyScripts.length > 0 ? normalScripts : [])
 
-  return [...normalScripts, ...lowPriorityScripts].map((file) => {
+  const scripts = [...normalScripts, ...lowPriorityScripts].map((file) => {
     return (
       <script
         key={file}
+        type={moduleScripts.has(file) ? 'module' : undefined}
         src={`${assetPrefix}/_next/${encodeURIPath(file)}${assetQueryString}`}
         nonce={props.nonce}
         async={!isDevelopment && disableOptimizedLoading}
@@ -207,6 +225,20 @@ function getScripts(
       />
     )
   })
+
+  return [
+    ...scripts,
+    ...legacyScripts.map((file) => (
+      <script
+        key={`legacy-${file}`}
+        noModule
+        src={`${assetPrefix}/_next/${encodeURIPath(file)}${assetQueryString}`}
+        nonce={props.nonce}
+        defer
+        crossOrigin={props.crossOrigin || crossOrigin}
+      />
+    )),
+  ]
 }
 
 function getPreNextWorkerScripts(context: HtmlProps, props: OriginProps) {
@@ -525,6 +557,31 @@ export class Head extends React.Component<HeadProps> {
     )
   }
 
+  getPrefetchDynamicImportLinks() {
+    const {
+      buildManifest,
+      __NEXT_DATA__,
+      assetPrefix,
+      assetQueryString,
+      crossOrigin,
+    } = this.context
+
+    return (buildManifest.prefetchFiles?.[__NEXT_DATA__.page] || [])
+      .filter((file) => file.endsWith('.js'))
+      .map((file) => (
+        <link
+          rel="prefetch"
+          key={file}
+          href={`${assetPrefix}/_next/${encodeURIPath(
+            file
+          )}${assetQueryString}`}
+          as="script"
+          nonce={this.props.nonce}
+          crossOrigin={this.props.crossOrigin || crossOrigin}
+        />
+      ))
+  }
+
   getPreloadMainLinks(files: DocumentFiles): JSX.Element[] | null {
     const { assetPrefix, assetQueryString, scriptLoader, crossOrigin } =
       this.context
@@ -880,6 +937,9 @@ export class Head extends React.Component<HeadProps> {
             {!disableRuntimeJS &&
               !disableJsPreload &&
               this.getPreloadMainLinks(files)}
+            {!disableRuntimeJS &&
+              !disableJsPreload &&
+              this.getPrefetchDynamicImportLinks()}
 
             {!disableOptimizedLoading &&
               !disableRuntimeJS &&
@@ -73,5 +73,25 @@ export function getRequiredScripts(
     }
   }
 
+  const prefetchFiles = (buildManifest.prefetchFiles?.[pagePath] || []).filter(
+    (file) => file.endsWith('.js')
+  )
+  if (prefetchFiles.length > 0) {
+    const preinitRequiredScripts = preinitScripts
+    preinitScripts = () => {
+      preinitRequiredScripts()
+      // the chunks of dynamic imports are fetched with a low priority, so they
+      // don't compete with the scripts the page needs to hydrate
+      for (const file of prefetchFiles) {
+        ReactDOM.preload(`${assetPrefix}/_next/` + encodeURIPath(file) + qs, {
+          as: 'script',
+          fetchPriority: 'low',
+          nonce,
+          crossOrigin,
+        })
+      }
+    }
+  }
+
   return [preinitScripts, bootstrapScript]
 }
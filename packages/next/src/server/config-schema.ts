@@ -114,6 +114,7 @@ const zTurboRuleConfigItemOptions: zod.ZodType<TurbopackRuleConfigItemOptions> =
   z.object({
     loaders: z.array(zTurboLoaderItem),
     as: z.string().optional(),
+    resolveConditions: z.array(z.string()).optional(),
   })
 
 const zTurboRuleConfigItem: zod.ZodType<TurbopackRuleConfigItem> = z.union([
@@ -140,7 +141,8 @@ const zTurbopackConfig: zod.ZodType<TurbopackOptions> = z.strictObject({
     )
     .optional(),
   resolveExtensions: z.array(z.string()).optional(),
-  moduleIds: z.enum(['named', 'deterministic']).optional(),
+  moduleIds: z.enum(['named', 'deterministic', 'hashed']).optional(),
+  moduleIdHashLength: z.number().int().min(4).max(16).optional(),
 })
 
 // Same as zTurbopackConfig but with deprecated properties. Unfortunately, base
@@ -163,7 +165,8 @@ const zDeprecatedExperimentalTurboConfig: zod.ZodType<DeprecatedExperimentalTurb
     treeShaking: z.boolean().optional(),
     persistentCaching: z.union([z.number(), z.literal(false)]).optional(),
     memoryLimit: z.number().optional(),
-    moduleIds: z.enum(['named', 'deterministic']).optional(),
+    moduleIds: z.enum(['named', 'deterministic', 'hashed']).optional(),
+    moduleIdHashLength: z.number().int().min(4).max(16).optional(),
     minify: z.boolean().optional(),
     sourceMaps: z.boolean().optional(),
   })
@@ -442,7 +445,36 @@ export const configSchema: zod.ZodType<NextConfig> = z.lazy(() =>
         turbo: zDeprecatedExperimentalTurboConfig.optional(),
         turbopackMemoryLimit: z.number().optional(),
         turbopackMinify: z.boolean().optional(),
+        turbopackMangleProperties: z
+          .object({
+            regex: z.string(),
+            reserved: z.array(z.string()).optional(),
+          })
+          .optional(),
+        turbopackBuildStats: z.boolean().optional(),
+        turbopackChunking: z
+          .object({
+            minChunkSize: z.number().int().nonnegative().optional(),
+            maxChunkSize: z.number().int().nonnegative().optional(),
+            maxChunkCountPerGroup: z.number().int().nonnegative().optional(),
+            maxMergeChunkSize: z.number().int().nonnegative().optional(),
+            cacheGroups: z
+              .array(z.object({ name: z.string(), test: z.string() }))
+              .optional(),
+            vendorMinChunkGroups: z.number().int().nonnegative().optional(),
+          })
+          .optional(),
+        turbopackContentHashing: z
+          .object({
+            hashLength: z.number().int().min(8).max(16).optional(),
+            includeName: z.boolean().optional(),
+          })
+          .optional(),
+        turbopackJsonTreeShaking: z.boolean().optional(),
         turbopackPersistentCaching: z.boolean().optional(),
+        turbopackPrefetchDynamicImports: z.boolean().optional(),
+        turbopackRuntimeChunk: z.boolean().optional(),
+        turbopackScopeHoisting: z.boolean().optional(),
         turbopackSourceMaps: z.boolean().optional(),
         turbopackTreeShaking: z.boolean().optional(),
         optimizePackageImports: z.array(z.string()).optional(),
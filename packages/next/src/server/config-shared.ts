@@ -112,6 +112,11 @@ export type TurbopackRuleConfigItemOrShortcut =
 export type TurbopackRuleConfigItemOptions = {
   loaders: TurbopackLoaderItem[]
   as?: string
+  /**
+   * Resolve conditions to set for imports from matched files, e.g. `react-server`.
+   * Conditions prefixed with `!` are unset instead.
+   */
+  resolveConditions?: string[]
 }
 
 export type TurbopackRuleConfigItem =
@@ -147,9 +152,20 @@ export interface TurbopackOptions {
   /**
    * The module ID strategy to use for Turbopack.
    * If not set, the default is `'named'` for development and `'deterministic'`
-   * for production.
+   * for production. `'hashed'` uses hashes of the module paths with a fixed
+   * length, so adding or removing modules doesn't change the IDs of other
+   * modules. The names of client chunks are hashes of their module paths too.
+   * The IDs are recorded in the `cache` directory, so a module whose ID
+   * collides with the one of an existing module gets a longer ID instead of
+   * changing the existing one.
+   */
+  moduleIds?: 'named' | 'deterministic' | 'hashed'
+
+  /**
+   * The number of hex digits of the module IDs when `moduleIds` is `'hashed'`.
+   * Modules whose IDs collide get longer IDs. Defaults to `8`.
    */
-  moduleIds?: 'named' | 'deterministic'
+  moduleIdHashLength?: number
 
   /**
    * This is the repo root usually and only files above this
@@ -397,11 +413,90 @@ export interface ExperimentalConfig {
    */
   turbopackMinify?: boolean
 
+  /**
+   * Mangle property names in client code when minifying. Only properties that
+   * are never accessed by name and never reach external modules are mangled,
+   * using the same names in all chunks.
+   */
+  turbopackMangleProperties?: {
+    /** Only properties matching this regex are mangled, e.g. `^_`. */
+    regex: string
+    /** Property names that are never mangled. */
+    reserved?: string[]
+  }
+
+  /**
+   * Tune the chunking of client code in production. Sizes are in bytes.
+   */
+  turbopackChunking?: {
+    /** Try to avoid creating more than 1 chunk smaller than this size. Defaults to 50000. */
+    minChunkSize?: number
+    /** Split chunks bigger than this size. Disabled by default. */
+    maxChunkSize?: number
+    /** Try to avoid loading more chunks than this per page. Defaults to 40. */
+    maxChunkCountPerGroup?: number
+    /** Never merge chunks bigger than this size with other chunks. Defaults to 200000. */
+    maxMergeChunkSize?: number
+    /**
+     * Modules whose ident matches `test`, a regex, are put into chunks of their own, which are
+     * not merged with chunks of other modules.
+     */
+    cacheGroups?: { name: string; test: string }[]
+    /**
+     * Modules in `node_modules` that are needed by at least this number of pages are put into
+     * vendor chunks, which are not merged with chunks of application code. Disabled by default.
+     */
+    vendorMinChunkGroups?: number
+  }
+
+  /**
+   * Remove the top level keys of JSON modules that are never read by the
+   * modules importing them in production builds.
+   */
+  turbopackJsonTreeShaking?: boolean
+
   /**
    * Enable persistent caching for the turbopack dev server and build.
    */
   turbopackPersistentCaching?: boolean
 
+  /**
+   * Controls the file names of client chunks in production, which contain a hash of their final
+   * content so they can be cached immutably.
+   */
+  turbopackContentHashing?: {
+    /** The length of the content hash in hex chars. Defaults to 16. */
+    hashLength?: number
+    /** Start the file names with the readable name of the chunk. Defaults to false. */
+    includeName?: boolean
+  }
+
+  /**
+   * Write a report of the build in the format of webpack's `stats.json` to
+   * `.next/turbopack-stats.json`, which can be used with bundle analyzer tools.
+   */
+  turbopackBuildStats?: boolean
+
+  /**
+   * List the chunks of the dynamic imports of each route in the build manifests, and emit
+   * `<link rel="prefetch">` hints for them when the route is rendered, so they don't have to be
+   * discovered when the dynamic import runs.
+   */
+  turbopackPrefetchDynamicImports?: boolean
+
+  /**
+   * Emit the turbopack runtime as a chunk of its own, so the entry chunks of pages don't change
+   * when only the runtime does.
+   */
+  turbopackRuntimeChunk?: boolean
+
+  /**
+   * Merge modules that are only imported by a single module into the module
+   * factory of their importer in production builds, so fewer module factories
+   * need to be registered and evaluated.
+   */
+  turbopackScopeHoisting?: boolean
+
   /**
    * Enable source maps. Defaults to true.
    */
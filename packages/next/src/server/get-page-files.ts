@@ -16,6 +16,13 @@ export type BuildManifest = {
     [page: string]: readonly string[]
   }
   ampFirstPages: readonly string[]
+  // the chunks of the dynamic imports of each page, which are prefetched
+  // when the page is rendered (turbopack only)
+  prefetchFiles?: { [page: string]: readonly string[] }
+  // the ES5 chunks of each page for browsers without module support, which
+  // are loaded with `nomodule` while the page chunks are loaded as modules
+  // (turbopack only)
+  legacyPages?: { [page: string]: readonly string[] }
 }
 
 export function getPageFiles(
@@ -416,6 +416,18 @@ export class TurbopackManifestLoader {
       if (m.rootMainFiles.length) manifest.rootMainFiles = m.rootMainFiles
       // polyfillFiles should always be the same, so we can overwrite instead of actually merging
       if (m.polyfillFiles.length) manifest.polyfillFiles = m.polyfillFiles
+      if (m.prefetchFiles) {
+        manifest.prefetchFiles = Object.assign(
+          manifest.prefetchFiles ?? {},
+          m.prefetchFiles
+        )
+      }
+      if (m.legacyPages) {
+        manifest.legacyPages = Object.assign(
+          manifest.legacyPages ?? {},
+          m.legacyPages
+        )
+      }
     }
     manifest.pages = sortObjectByKey(manifest.pages) as BuildManifest['pages']
     return manifest
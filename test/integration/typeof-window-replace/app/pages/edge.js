@@ -0,0 +1,12 @@
+const Page = () => (
+  <>
+    <p>Edge window {typeof window}</p>
+    <p>Edge document {typeof document}</p>
+  </>
+)
+
+export const config = { runtime: 'experimental-edge' }
+
+export const getServerSideProps = () => ({ props: {} })
+
+export default Page
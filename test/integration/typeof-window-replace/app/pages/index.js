@@ -3,6 +3,7 @@ import MyComp from 'comps'
 const Page = () => (
   <>
     <p>Hello {typeof window}</p>
+    <p>Document {typeof document}</p>
     <MyComp />
   </>
 )
@@ -7,6 +7,29 @@ import { nextBuild } from 'next-test-utils'
 const appDir = path.join(__dirname, '../app')
 let buildManifest
 
+async function readClientFiles(page) {
+  let allContent = ''
+  const files = buildManifest.pages[page].filter((item) => item.endsWith('.js'))
+  for (const file of files) {
+    allContent += await fs.readFile(path.join(appDir, '.next', file), 'utf8')
+  }
+  return allContent
+}
+
+async function readServerFiles(dir) {
+  let allContent = ''
+  const filesDir = path.join(appDir, '.next', 'server', dir)
+  const files = fs
+    .readdirSync(filesDir, {
+      recursive: true,
+    })
+    .filter((item) => item.endsWith('.js'))
+  for (const file of files) {
+    allContent += await fs.readFile(path.join(filesDir, file), 'utf8')
+  }
+  return allContent
+}
+
 describe('typeof window replace', () => {
   ;(process.env.TURBOPACK_DEV ? describe.skip : describe)(
     'production mode',
@@ -19,67 +42,32 @@ describe('typeof window replace', () => {
         ), 'utf8')
       })
 
-      it('Replaces `typeof window` with object for client code', async () => {
-        let allContent = ''
-        const files = buildManifest.pages['/'].filter((item) =>
-          item.endsWith('.js')
-        )
-        for (const file of files) {
-          const content = await fs.readFile(
-            path.join(appDir, '.next', file),
-            'utf8'
-          )
-          allContent += content
-        }
+      it('Replaces `typeof window` and `typeof document` with object for client code', async () => {
+        const allContent = await readClientFiles('/')
         expect(allContent).toMatch(/Hello.*?,.*?\n?.*?("|')object("|')/)
+        expect(allContent).toMatch(/Document.*?,.*?\n?.*?("|')object("|')/)
       })
 
-      it('Replaces `typeof window` with undefined for server code', async () => {
-        let allContent = ''
-        const chunksFilesDir = path.join(appDir, '.next', 'server', 'chunks')
-        const allFilesInDotNextServerChunks = await fs
-          .readdirSync(chunksFilesDir, {
-            recursive: true,
-          })
-          .filter((item) => item.endsWith('.js'))
-        for (const file of allFilesInDotNextServerChunks) {
-          const content = await fs.readFile(
-            path.join(chunksFilesDir, file),
-            'utf8'
-          )
-          allContent += content
-        }
-
-        const pagesFilesDir = path.join(appDir, '.next', 'server', 'pages')
-        const allFilesInDotNextServerPages = await fs
-          .readdirSync(pagesFilesDir, {
-            recursive: true,
-          })
-          .filter((item) => item.endsWith('.js'))
-        for (const file of allFilesInDotNextServerPages) {
-          const content = await fs.readFile(
-            path.join(pagesFilesDir, file),
-            'utf8'
-          )
-          allContent += content
-        }
-
+      it('Replaces `typeof window` and `typeof document` with undefined for server code', async () => {
+        const allContent =
+          (await readServerFiles('chunks')) + (await readServerFiles('pages'))
         expect(allContent).toMatch(/Hello.*?,.*?\n?.*?("|')undefined("|')/)
+        expect(allContent).toMatch(/Document.*?,.*?\n?.*?("|')undefined("|')/)
       })
 
-      it('Does not replace `typeof window` for `node_modules` code', async () => {
-        let allContent = ''
-        const files = buildManifest.pages['/'].filter((item) =>
-          item.endsWith('.js')
+      it('Replaces `typeof window` and `typeof document` with undefined for edge server code', async () => {
+        // Edge pages are emitted to `server/pages` or `server/edge` depending on the bundler
+        const allContent = await readServerFiles('')
+        expect(allContent).toMatch(/Edge window.*?,.*?\n?.*?("|')undefined("|')/)
+        expect(allContent).toMatch(
+          /Edge document.*?,.*?\n?.*?("|')undefined("|')/
         )
-        for (const file of files) {
-          const content = await fs.readFile(
-            path.join(appDir, '.next', file),
-            'utf8'
-          )
-          allContent += content
-        }
+      })
+
+      it('Does not replace `typeof window` and `typeof document` for `node_modules` code', async () => {
+        const allContent = await readClientFiles('/')
         expect(allContent).toMatch(/MyComp:.*?,.*?typeof window/)
+        expect(allContent).toMatch(/MyDocument:.*?,.*?typeof document/)
       })
     }
   )
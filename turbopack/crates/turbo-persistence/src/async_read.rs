@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use crate::{ArcSlice, QueryKey, TurboPersistence};
+
+/// Lookups that can be awaited. SST files are memory mapped, so reading a value that is not in the
+/// page cache blocks the thread on a page fault. These methods run the lookup on the blocking
+/// thread pool of tokio, so async worker threads stay available while data is restored from a cold
+/// cache.
+impl TurboPersistence {
+    /// Get a value from the database without blocking the async runtime. See
+    /// [TurboPersistence::get].
+    pub async fn get_async<K: QueryKey + Send + 'static>(
+        self: &Arc<Self>,
+        family: usize,
+        key: K,
+    ) -> Result<Option<ArcSlice<u8>>> {
+        let db = self.clone();
+        tokio::task::spawn_blocking(move || db.get(family, &key))
+            .await
+            .context("Lookup task failed")?
+    }
+
+    /// Get the values of multiple keys from the database without blocking the async runtime. See
+    /// [TurboPersistence::get_many].
+    pub async fn get_many_async<K: QueryKey + Send + 'static>(
+        self: &Arc<Self>,
+        family: usize,
+        keys: Vec<K>,
+    ) -> Result<Vec<Option<ArcSlice<u8>>>> {
+        let db = self.clone();
+        tokio::task::spawn_blocking(move || db.get_many(family, &keys))
+            .await
+            .context("Lookup task failed")?
+    }
+}
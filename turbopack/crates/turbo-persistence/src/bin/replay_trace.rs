@@ -0,0 +1,58 @@
+//! Replays a trace recorded with `DbOptions::trace` against a fresh database and prints how long
+//! the operations took.
+//!
+//! Usage: `replay_trace <trace file> <database directory>`
+
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use turbo_persistence::{read_trace, replay_trace, DbOptions, MergeOperator, TurboPersistence};
+
+/// The number of families that merge operators are registered for.
+const FAMILIES: usize = 64;
+
+fn main() -> Result<()> {
+    let mut args = env::args_os().skip(1);
+    let (Some(trace_path), Some(db_path), None) = (args.next(), args.next(), args.next()) else {
+        bail!("Usage: replay_trace <trace file> <database directory>");
+    };
+    let trace_path = PathBuf::from(trace_path);
+    let db_path = PathBuf::from(db_path);
+    if fs::read_dir(&db_path).is_ok_and(|mut entries| entries.next().is_some()) {
+        bail!(
+            "The database directory {} is not empty, a trace needs to be replayed against a fresh \
+             database",
+            db_path.display()
+        );
+    }
+
+    let records = read_trace(&trace_path)
+        .with_context(|| format!("Unable to read trace {}", trace_path.display()))?;
+    // Values are not part of the trace, so merging operands can't reproduce the original values.
+    // The last operand replaces the value instead.
+    let merge_operators = (0..FAMILIES)
+        .map(|_| Some(MergeOperator::new(|_, operand| operand.to_vec())))
+        .collect();
+    let db = TurboPersistence::open_with_options(
+        db_path,
+        DbOptions {
+            merge_operators,
+            ..Default::default()
+        },
+    )?;
+    let stats = replay_trace(&db, &records)?;
+    db.shutdown()?;
+
+    println!("Replayed {} records", records.len());
+    println!(
+        "Recorded duration: {:?}, replay duration: {:?}",
+        stats.recorded_duration, stats.duration
+    );
+    println!("Writes: {} in {:?}", stats.writes, stats.write_duration);
+    println!("Commits: {} in {:?}", stats.commits, stats.commit_duration);
+    println!(
+        "Gets: {} in {:?} ({} found a different result than recorded)",
+        stats.gets, stats.get_duration, stats.mismatched_gets
+    );
+    Ok(())
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read, Write},
+    mem::{transmute, MaybeUninit},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+use memmap2::Mmap;
+use parking_lot::Mutex;
+
+use crate::{
+    arc_slice::ArcSlice,
+    compression::Compression,
+    encryption::{self, Encryption, ENCRYPTED_FLAG},
+};
+
+/// Flag in the compression tag of blob files that marks the value as split into chunks, which are
+/// compressed and encrypted independently.
+pub const CHUNKED_FLAG: u8 = 0x40;
+
+/// Flag in the compression tag of blob files that marks that the content hash of the value is
+/// stored after the compression tag.
+pub const CONTENT_HASH_FLAG: u8 = 0x20;
+
+/// The uncompressed size of a chunk of a blob file.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Stored as value length in the header when the length doesn't fit into 4 bytes. The length
+/// follows in 8 bytes. Blob values are never empty, as only large values are stored in blob files.
+const LARGE_LENGTH: u32 = 0;
+
+/// Computes the content hash of a value, which identifies equal values for deduplication.
+pub fn content_hash(value: &[u8]) -> u128 {
+    twox_hash::XxHash3_128::oneshot(value)
+}
+
+/// Writes a value as content of a blob file and returns the number of written bytes. The value is
+/// split into chunks, so it can be read incrementally, and each chunk is written when it's
+/// compressed. The content hash is stored in the header when given.
+pub fn write_blob(
+    value: &[u8],
+    compression: Compression,
+    encryption: Option<&Encryption>,
+    content_hash: Option<u128>,
+    writer: &mut impl Write,
+) -> Result<u64> {
+    let mut buffer = Vec::new();
+    match u32::try_from(value.len()) {
+        Ok(length) if length != LARGE_LENGTH => buffer.write_u32::<BE>(length)?,
+        _ => {
+            buffer.write_u32::<BE>(LARGE_LENGTH)?;
+            buffer.write_u64::<BE>(value.len() as u64)?;
+        }
+    }
+    let mut compression_tag = compression.tag() | CHUNKED_FLAG;
+    if encryption.is_some() {
+        compression_tag |= ENCRYPTED_FLAG;
+    }
+    if content_hash.is_some() {
+        compression_tag |= CONTENT_HASH_FLAG;
+    }
+    buffer.write_u8(compression_tag)?;
+    if let Some(content_hash) = content_hash {
+        buffer.write_u128::<BE>(content_hash)?;
+    }
+    writer.write_all(&buffer)?;
+    let mut size = buffer.len() as u64;
+    for chunk in value.chunks(CHUNK_SIZE) {
+        let mut stored = compression
+            .compress(chunk, &[])
+            .context("Compression of value for blob file failed")?;
+        if let Some(encryption) = encryption {
+            stored = encryption
+                .encrypt(&stored)
+                .context("Encryption of value for blob file failed")?;
+        }
+        buffer.clear();
+        buffer.write_u32::<BE>(chunk.len() as u32)?;
+        buffer.write_u32::<BE>(stored.len() as u32)?;
+        writer.write_all(&buffer)?;
+        writer.write_all(&stored)?;
+        size += (buffer.len() + stored.len()) as u64;
+    }
+    Ok(size)
+}
+
+/// The header of a blob file.
+struct BlobHeader {
+    /// The sequence number of the blob file.
+    sequence_number: u32,
+    /// The uncompressed length of the value.
+    length: usize,
+    /// The compression of the value or its chunks.
+    compression: Compression,
+    /// Whether the value or its chunks are encrypted.
+    encrypted: bool,
+    /// Whether the value is split into chunks.
+    chunked: bool,
+    /// The content hash of the value, if it was stored.
+    content_hash: Option<u128>,
+}
+
+impl BlobHeader {
+    /// Reads the header from the start of the blob file content.
+    fn read(sequence_number: u32, content: &mut &[u8]) -> Result<Self> {
+        let mut length = content.read_u32::<BE>()? as u64;
+        if length == LARGE_LENGTH as u64 {
+            length = content.read_u64::<BE>()?;
+        }
+        let Ok(length) = usize::try_from(length) else {
+            bail!("Blob value of {length} bytes exceeds the address space");
+        };
+        let compression_tag = content.read_u8()?;
+        let content_hash = if compression_tag & CONTENT_HASH_FLAG != 0 {
+            Some(content.read_u128::<BE>()?)
+        } else {
+            None
+        };
+        Ok(Self {
+            sequence_number,
+            length,
+            compression: Compression::from_tag(
+                compression_tag & !(ENCRYPTED_FLAG | CHUNKED_FLAG | CONTENT_HASH_FLAG),
+            )?,
+            encrypted: compression_tag & ENCRYPTED_FLAG != 0,
+            chunked: compression_tag & CHUNKED_FLAG != 0,
+            content_hash,
+        })
+    }
+
+    /// Decrypts and decompresses the value or a chunk into a buffer of the uncompressed size.
+    fn decode(
+        &self,
+        stored: &[u8],
+        buffer: &mut [u8],
+        encryption: Option<&Encryption>,
+    ) -> Result<()> {
+        let decrypted;
+        let mut stored = stored;
+        if self.encrypted {
+            decrypted = encryption::required(encryption)?
+                .decrypt(stored)
+                .with_context(|| {
+                    format!(
+                        "Unable to decrypt blob file {:08}.blob",
+                        self.sequence_number
+                    )
+                })?;
+            stored = &decrypted;
+        }
+        self.compression.decompress(stored, buffer, &[])
+    }
+}
+
+/// Reads the header of the next chunk and returns the uncompressed length and the stored chunk.
+fn next_chunk<'l>(content: &mut &'l [u8]) -> Result<(usize, &'l [u8])> {
+    let length = content.read_u32::<BE>()? as usize;
+    let stored_length = content.read_u32::<BE>()? as usize;
+    let Some((stored, rest)) = content.split_at_checked(stored_length) else {
+        bail!("Blob chunk exceeds the file, the file might be truncated");
+    };
+    *content = rest;
+    if length == 0 {
+        bail!("Invalid empty blob chunk");
+    }
+    Ok((length, stored))
+}
+
+/// Memory maps a blob file for reading it once from start to end.
+fn map_blob_file(db_path: &Path, seq: u32) -> Result<Mmap> {
+    let path = db_path.join(format!("{:08}.blob", seq));
+    let mmap = unsafe { Mmap::map(&File::open(&path)?)? };
+    #[cfg(unix)]
+    mmap.advise(memmap2::Advice::Sequential)?;
+    #[cfg(unix)]
+    mmap.advise(memmap2::Advice::WillNeed)?;
+    #[cfg(target_os = "linux")]
+    mmap.advise(memmap2::Advice::DontFork)?;
+    #[cfg(target_os = "linux")]
+    mmap.advise(memmap2::Advice::Unmergeable)?;
+    Ok(mmap)
+}
+
+/// Reads and decompresses a blob file of the database in `db_path`.
+pub(crate) fn read_blob_file(
+    db_path: &Path,
+    seq: u32,
+    encryption: Option<&Encryption>,
+) -> Result<ArcSlice<u8>> {
+    let mmap = map_blob_file(db_path, seq)?;
+    let mut content = &mmap[..];
+    let header = BlobHeader::read(seq, &mut content)?;
+
+    let buffer = Arc::new_zeroed_slice(header.length);
+    // Safety: MaybeUninit<u8> can be safely transmuted to u8.
+    let mut buffer = unsafe { transmute::<Arc<[MaybeUninit<u8>]>, Arc<[u8]>>(buffer) };
+    // Safety: We know that the buffer is not shared yet.
+    let decompressed = unsafe { Arc::get_mut_unchecked(&mut buffer) };
+    if header.chunked {
+        let mut position = 0;
+        while position < header.length {
+            let (length, stored) = next_chunk(&mut content)?;
+            let Some(chunk) = decompressed.get_mut(position..position + length) else {
+                bail!("Blob chunk exceeds the value length");
+            };
+            header.decode(stored, chunk, encryption)?;
+            position += length;
+        }
+    } else {
+        header.decode(content, decompressed, encryption)?;
+    }
+    Ok(ArcSlice::from(buffer))
+}
+
+/// Opens a reader for a blob file. Chunked blob files are decompressed chunk by chunk while
+/// reading. Blob files written before chunking was introduced are decompressed completely.
+pub(crate) fn open_blob_reader(
+    db_path: &Path,
+    seq: u32,
+    encryption: Option<&Encryption>,
+) -> Result<ValueReader> {
+    let mmap = map_blob_file(db_path, seq)?;
+    let mut content = &mmap[..];
+    let header = BlobHeader::read(seq, &mut content)?;
+    if !header.chunked {
+        return Ok(ValueReader::from(read_blob_file(db_path, seq, encryption)?));
+    }
+    let encryption = if header.encrypted {
+        Some(encryption::required(encryption)?.clone())
+    } else {
+        None
+    };
+    let position = mmap.len() - content.len();
+    Ok(ValueReader {
+        len: header.length,
+        inner: ValueReaderInner::Blob(BlobReader {
+            remaining: header.length,
+            header,
+            mmap,
+            position,
+            encryption,
+            chunk: Vec::new(),
+            chunk_position: 0,
+        }),
+    })
+}
+
+/// Maps content hashes of committed blob files to their sequence numbers, so write batches can
+/// reference an existing blob file instead of writing the same value again.
+#[derive(Default)]
+pub(crate) struct BlobIndex {
+    blob_files: Mutex<HashMap<u128, u32>>,
+}
+
+impl BlobIndex {
+    /// Reads the content hashes from the headers of all blob files in the database directory.
+    /// Blob files without content hash are not deduplicated.
+    pub(crate) fn load(db_path: &Path) -> Result<Self> {
+        let mut blob_files = HashMap::new();
+        for entry in fs::read_dir(db_path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("blob") {
+                continue;
+            }
+            let Some(seq) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            // Only the length, the compression tag and the content hash are read
+            let mut header = Vec::with_capacity(29);
+            File::open(&path)?.take(29).read_to_end(&mut header)?;
+            let header = BlobHeader::read(seq, &mut &header[..])
+                .with_context(|| format!("Unable to read header of {:08}.blob", seq))?;
+            if let Some(content_hash) = header.content_hash {
+                blob_files.insert(content_hash, seq);
+            }
+        }
+        Ok(Self {
+            blob_files: Mutex::new(blob_files),
+        })
+    }
+
+    /// Returns the blob file with the content hash.
+    pub(crate) fn get(&self, content_hash: u128) -> Option<u32> {
+        self.blob_files.lock().get(&content_hash).copied()
+    }
+
+    /// Adds the blob files of a committed write batch.
+    pub(crate) fn extend(&self, blob_files: impl IntoIterator<Item = (u128, u32)>) {
+        self.blob_files.lock().extend(blob_files);
+    }
+
+    /// Removes deleted blob files.
+    pub(crate) fn remove(&self, removed_blob_files: &HashSet<u32>) {
+        self.blob_files
+            .lock()
+            .retain(|_, seq| !removed_blob_files.contains(seq));
+    }
+}
+
+/// Reads a value incrementally. Created by [crate::TurboPersistence::get_reader].
+///
+/// Values stored in blob files are decompressed chunk by chunk while reading, so only a single
+/// chunk is held in memory. Smaller values are already in memory.
+pub struct ValueReader {
+    /// The uncompressed length of the value.
+    len: usize,
+    inner: ValueReaderInner,
+}
+
+enum ValueReaderInner {
+    Slice {
+        value: ArcSlice<u8>,
+        position: usize,
+    },
+    Blob(BlobReader),
+}
+
+impl ValueReader {
+    /// Returns the length of the value in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the value is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl From<ArcSlice<u8>> for ValueReader {
+    fn from(value: ArcSlice<u8>) -> Self {
+        Self {
+            len: value.len(),
+            inner: ValueReaderInner::Slice { value, position: 0 },
+        }
+    }
+}
+
+impl Read for ValueReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            ValueReaderInner::Slice { value, position } => {
+                let n = (&value[*position..]).read(buf)?;
+                *position += n;
+                Ok(n)
+            }
+            ValueReaderInner::Blob(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Reads a chunked blob file.
+struct BlobReader {
+    header: BlobHeader,
+    mmap: Mmap,
+    /// The position of the next chunk in the file.
+    position: usize,
+    /// The uncompressed length of the chunks that are not read yet.
+    remaining: usize,
+    encryption: Option<Encryption>,
+    /// The current decompressed chunk.
+    chunk: Vec<u8>,
+    /// The read position in the current chunk.
+    chunk_position: usize,
+}
+
+impl BlobReader {
+    /// Decompresses the next chunk into the chunk buffer.
+    fn read_next_chunk(&mut self) -> Result<()> {
+        let mut content = &self.mmap[self.position..];
+        let (length, stored) = next_chunk(&mut content)?;
+        if length > self.remaining {
+            bail!("Blob chunk exceeds the value length");
+        }
+        self.chunk.resize(length, 0);
+        self.header
+            .decode(stored, &mut self.chunk, self.encryption.as_ref())?;
+        self.position = self.mmap.len() - content.len();
+        self.remaining -= length;
+        self.chunk_position = 0;
+        Ok(())
+    }
+}
+
+impl Read for BlobReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.chunk_position == self.chunk.len() {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+            self.read_next_chunk().map_err(io::Error::other)?;
+        }
+        let n = (&self.chunk[self.chunk_position..]).read(buf)?;
+        self.chunk_position += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,183 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    arc_slice::ArcSlice,
+    db::TurboPersistence,
+    lookup_entry::{LookupEntry, LookupValue},
+    range_tombstone::RangeTombstone,
+    static_sorted_file::{BlockCache, StaticSortedFile, StaticSortedFileIter},
+};
+
+/// A change that was committed to the database. Returned by a [ChangeFeed].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A value was written for a key.
+    Put {
+        family: usize,
+        key: ArcSlice<u8>,
+        value: ArcSlice<u8>,
+        /// The expiration timestamp of the value in seconds since the UNIX epoch, if any.
+        expires_at: Option<u64>,
+    },
+    /// A key was deleted, or its value expired.
+    Delete { family: usize, key: ArcSlice<u8> },
+    /// A merge operand was added to the value of a key. It's applied by the merge operator of the
+    /// family.
+    Merge {
+        family: usize,
+        key: ArcSlice<u8>,
+        operand: ArcSlice<u8>,
+    },
+    /// All keys of a family within `start..end` that were committed before were deleted.
+    DeleteRange {
+        family: usize,
+        start: Vec<u8>,
+        end: Vec<u8>,
+    },
+}
+
+/// A SST file or range tombstone with changes.
+enum Source {
+    StaticSortedFile(Arc<StaticSortedFile>),
+    RangeTombstone(RangeTombstone),
+}
+
+/// An iterator over the changes that were committed after a sequence number, in commit order.
+/// Created by [TurboPersistence::changes_since].
+///
+/// The changes are read from the SST files and range tombstones at the time the feed was created,
+/// like a [crate::Snapshot]. The changes of a commit are yielded in key hash order. Once all
+/// changes are read, the feed can be continued with the [ChangeFeed::sequence_number].
+pub struct ChangeFeed<'l> {
+    db: &'l TurboPersistence,
+    key_block_cache: &'l BlockCache,
+    value_block_cache: &'l BlockCache,
+    /// The sequence number of the database at the time the feed was created.
+    sequence_number: u32,
+    /// The remaining SST files and range tombstones, in commit order.
+    sources: VecDeque<Source>,
+    /// The family of the SST file that is currently read, and its iterator.
+    current: Option<(usize, StaticSortedFileIter<'l>)>,
+}
+
+impl<'l> ChangeFeed<'l> {
+    /// Creates a feed of the changes in the SST files and range tombstones that were committed
+    /// after `since`. Fails when compaction merged some of these changes with other commits.
+    pub(crate) fn new(
+        db: &'l TurboPersistence,
+        key_block_cache: &'l BlockCache,
+        value_block_cache: &'l BlockCache,
+        since: u32,
+        sequence_number: u32,
+        static_sorted_files: &[Arc<StaticSortedFile>],
+        range_tombstones: &[RangeTombstone],
+    ) -> Result<Self> {
+        let mut sources = Vec::new();
+        for sst in static_sorted_files {
+            let (min, max) = sst.commit_sequence_numbers()?;
+            if max <= since {
+                continue;
+            }
+            if min != max {
+                bail!(
+                    "The changes after sequence number {since} are no longer available, as \
+                     compaction merged them into SST file {:08}.sst",
+                    sst.sequence_number()
+                );
+            }
+            sources.push((min, Source::StaticSortedFile(sst.clone())));
+        }
+        for tombstone in range_tombstones {
+            if tombstone.file > since {
+                sources.push((
+                    tombstone.sequence_number,
+                    Source::RangeTombstone(tombstone.clone()),
+                ));
+            }
+        }
+        // A range tombstone deletes keys of the SST files up to its sequence number, so it comes
+        // after them and before all newer files
+        sources.sort_by_key(|(seq, source)| (*seq, matches!(source, Source::RangeTombstone(_))));
+        Ok(Self {
+            db,
+            key_block_cache,
+            value_block_cache,
+            sequence_number,
+            sources: sources.into_iter().map(|(_, source)| source).collect(),
+            current: None,
+        })
+    }
+
+    /// Returns the sequence number of the database at the time the feed was created. The feed
+    /// contains all changes up to it, so the next feed can start after it.
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    /// Converts an entry of a SST file into a change.
+    fn change(&self, family: usize, entry: LookupEntry) -> Result<Change> {
+        let LookupEntry {
+            key,
+            value,
+            expires_at,
+            ..
+        } = entry;
+        Ok(match value {
+            LookupValue::Deleted => Change::Delete { family, key },
+            LookupValue::Slice { value } => Change::Put {
+                family,
+                key,
+                value,
+                expires_at,
+            },
+            LookupValue::Blob { sequence_number } => Change::Put {
+                family,
+                key,
+                value: self.db.read_blob(sequence_number)?,
+                expires_at,
+            },
+            LookupValue::Merge { operand } => Change::Merge {
+                family,
+                key,
+                operand,
+            },
+        })
+    }
+
+    fn next_internal(&mut self) -> Result<Option<Change>> {
+        loop {
+            if let Some((family, iter)) = &mut self.current {
+                let family = *family;
+                if let Some(entry) = iter.next().transpose()? {
+                    return self.change(family, entry).map(Some);
+                }
+                self.current = None;
+            }
+            match self.sources.pop_front() {
+                Some(Source::StaticSortedFile(sst)) => {
+                    let family = sst.range()?.family as usize;
+                    let iter = sst.iter(self.key_block_cache, self.value_block_cache)?;
+                    self.current = Some((family, iter));
+                }
+                Some(Source::RangeTombstone(tombstone)) => {
+                    return Ok(Some(Change::DeleteRange {
+                        family: tombstone.family as usize,
+                        start: tombstone.start,
+                        end: tombstone.end,
+                    }));
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Iterator for ChangeFeed<'_> {
+    type Item = Result<Change>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_internal().transpose()
+    }
+}
@@ -0,0 +1,34 @@
+use std::{fmt, hash::Hasher};
+
+/// Computes the checksum of a block as stored in the file.
+pub fn block_checksum(block: &[u8]) -> u64 {
+    let mut hasher = twox_hash::XxHash64::with_seed(0);
+    hasher.write(block);
+    hasher.finish()
+}
+
+/// The error returned when a block of an SST file doesn't match its checksum. It can be
+/// retrieved from the returned [anyhow::Error] with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptionError {
+    /// The sequence number of the corrupted SST file.
+    pub sequence_number: u32,
+    /// The index of the corrupted block in the file.
+    pub block_index: u16,
+    /// The checksum stored in the file.
+    pub expected_checksum: u64,
+    /// The checksum of the block data.
+    pub actual_checksum: u64,
+}
+
+impl fmt::Display for CorruptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Corrupted file seq:{} block:{} checksum {:016x} doesn't match stored checksum {:016x}",
+            self.sequence_number, self.block_index, self.actual_checksum, self.expected_checksum
+        )
+    }
+}
+
+impl std::error::Error for CorruptionError {}
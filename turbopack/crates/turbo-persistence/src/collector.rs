@@ -1,8 +1,6 @@
 use crate::{
     collector_entry::{CollectorEntry, CollectorEntryValue, EntryKey},
-    constants::{
-        DATA_THRESHOLD_PER_INITIAL_FILE, MAX_ENTRIES_PER_INITIAL_FILE, MAX_SMALL_VALUE_SIZE,
-    },
+    constants::{MAX_ENTRIES_PER_INITIAL_FILE, MAX_SMALL_VALUE_SIZE},
     key::{hash_key, StoreKey},
     ValueBuffer,
 };
@@ -30,14 +28,15 @@ impl<K: StoreKey> Collector<K> {
         self.entries.is_empty()
     }
 
-    /// Returns true if the collector is full.
-    pub fn is_full(&self) -> bool {
+    /// Returns true if the collector is full, i. e. it has the maximum number of entries or the
+    /// size of its keys and values exceeds `max_size`.
+    pub fn is_full(&self, max_size: usize) -> bool {
         self.entries.len() >= MAX_ENTRIES_PER_INITIAL_FILE
-            || self.total_key_size + self.total_value_size > DATA_THRESHOLD_PER_INITIAL_FILE
+            || self.total_key_size + self.total_value_size > max_size
     }
 
-    /// Adds a normal key-value pair to the collector.
-    pub fn put(&mut self, key: K, value: ValueBuffer) {
+    /// Adds a normal key-value pair to the collector. It expires at the given timestamp, if any.
+    pub fn put(&mut self, key: K, value: ValueBuffer, expires_at: Option<u64>) {
         let key = EntryKey {
             hash: hash_key(&key),
             data: key,
@@ -53,11 +52,15 @@ impl<K: StoreKey> Collector<K> {
         };
         self.total_key_size += key.len();
         self.total_value_size += value.len();
-        self.entries.push(CollectorEntry { key, value });
+        self.entries.push(CollectorEntry {
+            key,
+            value,
+            expires_at,
+        });
     }
 
-    /// Adds a blob key-value pair to the collector.
-    pub fn put_blob(&mut self, key: K, blob: u32) {
+    /// Adds a blob key-value pair to the collector. It expires at the given timestamp, if any.
+    pub fn put_blob(&mut self, key: K, blob: u32, expires_at: Option<u64>) {
         let key = EntryKey {
             hash: hash_key(&key),
             data: key,
@@ -66,6 +69,7 @@ impl<K: StoreKey> Collector<K> {
         self.entries.push(CollectorEntry {
             key,
             value: CollectorEntryValue::Large { blob },
+            expires_at,
         });
     }
 
@@ -79,6 +83,25 @@ impl<K: StoreKey> Collector<K> {
         self.entries.push(CollectorEntry {
             key,
             value: CollectorEntryValue::Deleted,
+            expires_at: None,
+        });
+    }
+
+    /// Adds a merge operand to the collector.
+    pub fn merge(&mut self, key: K, operand: ValueBuffer) {
+        let key = EntryKey {
+            hash: hash_key(&key),
+            data: key,
+        };
+        let value = CollectorEntryValue::Merge {
+            value: operand.into_small_vec(),
+        };
+        self.total_key_size += key.len();
+        self.total_value_size += value.len();
+        self.entries.push(CollectorEntry {
+            key,
+            value,
+            expires_at: None,
         });
     }
 
@@ -115,4 +138,9 @@ impl<K: StoreKey> Collector<K> {
     pub fn len(&self) -> usize {
         self.entries.len()
     }
+
+    /// Returns the total size of the keys and values in the collector.
+    pub fn size(&self) -> usize {
+        self.total_key_size + self.total_value_size
+    }
 }
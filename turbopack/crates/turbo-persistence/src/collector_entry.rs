@@ -10,6 +10,8 @@ use crate::{
 pub struct CollectorEntry<K: StoreKey> {
     pub key: EntryKey<K>,
     pub value: CollectorEntryValue,
+    /// The expiration timestamp in seconds since the UNIX epoch, if any.
+    pub expires_at: Option<u64>,
 }
 
 pub enum CollectorEntryValue {
@@ -17,6 +19,7 @@ pub enum CollectorEntryValue {
     Medium { value: Vec<u8> },
     Large { blob: u32 },
     Deleted,
+    Merge { value: SmallVec<[u8; 16]> },
 }
 
 impl CollectorEntryValue {
@@ -26,6 +29,7 @@ impl CollectorEntryValue {
             CollectorEntryValue::Medium { value } => value.len(),
             CollectorEntryValue::Large { blob: _ } => 0,
             CollectorEntryValue::Deleted => 0,
+            CollectorEntryValue::Merge { value } => value.len(),
         }
     }
 }
@@ -76,12 +80,17 @@ impl<K: StoreKey> Entry for CollectorEntry<K> {
         self.key.data.write_to(buf);
     }
 
+    fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
     fn value(&self) -> EntryValue<'_> {
         match &self.value {
             CollectorEntryValue::Small { value } => EntryValue::Small { value },
             CollectorEntryValue::Medium { value } => EntryValue::Medium { value },
             CollectorEntryValue::Large { blob } => EntryValue::Large { blob: *blob },
             CollectorEntryValue::Deleted => EntryValue::Deleted,
+            CollectorEntryValue::Merge { value } => EntryValue::Merge { value },
         }
     }
 }
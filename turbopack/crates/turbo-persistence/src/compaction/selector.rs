@@ -19,6 +19,13 @@ type Range = (u64, u64);
 pub trait Compactable {
     /// Returns the range of the compactable.
     fn range(&self) -> Range;
+
+    /// Returns how much compacting the compactable would speed up lookups, relative to other
+    /// compactables. Merge jobs start with the compactables of the highest priority, so they are
+    /// merged when the number of files per merge job is limited.
+    fn priority(&self) -> f32 {
+        1.0
+    }
 }
 
 fn is_overlapping(a: &Range, b: &Range) -> bool {
@@ -86,19 +93,24 @@ fn get_compaction_jobs_internal<T: Compactable>(
     let age = |i| (len - 1 - i) as f32;
 
     loop {
-        // Find the first unused compactable.
-        let Some(start) = used_compactables
-            .iter()
-            .skip(start_index)
-            .position(|&used| !used)
-            .map(|i| i + start_index)
+        // Find the unused compactable with the highest priority, the oldest one for equal
+        // priorities.
+        let Some(start) = (start_index..len)
+            .filter(|&i| !used_compactables[i])
+            .rev()
+            .max_by(|&a, &b| {
+                compactables[a]
+                    .priority()
+                    .total_cmp(&compactables[b].priority())
+            })
         else {
             break;
         };
+        used_compactables[start] = true;
         if start >= len - 1 {
-            break;
+            // There is no newer compactable to merge with
+            continue;
         }
-        used_compactables[start] = true;
         let start_range = compactables[start].range();
         let mut range = start_range;
 
@@ -161,7 +173,17 @@ fn get_compaction_jobs_internal<T: Compactable>(
         for &i in merge_job.iter().skip(1) {
             extend_range(&mut merge_range, &compactables[i].range());
         }
-        merge_jobs_reducation = (merge_job_input_spread - spread(&merge_range) as f32) * age(start);
+        merge_jobs_reducation = (merge_job_input_spread - spread(&merge_range) as f32)
+            * age(start)
+            * compactables[start].priority();
+
+        // Older overlapping compactables are left as they are. Merging them would create a file
+        // that is newer than the merged newer compactables.
+        for i in start_index..start {
+            if !used_compactables[i] && is_overlapping(&merge_range, &compactables[i].range()) {
+                used_compactables[i] = true;
+            }
+        }
 
         for (i, compactable) in compactables
             .iter()
@@ -216,18 +238,30 @@ mod tests {
 
     struct TestCompactable {
         range: Range,
+        priority: f32,
     }
 
     impl Compactable for TestCompactable {
         fn range(&self) -> Range {
             self.range
         }
+
+        fn priority(&self) -> f32 {
+            self.priority
+        }
     }
 
     fn compact<const N: usize>(ranges: [(u64, u64); N], max_merge: usize) -> CompactionJobs {
-        let compactables = ranges
+        compact_with_priorities(ranges.map(|range| (range, 1.0)), max_merge)
+    }
+
+    fn compact_with_priorities<const N: usize>(
+        compactables: [((u64, u64), f32); N],
+        max_merge: usize,
+    ) -> CompactionJobs {
+        let compactables = compactables
             .iter()
-            .map(|&range| TestCompactable { range })
+            .map(|&(range, priority)| TestCompactable { range, priority })
             .collect::<Vec<_>>();
         let config = CompactConfig {
             max_merge,
@@ -260,6 +294,26 @@ mod tests {
         assert_eq!(move_jobs, vec![3, 8]);
     }
 
+    #[test]
+    fn test_compaction_jobs_priority() {
+        let CompactionJobs {
+            merge_jobs,
+            move_jobs,
+            ..
+        } = compact([(0, 10), (0, 10), (0, 10)], 2);
+        assert_eq!(merge_jobs, vec![vec![0, 1]]);
+        assert_eq!(move_jobs, vec![2]);
+
+        // The older file is left as it is, so it doesn't shadow the merged newer files
+        let CompactionJobs {
+            merge_jobs,
+            move_jobs,
+            ..
+        } = compact_with_priorities([((0, 10), 1.0), ((0, 10), 4.0), ((0, 10), 1.0)], 2);
+        assert_eq!(merge_jobs, vec![vec![1, 2]]);
+        assert!(move_jobs.is_empty());
+    }
+
     #[test]
     fn simulate_compactions() {
         let mut rnd = rand::rngs::SmallRng::from_seed([0; 32]);
@@ -0,0 +1,120 @@
+use std::{cmp::Ordering, fmt, sync::Arc};
+
+use anyhow::{bail, Result};
+
+use crate::{key::write_key_bytes, QueryKey};
+
+/// A function that orders the keys of a family for range scans and range deletions, e. g. for keys
+/// that store numbers in little endian or composite keys with variable length parts. Without a
+/// comparator keys are ordered by their bytes.
+///
+/// Entries are still stored by key hash, so the comparator only decides which keys are within the
+/// bounds of [crate::TurboPersistence::range] and [crate::WriteBatch::delete_range], and the order
+/// of [crate::TurboPersistence::range_rev].
+///
+/// The name identifies the ordering and is recorded in the manifest. Opening a database with a
+/// different comparator for a family that contains data fails, since its range tombstones would
+/// delete other keys with a different ordering.
+#[derive(Clone)]
+pub struct KeyComparator {
+    name: Arc<str>,
+    compare: Arc<dyn Fn(&[u8], &[u8]) -> Ordering + Send + Sync>,
+}
+
+impl KeyComparator {
+    /// Creates a comparator from a name and a function that implements a total order.
+    pub fn new(
+        name: impl Into<String>,
+        compare: impl Fn(&[u8], &[u8]) -> Ordering + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into().into(),
+            compare: Arc::new(compare),
+        }
+    }
+
+    /// Returns the name of the comparator.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the configured comparator for a family.
+    pub(crate) fn for_family(
+        comparators: &[Option<KeyComparator>],
+        family: u32,
+    ) -> Option<&KeyComparator> {
+        comparators.get(family as usize).and_then(|c| c.as_ref())
+    }
+
+    /// Returns the names of the configured comparators by family, as they are recorded in the
+    /// manifest.
+    pub(crate) fn names(comparators: &[Option<KeyComparator>]) -> Vec<(u32, String)> {
+        comparators
+            .iter()
+            .enumerate()
+            .filter_map(|(family, c)| Some((family as u32, c.as_ref()?.name().to_string())))
+            .collect()
+    }
+}
+
+impl fmt::Debug for KeyComparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KeyComparator").field(&self.name).finish()
+    }
+}
+
+/// Compares a query key with a stored key, using the comparator of the family or the byte order
+/// when there is none.
+pub(crate) fn compare<K: QueryKey>(
+    comparator: Option<&KeyComparator>,
+    query: &K,
+    key: &[u8],
+) -> Ordering {
+    match comparator {
+        Some(comparator) => {
+            let mut query_bytes = Vec::with_capacity(query.len());
+            write_key_bytes(query, &mut query_bytes);
+            (comparator.compare)(&query_bytes, key)
+        }
+        None => QueryKey::cmp(query, key),
+    }
+}
+
+/// Compares two stored keys, using the comparator of the family or the byte order when there is
+/// none.
+pub(crate) fn compare_keys(comparator: Option<&KeyComparator>, a: &[u8], b: &[u8]) -> Ordering {
+    match comparator {
+        Some(comparator) => (comparator.compare)(a, b),
+        None => a.cmp(b),
+    }
+}
+
+/// Checks that the configured comparators match the ones recorded in the manifest. A family
+/// without data can change its comparator.
+pub(crate) fn check_recorded(
+    recorded: &[(u32, String)],
+    configured: &[Option<KeyComparator>],
+    has_data: impl Fn(u32) -> bool,
+) -> Result<()> {
+    let families = recorded
+        .iter()
+        .map(|&(family, _)| family + 1)
+        .chain([configured.len() as u32])
+        .max()
+        .unwrap_or_default();
+    for family in 0..families {
+        let recorded = recorded
+            .iter()
+            .find(|&&(f, _)| f == family)
+            .map(|(_, name)| name.as_str());
+        let configured = KeyComparator::for_family(configured, family).map(|c| c.name());
+        if recorded != configured && has_data(family) {
+            bail!(
+                "Family {family} was written with key comparator {} but is opened with {}",
+                recorded.unwrap_or("<bytes>"),
+                configured.unwrap_or("<bytes>")
+            );
+        }
+    }
+    Ok(())
+}
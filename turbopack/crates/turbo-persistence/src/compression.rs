@@ -0,0 +1,161 @@
+use anyhow::{bail, Context, Result};
+use lzzzz::{
+    lz4::{self, max_compressed_size, ACC_LEVEL_DEFAULT},
+    lz4_hc,
+};
+
+/// The tag for uncompressed blocks.
+const COMPRESSION_NONE: u8 = 0;
+/// The tag for LZ4 compressed blocks.
+const COMPRESSION_LZ4: u8 = 1;
+/// The tag for zstd compressed blocks.
+const COMPRESSION_ZSTD: u8 = 2;
+
+/// The compression algorithm used for the blocks of SST files and for blob files of a family.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Blocks are stored uncompressed.
+    None,
+    /// Fast compression and decompression with moderate ratios.
+    #[default]
+    Lz4,
+    /// LZ4 with an acceleration factor. Higher factors compress faster with worse ratios, [Lz4]
+    /// uses a factor of 1.
+    ///
+    /// [Lz4]: Compression::Lz4
+    Lz4Fast { acceleration: i32 },
+    /// The high compression mode of LZ4. It compresses much slower with better ratios, e. g. for
+    /// blob files that are written once and read rarely, and decompresses as fast as LZ4. The
+    /// level ranges from 1 to 12, where higher levels compress better but slower.
+    Lz4Hc { level: i32 },
+    /// Better ratios at the cost of slower compression. The level is passed to zstd, where higher
+    /// levels compress better but slower.
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    /// Returns the configured compression for a family. Families without configuration use the
+    /// default compression.
+    pub(crate) fn for_family(compression: &[Compression], family: u32) -> Self {
+        compression
+            .get(family as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the configured compression for blob files of a family. Families without
+    /// configuration use the compression of their SST files.
+    pub(crate) fn for_blob_family(
+        compression: &[Compression],
+        blob_compression: &[Option<Compression>],
+        family: u32,
+    ) -> Self {
+        blob_compression
+            .get(family as usize)
+            .copied()
+            .flatten()
+            .unwrap_or_else(|| Self::for_family(compression, family))
+    }
+
+    /// Returns the tag that identifies the algorithm in file headers. The level is not needed for
+    /// decompression and is not stored.
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            Compression::None => COMPRESSION_NONE,
+            Compression::Lz4 | Compression::Lz4Fast { .. } | Compression::Lz4Hc { .. } => {
+                COMPRESSION_LZ4
+            }
+            Compression::Zstd { .. } => COMPRESSION_ZSTD,
+        }
+    }
+
+    /// Returns the algorithm for a tag read from a file header. LZ4 blocks are decompressed the
+    /// same way for all modes.
+    pub(crate) fn from_tag(tag: u8) -> Result<Self> {
+        Ok(match tag {
+            COMPRESSION_NONE => Compression::None,
+            COMPRESSION_LZ4 => Compression::Lz4,
+            COMPRESSION_ZSTD => Compression::Zstd { level: 0 },
+            _ => bail!("Invalid compression type {tag}"),
+        })
+    }
+
+    /// Returns true if a compression dictionary would be used by this algorithm.
+    pub(crate) fn uses_dictionary(&self) -> bool {
+        !matches!(self, Compression::None)
+    }
+
+    /// Compresses a block, optionally with a compression dictionary.
+    pub(crate) fn compress(&self, block: &[u8], dict: &[u8]) -> Result<Vec<u8>> {
+        match *self {
+            Compression::None => Ok(block.to_vec()),
+            Compression::Lz4 | Compression::Lz4Fast { .. } => {
+                let acceleration = match *self {
+                    Compression::Lz4Fast { acceleration } => acceleration,
+                    _ => ACC_LEVEL_DEFAULT,
+                };
+                let mut compressor =
+                    lz4::Compressor::with_dict(dict).context("LZ4 compressor creation failed")?;
+                let mut compressed = Vec::with_capacity(max_compressed_size(block.len()));
+                compressor
+                    .next_to_vec(block, &mut compressed, acceleration)
+                    .context("LZ4 compression failed")?;
+                Ok(compressed)
+            }
+            Compression::Lz4Hc { level } => {
+                let mut compressor = lz4_hc::Compressor::with_dict(dict, level)
+                    .context("LZ4 HC compressor creation failed")?;
+                let mut compressed = Vec::with_capacity(max_compressed_size(block.len()));
+                compressor
+                    .next_to_vec(block, &mut compressed)
+                    .context("LZ4 HC compression failed")?;
+                Ok(compressed)
+            }
+            Compression::Zstd { level } => {
+                let mut compressor = if dict.is_empty() {
+                    zstd::bulk::Compressor::new(level)
+                } else {
+                    zstd::bulk::Compressor::with_dictionary(level, dict)
+                }
+                .context("Zstd compressor creation failed")?;
+                compressor
+                    .compress(block)
+                    .context("Zstd compression failed")
+            }
+        }
+    }
+
+    /// Decompresses a block into a buffer of the uncompressed size, using the same compression
+    /// dictionary as for compression.
+    pub(crate) fn decompress(&self, block: &[u8], buffer: &mut [u8], dict: &[u8]) -> Result<()> {
+        match self {
+            Compression::None => {
+                if block.len() != buffer.len() {
+                    bail!("Uncompressed block has an unexpected length");
+                }
+                buffer.copy_from_slice(block);
+            }
+            Compression::Lz4 | Compression::Lz4Fast { .. } | Compression::Lz4Hc { .. } => {
+                let len = lz4::decompress_with_dict(block, buffer, dict)?;
+                if len != buffer.len() {
+                    bail!("Decompressed block has an unexpected length");
+                }
+            }
+            Compression::Zstd { .. } => {
+                let mut decompressor = if dict.is_empty() {
+                    zstd::bulk::Decompressor::new()
+                } else {
+                    zstd::bulk::Decompressor::with_dictionary(dict)
+                }
+                .context("Zstd decompressor creation failed")?;
+                let len = decompressor
+                    .decompress_to_buffer(block, buffer)
+                    .context("Zstd decompression failed")?;
+                if len != buffer.len() {
+                    bail!("Decompressed block has an unexpected length");
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -28,3 +28,28 @@ pub const KEY_BLOCK_AVG_SIZE: usize = 16 * 1024;
 /// Maximum RAM bytes for value block cache
 pub const VALUE_BLOCK_CACHE_SIZE: u64 = 300 * 1024 * 1024;
 pub const VALUE_BLOCK_AVG_SIZE: usize = 132000;
+
+/// Average RAM bytes of a row cache entry
+pub const ROW_CACHE_AVG_SIZE: usize = 1024;
+
+/// Maximum number of key families that can be written to the write-ahead log
+pub const MAX_WAL_FAMILIES: usize = 64;
+
+/// Number of sequence numbers that are reserved for the files of an open write batch when another
+/// write batch is started
+pub const WRITE_BATCH_SEQUENCE_NUMBER_RESERVATION: u32 = 64 * 1024;
+
+/// Maximum number of key families that can be written when replaying a trace
+pub const MAX_REPLAY_FAMILIES: usize = 64;
+
+/// Number of key blocks an iterator enters before it reads the following key blocks ahead
+pub const READAHEAD_TRIGGER_KEY_BLOCKS: usize = 2;
+
+/// Maximum number of key blocks an iterator reads ahead at once
+pub const READAHEAD_KEY_BLOCKS: usize = 16;
+
+/// Maximum number of key blocks per SST file that are read to estimate the size of a key range
+pub const APPROXIMATE_SIZE_SAMPLE_BLOCKS: usize = 16;
+
+/// Maximum number of key families that can be written by conditional writes
+pub const MAX_CONDITIONAL_WRITE_FAMILIES: usize = 64;
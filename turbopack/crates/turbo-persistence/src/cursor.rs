@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::{
+    arc_slice::ArcSlice,
+    db::TurboPersistence,
+    key::{hash_key, write_key_bytes},
+    range_tombstone::RangeTombstone,
+    static_sorted_file::{StaticSortedFile, StaticSortedFileCursor},
+    QueryKey,
+};
+
+/// The position of a [Cursor].
+enum Position {
+    /// Before the first entry.
+    Start,
+    /// At an entry. The SST file cursors are positioned relative to it.
+    Entry { hash: u64, key: Vec<u8> },
+    /// After the last entry.
+    End,
+}
+
+/// A cursor over the entries of a family, which can be positioned at a key and moved in both
+/// directions. Created by [TurboPersistence::cursor].
+///
+/// Like range iteration, entries are ordered by key hash, so `next` and `prev` move to the entry
+/// with the next higher or lower key hash. Overwritten, deleted and expired entries are skipped and
+/// merge operands are applied. The cursor reads from the SST files at the time it was created, like
+/// a [crate::Snapshot].
+pub struct Cursor<'l> {
+    db: &'l TurboPersistence,
+    family: usize,
+    /// The SST files at the time the cursor was created, in order.
+    static_sorted_files: Vec<Arc<StaticSortedFile>>,
+    /// The range tombstones at the time the cursor was created.
+    range_tombstones: Arc<Vec<RangeTombstone>>,
+    /// A cursor for every SST file of the family.
+    cursors: Vec<StaticSortedFileCursor<'l>>,
+    position: Position,
+    /// Whether the SST file cursors are at their first entry after the position. Otherwise they
+    /// are at their last entry before the position.
+    forward: bool,
+}
+
+impl<'l> Cursor<'l> {
+    pub(crate) fn new(
+        db: &'l TurboPersistence,
+        family: usize,
+        static_sorted_files: Vec<Arc<StaticSortedFile>>,
+        range_tombstones: Arc<Vec<RangeTombstone>>,
+        cursors: Vec<StaticSortedFileCursor<'l>>,
+    ) -> Self {
+        Self {
+            db,
+            family,
+            static_sorted_files,
+            range_tombstones,
+            cursors,
+            position: Position::Start,
+            // The SST file cursors are not positioned yet, which matches being before the start
+            forward: false,
+        }
+    }
+
+    /// Moves to the first entry and returns it.
+    pub fn seek_to_first(&mut self) -> Result<Option<(ArcSlice<u8>, ArcSlice<u8>)>> {
+        self.position = Position::Start;
+        self.turn_forward()?;
+        self.next()
+    }
+
+    /// Moves to the last entry and returns it.
+    pub fn seek_to_last(&mut self) -> Result<Option<(ArcSlice<u8>, ArcSlice<u8>)>> {
+        self.position = Position::End;
+        self.turn_backward()?;
+        self.prev()
+    }
+
+    /// Moves to the entry of the key and returns it. When the key doesn't exist, moves to the
+    /// entry that follows it in key hash order instead.
+    pub fn seek<K: QueryKey>(&mut self, key: &K) -> Result<Option<(ArcSlice<u8>, ArcSlice<u8>)>> {
+        let hash = hash_key(key);
+        let mut key_bytes = Vec::with_capacity(key.len());
+        write_key_bytes(key, &mut key_bytes);
+        for cursor in self.cursors.iter_mut() {
+            cursor.seek(hash, &key_bytes)?;
+        }
+        self.forward = true;
+        self.next()
+    }
+
+    /// Moves to the next entry and returns it. Returns `None` after the last entry. Calling it
+    /// before the first entry moves to the first entry.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<(ArcSlice<u8>, ArcSlice<u8>)>> {
+        if !self.forward {
+            self.turn_forward()?;
+        }
+        loop {
+            let Some((hash, key)) = self
+                .cursors
+                .iter()
+                .filter_map(|cursor| cursor.current())
+                .min()
+                .map(|(hash, key)| (hash, key.to_vec()))
+            else {
+                self.position = Position::End;
+                return Ok(None);
+            };
+            for cursor in self.cursors.iter_mut() {
+                if cursor.current() == Some((hash, &key[..])) {
+                    cursor.next_entry()?;
+                }
+            }
+            if let Some(entry) = self.enter(hash, key)? {
+                return Ok(Some(entry));
+            }
+        }
+    }
+
+    /// Moves to the previous entry and returns it. Returns `None` before the first entry. Calling
+    /// it after the last entry moves to the last entry.
+    pub fn prev(&mut self) -> Result<Option<(ArcSlice<u8>, ArcSlice<u8>)>> {
+        if self.forward {
+            self.turn_backward()?;
+        }
+        loop {
+            let Some((hash, key)) = self
+                .cursors
+                .iter()
+                .filter_map(|cursor| cursor.current())
+                .max()
+                .map(|(hash, key)| (hash, key.to_vec()))
+            else {
+                self.position = Position::Start;
+                return Ok(None);
+            };
+            for cursor in self.cursors.iter_mut() {
+                if cursor.current() == Some((hash, &key[..])) {
+                    cursor.prev_entry()?;
+                }
+            }
+            if let Some(entry) = self.enter(hash, key)? {
+                return Ok(Some(entry));
+            }
+        }
+    }
+
+    /// Moves to a key and looks up its value. Returns `None` when the key is deleted, so the
+    /// caller moves on to the following key.
+    fn enter(&mut self, hash: u64, key: Vec<u8>) -> Result<Option<(ArcSlice<u8>, ArcSlice<u8>)>> {
+        let value = self.db.get_internal(
+            &self.static_sorted_files,
+            &self.range_tombstones,
+            self.family,
+            &&key[..],
+            false,
+        )?;
+        let entry = value.map(|value| (ArcSlice::from(key.clone().into_boxed_slice()), value));
+        self.position = Position::Entry { hash, key };
+        Ok(entry)
+    }
+
+    /// Moves the SST file cursors to their first entry after the position.
+    fn turn_forward(&mut self) -> Result<()> {
+        for cursor in self.cursors.iter_mut() {
+            match &self.position {
+                Position::Start => cursor.seek_to_first()?,
+                Position::Entry { hash, key } => {
+                    cursor.seek(*hash, key)?;
+                    if cursor.current() == Some((*hash, &key[..])) {
+                        cursor.next_entry()?;
+                    }
+                }
+                Position::End => cursor.reset(),
+            }
+        }
+        self.forward = true;
+        Ok(())
+    }
+
+    /// Moves the SST file cursors to their last entry before the position.
+    fn turn_backward(&mut self) -> Result<()> {
+        for cursor in self.cursors.iter_mut() {
+            match &self.position {
+                Position::Start => cursor.reset(),
+                Position::Entry { hash, key } => {
+                    cursor.seek(*hash, key)?;
+                    if cursor.current().is_some() {
+                        cursor.prev_entry()?;
+                    } else {
+                        // All entries of the file are before the position
+                        cursor.seek_to_last()?;
+                    }
+                }
+                Position::End => cursor.seek_to_last()?,
+            }
+        }
+        self.forward = false;
+        Ok(())
+    }
+}
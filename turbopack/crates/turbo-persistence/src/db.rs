@@ -1,41 +1,76 @@
 use std::{
     any::{Any, TypeId},
-    collections::HashSet,
+    cmp,
+    collections::{HashMap, HashSet, VecDeque},
     fs::{self, File, OpenOptions, ReadDir},
     io::Write,
-    mem::{swap, transmute, MaybeUninit},
+    mem::swap,
+    ops::RangeBounds,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, AtomicU32, Ordering},
-        Arc,
+        Arc, Weak,
     },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use byteorder::{ReadBytesExt, WriteBytesExt, BE};
-use lzzzz::lz4::decompress;
-use memmap2::Mmap;
-use parking_lot::{Mutex, RwLock};
-use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use parking_lot::{Condvar, Mutex, RwLock};
+use rayon::{
+    iter::{
+        IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+    },
+    ThreadPool,
+};
 
 use crate::{
     arc_slice::ArcSlice,
+    blob::{open_blob_reader, read_blob_file, BlobIndex, ValueReader},
+    change_feed::ChangeFeed,
     compaction::selector::{
         get_compaction_jobs, total_coverage, CompactConfig, Compactable, CompactionJobs,
     },
+    comparator::{self, KeyComparator},
+    compression::Compression,
     constants::{
-        AQMF_AVG_SIZE, AQMF_CACHE_SIZE, DATA_THRESHOLD_PER_COMPACTED_FILE, KEY_BLOCK_AVG_SIZE,
-        KEY_BLOCK_CACHE_SIZE, MAX_ENTRIES_PER_COMPACTED_FILE, VALUE_BLOCK_AVG_SIZE,
-        VALUE_BLOCK_CACHE_SIZE,
+        APPROXIMATE_SIZE_SAMPLE_BLOCKS, AQMF_AVG_SIZE, AQMF_CACHE_SIZE, KEY_BLOCK_AVG_SIZE,
+        KEY_BLOCK_CACHE_SIZE, MAX_CONDITIONAL_WRITE_FAMILIES, MAX_ENTRIES_PER_COMPACTED_FILE,
+        MAX_WAL_FAMILIES, VALUE_BLOCK_AVG_SIZE, VALUE_BLOCK_CACHE_SIZE,
+        WRITE_BATCH_SEQUENCE_NUMBER_RESERVATION,
     },
-    key::{hash_key, StoreKey},
-    lookup_entry::LookupEntry,
+    cursor::Cursor,
+    encryption::Encryption,
+    event_listener::{
+        CompactionInfo, EvictionInfo, FileSource, FlushInfo, PersistenceEventListener, SstFileInfo,
+    },
+    failpoint::{Failpoint, Failpoints},
+    family::Family,
+    file_io::{self, SyncPolicy},
+    file_sizes::{FamilyFileSizes, FileSizes},
+    key::{hash_key, write_key_bytes, KeyBase, StoreKey},
+    lock::{DirectoryLock, LOCK_FILE},
+    lookup_entry::{LookupEntry, LookupValue},
+    manifest::{self, MANIFEST_FILE, MAX_MANIFEST_SIZE, NEW_MANIFEST_FILE},
     merge_iter::MergeIter,
+    merge_operator::{self, MergeOperator},
+    range_iter::{range_contains, RangeIter, RangeRevIter},
+    range_tombstone::{deleted_up_to, read_range_tombstones, RangeTombstone},
+    rate_limiter::{RateLimit, RateLimiter},
+    repair::{self, RepairReport},
+    row_cache::RowCache,
+    snapshot::Snapshot,
     static_sorted_file::{
-        AqmfCache, BlockCache, LookupResult, StaticSortedFile, StaticSortedFileRange,
+        set_commit_sequence_number, set_last_read, AqmfCache, BlockCache, EntryCounts,
+        LookupResult, StaticSortedFile, StaticSortedFileRange,
     },
     static_sorted_file_builder::StaticSortedFileBuilder,
-    write_batch::{FinishResult, WriteBatch},
+    trace::TraceRecorder,
+    value_buf::ValueBuffer,
+    verify::{self, VerifyReport},
+    wal::{read_wal, Wal, WalRecord, WAL_FILE},
+    write_batch::{FinishResult, SequenceNumberRange, WriteBatch},
     QueryKey,
 };
 
@@ -78,6 +113,10 @@ impl CacheStatistics {
 #[derive(Debug)]
 pub struct Statistics {
     pub sst_files: usize,
+    /// The number of entries in SST files that store it, including tombstones.
+    pub entries: u64,
+    /// The number of tombstones in SST files that store it.
+    pub tombstones: u64,
     pub key_block_cache: CacheStatistics,
     pub value_block_cache: CacheStatistics,
     pub aqmf_cache: CacheStatistics,
@@ -100,34 +139,242 @@ struct TrackedStats {
     miss_global: std::sync::atomic::AtomicU64,
 }
 
-/// TurboPersistence is a persistent key-value store. It is limited to a single writer at a time
-/// using a single write batch. It allows for concurrent reads.
+/// Compacts families with many SST files when the database is opened. See
+/// [DbOptions::compact_on_open].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompactOnOpen {
+    /// Families with more SST files than this are compacted.
+    pub max_files_per_family: usize,
+    /// The maximum number of SST files that are merged at once, like in
+    /// [TurboPersistence::compact].
+    pub max_merge_sequence: usize,
+}
+
+/// The maximum sizes of the caches in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheCapacity {
+    /// The cache for deserialized AQMF filters of SST files.
+    pub aqmf: u64,
+    /// The cache for decompressed index and key blocks.
+    pub key_block: u64,
+    /// The cache for decompressed value blocks.
+    pub value_block: u64,
+    /// The cache for the values of hot keys, which are returned by [TurboPersistence::get]
+    /// without a lookup in the SST files. It's disabled with a size of zero, which is the
+    /// default.
+    pub row: u64,
+}
+
+impl Default for CacheCapacity {
+    fn default() -> Self {
+        Self {
+            aqmf: AQMF_CACHE_SIZE,
+            key_block: KEY_BLOCK_CACHE_SIZE,
+            value_block: VALUE_BLOCK_CACHE_SIZE,
+            row: 0,
+        }
+    }
+}
+
+/// The result of [TurboPersistence::gc_blobs].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlobGcStats {
+    /// The number of SST files that were scanned for blob references.
+    pub scanned_sst_files: usize,
+    /// The number of blob files that are still referenced.
+    pub live_blob_files: usize,
+    /// The number of blob files that were removed.
+    pub removed_blob_files: usize,
+    /// The total size of the removed blob files in bytes.
+    pub removed_bytes: u64,
+}
+
+/// Options for opening a [TurboPersistence] database.
+#[derive(Clone, Debug, Default)]
+pub struct DbOptions {
+    /// Appends all operations of write batches to a write-ahead log, so they survive when the
+    /// process dies before the write batch is committed. They are committed when the database is
+    /// opened the next time. Only families below 64 can be written in this mode.
+    pub wal: bool,
+    /// The compression for each family, indexed by family. Families without an entry use
+    /// [Compression::Lz4]. Existing files keep their compression until they are compacted.
+    pub compression: Vec<Compression>,
+    /// The compression of blob files for each family, indexed by family. Families without an entry
+    /// use the compression of [DbOptions::compression]. Blob files are written once and read
+    /// rarely, so they can use a slower compression with better ratios, e. g.
+    /// [Compression::Lz4Hc] or [Compression::Zstd] with a high level.
+    pub blob_compression: Vec<Option<Compression>>,
+    /// Verifies the checksum of every block read from SST files. Reads of corrupted blocks fail
+    /// with a [crate::CorruptionError] instead of decoding garbage. This costs hashing every
+    /// block when it's read from disk, cached blocks are not verified again.
+    pub paranoid_checks: bool,
+    /// The merge operator for each family, indexed by family. It's required for families that are
+    /// written with [WriteBatch::merge].
+    pub merge_operators: Vec<Option<MergeOperator>>,
+    /// The key comparator for each family, indexed by family. Families without a comparator order
+    /// keys by their bytes in range scans and range deletions. The comparator of a family that
+    /// contains data can't be changed.
+    pub key_comparators: Vec<Option<KeyComparator>>,
+    /// Encrypts new SST and blob files and the records of the write-ahead log with this key.
+    /// Reading encrypted files requires the same key. Existing unencrypted files stay readable and
+    /// are encrypted when compaction rewrites them. A write-ahead log with unencrypted records
+    /// can't be replayed with encryption, so it must be replayed by opening the database without
+    /// encryption first.
+    pub encryption: Option<Encryption>,
+    /// Receives events about flushes, new files, compactions and cache misses.
+    pub event_listener: Option<Arc<dyn PersistenceEventListener>>,
+    /// The sizes of the caches. They can be changed later with
+    /// [TurboPersistence::set_cache_capacity].
+    pub cache_capacity: CacheCapacity,
+    /// The maximum size in bytes of keys and values that a write batch buffers in memory. When
+    /// it's exceeded, the largest buffers are written to SST files before the write batch is
+    /// committed. Without a budget, each thread buffers up to
+    /// [FileSizes::thread_local_collector_size] per family.
+    pub write_batch_memory_budget: Option<usize>,
+    /// Serves blocks of uncompressed and unencrypted SST files directly from the memory mapping
+    /// instead of copying them into the block caches. This avoids copies and keeps the caches
+    /// small, e. g. for families with [Compression::None] that are read once on startup. Blocks of
+    /// other files are still decompressed into the caches.
+    pub mapped_reads: bool,
+    /// Writes new SST and blob files with direct I/O (`O_DIRECT` on Linux, `F_NOCACHE` on macOS),
+    /// so writing large amounts of data doesn't evict other data from the page cache. File systems
+    /// that don't support it use buffered writes.
+    pub direct_io: bool,
+    /// Whether new files are fsynced individually or with a single sync at commit.
+    pub sync_policy: SyncPolicy,
+    /// Limits the rate at which write batches and compactions write new SST and blob files, so
+    /// writing in the background doesn't saturate the disk. Writers block when they exceed it.
+    pub write_rate_limit: Option<RateLimit>,
+    /// The sizes of new SST files and of the buffers of write batches. The defaults suit large
+    /// builds, machines with less memory benefit from smaller buffers.
+    pub file_sizes: FileSizes,
+    /// The sizes for each family, indexed by family. Families without an entry use
+    /// [DbOptions::file_sizes].
+    pub family_file_sizes: Vec<Option<FileSizes>>,
+    /// Stores the content hash of new blob files and references an existing blob file when a
+    /// write batch writes a value with the same content again. Only blob files written with this
+    /// option are deduplicated.
+    pub deduplicate_blobs: bool,
+    /// Records all puts, deletes and gets into a trace file at this path, which can be replayed
+    /// against a fresh database with the `replay_trace` binary. Only the sizes of values are
+    /// recorded, not their content.
+    pub trace: Option<PathBuf>,
+    /// The thread pool that write batches, compactions, blob garbage collection, verification and
+    /// batched lookups run their parallel work on. Without a thread pool they use the global rayon
+    /// pool, where they compete with the parallel work of the application.
+    pub thread_pool: Option<Arc<ThreadPool>>,
+    /// Compacts families with many SST files before the database is returned, so lookups don't
+    /// need to check the filters of hundreds of small files, e. g. of a cache that was written by
+    /// many short sessions. Opening blocks until the compaction finished.
+    pub compact_on_open: Option<CompactOnOpen>,
+    /// The maximum size in bytes of the SST and blob files. When a compaction leaves the database
+    /// larger than this, the least recently read SST files are removed, together with the blob
+    /// files only they referenced. This bounds databases that are used as a cache, where cold
+    /// entries can be computed again.
+    pub storage_quota: Option<u64>,
+}
+
+/// TurboPersistence is a persistent key-value store. It allows multiple concurrent write batches,
+/// which are committed in the order they were started, and concurrent reads. Compactions and other
+/// write operations require exclusive access.
 pub struct TurboPersistence {
     /// The path to the directory where the database is stored
     path: PathBuf,
+    /// The lock on the directory, so no other process opens it. Released on shutdown.
+    lock: Mutex<Option<DirectoryLock>>,
     /// The inner state of the database. Writing will update that.
     inner: RwLock<Inner>,
     /// A cache for the last WriteBatch. It is used to avoid reallocation of buffers for the
     /// WriteBatch.
     idle_write_batch: Mutex<Option<(TypeId, Box<dyn Any + Send + Sync>)>>,
-    /// A flag to indicate if a write operation is currently active. Prevents multiple concurrent
-    /// write operations.
+    /// A flag to indicate if an exclusive write operation, e. g. a compaction, is currently
+    /// active. Prevents multiple concurrent write operations.
     active_write_operation: AtomicBool,
+    /// The write batches that are currently open.
+    write_batches: Mutex<OpenWriteBatches>,
+    /// Notified when a write batch was committed or failed to commit.
+    write_batch_committed: Condvar,
+    /// The compaction that is running in the background, if any. Write operations wait for it to
+    /// finish.
+    background_compaction: Mutex<Option<JoinHandle<Result<()>>>>,
+    /// The write-ahead log, when enabled.
+    wal: Option<Arc<Wal>>,
+    /// The compression for each family.
+    compression: Arc<[Compression]>,
+    /// The compression of blob files for each family, when it differs from `compression`.
+    blob_compression: Arc<[Option<Compression>]>,
+    /// Whether block checksums are verified when reading SST files.
+    paranoid_checks: bool,
+    /// Whether lookups read uncompressed blocks directly from the memory mapping.
+    mapped_reads: bool,
+    /// Whether new SST and blob files are written with direct I/O.
+    direct_io: bool,
+    /// Limits the rate at which new SST and blob files are written, when enabled.
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// The sizes of new SST files and write batch buffers for each family.
+    file_sizes: Arc<FamilyFileSizes>,
+    /// The failures that are injected into the write path, with the `failpoints` feature.
+    failpoints: Arc<Failpoints>,
+    /// How new files are made durable on commit.
+    sync_policy: SyncPolicy,
+    /// The content hashes of blob files, when blobs are deduplicated.
+    blob_index: Option<Arc<BlobIndex>>,
+    /// The merge operator for each family.
+    merge_operators: Vec<Option<MergeOperator>>,
+    /// The key comparator for each family.
+    key_comparators: Vec<Option<KeyComparator>>,
+    /// The encryption of SST and blob files and the write-ahead log, when enabled.
+    encryption: Option<Encryption>,
+    /// The memory budget of write batches.
+    write_batch_memory_budget: Option<usize>,
+    /// Receives events about the database.
+    event_listener: Option<Arc<dyn PersistenceEventListener>>,
+    /// Records the operations on the database, when tracing is enabled.
+    trace: Option<Arc<TraceRecorder>>,
+    /// The thread pool for parallel work, when it's not the global rayon pool.
+    thread_pool: Option<Arc<ThreadPool>>,
+    /// The maximum size of the SST and blob files, when cold files are evicted.
+    storage_quota: Option<u64>,
     /// A cache for deserialized AQMF filters.
     aqmf_cache: AqmfCache,
     /// A cache for decompressed key blocks.
     key_block_cache: BlockCache,
     /// A cache for decompressed value blocks.
     value_block_cache: BlockCache,
+    /// A cache for the values of hot keys.
+    row_cache: RowCache,
     /// Statistics for the database.
     #[cfg(feature = "stats")]
     stats: TrackedStats,
 }
 
+/// The write batches that are currently open.
+#[derive(Default)]
+struct OpenWriteBatches {
+    /// The sequence number ranges of the open write batches in the order they were started, which
+    /// is the order they are committed in.
+    batches: VecDeque<Arc<SequenceNumberRange>>,
+    /// Set when committing a write batch failed. Later write batches can't be committed, since
+    /// they would make the partially written files of the failed write batch visible.
+    failed: bool,
+}
+
+/// A value found by a lookup. Blob files are not read yet.
+enum FoundValue {
+    Slice(ArcSlice<u8>),
+    Blob(u32),
+}
+
 /// The inner state of the database.
 struct Inner {
-    /// The list of SST files in the database in order.
-    static_sorted_files: Vec<StaticSortedFile>,
+    /// The list of SST files in the database in order. They are reference counted so iterators can
+    /// keep reading them while the database is compacted.
+    static_sorted_files: Vec<Arc<StaticSortedFile>>,
+    /// The range tombstones that still apply to some of the SST files.
+    range_tombstones: Arc<Vec<RangeTombstone>>,
+    /// SST files that were replaced by a compaction. Snapshots and iterators might still read
+    /// them, so blob files referenced by them are not garbage collected.
+    retired_static_sorted_files: Vec<Weak<StaticSortedFile>>,
     /// The current sequence number for the database.
     current_sequence_number: u32,
 }
@@ -138,42 +385,182 @@ impl TurboPersistence {
     /// properly. Cleanup only requires to read a few bytes from a few files and to delete
     /// files, so it's fast.
     pub fn open(path: PathBuf) -> Result<Self> {
+        Self::open_with_options(path, DbOptions::default())
+    }
+
+    /// Open a TurboPersistence database at the given path with the given options. See
+    /// [TurboPersistence::open].
+    pub fn open_with_options(path: PathBuf, options: DbOptions) -> Result<Self> {
+        fs::create_dir_all(&path).context("Creating persistence directory failed")?;
+        let lock = DirectoryLock::acquire(&path)?;
         let mut db = Self {
+            lock: Mutex::new(Some(lock)),
             path,
             inner: RwLock::new(Inner {
                 static_sorted_files: Vec::new(),
+                range_tombstones: Arc::new(Vec::new()),
+                retired_static_sorted_files: Vec::new(),
                 current_sequence_number: 0,
             }),
             idle_write_batch: Mutex::new(None),
+            background_compaction: Mutex::new(None),
             active_write_operation: AtomicBool::new(false),
+            write_batches: Mutex::new(OpenWriteBatches::default()),
+            write_batch_committed: Condvar::new(),
+            wal: None,
+            compression: options.compression.into(),
+            blob_compression: options.blob_compression.into(),
+            paranoid_checks: options.paranoid_checks,
+            mapped_reads: options.mapped_reads,
+            direct_io: options.direct_io,
+            rate_limiter: options
+                .write_rate_limit
+                .map(|limit| Arc::new(RateLimiter::new(limit))),
+            file_sizes: Arc::new(FamilyFileSizes::new(
+                options.file_sizes,
+                options.family_file_sizes,
+            )),
+            failpoints: Arc::new(Failpoints::default()),
+            sync_policy: options.sync_policy,
+            blob_index: None,
+            merge_operators: options.merge_operators,
+            key_comparators: options.key_comparators,
+            encryption: options.encryption,
+            write_batch_memory_budget: options.write_batch_memory_budget,
+            event_listener: options.event_listener,
+            trace: None,
+            thread_pool: options.thread_pool,
+            storage_quota: options.storage_quota,
             aqmf_cache: AqmfCache::with(
-                AQMF_CACHE_SIZE as usize / AQMF_AVG_SIZE,
-                AQMF_CACHE_SIZE,
+                (options.cache_capacity.aqmf as usize / AQMF_AVG_SIZE).max(1),
+                options.cache_capacity.aqmf,
                 Default::default(),
                 Default::default(),
                 Default::default(),
             ),
             key_block_cache: BlockCache::with(
-                KEY_BLOCK_CACHE_SIZE as usize / KEY_BLOCK_AVG_SIZE,
-                KEY_BLOCK_CACHE_SIZE,
+                (options.cache_capacity.key_block as usize / KEY_BLOCK_AVG_SIZE).max(1),
+                options.cache_capacity.key_block,
                 Default::default(),
                 Default::default(),
                 Default::default(),
             ),
             value_block_cache: BlockCache::with(
-                VALUE_BLOCK_CACHE_SIZE as usize / VALUE_BLOCK_AVG_SIZE,
-                VALUE_BLOCK_CACHE_SIZE,
+                (options.cache_capacity.value_block as usize / VALUE_BLOCK_AVG_SIZE).max(1),
+                options.cache_capacity.value_block,
                 Default::default(),
                 Default::default(),
                 Default::default(),
             ),
+            row_cache: RowCache::new(options.cache_capacity.row),
             #[cfg(feature = "stats")]
             stats: TrackedStats::default(),
         };
         db.open_directory()?;
+        if options.deduplicate_blobs {
+            db.blob_index = Some(Arc::new(
+                BlobIndex::load(&db.path).context("Unable to load the blob index")?,
+            ));
+        }
+        db.replay_wal()
+            .context("Replaying write-ahead log failed")?;
+        if options.wal {
+            db.wal = Some(Arc::new(Wal::create(&db.path, db.encryption.clone())?));
+        } else {
+            if let Err(e) = fs::remove_file(db.path.join(WAL_FILE)) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(e).context("Unable to remove write-ahead log");
+                }
+            }
+        }
+        if let Some(compact_on_open) = options.compact_on_open {
+            db.compact_on_open(compact_on_open)
+                .context("Compaction on open failed")?;
+        }
+        if let Some(path) = &options.trace {
+            db.trace = Some(Arc::new(TraceRecorder::create(path)?));
+        }
         Ok(db)
     }
 
+    /// Compacts the families that have more SST files than configured.
+    fn compact_on_open(&self, options: CompactOnOpen) -> Result<()> {
+        let mut files_per_family = Vec::new();
+        for sst in self.inner.read().static_sorted_files.iter() {
+            let family = sst.range()?.family as usize;
+            if family >= files_per_family.len() {
+                files_per_family.resize(family + 1, 0);
+            }
+            files_per_family[family] += 1;
+        }
+        for (family, files) in files_per_family.into_iter().enumerate() {
+            if files > options.max_files_per_family {
+                self.compact_now(Some(family as u32), 0.0, options.max_merge_sequence)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `f` in the thread pool of the database, so its parallel work runs on that pool. Without
+    /// a thread pool it runs on the current thread and uses the global rayon pool.
+    fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.install(f),
+            None => f(),
+        }
+    }
+
+    /// Commits the operations of a write batch that was not committed before the process died.
+    fn replay_wal(&self) -> Result<()> {
+        let records = read_wal(&self.path, self.encryption.as_ref())?;
+        if records.is_empty() {
+            return Ok(());
+        }
+        let batch = self.write_batch::<Vec<u8>, MAX_WAL_FAMILIES>()?;
+        for record in records {
+            match record {
+                WalRecord::Put {
+                    family,
+                    key,
+                    value,
+                    expires_at,
+                } => batch.put_internal(family, key, value.into(), expires_at)?,
+                WalRecord::Delete { family, key } => batch.delete(family, key)?,
+                WalRecord::DeleteRange { family, start, end } => {
+                    batch.delete_range(family, start, end)?
+                }
+                WalRecord::Merge {
+                    family,
+                    key,
+                    operand,
+                } => batch.merge(family, key, operand.into())?,
+            }
+        }
+        self.commit_write_batch(batch)
+    }
+
+    /// Repairs a damaged database, e. g. after files were truncated by a crash. The database must
+    /// not be open. See [TurboPersistence::repair_with_options].
+    pub fn repair(path: PathBuf) -> Result<RepairReport> {
+        Self::repair_with_options(path, &DbOptions::default())
+    }
+
+    /// Repairs a damaged database with the given options. The encryption option is needed to check
+    /// encrypted files.
+    ///
+    /// All files are read completely and the checksums of all SST blocks are verified. Damaged
+    /// files are removed, and so are files that were not committed according to the `CURRENT`
+    /// file, when it's readable. Removing a SST file would make older
+    /// values of its keys visible again, so older SST files that might contain the same keys are
+    /// removed too. The same applies to damaged range tombstone files. Finally the `CURRENT` file
+    /// is rewritten, so the database can be opened again.
+    pub fn repair_with_options(path: PathBuf, options: &DbOptions) -> Result<RepairReport> {
+        // Fails when the database is open
+        let _lock = DirectoryLock::acquire(&path)?;
+        repair::repair(&path, options.encryption.as_ref())
+            .with_context(|| format!("Repairing persistence directory {:?} failed", path))
+    }
+
     /// Performas the initial check on the database directory.
     fn open_directory(&mut self) -> Result<()> {
         match fs::read_dir(&self.path) {
@@ -205,8 +592,9 @@ impl TurboPersistence {
         self.init_directory()
     }
 
-    /// Initializes the directory by creating the CURRENT file.
+    /// Initializes the directory by creating the manifest and the CURRENT file.
     fn init_directory(&mut self) -> Result<()> {
+        manifest::write_manifest(&self.path, 0, &self.key_comparator_names())?;
         let mut current = File::create(self.path.join("CURRENT"))?;
         current.write_u32::<BE>(0)?;
         current.flush()?;
@@ -216,6 +604,7 @@ impl TurboPersistence {
     /// Loads an existing database directory and performs cleanup if necessary.
     fn load_directory(&mut self, entries: ReadDir) -> Result<bool> {
         let mut sst_files = Vec::new();
+        let mut range_tombstone_files = Vec::new();
         let mut current_file = match File::open(self.path.join("CURRENT")) {
             Ok(file) => file,
             Err(e) => {
@@ -228,6 +617,7 @@ impl TurboPersistence {
         };
         let current = current_file.read_u32::<BE>()?;
         drop(current_file);
+        let committed_files = manifest::read_manifest(&self.path, current)?;
 
         let mut deleted_files = HashSet::new();
         for entry in entries {
@@ -243,13 +633,20 @@ impl TurboPersistence {
                 if deleted_files.contains(&seq) {
                     continue;
                 }
-                if seq > current {
+                let uncommitted = matches!(ext, "sst" | "blob" | "rdel")
+                    && committed_files
+                        .as_ref()
+                        .is_some_and(|committed| !committed.contains(seq));
+                if seq > current || uncommitted {
                     fs::remove_file(&path)?;
                 } else {
                     match ext {
                         "sst" => {
                             sst_files.push(seq);
                         }
+                        "rdel" => {
+                            range_tombstone_files.push(seq);
+                        }
                         "del" => {
                             let mut content = &*fs::read(&path)?;
                             let mut no_existing_files = true;
@@ -258,7 +655,8 @@ impl TurboPersistence {
                                 deleted_files.insert(seq);
                                 let sst_file = self.path.join(format!("{:08}.sst", seq));
                                 let blob_file = self.path.join(format!("{:08}.blob", seq));
-                                for path in [sst_file, blob_file] {
+                                let rdel_file = self.path.join(format!("{:08}.rdel", seq));
+                                for path in [sst_file, blob_file, rdel_file] {
                                     if fs::exists(&path)? {
                                         fs::remove_file(path)?;
                                         no_existing_files = false;
@@ -287,6 +685,19 @@ impl TurboPersistence {
                     Some("CURRENT") => {
                         // Already read
                     }
+                    Some(WAL_FILE) => {
+                        // Replayed after loading
+                    }
+                    Some(MANIFEST_FILE) => {
+                        // Already read
+                    }
+                    Some(LOCK_FILE) => {
+                        // Held while the database is open
+                    }
+                    Some(NEW_MANIFEST_FILE) => {
+                        // Left over from an interrupted rewrite of the manifest
+                        fs::remove_file(&path)?;
+                    }
                     _ => {
                         if !path
                             .file_name()
@@ -303,8 +714,35 @@ impl TurboPersistence {
         sst_files.sort_unstable();
         let sst_files = sst_files
             .into_iter()
-            .map(|seq| self.open_sst(seq))
-            .collect::<Result<Vec<StaticSortedFile>>>()?;
+            .map(|seq| self.open_sst(seq).map(Arc::new))
+            .collect::<Result<Vec<_>>>()?;
+        range_tombstone_files.retain(|seq| !deleted_files.contains(seq));
+        range_tombstone_files.sort_unstable();
+        let mut range_tombstones = Vec::new();
+        for seq in range_tombstone_files {
+            range_tombstones.append(
+                &mut read_range_tombstones(&self.path, seq)
+                    .with_context(|| format!("Unable to read {:08}.rdel", seq))?,
+            );
+        }
+        let families_with_data = sst_files
+            .iter()
+            .map(|sst| anyhow::Ok(sst.range()?.family))
+            .chain(
+                range_tombstones
+                    .iter()
+                    .map(|tombstone| Ok(tombstone.family)),
+            )
+            .collect::<Result<HashSet<_>>>()?;
+        comparator::check_recorded(
+            committed_files
+                .as_ref()
+                .map(|committed| committed.key_comparators())
+                .unwrap_or_default(),
+            &self.key_comparators,
+            |family| families_with_data.contains(&family),
+        )?;
+        self.set_key_comparators(&mut range_tombstones);
         #[cfg(feature = "print_stats")]
         {
             for sst in sst_files.iter() {
@@ -323,41 +761,70 @@ impl TurboPersistence {
                 );
             }
         }
+        // All remaining files are committed, so the manifest can be replaced by a base record. This
+        // also drops an incomplete record at the end and creates the manifest for databases
+        // written by older versions.
+        manifest::write_manifest(&self.path, current, &self.key_comparator_names())?;
         let inner = self.inner.get_mut();
         inner.static_sorted_files = sst_files;
+        inner.range_tombstones = Arc::new(range_tombstones);
         inner.current_sequence_number = current;
         Ok(true)
     }
 
+    /// Returns the names of the key comparators by family, as they are recorded in the manifest.
+    fn key_comparator_names(&self) -> Vec<(u32, String)> {
+        KeyComparator::names(&self.key_comparators)
+    }
+
+    /// Sets the key comparator of their family on range tombstones, so they delete the keys
+    /// within their range by that ordering.
+    fn set_key_comparators(&self, range_tombstones: &mut [RangeTombstone]) {
+        for tombstone in range_tombstones {
+            tombstone.comparator =
+                KeyComparator::for_family(&self.key_comparators, tombstone.family).cloned();
+        }
+    }
+
     /// Opens a single SST file. This memory maps the file, but doesn't read it yet.
     fn open_sst(&self, seq: u32) -> Result<StaticSortedFile> {
         let path = self.path.join(format!("{:08}.sst", seq));
-        StaticSortedFile::open(seq, path)
-            .with_context(|| format!("Unable to open sst file {:08}.sst", seq))
+        StaticSortedFile::open(
+            seq,
+            path,
+            self.paranoid_checks,
+            self.encryption.clone(),
+            self.event_listener.clone(),
+            self.mapped_reads,
+        )
+        .with_context(|| format!("Unable to open sst file {:08}.sst", seq))
     }
 
     /// Reads and decompresses a blob file. This is not backed by any cache.
-    fn read_blob(&self, seq: u32) -> Result<ArcSlice<u8>> {
-        let path = self.path.join(format!("{:08}.blob", seq));
-        let mmap = unsafe { Mmap::map(&File::open(&path)?)? };
-        #[cfg(unix)]
-        mmap.advise(memmap2::Advice::Sequential)?;
-        #[cfg(unix)]
-        mmap.advise(memmap2::Advice::WillNeed)?;
-        #[cfg(target_os = "linux")]
-        mmap.advise(memmap2::Advice::DontFork)?;
-        #[cfg(target_os = "linux")]
-        mmap.advise(memmap2::Advice::Unmergeable)?;
-        let mut compressed = &mmap[..];
-        let uncompressed_length = compressed.read_u32::<BE>()? as usize;
-
-        let buffer = Arc::new_zeroed_slice(uncompressed_length);
-        // Safety: MaybeUninit<u8> can be safely transmuted to u8.
-        let mut buffer = unsafe { transmute::<Arc<[MaybeUninit<u8>]>, Arc<[u8]>>(buffer) };
-        // Safety: We know that the buffer is not shared yet.
-        let decompressed = unsafe { Arc::get_mut_unchecked(&mut buffer) };
-        decompress(compressed, decompressed)?;
-        Ok(ArcSlice::from(buffer))
+    pub(crate) fn read_blob(&self, seq: u32) -> Result<ArcSlice<u8>> {
+        read_blob_file(&self.path, seq, self.encryption.as_ref())
+    }
+
+    /// Opens a reader for a blob file, which decompresses it chunk by chunk.
+    pub(crate) fn open_blob_reader(&self, seq: u32) -> Result<ValueReader> {
+        open_blob_reader(&self.path, seq, self.encryption.as_ref())
+    }
+
+    /// Sets the action of a failpoint, or removes it with `None`. This injects failures into write
+    /// batches, commits and compactions to test crash recovery. After a failure the database might
+    /// be in an inconsistent state, so it should be dropped and opened again, like after a crash.
+    #[cfg(feature = "failpoints")]
+    pub fn set_failpoint(&self, failpoint: Failpoint, action: Option<crate::FailpointAction>) {
+        self.failpoints.set(failpoint, action);
+    }
+
+    /// Changes the sizes of the caches, e. g. to release memory under memory pressure. Shrinking a
+    /// cache evicts entries until it fits into the new size.
+    pub fn set_cache_capacity(&self, capacity: CacheCapacity) {
+        self.aqmf_cache.set_capacity(capacity.aqmf);
+        self.key_block_cache.set_capacity(capacity.key_block);
+        self.value_block_cache.set_capacity(capacity.value_block);
+        self.row_cache.set_capacity(capacity.row);
     }
 
     /// Returns true if the database is empty.
@@ -365,47 +832,200 @@ impl TurboPersistence {
         self.inner.read().static_sorted_files.is_empty()
     }
 
-    /// Starts a new WriteBatch for the database. Only a single write operation is allowed at a
-    /// time. The WriteBatch need to be committed with [`TurboPersistence::commit_write_batch`].
-    /// Note that the WriteBatch might start writing data to disk while it's filled up with data.
-    /// This data will only become visible after the WriteBatch is committed.
+    /// Returns the sequence number of the database, which increases with every commit.
+    pub fn sequence_number(&self) -> u32 {
+        self.inner.read().current_sequence_number
+    }
+
+    /// Returns the changes that were committed after the sequence number, in commit order, e. g.
+    /// to mirror the database without reading all of it. The feed reads the state at the time it
+    /// was created, the next feed starts after [ChangeFeed::sequence_number].
+    ///
+    /// Compaction merges the changes of multiple commits, so it fails when some of the changes
+    /// were compacted already. The database has to be read completely in that case. Truncated
+    /// families are not part of the changes.
+    pub fn changes_since(&self, sequence_number: u32) -> Result<ChangeFeed<'_>> {
+        let inner = self.inner.read();
+        ChangeFeed::new(
+            self,
+            &self.key_block_cache,
+            &self.value_block_cache,
+            sequence_number,
+            inner.current_sequence_number,
+            &inner.static_sorted_files,
+            &inner.range_tombstones,
+        )
+    }
+
+    /// Starts a new WriteBatch for the database. Multiple write batches can be open at the same
+    /// time, e. g. to fill them on different threads. They are committed in the order they were
+    /// started, so every WriteBatch need to be committed with
+    /// [`TurboPersistence::commit_write_batch`]. Each WriteBatch reserves a range of sequence
+    /// numbers for its files when another WriteBatch is started while it's open, so it can't write
+    /// more files than that afterwards. Note that the WriteBatch might start writing data to disk
+    /// while it's filled up with data. This data will only become visible after the WriteBatch is
+    /// committed.
     pub fn write_batch<K: StoreKey + Send + Sync + 'static, const FAMILIES: usize>(
         &self,
     ) -> Result<WriteBatch<K, FAMILIES>> {
-        if self
-            .active_write_operation
-            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
-            .is_err()
-        {
-            bail!(
-                "Another write batch or compaction is already active (Only a single write \
-                 operations is allowed at a time)"
-            );
-        }
-        let current = self.inner.read().current_sequence_number;
+        self.wait_for_background_compaction()?;
+        let (sequence_numbers, trace) = {
+            let mut write_batches = self.write_batches.lock();
+            if self.active_write_operation.load(Ordering::Acquire) {
+                bail!(
+                    "A compaction or another exclusive write operation is active (Write batches \
+                     can't be started concurrently)"
+                );
+            }
+            if write_batches.failed {
+                bail!("A previous write batch failed to commit");
+            }
+            let current = match write_batches.batches.back() {
+                Some(newest) => {
+                    if self.wal.is_some() {
+                        bail!(
+                            "Another write batch is already active (Concurrent write batches are \
+                             not supported with the write-ahead log)"
+                        );
+                    }
+                    newest.reserve(WRITE_BATCH_SEQUENCE_NUMBER_RESERVATION)
+                }
+                None => self.inner.read().current_sequence_number,
+            };
+            let sequence_numbers = Arc::new(SequenceNumberRange::new(current));
+            write_batches.batches.push_back(sequence_numbers.clone());
+            // The write batch is recorded while holding the lock, so the trace has write batches
+            // in the order they are committed
+            let trace = self
+                .trace
+                .as_ref()
+                .map(|trace| anyhow::Ok((trace.clone(), trace.write_batch()?)))
+                .transpose()?;
+            (sequence_numbers, trace)
+        };
         if let Some((ty, any)) = self.idle_write_batch.lock().take() {
             if ty == TypeId::of::<WriteBatch<K, FAMILIES>>() {
                 let mut write_batch = *any.downcast::<WriteBatch<K, FAMILIES>>().unwrap();
-                write_batch.reset(current);
+                write_batch.reset(sequence_numbers, trace);
                 return Ok(write_batch);
             }
         }
-        Ok(WriteBatch::new(self.path.clone(), current))
+        Ok(WriteBatch::new(
+            self.path.clone(),
+            sequence_numbers,
+            self.wal.clone(),
+            self.compression.clone(),
+            self.blob_compression.clone(),
+            self.encryption.clone(),
+            self.event_listener.clone(),
+            self.write_batch_memory_budget,
+            self.direct_io,
+            self.rate_limiter.clone(),
+            self.file_sizes.clone(),
+            self.failpoints.clone(),
+            self.blob_index.clone(),
+            trace,
+        ))
     }
 
     /// Commits a WriteBatch to the database. This will finish writing the data to disk and make it
-    /// visible to readers.
+    /// visible to readers. Waits until all WriteBatches that were started before it are committed.
     pub fn commit_write_batch<K: StoreKey + Send + Sync + 'static, const FAMILIES: usize>(
+        &self,
+        write_batch: WriteBatch<K, FAMILIES>,
+    ) -> Result<()> {
+        self.commit_write_batch_with(write_batch, |_| Ok(()))
+    }
+
+    /// Puts a key-value pair when the key has no value. Returns whether the value was written.
+    /// See [TurboPersistence::compare_and_swap].
+    pub fn put_if_absent<K: StoreKey + QueryKey + Send + Sync + 'static>(
+        &self,
+        family: usize,
+        key: K,
+        value: ValueBuffer<'_>,
+    ) -> Result<bool> {
+        self.compare_and_swap(family, key, None, Some(value))
+    }
+
+    /// Replaces the value of a key when its current value equals `expected`, or when the key has no
+    /// value and `expected` is `None`. A `new` value of `None` deletes the key. Returns whether the
+    /// value was replaced.
+    ///
+    /// The value is compared and written in a WriteBatch of its own, right before it's committed.
+    /// At that point all WriteBatches that were started before are committed and all later ones
+    /// wait for it, so no other write can happen in between. This waits until all open
+    /// WriteBatches are committed, so it must not be called while the current thread holds a
+    /// WriteBatch that is not committed.
+    pub fn compare_and_swap<K: StoreKey + QueryKey + Send + Sync + 'static>(
+        &self,
+        family: usize,
+        key: K,
+        expected: Option<&[u8]>,
+        new: Option<ValueBuffer<'_>>,
+    ) -> Result<bool> {
+        if family >= MAX_CONDITIONAL_WRITE_FAMILIES {
+            bail!(
+                "Family {family} can't be written conditionally (Only families below \
+                 {MAX_CONDITIONAL_WRITE_FAMILIES} are supported)"
+            );
+        }
+        let write_batch = self.write_batch::<K, MAX_CONDITIONAL_WRITE_FAMILIES>()?;
+        let mut written = false;
+        self.commit_write_batch_with(write_batch, |write_batch| {
+            if self.get(family, &key)?.as_deref() != expected {
+                return Ok(());
+            }
+            match new {
+                Some(value) => write_batch.put(family, key, value)?,
+                None => write_batch.delete(family, key)?,
+            }
+            written = true;
+            Ok(())
+        })?;
+        Ok(written)
+    }
+
+    /// Commits a WriteBatch like [TurboPersistence::commit_write_batch]. `before_commit` is called
+    /// when all WriteBatches that were started before are committed, right before the WriteBatch
+    /// is finished.
+    fn commit_write_batch_with<K: StoreKey + Send + Sync + 'static, const FAMILIES: usize>(
         &self,
         mut write_batch: WriteBatch<K, FAMILIES>,
+        before_commit: impl FnOnce(&WriteBatch<K, FAMILIES>) -> Result<()>,
     ) -> Result<()> {
-        let FinishResult {
-            sequence_number,
-            new_sst_files,
-            new_blob_files,
-        } = write_batch.finish()?;
-        self.commit(new_sst_files, new_blob_files, vec![], sequence_number)?;
-        self.active_write_operation.store(false, Ordering::Release);
+        {
+            let mut write_batches = self.write_batches.lock();
+            loop {
+                if write_batches.failed {
+                    bail!("A previous write batch failed to commit");
+                }
+                if write_batches
+                    .batches
+                    .front()
+                    .is_some_and(|oldest| write_batch.has_sequence_numbers(oldest))
+                {
+                    break;
+                }
+                self.write_batch_committed.wait(&mut write_batches);
+            }
+        }
+        let result = before_commit(&write_batch)
+            .and_then(|_| write_batch.trace_commit())
+            .and_then(|_| self.commit_write_batch_internal(&mut write_batch));
+        {
+            let mut write_batches = self.write_batches.lock();
+            if result.is_ok() {
+                // Absorbed write batches are committed with it
+                for _ in 0..write_batch.sequence_number_range_count() {
+                    write_batches.batches.pop_front();
+                }
+            } else {
+                write_batches.failed = true;
+            }
+        }
+        self.write_batch_committed.notify_all();
+        result?;
         self.idle_write_batch.lock().replace((
             TypeId::of::<WriteBatch<K, FAMILIES>>(),
             Box::new(write_batch),
@@ -413,41 +1033,186 @@ impl TurboPersistence {
         Ok(())
     }
 
-    /// fsyncs the new files and updates the CURRENT file. Updates the database state to include the
-    /// new files.
+    /// Finishes and commits a WriteBatch. All WriteBatches that were started before it are
+    /// committed.
+    fn commit_write_batch_internal<K: StoreKey + Send + Sync + 'static, const FAMILIES: usize>(
+        &self,
+        write_batch: &mut WriteBatch<K, FAMILIES>,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let FinishResult {
+            sequence_number,
+            new_sst_files,
+            new_blob_files,
+            new_blob_hashes,
+            new_range_tombstones,
+        } = self.install(|| write_batch.finish())?;
+        let flush_info = FlushInfo {
+            sequence_number,
+            sst_files: new_sst_files.len(),
+            blob_files: new_blob_files.len(),
+            duration: Duration::ZERO,
+        };
+        self.commit(
+            new_sst_files,
+            new_blob_files,
+            new_range_tombstones,
+            vec![],
+            sequence_number,
+        )?;
+        if let Some(blob_index) = &self.blob_index {
+            blob_index.extend(new_blob_hashes);
+        }
+        if let Some(event_listener) = &self.event_listener {
+            event_listener.on_flush(&FlushInfo {
+                duration: start.elapsed(),
+                ..flush_info
+            });
+        }
+        if let Some(wal) = &self.wal {
+            // The operations are persisted in the SST files now
+            wal.truncate()?;
+        }
+        Ok(())
+    }
+
+    /// Starts a write operation that requires exclusive access to the database, e. g. a
+    /// compaction. Fails when another write operation or a write batch is active.
+    fn start_exclusive_write_operation(&self) -> Result<()> {
+        if self
+            .active_write_operation
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            bail!(
+                "Another write batch or compaction is already active (Only a single write \
+                 operations is allowed at a time)"
+            );
+        }
+        if !self.write_batches.lock().batches.is_empty() {
+            self.active_write_operation.store(false, Ordering::Release);
+            bail!(
+                "Another write batch or compaction is already active (Only a single write \
+                 operations is allowed at a time)"
+            );
+        }
+        Ok(())
+    }
+
+    /// fsyncs the new files according to the sync policy and updates the CURRENT file. Updates the
+    /// database state to include the new files.
     fn commit(
         &self,
         mut new_sst_files: Vec<(u32, File)>,
-        new_blob_files: Vec<File>,
+        new_blob_files: Vec<(u32, File)>,
+        new_range_tombstones: Option<(File, Vec<RangeTombstone>)>,
         mut indicies_to_delete: Vec<usize>,
         mut seq: u32,
     ) -> Result<(), anyhow::Error> {
         new_sst_files.sort_unstable_by_key(|(seq, _)| *seq);
+        let new_files = new_sst_files
+            .iter()
+            .chain(new_blob_files.iter())
+            .map(|(seq, _)| *seq)
+            .chain(
+                new_range_tombstones
+                    .iter()
+                    .filter_map(|(_, range_tombstones)| range_tombstones.first())
+                    .map(|range_tombstone| range_tombstone.file),
+            )
+            .collect::<Vec<_>>();
 
+        let sync_files = self.sync_policy.sync_files();
         let mut new_sst_files = new_sst_files
             .into_iter()
             .map(|(seq, file)| {
-                file.sync_all()?;
-                self.open_sst(seq)
+                if sync_files {
+                    file.sync_all()?;
+                }
+                self.open_sst(seq).map(Arc::new)
             })
             .collect::<Result<Vec<_>>>()?;
 
-        for file in new_blob_files {
-            file.sync_all()?;
+        for (_, file) in new_blob_files {
+            if sync_files {
+                file.sync_all()?;
+            }
         }
 
-        if !indicies_to_delete.is_empty() {
-            seq += 1;
+        let new_range_tombstones = new_range_tombstones
+            .map(|(file, mut range_tombstones)| {
+                if sync_files {
+                    file.sync_all()?;
+                }
+                self.set_key_comparators(&mut range_tombstones);
+                anyhow::Ok(range_tombstones)
+            })
+            .transpose()?;
+
+        if !sync_files {
+            file_io::sync_file_system(&self.path)
+                .context("Unable to sync the persistence directory")?;
         }
+        self.failpoints.check(Failpoint::BeforeCommit)?;
 
         let removed_ssts;
+        let mut removed_range_tombstone_files = Vec::new();
 
         {
             let mut inner = self.inner.write();
-            inner.current_sequence_number = seq;
+            let inner = &mut *inner;
+            if indicies_to_delete.is_empty() {
+                // Compaction only rewrites existing entries, which doesn't change any value
+                self.row_cache.invalidate(
+                    &new_sst_files,
+                    new_range_tombstones.as_deref().unwrap_or_default(),
+                    &self.aqmf_cache,
+                );
+            }
             indicies_to_delete.sort_unstable();
             removed_ssts = remove_indicies(&mut inner.static_sorted_files, &indicies_to_delete);
             inner.static_sorted_files.append(&mut new_sst_files);
+            inner
+                .retired_static_sorted_files
+                .retain(|sst| sst.strong_count() > 0);
+            inner
+                .retired_static_sorted_files
+                .extend(removed_ssts.iter().map(Arc::downgrade));
+
+            if let Some(mut new_range_tombstones) = new_range_tombstones {
+                Arc::make_mut(&mut inner.range_tombstones).append(&mut new_range_tombstones);
+            }
+            if !inner.range_tombstones.is_empty() {
+                // Range tombstones are dropped when compaction has replaced all files they apply
+                // to. Their file can be removed when all its tombstones are dropped.
+                let ssts = inner
+                    .static_sorted_files
+                    .iter()
+                    .flat_map(|sst| {
+                        sst.range()
+                            .ok()
+                            .map(|range| (range.family, sst.sequence_number()))
+                    })
+                    .collect::<Vec<_>>();
+                let mut files = HashSet::new();
+                let mut live_files = HashSet::new();
+                Arc::make_mut(&mut inner.range_tombstones).retain(|tombstone| {
+                    files.insert(tombstone.file);
+                    let live = ssts
+                        .iter()
+                        .any(|&(family, seq)| tombstone.applies_to(family, seq));
+                    if live {
+                        live_files.insert(tombstone.file);
+                    }
+                    live
+                });
+                removed_range_tombstone_files.extend(files.difference(&live_files).copied());
+            }
+
+            if !indicies_to_delete.is_empty() || !removed_range_tombstone_files.is_empty() {
+                seq += 1;
+            }
+            inner.current_sequence_number = seq;
         }
 
         let mut removed_ssts = removed_ssts
@@ -455,11 +1220,16 @@ impl TurboPersistence {
             .map(|sst| sst.sequence_number())
             .collect::<Vec<_>>();
         removed_ssts.sort_unstable();
+        removed_range_tombstone_files.sort_unstable();
 
-        if !indicies_to_delete.is_empty() {
+        if !removed_ssts.is_empty() || !removed_range_tombstone_files.is_empty() {
             // Write *.del file, marking the selected files as to delete
-            let mut buf = Vec::with_capacity(removed_ssts.len() * 4);
-            for seq in removed_ssts.iter() {
+            let mut buf =
+                Vec::with_capacity((removed_ssts.len() + removed_range_tombstone_files.len()) * 4);
+            for seq in removed_ssts
+                .iter()
+                .chain(removed_range_tombstone_files.iter())
+            {
                 buf.write_u32::<BE>(*seq)?;
             }
             let mut file = File::create(self.path.join(format!("{:08}.del", seq)))?;
@@ -467,6 +1237,11 @@ impl TurboPersistence {
             file.sync_all()?;
         }
 
+        // The commit record makes the new files committed once the CURRENT file is updated. Files
+        // of a commit that didn't finish are removed on open.
+        let manifest_size = manifest::append_commit_record(&self.path, seq, &new_files)?;
+        self.failpoints.check(Failpoint::BeforeCurrentUpdate)?;
+
         let mut current_file = OpenOptions::new()
             .write(true)
             .truncate(false)
@@ -475,96 +1250,715 @@ impl TurboPersistence {
         current_file.write_u32::<BE>(seq)?;
         current_file.sync_all()?;
 
+        if manifest_size > MAX_MANIFEST_SIZE {
+            manifest::write_manifest(&self.path, seq, &self.key_comparator_names())?;
+        }
+        self.failpoints.check(Failpoint::BeforeFileDeletion)?;
+
         for seq in removed_ssts {
             fs::remove_file(self.path.join(format!("{seq:08}.sst")))?;
         }
 
+        for seq in removed_range_tombstone_files {
+            fs::remove_file(self.path.join(format!("{seq:08}.rdel")))?;
+        }
+
         Ok(())
     }
 
-    /// Runs a full compaction on the database. This will rewrite all SST files, removing all
-    /// duplicate keys and separating all key ranges into unique files.
-    pub fn full_compact(&self) -> Result<()> {
-        self.compact(0.0, usize::MAX)?;
-        Ok(())
+    /// Creates a consistent copy of the database in `target`, which can be opened independently.
+    /// The files are hard linked when possible, since they are never modified after they are
+    /// written, and copied otherwise (e. g. when `target` is on a different file system). Waits
+    /// for a background compaction and fails when a write batch or compaction is active.
+    pub fn checkpoint(&self, target: &Path) -> Result<()> {
+        self.wait_for_background_compaction()?;
+        self.start_exclusive_write_operation()?;
+        let result = self.checkpoint_internal(target);
+        self.active_write_operation.store(false, Ordering::Release);
+        result
     }
 
-    /// Runs a (partial) compaction. Compaction will only be performed if the coverage of the SST
-    /// files is above the given threshold. The coverage is the average number of SST files that
-    /// need to be read to find a key. It also limits the maximum number of SST files that are
-    /// merged at once, which is the main factor for the runtime of the compaction.
-    pub fn compact(&self, max_coverage: f32, max_merge_sequence: usize) -> Result<()> {
-        if self
-            .active_write_operation
-            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
-            .is_err()
-        {
-            bail!(
-                "Another write batch or compaction is already active (Only a single write \
-                 operations is allowed at a time)"
-            );
+    /// Links or copies all files of the current state into `target`. No write operation can change
+    /// the files while this is running.
+    fn checkpoint_internal(&self, target: &Path) -> Result<()> {
+        fs::create_dir_all(target).context("Unable to create checkpoint directory")?;
+        if fs::read_dir(target)?.next().is_some() {
+            bail!("Checkpoint directory {:?} is not empty", target);
         }
 
-        let mut sequence_number;
-        let mut new_sst_files = Vec::new();
-        let mut indicies_to_delete = Vec::new();
-
-        {
+        let mut files = Vec::new();
+        let current = {
             let inner = self.inner.read();
-            sequence_number = AtomicU32::new(inner.current_sequence_number);
-            self.compact_internal(
-                &inner.static_sorted_files,
-                &sequence_number,
-                &mut new_sst_files,
-                &mut indicies_to_delete,
-                max_coverage,
-                max_merge_sequence,
-            )?;
+            for sst in inner.static_sorted_files.iter() {
+                files.push(format!("{:08}.sst", sst.sequence_number()));
+            }
+            let range_tombstone_files = inner
+                .range_tombstones
+                .iter()
+                .map(|tombstone| tombstone.file)
+                .collect::<HashSet<_>>();
+            for seq in range_tombstone_files {
+                files.push(format!("{:08}.rdel", seq));
+            }
+            inner.current_sequence_number
+        };
+        // Blob files are not tracked, they are referenced from SST files. Blob files that are no
+        // longer referenced are copied too, which is harmless.
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("blob") {
+                continue;
+            }
+            let Some(seq) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            if seq <= current {
+                files.push(format!("{:08}.blob", seq));
+            }
         }
 
-        self.commit(
-            new_sst_files,
-            Vec::new(),
-            indicies_to_delete,
-            *sequence_number.get_mut(),
-        )?;
-
-        self.active_write_operation.store(false, Ordering::Release);
+        for file in files {
+            let src = self.path.join(&file);
+            let dst = target.join(&file);
+            if fs::hard_link(&src, &dst).is_err() {
+                fs::copy(&src, &dst)
+                    .with_context(|| format!("Unable to copy {file} into checkpoint"))?;
+                File::open(&dst)?.sync_all()?;
+            }
+        }
 
+        // The CURRENT file is written last, so a checkpoint that was interrupted doesn't contain
+        // one and is initialized as an empty database when opened.
+        manifest::write_manifest(target, current, &self.key_comparator_names())?;
+        let mut current_file = File::create(target.join("CURRENT"))?;
+        current_file.write_u32::<BE>(current)?;
+        current_file.sync_all()?;
         Ok(())
     }
 
-    /// Internal function to perform a compaction.
-    fn compact_internal(
-        &self,
-        static_sorted_files: &[StaticSortedFile],
-        sequence_number: &AtomicU32,
-        new_sst_files: &mut Vec<(u32, File)>,
-        indicies_to_delete: &mut Vec<usize>,
-        max_coverage: f32,
-        max_merge_sequence: usize,
-    ) -> Result<bool> {
-        if static_sorted_files.is_empty() {
-            return Ok(false);
-        }
+    /// Removes blob files that are no longer referenced by any SST file, e. g. because their key
+    /// was deleted or overwritten and compaction dropped the old entry. Blob files referenced by
+    /// SST files that are still used by snapshots or iterators are kept. All index and key blocks
+    /// of the SST files are read, but not cached. Waits for a background compaction and fails when
+    /// a write batch or compaction is active.
+    pub fn gc_blobs(&self) -> Result<BlobGcStats> {
+        self.wait_for_background_compaction()?;
+        self.start_exclusive_write_operation()?;
+        let result = self.install(|| self.gc_blobs_internal());
+        self.active_write_operation.store(false, Ordering::Release);
+        result
+    }
 
-        struct SstWithRange {
-            index: usize,
-            range: StaticSortedFileRange,
+    /// Collects the blob references of all SST files and removes the unreferenced blob files. No
+    /// write operation can add blob files while this is running.
+    fn gc_blobs_internal(&self) -> Result<BlobGcStats> {
+        let (static_sorted_files, current) = {
+            let mut inner = self.inner.write();
+            inner
+                .retired_static_sorted_files
+                .retain(|sst| sst.strong_count() > 0);
+            let static_sorted_files = inner
+                .static_sorted_files
+                .iter()
+                .cloned()
+                .chain(
+                    inner
+                        .retired_static_sorted_files
+                        .iter()
+                        .filter_map(Weak::upgrade),
+                )
+                .collect::<Vec<_>>();
+            (static_sorted_files, inner.current_sequence_number)
+        };
+
+        let mut stats = BlobGcStats {
+            scanned_sst_files: static_sorted_files.len(),
+            ..Default::default()
+        };
+        let referenced_blob_files = static_sorted_files
+            .into_par_iter()
+            .map(|sst| {
+                sst.blob_references().with_context(|| {
+                    format!(
+                        "Unable to read blob references of {:08}.sst",
+                        sst.sequence_number()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect::<HashSet<_>>();
+
+        let mut unreferenced_blob_files = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("blob") {
+                continue;
+            }
+            let Some(seq) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            if seq > current || referenced_blob_files.contains(&seq) {
+                stats.live_blob_files += 1;
+            } else {
+                unreferenced_blob_files.push((seq, path, entry.metadata()?.len()));
+            }
+        }
+
+        // Write batches must not reference the files anymore
+        if let Some(blob_index) = &self.blob_index {
+            blob_index.remove(
+                &unreferenced_blob_files
+                    .iter()
+                    .map(|(seq, _, _)| *seq)
+                    .collect(),
+            );
+        }
+        for (_, path, size) in unreferenced_blob_files {
+            fs::remove_file(&path).with_context(|| format!("Unable to remove {:?}", path))?;
+            stats.removed_blob_files += 1;
+            stats.removed_bytes += size;
+        }
+        Ok(stats)
+    }
+
+    /// Verifies the SST files of a family, or of all families when `family` is `None`. All blocks
+    /// are read and their checksums are verified, the entries are checked to be sorted and within
+    /// the hash range of their file, and every entry is looked up through the AQMF filter and the
+    /// index of its file. All blob files referenced by the SST files are read completely.
+    ///
+    /// Problems are returned in the report instead of failing, so all files are verified. This
+    /// reads the whole database, so it's slow. Waits for a background compaction and fails when a
+    /// write batch or compaction is active.
+    pub fn verify(&self, family: Option<usize>) -> Result<VerifyReport> {
+        self.wait_for_background_compaction()?;
+        self.start_exclusive_write_operation()?;
+        let result = self.install(|| self.verify_internal(family));
+        self.active_write_operation.store(false, Ordering::Release);
+        result
+    }
+
+    fn verify_internal(&self, family: Option<usize>) -> Result<VerifyReport> {
+        let static_sorted_files = self
+            .inner
+            .read()
+            .static_sorted_files
+            .iter()
+            .map(|sst| Ok((sst.sequence_number(), sst.range()?.family)))
+            .collect::<Result<Vec<_>>>()?;
+        let verifications = static_sorted_files
+            .into_par_iter()
+            .filter(|&(_, sst_family)| family.is_none_or(|family| family as u32 == sst_family))
+            .map(|(seq, _)| {
+                (
+                    seq,
+                    verify::verify_sst_file(&self.path, seq, self.encryption.as_ref()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut report = VerifyReport {
+            sst_files: verifications.len(),
+            ..Default::default()
+        };
+        let mut blob_references = Vec::new();
+        for (seq, verification) in verifications {
+            report.entries += verification.entries;
+            report.issues.extend(verification.issues);
+            blob_references.extend(
+                verification
+                    .blob_references
+                    .into_iter()
+                    .map(|blob| (seq, blob)),
+            );
+        }
+        verify::verify_blob_files(
+            &self.path,
+            blob_references,
+            self.encryption.as_ref(),
+            &mut report,
+        );
+        Ok(report)
+    }
+
+    /// Adds pre-built SST files to a family, e. g. SST files of another database. The files are
+    /// copied into the database with new sequence numbers, in the given order, so their entries
+    /// replace older entries of the same keys and later files replace earlier ones.
+    ///
+    /// The files need to belong to the family and must not reference blob files, since the blob
+    /// files would have different sequence numbers in this database. The files are read completely
+    /// to check that. Fails when a write batch or compaction is active.
+    pub fn ingest_external_files(&self, family: usize, paths: &[PathBuf]) -> Result<()> {
+        self.wait_for_background_compaction()?;
+        self.start_exclusive_write_operation()?;
+        let result = self.ingest_external_files_internal(family as u32, paths);
+        self.active_write_operation.store(false, Ordering::Release);
+        result
+    }
+
+    /// Copies and validates the external SST files and commits them.
+    fn ingest_external_files_internal(&self, family: u32, paths: &[PathBuf]) -> Result<()> {
+        let mut seq = self.inner.read().current_sequence_number;
+        let mut new_sst_files = Vec::with_capacity(paths.len());
+        for path in paths {
+            seq += 1;
+            let dst = self.path.join(format!("{:08}.sst", seq));
+            fs::copy(path, &dst).with_context(|| format!("Unable to copy {:?}", path))?;
+            // The entries of the file are committed with its new sequence number
+            if let Err(err) = set_commit_sequence_number(&dst, seq)
+                .and_then(|_| self.validate_external_file(seq, family))
+            {
+                fs::remove_file(&dst)?;
+                return Err(err).with_context(|| format!("Unable to ingest {:?}", path));
+            }
+            new_sst_files.push((seq, File::open(&dst)?));
+        }
+        if new_sst_files.is_empty() {
+            return Ok(());
+        }
+        self.commit(new_sst_files, Vec::new(), None, Vec::new(), seq)
+    }
+
+    /// Checks that a copied external SST file belongs to the family and doesn't reference blob
+    /// files. All blocks are read and their checksums are verified. The blocks are not cached in
+    /// the database caches, as the sequence number is reused when the file is rejected.
+    fn validate_external_file(&self, seq: u32, family: u32) -> Result<()> {
+        let path = self.path.join(format!("{:08}.sst", seq));
+        let sst = Arc::new(StaticSortedFile::open(
+            seq,
+            path,
+            true,
+            self.encryption.clone(),
+            None,
+            false,
+        )?);
+        let new_cache = || {
+            BlockCache::with(
+                16,
+                16 * 1024 * 1024,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )
+        };
+        let key_block_cache = new_cache();
+        let value_block_cache = new_cache();
+        let range = sst.range()?;
+        if range.family != family {
+            bail!(
+                "SST file belongs to family {} instead of family {}",
+                range.family,
+                family
+            );
+        }
+        for entry in sst.iter(&key_block_cache, &value_block_cache)? {
+            if let LookupValue::Blob { .. } = entry?.value {
+                bail!("SST file references a blob file");
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a full compaction on the database. This will rewrite all SST files, removing all
+    /// duplicate keys and separating all key ranges into unique files.
+    pub fn full_compact(&self) -> Result<()> {
+        self.compact(0.0, usize::MAX)?;
+        Ok(())
+    }
+
+    /// Runs a (partial) compaction. Compaction will only be performed if the coverage of the SST
+    /// files is above the given threshold. The coverage is the average number of SST files that
+    /// need to be read to find a key. It also limits the maximum number of SST files that are
+    /// merged at once, which is the main factor for the runtime of the compaction.
+    pub fn compact(&self, max_coverage: f32, max_merge_sequence: usize) -> Result<()> {
+        self.wait_for_background_compaction()?;
+        self.compact_now(None, max_coverage, max_merge_sequence)
+    }
+
+    /// Runs a full compaction of all families, e. g. when an embedder becomes idle. Same as
+    /// [TurboPersistence::full_compact].
+    pub fn compact_all(&self) -> Result<()> {
+        self.full_compact()
+    }
+
+    /// Runs a full compaction of a single family. This is useful to consolidate a family that has
+    /// seen many writes during an idle period, without paying for rewriting the other families.
+    pub fn compact_family(&self, family: usize) -> Result<()> {
+        self.wait_for_background_compaction()?;
+        self.compact_now(Some(family as u32), 0.0, usize::MAX)
+    }
+
+    /// Compacts the keys of a family in `start..end`, so that reading them afterwards only needs
+    /// a single SST file.
+    ///
+    /// Keys are distributed over SST files by their hash, so every SST file of the family can
+    /// contain keys of the range and there is no cheaper way to compact a key range than
+    /// compacting the whole family with [TurboPersistence::compact_family]. Nothing is compacted
+    /// when the range is empty.
+    pub fn compact_range<K: QueryKey>(&self, family: usize, start: &K, end: &K) -> Result<()> {
+        let comparator = KeyComparator::for_family(&self.key_comparators, family as u32);
+        let mut start_bytes = Vec::with_capacity(start.len());
+        write_key_bytes(start, &mut start_bytes);
+        if comparator::compare(comparator, end, &start_bytes) != cmp::Ordering::Greater {
+            return Ok(());
+        }
+        self.compact_family(family)
+    }
+
+    /// Drops all keys of a family, e. g. when the serialization format of its values changed and
+    /// the stored data can't be read anymore. The SST files of the family are removed in a single
+    /// commit, like files that are replaced by a compaction, which is much cheaper than deleting
+    /// the keys. Blob files that were only referenced by the family are removed by
+    /// [TurboPersistence::gc_blobs]. Waits for a background compaction and fails when a write batch
+    /// or compaction is active.
+    pub fn truncate_family(&self, family: usize) -> Result<()> {
+        self.wait_for_background_compaction()?;
+        self.start_exclusive_write_operation()?;
+        let result = self.truncate_family_internal(family as u32);
+        self.active_write_operation.store(false, Ordering::Release);
+        result
+    }
+
+    /// Removes all SST files of a family. No write operation can add files while this is running.
+    fn truncate_family_internal(&self, family: u32) -> Result<()> {
+        let (indicies_to_delete, sequence_number) = {
+            let inner = self.inner.read();
+            let mut indicies_to_delete = Vec::new();
+            for (index, sst) in inner.static_sorted_files.iter().enumerate() {
+                if sst.range()?.family == family {
+                    indicies_to_delete.push(index);
+                }
+            }
+            (indicies_to_delete, inner.current_sequence_number)
+        };
+        if indicies_to_delete.is_empty() {
+            return Ok(());
+        }
+        self.commit(
+            Vec::new(),
+            Vec::new(),
+            None,
+            indicies_to_delete,
+            sequence_number,
+        )?;
+        // Lookups that started before the commit might have cached values of the removed files
+        self.row_cache.invalidate_family(family);
+        Ok(())
+    }
+
+    /// Starts a (partial) compaction in a background thread, see [TurboPersistence::compact].
+    /// Write batches, compactions and shutdown wait for it to finish, and they report its error
+    /// if it failed.
+    ///
+    /// This runs the same coverage based merge as [TurboPersistence::compact]. There is no level
+    /// structure: files are not assigned to levels with size limits, so a key can be rewritten by
+    /// many compactions and the write amplification is not bounded like with leveled compaction.
+    pub fn spawn_compact(
+        self: Arc<Self>,
+        max_coverage: f32,
+        max_merge_sequence: usize,
+    ) -> Result<()> {
+        self.wait_for_background_compaction()?;
+        let db = self.clone();
+        let handle = thread::Builder::new()
+            .name("turbo-persistence compaction".to_string())
+            .spawn(move || db.compact_now(None, max_coverage, max_merge_sequence))
+            .context("Unable to spawn compaction thread")?;
+        *self.background_compaction.lock() = Some(handle);
+        Ok(())
+    }
+
+    /// Waits for the background compaction to finish, if one is running.
+    fn wait_for_background_compaction(&self) -> Result<()> {
+        let handle = self.background_compaction.lock().take();
+        if let Some(handle) = handle {
+            handle
+                .join()
+                .map_err(|_| anyhow!("Background compaction panicked"))?
+                .context("Background compaction failed")?;
+        }
+        Ok(())
+    }
+
+    /// Runs a compaction on the current thread. Only SST files of the given family are compacted
+    /// when one is passed.
+    fn compact_now(
+        &self,
+        family: Option<u32>,
+        max_coverage: f32,
+        max_merge_sequence: usize,
+    ) -> Result<()> {
+        self.start_exclusive_write_operation()?;
+        let result = self.compact_now_internal(family, max_coverage, max_merge_sequence);
+        self.active_write_operation.store(false, Ordering::Release);
+        result
+    }
+
+    /// Compacts the SST files and evicts cold files when the database exceeds the storage quota.
+    /// No write operation can add files while this is running.
+    fn compact_now_internal(
+        &self,
+        family: Option<u32>,
+        max_coverage: f32,
+        max_merge_sequence: usize,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let mut sequence_number;
+        let mut new_sst_files = Vec::new();
+        let mut indicies_to_delete = Vec::new();
+
+        {
+            let inner = self.inner.read();
+            sequence_number = AtomicU32::new(inner.current_sequence_number);
+            // Moved files are hard linked, so they keep the persisted time of the last read
+            self.persist_last_reads(&inner.static_sorted_files)?;
+            self.install(|| {
+                self.compact_internal(
+                    &inner.static_sorted_files,
+                    &inner.range_tombstones,
+                    &sequence_number,
+                    &mut new_sst_files,
+                    &mut indicies_to_delete,
+                    family,
+                    max_coverage,
+                    max_merge_sequence,
+                )
+            })?;
+        }
+
+        let compaction_info = CompactionInfo {
+            family,
+            removed_files: indicies_to_delete.len(),
+            new_files: new_sst_files.len(),
+            duration: Duration::ZERO,
+        };
+        self.commit(
+            new_sst_files,
+            Vec::new(),
+            None,
+            indicies_to_delete,
+            *sequence_number.get_mut(),
+        )?;
+        if let Some(event_listener) = &self.event_listener {
+            if compaction_info.removed_files > 0 {
+                event_listener.on_compaction(&CompactionInfo {
+                    duration: start.elapsed(),
+                    ..compaction_info
+                });
+            }
+        }
+
+        if let Some(storage_quota) = self.storage_quota {
+            self.evict_cold_files(storage_quota)?;
+        }
+        Ok(())
+    }
+
+    /// Stores the time of the last read of the SST files in their modification time.
+    fn persist_last_reads(&self, static_sorted_files: &[Arc<StaticSortedFile>]) -> Result<()> {
+        for sst in static_sorted_files.iter() {
+            sst.persist_last_read(&self.path.join(format!("{:08}.sst", sst.sequence_number())))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the least recently read SST files until the SST and blob files fit into the storage
+    /// quota. Removing a SST file would make older values of its keys visible again and would
+    /// leave merge operands in newer files without their value, so SST files of a family with
+    /// overlapping hash ranges are only removed together. Blob files that are no longer referenced
+    /// are removed afterwards. No write operation can add files while this is running.
+    fn evict_cold_files(&self, storage_quota: u64) -> Result<()> {
+        let start = Instant::now();
+        let mut size = 0;
+        let mut blob_sizes = HashMap::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let path = entry.path();
+            let len = entry.metadata()?.len();
+            match path.extension().and_then(|s| s.to_str()) {
+                Some("sst") => size += len,
+                Some("blob") => {
+                    size += len;
+                    if let Some(seq) = path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .and_then(|s| s.parse::<u32>().ok())
+                    {
+                        blob_sizes.insert(seq, len);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if size <= storage_quota {
+            return Ok(());
+        }
+
+        struct EvictionGroup {
+            indicies: Vec<usize>,
+            family: u32,
+            max_hash: u64,
+            last_read: u64,
+        }
+
+        let (static_sorted_files, sequence_number) = {
+            let inner = self.inner.read();
+            (
+                inner.static_sorted_files.clone(),
+                inner.current_sequence_number,
+            )
+        };
+        let mut ssts_with_ranges = static_sorted_files
+            .iter()
+            .enumerate()
+            .map(|(index, sst)| anyhow::Ok((index, sst.range()?)))
+            .collect::<Result<Vec<_>>>()?;
+        ssts_with_ranges.sort_unstable_by_key(|(_, range)| (range.family, range.min_hash));
+        let mut groups: Vec<EvictionGroup> = Vec::new();
+        for (index, range) in ssts_with_ranges {
+            let last_read = static_sorted_files[index].last_read();
+            match groups.last_mut() {
+                Some(group) if group.family == range.family && group.max_hash >= range.min_hash => {
+                    group.indicies.push(index);
+                    group.max_hash = group.max_hash.max(range.max_hash);
+                    group.last_read = group.last_read.max(last_read);
+                }
+                _ => groups.push(EvictionGroup {
+                    indicies: vec![index],
+                    family: range.family,
+                    max_hash: range.max_hash,
+                    last_read,
+                }),
+            }
+        }
+        groups.sort_by_key(|group| group.last_read);
+
+        let mut indicies_to_delete = Vec::new();
+        let mut evicted_blob_files = HashSet::new();
+        let mut removed_bytes = 0;
+        for group in groups {
+            if size <= storage_quota {
+                break;
+            }
+            for index in group.indicies {
+                let sst = &static_sorted_files[index];
+                let mut freed = sst.size();
+                // Blob files might be referenced by other SST files too, then they are kept
+                for blob in sst.blob_references()? {
+                    if evicted_blob_files.insert(blob) {
+                        freed += blob_sizes.get(&blob).copied().unwrap_or_default();
+                    }
+                }
+                size = size.saturating_sub(freed);
+                removed_bytes += freed;
+                indicies_to_delete.push(index);
+            }
+        }
+        if indicies_to_delete.is_empty() {
+            return Ok(());
+        }
+
+        let removed_files = indicies_to_delete.len();
+        let evicted = indicies_to_delete
+            .iter()
+            .map(|&index| static_sorted_files[index].clone())
+            .collect::<Vec<_>>();
+        drop(static_sorted_files);
+        self.commit(
+            Vec::new(),
+            Vec::new(),
+            None,
+            indicies_to_delete,
+            sequence_number,
+        )?;
+        // Lookups that started before the commit might have cached values of the removed files
+        self.row_cache.invalidate(&evicted, &[], &self.aqmf_cache);
+        // The blob files of the evicted files are only kept when a snapshot still uses them
+        drop(evicted);
+        self.install(|| self.gc_blobs_internal())?;
+
+        if let Some(event_listener) = &self.event_listener {
+            event_listener.on_eviction(&EvictionInfo {
+                removed_files,
+                removed_bytes,
+                duration: start.elapsed(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Internal function to perform a compaction.
+    fn compact_internal(
+        &self,
+        static_sorted_files: &[Arc<StaticSortedFile>],
+        range_tombstones: &[RangeTombstone],
+        sequence_number: &AtomicU32,
+        new_sst_files: &mut Vec<(u32, File)>,
+        indicies_to_delete: &mut Vec<usize>,
+        family: Option<u32>,
+        max_coverage: f32,
+        max_merge_sequence: usize,
+    ) -> Result<bool> {
+        if static_sorted_files.is_empty() {
+            return Ok(false);
+        }
+
+        struct SstWithRange {
+            index: usize,
+            range: StaticSortedFileRange,
+            priority: f32,
         }
 
         impl Compactable for SstWithRange {
             fn range(&self) -> (u64, u64) {
                 (self.range.min_hash, self.range.max_hash)
             }
+
+            fn priority(&self) -> f32 {
+                self.priority
+            }
         }
 
+        // Files that are probed by many lookups and contain few live entries slow down lookups the
+        // most, so they are compacted first.
+        let priority = |sst: &StaticSortedFile| {
+            let live_ratio = match sst.entry_counts() {
+                Ok(Some(EntryCounts {
+                    entries,
+                    tombstones,
+                })) if entries > 0 => entries.saturating_sub(tombstones) as f32 / entries as f32,
+                _ => 1.0,
+            };
+            (1.0 + (sst.reads() as f32).ln_1p()) * (2.0 - live_ratio)
+        };
+
         let ssts_with_ranges = static_sorted_files
             .iter()
             .enumerate()
-            .flat_map(|(index, sst)| sst.range().ok().map(|range| SstWithRange { index, range }))
+            .flat_map(|(index, sst)| {
+                sst.range().ok().map(|range| SstWithRange {
+                    index,
+                    range,
+                    priority: priority(sst),
+                })
+            })
+            .filter(|sst| family.is_none_or(|family| sst.range.family == family))
             .collect::<Vec<_>>();
 
+        if ssts_with_ranges.is_empty() {
+            return Ok(false);
+        }
+
         let families = ssts_with_ranges
             .iter()
             .map(|s| s.range.family)
@@ -604,6 +1998,14 @@ impl TurboPersistence {
                     },
                 );
 
+                // Merged files are written with the currently configured compression
+                let compression = Compression::for_family(&self.compression, family as u32);
+                let encryption = self.encryption.as_ref();
+                let event_listener = self.event_listener.as_deref();
+                let direct_io = self.direct_io;
+                let rate_limiter = self.rate_limiter.as_deref();
+                let target_file_size = self.file_sizes.for_family(family as u32).target_file_size;
+
                 // Later we will remove the merged and moved files
                 let indicies_to_delete = merge_jobs
                     .iter()
@@ -612,61 +2014,161 @@ impl TurboPersistence {
                     .map(|index| ssts_with_ranges[index].index)
                     .collect::<Vec<_>>();
 
-                // Merge SST files
-                let merge_result = merge_jobs
-                    .into_par_iter()
-                    .with_min_len(1)
-                    .map(|indicies| {
-                        fn create_sst_file(
-                            family: u32,
-                            entries: &[LookupEntry],
-                            total_key_size: usize,
-                            total_value_size: usize,
-                            path: &Path,
-                            seq: u32,
-                        ) -> Result<(u32, File)> {
-                            let builder = StaticSortedFileBuilder::new(
+                // Merges SST files into new SST files. Entries deleted by range tombstones are
+                // removed.
+                let merge = |indicies: Vec<usize>| {
+                    // Tombstones only need to be kept when an older SST file that is not
+                    // part of the merge might contain the deleted key.
+                    let last = *indicies.iter().max().unwrap();
+                    let mut merged_range = (u64::MAX, 0);
+                    for &index in indicies.iter() {
+                        let (min_hash, max_hash) = ssts_with_ranges[index].range();
+                        merged_range.0 = merged_range.0.min(min_hash);
+                        merged_range.1 = merged_range.1.max(max_hash);
+                    }
+                    // The merged entries were committed in the input files. Entries that are
+                    // deleted by a range tombstone are dropped, so the new files contain the
+                    // commit of the range tombstone too.
+                    let mut commit_sequence_numbers = (u32::MAX, 0);
+                    for &index in indicies.iter() {
+                        let sst = &static_sorted_files[ssts_with_ranges[index].index];
+                        let (min, max) = sst.commit_sequence_numbers()?;
+                        commit_sequence_numbers.0 = commit_sequence_numbers.0.min(min);
+                        commit_sequence_numbers.1 = commit_sequence_numbers.1.max(max);
+                        for tombstone in range_tombstones.iter() {
+                            if tombstone.applies_to(family as u32, sst.sequence_number()) {
+                                commit_sequence_numbers.1 =
+                                    commit_sequence_numbers.1.max(tombstone.file);
+                            }
+                        }
+                    }
+                    let older_files = (0..last)
+                        .filter(|index| !indicies.contains(index))
+                        .filter(|&index| {
+                            let (min_hash, max_hash) = ssts_with_ranges[index].range();
+                            max_hash >= merged_range.0 && min_hash <= merged_range.1
+                        })
+                        .map(|index| &static_sorted_files[ssts_with_ranges[index].index])
+                        .collect::<Vec<_>>();
+                    // The hash range and AQMF filter of the older files tell for each key
+                    // whether they might contain it.
+                    let in_older_files = |hash: u64| {
+                        for sst in older_files.iter() {
+                            if sst.may_contain_hash(hash, &self.aqmf_cache)? {
+                                return Ok(true);
+                            }
+                        }
+                        anyhow::Ok(false)
+                    };
+                    // Without older files that might contain the key, tombstones are dropped
+                    // and merge operands can be applied to the missing value. Otherwise they
+                    // are kept and merge operands are only combined with each other. Returns
+                    // `None` for dropped tombstones.
+                    let merge_operator =
+                        MergeOperator::for_family(&self.merge_operators, family as u32);
+                    let resolve = |mut entry: LookupEntry| {
+                        if !matches!(
+                            entry.value,
+                            LookupValue::Deleted | LookupValue::Merge { .. }
+                        ) || in_older_files(entry.hash)?
+                        {
+                            return Ok(Some(entry));
+                        }
+                        if matches!(entry.value, LookupValue::Deleted) {
+                            return Ok(None);
+                        }
+                        entry.value =
+                            merge_operator::resolve(merge_operator, family as u32, entry.value)?;
+                        anyhow::Ok(Some(entry))
+                    };
+
+                    fn create_sst_file(
+                        family: u32,
+                        compression: Compression,
+                        encryption: Option<&Encryption>,
+                        commit_sequence_numbers: (u32, u32),
+                        event_listener: Option<&dyn PersistenceEventListener>,
+                        direct_io: bool,
+                        rate_limiter: Option<&RateLimiter>,
+                        entries: &[LookupEntry],
+                        total_key_size: usize,
+                        total_value_size: usize,
+                        path: &Path,
+                        seq: u32,
+                    ) -> Result<(u32, File)> {
+                        let start = Instant::now();
+                        let builder = StaticSortedFileBuilder::new(
+                            family,
+                            compression,
+                            encryption.cloned(),
+                            commit_sequence_numbers,
+                            entries,
+                            total_key_size,
+                            total_value_size,
+                        )?;
+                        let file = builder.write(
+                            &path.join(format!("{:08}.sst", seq)),
+                            direct_io,
+                            rate_limiter,
+                        )?;
+                        if let Some(event_listener) = event_listener {
+                            event_listener.on_sst_file_created(&SstFileInfo {
+                                sequence_number: seq,
                                 family,
-                                entries,
-                                total_key_size,
-                                total_value_size,
-                            )?;
-                            Ok((seq, builder.write(&path.join(format!("{:08}.sst", seq)))?))
+                                entries: entries.len(),
+                                tombstones: builder.tombstones(),
+                                size: file.metadata()?.len(),
+                                source: FileSource::Compaction,
+                                duration: start.elapsed(),
+                            });
                         }
+                        Ok((seq, file))
+                    }
 
-                        let mut new_sst_files = Vec::new();
-
-                        // Iterate all SST files
-                        let iters = indicies
-                            .iter()
-                            .map(|&index| {
-                                let index = ssts_with_ranges[index].index;
-                                let sst = &static_sorted_files[index];
-                                sst.iter(key_block_cache, value_block_cache)
-                            })
-                            .collect::<Result<Vec<_>>>()?;
-
-                        let iter = MergeIter::new(iters.into_iter())?;
-
-                        let mut total_key_size = 0;
-                        let mut total_value_size = 0;
-                        let mut current: Option<LookupEntry> = None;
-                        let mut entries = Vec::new();
-                        let mut last_entries = Vec::new();
-                        let mut last_entries_total_sizes = (0, 0);
-                        for entry in iter {
-                            let entry = entry?;
-
-                            // Remove duplicates
-                            if let Some(current) = current.take() {
-                                if current.key != entry.key {
+                    let mut new_sst_files = Vec::new();
+
+                    // Iterate all SST files
+                    let iters = indicies
+                        .iter()
+                        .map(|&index| {
+                            let index = ssts_with_ranges[index].index;
+                            let sst = &static_sorted_files[index];
+                            sst.clone()
+                                .iter(key_block_cache, value_block_cache)?
+                                .with_readahead(self.thread_pool.clone())
+                                .skip_deleted_ranges(range_tombstones)
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+
+                    let iter = MergeIter::new(iters.into_iter())?;
+
+                    let mut total_key_size = 0;
+                    let mut total_value_size = 0;
+                    let mut current: Option<LookupEntry> = None;
+                    let mut entries = Vec::new();
+                    let mut last_entries = Vec::new();
+                    let mut last_entries_total_sizes = (0, 0);
+                    for entry in iter {
+                        let mut entry = entry?;
+
+                        // Remove duplicates and obsolete tombstones, and apply merge operands
+                        if let Some(current) = current.take() {
+                            if current.key == entry.key {
+                                entry.value = merge_operator::combine(
+                                    merge_operator,
+                                    family as u32,
+                                    current.value,
+                                    entry.value,
+                                    |seq| self.read_blob(seq),
+                                )?;
+                            } else {
+                                if let Some(current) = resolve(current)? {
                                     let key_size = current.key.len();
                                     let value_size = current.value.size_in_sst();
                                     total_key_size += key_size;
                                     total_value_size += value_size;
 
-                                    if total_key_size + total_value_size
-                                        > DATA_THRESHOLD_PER_COMPACTED_FILE
+                                    if total_key_size + total_value_size > target_file_size
                                         || entries.len() >= MAX_ENTRIES_PER_COMPACTED_FILE
                                     {
                                         let (selected_total_key_size, selected_total_value_size) =
@@ -685,6 +2187,12 @@ impl TurboPersistence {
 
                                             new_sst_files.push(create_sst_file(
                                                 family as u32,
+                                                compression,
+                                                encryption,
+                                                commit_sequence_numbers,
+                                                event_listener,
+                                                direct_io,
+                                                rate_limiter,
                                                 &entries,
                                                 selected_total_key_size,
                                                 selected_total_value_size,
@@ -698,90 +2206,154 @@ impl TurboPersistence {
 
                                     entries.push(current);
                                 } else {
-                                    // Override value
+                                    // Drop tombstone
                                 }
                             }
-                            current = Some(entry);
-                        }
-                        if let Some(entry) = current {
-                            total_key_size += entry.key.len();
-                            total_value_size += entry.value.size_in_sst();
-                            entries.push(entry);
                         }
+                        current = Some(entry);
+                    }
+                    if let Some(entry) = current.map(resolve).transpose()?.flatten() {
+                        total_key_size += entry.key.len();
+                        total_value_size += entry.value.size_in_sst();
+                        entries.push(entry);
+                    }
 
-                        // If we have one set of entries left, write them to a new SST file
-                        if last_entries.is_empty() && !entries.is_empty() {
-                            let seq = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
+                    // If we have one set of entries left, write them to a new SST file
+                    if last_entries.is_empty() && !entries.is_empty() {
+                        let seq = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
 
-                            new_sst_files.push(create_sst_file(
-                                family as u32,
-                                &entries,
-                                total_key_size,
-                                total_value_size,
-                                path,
-                                seq,
-                            )?);
-                        } else
-                        // If we have two sets of entries left, merge them and
-                        // split it into two SST files, to avoid having a
-                        // single SST file that is very small.
-                        if !last_entries.is_empty() {
-                            last_entries.append(&mut entries);
-
-                            last_entries_total_sizes.0 += total_key_size;
-                            last_entries_total_sizes.1 += total_value_size;
-
-                            let (part1, part2) = last_entries.split_at(last_entries.len() / 2);
-
-                            let seq1 = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
-                            let seq2 = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
-
-                            new_sst_files.push(create_sst_file(
-                                family as u32,
-                                part1,
-                                // We don't know the exact sizes so we estimate them
-                                last_entries_total_sizes.0 / 2,
-                                last_entries_total_sizes.1 / 2,
-                                path,
-                                seq1,
-                            )?);
-
-                            new_sst_files.push(create_sst_file(
-                                family as u32,
-                                part2,
-                                last_entries_total_sizes.0 / 2,
-                                last_entries_total_sizes.1 / 2,
-                                path,
-                                seq2,
-                            )?);
-                        }
-                        Ok(new_sst_files)
-                    })
+                        new_sst_files.push(create_sst_file(
+                            family as u32,
+                            compression,
+                            encryption,
+                            commit_sequence_numbers,
+                            event_listener,
+                            direct_io,
+                            rate_limiter,
+                            &entries,
+                            total_key_size,
+                            total_value_size,
+                            path,
+                            seq,
+                        )?);
+                    } else
+                    // If we have two sets of entries left, merge them and
+                    // split it into two SST files, to avoid having a
+                    // single SST file that is very small.
+                    if !last_entries.is_empty() {
+                        last_entries.append(&mut entries);
+
+                        last_entries_total_sizes.0 += total_key_size;
+                        last_entries_total_sizes.1 += total_value_size;
+
+                        let (part1, part2) = last_entries.split_at(last_entries.len() / 2);
+
+                        let seq1 = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
+                        let seq2 = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
+
+                        new_sst_files.push(create_sst_file(
+                            family as u32,
+                            compression,
+                            encryption,
+                            commit_sequence_numbers,
+                            event_listener,
+                            direct_io,
+                            rate_limiter,
+                            part1,
+                            // We don't know the exact sizes so we estimate them
+                            last_entries_total_sizes.0 / 2,
+                            last_entries_total_sizes.1 / 2,
+                            path,
+                            seq1,
+                        )?);
+
+                        new_sst_files.push(create_sst_file(
+                            family as u32,
+                            compression,
+                            encryption,
+                            commit_sequence_numbers,
+                            event_listener,
+                            direct_io,
+                            rate_limiter,
+                            part2,
+                            last_entries_total_sizes.0 / 2,
+                            last_entries_total_sizes.1 / 2,
+                            path,
+                            seq2,
+                        )?);
+                    }
+                    // The merged entries were last read when their most recently read input file
+                    // was, so a compaction doesn't make cold entries look recently used.
+                    let last_read = indicies
+                        .iter()
+                        .map(|&index| {
+                            static_sorted_files[ssts_with_ranges[index].index].last_read()
+                        })
+                        .max()
+                        .unwrap_or_default();
+                    for (_, file) in new_sst_files.iter() {
+                        set_last_read(file, last_read)?;
+                    }
+                    anyhow::Ok(new_sst_files)
+                };
+
+                // Merge SST files
+                let merge_result = merge_jobs
+                    .into_par_iter()
+                    .with_min_len(1)
+                    .map(&merge)
                     .collect::<Result<Vec<_>>>()?;
 
-                let move_jobs = move_jobs
-                    .into_iter()
-                    .map(|index| {
-                        let seq = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
-                        (index, seq)
-                    })
-                    .collect::<Vec<_>>();
+                let move_sst = |index: usize, seq: u32| {
+                    let index = ssts_with_ranges[index].index;
+                    let sst = &static_sorted_files[index];
+                    let src_path = self.path.join(format!("{:08}.sst", sst.sequence_number()));
+                    let dst_path = self.path.join(format!("{:08}.sst", seq));
+                    if fs::hard_link(&src_path, &dst_path).is_err() {
+                        fs::copy(src_path, &dst_path)?;
+                        let file = OpenOptions::new().write(true).open(&dst_path)?;
+                        set_last_read(&file, sst.last_read())?;
+                    }
+                    anyhow::Ok((seq, File::open(dst_path)?))
+                };
+
+                // Moved files get a new sequence number, so range tombstones would no longer
+                // apply to them. These files are rewritten instead.
+                let needs_rewrite = |index: usize| {
+                    let sst = &static_sorted_files[ssts_with_ranges[index].index];
+                    range_tombstones
+                        .iter()
+                        .any(|tombstone| tombstone.applies_to(family as u32, sst.sequence_number()))
+                };
 
                 // Move SST files
-                let mut new_sst_files = move_jobs
-                    .into_par_iter()
-                    .with_min_len(1)
-                    .map(|(index, seq)| {
-                        let index = ssts_with_ranges[index].index;
-                        let sst = &static_sorted_files[index];
-                        let src_path = self.path.join(format!("{:08}.sst", sst.sequence_number()));
-                        let dst_path = self.path.join(format!("{:08}.sst", seq));
-                        if fs::hard_link(&src_path, &dst_path).is_err() {
-                            fs::copy(src_path, &dst_path)?;
+                let mut new_sst_files = if move_jobs.iter().any(|&index| needs_rewrite(index)) {
+                    // Sequentially, as the moved files need to keep their order
+                    let mut new_sst_files = Vec::new();
+                    for index in move_jobs {
+                        if needs_rewrite(index) {
+                            new_sst_files.append(&mut merge(vec![index])?);
+                        } else {
+                            let seq = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
+                            new_sst_files.push(move_sst(index, seq)?);
                         }
-                        Ok((seq, File::open(dst_path)?))
-                    })
-                    .collect::<Result<Vec<_>>>()?;
+                    }
+                    new_sst_files
+                } else {
+                    let move_jobs = move_jobs
+                        .into_iter()
+                        .map(|index| {
+                            let seq = sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
+                            (index, seq)
+                        })
+                        .collect::<Vec<_>>();
+
+                    move_jobs
+                        .into_par_iter()
+                        .with_min_len(1)
+                        .map(|(index, seq)| move_sst(index, seq))
+                        .collect::<Result<Vec<_>>>()?
+                };
 
                 new_sst_files.extend(merge_result.into_iter().flatten());
                 Ok((new_sst_files, indicies_to_delete))
@@ -799,9 +2371,149 @@ impl TurboPersistence {
     /// Get a value from the database. Returns None if the key is not found. The returned value
     /// might hold onto a block of the database and it should not be hold long-term.
     pub fn get<K: QueryKey>(&self, family: usize, key: &K) -> Result<Option<ArcSlice<u8>>> {
-        let hash = hash_key(key);
         let inner = self.inner.read();
-        for sst in inner.static_sorted_files.iter().rev() {
+        let value = self.get_internal(
+            &inner.static_sorted_files,
+            &inner.range_tombstones,
+            family,
+            key,
+            true,
+        )?;
+        self.trace_get(family, key, value.as_ref().map(|value| value.len()))?;
+        Ok(value)
+    }
+
+    /// Get a reader for a value from the database. Returns None if the key is not found. Values
+    /// stored in blob files are decompressed incrementally while reading, so large values can be
+    /// consumed without holding them in memory completely. Values with pending merge operands are
+    /// merged in memory.
+    pub fn get_reader<K: QueryKey>(&self, family: usize, key: &K) -> Result<Option<ValueReader>> {
+        let inner = self.inner.read();
+        let reader = self.get_reader_internal(
+            &inner.static_sorted_files,
+            &inner.range_tombstones,
+            family,
+            key,
+            true,
+        )?;
+        self.trace_get(family, key, reader.as_ref().map(|reader| reader.len()))?;
+        Ok(reader)
+    }
+
+    /// Records a lookup in the trace, when tracing is enabled.
+    fn trace_get<K: KeyBase>(
+        &self,
+        family: usize,
+        key: &K,
+        value_size: Option<usize>,
+    ) -> Result<()> {
+        if let Some(trace) = &self.trace {
+            trace.get(family, key, value_size)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a snapshot of the database. Reads from the snapshot observe the state of the
+    /// database at this point in time, even when write batches are committed or the database is
+    /// compacted afterwards.
+    ///
+    /// The snapshot keeps the SST files it reads from alive. It should not be held long-term, as
+    /// the disk space of files that are replaced by compaction is only released when the last
+    /// snapshot referencing them is dropped.
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        let inner = self.inner.read();
+        Snapshot::new(
+            self,
+            inner.static_sorted_files.clone(),
+            inner.range_tombstones.clone(),
+            inner.current_sequence_number,
+        )
+    }
+
+    /// Looks up a value in the given SST files, newest first. `use_row_cache` must only be set when
+    /// the files are the current files of the database and its state is locked.
+    pub(crate) fn get_internal<K: QueryKey>(
+        &self,
+        static_sorted_files: &[Arc<StaticSortedFile>],
+        range_tombstones: &[RangeTombstone],
+        family: usize,
+        key: &K,
+        use_row_cache: bool,
+    ) -> Result<Option<ArcSlice<u8>>> {
+        Ok(
+            match self.lookup_internal(
+                static_sorted_files,
+                range_tombstones,
+                family,
+                key,
+                use_row_cache,
+            )? {
+                Some(FoundValue::Slice(value)) => Some(value),
+                Some(FoundValue::Blob(seq)) => Some(self.read_blob(seq)?),
+                None => None,
+            },
+        )
+    }
+
+    /// Looks up a value in the given SST files, newest first, and returns a reader for it.
+    pub(crate) fn get_reader_internal<K: QueryKey>(
+        &self,
+        static_sorted_files: &[Arc<StaticSortedFile>],
+        range_tombstones: &[RangeTombstone],
+        family: usize,
+        key: &K,
+        use_row_cache: bool,
+    ) -> Result<Option<ValueReader>> {
+        Ok(
+            match self.lookup_internal(
+                static_sorted_files,
+                range_tombstones,
+                family,
+                key,
+                use_row_cache,
+            )? {
+                Some(FoundValue::Slice(value)) => Some(ValueReader::from(value)),
+                Some(FoundValue::Blob(seq)) => Some(self.open_blob_reader(seq)?),
+                None => None,
+            },
+        )
+    }
+
+    /// Looks up a value in the given SST files, newest first. Blob files are only read when merge
+    /// operands need to be applied to them. Values that are stored directly in a SST file are
+    /// cached in the row cache when `use_row_cache` is set.
+    fn lookup_internal<K: QueryKey>(
+        &self,
+        static_sorted_files: &[Arc<StaticSortedFile>],
+        range_tombstones: &[RangeTombstone],
+        family: usize,
+        key: &K,
+        use_row_cache: bool,
+    ) -> Result<Option<FoundValue>> {
+        let hash = hash_key(key);
+        let use_row_cache = use_row_cache && !self.row_cache.is_disabled();
+        if use_row_cache {
+            if let Some(value) = self.row_cache.get(family as u32, hash, key) {
+                return Ok(Some(FoundValue::Slice(value)));
+            }
+        }
+        let deleted_up_to = deleted_up_to(range_tombstones, family as u32, key);
+        // Merge operands found in newer files, newest first. They are applied to the value found in
+        // an older file.
+        let mut operands = Vec::new();
+        let merge_operator = MergeOperator::for_family(&self.merge_operators, family as u32);
+        let apply_operands = |existing, mut operands: Vec<ArcSlice<u8>>| {
+            operands.reverse();
+            anyhow::Ok(
+                merge_operator::apply_operands(merge_operator, family as u32, existing, operands)?
+                    .map(FoundValue::Slice),
+            )
+        };
+        for sst in static_sorted_files.iter().rev() {
+            if deleted_up_to.is_some_and(|seq| sst.sequence_number() <= seq) {
+                // This and all older files are covered by a range tombstone
+                return apply_operands(None, operands);
+            }
             match sst.lookup(
                 family as u32,
                 hash,
@@ -813,18 +2525,28 @@ impl TurboPersistence {
                 LookupResult::Deleted => {
                     #[cfg(feature = "stats")]
                     self.stats.hits_deleted.fetch_add(1, Ordering::Relaxed);
-                    return Ok(None);
+                    return apply_operands(None, operands);
                 }
-                LookupResult::Slice { value } => {
+                LookupResult::Slice { value, expires_at } => {
                     #[cfg(feature = "stats")]
                     self.stats.hits_small.fetch_add(1, Ordering::Relaxed);
-                    return Ok(Some(value));
+                    if use_row_cache && operands.is_empty() {
+                        self.row_cache
+                            .insert(family as u32, hash, key, &value, expires_at);
+                    }
+                    return apply_operands(Some(value), operands);
                 }
                 LookupResult::Blob { sequence_number } => {
                     #[cfg(feature = "stats")]
                     self.stats.hits_blob.fetch_add(1, Ordering::Relaxed);
+                    if operands.is_empty() {
+                        return Ok(Some(FoundValue::Blob(sequence_number)));
+                    }
                     let blob = self.read_blob(sequence_number)?;
-                    return Ok(Some(blob));
+                    return apply_operands(Some(blob), operands);
+                }
+                LookupResult::Merge { operand } => {
+                    operands.push(operand);
                 }
                 LookupResult::RangeMiss => {
                     #[cfg(feature = "stats")]
@@ -842,15 +2564,374 @@ impl TurboPersistence {
         }
         #[cfg(feature = "stats")]
         self.stats.miss_global.fetch_add(1, Ordering::Relaxed);
-        Ok(None)
+        apply_operands(None, operands)
+    }
+
+    /// Get a value from the family `F`, which has its own key type. See [TurboPersistence::get]
+    /// and [Family].
+    pub fn get_typed<F: Family>(&self, key: &F::Key) -> Result<Option<ArcSlice<u8>>> {
+        self.get(F::INDEX, key)
+    }
+
+    /// Get the values of multiple keys from the family `F`, which has its own key type. See
+    /// [TurboPersistence::get_many] and [Family].
+    pub fn get_many_typed<F: Family>(&self, keys: &[F::Key]) -> Result<Vec<Option<ArcSlice<u8>>>> {
+        self.get_many(F::INDEX, keys)
+    }
+
+    /// Get the values of multiple keys from the database. Returns the values in the order of the
+    /// keys, with None for keys that are not found. See [TurboPersistence::get].
+    ///
+    /// This is faster than calling `get` for every key. The keys are looked up one SST file at a
+    /// time in hash order, so consecutive lookups hit the same cached filter, index and key blocks,
+    /// and files whose hash range doesn't contain a key are skipped without a lookup.
+    pub fn get_many<K: QueryKey>(
+        &self,
+        family: usize,
+        keys: &[K],
+    ) -> Result<Vec<Option<ArcSlice<u8>>>> {
+        let inner = self.inner.read();
+        let values = self.get_many_internal(
+            &inner.static_sorted_files,
+            &inner.range_tombstones,
+            family,
+            keys,
+        )?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            self.trace_get(family, key, value.as_ref().map(|value| value.len()))?;
+        }
+        Ok(values)
+    }
+
+    /// Get the values of keys in multiple families in one call, e. g. the metadata and the data of
+    /// the same items that are stored in different families. Returns the values for each pair of
+    /// family and keys, in the order of the keys. See [TurboPersistence::get_many].
+    ///
+    /// The families are looked up in parallel. All lookups read the same state of the database,
+    /// even when a write batch is committed concurrently, and they share the block caches.
+    pub fn get_many_families<K: QueryKey + Sync>(
+        &self,
+        lookups: &[(usize, &[K])],
+    ) -> Result<Vec<Vec<Option<ArcSlice<u8>>>>> {
+        // The lock is not held while the lookups run on other threads
+        let (static_sorted_files, range_tombstones) = {
+            let inner = self.inner.read();
+            (
+                inner.static_sorted_files.clone(),
+                inner.range_tombstones.clone(),
+            )
+        };
+        let values = self.install(|| {
+            lookups
+                .par_iter()
+                .map(|&(family, keys)| {
+                    self.get_many_internal(&static_sorted_files, &range_tombstones, family, keys)
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+        for (&(family, keys), values) in lookups.iter().zip(values.iter()) {
+            for (key, value) in keys.iter().zip(values.iter()) {
+                self.trace_get(family, key, value.as_ref().map(|value| value.len()))?;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Looks up multiple values in the given SST files, newest first.
+    pub(crate) fn get_many_internal<K: QueryKey>(
+        &self,
+        static_sorted_files: &[Arc<StaticSortedFile>],
+        range_tombstones: &[RangeTombstone],
+        family: usize,
+        keys: &[K],
+    ) -> Result<Vec<Option<ArcSlice<u8>>>> {
+        /// A key that was not found yet.
+        struct PendingKey {
+            index: usize,
+            hash: u64,
+            deleted_up_to: Option<u32>,
+            /// Merge operands found in newer files, newest first.
+            operands: Vec<ArcSlice<u8>>,
+        }
+
+        let merge_operator = MergeOperator::for_family(&self.merge_operators, family as u32);
+        let apply_operands = |existing, mut operands: Vec<ArcSlice<u8>>| {
+            operands.reverse();
+            merge_operator::apply_operands(merge_operator, family as u32, existing, operands)
+        };
+
+        let mut results = keys.iter().map(|_| None).collect::<Vec<_>>();
+        let mut pending = keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| PendingKey {
+                index,
+                hash: hash_key(key),
+                deleted_up_to: deleted_up_to(range_tombstones, family as u32, key),
+                operands: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        pending.sort_unstable_by_key(|key| key.hash);
+
+        for sst in static_sorted_files.iter().rev() {
+            if pending.is_empty() {
+                break;
+            }
+            let range = sst.range()?;
+            if range.family != family as u32 {
+                continue;
+            }
+            let mut still_pending = Vec::with_capacity(pending.len());
+            for mut key in pending {
+                let existing = if key
+                    .deleted_up_to
+                    .is_some_and(|seq| sst.sequence_number() <= seq)
+                {
+                    // This and all older files are covered by a range tombstone
+                    Some(None)
+                } else if key.hash < range.min_hash || key.hash > range.max_hash {
+                    #[cfg(feature = "stats")]
+                    self.stats.miss_range.fetch_add(1, Ordering::Relaxed);
+                    None
+                } else {
+                    match sst.lookup(
+                        family as u32,
+                        key.hash,
+                        &keys[key.index],
+                        &self.aqmf_cache,
+                        &self.key_block_cache,
+                        &self.value_block_cache,
+                    )? {
+                        LookupResult::Deleted => {
+                            #[cfg(feature = "stats")]
+                            self.stats.hits_deleted.fetch_add(1, Ordering::Relaxed);
+                            Some(None)
+                        }
+                        LookupResult::Slice { value, .. } => {
+                            #[cfg(feature = "stats")]
+                            self.stats.hits_small.fetch_add(1, Ordering::Relaxed);
+                            Some(Some(value))
+                        }
+                        LookupResult::Blob { sequence_number } => {
+                            #[cfg(feature = "stats")]
+                            self.stats.hits_blob.fetch_add(1, Ordering::Relaxed);
+                            Some(Some(self.read_blob(sequence_number)?))
+                        }
+                        LookupResult::Merge { operand } => {
+                            key.operands.push(operand);
+                            None
+                        }
+                        LookupResult::RangeMiss => {
+                            #[cfg(feature = "stats")]
+                            self.stats.miss_range.fetch_add(1, Ordering::Relaxed);
+                            None
+                        }
+                        LookupResult::QuickFilterMiss => {
+                            #[cfg(feature = "stats")]
+                            self.stats.miss_aqmf.fetch_add(1, Ordering::Relaxed);
+                            None
+                        }
+                        LookupResult::KeyMiss => {
+                            #[cfg(feature = "stats")]
+                            self.stats.miss_key.fetch_add(1, Ordering::Relaxed);
+                            None
+                        }
+                    }
+                };
+                match existing {
+                    Some(existing) => {
+                        results[key.index] = apply_operands(existing, key.operands)?;
+                    }
+                    None => still_pending.push(key),
+                }
+            }
+            pending = still_pending;
+        }
+        for key in pending {
+            #[cfg(feature = "stats")]
+            self.stats.miss_global.fetch_add(1, Ordering::Relaxed);
+            results[key.index] = apply_operands(None, key.operands)?;
+        }
+        Ok(results)
+    }
+
+    /// Iterates over all entries of a family whose key is within `range`. Results are merged across
+    /// all SST files, so every key is yielded at most once with its most recent value, and deleted
+    /// keys are skipped.
+    ///
+    /// Keys are stored by their hash, so entries are yielded in hash order and not in key order.
+    /// The bounds only select which entries are yielded and every SST file of the family is still
+    /// scanned, so the cost depends on the size of the family and not on the size of the range.
+    /// The scan is lazy though, so e. g. `range(..).take(n)` stops after `n` matching entries.
+    ///
+    /// The iterator operates on the SST files that exist when calling this method. Writes and
+    /// compactions that happen while iterating are not visible to it.
+    pub fn range<K: QueryKey + Clone>(
+        &self,
+        family: usize,
+        range: impl RangeBounds<K>,
+    ) -> Result<RangeIter<'_, K>> {
+        let (ssts, range_tombstones) = {
+            let inner = self.inner.read();
+            (
+                inner.static_sorted_files.clone(),
+                inner.range_tombstones.clone(),
+            )
+        };
+        self.range_internal(&ssts, &range_tombstones, family, range)
+    }
+
+    /// Like [TurboPersistence::range], but yields the entries in descending key order, e. g. to
+    /// find the last matching entry. Keys are ordered by the [KeyComparator] of the family, or by
+    /// their bytes when it has none.
+    ///
+    /// Entries are stored by key hash, so the range is scanned completely for every batch of
+    /// entries that is yielded, see [RangeRevIter]. Only a batch is kept in memory, and reading
+    /// the last few entries of a range, e. g. with `range_rev(..).take(n)`, scans it once.
+    pub fn range_rev<K: QueryKey + Clone>(
+        &self,
+        family: usize,
+        range: impl RangeBounds<K>,
+    ) -> Result<RangeRevIter<'_, K>> {
+        let (ssts, range_tombstones) = {
+            let inner = self.inner.read();
+            (
+                inner.static_sorted_files.clone(),
+                inner.range_tombstones.clone(),
+            )
+        };
+        self.range_rev_internal(ssts, range_tombstones, family, range)
+    }
+
+    /// Iterates over a key range of a family in the given SST files in descending key order.
+    pub(crate) fn range_rev_internal<K: QueryKey + Clone>(
+        &self,
+        static_sorted_files: Vec<Arc<StaticSortedFile>>,
+        range_tombstones: Arc<Vec<RangeTombstone>>,
+        family: usize,
+        range: impl RangeBounds<K>,
+    ) -> Result<RangeRevIter<'_, K>> {
+        Ok(RangeRevIter::new(
+            self,
+            static_sorted_files,
+            range_tombstones,
+            family,
+            KeyComparator::for_family(&self.key_comparators, family as u32).cloned(),
+            range.start_bound().cloned(),
+            range.end_bound().cloned(),
+        ))
+    }
+
+    /// Iterates over a key range of a family in the given SST files.
+    pub(crate) fn range_internal<K: QueryKey + Clone>(
+        &self,
+        static_sorted_files: &[Arc<StaticSortedFile>],
+        range_tombstones: &[RangeTombstone],
+        family: usize,
+        range: impl RangeBounds<K>,
+    ) -> Result<RangeIter<'_, K>> {
+        let mut ssts = Vec::new();
+        for sst in static_sorted_files.iter() {
+            if sst.range()?.family == family as u32 {
+                sst.touch();
+                ssts.push(sst.clone());
+            }
+        }
+        // Older files come first, so the merged iterator yields the most recent entry of a key
+        // last.
+        let iters = ssts
+            .into_iter()
+            .map(|sst| {
+                sst.iter(&self.key_block_cache, &self.value_block_cache)?
+                    .with_readahead(self.thread_pool.clone())
+                    .skip_deleted_ranges(range_tombstones)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let iter = MergeIter::new(iters.into_iter())?;
+        Ok(RangeIter::new(
+            self,
+            family as u32,
+            MergeOperator::for_family(&self.merge_operators, family as u32).cloned(),
+            KeyComparator::for_family(&self.key_comparators, family as u32).cloned(),
+            iter,
+            range.start_bound().cloned(),
+            range.end_bound().cloned(),
+        ))
+    }
+
+    /// Estimates the size in bytes that the entries of a family within a key range occupy in SST
+    /// files, without iterating the range. Keys are stored by hash, so a sample of the key blocks
+    /// of each SST file is read and the size of the file is scaled by the share of the sampled
+    /// keys that are within the range. Overwritten and deleted entries that were not compacted yet
+    /// are counted, values stored in blob files are not.
+    pub fn approximate_size<K: QueryKey>(
+        &self,
+        family: usize,
+        range: impl RangeBounds<K>,
+    ) -> Result<u64> {
+        let ssts = self.inner.read().static_sorted_files.clone();
+        let comparator = KeyComparator::for_family(&self.key_comparators, family as u32);
+        let mut size = 0;
+        for sst in ssts.iter() {
+            if sst.range()?.family == family as u32 {
+                size += sst.approximate_size(APPROXIMATE_SIZE_SAMPLE_BLOCKS, |key| {
+                    range_contains(comparator, range.start_bound(), range.end_bound(), key)
+                })?;
+            }
+        }
+        Ok(size)
+    }
+
+    /// Creates a cursor over the entries of a family, which can be positioned at a key with
+    /// [Cursor::seek] and moved in both directions with [Cursor::next] and [Cursor::prev]. The
+    /// cursor starts before the first entry. Like a snapshot, it reads from the SST files at the
+    /// time it was created.
+    pub fn cursor(&self, family: usize) -> Result<Cursor<'_>> {
+        let (ssts, range_tombstones) = {
+            let inner = self.inner.read();
+            (
+                inner.static_sorted_files.clone(),
+                inner.range_tombstones.clone(),
+            )
+        };
+        self.cursor_internal(ssts, range_tombstones, family)
+    }
+
+    /// Creates a cursor over the entries of a family in the given SST files.
+    pub(crate) fn cursor_internal(
+        &self,
+        static_sorted_files: Vec<Arc<StaticSortedFile>>,
+        range_tombstones: Arc<Vec<RangeTombstone>>,
+        family: usize,
+    ) -> Result<Cursor<'_>> {
+        let mut cursors = Vec::new();
+        for sst in static_sorted_files.iter() {
+            if sst.range()?.family == family as u32 {
+                cursors.push(sst.clone().cursor(&self.key_block_cache)?);
+            }
+        }
+        Ok(Cursor::new(
+            self,
+            family,
+            static_sorted_files,
+            range_tombstones,
+            cursors,
+        ))
     }
 
     /// Returns database statistics.
     #[cfg(feature = "stats")]
     pub fn statistics(&self) -> Statistics {
         let inner = self.inner.read();
+        let counts = inner
+            .static_sorted_files
+            .iter()
+            .filter_map(|sst| sst.entry_counts().ok().flatten())
+            .collect::<Vec<_>>();
         Statistics {
             sst_files: inner.static_sorted_files.len(),
+            entries: counts.iter().map(|c| c.entries as u64).sum(),
+            tombstones: counts.iter().map(|c| c.tombstones as u64).sum(),
             key_block_cache: CacheStatistics::new(&self.key_block_cache),
             value_block_cache: CacheStatistics::new(&self.value_block_cache),
             aqmf_cache: CacheStatistics::new(&self.aqmf_cache),
@@ -864,10 +2945,17 @@ impl TurboPersistence {
         }
     }
 
-    /// Shuts down the database. This will print statistics if the `print_stats` feature is enabled.
+    /// Shuts down the database and releases the lock on the directory. This will print statistics
+    /// if the `print_stats` feature is enabled.
     pub fn shutdown(&self) -> Result<()> {
+        self.wait_for_background_compaction()?;
+        if let Some(trace) = &self.trace {
+            trace.flush()?;
+        }
         #[cfg(feature = "print_stats")]
         println!("{:#?}", self.statistics());
+        // Other processes can open the database now
+        self.lock.lock().take();
         Ok(())
     }
 }
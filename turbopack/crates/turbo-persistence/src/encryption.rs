@@ -0,0 +1,88 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+
+/// Flag in the compression tag of SST and blob files that marks the blocks of the file as
+/// encrypted.
+pub const ENCRYPTED_FLAG: u8 = 0x80;
+
+/// The size of the nonce that is stored in front of every encrypted block.
+const NONCE_SIZE: usize = 12;
+
+/// Encrypts SST blocks, blob files and write-ahead log records with ChaCha20-Poly1305 using a key
+/// provided by the caller.
+///
+/// Every block is encrypted after compression with a random nonce, which is stored in front of the
+/// encrypted block. The authentication tag detects a wrong key and modified data. File headers,
+/// AQMF filters and key hashes are not encrypted.
+#[derive(Clone)]
+pub struct Encryption(ChaCha20Poly1305);
+
+impl Encryption {
+    /// Creates an encryption from a 256 bit key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self(ChaCha20Poly1305::new(Key::from_slice(key)))
+    }
+
+    /// Encrypts a block. Returns the nonce followed by the encrypted block.
+    pub(crate) fn encrypt(&self, block: &[u8]) -> Result<Vec<u8>> {
+        self.encrypt_with_aad(block, &[])
+    }
+
+    /// Encrypts a block and authenticates the additional data with it, which is not stored. The
+    /// same additional data is needed to decrypt the block.
+    pub(crate) fn encrypt_with_aad(&self, block: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let encrypted = self
+            .0
+            .encrypt(&nonce, Payload { msg: block, aad })
+            .map_err(|_| anyhow!("Encryption failed"))?;
+        let mut result = Vec::with_capacity(NONCE_SIZE + encrypted.len());
+        result.extend_from_slice(&nonce);
+        result.extend_from_slice(&encrypted);
+        Ok(result)
+    }
+
+    /// Decrypts a block that was encrypted by [Encryption::encrypt].
+    pub(crate) fn decrypt(&self, block: &[u8]) -> Result<Vec<u8>> {
+        self.decrypt_with_aad(block, &[])
+    }
+
+    /// Decrypts a block that was encrypted by [Encryption::encrypt_with_aad] with the same
+    /// additional data.
+    pub(crate) fn decrypt_with_aad(&self, block: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let Some((nonce, encrypted)) = block.split_at_checked(NONCE_SIZE) else {
+            bail!("Encrypted block is too short");
+        };
+        self.0
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: encrypted,
+                    aad,
+                },
+            )
+            .map_err(|_| {
+                anyhow!("Decryption failed, the encryption key is wrong or the block is corrupted")
+            })
+    }
+}
+
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Encryption")
+    }
+}
+
+/// Returns the encryption of the database, or an error when an encrypted file is read from a
+/// database without encryption key.
+pub fn required(encryption: Option<&Encryption>) -> Result<&Encryption> {
+    match encryption {
+        Some(encryption) => Ok(encryption),
+        None => bail!("File is encrypted, but the database has no encryption key"),
+    }
+}
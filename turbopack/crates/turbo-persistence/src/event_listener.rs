@@ -0,0 +1,121 @@
+use std::{fmt, time::Duration};
+
+/// Receives events from the database, e. g. to report them as metrics or tracing spans. All
+/// methods have empty default implementations, so only the interesting events need to be
+/// implemented.
+///
+/// The methods are called synchronously from the thread that caused the event, which might be a
+/// rayon worker or the background compaction thread. They should return quickly.
+pub trait PersistenceEventListener: Send + Sync {
+    /// Called after a write batch was committed.
+    fn on_flush(&self, _info: &FlushInfo) {}
+
+    /// Called after a SST file was written by a write batch or a compaction.
+    fn on_sst_file_created(&self, _info: &SstFileInfo) {}
+
+    /// Called after a blob file was written by a write batch.
+    fn on_blob_file_created(&self, _info: &BlobFileInfo) {}
+
+    /// Called after a compaction was committed.
+    fn on_compaction(&self, _info: &CompactionInfo) {}
+
+    /// Called after cold SST files were removed to stay within [crate::DbOptions::storage_quota].
+    fn on_eviction(&self, _info: &EvictionInfo) {}
+
+    /// Called when a lookup needs to read from a SST file, because the data is not cached.
+    fn on_cache_miss(&self, _cache: CacheKind) {}
+}
+
+impl fmt::Debug for dyn PersistenceEventListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PersistenceEventListener")
+    }
+}
+
+/// Information about a committed write batch.
+#[derive(Clone, Debug)]
+pub struct FlushInfo {
+    /// The sequence number of the database after the commit.
+    pub sequence_number: u32,
+    /// The number of SST files written by the write batch.
+    pub sst_files: usize,
+    /// The number of blob files written by the write batch.
+    pub blob_files: usize,
+    /// The time it took to write the remaining buffered entries and to commit them.
+    pub duration: Duration,
+}
+
+/// The operation that wrote a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileSource {
+    /// The file was written by a write batch.
+    WriteBatch,
+    /// The file was written by a compaction.
+    Compaction,
+}
+
+/// Information about a new SST file.
+#[derive(Clone, Debug)]
+pub struct SstFileInfo {
+    /// The sequence number of the file.
+    pub sequence_number: u32,
+    /// The family of the entries in the file.
+    pub family: u32,
+    /// The number of entries in the file.
+    pub entries: usize,
+    /// The number of tombstones, i. e. deleted keys, among the entries.
+    pub tombstones: usize,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The operation that wrote the file.
+    pub source: FileSource,
+    /// The time it took to build and write the file, including waiting for the write rate limit.
+    pub duration: Duration,
+}
+
+/// Information about a new blob file.
+#[derive(Clone, Debug)]
+pub struct BlobFileInfo {
+    /// The sequence number of the file.
+    pub sequence_number: u32,
+    /// The family of the value.
+    pub family: u32,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+/// Information about a committed compaction.
+#[derive(Clone, Debug)]
+pub struct CompactionInfo {
+    /// The compacted family, when a single family was compacted.
+    pub family: Option<u32>,
+    /// The number of SST files that were replaced.
+    pub removed_files: usize,
+    /// The number of SST files that were written.
+    pub new_files: usize,
+    /// The time the compaction took.
+    pub duration: Duration,
+}
+
+/// Information about SST files that were removed to stay within the storage quota.
+#[derive(Clone, Debug)]
+pub struct EvictionInfo {
+    /// The number of SST files that were removed.
+    pub removed_files: usize,
+    /// The size of the removed SST files and of the blob files they referenced, in bytes. Blob
+    /// files that are still referenced by other SST files are counted too, so it's an estimate.
+    pub removed_bytes: u64,
+    /// The time the eviction took, including the removal of unreferenced blob files.
+    pub duration: Duration,
+}
+
+/// The caches of the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheKind {
+    /// The cache for deserialized AQMF filters.
+    Aqmf,
+    /// The cache for decompressed index and key blocks.
+    KeyBlock,
+    /// The cache for decompressed value blocks.
+    ValueBlock,
+}
@@ -0,0 +1,13 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Converts an expiration time to seconds since the UNIX epoch, as it's stored in SST files.
+pub fn to_timestamp(expires_at: SystemTime) -> u64 {
+    expires_at
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Returns true if an entry with the given expiration timestamp has expired.
+pub fn is_expired(expires_at: Option<u64>) -> bool {
+    expires_at.is_some_and(|expires_at| expires_at <= to_timestamp(SystemTime::now()))
+}
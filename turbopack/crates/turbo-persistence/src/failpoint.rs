@@ -0,0 +1,79 @@
+#[cfg(feature = "failpoints")]
+use std::collections::HashMap;
+
+use anyhow::Result;
+#[cfg(feature = "failpoints")]
+use parking_lot::Mutex;
+
+/// A point in the write path where a failure can be injected with
+/// [crate::TurboPersistence::set_failpoint], to test that the database recovers from crashes and
+/// errors at this point. Only available with the `failpoints` feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Failpoint {
+    /// After a write batch wrote a blob file.
+    AfterBlobWrite,
+    /// After a write batch wrote a SST file, either because a buffer was full or when it's
+    /// committed.
+    AfterSstWrite,
+    /// When a write batch is committed, after the buffered entries were written to SST files and
+    /// before the range tombstones are written.
+    Finish,
+    /// After the files of a write batch or compaction were written and synced, before they are
+    /// committed.
+    BeforeCommit,
+    /// After the commit record was appended to the manifest, before the `CURRENT` file is updated,
+    /// which makes the commit durable.
+    BeforeCurrentUpdate,
+    /// After the `CURRENT` file was updated, before the files that were replaced by a compaction
+    /// are deleted.
+    BeforeFileDeletion,
+}
+
+/// What happens when a [Failpoint] is reached.
+#[cfg(feature = "failpoints")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailpointAction {
+    /// The operation fails with an error.
+    Error,
+    /// The thread panics.
+    Panic,
+    /// The process is aborted, like a crash. Useful for tests that run the database in a child
+    /// process.
+    Abort,
+}
+
+/// The failpoints of a database and their actions. Without the `failpoints` feature it's empty and
+/// checking a failpoint does nothing.
+#[derive(Default)]
+pub(crate) struct Failpoints {
+    #[cfg(feature = "failpoints")]
+    actions: Mutex<HashMap<Failpoint, FailpointAction>>,
+}
+
+impl Failpoints {
+    /// Sets the action of a failpoint, or removes it with `None`.
+    #[cfg(feature = "failpoints")]
+    pub(crate) fn set(&self, failpoint: Failpoint, action: Option<FailpointAction>) {
+        let mut actions = self.actions.lock();
+        match action {
+            Some(action) => actions.insert(failpoint, action),
+            None => actions.remove(&failpoint),
+        };
+    }
+
+    /// Runs the action of a failpoint, if it has one.
+    #[inline]
+    pub(crate) fn check(&self, failpoint: Failpoint) -> Result<()> {
+        #[cfg(feature = "failpoints")]
+        if let Some(action) = self.actions.lock().get(&failpoint).copied() {
+            match action {
+                FailpointAction::Error => anyhow::bail!("Injected failure at {failpoint:?}"),
+                FailpointAction::Panic => panic!("Injected panic at {failpoint:?}"),
+                FailpointAction::Abort => std::process::abort(),
+            }
+        }
+        #[cfg(not(feature = "failpoints"))]
+        let _ = failpoint;
+        Ok(())
+    }
+}
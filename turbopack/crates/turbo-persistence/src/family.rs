@@ -0,0 +1,14 @@
+use crate::{QueryKey, StoreKey};
+
+/// A family with its own key type. Implemented by marker types, so families with different key
+/// types can be written with the same [crate::WriteBatch] and read without encoding keys by hand,
+/// e. g. compact numeric keys in one family and composite keys in another.
+///
+/// The keys are serialized with [StoreKey::write_to] when they are written. A write batch that
+/// writes typed keys uses `Vec<u8>` as key type, see [crate::WriteBatch::put_typed].
+pub trait Family {
+    /// The index of the family.
+    const INDEX: usize;
+    /// The type of the keys of the family.
+    type Key: StoreKey + QueryKey;
+}
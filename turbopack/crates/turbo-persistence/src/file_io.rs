@@ -0,0 +1,252 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::rate_limiter::RateLimiter;
+
+/// How new SST, blob and range tombstone files are made durable when a write batch or a
+/// compaction is committed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Every new file is fsynced on its own before the `CURRENT` file is updated.
+    #[default]
+    PerFile,
+    /// New files are not fsynced individually. Instead the commit syncs the file system of the
+    /// database directory once with `syncfs` and then syncs the directory. This is a lot faster
+    /// when a commit writes many files on machines with slow fsync, but it also flushes unrelated
+    /// dirty data of the same file system. Only supported on Linux, other platforms use
+    /// [SyncPolicy::PerFile].
+    Batched,
+}
+
+impl SyncPolicy {
+    /// Returns true if every new file needs to be synced on its own.
+    pub(crate) fn sync_files(self) -> bool {
+        self == SyncPolicy::PerFile || !cfg!(target_os = "linux")
+    }
+}
+
+/// The alignment of buffers, offsets and lengths for direct I/O. It's a multiple of the logical
+/// block size of all common devices.
+const DIRECT_IO_ALIGNMENT: usize = 4096;
+
+/// The size of the buffer that is written with a single direct I/O write.
+const DIRECT_IO_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// The number of written bytes that are collected before they are accounted in the rate limiter,
+/// so small writes don't need to lock it.
+const RATE_LIMIT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes a new file, either through a buffer or with direct I/O, optionally limited by a rate
+/// limiter.
+pub(crate) struct FileWriter<'l> {
+    writer: Writer,
+    rate_limiter: Option<&'l RateLimiter>,
+    /// The number of bytes written since they were last accounted in the rate limiter.
+    unlimited_bytes: usize,
+}
+
+enum Writer {
+    Buffered(BufWriter<File>),
+    Direct(DirectIoWriter),
+}
+
+impl<'l> FileWriter<'l> {
+    /// Creates a new file. With `direct_io`, the file is opened with `O_DIRECT` on Linux or
+    /// `F_NOCACHE` on macOS, so the written data bypasses the page cache. File systems that don't
+    /// support direct I/O fall back to buffered writes. With a `rate_limiter`, writes block when
+    /// they exceed its rate.
+    pub(crate) fn create(
+        path: &Path,
+        direct_io: bool,
+        rate_limiter: Option<&'l RateLimiter>,
+    ) -> io::Result<Self> {
+        let direct = if direct_io {
+            create_direct(path)?
+        } else {
+            None
+        };
+        let writer = match direct {
+            Some(file) => Writer::Direct(DirectIoWriter::new(file)),
+            None => Writer::Buffered(BufWriter::new(File::create(path)?)),
+        };
+        Ok(Self {
+            writer,
+            rate_limiter,
+            unlimited_bytes: 0,
+        })
+    }
+
+    /// Writes the remaining buffered data and returns the file.
+    pub(crate) fn finish(self) -> io::Result<File> {
+        if let Some(rate_limiter) = self.rate_limiter {
+            rate_limiter.acquire(self.unlimited_bytes);
+        }
+        match self.writer {
+            Writer::Buffered(writer) => Ok(writer.into_inner()?),
+            Writer::Direct(writer) => writer.finish(),
+        }
+    }
+}
+
+impl Write for FileWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = match &mut self.writer {
+            Writer::Buffered(writer) => writer.write(buf)?,
+            Writer::Direct(writer) => writer.write(buf)?,
+        };
+        if let Some(rate_limiter) = self.rate_limiter {
+            self.unlimited_bytes += n;
+            if self.unlimited_bytes >= RATE_LIMIT_CHUNK_SIZE {
+                rate_limiter.acquire(self.unlimited_bytes);
+                self.unlimited_bytes = 0;
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Writer::Buffered(writer) => writer.flush(),
+            Writer::Direct(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Creates a file for direct I/O. Returns `None` when the platform or the file system doesn't
+/// support direct I/O.
+#[cfg(target_os = "linux")]
+fn create_direct(path: &Path) -> io::Result<Option<File>> {
+    use std::{fs::OpenOptions, os::unix::fs::OpenOptionsExt};
+
+    match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(path)
+    {
+        Ok(file) => Ok(Some(file)),
+        // e. g. tmpfs doesn't support O_DIRECT
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Creates a file for direct I/O. Returns `None` when the platform or the file system doesn't
+/// support direct I/O.
+#[cfg(target_os = "macos")]
+fn create_direct(path: &Path) -> io::Result<Option<File>> {
+    use std::os::fd::AsRawFd;
+
+    let file = File::create(path)?;
+    // Safety: The file descriptor is valid while the file is open.
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } == -1 {
+        return Ok(None);
+    }
+    Ok(Some(file))
+}
+
+/// Creates a file for direct I/O. Returns `None` when the platform or the file system doesn't
+/// support direct I/O.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn create_direct(_path: &Path) -> io::Result<Option<File>> {
+    Ok(None)
+}
+
+/// Writes a file opened for direct I/O. The data is collected in an aligned buffer and written in
+/// aligned chunks. The last chunk is padded with zeros and the file is truncated to the written
+/// length afterwards.
+pub(crate) struct DirectIoWriter {
+    file: File,
+    /// The allocation of the buffer. It's larger than the buffer, so an aligned buffer fits in.
+    allocation: Vec<u8>,
+    /// The offset of the aligned buffer in the allocation.
+    offset: usize,
+    /// The number of bytes in the buffer.
+    len: usize,
+    /// The number of bytes written to the file.
+    written: u64,
+}
+
+impl DirectIoWriter {
+    fn new(file: File) -> Self {
+        let allocation = vec![0; DIRECT_IO_BUFFER_SIZE + DIRECT_IO_ALIGNMENT];
+        let offset = allocation.as_ptr().align_offset(DIRECT_IO_ALIGNMENT);
+        Self {
+            file,
+            allocation,
+            offset,
+            len: 0,
+            written: 0,
+        }
+    }
+
+    /// Returns the aligned buffer.
+    fn buffer(&mut self) -> &mut [u8] {
+        &mut self.allocation[self.offset..self.offset + DIRECT_IO_BUFFER_SIZE]
+    }
+
+    /// Writes the buffer to the file. The length is padded to the alignment.
+    fn write_buffer(&mut self) -> io::Result<()> {
+        let len = self.len;
+        let padded_len = len.next_multiple_of(DIRECT_IO_ALIGNMENT);
+        let buffer = &mut self.allocation[self.offset..self.offset + padded_len];
+        buffer[len..].fill(0);
+        self.file.write_all(buffer)?;
+        self.written += len as u64;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Writes the remaining data and truncates the padding of the last chunk.
+    fn finish(mut self) -> io::Result<File> {
+        if self.len > 0 {
+            self.write_buffer()?;
+            self.file.set_len(self.written)?;
+        }
+        Ok(self.file)
+    }
+}
+
+impl Write for DirectIoWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.len;
+        let n = buf.len().min(DIRECT_IO_BUFFER_SIZE - len);
+        self.buffer()[len..len + n].copy_from_slice(&buf[..n]);
+        self.len += n;
+        if self.len == DIRECT_IO_BUFFER_SIZE {
+            self.write_buffer()?;
+        }
+        Ok(n)
+    }
+
+    /// Does nothing, since only full chunks can be written. The remaining data is written by
+    /// [DirectIoWriter::finish].
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Syncs the file system that contains the directory and the directory itself. This makes all
+/// files written to the directory durable with a single call.
+#[cfg(target_os = "linux")]
+pub(crate) fn sync_file_system(path: &Path) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let dir = File::open(path)?;
+    // Safety: The file descriptor is valid while the directory is open.
+    if unsafe { libc::syncfs(dir.as_raw_fd()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    dir.sync_all()
+}
+
+/// Syncs the file system that contains the directory. Only supported on Linux, other platforms
+/// sync every file on its own.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn sync_file_system(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
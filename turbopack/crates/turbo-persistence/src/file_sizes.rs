@@ -0,0 +1,51 @@
+use crate::constants::{DATA_THRESHOLD_PER_COMPACTED_FILE, DATA_THRESHOLD_PER_INITIAL_FILE};
+
+/// The sizes of new SST files and of the buffers that write batches collect entries in. All sizes
+/// are the total size of keys and values in bytes. Independent of them, SST files contain at most
+/// 1M entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileSizes {
+    /// The size that each thread of a write batch buffers per family. When it's exceeded, the
+    /// buffer is written to a SST file before the write batch is committed. Smaller buffers need
+    /// less memory, larger buffers lead to fewer and larger files.
+    pub thread_local_collector_size: usize,
+    /// The size of the SST files that a write batch writes when it's committed, after merging the
+    /// buffers of all threads. Also used for [crate::WriteBatch::ingest_sorted].
+    pub global_collector_size: usize,
+    /// The size of the SST files that compaction writes.
+    pub target_file_size: usize,
+}
+
+impl Default for FileSizes {
+    fn default() -> Self {
+        Self {
+            thread_local_collector_size: DATA_THRESHOLD_PER_INITIAL_FILE,
+            global_collector_size: DATA_THRESHOLD_PER_INITIAL_FILE,
+            target_file_size: DATA_THRESHOLD_PER_COMPACTED_FILE,
+        }
+    }
+}
+
+/// The configured file sizes of all families.
+#[derive(Debug, Default)]
+pub(crate) struct FamilyFileSizes {
+    /// The sizes of families without configuration.
+    default: FileSizes,
+    /// The sizes for each family, indexed by family.
+    families: Vec<Option<FileSizes>>,
+}
+
+impl FamilyFileSizes {
+    pub(crate) fn new(default: FileSizes, families: Vec<Option<FileSizes>>) -> Self {
+        Self { default, families }
+    }
+
+    /// Returns the file sizes of a family.
+    pub(crate) fn for_family(&self, family: u32) -> FileSizes {
+        self.families
+            .get(family as usize)
+            .copied()
+            .flatten()
+            .unwrap_or(self.default)
+    }
+}
@@ -74,6 +74,34 @@ impl KeyBase for u8 {
     }
 }
 
+impl KeyBase for u32 {
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(&self.to_be_bytes());
+    }
+}
+
+impl KeyBase for u64 {
+    fn len(&self) -> usize {
+        8
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(&self.to_be_bytes());
+    }
+}
+
 impl<A: KeyBase, B: KeyBase> KeyBase for (A, B) {
     fn len(&self) -> usize {
         let (a, b) = self;
@@ -136,6 +164,18 @@ impl QueryKey for u8 {
     }
 }
 
+impl QueryKey for u32 {
+    fn cmp(&self, key: &[u8]) -> std::cmp::Ordering {
+        Ord::cmp(&self.to_be_bytes()[..], key)
+    }
+}
+
+impl QueryKey for u64 {
+    fn cmp(&self, key: &[u8]) -> std::cmp::Ordering {
+        Ord::cmp(&self.to_be_bytes()[..], key)
+    }
+}
+
 impl<A: QueryKey, B: QueryKey> QueryKey for (A, B) {
     fn cmp(&self, mut key: &[u8]) -> std::cmp::Ordering {
         let (a, b) = self;
@@ -187,6 +227,19 @@ impl StoreKey for u8 {
     }
 }
 
+/// Numbers are stored in big endian, so their order matches the order of the bytes.
+impl StoreKey for u32 {
+    fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl StoreKey for u64 {
+    fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
 impl<A: StoreKey, B: StoreKey> StoreKey for (A, B) {
     fn write_to(&self, buf: &mut Vec<u8>) {
         self.0.write_to(buf);
@@ -207,6 +260,24 @@ pub fn hash_key(key: &impl KeyBase) -> u64 {
     hasher.finish()
 }
 
+/// Appends the bytes of a key to a buffer. This works for all keys, unlike [StoreKey::write_to].
+pub fn write_key_bytes(key: &impl KeyBase, buf: &mut Vec<u8>) {
+    key.hash(&mut KeyBytes(buf));
+}
+
+/// Collects the bytes of a key, which are passed to the hasher by [KeyBase::hash].
+struct KeyBytes<'l>(&'l mut Vec<u8>);
+
+impl Hasher for KeyBytes<'_> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp::Ordering;
@@ -236,4 +307,22 @@ mod tests {
         assert_eq!(h2, h1);
         assert_eq!(h3, h1);
     }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(hash_key(&0x01020304u32), hash_key(&[1, 2, 3, 4]));
+        assert_eq!(
+            hash_key(&0x0102030405060708u64),
+            hash_key(&[1, 2, 3, 4, 5, 6, 7, 8])
+        );
+        assert_eq!(
+            QueryKey::cmp(&0x01020304u32, &[1, 2, 3, 4]),
+            Ordering::Equal
+        );
+        assert_eq!(QueryKey::cmp(&0x01020304u32, &[1, 2, 3, 5]), Ordering::Less);
+        assert_eq!(
+            QueryKey::cmp(&(1u64, 2u8), &[0, 0, 0, 0, 0, 0, 0, 1, 2]),
+            Ordering::Equal
+        );
+    }
 }
@@ -3,24 +3,75 @@
 #![feature(get_mut_unchecked)]
 
 mod arc_slice;
+#[cfg(feature = "tokio")]
+mod async_read;
+mod blob;
+mod change_feed;
+mod checksum;
 mod collector;
 mod collector_entry;
 mod compaction;
+mod comparator;
+mod compression;
 mod constants;
+mod cursor;
 mod db;
+mod encryption;
+mod event_listener;
+mod expiry;
+mod failpoint;
+mod family;
+mod file_io;
+mod file_sizes;
 mod key;
+mod lock;
 mod lookup_entry;
+mod manifest;
 mod merge_iter;
+mod merge_operator;
+mod range_iter;
+mod range_tombstone;
+mod rate_limiter;
+mod repair;
+mod row_cache;
+mod snapshot;
 mod static_sorted_file;
 mod static_sorted_file_builder;
+mod trace;
+mod verify;
 mod write_batch;
 
 #[cfg(test)]
 mod tests;
 mod value_buf;
+mod wal;
 
 pub use arc_slice::ArcSlice;
-pub use db::TurboPersistence;
-pub use key::{KeyBase, QueryKey, StoreKey};
+pub use blob::ValueReader;
+pub use change_feed::{Change, ChangeFeed};
+pub use checksum::CorruptionError;
+pub use comparator::KeyComparator;
+pub use compression::Compression;
+pub use cursor::Cursor;
+pub use db::{BlobGcStats, CacheCapacity, CompactOnOpen, DbOptions, TurboPersistence};
+pub use encryption::Encryption;
+pub use event_listener::{
+    BlobFileInfo, CacheKind, CompactionInfo, EvictionInfo, FileSource, FlushInfo,
+    PersistenceEventListener, SstFileInfo,
+};
+#[cfg(feature = "failpoints")]
+pub use failpoint::{Failpoint, FailpointAction};
+pub use family::Family;
+pub use file_io::SyncPolicy;
+pub use file_sizes::FileSizes;
+pub use key::{hash_key, KeyBase, QueryKey, StoreKey};
+pub use lock::LockedError;
+pub use merge_operator::MergeOperator;
+pub use range_iter::{RangeIter, RangeRevIter};
+pub use rate_limiter::RateLimit;
+pub use repair::RepairReport;
+pub use snapshot::Snapshot;
+pub use trace::{read_trace, replay_trace, ReplayStats, TraceOperation, TraceRecord};
 pub use value_buf::ValueBuffer;
-pub use write_batch::WriteBatch;
+pub use verify::{VerifyIssue, VerifyReport};
+pub use write_batch::{PendingEntries, WriteBatch};
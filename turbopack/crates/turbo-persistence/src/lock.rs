@@ -0,0 +1,126 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+/// The name of the lock file in the persistence directory.
+pub const LOCK_FILE: &str = "LOCK";
+
+/// The error returned when the persistence directory is opened by another process, or by another
+/// database instance of the same process. It can be retrieved from the returned [anyhow::Error]
+/// with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedError {
+    /// The persistence directory.
+    pub path: PathBuf,
+    /// The id of the process that holds the lock, when it could be read from the lock file.
+    pub pid: Option<u32>,
+}
+
+impl fmt::Display for LockedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Persistence directory {:?} is already opened by another process",
+            self.path
+        )?;
+        match self.pid {
+            Some(pid) => write!(f, " (pid {pid})"),
+            None => write!(f, " (unknown pid)"),
+        }
+    }
+}
+
+impl std::error::Error for LockedError {}
+
+/// An advisory lock on the persistence directory. It's held while the database is open and
+/// released when it's dropped. The operating system releases it when the process exits, so a
+/// crashed process doesn't leave a stale lock behind.
+pub(crate) struct DirectoryLock {
+    _file: File,
+}
+
+impl DirectoryLock {
+    /// Acquires the lock and writes the id of the current process into the lock file. Fails with a
+    /// [LockedError] when the lock is held.
+    pub(crate) fn acquire(path: &Path) -> Result<Self> {
+        let lock_path = path.join(LOCK_FILE);
+        let Some(mut file) =
+            try_lock(&lock_path).context("Unable to lock persistence directory")?
+        else {
+            let pid = fs::read_to_string(&lock_path)
+                .ok()
+                .and_then(|content| content.trim().parse().ok());
+            return Err(LockedError {
+                path: path.to_path_buf(),
+                pid,
+            }
+            .into());
+        };
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Opens the lock file and locks it with `flock`. Returns `None` when another file handle holds
+/// the lock.
+#[cfg(unix)]
+fn try_lock(path: &Path) -> Result<Option<File>> {
+    use std::os::fd::AsRawFd;
+
+    // The file is not truncated before it's locked, as it contains the id of the owner
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == -1 {
+        let error = std::io::Error::last_os_error();
+        if error.kind() == std::io::ErrorKind::WouldBlock {
+            return Ok(None);
+        }
+        return Err(error.into());
+    }
+    Ok(Some(file))
+}
+
+/// Opens the lock file without allowing others to open it for writing. Returns `None` when
+/// another file handle has it open.
+#[cfg(windows)]
+fn try_lock(path: &Path) -> Result<Option<File>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_SHARE_READ: u32 = 1;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .share_mode(FILE_SHARE_READ)
+        .open(path)
+    {
+        Ok(file) => Ok(Some(file)),
+        Err(error) if error.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Other platforms don't support locking, the lock file only records the owner.
+#[cfg(not(any(unix, windows)))]
+fn try_lock(path: &Path) -> Result<Option<File>> {
+    Ok(Some(
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?,
+    ))
+}
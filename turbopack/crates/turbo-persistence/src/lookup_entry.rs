@@ -12,6 +12,8 @@ pub enum LookupValue {
     Slice { value: ArcSlice<u8> },
     /// The value is stored in a blob file.
     Blob { sequence_number: u32 },
+    /// A merge operand that is applied to the older value by the merge operator of the family.
+    Merge { operand: ArcSlice<u8> },
 }
 
 impl LookupValue {
@@ -21,6 +23,7 @@ impl LookupValue {
             LookupValue::Slice { value } => value.len(),
             LookupValue::Deleted => 0,
             LookupValue::Blob { .. } => 0,
+            LookupValue::Merge { operand } => operand.len(),
         }
     }
 }
@@ -33,6 +36,8 @@ pub struct LookupEntry {
     pub key: ArcSlice<u8>,
     /// The value.
     pub value: LookupValue,
+    /// The expiration timestamp of the value in seconds since the UNIX epoch, if any.
+    pub expires_at: Option<u64>,
 }
 
 impl Entry for LookupEntry {
@@ -48,6 +53,10 @@ impl Entry for LookupEntry {
         buf.extend_from_slice(&self.key);
     }
 
+    fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
     fn value(&self) -> EntryValue<'_> {
         match &self.value {
             LookupValue::Deleted => EntryValue::Deleted,
@@ -61,6 +70,7 @@ impl Entry for LookupEntry {
             LookupValue::Blob { sequence_number } => EntryValue::Large {
                 blob: *sequence_number,
             },
+            LookupValue::Merge { operand } => EntryValue::Merge { value: operand },
         }
     }
 }
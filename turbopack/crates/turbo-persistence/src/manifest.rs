@@ -0,0 +1,217 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+
+use crate::checksum::block_checksum;
+
+/// The file name of the manifest in the database directory.
+pub const MANIFEST_FILE: &str = "MANIFEST";
+/// The file name of a new manifest while it's written. It replaces the manifest when it's complete.
+pub const NEW_MANIFEST_FILE: &str = "MANIFEST_NEW";
+
+/// The manifest is rewritten as a single base record when it grows larger than this.
+pub const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+
+/// The tag for a base record.
+const RECORD_TYPE_BASE: u8 = 0;
+/// The tag for a commit record.
+const RECORD_TYPE_COMMIT: u8 = 1;
+/// The tag for a record with the names of the key comparators.
+const RECORD_TYPE_KEY_COMPARATORS: u8 = 2;
+
+/// The size of the length and checksum in front of each record.
+const RECORD_HEADER_SIZE: usize = 12;
+
+/// The files that are committed according to the manifest.
+///
+/// The manifest is a log of records with the following format:
+/// - 4 bytes payload length
+/// - 8 bytes xxHash64 checksum of the payload
+/// - 1 byte record type
+/// - 4 bytes sequence number
+/// - 4 bytes sequence number per new file (only for commit records)
+/// - for each family with a key comparator (only for key comparator records):
+///   - 4 bytes family
+///   - 4 bytes name length
+///   - name
+///
+/// A base record marks all files up to its sequence number as committed. A commit record marks the
+/// new SST, blob and range tombstone files of a commit as committed. The sequence number of a
+/// commit record is the one that is written to the `CURRENT` file afterwards. A key comparator
+/// record follows the base record when families have a [crate::KeyComparator].
+pub struct CommittedFiles {
+    /// All files up to this sequence number are committed.
+    base: u32,
+    /// The committed files with a higher sequence number than `base`.
+    files: HashSet<u32>,
+    /// The names of the key comparators by family.
+    key_comparators: Vec<(u32, String)>,
+}
+
+impl CommittedFiles {
+    /// Returns true if the file with the sequence number is committed.
+    pub fn contains(&self, sequence_number: u32) -> bool {
+        sequence_number <= self.base || self.files.contains(&sequence_number)
+    }
+
+    /// Returns the names of the key comparators by family that the database was written with.
+    pub fn key_comparators(&self) -> &[(u32, String)] {
+        &self.key_comparators
+    }
+}
+
+/// Encodes a record with its length and checksum.
+fn encode_record(ty: u8, sequence_number: u32, files: &[u32]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(5 + files.len() * 4);
+    payload.push(ty);
+    payload.write_u32::<BE>(sequence_number).unwrap();
+    for &file in files {
+        payload.write_u32::<BE>(file).unwrap();
+    }
+    frame_record(payload)
+}
+
+/// Encodes a key comparator record with its length and checksum.
+fn encode_key_comparators_record(
+    sequence_number: u32,
+    key_comparators: &[(u32, String)],
+) -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.push(RECORD_TYPE_KEY_COMPARATORS);
+    payload.write_u32::<BE>(sequence_number).unwrap();
+    for (family, name) in key_comparators {
+        payload.write_u32::<BE>(*family).unwrap();
+        payload.write_u32::<BE>(name.len() as u32).unwrap();
+        payload.extend_from_slice(name.as_bytes());
+    }
+    frame_record(payload)
+}
+
+/// Prefixes a record payload with its length and checksum.
+fn frame_record(payload: Vec<u8>) -> Vec<u8> {
+    let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+    record.write_u32::<BE>(payload.len() as u32).unwrap();
+    record.write_u64::<BE>(block_checksum(&payload)).unwrap();
+    record.extend_from_slice(&payload);
+    record
+}
+
+/// Appends a commit record with the new files of a commit to the manifest. The record is written
+/// with a single write and synced, so when the process dies it's either complete or fails its
+/// checksum. Returns the size of the manifest.
+pub fn append_commit_record(db_path: &Path, sequence_number: u32, files: &[u32]) -> Result<u64> {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(db_path.join(MANIFEST_FILE))
+        .context("Unable to open manifest")?;
+    file.write_all(&encode_record(RECORD_TYPE_COMMIT, sequence_number, files))
+        .context("Unable to write manifest")?;
+    file.sync_all().context("Unable to sync manifest")?;
+    Ok(file.metadata()?.len())
+}
+
+/// Replaces the manifest with a single base record, which marks all files up to the sequence
+/// number as committed, followed by the names of the key comparators. The new manifest is written
+/// to a separate file and renamed, so the manifest is replaced atomically.
+///
+/// Without key comparators no key comparator record is written, so older versions can still read
+/// the manifest. With them, older versions fail on the unknown record type instead of ordering the
+/// keys by their bytes.
+pub fn write_manifest(
+    db_path: &Path,
+    sequence_number: u32,
+    key_comparators: &[(u32, String)],
+) -> Result<()> {
+    let new_path = db_path.join(NEW_MANIFEST_FILE);
+    let mut file = File::create(&new_path).context("Unable to create manifest")?;
+    let mut content = encode_record(RECORD_TYPE_BASE, sequence_number, &[]);
+    if !key_comparators.is_empty() {
+        content.extend(encode_key_comparators_record(
+            sequence_number,
+            key_comparators,
+        ));
+    }
+    file.write_all(&content)
+        .context("Unable to write manifest")?;
+    file.sync_all().context("Unable to sync manifest")?;
+    fs::rename(&new_path, db_path.join(MANIFEST_FILE)).context("Unable to replace manifest")?;
+    // The rename is only durable after the directory is synced
+    #[cfg(unix)]
+    File::open(db_path)?.sync_all()?;
+    Ok(())
+}
+
+/// Reads the manifest. Returns `None` when there is no manifest, e. g. for a database written by
+/// an older version, in which case all files up to `current` are committed.
+///
+/// Records with a higher sequence number than `current` belong to a commit that didn't update the
+/// `CURRENT` file before the process died, and are ignored. So is an incomplete record at the end.
+pub fn read_manifest(db_path: &Path, current: u32) -> Result<Option<CommittedFiles>> {
+    let content = match fs::read(db_path.join(MANIFEST_FILE)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Unable to read manifest"),
+    };
+    let mut committed = CommittedFiles {
+        base: 0,
+        files: HashSet::new(),
+        key_comparators: Vec::new(),
+    };
+    let mut content = &content[..];
+    while let Some(mut payload) = read_record(&mut content) {
+        let ty = payload.read_u8()?;
+        let sequence_number = payload.read_u32::<BE>()?;
+        if sequence_number > current {
+            break;
+        }
+        match ty {
+            RECORD_TYPE_BASE => {
+                committed.base = sequence_number;
+                committed.files.clear();
+            }
+            RECORD_TYPE_COMMIT => {
+                for mut file in payload.chunks_exact(4) {
+                    committed.files.insert(file.read_u32::<BE>()?);
+                }
+            }
+            RECORD_TYPE_KEY_COMPARATORS => {
+                committed.key_comparators.clear();
+                while !payload.is_empty() {
+                    let family = payload.read_u32::<BE>()?;
+                    let len = payload.read_u32::<BE>()? as usize;
+                    let (name, rest) = payload
+                        .split_at_checked(len)
+                        .context("Key comparator name exceeds the record")?;
+                    committed
+                        .key_comparators
+                        .push((family, String::from_utf8(name.to_vec())?));
+                    payload = rest;
+                }
+            }
+            _ => {
+                bail!("Invalid manifest record type {ty}");
+            }
+        }
+    }
+    Ok(Some(committed))
+}
+
+/// Reads the payload of a single record. Returns `None` when the record is incomplete or doesn't
+/// match its checksum.
+fn read_record<'l>(content: &mut &'l [u8]) -> Option<&'l [u8]> {
+    let mut header = content.get(..RECORD_HEADER_SIZE)?;
+    let len = header.read_u32::<BE>().ok()? as usize;
+    let checksum = header.read_u64::<BE>().ok()?;
+    let payload = content.get(RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + len)?;
+    if len < 5 || block_checksum(payload) != checksum {
+        return None;
+    }
+    *content = &content[RECORD_HEADER_SIZE + len..];
+    Some(payload)
+}
@@ -0,0 +1,119 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::{bail, Result};
+
+use crate::{constants::MAX_SMALL_VALUE_SIZE, lookup_entry::LookupValue, ArcSlice};
+
+/// A function that combines the existing value of a key with a merge operand written by
+/// [crate::WriteBatch::merge]. The existing value is `None` when the key has no value.
+///
+/// The operands of a key are combined with each other before the existing value is known, by
+/// passing the older operand as existing value. So the function needs to be associative:
+/// `f(Some(&f(x, a)), b)` has to be equal to `f(x, &f(Some(a), b))` for every existing value `x`.
+#[derive(Clone)]
+pub struct MergeOperator(Arc<dyn Fn(Option<&[u8]>, &[u8]) -> Vec<u8> + Send + Sync>);
+
+impl MergeOperator {
+    /// Creates a merge operator from a function.
+    pub fn new(f: impl Fn(Option<&[u8]>, &[u8]) -> Vec<u8> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Returns the configured merge operator for a family.
+    pub(crate) fn for_family(
+        merge_operators: &[Option<MergeOperator>],
+        family: u32,
+    ) -> Option<&MergeOperator> {
+        merge_operators
+            .get(family as usize)
+            .and_then(|op| op.as_ref())
+    }
+
+    fn apply(&self, existing: Option<&[u8]>, operand: &[u8]) -> ArcSlice<u8> {
+        ArcSlice::from(Arc::<[u8]>::from((self.0)(existing, operand)))
+    }
+}
+
+impl fmt::Debug for MergeOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MergeOperator")
+    }
+}
+
+/// Returns the merge operator of a family, or an error when merge operands need to be applied to
+/// a family without merge operator.
+fn required(merge_operator: Option<&MergeOperator>, family: u32) -> Result<&MergeOperator> {
+    match merge_operator {
+        Some(merge_operator) => Ok(merge_operator),
+        None => bail!("Family {family} contains merge operands, but has no merge operator"),
+    }
+}
+
+/// Applies merge operands, ordered oldest first, to an existing value.
+pub(crate) fn apply_operands(
+    merge_operator: Option<&MergeOperator>,
+    family: u32,
+    existing: Option<ArcSlice<u8>>,
+    operands: impl IntoIterator<Item = ArcSlice<u8>>,
+) -> Result<Option<ArcSlice<u8>>> {
+    let mut operands = operands.into_iter().peekable();
+    if operands.peek().is_none() {
+        return Ok(existing);
+    }
+    let merge_operator = required(merge_operator, family)?;
+    let mut value = existing;
+    for operand in operands {
+        value = Some(merge_operator.apply(value.as_deref(), &operand));
+    }
+    Ok(value)
+}
+
+/// Applies a merge operand to a missing value, when it's known that no older value exists.
+pub(crate) fn resolve(
+    merge_operator: Option<&MergeOperator>,
+    family: u32,
+    value: LookupValue,
+) -> Result<LookupValue> {
+    Ok(match value {
+        LookupValue::Merge { operand } => LookupValue::Slice {
+            value: required(merge_operator, family)?.apply(None, &operand),
+        },
+        value => value,
+    })
+}
+
+/// Combines two values of the same key, where `newer` replaces `older` or is merged into it. When
+/// both are merge operands the result is a combined merge operand.
+pub(crate) fn combine(
+    merge_operator: Option<&MergeOperator>,
+    family: u32,
+    older: LookupValue,
+    newer: LookupValue,
+    read_blob: impl FnOnce(u32) -> Result<ArcSlice<u8>>,
+) -> Result<LookupValue> {
+    let LookupValue::Merge { operand } = newer else {
+        return Ok(newer);
+    };
+    let merge_operator = required(merge_operator, family)?;
+    Ok(match older {
+        LookupValue::Deleted => LookupValue::Slice {
+            value: merge_operator.apply(None, &operand),
+        },
+        LookupValue::Slice { value } => LookupValue::Slice {
+            value: merge_operator.apply(Some(&value), &operand),
+        },
+        LookupValue::Blob { sequence_number } => LookupValue::Slice {
+            value: merge_operator.apply(Some(&read_blob(sequence_number)?), &operand),
+        },
+        LookupValue::Merge { operand: older } => {
+            let operand = merge_operator.apply(Some(&older), &operand);
+            if operand.len() > MAX_SMALL_VALUE_SIZE {
+                bail!(
+                    "Combined merge operand of family {family} exceeds the maximum size of \
+                     {MAX_SMALL_VALUE_SIZE} bytes"
+                );
+            }
+            LookupValue::Merge { operand }
+        }
+    })
+}
@@ -0,0 +1,265 @@
+use std::{cmp::Ordering, iter::Peekable, ops::Bound, sync::Arc};
+
+use anyhow::Result;
+
+use crate::{
+    arc_slice::ArcSlice,
+    comparator::{self, KeyComparator},
+    db::TurboPersistence,
+    lookup_entry::{LookupEntry, LookupValue},
+    merge_iter::MergeIter,
+    merge_operator::{self, MergeOperator},
+    range_tombstone::RangeTombstone,
+    static_sorted_file::{StaticSortedFile, StaticSortedFileIter},
+    QueryKey,
+};
+
+/// The number of entries of the first batch of a [RangeRevIter]. Every following batch is twice as
+/// large as the previous one.
+pub(crate) const RANGE_REV_FIRST_BATCH_SIZE: usize = 1024;
+
+/// Returns true if the key is within the bounds, ordered by the key comparator of the family.
+pub(crate) fn range_contains<K: QueryKey>(
+    comparator: Option<&KeyComparator>,
+    start: Bound<&K>,
+    end: Bound<&K>,
+    key: &[u8],
+) -> bool {
+    let cmp = |bound: &K| comparator::compare(comparator, bound, key);
+    let after_start = match start {
+        Bound::Included(start) => cmp(start) != Ordering::Greater,
+        Bound::Excluded(start) => cmp(start) == Ordering::Less,
+        Bound::Unbounded => true,
+    };
+    let before_end = match end {
+        Bound::Included(end) => cmp(end) != Ordering::Less,
+        Bound::Excluded(end) => cmp(end) == Ordering::Greater,
+        Bound::Unbounded => true,
+    };
+    after_start && before_end
+}
+
+/// An iterator over the entries of a family within a key range. Created by
+/// [TurboPersistence::range]. Yields `(key, value)` pairs.
+pub struct RangeIter<'l, K: QueryKey> {
+    db: &'l TurboPersistence,
+    family: u32,
+    merge_operator: Option<MergeOperator>,
+    comparator: Option<KeyComparator>,
+    iter: Peekable<MergeIter<StaticSortedFileIter<'l>>>,
+    start: Bound<K>,
+    end: Bound<K>,
+}
+
+impl<'l, K: QueryKey> RangeIter<'l, K> {
+    pub(crate) fn new(
+        db: &'l TurboPersistence,
+        family: u32,
+        merge_operator: Option<MergeOperator>,
+        comparator: Option<KeyComparator>,
+        iter: MergeIter<StaticSortedFileIter<'l>>,
+        start: Bound<K>,
+        end: Bound<K>,
+    ) -> Self {
+        Self {
+            db,
+            family,
+            merge_operator,
+            comparator,
+            iter: iter.peekable(),
+            start,
+            end,
+        }
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        range_contains(
+            self.comparator.as_ref(),
+            self.start.as_ref(),
+            self.end.as_ref(),
+            key,
+        )
+    }
+
+    /// Returns the next entry, skipping all but the most recent entry of each key and applying
+    /// merge operands to the older entries.
+    fn next_entry(&mut self) -> Option<Result<LookupEntry>> {
+        let entry = match self.iter.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(self.combine_with_newer_entries(entry))
+    }
+
+    fn combine_with_newer_entries(&mut self, mut entry: LookupEntry) -> Result<LookupEntry> {
+        // All files of the family are iterated, so there is no older value to apply the merge
+        // operand of the oldest entry to.
+        entry.value =
+            merge_operator::resolve(self.merge_operator.as_ref(), self.family, entry.value)?;
+        // Entries of newer files come after the entries of older files for the same key.
+        while let Some(Ok(next)) = self.iter.peek() {
+            if next.hash != entry.hash || *next.key != *entry.key {
+                break;
+            }
+            if let Some(Ok(next)) = self.iter.next() {
+                entry.value = merge_operator::combine(
+                    self.merge_operator.as_ref(),
+                    self.family,
+                    entry.value,
+                    next.value,
+                    |seq| self.db.read_blob(seq),
+                )?;
+            }
+        }
+        Ok(entry)
+    }
+}
+
+impl<K: QueryKey> Iterator for RangeIter<'_, K> {
+    type Item = Result<(ArcSlice<u8>, ArcSlice<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let LookupEntry { key, value, .. } = match self.next_entry()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            };
+            if !self.contains(&key) {
+                continue;
+            }
+            match value {
+                LookupValue::Deleted => {}
+                LookupValue::Merge { .. } => unreachable!("merge operands are resolved"),
+                LookupValue::Slice { value } => return Some(Ok((key, value))),
+                LookupValue::Blob { sequence_number } => {
+                    return Some(self.db.read_blob(sequence_number).map(|value| (key, value)));
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the entries of a family within a key range in descending key order. Created by
+/// [TurboPersistence::range_rev]. Yields `(key, value)` pairs.
+///
+/// Entries are stored by key hash, so the largest keys can't be read first. Instead the range is
+/// scanned in batches, and every scan only keeps the largest keys below the keys of the previous
+/// batch in memory. The first batch has [RANGE_REV_FIRST_BATCH_SIZE] entries, so reading the last
+/// few entries of a range scans it once with bounded memory. Batches double in size, so iterating
+/// a whole range scans it a logarithmic number of times.
+pub struct RangeRevIter<'l, K: QueryKey> {
+    db: &'l TurboPersistence,
+    static_sorted_files: Vec<Arc<StaticSortedFile>>,
+    range_tombstones: Arc<Vec<RangeTombstone>>,
+    family: usize,
+    comparator: Option<KeyComparator>,
+    start: Bound<K>,
+    end: Bound<K>,
+    /// The remaining entries of the current batch in ascending order, so the next entry is the
+    /// last one.
+    batch: Vec<(ArcSlice<u8>, ArcSlice<u8>)>,
+    /// The smallest key of the current batch. The next batch only contains smaller keys.
+    batch_end: Option<ArcSlice<u8>>,
+    /// The number of entries of the next batch.
+    batch_size: usize,
+    /// Whether the current batch contains all remaining entries of the range.
+    last_batch: bool,
+}
+
+impl<'l, K: QueryKey + Clone> RangeRevIter<'l, K> {
+    pub(crate) fn new(
+        db: &'l TurboPersistence,
+        static_sorted_files: Vec<Arc<StaticSortedFile>>,
+        range_tombstones: Arc<Vec<RangeTombstone>>,
+        family: usize,
+        comparator: Option<KeyComparator>,
+        start: Bound<K>,
+        end: Bound<K>,
+    ) -> Self {
+        Self {
+            db,
+            static_sorted_files,
+            range_tombstones,
+            family,
+            comparator,
+            start,
+            end,
+            batch: Vec::new(),
+            batch_end: None,
+            batch_size: RANGE_REV_FIRST_BATCH_SIZE,
+            last_batch: false,
+        }
+    }
+
+    /// Scans the range for the largest keys below the current batch.
+    fn next_batch(&mut self) -> Result<()> {
+        let db = self.db;
+        let comparator = self.comparator.as_ref();
+        let iter = db.range_internal(
+            &self.static_sorted_files,
+            &self.range_tombstones,
+            self.family,
+            (self.start.clone(), self.end.clone()),
+        )?;
+        let mut entries = Vec::new();
+        let mut truncated = false;
+        for entry in iter {
+            let (key, value) = entry?;
+            if let Some(batch_end) = &self.batch_end {
+                if comparator::compare_keys(comparator, &key, batch_end) != Ordering::Less {
+                    continue;
+                }
+            }
+            entries.push((key, value));
+            // Keep the memory bounded by discarding the smaller keys every few entries
+            if entries.len() >= 2 * self.batch_size {
+                keep_largest(comparator, &mut entries, self.batch_size);
+                truncated = true;
+            }
+        }
+        if entries.len() > self.batch_size {
+            keep_largest(comparator, &mut entries, self.batch_size);
+            truncated = true;
+        }
+        entries.sort_unstable_by(|(a, _), (b, _)| comparator::compare_keys(comparator, a, b));
+        self.batch_end = entries.first().map(|(key, _)| key.clone());
+        self.batch = entries;
+        self.batch_size = self.batch_size.saturating_mul(2);
+        self.last_batch = !truncated;
+        Ok(())
+    }
+}
+
+/// Keeps the `count` entries with the largest keys, in no particular order.
+fn keep_largest(
+    comparator: Option<&KeyComparator>,
+    entries: &mut Vec<(ArcSlice<u8>, ArcSlice<u8>)>,
+    count: usize,
+) {
+    entries.select_nth_unstable_by(count - 1, |(a, _), (b, _)| {
+        comparator::compare_keys(comparator, b, a)
+    });
+    entries.truncate(count);
+}
+
+impl<K: QueryKey + Clone> Iterator for RangeRevIter<'_, K> {
+    type Item = Result<(ArcSlice<u8>, ArcSlice<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() {
+            if self.last_batch {
+                return None;
+            }
+            if let Err(err) = self.next_batch() {
+                self.last_batch = true;
+                return Some(Err(err));
+            }
+        }
+        self.batch.pop().map(Ok)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.batch.len();
+        (buffered, self.last_batch.then_some(buffered))
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+
+use crate::{comparator, KeyComparator, QueryKey};
+
+/// A deletion of all keys of a family within `start..end`.
+///
+/// Keys are distributed over SST files by their hash, so a range tombstone can't be stored next to
+/// the keys it deletes. Instead it's stored in a separate `*.rdel` file and applies to all SST
+/// files of the family with a sequence number up to `sequence_number`. These are the files that
+/// were committed before the write batch that deleted the range. Compaction removes the deleted
+/// entries from the files it rewrites, and the tombstone is dropped once no file it applies to is
+/// left.
+///
+/// The file has the following format:
+/// - 4 bytes sequence number
+/// - for each range:
+///   - 4 bytes family
+///   - 4 bytes start key length
+///   - start key
+///   - 4 bytes end key length
+///   - end key
+#[derive(Clone, Debug)]
+pub struct RangeTombstone {
+    /// The sequence number of the `*.rdel` file that contains the tombstone.
+    pub file: u32,
+    /// The family of the deleted keys.
+    pub family: u32,
+    /// The tombstone applies to SST files with a sequence number up to this.
+    pub sequence_number: u32,
+    /// The first deleted key.
+    pub start: Vec<u8>,
+    /// The end of the deleted range. This key is not deleted.
+    pub end: Vec<u8>,
+    /// The key comparator of the family. It's not stored in the file, but set when the tombstone
+    /// is added to the database.
+    pub comparator: Option<KeyComparator>,
+}
+
+impl RangeTombstone {
+    /// Returns true if the tombstone deletes keys from a SST file of the given family and sequence
+    /// number.
+    pub fn applies_to(&self, family: u32, sst_sequence_number: u32) -> bool {
+        self.family == family && sst_sequence_number <= self.sequence_number
+    }
+
+    /// Returns true if the key is within the deleted range.
+    pub fn contains<K: QueryKey>(&self, key: &K) -> bool {
+        let comparator = self.comparator.as_ref();
+        comparator::compare(comparator, key, &self.start) != Ordering::Less
+            && comparator::compare(comparator, key, &self.end) == Ordering::Less
+    }
+}
+
+/// Returns the highest sequence number of SST files in which the key is deleted by a range
+/// tombstone.
+pub fn deleted_up_to<K: QueryKey>(
+    range_tombstones: &[RangeTombstone],
+    family: u32,
+    key: &K,
+) -> Option<u32> {
+    range_tombstones
+        .iter()
+        .filter(|tombstone| tombstone.family == family && tombstone.contains(key))
+        .map(|tombstone| tombstone.sequence_number)
+        .max()
+}
+
+/// Writes a `*.rdel` file with the given ranges. Returns the file and the tombstones it contains.
+pub fn write_range_tombstones(
+    db_path: &Path,
+    file_sequence_number: u32,
+    sequence_number: u32,
+    ranges: Vec<(u32, Vec<u8>, Vec<u8>)>,
+) -> Result<(File, Vec<RangeTombstone>)> {
+    let mut buf = Vec::new();
+    buf.write_u32::<BE>(sequence_number)?;
+    for (family, start, end) in ranges.iter() {
+        buf.write_u32::<BE>(*family)?;
+        buf.write_u32::<BE>(start.len() as u32)?;
+        buf.extend_from_slice(start);
+        buf.write_u32::<BE>(end.len() as u32)?;
+        buf.extend_from_slice(end);
+    }
+    let path = db_path.join(format!("{:08}.rdel", file_sequence_number));
+    let mut file = File::create(&path).context("Unable to create range tombstone file")?;
+    file.write_all(&buf)
+        .context("Unable to write range tombstone file")?;
+    file.flush()
+        .context("Unable to flush range tombstone file")?;
+    let tombstones = ranges
+        .into_iter()
+        .map(|(family, start, end)| RangeTombstone {
+            file: file_sequence_number,
+            family,
+            sequence_number,
+            start,
+            end,
+            comparator: None,
+        })
+        .collect();
+    Ok((file, tombstones))
+}
+
+/// Reads the tombstones of a `*.rdel` file.
+pub fn read_range_tombstones(
+    db_path: &Path,
+    file_sequence_number: u32,
+) -> Result<Vec<RangeTombstone>> {
+    fn read_bytes(content: &mut &[u8]) -> Result<Vec<u8>> {
+        let len = content.read_u32::<BE>()? as usize;
+        let (bytes, rest) = content
+            .split_at_checked(len)
+            .context("Key exceeds the file")?;
+        *content = rest;
+        Ok(bytes.to_vec())
+    }
+
+    let path = db_path.join(format!("{:08}.rdel", file_sequence_number));
+    let content = fs::read(&path).context("Unable to read range tombstone file")?;
+    let mut content = &content[..];
+    let sequence_number = content.read_u32::<BE>()?;
+    let mut tombstones = Vec::new();
+    while !content.is_empty() {
+        let family = content.read_u32::<BE>()?;
+        let start = read_bytes(&mut content)?;
+        let end = read_bytes(&mut content)?;
+        tombstones.push(RangeTombstone {
+            file: file_sequence_number,
+            family,
+            sequence_number,
+            start,
+            end,
+            comparator: None,
+        });
+    }
+    Ok(tombstones)
+}
@@ -0,0 +1,64 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// Limits the rate at which new SST and blob files are written, so writing in the background
+/// doesn't saturate the disk for other work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The sustained number of bytes per second.
+    pub bytes_per_second: u64,
+    /// The number of bytes that can be written at once without waiting, after writes were idle
+    /// for a while.
+    pub burst: u64,
+}
+
+/// A token bucket that is shared by all writers of a database.
+pub(crate) struct RateLimiter {
+    bytes_per_second: f64,
+    burst: f64,
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    /// The number of bytes that can be written without waiting. It's negative when writers need
+    /// to wait for earlier writes.
+    available: f64,
+    /// The time `available` was last updated.
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        let burst = limit.burst as f64;
+        Self {
+            // A rate of zero would never allow any write
+            bytes_per_second: limit.bytes_per_second.max(1) as f64,
+            burst,
+            state: Mutex::new(RateLimiterState {
+                available: burst,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Accounts for `bytes` that are written and blocks the current thread until the rate allows
+    /// it. Writes larger than the burst are allowed and delay later writes.
+    pub(crate) fn acquire(&self, bytes: usize) {
+        let wait = {
+            let mut state = self.state.lock();
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.updated).as_secs_f64();
+            state.available =
+                (state.available + elapsed * self.bytes_per_second).min(self.burst) - bytes as f64;
+            state.updated = now;
+            -state.available / self.bytes_per_second
+        };
+        if wait > 0.0 {
+            thread::sleep(Duration::from_secs_f64(wait));
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+
+use crate::{
+    blob::read_blob_file,
+    encryption::Encryption,
+    lookup_entry::LookupValue,
+    manifest::{read_manifest, write_manifest},
+    range_tombstone::read_range_tombstones,
+    static_sorted_file::{BlockCache, StaticSortedFile, StaticSortedFileRange},
+};
+
+/// The result of [crate::TurboPersistence::repair].
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// The files that were removed, because they were damaged, not committed, or might contain
+    /// keys of a damaged file.
+    pub removed_files: Vec<PathBuf>,
+    /// The sequence number that was written to the `CURRENT` file.
+    pub sequence_number: u32,
+}
+
+/// A region of keys that is affected by a damaged file. Older SST files with keys in this region
+/// are removed too, since the damaged file might contain newer values or tombstones for them.
+struct DamagedRegion {
+    /// The sequence number of the damaged file.
+    sequence_number: u32,
+    /// The family and hash range of the damaged file, if the header could be read.
+    range: Option<StaticSortedFileRange>,
+}
+
+impl DamagedRegion {
+    /// Returns true if a SST file might contain keys of the region.
+    fn covers(&self, sequence_number: u32, range: &StaticSortedFileRange) -> bool {
+        sequence_number < self.sequence_number
+            && self.range.is_none_or(|damaged| {
+                damaged.family == range.family
+                    && damaged.min_hash <= range.max_hash
+                    && range.min_hash <= damaged.max_hash
+            })
+    }
+}
+
+/// Repairs the database in `path`. See [crate::TurboPersistence::repair].
+pub fn repair(path: &Path, encryption: Option<&Encryption>) -> Result<RepairReport> {
+    let mut report = RepairReport::default();
+    let mut remove = |path: PathBuf| -> Result<()> {
+        fs::remove_file(&path).with_context(|| format!("Unable to remove {:?}", path))?;
+        report.removed_files.push(path);
+        Ok(())
+    };
+
+    // A missing or truncated CURRENT file is rebuilt from the remaining files
+    let current = fs::read(path.join("CURRENT"))
+        .ok()
+        .and_then(|content| (&content[..]).read_u32::<BE>().ok());
+
+    let mut files: HashMap<&str, Vec<u32>> = HashMap::new();
+    for entry in fs::read_dir(path).context("Unable to read persistence directory")? {
+        let file_path = entry?.path();
+        let (Some(ext), Some(seq)) = (
+            file_path.extension().and_then(|s| s.to_str()),
+            file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u32>().ok()),
+        ) else {
+            continue;
+        };
+        let Some(ext) = ["sst", "blob", "rdel", "del"]
+            .into_iter()
+            .find(|e| *e == ext)
+        else {
+            continue;
+        };
+        if current.is_some_and(|current| seq > current) {
+            // Not committed
+            remove(file_path)?;
+        } else {
+            files.entry(ext).or_default().push(seq);
+        }
+    }
+    let mut take = |ext: &str| {
+        let mut seqs = files.remove(ext).unwrap_or_default();
+        seqs.sort_unstable();
+        seqs
+    };
+    let mut sst_files = take("sst");
+    let mut blob_files = take("blob");
+    let mut range_tombstone_files = take("rdel");
+    let del_files = take("del");
+
+    // Finish deletions of compactions. A truncated *.del file still lists the first files.
+    for seq in del_files {
+        let del_path = path.join(format!("{:08}.del", seq));
+        let content = fs::read(&del_path).unwrap_or_default();
+        let mut content = &content[..];
+        let mut deleted_files = HashSet::new();
+        while let Ok(seq) = content.read_u32::<BE>() {
+            deleted_files.insert(seq);
+        }
+        for (ext, seqs) in [
+            ("sst", &mut sst_files),
+            ("blob", &mut blob_files),
+            ("rdel", &mut range_tombstone_files),
+        ] {
+            for seq in seqs.extract_if(.., |seq| deleted_files.contains(seq)) {
+                remove(path.join(format!("{:08}.{}", seq, ext)))?;
+            }
+        }
+        remove(del_path)?;
+    }
+
+    let mut damaged_regions = Vec::new();
+
+    // A missing range tombstone would make deleted keys visible again
+    for seq in
+        range_tombstone_files.extract_if(.., |&mut seq| read_range_tombstones(path, seq).is_err())
+    {
+        damaged_regions.push(DamagedRegion {
+            sequence_number: seq,
+            range: None,
+        });
+        remove(path.join(format!("{:08}.rdel", seq)))?;
+    }
+
+    let damaged_blob_files = blob_files
+        .extract_if(.., |&mut seq| {
+            read_blob_file(path, seq, encryption).is_err()
+        })
+        .collect::<HashSet<_>>();
+    let blob_files = blob_files.into_iter().collect::<HashSet<_>>();
+
+    // Newer files are checked first, so all damaged regions that apply to a file are known when
+    // it's checked.
+    let mut remaining_files = Vec::new();
+    for &seq in sst_files.iter().rev() {
+        let sst_path = path.join(format!("{:08}.sst", seq));
+        let sst = StaticSortedFile::open(
+            seq,
+            sst_path.clone(),
+            true,
+            encryption.cloned(),
+            None,
+            false,
+        )
+        .map(Arc::new);
+        // The header is not covered by checksums, but it's still the best guess for the keys a
+        // damaged file contains.
+        let range = sst.as_ref().ok().and_then(|sst| sst.range().ok());
+        let damaged = match (sst, &range) {
+            (Ok(sst), Some(range)) => {
+                check_sst_file(sst, &blob_files).is_err()
+                    || damaged_regions
+                        .iter()
+                        .any(|region| region.covers(seq, range))
+            }
+            _ => true,
+        };
+        if damaged {
+            damaged_regions.push(DamagedRegion {
+                sequence_number: seq,
+                range,
+            });
+            remove(sst_path)?;
+        } else {
+            remaining_files.push(seq);
+        }
+    }
+
+    for seq in damaged_blob_files {
+        remove(path.join(format!("{:08}.blob", seq)))?;
+    }
+
+    remaining_files.extend(blob_files);
+    remaining_files.extend(range_tombstone_files);
+    let sequence_number = current
+        .into_iter()
+        .chain(remaining_files)
+        .max()
+        .unwrap_or_default();
+    let mut current_file = File::create(path.join("CURRENT"))?;
+    current_file.write_u32::<BE>(sequence_number)?;
+    current_file.sync_all()?;
+    // All remaining files are committed now. The key comparators are kept, when the manifest is
+    // readable.
+    let key_comparators = read_manifest(path, u32::MAX)
+        .ok()
+        .flatten()
+        .map(|committed| committed.key_comparators().to_vec())
+        .unwrap_or_default();
+    write_manifest(path, sequence_number, &key_comparators)?;
+
+    report.removed_files.sort();
+    report.sequence_number = sequence_number;
+    Ok(report)
+}
+
+/// Reads all blocks of a SST file and verifies their checksums. Also checks that all referenced
+/// blob files exist and are readable.
+fn check_sst_file(sst: Arc<StaticSortedFile>, blob_files: &HashSet<u32>) -> Result<()> {
+    sst.check_block_layout()?;
+    let new_cache = || {
+        BlockCache::with(
+            16,
+            16 * 1024 * 1024,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+    };
+    let key_block_cache = new_cache();
+    let value_block_cache = new_cache();
+    for entry in sst.iter(&key_block_cache, &value_block_cache)? {
+        if let LookupValue::Blob { sequence_number } = entry?.value {
+            if !blob_files.contains(&sequence_number) {
+                bail!(
+                    "Referenced blob file {:08}.blob is missing",
+                    sequence_number
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::{cmp::Ordering, hash::BuildHasherDefault, sync::Arc};
+
+use rustc_hash::FxHasher;
+
+use crate::{
+    arc_slice::ArcSlice,
+    constants::ROW_CACHE_AVG_SIZE,
+    expiry::is_expired,
+    key::write_key_bytes,
+    range_tombstone::RangeTombstone,
+    static_sorted_file::{AqmfCache, StaticSortedFile},
+    QueryKey,
+};
+
+/// A cached value of a key.
+#[derive(Clone)]
+struct Row {
+    /// The key, to tell apart keys with the same hash.
+    key: Arc<[u8]>,
+    value: ArcSlice<u8>,
+    /// The expiration time of the value, in seconds since the UNIX epoch.
+    expires_at: Option<u64>,
+}
+
+#[derive(Clone, Default)]
+struct RowWeighter;
+
+impl quick_cache::Weighter<(u32, u64), Row> for RowWeighter {
+    fn weight(&self, _key: &(u32, u64), row: &Row) -> u64 {
+        (row.key.len() + row.value.len()) as u64 + 32
+    }
+}
+
+type RowCacheMap =
+    quick_cache::sync::Cache<(u32, u64), Row, RowWeighter, BuildHasherDefault<FxHasher>>;
+
+/// A cache for decoded values of keys, indexed by family and key hash. It's consulted before the
+/// SST files, so hot keys are read without decompressing their blocks again. Only values that are
+/// stored directly in a SST file are cached, not blob values and values with merge operands.
+///
+/// The cache describes the current state of the database. Entries are removed when a commit adds a
+/// SST file that might contain their key or a range tombstone that deletes it. Lookups and
+/// commits synchronize through the lock of the database state.
+pub(crate) struct RowCache {
+    cache: RowCacheMap,
+}
+
+impl RowCache {
+    pub(crate) fn new(capacity: u64) -> Self {
+        Self {
+            cache: RowCacheMap::with(
+                (capacity as usize / ROW_CACHE_AVG_SIZE).max(1),
+                capacity,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            ),
+        }
+    }
+
+    /// Returns true if the cache has a capacity of zero, so it's not used.
+    pub(crate) fn is_disabled(&self) -> bool {
+        self.cache.capacity() == 0
+    }
+
+    pub(crate) fn set_capacity(&self, capacity: u64) {
+        self.cache.set_capacity(capacity);
+    }
+
+    /// Returns the cached value of a key.
+    pub(crate) fn get<K: QueryKey>(&self, family: u32, hash: u64, key: &K) -> Option<ArcSlice<u8>> {
+        let row = self.cache.get(&(family, hash))?;
+        if QueryKey::cmp(key, &row.key) != Ordering::Equal {
+            return None;
+        }
+        if is_expired(row.expires_at) {
+            self.cache.remove(&(family, hash));
+            return None;
+        }
+        Some(row.value)
+    }
+
+    /// Caches the value of a key. The value is copied, so the cache doesn't keep the block it was
+    /// read from alive.
+    pub(crate) fn insert<K: QueryKey>(
+        &self,
+        family: u32,
+        hash: u64,
+        key: &K,
+        value: &[u8],
+        expires_at: Option<u64>,
+    ) {
+        let mut key_bytes = Vec::with_capacity(key.len());
+        write_key_bytes(key, &mut key_bytes);
+        self.cache.insert(
+            (family, hash),
+            Row {
+                key: key_bytes.into(),
+                value: ArcSlice::from(Box::from(value)),
+                expires_at,
+            },
+        );
+    }
+
+    /// Removes all cached values of a family.
+    pub(crate) fn invalidate_family(&self, family: u32) {
+        if self.cache.is_empty() {
+            return;
+        }
+        self.cache
+            .retain(|&(row_family, _), _| row_family != family);
+    }
+
+    /// Removes the cached values that might be changed by new SST files or range tombstones.
+    pub(crate) fn invalidate(
+        &self,
+        new_sst_files: &[Arc<StaticSortedFile>],
+        new_range_tombstones: &[RangeTombstone],
+        aqmf_cache: &AqmfCache,
+    ) {
+        if self.cache.is_empty() {
+            return;
+        }
+        self.cache.retain(|&(family, hash), row| {
+            let changed_by_sst = new_sst_files.iter().any(|sst| match sst.range() {
+                Ok(range) => {
+                    range.family == family && sst.may_contain_hash(hash, aqmf_cache).unwrap_or(true)
+                }
+                // Be conservative when the file can't be read
+                Err(_) => true,
+            });
+            let deleted = new_range_tombstones
+                .iter()
+                .any(|tombstone| tombstone.family == family && tombstone.contains(&&*row.key));
+            !changed_by_sst && !deleted
+        });
+    }
+}
@@ -0,0 +1,123 @@
+use std::{ops::RangeBounds, sync::Arc};
+
+use anyhow::Result;
+
+use crate::{
+    arc_slice::ArcSlice,
+    blob::ValueReader,
+    cursor::Cursor,
+    db::TurboPersistence,
+    range_iter::{RangeIter, RangeRevIter},
+    range_tombstone::RangeTombstone,
+    static_sorted_file::StaticSortedFile,
+    QueryKey,
+};
+
+/// A consistent view of the database at a point in time. Created by
+/// [TurboPersistence::snapshot].
+pub struct Snapshot<'l> {
+    db: &'l TurboPersistence,
+    /// The SST files at the time the snapshot was taken, in order.
+    static_sorted_files: Vec<Arc<StaticSortedFile>>,
+    /// The range tombstones at the time the snapshot was taken.
+    range_tombstones: Arc<Vec<RangeTombstone>>,
+    /// The sequence number at the time the snapshot was taken.
+    sequence_number: u32,
+}
+
+impl<'l> Snapshot<'l> {
+    pub(crate) fn new(
+        db: &'l TurboPersistence,
+        static_sorted_files: Vec<Arc<StaticSortedFile>>,
+        range_tombstones: Arc<Vec<RangeTombstone>>,
+        sequence_number: u32,
+    ) -> Self {
+        Self {
+            db,
+            static_sorted_files,
+            range_tombstones,
+            sequence_number,
+        }
+    }
+
+    /// Returns the sequence number of the database at the time the snapshot was taken.
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    /// Get a value from the snapshot. Returns None if the key is not found. See
+    /// [TurboPersistence::get].
+    pub fn get<K: QueryKey>(&self, family: usize, key: &K) -> Result<Option<ArcSlice<u8>>> {
+        self.db.get_internal(
+            &self.static_sorted_files,
+            &self.range_tombstones,
+            family,
+            key,
+            false,
+        )
+    }
+
+    /// Get a reader for a value from the snapshot. See [TurboPersistence::get_reader].
+    pub fn get_reader<K: QueryKey>(&self, family: usize, key: &K) -> Result<Option<ValueReader>> {
+        self.db.get_reader_internal(
+            &self.static_sorted_files,
+            &self.range_tombstones,
+            family,
+            key,
+            false,
+        )
+    }
+
+    /// Get the values of multiple keys from the snapshot. See [TurboPersistence::get_many].
+    pub fn get_many<K: QueryKey>(
+        &self,
+        family: usize,
+        keys: &[K],
+    ) -> Result<Vec<Option<ArcSlice<u8>>>> {
+        self.db.get_many_internal(
+            &self.static_sorted_files,
+            &self.range_tombstones,
+            family,
+            keys,
+        )
+    }
+
+    /// Iterates over all entries of a family whose key is within `range`. See
+    /// [TurboPersistence::range].
+    pub fn range<K: QueryKey + Clone>(
+        &self,
+        family: usize,
+        range: impl RangeBounds<K>,
+    ) -> Result<RangeIter<'l, K>> {
+        self.db.range_internal(
+            &self.static_sorted_files,
+            &self.range_tombstones,
+            family,
+            range,
+        )
+    }
+
+    /// Iterates over all entries of a family whose key is within `range` in descending key order.
+    /// See [TurboPersistence::range_rev].
+    pub fn range_rev<K: QueryKey + Clone>(
+        &self,
+        family: usize,
+        range: impl RangeBounds<K>,
+    ) -> Result<RangeRevIter<'l, K>> {
+        self.db.range_rev_internal(
+            self.static_sorted_files.clone(),
+            self.range_tombstones.clone(),
+            family,
+            range,
+        )
+    }
+
+    /// Creates a cursor over the entries of a family. See [TurboPersistence::cursor].
+    pub fn cursor(&self, family: usize) -> Result<Cursor<'l>> {
+        self.db.cursor_internal(
+            self.static_sorted_files.clone(),
+            self.range_tombstones.clone(),
+            family,
+        )
+    }
+}
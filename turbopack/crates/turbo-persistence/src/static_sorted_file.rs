@@ -1,25 +1,63 @@
 use std::{
     cmp::Ordering,
-    fs::File,
+    collections::VecDeque,
+    fs::{File, OpenOptions},
     hash::BuildHasherDefault,
+    io::{Seek, SeekFrom},
     mem::{transmute, MaybeUninit},
-    path::PathBuf,
-    sync::{Arc, OnceLock},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{self, AtomicU64},
+        mpsc::{self, Receiver, Sender},
+        Arc, OnceLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{bail, Result};
-use byteorder::{ReadBytesExt, BE};
-use lzzzz::lz4::decompress_with_dict;
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt, BE};
 use memmap2::Mmap;
 use quick_cache::sync::GuardResult;
+use rayon::ThreadPool;
 use rustc_hash::FxHasher;
 
 use crate::{
     arc_slice::ArcSlice,
+    checksum::{block_checksum, CorruptionError},
+    compression::Compression,
+    constants::{READAHEAD_KEY_BLOCKS, READAHEAD_TRIGGER_KEY_BLOCKS},
+    encryption::{self, Encryption, ENCRYPTED_FLAG},
+    event_listener::{CacheKind, PersistenceEventListener},
+    expiry::{is_expired, to_timestamp},
     lookup_entry::{LookupEntry, LookupValue},
+    range_tombstone::RangeTombstone,
     QueryKey,
 };
 
+/// The magic number and version of SST files.
+pub const SST_MAGIC: u32 = 0x53535408;
+/// The magic number of SST files of version 7, which don't store in which SST files their entries
+/// were committed. They are still readable.
+pub const SST_MAGIC_V7: u32 = 0x53535407;
+/// The magic number of SST files of version 6, which store the AQMF as a single filter. They are
+/// still readable.
+pub const SST_MAGIC_V6: u32 = 0x53535406;
+/// The magic number of SST files of version 5, which don't store the number of entries and
+/// tombstones in the header. They are still readable.
+pub const SST_MAGIC_V5: u32 = 0x53535405;
+/// The magic number of SST files of version 4, which also store every key of a key block in full.
+/// They are still readable.
+pub const SST_MAGIC_V4: u32 = 0x53535404;
+
+/// The offset of the commit sequence numbers in the header of SST files.
+const COMMIT_SEQUENCE_NUMBERS_OFFSET: u64 = 42;
+
+/// Every n-th entry of a key block is a restart point that stores the full key. The keys of all
+/// other entries are stored as the length of the prefix shared with the previous key and the
+/// remaining suffix.
+pub const KEY_BLOCK_RESTART_INTERVAL: usize = 16;
+
 /// The block header for an index block.
 pub const BLOCK_TYPE_INDEX: u8 = 0;
 /// The block header for a key block.
@@ -33,15 +71,29 @@ pub const KEY_BLOCK_ENTRY_TYPE_BLOB: u8 = 1;
 pub const KEY_BLOCK_ENTRY_TYPE_DELETED: u8 = 2;
 /// The tag for a medium-sized value.
 pub const KEY_BLOCK_ENTRY_TYPE_MEDIUM: u8 = 3;
+/// The tag for a small-sized value with expiration timestamp.
+pub const KEY_BLOCK_ENTRY_TYPE_SMALL_EXPIRING: u8 = 4;
+/// The tag for the blob value with expiration timestamp.
+pub const KEY_BLOCK_ENTRY_TYPE_BLOB_EXPIRING: u8 = 5;
+/// The tag for a medium-sized value with expiration timestamp.
+pub const KEY_BLOCK_ENTRY_TYPE_MEDIUM_EXPIRING: u8 = 6;
+/// The tag for a merge operand. It's stored like a small-sized value.
+pub const KEY_BLOCK_ENTRY_TYPE_MERGE: u8 = 7;
 
 /// The result of a lookup operation.
 pub enum LookupResult {
     /// The key was deleted.
     Deleted,
     /// The key was found and the value is a slice.
-    Slice { value: ArcSlice<u8> },
+    Slice {
+        value: ArcSlice<u8>,
+        /// The expiration time of the value, in seconds since the UNIX epoch.
+        expires_at: Option<u64>,
+    },
     /// The key was found and the value is a blob.
     Blob { sequence_number: u32 },
+    /// The key was found and the value is a merge operand for the older value.
+    Merge { operand: ArcSlice<u8> },
     /// The key was not found because it is out of the range of this SST file.
     RangeMiss,
     /// The key was not found because it was not in the AQMF filter. But it was in the range.
@@ -50,17 +102,8 @@ pub enum LookupResult {
     KeyMiss,
 }
 
-impl From<LookupValue> for LookupResult {
-    fn from(value: LookupValue) -> Self {
-        match value {
-            LookupValue::Deleted => LookupResult::Deleted,
-            LookupValue::Slice { value } => LookupResult::Slice { value },
-            LookupValue::Blob { sequence_number } => LookupResult::Blob { sequence_number },
-        }
-    }
-}
-
 /// A byte range in the SST file.
+#[derive(Clone, Copy)]
 struct LocationInFile {
     start: usize,
     end: usize,
@@ -70,12 +113,20 @@ struct LocationInFile {
 struct Header {
     /// The key family stored in this file.
     family: u32,
+    /// The compression of the blocks in this file.
+    compression: Compression,
+    /// Whether the blocks of this file are encrypted.
+    encrypted: bool,
     /// The minimum hash value in this file.
     min_hash: u64,
     /// The maximum hash value in this file.
     max_hash: u64,
-    /// The location of the AQMF filter in the file.
+    /// The location of the AQMF filter in the file. For a partitioned AQMF, the location of all
+    /// partitions.
     aqmf: LocationInFile,
+    /// The location of the partition table of the AQMF, for files that split their AQMF into
+    /// partitions by key hash.
+    aqmf_partitions: Option<LocationInFile>,
     /// The location of the key compression dictionary in the file.
     key_compression_dictionary: LocationInFile,
     /// The location of the value compression dictionary in the file.
@@ -86,6 +137,22 @@ struct Header {
     blocks_start: usize,
     /// The number of blocks in this file.
     block_count: u16,
+    /// Whether the keys in the key blocks are prefix compressed.
+    prefix_compressed_keys: bool,
+    /// The number of entries and tombstones, if stored in the header.
+    counts: Option<EntryCounts>,
+    /// The lowest and highest sequence number of the SST files in which the entries of this file
+    /// were committed, if stored in the header.
+    commit_sequence_numbers: Option<(u32, u32)>,
+}
+
+/// The number of entries in an SST file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EntryCounts {
+    /// The number of entries, including tombstones.
+    pub entries: u32,
+    /// The number of tombstones, i. e. deleted keys.
+    pub tombstones: u32,
 }
 
 /// The key family and hash range of an SST file.
@@ -99,8 +166,8 @@ pub struct StaticSortedFileRange {
 #[derive(Clone, Default)]
 pub struct AqmfWeighter;
 
-impl quick_cache::Weighter<u32, Arc<qfilter::Filter>> for AqmfWeighter {
-    fn weight(&self, _key: &u32, filter: &Arc<qfilter::Filter>) -> u64 {
+impl quick_cache::Weighter<(u32, u16), Arc<qfilter::Filter>> for AqmfWeighter {
+    fn weight(&self, _key: &(u32, u16), filter: &Arc<qfilter::Filter>) -> u64 {
         filter.capacity() + 1
     }
 }
@@ -114,8 +181,12 @@ impl quick_cache::Weighter<(u32, u16), ArcSlice<u8>> for BlockWeighter {
     }
 }
 
-pub type AqmfCache =
-    quick_cache::sync::Cache<u32, Arc<qfilter::Filter>, AqmfWeighter, BuildHasherDefault<FxHasher>>;
+pub type AqmfCache = quick_cache::sync::Cache<
+    (u32, u16),
+    Arc<qfilter::Filter>,
+    AqmfWeighter,
+    BuildHasherDefault<FxHasher>,
+>;
 pub type BlockCache =
     quick_cache::sync::Cache<(u32, u16), ArcSlice<u8>, BlockWeighter, BuildHasherDefault<FxHasher>>;
 
@@ -127,9 +198,25 @@ pub struct StaticSortedFile {
     mmap: Mmap,
     /// The parsed header of this file.
     header: OnceLock<Header>,
-    /// The AQMF filter of this file. This is only used if the range is very large. Smaller ranges
-    /// use the AQMF cache instead.
-    aqmf: OnceLock<qfilter::Filter>,
+    /// The AQMF filter partitions of this file. This is only used if the range is very large.
+    /// Smaller ranges use the AQMF cache instead.
+    aqmf: OnceLock<Box<[OnceLock<qfilter::Filter>]>>,
+    /// Whether block checksums are verified when reading blocks.
+    verify_checksums: bool,
+    /// The encryption to decrypt the blocks of this file, if they are encrypted.
+    encryption: Option<Encryption>,
+    /// Receives cache misses of lookups in this file.
+    event_listener: Option<Arc<dyn PersistenceEventListener>>,
+    /// Whether lookups read uncompressed and unencrypted blocks directly from the memory mapping
+    /// instead of copying them into the block caches.
+    mapped_reads: bool,
+    /// The number of lookups that probed this file, i. e. that were within its key range.
+    reads: AtomicU64,
+    /// The time of the last lookup or range scan that read this file, in seconds since the UNIX
+    /// epoch. It starts at the modification time of the file, which stores it across restarts.
+    last_read: AtomicU64,
+    /// The time that is stored in the modification time of the file.
+    persisted_last_read: AtomicU64,
 }
 
 impl StaticSortedFile {
@@ -139,14 +226,34 @@ impl StaticSortedFile {
     }
 
     /// Opens an SST file at the given path. This memory maps the file, but does not read it yet.
-    /// It's lazy read on demand.
-    pub fn open(sequence_number: u32, path: PathBuf) -> Result<Self> {
-        let mmap = unsafe { Mmap::map(&File::open(&path)?)? };
+    /// It's lazy read on demand. When `verify_checksums` is set, every block is checked against its
+    /// checksum when it's read, and a [CorruptionError] is returned on mismatch. The encryption is
+    /// only used when the file is encrypted. The event listener is notified about cache misses.
+    /// When `mapped_reads` is set, lookups serve blocks that don't need decompression directly from
+    /// the memory mapping.
+    pub fn open(
+        sequence_number: u32,
+        path: PathBuf,
+        verify_checksums: bool,
+        encryption: Option<Encryption>,
+        event_listener: Option<Arc<dyn PersistenceEventListener>>,
+        mapped_reads: bool,
+    ) -> Result<Self> {
+        let file = File::open(&path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let modified = file.metadata()?.modified().map_or(0, to_timestamp);
         let file = Self {
             sequence_number,
             mmap,
             header: OnceLock::new(),
             aqmf: OnceLock::new(),
+            verify_checksums,
+            encryption,
+            event_listener,
+            mapped_reads,
+            reads: AtomicU64::new(0),
+            last_read: AtomicU64::new(modified),
+            persisted_last_read: AtomicU64::new(modified),
         };
         Ok(file)
     }
@@ -156,23 +263,70 @@ impl StaticSortedFile {
         self.header.get_or_try_init(|| {
             let mut file = &*self.mmap;
             let magic = file.read_u32::<BE>()?;
-            if magic != 0x53535401 {
-                bail!("Invalid magic number or version");
-            }
+            let (prefix_compressed_keys, has_counts, partitioned_aqmf, has_commits) = match magic {
+                SST_MAGIC => (true, true, true, true),
+                SST_MAGIC_V7 => (true, true, true, false),
+                SST_MAGIC_V6 => (true, true, false, false),
+                SST_MAGIC_V5 => (true, false, false, false),
+                SST_MAGIC_V4 => (false, false, false, false),
+                _ => bail!("Invalid magic number or version"),
+            };
             let family = file.read_u32::<BE>()?;
+            let compression_tag = file.read_u8()?;
+            let encrypted = compression_tag & ENCRYPTED_FLAG != 0;
+            let compression = Compression::from_tag(compression_tag & !ENCRYPTED_FLAG)?;
             let min_hash = file.read_u64::<BE>()?;
             let max_hash = file.read_u64::<BE>()?;
             let aqmf_length = file.read_u24::<BE>()? as usize;
             let key_compression_dictionary_length = file.read_u16::<BE>()? as usize;
             let value_compression_dictionary_length = file.read_u16::<BE>()? as usize;
             let block_count = file.read_u16::<BE>()?;
-            const HEADER_SIZE: usize = 33;
+            let counts = if has_counts {
+                Some(EntryCounts {
+                    entries: file.read_u32::<BE>()?,
+                    tombstones: file.read_u32::<BE>()?,
+                })
+            } else {
+                None
+            };
+            let commit_sequence_numbers = if has_commits {
+                Some((file.read_u32::<BE>()?, file.read_u32::<BE>()?))
+            } else {
+                None
+            };
+            const HEADER_SIZE: usize = 34;
+            const COUNTS_SIZE: usize = 8;
+            const COMMITS_SIZE: usize = 8;
             let mut current_offset = HEADER_SIZE;
-            let aqmf = LocationInFile {
+            if has_counts {
+                current_offset += COUNTS_SIZE;
+            }
+            if has_commits {
+                current_offset += COMMITS_SIZE;
+            }
+            let mut aqmf = LocationInFile {
                 start: current_offset,
                 end: current_offset + aqmf_length,
             };
             current_offset += aqmf_length;
+            let aqmf_partitions = if partitioned_aqmf {
+                let mut section = self
+                    .mmap
+                    .get(aqmf.start..aqmf.end)
+                    .context("AQMF exceeds the file")?;
+                let partition_count = section.read_u16::<BE>()? as usize;
+                let partitions = LocationInFile {
+                    start: aqmf.start + 2,
+                    end: aqmf.start + 2 + partition_count * 12,
+                };
+                if partition_count == 0 || partitions.end > aqmf.end {
+                    bail!("Invalid AQMF partitions");
+                }
+                aqmf.start = partitions.end;
+                Some(partitions)
+            } else {
+                None
+            };
             let key_compression_dictionary = LocationInFile {
                 start: current_offset,
                 end: current_offset + key_compression_dictionary_length,
@@ -188,18 +342,81 @@ impl StaticSortedFile {
 
             Ok(Header {
                 family,
+                compression,
+                encrypted,
                 min_hash,
                 max_hash,
                 aqmf,
+                aqmf_partitions,
                 key_compression_dictionary,
                 value_compression_dictionary,
                 block_offsets_start,
                 blocks_start,
                 block_count,
+                prefix_compressed_keys,
+                counts,
+                commit_sequence_numbers,
             })
         })
     }
 
+    /// Returns the number of entries and tombstones in this file. Files written before version 6
+    /// don't store them.
+    pub fn entry_counts(&self) -> Result<Option<EntryCounts>> {
+        Ok(self.header()?.counts)
+    }
+
+    /// Returns the number of lookups that probed this file since it was opened. Lookups outside of
+    /// the key range of the file are not counted.
+    pub fn reads(&self) -> u64 {
+        self.reads.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Returns the size of this file in bytes.
+    pub fn size(&self) -> u64 {
+        self.mmap.len() as u64
+    }
+
+    /// Returns the time of the last lookup or range scan that read this file, in seconds since the
+    /// UNIX epoch. Files that were not read since they were opened return the time that was
+    /// persisted with [StaticSortedFile::persist_last_read], or the time they were written.
+    pub fn last_read(&self) -> u64 {
+        self.last_read.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Records that this file was read now.
+    pub fn touch(&self) {
+        self.last_read
+            .fetch_max(to_timestamp(SystemTime::now()), atomic::Ordering::Relaxed);
+    }
+
+    /// Stores the time of the last read in the modification time of the file at `path`, so it's
+    /// known when the file is opened again. The content of SST files doesn't change after they
+    /// are committed, so the modification time isn't needed otherwise.
+    pub fn persist_last_read(&self, path: &Path) -> Result<()> {
+        let last_read = self.last_read();
+        if last_read <= self.persisted_last_read.load(atomic::Ordering::Relaxed) {
+            return Ok(());
+        }
+        set_last_read(&OpenOptions::new().write(true).open(path)?, last_read)
+            .with_context(|| format!("Unable to set the access time of {:?}", path))?;
+        self.persisted_last_read
+            .fetch_max(last_read, atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns the lowest and highest sequence number of the SST files in which the entries of this
+    /// file were committed. Files written by a write batch or ingested have their own sequence
+    /// number, files written by compaction have the range of the files they were merged from and
+    /// moved files keep their range. Files written before version 8 are treated as committed with
+    /// their own sequence number.
+    pub fn commit_sequence_numbers(&self) -> Result<(u32, u32)> {
+        Ok(self
+            .header()?
+            .commit_sequence_numbers
+            .unwrap_or((self.sequence_number, self.sequence_number)))
+    }
+
     /// Returns the key family and hash range of this file.
     pub fn range(&self) -> Result<StaticSortedFileRange> {
         let header = self.header()?;
@@ -210,68 +427,339 @@ impl StaticSortedFile {
         })
     }
 
-    /// Iterate over all entries in this file in sorted order.
+    /// Checks that the blocks of this file are within the file and that the file ends after the
+    /// last block. Reading blocks of a truncated file could read out of bounds otherwise, when the
+    /// `strict_checks` feature is disabled.
+    pub fn check_block_layout(&self) -> Result<()> {
+        let header = self.header()?;
+        if header.blocks_start > self.mmap.len() {
+            bail!("Block offsets exceed the file");
+        }
+        let mut offsets = &self.mmap[header.block_offsets_start..header.blocks_start];
+        let mut end = 0;
+        for _ in 0..header.block_count {
+            let offset = offsets.read_u32::<BE>()? as usize;
+            // Every block has a 12 bytes header
+            if offset < end + 12 {
+                bail!("Invalid block offset");
+            }
+            end = offset;
+        }
+        if header.blocks_start + end != self.mmap.len() {
+            bail!("The file size doesn't match the blocks, the file might be truncated");
+        }
+        Ok(())
+    }
+
+    /// Returns the sequence numbers of all blob files referenced by this file. Only index and key
+    /// blocks are read and they are not inserted into the caches.
+    pub fn blob_references(&self) -> Result<Vec<u32>> {
+        let header = self.header()?;
+        let mut blob_files = Vec::new();
+        let mut stack = vec![header.block_count - 1];
+        while let Some(block_index) = stack.pop() {
+            let block = self.read_key_block(header, block_index)?;
+            let mut block = &block[..];
+            match block.read_u8()? {
+                BLOCK_TYPE_INDEX => {
+                    stack.push(block.read_u16::<BE>()?);
+                    for mut entry in block.chunks_exact(10) {
+                        entry.read_u64::<BE>()?;
+                        stack.push(entry.read_u16::<BE>()?);
+                    }
+                }
+                BLOCK_TYPE_KEY => {
+                    let entry_count = block.read_u24::<BE>()? as usize;
+                    let offsets = &block[..entry_count * 4];
+                    let entries = &block[entry_count * 4..];
+                    for index in 0..entry_count {
+                        let entry = get_key_entry(
+                            offsets,
+                            entries,
+                            entry_count,
+                            index,
+                            header.prefix_compressed_keys,
+                        )?;
+                        if entry.ty == KEY_BLOCK_ENTRY_TYPE_BLOB {
+                            blob_files.push((&entry.val[..]).read_u32::<BE>()?);
+                        }
+                    }
+                }
+                _ => {
+                    bail!("Invalid block type");
+                }
+            }
+        }
+        Ok(blob_files)
+    }
+
+    /// Estimates the size of the entries in this file whose key is accepted by `contains`.
+    /// Entries are sorted by key hash, so the entries of a key range are spread over all key
+    /// blocks. Up to `sample_blocks` key blocks, evenly spread over the file, are read and the
+    /// size of the file is scaled by the share of their entries that are accepted. Only index and
+    /// key blocks are read and they are not inserted into the caches.
+    pub fn approximate_size(
+        &self,
+        sample_blocks: usize,
+        contains: impl Fn(&[u8]) -> bool,
+    ) -> Result<u64> {
+        let header = self.header()?;
+        let index = self.key_block_index(header, |block| self.read_key_block(header, block))?;
+        let mut index = &index[..];
+        let mut key_blocks = vec![index.read_u16::<BE>()?];
+        for mut entry in index.chunks_exact(10) {
+            entry.read_u64::<BE>()?;
+            key_blocks.push(entry.read_u16::<BE>()?);
+        }
+        let samples = sample_blocks.clamp(1, key_blocks.len());
+        let mut sampled_entries = 0;
+        let mut accepted_entries = 0;
+        let mut key_buffer = Vec::new();
+        for sample in 0..samples {
+            let block_index = key_blocks[sample * key_blocks.len() / samples];
+            let block = self.read_key_block(header, block_index)?;
+            let mut block = &block[..];
+            if block.read_u8()? != BLOCK_TYPE_KEY {
+                bail!("Invalid block type");
+            }
+            let entry_count = block.read_u24::<BE>()? as usize;
+            let offsets = &block[..entry_count * 4];
+            let entries = &block[entry_count * 4..];
+            for index in 0..entry_count {
+                let entry = get_key_entry(
+                    offsets,
+                    entries,
+                    entry_count,
+                    index,
+                    header.prefix_compressed_keys,
+                )?;
+                let key = if header.prefix_compressed_keys {
+                    apply_shared_prefix(&mut key_buffer, &entry)?;
+                    &key_buffer[..]
+                } else {
+                    entry.key
+                };
+                if contains(key) {
+                    accepted_entries += 1;
+                }
+            }
+            sampled_entries += entry_count;
+        }
+        if sampled_entries == 0 {
+            return Ok(0);
+        }
+        Ok((self.mmap.len() as u128 * accepted_entries as u128 / sampled_entries as u128) as u64)
+    }
+
+    /// Returns the entries of an index block that references all key blocks of this file. The
+    /// partitions of a multi level index are combined into a single index.
+    fn key_block_index(
+        &self,
+        header: &Header,
+        read_block: impl Fn(u16) -> Result<ArcSlice<u8>>,
+    ) -> Result<ArcSlice<u8>> {
+        let root = read_block(header.block_count - 1)?;
+        if root.first() != Some(&BLOCK_TYPE_INDEX) {
+            bail!("Invalid block type");
+        }
+        // All key blocks are on the same level, so the number of index levels is found by
+        // following the first block of every index block
+        let mut levels = 1;
+        let mut block = (&root[1..]).read_u16::<BE>()?;
+        loop {
+            let child = read_block(block)?;
+            let mut child = &child[..];
+            match child.read_u8()? {
+                BLOCK_TYPE_INDEX => {
+                    levels += 1;
+                    block = child.read_u16::<BE>()?;
+                }
+                BLOCK_TYPE_KEY => break,
+                _ => bail!("Invalid block type"),
+            }
+        }
+        if levels == 1 {
+            let range = 1..root.len();
+            return Ok(root.slice(range));
+        }
+        let mut index = Vec::new();
+        self.append_index_entries(&root[1..], None, levels - 1, &read_block, &mut index)?;
+        Ok(ArcSlice::from(index.into_boxed_slice()))
+    }
+
+    /// Appends the entries of the index blocks below an index block to a combined index. The
+    /// first hash is the hash of the index block in its parent, which is omitted for the first
+    /// block of the index.
+    fn append_index_entries(
+        &self,
+        mut entries: &[u8],
+        first_hash: Option<u64>,
+        levels_below: usize,
+        read_block: &impl Fn(u16) -> Result<ArcSlice<u8>>,
+        index: &mut Vec<u8>,
+    ) -> Result<()> {
+        if levels_below == 0 {
+            if let Some(hash) = first_hash {
+                index.write_u64::<BE>(hash)?;
+            }
+            index.extend_from_slice(entries);
+            return Ok(());
+        }
+        let mut hash = first_hash;
+        let mut block = entries.read_u16::<BE>()?;
+        loop {
+            let child = read_block(block)?;
+            let mut child = &child[..];
+            if child.read_u8()? != BLOCK_TYPE_INDEX {
+                bail!("Invalid block type");
+            }
+            self.append_index_entries(child, hash, levels_below - 1, read_block, index)?;
+            if entries.is_empty() {
+                return Ok(());
+            }
+            hash = Some(entries.read_u64::<BE>()?);
+            block = entries.read_u16::<BE>()?;
+        }
+    }
+
+    /// Iterate over all entries in this file in sorted order. The iterator keeps the file alive.
     pub fn iter<'l>(
-        &'l self,
+        self: Arc<Self>,
         key_block_cache: &'l BlockCache,
         value_block_cache: &'l BlockCache,
     ) -> Result<StaticSortedFileIter<'l>> {
+        StaticSortedFileIter::new(self, key_block_cache, value_block_cache)
+    }
+
+    /// Creates a cursor over the keys of this file. The cursor keeps the file alive.
+    pub fn cursor<'l>(
+        self: Arc<Self>,
+        key_block_cache: &'l BlockCache,
+    ) -> Result<StaticSortedFileCursor<'l>> {
+        StaticSortedFileCursor::new(self, key_block_cache)
+    }
+
+    /// Returns false when this file doesn't contain a key with the hash, according to its hash
+    /// range and AQMF filter. Returns true when it might contain one.
+    pub fn may_contain_hash(&self, key_hash: u64, aqmf_cache: &AqmfCache) -> Result<bool> {
         let header = self.header()?;
-        let mut iter = StaticSortedFileIter {
-            this: self,
-            key_block_cache,
-            value_block_cache,
-            header,
-            stack: Vec::new(),
-            current_key_block: None,
+        if key_hash < header.min_hash || key_hash > header.max_hash {
+            return Ok(false);
+        }
+        self.aqmf_contains(header, key_hash, aqmf_cache)
+    }
+
+    /// Returns the index and the location of the AQMF partition that covers a key hash. Files
+    /// with a single filter have one partition.
+    fn aqmf_partition(&self, header: &Header, key_hash: u64) -> Result<(u16, LocationInFile)> {
+        let Some(partitions) = &header.aqmf_partitions else {
+            return Ok((0, header.aqmf));
         };
-        iter.enter_block(header.block_count - 1)?;
-        Ok(iter)
+        let partitions = &self.mmap[partitions.start..partitions.end];
+        fn get_first_hash(partitions: &[u8], index: usize) -> u64 {
+            BE::read_u64(&partitions[index * 12..])
+        }
+        fn get_end(partitions: &[u8], index: usize) -> usize {
+            BE::read_u32(&partitions[index * 12 + 8..]) as usize
+        }
+        // binary search for the last partition whose first hash is not greater than the hash
+        let mut l = 1;
+        let mut r = partitions.len() / 12;
+        while l < r {
+            let m = (l + r) / 2;
+            if get_first_hash(partitions, m) <= key_hash {
+                l = m + 1;
+            } else {
+                r = m;
+            }
+        }
+        let partition = l - 1;
+        let start = if partition == 0 {
+            0
+        } else {
+            get_end(partitions, partition - 1)
+        };
+        let end = get_end(partitions, partition);
+        if start > end || header.aqmf.start + end > header.aqmf.end {
+            bail!("Invalid AQMF partitions");
+        }
+        Ok((
+            partition as u16,
+            LocationInFile {
+                start: header.aqmf.start + start,
+                end: header.aqmf.start + end,
+            },
+        ))
     }
 
-    /// Looks up a key in this file.
-    pub fn lookup<K: QueryKey>(
+    /// Checks the AQMF filter of this file for a key hash. Only the partition that covers the hash
+    /// is read.
+    fn aqmf_contains(
         &self,
-        key_family: u32,
+        header: &Header,
         key_hash: u64,
-        key: &K,
         aqmf_cache: &AqmfCache,
-        key_block_cache: &BlockCache,
-        value_block_cache: &BlockCache,
-    ) -> Result<LookupResult> {
-        let header = self.header()?;
-        if key_family != header.family || key_hash < header.min_hash || key_hash > header.max_hash {
-            return Ok(LookupResult::RangeMiss);
-        }
-
+    ) -> Result<bool> {
+        let (partition, location) = self.aqmf_partition(header, key_hash)?;
         let use_aqmf_cache = header.max_hash - header.min_hash < 1 << 62;
         if use_aqmf_cache {
-            let aqmf = match aqmf_cache.get_value_or_guard(&self.sequence_number, None) {
+            let aqmf = match aqmf_cache.get_value_or_guard(&(self.sequence_number, partition), None)
+            {
                 GuardResult::Value(aqmf) => aqmf,
                 GuardResult::Guard(guard) => {
-                    let aqmf = &self.mmap[header.aqmf.start..header.aqmf.end];
+                    self.report_cache_miss(CacheKind::Aqmf);
+                    let aqmf = &self.mmap[location.start..location.end];
                     let aqmf: Arc<qfilter::Filter> = Arc::new(pot::from_slice(aqmf)?);
                     let _ = guard.insert(aqmf.clone());
                     aqmf
                 }
                 GuardResult::Timeout => unreachable!(),
             };
-            if !aqmf.contains_fingerprint(key_hash) {
-                return Ok(LookupResult::QuickFilterMiss);
-            }
+            Ok(aqmf.contains_fingerprint(key_hash))
         } else {
-            let aqmf = self.aqmf.get_or_try_init(|| {
-                let aqmf = &self.mmap[header.aqmf.start..header.aqmf.end];
+            let partitions = self.aqmf.get_or_init(|| {
+                let partition_count = header
+                    .aqmf_partitions
+                    .map_or(1, |partitions| (partitions.end - partitions.start) / 12);
+                (0..partition_count).map(|_| OnceLock::new()).collect()
+            });
+            let aqmf = partitions[partition as usize].get_or_try_init(|| {
+                let aqmf = &self.mmap[location.start..location.end];
                 anyhow::Ok(pot::from_slice(aqmf)?)
             })?;
-            if !aqmf.contains_fingerprint(key_hash) {
-                return Ok(LookupResult::QuickFilterMiss);
-            }
+            Ok(aqmf.contains_fingerprint(key_hash))
+        }
+    }
+
+    /// Looks up a key in this file.
+    pub fn lookup<K: QueryKey>(
+        &self,
+        key_family: u32,
+        key_hash: u64,
+        key: &K,
+        aqmf_cache: &AqmfCache,
+        key_block_cache: &BlockCache,
+        value_block_cache: &BlockCache,
+    ) -> Result<LookupResult> {
+        let header = self.header()?;
+        if key_family != header.family || key_hash < header.min_hash || key_hash > header.max_hash {
+            return Ok(LookupResult::RangeMiss);
+        }
+        self.reads.fetch_add(1, atomic::Ordering::Relaxed);
+        if !self.aqmf_contains(header, key_hash, aqmf_cache)? {
+            return Ok(LookupResult::QuickFilterMiss);
         }
+        self.touch();
         let mut current_block = header.block_count - 1;
         loop {
-            let block = self.get_key_block(header, current_block, key_block_cache)?;
-            let mut block = &block[..];
+            let cached_block;
+            let mut block = match self.mapped_block(header, current_block)? {
+                Some(block) => block,
+                None => {
+                    cached_block = self.get_key_block(header, current_block, key_block_cache)?;
+                    &cached_block[..]
+                }
+            };
             let block_type = block.read_u8()?;
             match block_type {
                 BLOCK_TYPE_INDEX => {
@@ -346,34 +834,124 @@ impl StaticSortedFile {
         let offsets = &block[..entry_count * 4];
         let entries = &block[entry_count * 4..];
 
+        if header.prefix_compressed_keys {
+            return self.lookup_prefix_compressed_key_block(
+                offsets,
+                entries,
+                entry_count,
+                key_hash,
+                key,
+                header,
+                value_block_cache,
+            );
+        }
+
         let mut l = 0;
         let mut r = entry_count;
         // binary search for the key
         while l < r {
             let m = (l + r) / 2;
-            let GetKeyEntryResult {
-                hash: mid_hash,
-                key: mid_key,
-                ty,
-                val: mid_val,
-            } = get_key_entry(offsets, entries, entry_count, m)?;
-            match key_hash.cmp(&mid_hash).then_with(|| key.cmp(mid_key)) {
+            let entry = get_key_entry(offsets, entries, entry_count, m, false)?;
+            match key_hash.cmp(&entry.hash).then_with(|| key.cmp(entry.key)) {
+                Ordering::Less => {
+                    r = m;
+                }
+                Ordering::Equal => {
+                    return self.handle_key_entry(&entry, header, value_block_cache);
+                }
+                Ordering::Greater => {
+                    l = m + 1;
+                }
+            }
+        }
+        Ok(LookupResult::KeyMiss)
+    }
+
+    /// Looks up a key in a key block with prefix compressed keys. Only the restart points store
+    /// full keys, so they are binary searched first and the interval of the last restart point
+    /// that is not greater than the key is scanned afterwards.
+    fn lookup_prefix_compressed_key_block<K: QueryKey>(
+        &self,
+        offsets: &[u8],
+        entries: &[u8],
+        entry_count: usize,
+        key_hash: u64,
+        key: &K,
+        header: &Header,
+        value_block_cache: &BlockCache,
+    ) -> Result<LookupResult> {
+        let mut l = 0;
+        let mut r = entry_count.div_ceil(KEY_BLOCK_RESTART_INTERVAL);
+        // binary search for the restart point
+        while l < r {
+            let m = (l + r) / 2;
+            let entry = get_key_entry(
+                offsets,
+                entries,
+                entry_count,
+                m * KEY_BLOCK_RESTART_INTERVAL,
+                true,
+            )?;
+            match key_hash.cmp(&entry.hash).then_with(|| key.cmp(entry.key)) {
                 Ordering::Less => {
                     r = m;
                 }
                 Ordering::Equal => {
-                    return Ok(self
-                        .handle_key_match(ty, mid_val, header, value_block_cache)?
-                        .into());
+                    return self.handle_key_entry(&entry, header, value_block_cache);
                 }
                 Ordering::Greater => {
                     l = m + 1;
                 }
             }
         }
+        if l == 0 {
+            return Ok(LookupResult::KeyMiss);
+        }
+        let start = (l - 1) * KEY_BLOCK_RESTART_INTERVAL;
+        let end = (start + KEY_BLOCK_RESTART_INTERVAL).min(entry_count);
+        let mut key_buffer = Vec::new();
+        key_buffer
+            .extend_from_slice(get_key_entry(offsets, entries, entry_count, start, true)?.key);
+        // linear scan of the entries after the restart point
+        for index in start + 1..end {
+            let entry = get_key_entry(offsets, entries, entry_count, index, true)?;
+            apply_shared_prefix(&mut key_buffer, &entry)?;
+            match key_hash.cmp(&entry.hash).then_with(|| key.cmp(&key_buffer)) {
+                Ordering::Less => {
+                    break;
+                }
+                Ordering::Equal => {
+                    return self.handle_key_entry(&entry, header, value_block_cache);
+                }
+                Ordering::Greater => {}
+            }
+        }
         Ok(LookupResult::KeyMiss)
     }
 
+    /// Handles a key entry that matches the looked up key.
+    fn handle_key_entry(
+        &self,
+        entry: &GetKeyEntryResult<'_>,
+        header: &Header,
+        value_block_cache: &BlockCache,
+    ) -> Result<LookupResult> {
+        if is_expired(entry.expires_at) {
+            return Ok(LookupResult::Deleted);
+        }
+        Ok(
+            match self.handle_key_match(entry.ty, entry.val, header, value_block_cache)? {
+                LookupValue::Deleted => LookupResult::Deleted,
+                LookupValue::Slice { value } => LookupResult::Slice {
+                    value,
+                    expires_at: entry.expires_at,
+                },
+                LookupValue::Blob { sequence_number } => LookupResult::Blob { sequence_number },
+                LookupValue::Merge { operand } => LookupResult::Merge { operand },
+            },
+        )
+    }
+
     /// Handles a key match by looking up the value.
     fn handle_key_match(
         &self,
@@ -383,18 +961,34 @@ impl StaticSortedFile {
         value_block_cache: &BlockCache,
     ) -> Result<LookupValue> {
         Ok(match ty {
-            KEY_BLOCK_ENTRY_TYPE_SMALL => {
+            KEY_BLOCK_ENTRY_TYPE_SMALL | KEY_BLOCK_ENTRY_TYPE_MERGE => {
                 let block = val.read_u16::<BE>()?;
                 let size = val.read_u16::<BE>()? as usize;
                 let position = val.read_u32::<BE>()? as usize;
-                let value = self
-                    .get_value_block(header, block, value_block_cache)?
-                    .slice(position..position + size);
-                LookupValue::Slice { value }
+                let range = position..position + size;
+                let value = match self.mapped_block(header, block)? {
+                    // Only the value is copied, not the whole block
+                    Some(block) => ArcSlice::from(Box::from(&block[range])),
+                    None => self
+                        .get_value_block(header, block, value_block_cache)?
+                        .slice(range),
+                };
+                if ty == KEY_BLOCK_ENTRY_TYPE_MERGE {
+                    LookupValue::Merge { operand: value }
+                } else {
+                    LookupValue::Slice { value }
+                }
             }
             KEY_BLOCK_ENTRY_TYPE_MEDIUM => {
                 let block = val.read_u16::<BE>()?;
-                let value = self.read_value_block(header, block)?;
+                let value = match self.mapped_block(header, block)? {
+                    Some(block) => ArcSlice::from(Box::from(block)),
+                    None => {
+                        // Medium values are not cached
+                        self.report_cache_miss(CacheKind::ValueBlock);
+                        self.read_medium_value_block(header, block)?
+                    }
+                };
                 LookupValue::Slice { value }
             }
             KEY_BLOCK_ENTRY_TYPE_BLOB => {
@@ -408,6 +1002,13 @@ impl StaticSortedFile {
         })
     }
 
+    /// Notifies the event listener about a cache miss.
+    fn report_cache_miss(&self, cache: CacheKind) {
+        if let Some(event_listener) = &self.event_listener {
+            event_listener.on_cache_miss(cache);
+        }
+    }
+
     /// Gets a key block from the cache or reads it from the file.
     fn get_key_block(
         &self,
@@ -419,6 +1020,7 @@ impl StaticSortedFile {
             match key_block_cache.get_value_or_guard(&(self.sequence_number, block), None) {
                 GuardResult::Value(block) => block,
                 GuardResult::Guard(guard) => {
+                    self.report_cache_miss(CacheKind::KeyBlock);
                     let block = self.read_key_block(header, block)?;
                     let _ = guard.insert(block.clone());
                     block
@@ -439,6 +1041,7 @@ impl StaticSortedFile {
         {
             GuardResult::Value(block) => block,
             GuardResult::Guard(guard) => {
+                self.report_cache_miss(CacheKind::ValueBlock);
                 let block = self.read_value_block(header, block)?;
                 let _ = guard.insert(block.clone());
                 block
@@ -468,6 +1071,26 @@ impl StaticSortedFile {
         )
     }
 
+    /// Reads a value block of a single medium-sized value from the file. These blocks are
+    /// compressed without the value compression dictionary.
+    fn read_medium_value_block(&self, header: &Header, block_index: u16) -> Result<ArcSlice<u8>> {
+        self.read_block(header, block_index, &[])
+    }
+
+    /// Returns a block directly from the memory mapping, when mapped reads are enabled and the
+    /// block is neither compressed nor encrypted. The checksum is verified on every access, since
+    /// the block isn't cached.
+    fn mapped_block(&self, header: &Header, block_index: u16) -> Result<Option<&[u8]>> {
+        if !self.mapped_reads
+            || header.encrypted
+            || !matches!(header.compression, Compression::None)
+        {
+            return Ok(None);
+        }
+        let block = self.raw_block(header, block_index)?;
+        Ok(Some(block.data))
+    }
+
     /// Reads a block from the file.
     fn read_block(
         &self,
@@ -475,6 +1098,67 @@ impl StaticSortedFile {
         block_index: u16,
         compression_dictionary: &[u8],
     ) -> Result<ArcSlice<u8>> {
+        let RawBlock {
+            data,
+            uncompressed_length,
+        } = self.raw_block(header, block_index)?;
+        let decrypted;
+        let block = if header.encrypted {
+            decrypted = encryption::required(self.encryption.as_ref())?
+                .decrypt(data)
+                .with_context(|| {
+                    format!(
+                        "Unable to decrypt block {} of SST file {:08}.sst",
+                        block_index, self.sequence_number
+                    )
+                })?;
+            &decrypted[..]
+        } else {
+            data
+        };
+
+        let buffer = Arc::new_zeroed_slice(uncompressed_length);
+        // Safety: MaybeUninit<u8> can be safely transmuted to u8.
+        let mut buffer = unsafe { transmute::<Arc<[MaybeUninit<u8>]>, Arc<[u8]>>(buffer) };
+        // Safety: We know that the buffer is not shared yet.
+        let decompressed = unsafe { Arc::get_mut_unchecked(&mut buffer) };
+        header
+            .compression
+            .decompress(block, decompressed, compression_dictionary)?;
+        Ok(ArcSlice::from(buffer))
+    }
+
+    /// Locates a block in the memory mapping and verifies its checksum if enabled.
+    fn raw_block(&self, header: &Header, block_index: u16) -> Result<RawBlock<'_>> {
+        let Range {
+            start: block_start,
+            end: block_end,
+        } = self.block_range(header, block_index)?;
+        let mut block_header = &self.mmap[block_start..block_start + 12];
+        let uncompressed_length = block_header.read_u32::<BE>()? as usize;
+        let expected_checksum = block_header.read_u64::<BE>()?;
+        let data = &self.mmap[block_start + 12..block_end];
+        if self.verify_checksums {
+            let actual_checksum = block_checksum(data);
+            if actual_checksum != expected_checksum {
+                return Err(CorruptionError {
+                    sequence_number: self.sequence_number,
+                    block_index,
+                    expected_checksum,
+                    actual_checksum,
+                }
+                .into());
+            }
+        }
+        Ok(RawBlock {
+            data,
+            uncompressed_length,
+        })
+    }
+
+    /// Returns the location of a block in the memory mapping, including its header. Only the block
+    /// offsets are read, not the block itself.
+    fn block_range(&self, header: &Header, block_index: u16) -> Result<Range<usize>> {
         #[cfg(feature = "strict_checks")]
         if block_index >= header.block_count {
             bail!(
@@ -522,29 +1206,117 @@ impl StaticSortedFile {
                 header.blocks_start
             );
         }
-        let uncompressed_length =
-            (&self.mmap[block_start..block_start + 4]).read_u32::<BE>()? as usize;
-        let block = self.mmap[block_start + 4..block_end].to_vec();
+        Ok(block_start..block_end)
+    }
 
-        let buffer = Arc::new_zeroed_slice(uncompressed_length);
-        // Safety: MaybeUninit<u8> can be safely transmuted to u8.
-        let mut buffer = unsafe { transmute::<Arc<[MaybeUninit<u8>]>, Arc<[u8]>>(buffer) };
-        // Safety: We know that the buffer is not shared yet.
-        let decompressed = unsafe { Arc::get_mut_unchecked(&mut buffer) };
-        decompress_with_dict(&block, decompressed, compression_dictionary)?;
-        Ok(ArcSlice::from(buffer))
+    /// Advises the OS to read the blocks from `first` to `last` into the page cache, so they are
+    /// read with a few large reads instead of a page fault per block.
+    fn will_need(&self, header: &Header, first: u16, last: u16) -> Result<()> {
+        let start = self.block_range(header, first.min(last))?.start;
+        let end = self
+            .block_range(header, first.max(last))?
+            .end
+            .min(self.mmap.len());
+        if start < end {
+            #[cfg(unix)]
+            self.mmap
+                .advise_range(memmap2::Advice::WillNeed, start, end - start)?;
+        }
+        Ok(())
+    }
+
+    /// Decompresses key blocks for an iterator that reads ahead, and advises the OS to read the
+    /// value blocks they reference. The blocks are sent in order. Stops when the iterator is
+    /// dropped.
+    fn prefetch_key_blocks(&self, blocks: &[u16], sender: Sender<(u16, Result<ArcSlice<u8>>)>) {
+        for &block_index in blocks {
+            let block = self.header().and_then(|header| {
+                self.report_cache_miss(CacheKind::KeyBlock);
+                let block = self.read_key_block(header, block_index)?;
+                self.will_need_values(header, &block)?;
+                Ok(block)
+            });
+            if sender.send((block_index, block)).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Advises the OS to read the value blocks that are referenced by a key block.
+    fn will_need_values(&self, header: &Header, mut block: &[u8]) -> Result<()> {
+        if block.read_u8()? != BLOCK_TYPE_KEY {
+            return Ok(());
+        }
+        let entry_count = block.read_u24::<BE>()? as usize;
+        let offsets = &block[..entry_count * 4];
+        let entries = &block[entry_count * 4..];
+        let mut value_blocks: Option<(u16, u16)> = None;
+        for index in 0..entry_count {
+            let entry = get_key_entry(
+                offsets,
+                entries,
+                entry_count,
+                index,
+                header.prefix_compressed_keys,
+            )?;
+            if matches!(
+                entry.ty,
+                KEY_BLOCK_ENTRY_TYPE_SMALL
+                    | KEY_BLOCK_ENTRY_TYPE_MEDIUM
+                    | KEY_BLOCK_ENTRY_TYPE_MERGE
+            ) {
+                let value_block = (&entry.val[..]).read_u16::<BE>()?;
+                value_blocks = Some(match value_blocks {
+                    Some((first, last)) => (first.min(value_block), last.max(value_block)),
+                    None => (value_block, value_block),
+                });
+            }
+        }
+        if let Some((first, last)) = value_blocks {
+            self.will_need(header, first, last)?;
+        }
+        Ok(())
     }
 }
 
+/// A block as stored in the file, before decryption and decompression.
+struct RawBlock<'l> {
+    /// The stored bytes of the block.
+    data: &'l [u8],
+    /// The length of the block after decompression.
+    uncompressed_length: usize,
+}
+
 /// An iterator over all entries in a SST file in sorted order.
 pub struct StaticSortedFileIter<'l> {
-    this: &'l StaticSortedFile,
+    this: Arc<StaticSortedFile>,
     key_block_cache: &'l BlockCache,
     value_block_cache: &'l BlockCache,
-    header: &'l Header,
+    /// Entries within these ranges are skipped.
+    deleted_ranges: Vec<RangeTombstone>,
 
     stack: Vec<CurrentIndexBlock>,
     current_key_block: Option<CurrentKeyBlock>,
+    /// The reconstructed key of a prefix compressed key block.
+    key_buffer: Vec<u8>,
+    /// The index of the entry in the current key block whose key is in `key_buffer`.
+    key_buffer_index: Option<usize>,
+    /// The number of key blocks the iterator entered.
+    key_blocks_entered: usize,
+    /// Reads upcoming key blocks ahead, when enabled.
+    readahead: Option<Readahead>,
+}
+
+/// The readahead of a [StaticSortedFileIter]. Once the iterator entered a few key blocks, the
+/// following key blocks of the current index block are read into the page cache with a single
+/// advice and decompressed in the background, together with an advice for the value blocks they
+/// reference. A full scan then doesn't wait for many small synchronous reads.
+struct Readahead {
+    /// The thread pool that decompresses the blocks. Without one the global rayon pool is used.
+    thread_pool: Option<Arc<ThreadPool>>,
+    /// The key blocks that are decompressed in the background, in iteration order, and the
+    /// receiver for them.
+    pending: Option<(VecDeque<u16>, Receiver<(u16, Result<ArcSlice<u8>>)>)>,
 }
 
 struct CurrentKeyBlock {
@@ -564,16 +1336,78 @@ impl Iterator for StaticSortedFileIter<'_> {
     type Item = Result<LookupEntry>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.next_internal().transpose()
+        loop {
+            let entry = self.next_internal().transpose()?;
+            if let Ok(entry) = &entry {
+                let key = &*entry.key;
+                if self.deleted_ranges.iter().any(|range| range.contains(&key)) {
+                    continue;
+                }
+            }
+            return Some(entry);
+        }
     }
 }
 
-impl StaticSortedFileIter<'_> {
+impl<'l> StaticSortedFileIter<'l> {
+    fn new(
+        this: Arc<StaticSortedFile>,
+        key_block_cache: &'l BlockCache,
+        value_block_cache: &'l BlockCache,
+    ) -> Result<Self> {
+        let root_block = this.header()?.block_count - 1;
+        let mut iter = StaticSortedFileIter {
+            this,
+            key_block_cache,
+            value_block_cache,
+            deleted_ranges: Vec::new(),
+            stack: Vec::new(),
+            current_key_block: None,
+            key_buffer: Vec::new(),
+            key_buffer_index: None,
+            key_blocks_entered: 0,
+            readahead: None,
+        };
+        iter.enter_block(root_block)?;
+        Ok(iter)
+    }
+
+    /// Reads upcoming key blocks ahead once the iterator reads key blocks in sequence. See
+    /// [Readahead].
+    pub fn with_readahead(mut self, thread_pool: Option<Arc<ThreadPool>>) -> Self {
+        self.readahead = Some(Readahead {
+            thread_pool,
+            pending: None,
+        });
+        self
+    }
+
+    /// Skips all entries that are deleted by the range tombstones that apply to this file.
+    pub fn skip_deleted_ranges(mut self, range_tombstones: &[RangeTombstone]) -> Result<Self> {
+        let family = self.this.header()?.family;
+        let seq = self.this.sequence_number();
+        self.deleted_ranges = range_tombstones
+            .iter()
+            .filter(|tombstone| tombstone.applies_to(family, seq))
+            .cloned()
+            .collect();
+        Ok(self)
+    }
+
+    /// Returns the index that follows `index`, or `None` when the end of the block is reached.
+    fn next_index(&self, index: usize, count: usize) -> Option<usize> {
+        (index + 1 < count).then_some(index + 1)
+    }
+
     /// Enters a block at the given index.
     fn enter_block(&mut self, block_index: u16) -> Result<()> {
-        let block_arc = self
-            .this
-            .get_key_block(self.header, block_index, self.key_block_cache)?;
+        let block_arc = match self.take_read_ahead(block_index)? {
+            Some(block) => block,
+            None => {
+                self.this
+                    .get_key_block(self.this.header()?, block_index, self.key_block_cache)?
+            }
+        };
         let mut block = &*block_arc;
         let block_type = block.read_u8()?;
         match block_type {
@@ -598,6 +1432,8 @@ impl StaticSortedFileIter<'_> {
                     entry_count,
                     index: 0,
                 });
+                self.key_buffer_index = None;
+                self.key_blocks_entered += 1;
             }
             _ => {
                 bail!("Invalid block type");
@@ -606,6 +1442,89 @@ impl StaticSortedFileIter<'_> {
         Ok(())
     }
 
+    /// Returns a block that was read ahead, when it's the next one and was decompressed already.
+    /// It doesn't wait for the background job, as it might be queued behind work of the thread
+    /// that is waiting. When the iterator leaves the blocks that are read ahead, the readahead is
+    /// dropped, which stops it.
+    fn take_read_ahead(&mut self, block_index: u16) -> Result<Option<ArcSlice<u8>>> {
+        let Some(readahead) = &mut self.readahead else {
+            return Ok(None);
+        };
+        let Some((blocks, receiver)) = &mut readahead.pending else {
+            return Ok(None);
+        };
+        if blocks.pop_front() != Some(block_index) {
+            readahead.pending = None;
+            return Ok(None);
+        }
+        // Blocks are sent in order, so other blocks are earlier ones that were read without
+        // waiting for them
+        let block = loop {
+            match receiver.try_recv() {
+                Ok((index, block)) if index == block_index => break Some(block),
+                Ok(_) => {}
+                Err(_) => break None,
+            }
+        };
+        if blocks.is_empty() {
+            readahead.pending = None;
+        }
+        let Some(block) = block.transpose()? else {
+            return Ok(None);
+        };
+        self.key_block_cache
+            .insert((self.this.sequence_number(), block_index), block.clone());
+        Ok(Some(block))
+    }
+
+    /// Starts to read the key blocks of an index block ahead, beginning at `index`. Nothing is
+    /// read ahead until the iterator entered a few key blocks, or while the previous readahead is
+    /// not consumed. It stops at the first block that is cached already.
+    fn read_ahead(
+        &mut self,
+        entries: &[u8],
+        block_indicies_count: usize,
+        mut index: Option<usize>,
+    ) -> Result<()> {
+        let idle = self
+            .readahead
+            .as_ref()
+            .is_some_and(|readahead| readahead.pending.is_none());
+        if !idle || self.key_blocks_entered < READAHEAD_TRIGGER_KEY_BLOCKS {
+            return Ok(());
+        }
+        let mut blocks = VecDeque::new();
+        while let Some(i) = index {
+            let block = (&entries[i * 10..]).read_u16::<BE>()?;
+            if blocks.len() >= READAHEAD_KEY_BLOCKS
+                || self
+                    .key_block_cache
+                    .contains_key(&(self.this.sequence_number(), block))
+            {
+                break;
+            }
+            blocks.push_back(block);
+            index = self.next_index(i, block_indicies_count);
+        }
+        let (Some(&first), Some(&last)) = (blocks.front(), blocks.back()) else {
+            return Ok(());
+        };
+        self.this.will_need(self.this.header()?, first, last)?;
+        let (sender, receiver) = mpsc::channel();
+        let this = self.this.clone();
+        let job_blocks = blocks.iter().copied().collect::<Vec<_>>();
+        let job = move || this.prefetch_key_blocks(&job_blocks, sender);
+        let Some(readahead) = &mut self.readahead else {
+            return Ok(());
+        };
+        match &readahead.thread_pool {
+            Some(thread_pool) => thread_pool.spawn(job),
+            None => rayon::spawn(job),
+        }
+        readahead.pending = Some((blocks, receiver));
+        Ok(())
+    }
+
     /// Gets the next entry in the file and moves the cursor.
     fn next_internal(&mut self) -> Result<Option<LookupEntry>> {
         loop {
@@ -616,23 +1535,55 @@ impl StaticSortedFileIter<'_> {
                 index,
             }) = self.current_key_block.take()
             {
-                let GetKeyEntryResult { hash, key, ty, val } =
-                    get_key_entry(&offsets, &entries, entry_count, index)?;
-                let value =
-                    self.this
-                        .handle_key_match(ty, val, self.header, self.value_block_cache)?;
+                let header = self.this.header()?;
+                let prefix_compressed = header.prefix_compressed_keys;
+                let entry =
+                    get_key_entry(&offsets, &entries, entry_count, index, prefix_compressed)?;
+                if prefix_compressed {
+                    reconstruct_key(
+                        &offsets,
+                        &entries,
+                        entry_count,
+                        index,
+                        &mut self.key_buffer,
+                        self.key_buffer_index,
+                    )?;
+                    self.key_buffer_index = Some(index);
+                }
+                let key = if entry.shared_prefix.is_some() {
+                    ArcSlice::from(Box::from(&self.key_buffer[..]))
+                } else {
+                    // Safety: The key is a valid slice of the entries.
+                    unsafe { ArcSlice::new_unchecked(entry.key, ArcSlice::full_arc(&entries)) }
+                };
+                let GetKeyEntryResult {
+                    hash,
+                    ty,
+                    val,
+                    expires_at,
+                    ..
+                } = entry;
+                // Expired entries are yielded as tombstones, so they still hide older values
+                let (value, expires_at) = if is_expired(expires_at) {
+                    (LookupValue::Deleted, None)
+                } else {
+                    let value =
+                        self.this
+                            .handle_key_match(ty, val, header, self.value_block_cache)?;
+                    (value, expires_at)
+                };
                 let entry = LookupEntry {
                     hash,
-                    // Safety: The key is a valid slice of the entries.
-                    key: unsafe { ArcSlice::new_unchecked(key, ArcSlice::full_arc(&entries)) },
+                    key,
                     value,
+                    expires_at,
                 };
-                if index + 1 < entry_count {
+                if let Some(index) = self.next_index(index, entry_count) {
                     self.current_key_block = Some(CurrentKeyBlock {
                         offsets,
                         entries,
                         entry_count,
-                        index: index + 1,
+                        index,
                     });
                 }
                 return Ok(Some(entry));
@@ -644,14 +1595,19 @@ impl StaticSortedFileIter<'_> {
             }) = self.stack.pop()
             {
                 let block_index = (&entries[index * 10..]).read_u16::<BE>()?;
-                if index + 1 < block_indicies_count {
+                let next_index = self.next_index(index, block_indicies_count);
+                if let Some(index) = next_index {
                     self.stack.push(CurrentIndexBlock {
-                        entries,
+                        entries: entries.clone(),
                         block_indicies_count,
-                        index: index + 1,
+                        index,
                     });
                 }
                 self.enter_block(block_index)?;
+                if self.current_key_block.is_some() {
+                    // The index block points to key blocks, which are read in sequence
+                    self.read_ahead(&entries, block_indicies_count, next_index)?;
+                }
             } else {
                 return Ok(None);
             }
@@ -659,19 +1615,288 @@ impl StaticSortedFileIter<'_> {
     }
 }
 
+/// A cursor over the keys of an SST file, which can be moved in both directions and positioned at
+/// a key with a binary search. Only keys are read, values are looked up separately.
+pub struct StaticSortedFileCursor<'l> {
+    this: Arc<StaticSortedFile>,
+    key_block_cache: &'l BlockCache,
+    /// The entries of the index, which references all key blocks. The partitions of a multi level
+    /// index are combined into a single index.
+    index: ArcSlice<u8>,
+    /// The number of key blocks.
+    key_block_count: usize,
+    /// The current key block and its position in the index block. `None` when the cursor is
+    /// before the first or after the last entry.
+    current_key_block: Option<(usize, CurrentKeyBlock)>,
+    /// The hash of the current entry.
+    hash: u64,
+    /// The key of the current entry.
+    key_buffer: Vec<u8>,
+    /// The index of the entry in the current key block whose key is in `key_buffer`.
+    key_buffer_index: Option<usize>,
+}
+
+impl<'l> StaticSortedFileCursor<'l> {
+    fn new(this: Arc<StaticSortedFile>, key_block_cache: &'l BlockCache) -> Result<Self> {
+        let header = this.header()?;
+        let index = this.key_block_index(header, |block| {
+            this.get_key_block(header, block, key_block_cache)
+        })?;
+        let key_block_count = (index.len() + 8) / 10;
+        Ok(Self {
+            this,
+            key_block_cache,
+            index,
+            key_block_count,
+            current_key_block: None,
+            hash: 0,
+            key_buffer: Vec::new(),
+            key_buffer_index: None,
+        })
+    }
+
+    /// Returns the hash and key of the current entry, or `None` when the cursor is before the
+    /// first or after the last entry.
+    pub fn current(&self) -> Option<(u64, &[u8])> {
+        self.current_key_block
+            .as_ref()
+            .map(|_| (self.hash, &self.key_buffer[..]))
+    }
+
+    /// Moves the cursor out of the file, so it has no current entry.
+    pub fn reset(&mut self) {
+        self.current_key_block = None;
+    }
+
+    /// Moves to the first entry.
+    pub fn seek_to_first(&mut self) -> Result<()> {
+        self.enter_key_block(0, false)
+    }
+
+    /// Moves to the last entry.
+    pub fn seek_to_last(&mut self) -> Result<()> {
+        self.enter_key_block(self.key_block_count - 1, true)
+    }
+
+    /// Moves to the first entry that is not less than the hash and key. Moves after the last entry
+    /// when there is none.
+    pub fn seek(&mut self, hash: u64, key: &[u8]) -> Result<()> {
+        // binary search for the last key block whose first hash is not greater than the hash
+        let mut l = 1;
+        let mut r = self.key_block_count;
+        while l < r {
+            let m = (l + r) / 2;
+            let first_hash = (&self.index[(m - 1) * 10 + 2..]).read_u64::<BE>()?;
+            if first_hash <= hash {
+                l = m + 1;
+            } else {
+                r = m;
+            }
+        }
+        self.enter_key_block(l - 1, false)?;
+
+        // binary search for the last restart point that is less than the key, only restart points
+        // store full keys
+        let prefix_compressed = self.this.header()?.prefix_compressed_keys;
+        let step = if prefix_compressed {
+            KEY_BLOCK_RESTART_INTERVAL
+        } else {
+            1
+        };
+        let Some((_, block)) = &self.current_key_block else {
+            unreachable!("the key block was entered");
+        };
+        let entry_count = block.entry_count;
+        let mut l = 0;
+        let mut r = entry_count.div_ceil(step);
+        while l < r {
+            let m = (l + r) / 2;
+            let entry = get_key_entry(
+                &block.offsets,
+                &block.entries,
+                entry_count,
+                m * step,
+                prefix_compressed,
+            )?;
+            if (entry.hash, entry.key) < (hash, key) {
+                l = m + 1;
+            } else {
+                r = m;
+            }
+        }
+        // linear scan of the entries after the restart point
+        for index in l.saturating_sub(1) * step..entry_count {
+            self.load_entry(index)?;
+            if (self.hash, &self.key_buffer[..]) >= (hash, key) {
+                return Ok(());
+            }
+        }
+        self.next_key_block()
+    }
+
+    /// Moves to the next entry. Moves after the last entry at the end of the file.
+    pub fn next_entry(&mut self) -> Result<()> {
+        let Some((_, block)) = &self.current_key_block else {
+            return Ok(());
+        };
+        let index = block.index + 1;
+        if index < block.entry_count {
+            self.load_entry(index)
+        } else {
+            self.next_key_block()
+        }
+    }
+
+    /// Moves to the previous entry. Moves before the first entry at the start of the file.
+    pub fn prev_entry(&mut self) -> Result<()> {
+        let Some((position, block)) = &self.current_key_block else {
+            return Ok(());
+        };
+        let (position, index) = (*position, block.index);
+        if index > 0 {
+            self.load_entry(index - 1)
+        } else if position > 0 {
+            self.enter_key_block(position - 1, true)
+        } else {
+            self.current_key_block = None;
+            Ok(())
+        }
+    }
+
+    /// Moves to the first entry of the next key block, or after the last entry.
+    fn next_key_block(&mut self) -> Result<()> {
+        match self
+            .current_key_block
+            .as_ref()
+            .map(|(position, _)| position + 1)
+        {
+            Some(position) if position < self.key_block_count => {
+                self.enter_key_block(position, false)
+            }
+            _ => {
+                self.current_key_block = None;
+                Ok(())
+            }
+        }
+    }
+
+    /// Enters the key block at the position in the index block and moves to its first or last
+    /// entry.
+    fn enter_key_block(&mut self, position: usize, last: bool) -> Result<()> {
+        let block_index = (&self.index[position * 10..]).read_u16::<BE>()?;
+        let block_arc =
+            self.this
+                .get_key_block(self.this.header()?, block_index, self.key_block_cache)?;
+        let mut block = &*block_arc;
+        if block.read_u8()? != BLOCK_TYPE_KEY {
+            bail!("Invalid block type");
+        }
+        let entry_count = block.read_u24::<BE>()? as usize;
+        if entry_count == 0 {
+            bail!("Empty key block");
+        }
+        let offsets_range = 4..4 + entry_count * 4;
+        let entries_range = 4 + entry_count * 4..block_arc.len();
+        let offsets = block_arc.clone().slice(offsets_range);
+        let entries = block_arc.slice(entries_range);
+        self.current_key_block = Some((
+            position,
+            CurrentKeyBlock {
+                offsets,
+                entries,
+                entry_count,
+                index: 0,
+            },
+        ));
+        self.key_buffer_index = None;
+        self.load_entry(if last { entry_count - 1 } else { 0 })
+    }
+
+    /// Moves to an entry of the current key block and reads its hash and key.
+    fn load_entry(&mut self, index: usize) -> Result<()> {
+        let prefix_compressed = self.this.header()?.prefix_compressed_keys;
+        let Some((_, block)) = &mut self.current_key_block else {
+            unreachable!("a key block is entered");
+        };
+        block.index = index;
+        let entry = get_key_entry(
+            &block.offsets,
+            &block.entries,
+            block.entry_count,
+            index,
+            prefix_compressed,
+        )?;
+        self.hash = entry.hash;
+        if prefix_compressed {
+            reconstruct_key(
+                &block.offsets,
+                &block.entries,
+                block.entry_count,
+                index,
+                &mut self.key_buffer,
+                self.key_buffer_index,
+            )?;
+            self.key_buffer_index = Some(index);
+        } else {
+            self.key_buffer.clear();
+            self.key_buffer.extend_from_slice(entry.key);
+        }
+        Ok(())
+    }
+}
+
 struct GetKeyEntryResult<'l> {
     hash: u64,
+    /// The key, or only the suffix of the key when `shared_prefix` is set.
     key: &'l [u8],
+    /// The length of the prefix the key shares with the key of the previous entry. Only set for
+    /// prefix compressed entries that are not restart points.
+    shared_prefix: Option<usize>,
+    /// The entry type. Expiring entry types are reported as the non-expiring type.
     ty: u8,
     val: &'l [u8],
+    expires_at: Option<u64>,
 }
 
-/// Reads a key entry from a key block.
+/// Marks the entries of an SST file as committed in the file with the sequence number, when an
+/// external file is ingested. Files written before version 8 are left unchanged, they are treated
+/// as committed with their own sequence number anyway.
+pub fn set_commit_sequence_number(path: &Path, sequence_number: u32) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    if file.read_u32::<BE>()? != SST_MAGIC {
+        return Ok(());
+    }
+    file.seek(SeekFrom::Start(COMMIT_SEQUENCE_NUMBERS_OFFSET))?;
+    file.write_u32::<BE>(sequence_number)?;
+    file.write_u32::<BE>(sequence_number)?;
+    Ok(())
+}
+
+/// Sets the time of the last read of a SST file, in seconds since the UNIX epoch, as its
+/// modification time. Compaction gives new files the time of the files they were merged from.
+pub fn set_last_read(file: &File, last_read: u64) -> Result<()> {
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(last_read))?;
+    Ok(())
+}
+
+/// Returns the entry type that stores a value of the given type with expiration timestamp.
+pub fn expiring_entry_type(ty: u8) -> u8 {
+    match ty {
+        KEY_BLOCK_ENTRY_TYPE_SMALL => KEY_BLOCK_ENTRY_TYPE_SMALL_EXPIRING,
+        KEY_BLOCK_ENTRY_TYPE_BLOB => KEY_BLOCK_ENTRY_TYPE_BLOB_EXPIRING,
+        KEY_BLOCK_ENTRY_TYPE_MEDIUM => KEY_BLOCK_ENTRY_TYPE_MEDIUM_EXPIRING,
+        _ => panic!("Entry type {ty} can't expire"),
+    }
+}
+
+/// Reads a key entry from a key block. With `prefix_compressed`, the key of an entry that is not a
+/// restart point only contains the suffix after the shared prefix.
 fn get_key_entry<'l>(
     offsets: &[u8],
     entries: &'l [u8],
     entry_count: usize,
     index: usize,
+    prefix_compressed: bool,
 ) -> Result<GetKeyEntryResult<'l>> {
     let mut offset = &offsets[index * 4..];
     let ty = offset.read_u8()?;
@@ -682,33 +1907,97 @@ fn get_key_entry<'l>(
         (&offsets[(index + 1) * 4 + 1..]).read_u24::<BE>()? as usize
     };
     let hash = (&entries[start..start + 8]).read_u64::<BE>()?;
+    // The shared prefix length is stored after the hash, so the key starts 2 bytes later
+    let (shared_prefix, start) = if prefix_compressed && index % KEY_BLOCK_RESTART_INTERVAL != 0 {
+        let shared_prefix = (&entries[start + 8..start + 10]).read_u16::<BE>()? as usize;
+        (Some(shared_prefix), start + 2)
+    } else {
+        (None, start)
+    };
+    // The expiration timestamp is stored after the value reference
+    let (ty, end, expires_at) = match ty {
+        KEY_BLOCK_ENTRY_TYPE_SMALL_EXPIRING
+        | KEY_BLOCK_ENTRY_TYPE_BLOB_EXPIRING
+        | KEY_BLOCK_ENTRY_TYPE_MEDIUM_EXPIRING => {
+            let expires_at = (&entries[end - 8..end]).read_u64::<BE>()?;
+            let ty = match ty {
+                KEY_BLOCK_ENTRY_TYPE_SMALL_EXPIRING => KEY_BLOCK_ENTRY_TYPE_SMALL,
+                KEY_BLOCK_ENTRY_TYPE_BLOB_EXPIRING => KEY_BLOCK_ENTRY_TYPE_BLOB,
+                _ => KEY_BLOCK_ENTRY_TYPE_MEDIUM,
+            };
+            (ty, end - 8, Some(expires_at))
+        }
+        _ => (ty, end, None),
+    };
     Ok(match ty {
-        KEY_BLOCK_ENTRY_TYPE_SMALL => GetKeyEntryResult {
+        KEY_BLOCK_ENTRY_TYPE_SMALL | KEY_BLOCK_ENTRY_TYPE_MERGE => GetKeyEntryResult {
             hash,
             key: &entries[start + 8..end - 8],
+            shared_prefix,
             ty,
             val: &entries[end - 8..end],
+            expires_at,
         },
         KEY_BLOCK_ENTRY_TYPE_MEDIUM => GetKeyEntryResult {
             hash,
             key: &entries[start + 8..end - 2],
+            shared_prefix,
             ty,
             val: &entries[end - 2..end],
+            expires_at,
         },
         KEY_BLOCK_ENTRY_TYPE_BLOB => GetKeyEntryResult {
             hash,
             key: &entries[start + 8..end - 4],
+            shared_prefix,
             ty,
             val: &entries[end - 4..end],
+            expires_at,
         },
         KEY_BLOCK_ENTRY_TYPE_DELETED => GetKeyEntryResult {
             hash,
             key: &entries[start + 8..end],
+            shared_prefix,
             ty,
             val: &[],
+            expires_at,
         },
         _ => {
             bail!("Invalid key block entry type");
         }
     })
 }
+
+/// Applies the shared prefix and the key suffix of a prefix compressed entry to the key of the
+/// previous entry in `key_buffer`.
+fn apply_shared_prefix(key_buffer: &mut Vec<u8>, entry: &GetKeyEntryResult<'_>) -> Result<()> {
+    let shared_prefix = entry.shared_prefix.unwrap_or(0);
+    if shared_prefix > key_buffer.len() {
+        bail!("Invalid shared prefix length in key block");
+    }
+    key_buffer.truncate(shared_prefix);
+    key_buffer.extend_from_slice(entry.key);
+    Ok(())
+}
+
+/// Reconstructs the full key of an entry in a prefix compressed key block into `key_buffer`. When
+/// `key_buffer` contains the key of the previous entry, only this entry is applied, otherwise the
+/// key is rebuilt from the last restart point.
+fn reconstruct_key(
+    offsets: &[u8],
+    entries: &[u8],
+    entry_count: usize,
+    index: usize,
+    key_buffer: &mut Vec<u8>,
+    key_buffer_index: Option<usize>,
+) -> Result<()> {
+    let first = match key_buffer_index {
+        Some(buffered) if buffered + 1 == index => index,
+        _ => index - index % KEY_BLOCK_RESTART_INTERVAL,
+    };
+    for index in first..=index {
+        let entry = get_key_entry(offsets, entries, entry_count, index, true)?;
+        apply_shared_prefix(key_buffer, &entry)?;
+    }
+    Ok(())
+}
@@ -1,17 +1,25 @@
 use std::{
     cmp::min,
     fs::File,
-    io::{self, BufWriter, Write},
+    io::{self, Write},
+    mem::swap,
     path::Path,
 };
 
 use anyhow::{Context, Result};
 use byteorder::{ByteOrder, WriteBytesExt, BE};
-use lzzzz::lz4::{max_compressed_size, ACC_LEVEL_DEFAULT};
 
-use crate::static_sorted_file::{
-    BLOCK_TYPE_INDEX, BLOCK_TYPE_KEY, KEY_BLOCK_ENTRY_TYPE_BLOB, KEY_BLOCK_ENTRY_TYPE_DELETED,
-    KEY_BLOCK_ENTRY_TYPE_MEDIUM, KEY_BLOCK_ENTRY_TYPE_SMALL,
+use crate::{
+    checksum::block_checksum,
+    compression::Compression,
+    encryption::{Encryption, ENCRYPTED_FLAG},
+    file_io::FileWriter,
+    rate_limiter::RateLimiter,
+    static_sorted_file::{
+        expiring_entry_type, BLOCK_TYPE_INDEX, BLOCK_TYPE_KEY, KEY_BLOCK_ENTRY_TYPE_BLOB,
+        KEY_BLOCK_ENTRY_TYPE_DELETED, KEY_BLOCK_ENTRY_TYPE_MEDIUM, KEY_BLOCK_ENTRY_TYPE_MERGE,
+        KEY_BLOCK_ENTRY_TYPE_SMALL, KEY_BLOCK_RESTART_INTERVAL, SST_MAGIC,
+    },
 };
 
 /// The maximum number of entries that should go into a single key block
@@ -20,13 +28,19 @@ const MAX_KEY_BLOCK_ENTRIES: usize = 100 * 1024;
 // Note this must fit into 3 bytes length
 const MAX_KEY_BLOCK_SIZE: usize = 16 * 1024;
 /// Overhead of bytes that should be counted for entries in a key block in addition to the key size
-const KEY_BLOCK_ENTRY_META_OVERHEAD: usize = 8;
+const KEY_BLOCK_ENTRY_META_OVERHEAD: usize = 10;
 /// The maximum number of entries that should go into a single small value block
 const MAX_SMALL_VALUE_BLOCK_ENTRIES: usize = 100 * 1024;
 /// The maximum bytes that should go into a single small value block
 const MAX_SMALL_VALUE_BLOCK_SIZE: usize = 16 * 1024;
 /// The aimed false positive rate for the AQMF
 const AQMF_FALSE_POSITIVE_RATE: f64 = 0.01;
+/// The maximum number of entries per AQMF partition. The AQMF of larger files is split by key hash,
+/// so a lookup only decodes the partition that covers its hash.
+const AQMF_PARTITION_ENTRIES: usize = 32 * 1024;
+/// The maximum number of blocks an index block points to. Files with more key blocks get a multi
+/// level index, so a lookup only reads the index partition that covers its hash.
+const INDEX_PARTITION_BLOCKS: usize = 1024;
 
 /// The maximum compression dictionay size for value blocks
 const VALUE_COMPRESSION_DICTIONARY_SIZE: usize = 64 * 1024 - 1;
@@ -56,6 +70,9 @@ pub trait Entry {
 
     /// Returns the value
     fn value(&self) -> EntryValue<'_>;
+
+    /// Returns the expiration timestamp of the value in seconds since the UNIX epoch, if any.
+    fn expires_at(&self) -> Option<u64>;
 }
 
 /// Reference to a value
@@ -69,22 +86,35 @@ pub enum EntryValue<'l> {
     Large { blob: u32 },
     /// Tombstone. The value was removed.
     Deleted,
+    /// Merge operand. They are stored in shared value blocks like small-sized values.
+    Merge { value: &'l [u8] },
 }
 
 #[derive(Debug, Default)]
 pub struct StaticSortedFileBuilder {
     family: u32,
+    compression: Compression,
+    encryption: Option<Encryption>,
     aqmf: Vec<u8>,
     key_compression_dictionary: Vec<u8>,
     value_compression_dictionary: Vec<u8>,
     blocks: Vec<(u32, Vec<u8>)>,
     min_hash: u64,
     max_hash: u64,
+    entry_count: u32,
+    tombstone_count: u32,
+    commit_sequence_numbers: (u32, u32),
 }
 
 impl StaticSortedFileBuilder {
+    /// Creates a builder for a SST file with the entries. `commit_sequence_numbers` are the lowest
+    /// and highest sequence number of the SST files in which the entries were committed, which is
+    /// the sequence number of the new file itself unless it's written by compaction.
     pub fn new<E: Entry>(
         family: u32,
+        compression: Compression,
+        encryption: Option<Encryption>,
+        commit_sequence_numbers: (u32, u32),
         entries: &[E],
         total_key_size: usize,
         total_value_size: usize,
@@ -92,8 +122,18 @@ impl StaticSortedFileBuilder {
         debug_assert!(entries.iter().map(|e| e.key_hash()).is_sorted());
         let mut builder = Self {
             family,
+            compression,
+            encryption,
+            commit_sequence_numbers,
             min_hash: entries.first().map(|e| e.key_hash()).unwrap_or(u64::MAX),
             max_hash: entries.last().map(|e| e.key_hash()).unwrap_or(0),
+            entry_count: entries.len().try_into().unwrap(),
+            tombstone_count: entries
+                .iter()
+                .filter(|e| matches!(e.value(), EntryValue::Deleted))
+                .count()
+                .try_into()
+                .unwrap(),
             ..Default::default()
         };
         builder.compute_aqmf(entries);
@@ -102,27 +142,79 @@ impl StaticSortedFileBuilder {
         Ok(builder)
     }
 
-    /// Computes a AQMF from the keys of all entries.
+    /// The number of tombstones in the file.
+    pub fn tombstones(&self) -> usize {
+        self.tombstone_count as usize
+    }
+
+    /// Computes a partitioned AQMF from the keys of all entries. Every partition covers a range of
+    /// key hashes and is preceded by a table of the first hash and end offset of each partition.
     fn compute_aqmf<E: Entry>(&mut self, entries: &[E]) {
-        let mut filter = qfilter::Filter::new(entries.len() as u64, AQMF_FALSE_POSITIVE_RATE)
-            // This won't fail as we limit the number of entries per SST file
-            .expect("Filter can't be constructed");
-        for entry in entries {
-            filter
-                .insert_fingerprint(false, entry.key_hash())
-                // This can't fail as we allocated enough capacity
-                .expect("AQMF insert failed");
+        let mut partitions = Vec::new();
+        let mut start = 0;
+        while start < entries.len() {
+            let mut end = min(start + AQMF_PARTITION_ENTRIES, entries.len());
+            // avoid splitting the entries of a hash conflict
+            while end < entries.len() && entries[end - 1].key_hash() == entries[end].key_hash() {
+                end += 1;
+            }
+            let partition_entries = &entries[start..end];
+            let mut filter =
+                qfilter::Filter::new(partition_entries.len() as u64, AQMF_FALSE_POSITIVE_RATE)
+                    // This won't fail as we limit the number of entries per SST file
+                    .expect("Filter can't be constructed");
+            for entry in partition_entries {
+                filter
+                    .insert_fingerprint(false, entry.key_hash())
+                    // This can't fail as we allocated enough capacity
+                    .expect("AQMF insert failed");
+            }
+            let filter = pot::to_vec(&filter).expect("AQMF serialization failed");
+            partitions.push((entries[start].key_hash(), filter));
+            start = end;
         }
-        self.aqmf = pot::to_vec(&filter).expect("AQMF serialization failed");
+
+        let filters_size = partitions
+            .iter()
+            .map(|(_, filter)| filter.len())
+            .sum::<usize>();
+        let mut aqmf = Vec::with_capacity(2 + partitions.len() * 12 + filters_size);
+        aqmf.write_u16::<BE>(partitions.len().try_into().unwrap())
+            .unwrap();
+        let mut end = 0;
+        for (first_hash, filter) in &partitions {
+            end += filter.len();
+            aqmf.write_u64::<BE>(*first_hash).unwrap();
+            aqmf.write_u32::<BE>(end.try_into().unwrap()).unwrap();
+        }
+        for (_, filter) in &partitions {
+            aqmf.extend_from_slice(filter);
+        }
+        self.aqmf = aqmf;
     }
 
-    /// Computes compression dictionaries from keys and values of all entries
+    /// Computes compression dictionaries from keys and small values of all entries. Small values
+    /// are often structurally similar and too short to compress well on their own, so the value
+    /// dictionary is trained from them only. Encrypted files don't use dictionaries, as they would
+    /// be stored unencrypted and contain parts of the keys and values.
     fn compute_compression_dictionary<E: Entry>(
         &mut self,
         entries: &[E],
         total_key_size: usize,
         total_value_size: usize,
     ) -> Result<()> {
+        if !self.compression.uses_dictionary() || self.encryption.is_some() {
+            return Ok(());
+        }
+        let small_value_size = entries
+            .iter()
+            .map(|entry| match entry.value() {
+                EntryValue::Small { value } => value.len(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        // Medium values are not sampled
+        let total_value_size = min(total_value_size, small_value_size);
         if total_key_size < MIN_KEY_COMPRESSION_SAMPLES_SIZE
             && total_value_size < MIN_VALUE_COMPRESSION_SAMPLES_SIZE
         {
@@ -142,7 +234,7 @@ impl StaticSortedFileBuilder {
             let value_remaining = value_compression_samples_size - value_samples.len();
             let key_remaining = key_compression_samples_size - key_samples.len();
             if value_remaining > 0 {
-                if let EntryValue::Small { value } | EntryValue::Medium { value } = entry.value() {
+                if let EntryValue::Small { value } = entry.value() {
                     let value = if value.len() <= COMPRESSION_DICTIONARY_SAMPLE_PER_ENTRY {
                         value
                     } else {
@@ -203,13 +295,12 @@ impl StaticSortedFileBuilder {
 
     /// Compute index, key and value blocks.
     fn compute_blocks<E: Entry>(&mut self, entries: &[E]) {
-        // TODO implement multi level index
         // TODO place key and value block near to each other
 
         // For now we use something simple to implement:
         // Start with Value blocks
         // And then Key blocks
-        // Last block is Index block
+        // And then Index blocks, the last block is the root Index block
 
         // Store the locations of the values
         let mut value_locations: Vec<(usize, usize)> = Vec::with_capacity(entries.len());
@@ -220,14 +311,16 @@ impl StaticSortedFileBuilder {
         let mut current_block_size = 0;
         for (i, entry) in entries.iter().enumerate() {
             match entry.value() {
-                EntryValue::Small { value } => {
+                EntryValue::Small { value } | EntryValue::Merge { value } => {
                     if current_block_size + value.len() > MAX_SMALL_VALUE_BLOCK_SIZE
                         || current_block_count + 1 >= MAX_SMALL_VALUE_BLOCK_ENTRIES
                     {
                         let block_index = self.blocks.len();
                         let mut block = Vec::with_capacity(current_block_size);
                         for j in current_block_start..i {
-                            if let EntryValue::Small { value } = &entries[j].value() {
+                            if let EntryValue::Small { value } | EntryValue::Merge { value } =
+                                &entries[j].value()
+                            {
                                 block.extend_from_slice(value);
                                 value_locations[j].0 = block_index;
                             }
@@ -243,7 +336,7 @@ impl StaticSortedFileBuilder {
                 }
                 EntryValue::Medium { value } => {
                     value_locations.push((self.blocks.len(), value.len()));
-                    self.blocks.push(self.compress_value_block(value));
+                    self.blocks.push(self.compress_medium_value_block(value));
                 }
                 _ => {
                     value_locations.push((0, 0));
@@ -254,7 +347,9 @@ impl StaticSortedFileBuilder {
             let block_index = self.blocks.len();
             let mut block = Vec::with_capacity(current_block_size);
             for j in current_block_start..entries.len() {
-                if let EntryValue::Small { value } = &entries[j].value() {
+                if let EntryValue::Small { value } | EntryValue::Merge { value } =
+                    &entries[j].value()
+                {
                     block.extend_from_slice(value);
                     value_locations[j].0 = block_index;
                 }
@@ -288,6 +383,14 @@ impl StaticSortedFileBuilder {
                 EntryValue::Deleted => {
                     block.delete(entry);
                 }
+                EntryValue::Merge { value } => {
+                    block.put_merge(
+                        entry,
+                        value_location.0.try_into().unwrap(),
+                        value_location.1.try_into().unwrap(),
+                        value.len().try_into().unwrap(),
+                    );
+                }
             }
         }
         let mut current_block_start = 0;
@@ -325,26 +428,42 @@ impl StaticSortedFileBuilder {
             self.blocks.push(self.compress_key_block(&block.finish()));
         }
 
-        // Compute the index
-        let mut index_block = IndexBlockBuilder::new(
-            key_block_boundaries.len() as u16,
-            key_block_boundaries[0].1 as u16,
-        );
-        for (hash, block) in &key_block_boundaries[1..] {
+        // Compute the index, with multiple levels when there are too many key blocks for a single
+        // index block
+        let mut boundaries = key_block_boundaries;
+        while boundaries.len() > INDEX_PARTITION_BLOCKS {
+            boundaries = boundaries
+                .chunks(INDEX_PARTITION_BLOCKS)
+                .map(|partition| (partition[0].0, self.add_index_block(partition)))
+                .collect();
+        }
+        self.add_index_block(&boundaries);
+    }
+
+    /// Adds an index block that points to the blocks with the given first hashes. Returns the
+    /// index of the new block.
+    fn add_index_block(&mut self, boundaries: &[(u64, usize)]) -> usize {
+        let mut index_block =
+            IndexBlockBuilder::new(boundaries.len() as u16, boundaries[0].1 as u16);
+        for (hash, block) in &boundaries[1..] {
             index_block.put(*hash, *block as u16);
         }
+        let block_index = self.blocks.len();
         self.blocks
             .push(self.compress_key_block(&index_block.finish()));
+        block_index
     }
 
-    /// Compresses a block with a compression dictionary.
+    /// Compresses a block with a compression dictionary, and encrypts it when encryption is
+    /// enabled.
     fn compress_block(&self, block: &[u8], dict: &[u8]) -> (u32, Vec<u8>) {
-        let mut compressor =
-            lzzzz::lz4::Compressor::with_dict(dict).expect("LZ4 compressor creation failed");
-        let mut compressed = Vec::with_capacity(max_compressed_size(block.len()));
-        compressor
-            .next_to_vec(block, &mut compressed, ACC_LEVEL_DEFAULT)
+        let mut compressed = self
+            .compression
+            .compress(block, dict)
             .expect("Compression failed");
+        if let Some(encryption) = &self.encryption {
+            compressed = encryption.encrypt(&compressed).expect("Encryption failed");
+        }
         if compressed.capacity() > compressed.len() * 2 {
             compressed.shrink_to_fit();
         }
@@ -361,13 +480,30 @@ impl StaticSortedFileBuilder {
         self.compress_block(block, &self.value_compression_dictionary)
     }
 
-    /// Writes the SST file.
-    pub fn write(&self, file: &Path) -> io::Result<File> {
-        let mut file = BufWriter::new(File::create(file)?);
+    /// Compresses the block of a single medium-sized value. It's large enough to compress well
+    /// without a dictionary.
+    fn compress_medium_value_block(&self, block: &[u8]) -> (u32, Vec<u8>) {
+        self.compress_block(block, &[])
+    }
+
+    /// Writes the SST file, optionally with direct I/O and limited by a rate limiter.
+    pub fn write(
+        &self,
+        file: &Path,
+        direct_io: bool,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> io::Result<File> {
+        let mut file = FileWriter::create(file, direct_io, rate_limiter)?;
         // magic number and version
-        file.write_u32::<BE>(0x53535401)?;
+        file.write_u32::<BE>(SST_MAGIC)?;
         // family
         file.write_u32::<BE>(self.family)?;
+        // compression and encryption
+        let mut compression_tag = self.compression.tag();
+        if self.encryption.is_some() {
+            compression_tag |= ENCRYPTED_FLAG;
+        }
+        file.write_u8(compression_tag)?;
         // min hash
         file.write_u64::<BE>(self.min_hash)?;
         // max hash
@@ -380,6 +516,13 @@ impl StaticSortedFileBuilder {
         file.write_u16::<BE>(self.value_compression_dictionary.len().try_into().unwrap())?;
         // Number of blocks
         file.write_u16::<BE>(self.blocks.len().try_into().unwrap())?;
+        // Number of entries
+        file.write_u32::<BE>(self.entry_count)?;
+        // Number of tombstones
+        file.write_u32::<BE>(self.tombstone_count)?;
+        // Sequence numbers of the commits
+        file.write_u32::<BE>(self.commit_sequence_numbers.0)?;
+        file.write_u32::<BE>(self.commit_sequence_numbers.1)?;
 
         // Write the AQMF
         file.write_all(&self.aqmf)?;
@@ -391,18 +534,20 @@ impl StaticSortedFileBuilder {
         // Write the blocks
         let mut offset = 0;
         for (_, block) in &self.blocks {
-            // Block length (including the uncompressed length field)
-            let len = block.len() + 4;
+            // Block length (including the uncompressed length and checksum fields)
+            let len = block.len() + 12;
             offset += len;
             file.write_u32::<BE>(offset.try_into().unwrap())?;
         }
         for (uncompressed_size, block) in &self.blocks {
             // Uncompressed size
             file.write_u32::<BE>(*uncompressed_size)?;
+            // Checksum of the compressed block
+            file.write_u64::<BE>(block_checksum(block))?;
             // Compressed block
             file.write_all(block)?;
         }
-        Ok(file.into_inner()?)
+        file.finish()
     }
 }
 
@@ -411,6 +556,10 @@ pub struct KeyBlockBuilder {
     current_entry: usize,
     header_size: usize,
     data: Vec<u8>,
+    /// The key of the previous entry, used for prefix compression.
+    previous_key: Vec<u8>,
+    /// The key of the current entry.
+    key: Vec<u8>,
 }
 
 /// The size of the key block header.
@@ -432,6 +581,8 @@ impl KeyBlockBuilder {
             current_entry: 0,
             header_size: data.len(),
             data,
+            previous_key: Vec::new(),
+            key: Vec::new(),
         }
     }
 
@@ -443,57 +594,117 @@ impl KeyBlockBuilder {
         value_offset: u32,
         value_size: u16,
     ) {
-        let pos = self.data.len() - self.header_size;
-        let header_offset = KEY_BLOCK_HEADER_SIZE + self.current_entry * 4;
-        let header = (pos as u32) | ((KEY_BLOCK_ENTRY_TYPE_SMALL as u32) << 24);
-        BE::write_u32(&mut self.data[header_offset..header_offset + 4], header);
+        self.put_in_value_block(
+            entry,
+            KEY_BLOCK_ENTRY_TYPE_SMALL,
+            value_block,
+            value_offset,
+            value_size,
+        );
+    }
 
-        self.data.write_u64::<BE>(entry.key_hash()).unwrap();
-        entry.write_key_to(&mut self.data);
+    /// Writes a merge operand to the buffer.
+    pub fn put_merge<E: Entry>(
+        &mut self,
+        entry: &E,
+        value_block: u16,
+        value_offset: u32,
+        value_size: u16,
+    ) {
+        self.put_in_value_block(
+            entry,
+            KEY_BLOCK_ENTRY_TYPE_MERGE,
+            value_block,
+            value_offset,
+            value_size,
+        );
+    }
+
+    /// Writes an entry whose value is stored in a shared value block to the buffer.
+    fn put_in_value_block<E: Entry>(
+        &mut self,
+        entry: &E,
+        ty: u8,
+        value_block: u16,
+        value_offset: u32,
+        value_size: u16,
+    ) {
+        self.write_entry_header(entry, ty);
         self.data.write_u16::<BE>(value_block).unwrap();
         self.data.write_u16::<BE>(value_size).unwrap();
         self.data.write_u32::<BE>(value_offset).unwrap();
+        self.write_expiry(entry);
 
         self.current_entry += 1;
     }
 
     /// Writes a medium-sized value to the buffer.
     pub fn put_medium<E: Entry>(&mut self, entry: &E, value_block: u16) {
-        let pos = self.data.len() - self.header_size;
-        let header_offset = KEY_BLOCK_HEADER_SIZE + self.current_entry * 4;
-        let header = (pos as u32) | ((KEY_BLOCK_ENTRY_TYPE_MEDIUM as u32) << 24);
-        BE::write_u32(&mut self.data[header_offset..header_offset + 4], header);
-
-        self.data.write_u64::<BE>(entry.key_hash()).unwrap();
-        entry.write_key_to(&mut self.data);
+        self.write_entry_header(entry, KEY_BLOCK_ENTRY_TYPE_MEDIUM);
         self.data.write_u16::<BE>(value_block).unwrap();
+        self.write_expiry(entry);
 
         self.current_entry += 1;
     }
 
-    /// Writes a tombstone to the buffer.
-    pub fn delete<E: Entry>(&mut self, entry: &E) {
+    /// Writes the offset of the entry, its key hash and its key. Entries with an expiration
+    /// timestamp use the expiring variant of the entry type.
+    fn write_entry_header<E: Entry>(&mut self, entry: &E, ty: u8) {
+        let ty = if entry.expires_at().is_some() {
+            expiring_entry_type(ty)
+        } else {
+            ty
+        };
+        self.write_offset_and_key(entry, ty);
+    }
+
+    /// Writes the offset of the entry with its type, its key hash and its key. Keys of restart
+    /// points are written in full, all other keys as the length of the prefix shared with the
+    /// previous key followed by the remaining suffix.
+    fn write_offset_and_key<E: Entry>(&mut self, entry: &E, ty: u8) {
         let pos = self.data.len() - self.header_size;
         let header_offset = KEY_BLOCK_HEADER_SIZE + self.current_entry * 4;
-        let header = (pos as u32) | ((KEY_BLOCK_ENTRY_TYPE_DELETED as u32) << 24);
+        let header = (pos as u32) | ((ty as u32) << 24);
         BE::write_u32(&mut self.data[header_offset..header_offset + 4], header);
 
         self.data.write_u64::<BE>(entry.key_hash()).unwrap();
-        entry.write_key_to(&mut self.data);
+        self.key.clear();
+        entry.write_key_to(&mut self.key);
+        if self.current_entry % KEY_BLOCK_RESTART_INTERVAL == 0 {
+            self.data.extend_from_slice(&self.key);
+        } else {
+            let shared_prefix = self
+                .key
+                .iter()
+                .zip(self.previous_key.iter())
+                .take(u16::MAX as usize)
+                .take_while(|(a, b)| a == b)
+                .count();
+            self.data.write_u16::<BE>(shared_prefix as u16).unwrap();
+            self.data.extend_from_slice(&self.key[shared_prefix..]);
+        }
+        swap(&mut self.key, &mut self.previous_key);
+    }
+
+    /// Writes the expiration timestamp after the value reference, if the entry has one.
+    fn write_expiry<E: Entry>(&mut self, entry: &E) {
+        if let Some(expires_at) = entry.expires_at() {
+            self.data.write_u64::<BE>(expires_at).unwrap();
+        }
+    }
+
+    /// Writes a tombstone to the buffer.
+    pub fn delete<E: Entry>(&mut self, entry: &E) {
+        self.write_offset_and_key(entry, KEY_BLOCK_ENTRY_TYPE_DELETED);
 
         self.current_entry += 1;
     }
 
     /// Writes a blob value to the buffer.
     pub fn put_blob<E: Entry>(&mut self, entry: &E, blob: u32) {
-        let pos = self.data.len() - self.header_size;
-        let header_offset = KEY_BLOCK_HEADER_SIZE + self.current_entry * 4;
-        let header = (pos as u32) | ((KEY_BLOCK_ENTRY_TYPE_BLOB as u32) << 24);
-        BE::write_u32(&mut self.data[header_offset..header_offset + 4], header);
-
-        self.data.write_u64::<BE>(entry.key_hash()).unwrap();
-        entry.write_key_to(&mut self.data);
+        self.write_entry_header(entry, KEY_BLOCK_ENTRY_TYPE_BLOB);
         self.data.write_u32::<BE>(blob).unwrap();
+        self.write_expiry(entry);
 
         self.current_entry += 1;
     }
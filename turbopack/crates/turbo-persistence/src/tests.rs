@@ -1,9 +1,33 @@
-use std::time::Instant;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Read,
+    ops::RangeBounds,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
 use anyhow::Result;
+use parking_lot::Mutex;
+use rand::{rngs::SmallRng, RngCore, SeedableRng};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::{db::TurboPersistence, write_batch::WriteBatch};
+use crate::{
+    compression::Compression,
+    db::{CacheCapacity, DbOptions, TurboPersistence},
+    hash_key,
+    range_iter::RANGE_REV_FIRST_BATCH_SIZE,
+    read_trace, replay_trace,
+    write_batch::WriteBatch,
+    ArcSlice, BlobFileInfo, CacheKind, Change, CompactOnOpen, CompactionInfo, CorruptionError,
+    Encryption, EvictionInfo, Family, FileSizes, FileSource, FlushInfo, KeyComparator, LockedError,
+    MergeOperator, PendingEntries, PersistenceEventListener, RateLimit, SstFileInfo, SyncPolicy,
+    TraceOperation, VerifyIssue,
+};
 
 #[test]
 fn full_cycle() -> Result<()> {
@@ -448,3 +472,3429 @@ fn persist_changes() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn range() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    fn collect(db: &TurboPersistence, range: impl RangeBounds<[u8; 4]>) -> Result<Vec<u32>> {
+        let mut keys = db
+            .range(0, range)?
+            .map(|entry| {
+                let (key, value) = entry?;
+                assert_eq!(&*key, &*value);
+                Ok(u32::from_be_bytes(<[u8; 4]>::try_from(&*key)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        keys.sort_unstable();
+        Ok(keys)
+    }
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..1000u32 {
+        b.put(0, i.to_be_bytes(), i.to_be_bytes().to_vec().into())?;
+        b.put(1, i.to_be_bytes(), vec![].into())?;
+    }
+    db.commit_write_batch(b)?;
+
+    let b = db.write_batch::<_, 2>()?;
+    for i in (0..1000u32).step_by(2) {
+        b.delete(0, i.to_be_bytes())?;
+    }
+    b.put(0, 2000u32.to_be_bytes(), vec![0; 100 * 1024].into())?;
+    db.commit_write_batch(b)?;
+
+    let b = db.write_batch::<_, 2>()?;
+    for i in (0..1000u32).step_by(4) {
+        b.put(0, i.to_be_bytes(), i.to_be_bytes().to_vec().into())?;
+    }
+    b.delete(0, 2000u32.to_be_bytes())?;
+    db.commit_write_batch(b)?;
+
+    let expected = |range: std::ops::Range<u32>| {
+        range
+            .filter(|i| i % 2 == 1 || i % 4 == 0)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(collect(&db, ..)?, expected(0..1000));
+    assert_eq!(
+        collect(&db, 100u32.to_be_bytes()..200u32.to_be_bytes())?,
+        expected(100..200)
+    );
+    assert_eq!(
+        collect(&db, 100u32.to_be_bytes()..=200u32.to_be_bytes())?,
+        expected(100..201)
+    );
+    assert_eq!(collect(&db, 990u32.to_be_bytes()..)?, expected(990..1000));
+
+    db.full_compact()?;
+    assert_eq!(collect(&db, ..)?, expected(0..1000));
+    assert_eq!(collect(&db, ..500u32.to_be_bytes())?, expected(0..500));
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn range_rev() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    fn keys(iter: impl Iterator<Item = Result<(ArcSlice<u8>, ArcSlice<u8>)>>) -> Result<Vec<u32>> {
+        iter.map(|entry| {
+            let (key, value) = entry?;
+            assert_eq!(&*value, &[(key[3] % 4) as u8 + 1]);
+            Ok(u32::from_be_bytes(<[u8; 4]>::try_from(&*key)?))
+        })
+        .collect()
+    }
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for round in 0..4u8 {
+        let b = db.write_batch::<_, 1>()?;
+        for i in 0..10000u32 {
+            if i % 4 == round as u32 {
+                b.put(
+                    0,
+                    i.to_be_bytes(),
+                    vec![(i.to_be_bytes()[3] % 4) + 1].into(),
+                )?;
+            } else if i % 4 > round as u32 {
+                b.put(0, i.to_be_bytes(), vec![0].into())?;
+            }
+        }
+        db.commit_write_batch(b)?;
+    }
+    let b = db.write_batch::<_, 1>()?;
+    for i in (0..10000u32).step_by(3) {
+        b.delete(0, i.to_be_bytes())?;
+    }
+    db.commit_write_batch(b)?;
+
+    // Forward iteration yields the entries in key hash order, reverse iteration in descending key
+    // order
+    let mut forward = keys(db.range(0, ..)?)?;
+    forward.sort_unstable();
+    let mut backward = keys(db.range_rev(0, ..)?)?;
+    backward.reverse();
+    assert_eq!(forward.len(), 10000 - 3334);
+    assert_eq!(forward, backward);
+
+    let start = 5000u32.to_be_bytes();
+    let mut forward = keys(db.range(0, start..)?)?;
+    forward.sort_unstable();
+    let mut backward = keys(db.range_rev(0, start..)?)?;
+    backward.reverse();
+    assert_eq!(forward, backward);
+    assert!(forward.iter().all(|&i| i >= 5000 && i % 3 != 0));
+
+    // The last entry of a range is the first one of the reverse iteration
+    let end = 100u32.to_be_bytes();
+    let (key, _) = db.range_rev(0, ..end)?.next().unwrap()?;
+    assert_eq!(&*key, &98u32.to_be_bytes());
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn range_rev_batches() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    fn keys(iter: impl Iterator<Item = Result<(ArcSlice<u8>, ArcSlice<u8>)>>) -> Result<Vec<u32>> {
+        iter.map(|entry| Ok(u32::from_be_bytes(<[u8; 4]>::try_from(&*entry?.0)?)))
+            .collect()
+    }
+
+    const COUNT: u32 = 20000;
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for round in 0..2u32 {
+        let b = db.write_batch::<_, 1>()?;
+        for i in (round..COUNT).step_by(2) {
+            b.put(0, i.to_be_bytes(), vec![].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+
+    // Reading the last entries stops after the first batch, which bounds the entries in memory
+    let mut iter = db.range_rev::<[u8; 4]>(0, ..)?;
+    assert_eq!(iter.size_hint(), (0, None));
+    assert_eq!(
+        keys(iter.by_ref().take(10))?,
+        (COUNT - 10..COUNT).rev().collect::<Vec<_>>()
+    );
+    assert_eq!(iter.size_hint(), (RANGE_REV_FIRST_BATCH_SIZE - 10, None));
+
+    // Iterating the whole range continues with the following batches
+    assert_eq!(
+        keys(db.range_rev::<[u8; 4]>(0, ..)?)?,
+        (0..COUNT).rev().collect::<Vec<_>>()
+    );
+    let range = 1000u32.to_be_bytes()..15000u32.to_be_bytes();
+    assert_eq!(
+        keys(db.range_rev(0, range)?)?,
+        (1000..15000).rev().collect::<Vec<_>>()
+    );
+
+    // The last batch is not full
+    let mut iter = db.range_rev(0, ..100u32.to_be_bytes())?;
+    assert_eq!(
+        iter.next().transpose()?.map(|(key, _)| key.to_vec()),
+        Some(99u32.to_be_bytes().to_vec())
+    );
+    assert_eq!(iter.size_hint(), (99, Some(99)));
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn snapshot() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..1000u32 {
+        b.put(0, i.to_be_bytes(), vec![1].into())?;
+    }
+    db.commit_write_batch(b)?;
+
+    let snapshot = db.snapshot();
+
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..1000u32 {
+        if i % 2 == 0 {
+            b.put(0, i.to_be_bytes(), vec![2].into())?;
+        } else {
+            b.delete(0, i.to_be_bytes())?;
+        }
+    }
+    db.commit_write_batch(b)?;
+    db.full_compact()?;
+
+    assert!(db.snapshot().sequence_number() > snapshot.sequence_number());
+    for i in 0..1000u32 {
+        assert_eq!(
+            snapshot.get(0, &i.to_be_bytes())?.as_deref(),
+            Some(&[1][..])
+        );
+        let expected = if i % 2 == 0 { Some(&[2][..]) } else { None };
+        assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), expected);
+    }
+    let mut count = 0;
+    for entry in snapshot.range::<[u8; 4]>(0, ..)? {
+        let (_, value) = entry?;
+        assert_eq!(&*value, &[1]);
+        count += 1;
+    }
+    assert_eq!(count, 1000);
+    drop(snapshot);
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn wal_replay() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let options = DbOptions {
+        wal: true,
+        ..Default::default()
+    };
+
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options.clone())?;
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..1000u32 {
+            b.put(0, i.to_be_bytes(), vec![1].into())?;
+        }
+        db.commit_write_batch(b)?;
+
+        // The process dies before the write batch is committed
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..1000u32 {
+            if i % 2 == 0 {
+                b.put(1, i.to_be_bytes(), vec![2].into())?;
+            } else {
+                b.delete(0, i.to_be_bytes())?;
+            }
+        }
+        drop(b);
+        db.shutdown()?;
+    }
+
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+        for i in 0..1000u32 {
+            if i % 2 == 0 {
+                assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+                assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[2][..]));
+            } else {
+                assert_eq!(db.get(0, &i.to_be_bytes())?, None);
+                assert_eq!(db.get(1, &i.to_be_bytes())?, None);
+            }
+        }
+        db.shutdown()?;
+    }
+
+    {
+        // Opening without write-ahead log keeps the replayed data
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        assert_eq!(db.get(1, &0u32.to_be_bytes())?.as_deref(), Some(&[2][..]));
+        assert_eq!(db.get(0, &1u32.to_be_bytes())?, None);
+        db.shutdown()?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "failpoints")]
+#[test]
+fn failpoints() -> Result<()> {
+    use crate::{Failpoint, FailpointAction};
+
+    for failpoint in [
+        Failpoint::AfterBlobWrite,
+        Failpoint::AfterSstWrite,
+        Failpoint::Finish,
+        Failpoint::BeforeCommit,
+        Failpoint::BeforeCurrentUpdate,
+        Failpoint::BeforeFileDeletion,
+    ] {
+        let tempdir = tempfile::tempdir()?;
+        let path = tempdir.path();
+        {
+            let db = TurboPersistence::open(path.to_path_buf())?;
+            for i in 0..2u32 {
+                let b = db.write_batch::<_, 1>()?;
+                b.put(0, i.to_be_bytes(), vec![1].into())?;
+                db.commit_write_batch(b)?;
+            }
+
+            db.set_failpoint(failpoint, Some(FailpointAction::Error));
+            if failpoint == Failpoint::BeforeFileDeletion {
+                // Only compactions delete files
+                assert!(db.full_compact().is_err());
+            } else {
+                let b = db.write_batch::<_, 1>()?;
+                b.put(0, 0u32.to_be_bytes(), vec![2].into())?;
+                b.delete_range(0, 1u32.to_be_bytes(), 2u32.to_be_bytes())?;
+                let result = if failpoint == Failpoint::AfterBlobWrite {
+                    b.put(0, 2u32.to_be_bytes(), vec![2; 65 * 1024 * 1024].into())
+                } else {
+                    db.commit_write_batch(b)
+                };
+                assert!(
+                    format!("{:?}", result.unwrap_err()).contains("Injected failure"),
+                    "{failpoint:?}"
+                );
+            }
+            // The process dies
+        }
+        {
+            let db = TurboPersistence::open(path.to_path_buf())?;
+            for i in 0..2u32 {
+                assert_eq!(
+                    db.get(0, &i.to_be_bytes())?.as_deref(),
+                    Some(&[1][..]),
+                    "{failpoint:?}"
+                );
+            }
+            assert_eq!(db.get(0, &2u32.to_be_bytes())?, None);
+            assert!(db.verify(None)?.is_ok());
+            let b = db.write_batch::<_, 1>()?;
+            b.put(0, 0u32.to_be_bytes(), vec![3].into())?;
+            db.commit_write_batch(b)?;
+            assert_eq!(db.get(0, &0u32.to_be_bytes())?.as_deref(), Some(&[3][..]));
+            db.shutdown()?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn compression() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    fn check(db: &TurboPersistence) -> Result<()> {
+        for family in 0..3usize {
+            for i in 0..1000u32 {
+                let value = db.get(family, &i.to_be_bytes())?;
+                assert_eq!(value.as_deref(), Some(&vec![i as u8; 1000][..]));
+            }
+            let blob = db.get(family, &u32::MAX.to_be_bytes())?;
+            assert_eq!(
+                blob.as_deref(),
+                Some(&vec![family as u8; 65 * 1024 * 1024][..])
+            );
+        }
+        Ok(())
+    }
+
+    let families = [
+        Compression::None,
+        Compression::Lz4,
+        Compression::Zstd { level: 3 },
+    ];
+    {
+        let db = TurboPersistence::open_with_options(
+            path.to_path_buf(),
+            DbOptions {
+                compression: families.to_vec(),
+                ..Default::default()
+            },
+        )?;
+        let b = db.write_batch::<_, 3>()?;
+        for family in 0..3usize {
+            for i in 0..1000u32 {
+                b.put(family, i.to_be_bytes(), vec![i as u8; 1000].into())?;
+            }
+            b.put(
+                family,
+                u32::MAX.to_be_bytes(),
+                vec![family as u8; 65 * 1024 * 1024].into(),
+            )?;
+        }
+        db.commit_write_batch(b)?;
+        check(&db)?;
+        db.shutdown()?;
+    }
+
+    {
+        // Files are read with the compression they were written with
+        let mut families = families;
+        families.reverse();
+        let db = TurboPersistence::open_with_options(
+            path.to_path_buf(),
+            DbOptions {
+                compression: families.to_vec(),
+                ..Default::default()
+            },
+        )?;
+        check(&db)?;
+        db.full_compact()?;
+        check(&db)?;
+        db.shutdown()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn compression_levels() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    fn check(db: &TurboPersistence) -> Result<()> {
+        for family in 0..2usize {
+            for i in 0..1000u32 {
+                let value = db.get(family, &i.to_be_bytes())?;
+                assert_eq!(value.as_deref(), Some(&vec![i as u8; 1000][..]));
+            }
+            let value = db.get(family, &u32::MAX.to_be_bytes())?;
+            assert_eq!(
+                value.as_deref(),
+                Some(&vec![family as u8; 65 * 1024 * 1024][..])
+            );
+        }
+        Ok(())
+    }
+
+    {
+        let db = TurboPersistence::open_with_options(
+            path.to_path_buf(),
+            DbOptions {
+                compression: vec![
+                    Compression::Lz4Fast { acceleration: 8 },
+                    Compression::Lz4Hc { level: 9 },
+                ],
+                blob_compression: vec![Some(Compression::Lz4Hc { level: 12 })],
+                ..Default::default()
+            },
+        )?;
+        let b = db.write_batch::<_, 2>()?;
+        for family in 0..2usize {
+            for i in 0..1000u32 {
+                b.put(family, i.to_be_bytes(), vec![i as u8; 1000].into())?;
+            }
+            b.put(
+                family,
+                u32::MAX.to_be_bytes(),
+                vec![family as u8; 65 * 1024 * 1024].into(),
+            )?;
+        }
+        db.commit_write_batch(b)?;
+        check(&db)?;
+        db.shutdown()?;
+    }
+
+    {
+        // The files are read like LZ4 files
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        check(&db)?;
+        db.full_compact()?;
+        check(&db)?;
+        db.shutdown()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn small_value_dictionary() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    // Serialized task cells are small and structurally similar
+    let small_value = |i: u32| {
+        format!(
+            "{{\"task\":\"TaskType::some_module::some_function\",\"id\":{i},\"cell\":{}}}",
+            i % 7
+        )
+        .into_bytes()
+    };
+    let medium_value = |i: u32| vec![i as u8; 100 * 1024];
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..10000u32 {
+            assert_eq!(
+                db.get(0, &i.to_be_bytes())?.as_deref(),
+                Some(&small_value(i)[..])
+            );
+            assert_eq!(
+                db.get(2, &i.to_be_bytes())?.as_deref(),
+                Some(&small_value(i)[..])
+            );
+        }
+        for i in 0..20u32 {
+            assert_eq!(
+                db.get(1, &i.to_be_bytes())?.as_deref(),
+                Some(&medium_value(i)[..])
+            );
+        }
+        Ok(())
+    };
+    // Returns the length of the value compression dictionary of the SST files of a family, which
+    // is stored in the header after the magic number, the family, the compression, the hash range,
+    // the AQMF length and the key compression dictionary length.
+    let value_dictionary_lengths = |family: u32| -> Result<Vec<u16>> {
+        let mut lengths = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "sst") {
+                let header = fs::read(&path)?;
+                if u32::from_be_bytes(header[4..8].try_into()?) == family {
+                    lengths.push(u16::from_be_bytes(header[30..32].try_into()?));
+                }
+            }
+        }
+        Ok(lengths)
+    };
+
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            compression: vec![
+                Compression::Zstd { level: 3 },
+                Compression::Zstd { level: 3 },
+                Compression::None,
+            ],
+            ..Default::default()
+        },
+    )?;
+    let b = db.write_batch::<_, 3>()?;
+    for i in 0..10000u32 {
+        b.put(0, i.to_be_bytes(), small_value(i).into())?;
+        b.put(2, i.to_be_bytes(), small_value(i).into())?;
+    }
+    for i in 0..20u32 {
+        b.put(1, i.to_be_bytes(), medium_value(i).into())?;
+    }
+    db.commit_write_batch(b)?;
+    check(&db)?;
+    db.shutdown()?;
+
+    // The dictionary is trained from the small values
+    let lengths = value_dictionary_lengths(0)?;
+    assert!(!lengths.is_empty());
+    assert!(lengths.iter().all(|&length| length > 0));
+    // Medium values are not sampled
+    assert!(value_dictionary_lengths(1)?
+        .iter()
+        .all(|&length| length == 0));
+    // Uncompressed files don't use dictionaries
+    assert!(value_dictionary_lengths(2)?
+        .iter()
+        .all(|&length| length == 0));
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    check(&db)?;
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn corruption() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let options = DbOptions {
+        paranoid_checks: true,
+        ..Default::default()
+    };
+
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options.clone())?;
+        let b = db.write_batch::<_, 1>()?;
+        for i in 0..100u32 {
+            b.put(0, i.to_be_bytes(), vec![i as u8; 100].into())?;
+        }
+        db.commit_write_batch(b)?;
+        assert_eq!(
+            db.get(0, &42u32.to_be_bytes())?.as_deref(),
+            Some(&[42; 100][..])
+        );
+        db.shutdown()?;
+    }
+
+    // Flip a bit in the last block, which is the index block
+    let mut sst_files = std::fs::read_dir(path)?
+        .map(|entry| Ok(entry?.path()))
+        .filter(|path| {
+            path.as_ref()
+                .is_ok_and(|path| path.extension().is_some_and(|ext| ext == "sst"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(sst_files.len(), 1);
+    let sst_file = sst_files.pop().unwrap();
+    let mut content = std::fs::read(&sst_file)?;
+    *content.last_mut().unwrap() ^= 1;
+    std::fs::write(&sst_file, content)?;
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+    let err = db.get(0, &42u32.to_be_bytes()).unwrap_err();
+    let corruption = err
+        .downcast_ref::<CorruptionError>()
+        .expect("corruption error");
+    assert_ne!(corruption.expected_checksum, corruption.actual_checksum);
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn compaction_drops_tombstones() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = Arc::new(TurboPersistence::open(path.to_path_buf())?);
+    for round in 0..3u8 {
+        let b = db.write_batch::<_, 1>()?;
+        for i in 0..1000u32 {
+            b.put(0, i.to_be_bytes(), vec![round].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..1000u32 {
+        b.delete(0, i.to_be_bytes())?;
+    }
+    db.commit_write_batch(b)?;
+
+    db.clone().spawn_compact(0.0, usize::MAX)?;
+    // Waits for the background compaction
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 1u32.to_be_bytes(), vec![42].into())?;
+    db.commit_write_batch(b)?;
+
+    assert_eq!(db.get(0, &0u32.to_be_bytes())?, None);
+    assert_eq!(db.get(0, &1u32.to_be_bytes())?.as_deref(), Some(&[42][..]));
+    db.full_compact()?;
+    assert_eq!(db.range::<[u8; 4]>(0, ..)?.count(), 1);
+    assert_eq!(
+        std::fs::read_dir(path)?
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "sst"))
+            })
+            .count(),
+        1
+    );
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn compaction_prioritizes_read_files() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let sst_files = || -> Result<HashSet<PathBuf>> {
+        let mut files = HashSet::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "sst") {
+                files.insert(path);
+            }
+        }
+        Ok(files)
+    };
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let mut files_by_batch = Vec::new();
+    for round in 0..4u32 {
+        let before = sst_files()?;
+        let b = db.write_batch::<_, 1>()?;
+        for i in round * 1000..(round + 1) * 1000 {
+            b.put(0, i.to_be_bytes(), vec![round as u8].into())?;
+        }
+        db.commit_write_batch(b)?;
+        files_by_batch.push(
+            sst_files()?
+                .difference(&before)
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    // Lookups of the keys of the third batch probe the files of the third and the fourth batch
+    for i in 2000..3000u32 {
+        assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), Some(&[2][..]));
+    }
+
+    // Only two files are merged at once. Without reads the oldest files would be merged, but the
+    // files that were read are merged first, which leaves the older files unchanged.
+    db.compact(0.0, 2)?;
+    let files = sst_files()?;
+    for (batch, batch_files) in files_by_batch.iter().enumerate() {
+        for file in batch_files {
+            assert_eq!(files.contains(file), batch < 2, "file of batch {batch}");
+        }
+    }
+    for i in 0..4000u32 {
+        assert_eq!(
+            db.get(0, &i.to_be_bytes())?.as_deref(),
+            Some(&[(i / 1000) as u8][..])
+        );
+    }
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn compact_family() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let sst_count = || -> Result<usize> {
+        let mut count = 0;
+        for entry in std::fs::read_dir(path)? {
+            if entry?.path().extension().is_some_and(|ext| ext == "sst") {
+                count += 1;
+            }
+        }
+        Ok(count)
+    };
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for round in 0..3u8 {
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..1000u32 {
+            b.put(0, i.to_be_bytes(), vec![round].into())?;
+            b.put(1, i.to_be_bytes(), vec![round].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    assert_eq!(sst_count()?, 6);
+
+    db.compact_family(0)?;
+    assert_eq!(sst_count()?, 4);
+    for i in 0..1000u32 {
+        assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), Some(&[2][..]));
+        assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[2][..]));
+    }
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn compact_range() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let sst_count = || -> Result<usize> {
+        let mut count = 0;
+        for entry in std::fs::read_dir(path)? {
+            if entry?.path().extension().is_some_and(|ext| ext == "sst") {
+                count += 1;
+            }
+        }
+        Ok(count)
+    };
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for round in 0..3u8 {
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..1000u32 {
+            b.put(0, i.to_be_bytes(), vec![round].into())?;
+            b.put(1, i.to_be_bytes(), vec![round].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    assert_eq!(sst_count()?, 6);
+
+    // An empty range doesn't compact anything
+    db.compact_range(0, &500u32.to_be_bytes(), &500u32.to_be_bytes())?;
+    assert_eq!(sst_count()?, 6);
+
+    db.compact_range(1, &100u32.to_be_bytes(), &200u32.to_be_bytes())?;
+    assert_eq!(sst_count()?, 4);
+    for i in 0..1000u32 {
+        assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[2][..]));
+    }
+
+    db.compact_all()?;
+    assert_eq!(sst_count()?, 2);
+    for i in 0..1000u32 {
+        assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), Some(&[2][..]));
+        assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[2][..]));
+    }
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn delete_range() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    {
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..100u32 {
+            b.put(0, i.to_be_bytes(), vec![1].into())?;
+            b.put(1, i.to_be_bytes(), vec![1].into())?;
+        }
+        db.commit_write_batch(b)?;
+
+        let b = db.write_batch::<_, 2>()?;
+        b.delete_range(0, 10u32.to_be_bytes(), 20u32.to_be_bytes())?;
+        // Keys written in the same batch are not deleted
+        b.put(0, 15u32.to_be_bytes(), vec![2].into())?;
+        db.commit_write_batch(b)?;
+
+        let check = |db: &TurboPersistence| -> Result<()> {
+            for i in 0..100u32 {
+                let expected = match i {
+                    15 => Some(&[2][..]),
+                    10..20 => None,
+                    _ => Some(&[1][..]),
+                };
+                assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), expected);
+                assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+            }
+            let keys = db
+                .range(0, 0u32.to_be_bytes()..100u32.to_be_bytes())?
+                .map(|entry| entry.map(|(key, _)| u32::from_be_bytes((*key).try_into().unwrap())))
+                .collect::<Result<Vec<_>>>()?;
+            assert_eq!(keys.len(), 91);
+            assert!(keys.contains(&15));
+            assert!(!keys.contains(&10));
+            Ok(())
+        };
+        check(&db)?;
+
+        // Range tombstones survive a restart
+        db.shutdown()?;
+        drop(db);
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        check(&db)?;
+
+        // Compaction removes the deleted entries and the range tombstone
+        db.full_compact()?;
+        check(&db)?;
+        db.shutdown()?;
+    }
+
+    for entry in std::fs::read_dir(path)? {
+        assert!(entry?.path().extension().is_none_or(|ext| ext != "rdel"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn truncate_family() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    {
+        let db = TurboPersistence::open_with_options(
+            path.to_path_buf(),
+            DbOptions {
+                cache_capacity: CacheCapacity {
+                    row: 1024 * 1024,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        for _ in 0..2 {
+            let b = db.write_batch::<_, 2>()?;
+            for i in 0..100u32 {
+                b.put(0, i.to_be_bytes(), vec![1].into())?;
+                b.put(1, i.to_be_bytes(), vec![1].into())?;
+            }
+            db.commit_write_batch(b)?;
+        }
+        let b = db.write_batch::<_, 2>()?;
+        b.delete_range(0, 10u32.to_be_bytes(), 20u32.to_be_bytes())?;
+        db.commit_write_batch(b)?;
+        // Fill the row cache
+        assert_eq!(db.get(0, &0u32.to_be_bytes())?.as_deref(), Some(&[1][..]));
+
+        let snapshot = db.snapshot();
+        db.truncate_family(0)?;
+        for i in 0..100u32 {
+            assert!(db.get(0, &i.to_be_bytes())?.is_none());
+            assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+        }
+        assert_eq!(db.range::<&[u8]>(0, ..)?.count(), 0);
+        // Snapshots still see the dropped keys
+        assert_eq!(
+            snapshot.get(0, &0u32.to_be_bytes())?.as_deref(),
+            Some(&[1][..])
+        );
+        drop(snapshot);
+
+        // The family can be written again
+        let b = db.write_batch::<_, 2>()?;
+        b.put(0, 5u32.to_be_bytes(), vec![2].into())?;
+        db.commit_write_batch(b)?;
+        db.shutdown()?;
+    }
+
+    {
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        for i in 0..100u32 {
+            let expected = (i == 5).then_some(&[2][..]);
+            assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), expected);
+            assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+        }
+        // Truncating an empty family does nothing
+        db.truncate_family(2)?;
+        db.shutdown()?;
+    }
+
+    for entry in fs::read_dir(path)? {
+        assert!(entry?.path().extension().is_none_or(|ext| ext != "rdel"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn change_feed() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let bytes = |bytes: &[u8]| ArcSlice::from(Box::from(bytes));
+    let check = |changes: Vec<Change>| {
+        assert_eq!(changes.len(), 4);
+        assert_eq!(
+            changes[0],
+            Change::DeleteRange {
+                family: 0,
+                start: 5u32.to_be_bytes().to_vec(),
+                end: 8u32.to_be_bytes().to_vec(),
+            }
+        );
+        assert!(changes.contains(&Change::Put {
+            family: 0,
+            key: bytes(&20u32.to_be_bytes()),
+            value: bytes(&[2]),
+            expires_at: None,
+        }));
+        assert!(changes.contains(&Change::Delete {
+            family: 0,
+            key: bytes(&1u32.to_be_bytes()),
+        }));
+        assert!(changes.contains(&Change::Put {
+            family: 1,
+            key: bytes(&0u32.to_be_bytes()),
+            value: bytes(&[3]),
+            expires_at: None,
+        }));
+    };
+
+    let since = {
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..10u32 {
+            b.put(0, i.to_be_bytes(), vec![1].into())?;
+        }
+        db.commit_write_batch(b)?;
+        let since = db.sequence_number();
+
+        let b = db.write_batch::<_, 2>()?;
+        b.put(0, 20u32.to_be_bytes(), vec![2].into())?;
+        b.delete(0, 1u32.to_be_bytes())?;
+        b.delete_range(0, 5u32.to_be_bytes(), 8u32.to_be_bytes())?;
+        b.put(1, 0u32.to_be_bytes(), vec![3].into())?;
+        db.commit_write_batch(b)?;
+
+        let feed = db.changes_since(since)?;
+        assert_eq!(feed.sequence_number(), db.sequence_number());
+        check(feed.collect::<Result<Vec<_>>>()?);
+        assert_eq!(db.changes_since(db.sequence_number())?.count(), 0);
+        assert_eq!(db.changes_since(0)?.count(), 14);
+        db.shutdown()?;
+        since
+    };
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    check(db.changes_since(since)?.collect::<Result<Vec<_>>>()?);
+    // Compaction merges the changes with older entries
+    db.full_compact()?;
+    assert!(db.changes_since(since).is_err());
+    assert_eq!(db.changes_since(db.sequence_number())?.count(), 0);
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn merge_operator() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    // Family 0 contains counters that are incremented by merge operands
+    let options = DbOptions {
+        merge_operators: vec![Some(MergeOperator::new(|existing, operand| {
+            let existing = existing.map_or(0, |v| u32::from_be_bytes(v.try_into().unwrap()));
+            let operand = u32::from_be_bytes(operand.try_into().unwrap());
+            (existing + operand).to_be_bytes().to_vec()
+        }))],
+        ..Default::default()
+    };
+
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..100u32 {
+            let expected = if i < 50 { 1000 + 3 * i } else { 3 * i };
+            assert_eq!(
+                db.get(0, &i.to_be_bytes())?.as_deref(),
+                Some(&expected.to_be_bytes()[..])
+            );
+        }
+        let mut count = 0;
+        for entry in db.range::<[u8; 4]>(0, ..)? {
+            let (key, value) = entry?;
+            let i = u32::from_be_bytes((*key).try_into().unwrap());
+            let expected = if i < 50 { 1000 + 3 * i } else { 3 * i };
+            assert_eq!(*value, expected.to_be_bytes());
+            count += 1;
+        }
+        assert_eq!(count, 100);
+        Ok(())
+    };
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..50u32 {
+        b.put(0, i.to_be_bytes(), 1000u32.to_be_bytes().to_vec().into())?;
+    }
+    db.commit_write_batch(b)?;
+    for _ in 0..3 {
+        let b = db.write_batch::<_, 1>()?;
+        for i in 0..100u32 {
+            b.merge(0, i.to_be_bytes(), i.to_be_bytes().to_vec().into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    check(&db)?;
+
+    // Combines the operands of two files without knowing the value
+    db.compact(0.0, 2)?;
+    check(&db)?;
+
+    db.full_compact()?;
+    check(&db)?;
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn get_many() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for round in 0..3u32 {
+        let b = db.write_batch::<_, 2>()?;
+        for i in (round * 100)..1000u32 {
+            if i % 10 == 0 {
+                b.delete(0, i.to_be_bytes())?;
+            } else {
+                b.put(0, i.to_be_bytes(), round.to_be_bytes().to_vec().into())?;
+            }
+            b.put(1, i.to_be_bytes(), vec![].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+
+    let keys = (0..1100u32).rev().map(u32::to_be_bytes).collect::<Vec<_>>();
+    let values = db.get_many(0, &keys)?;
+    assert_eq!(values.len(), keys.len());
+    for (key, value) in keys.iter().zip(values) {
+        assert_eq!(value.as_deref(), db.get(0, key)?.as_deref());
+    }
+    let values = db.snapshot().get_many(0, &keys)?;
+    assert_eq!(values[0], None);
+    assert_eq!(values[1099 - 555].as_deref(), Some(&2u32.to_be_bytes()[..]));
+    assert_eq!(values[1099 - 150].as_deref(), Some(&1u32.to_be_bytes()[..]));
+    assert_eq!(values[1099 - 50].as_deref(), Some(&0u32.to_be_bytes()[..]));
+    assert_eq!(values[1099 - 500], None);
+
+    let other_keys = (500..600u32).map(u32::to_be_bytes).collect::<Vec<_>>();
+    let values = db.get_many_families(&[(0, &keys[..]), (1, &keys[..]), (0, &other_keys[..])])?;
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[0], db.get_many(0, &keys)?);
+    assert_eq!(values[1], db.get_many(1, &keys)?);
+    assert_eq!(values[2], db.get_many(0, &other_keys)?);
+    assert_eq!(values[1][0], None);
+    assert_eq!(values[1][1099 - 500].as_deref(), Some(&[][..]));
+    assert!(db.get_many_families::<[u8; 4]>(&[])?.is_empty());
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn get_many_families() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(4).build()?;
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            thread_pool: Some(Arc::new(thread_pool)),
+            ..Default::default()
+        },
+    )?;
+
+    // Metadata and data of the same items are stored in different families
+    let b = db.write_batch::<_, 3>()?;
+    for i in 0..1000u32 {
+        b.put(0, i.to_be_bytes(), vec![0].into())?;
+        b.put(1, i.to_be_bytes(), vec![0; 100].into())?;
+        if i % 2 == 0 {
+            b.put(2, i.to_be_bytes(), vec![0; 1000].into())?;
+        }
+    }
+    db.commit_write_batch(b)?;
+
+    let keys = (0..1000u32).map(u32::to_be_bytes).collect::<Vec<_>>();
+    let lookups = [(0, &keys[..]), (1, &keys[..]), (2, &keys[..])];
+    let values = db.get_many_families(&lookups)?;
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(values[0][i].as_deref(), Some(&[0][..]));
+        assert_eq!(values[1][i], db.get(1, key)?);
+        assert_eq!(values[2][i], db.get(2, key)?);
+        assert_eq!(values[2][i].is_some(), i % 2 == 0);
+    }
+
+    // All families are read from the same state while write batches are committed concurrently
+    std::thread::scope(|scope| -> Result<()> {
+        let writer = scope.spawn(|| -> Result<()> {
+            for round in 1..=20u8 {
+                let b = db.write_batch::<_, 3>()?;
+                for i in 0..1000u32 {
+                    b.put(0, i.to_be_bytes(), vec![round].into())?;
+                    b.put(1, i.to_be_bytes(), vec![round; 100].into())?;
+                    b.put(2, i.to_be_bytes(), vec![round; 1000].into())?;
+                }
+                db.commit_write_batch(b)?;
+            }
+            Ok(())
+        });
+        while !writer.is_finished() {
+            let values = db.get_many_families(&lookups)?;
+            let round = values[0][0].as_deref().unwrap()[0];
+            for (family, values) in values.iter().enumerate() {
+                for (i, value) in values.iter().enumerate() {
+                    let value = value.as_deref();
+                    if round == 0 && family == 2 && i % 2 == 1 {
+                        assert_eq!(value, None);
+                    } else {
+                        assert!(value.unwrap().iter().all(|&byte| byte == round));
+                    }
+                }
+            }
+        }
+        writer.join().unwrap()
+    })?;
+
+    let values = db.get_many_families(&lookups)?;
+    for values in values {
+        assert!(values
+            .iter()
+            .all(|value| value.as_deref().is_some_and(|value| value[0] == 20)));
+    }
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn expiry() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let past = SystemTime::now() - Duration::from_secs(60);
+    let future = SystemTime::now() + Duration::from_secs(24 * 60 * 60);
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        b.put(0, i.to_be_bytes(), vec![1].into())?;
+    }
+    db.commit_write_batch(b)?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        let expires_at = if i % 2 == 0 { past } else { future };
+        b.put_with_expiry(0, i.to_be_bytes(), vec![2].into(), expires_at)?;
+    }
+    // Large values are stored in blob files and expire too
+    b.put_with_expiry(
+        0,
+        1000u32.to_be_bytes(),
+        vec![3; 65 * 1024 * 1024].into(),
+        past,
+    )?;
+    db.commit_write_batch(b)?;
+
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..100u32 {
+            // Expired values hide the older values of the key
+            let expected = if i % 2 == 0 { None } else { Some(&[2][..]) };
+            assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), expected);
+        }
+        assert_eq!(db.get(0, &1000u32.to_be_bytes())?, None);
+        assert_eq!(db.range::<[u8; 4]>(0, ..)?.count(), 50);
+        Ok(())
+    };
+    check(&db)?;
+
+    db.full_compact()?;
+    check(&db)?;
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn checkpoint() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join("db");
+    let checkpoint_path = tempdir.path().join("checkpoint");
+
+    let db = TurboPersistence::open(path.clone())?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        b.put(0, i.to_be_bytes(), vec![1].into())?;
+    }
+    b.put(0, 1000u32.to_be_bytes(), vec![1; 65 * 1024 * 1024].into())?;
+    db.commit_write_batch(b)?;
+    let b = db.write_batch::<_, 1>()?;
+    b.delete_range(0, 50u32.to_be_bytes(), 60u32.to_be_bytes())?;
+    db.commit_write_batch(b)?;
+
+    db.checkpoint(&checkpoint_path)?;
+    assert!(db.checkpoint(&checkpoint_path).is_err());
+
+    // Changes after the checkpoint are not visible in the checkpoint
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        b.put(0, i.to_be_bytes(), vec![2].into())?;
+    }
+    db.commit_write_batch(b)?;
+    db.full_compact()?;
+    db.shutdown()?;
+    drop(db);
+
+    let checkpoint = TurboPersistence::open(checkpoint_path)?;
+    for i in 0..100u32 {
+        let expected = if (50..60).contains(&i) {
+            None
+        } else {
+            Some(&[1][..])
+        };
+        assert_eq!(checkpoint.get(0, &i.to_be_bytes())?.as_deref(), expected);
+    }
+    assert_eq!(
+        checkpoint.get(0, &1000u32.to_be_bytes())?.as_deref(),
+        Some(&vec![1; 65 * 1024 * 1024][..])
+    );
+    checkpoint.shutdown()?;
+
+    let db = TurboPersistence::open(path)?;
+    assert_eq!(db.get(0, &55u32.to_be_bytes())?.as_deref(), Some(&[2][..]));
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn ingest_external_files() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let external_path = tempdir.path().join("external");
+    let path = tempdir.path().join("db");
+
+    let external = TurboPersistence::open(external_path.clone())?;
+    let b = external.write_batch::<_, 2>()?;
+    for i in 0..100u32 {
+        b.put(1, i.to_be_bytes(), vec![1].into())?;
+    }
+    external.commit_write_batch(b)?;
+    external.shutdown()?;
+    drop(external);
+    let mut files = fs::read_dir(&external_path)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "sst"));
+    files.sort();
+    assert!(!files.is_empty());
+
+    let db = TurboPersistence::open(path.clone())?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 50..150u32 {
+        b.put(1, i.to_be_bytes(), vec![2].into())?;
+    }
+    db.commit_write_batch(b)?;
+
+    assert!(db.ingest_external_files(0, &files).is_err());
+    db.ingest_external_files(1, &files)?;
+
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..150u32 {
+            let expected = if i < 100 { [1] } else { [2] };
+            assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&expected[..]));
+            assert_eq!(db.get(0, &i.to_be_bytes())?, None);
+        }
+        Ok(())
+    };
+    check(&db)?;
+    db.full_compact()?;
+    check(&db)?;
+    db.shutdown()?;
+    drop(db);
+
+    let db = TurboPersistence::open(path)?;
+    check(&db)?;
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn encryption() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let options = |key: u8| DbOptions {
+        encryption: Some(Encryption::new(&[key; 32])),
+        ..Default::default()
+    };
+
+    // Start with unencrypted files, which stay readable
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        b.put(
+            0,
+            format!("plain-key-{i}").into_bytes(),
+            format!("plain-value-{i}").into_bytes().into(),
+        )?;
+    }
+    db.commit_write_batch(b)?;
+    db.shutdown()?;
+    drop(db);
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options(1))?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        b.put(
+            0,
+            format!("secret-key-{i}").into_bytes(),
+            format!("secret-value-{i}").into_bytes().into(),
+        )?;
+    }
+    b.put(
+        0,
+        b"secret-blob".to_vec(),
+        b"secret-value-blob".repeat(4 * 1024 * 1024).into(),
+    )?;
+    db.commit_write_batch(b)?;
+    db.full_compact()?;
+
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..100u32 {
+            assert_eq!(
+                db.get(0, &format!("secret-key-{i}").into_bytes())?
+                    .as_deref(),
+                Some(format!("secret-value-{i}").as_bytes())
+            );
+        }
+        assert_eq!(
+            db.get(0, &b"secret-blob".to_vec())?.as_deref(),
+            Some(&b"secret-value-blob".repeat(4 * 1024 * 1024)[..])
+        );
+        assert_eq!(
+            db.get(0, &b"plain-key-1".to_vec())?.as_deref(),
+            Some(&b"plain-value-1"[..])
+        );
+        Ok(())
+    };
+    check(&db)?;
+    db.shutdown()?;
+    drop(db);
+
+    // Compaction encrypted all files
+    for entry in fs::read_dir(path)? {
+        let content = fs::read(entry?.path())?;
+        for plaintext in [&b"secret-"[..], b"plain-"] {
+            assert!(!content
+                .windows(plaintext.len())
+                .any(|window| window == plaintext));
+        }
+    }
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options(1))?;
+    check(&db)?;
+    db.shutdown()?;
+    drop(db);
+
+    // The database is locked while it's open, so it's opened one after the other
+    for options in [DbOptions::default(), options(2)] {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+        assert!(db.get(0, &b"secret-key-1".to_vec()).is_err());
+        assert!(db.get(0, &b"secret-blob".to_vec()).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn wal_with_encryption() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let wal_path = path.join(crate::wal::WAL_FILE);
+    let options = |key: Option<u8>| DbOptions {
+        wal: true,
+        encryption: key.map(|key| Encryption::new(&[key; 32])),
+        ..Default::default()
+    };
+
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options(Some(1)))?;
+        // The process dies before the write batch is committed
+        let b = db.write_batch::<_, 1>()?;
+        for i in 0..100u32 {
+            b.put(
+                0,
+                format!("secret-key-{i}").into_bytes(),
+                format!("secret-value-{i}").into_bytes().into(),
+            )?;
+        }
+        drop(b);
+        db.shutdown()?;
+    }
+
+    // The log contains no plaintext
+    let wal = fs::read(&wal_path)?;
+    assert!(!wal.is_empty());
+    assert!(!wal.windows(7).any(|window| window == b"secret-"));
+
+    // The log can't be replayed without the key, and it's kept for the next attempt
+    for key in [None, Some(2)] {
+        assert!(TurboPersistence::open_with_options(path.to_path_buf(), options(key)).is_err());
+    }
+
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options(Some(1)))?;
+        for i in 0..100u32 {
+            assert_eq!(
+                db.get(0, &format!("secret-key-{i}").into_bytes())?
+                    .as_deref(),
+                Some(format!("secret-value-{i}").as_bytes())
+            );
+        }
+        let b = db.write_batch::<_, 1>()?;
+        b.put(0, b"secret-key-0".to_vec(), b"other".to_vec().into())?;
+        drop(b);
+        db.shutdown()?;
+    }
+
+    // A record can't be replayed at another offset
+    let wal = fs::read(&wal_path)?;
+    fs::write(&wal_path, [&wal[..], &wal[..]].concat())?;
+    assert!(TurboPersistence::open_with_options(path.to_path_buf(), options(Some(1))).is_err());
+    fs::write(&wal_path, &wal)?;
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options(Some(1)))?;
+    assert_eq!(
+        db.get(0, &b"secret-key-0".to_vec())?.as_deref(),
+        Some(&b"other"[..])
+    );
+    db.shutdown()?;
+    drop(db);
+
+    // An unencrypted log is not replayed into an encrypted database
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options(None))?;
+        let b = db.write_batch::<_, 1>()?;
+        b.put(0, b"plain-key".to_vec(), b"plain-value".to_vec().into())?;
+        drop(b);
+        db.shutdown()?;
+    }
+    assert!(TurboPersistence::open_with_options(path.to_path_buf(), options(Some(1))).is_err());
+    Ok(())
+}
+
+#[test]
+fn repair() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..100u32 {
+        b.put(0, i.to_be_bytes(), vec![1].into())?;
+        b.put(1, i.to_be_bytes(), vec![1].into())?;
+    }
+    db.commit_write_batch(b)?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..100u32 {
+        b.put(0, i.to_be_bytes(), vec![2].into())?;
+    }
+    db.commit_write_batch(b)?;
+    db.shutdown()?;
+    drop(db);
+
+    let mut sst_files = fs::read_dir(path)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    sst_files.retain(|path| path.extension().is_some_and(|ext| ext == "sst"));
+    sst_files.sort();
+    assert_eq!(sst_files.len(), 3);
+
+    // Truncate the newest file, as if the process crashed while writing it
+    let newest = sst_files.last().unwrap();
+    let content = fs::read(newest)?;
+    fs::write(newest, &content[..content.len() / 2])?;
+    // A file that was not committed
+    let uncommitted = path.join("00001000.sst");
+    fs::write(&uncommitted, b"garbage")?;
+
+    let report = TurboPersistence::repair(path.to_path_buf())?;
+    // The older file of family 0 is removed too, as it contains outdated values
+    assert_eq!(report.removed_files.len(), 3);
+    assert!(report.removed_files.contains(newest));
+    assert!(report.removed_files.contains(&uncommitted));
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for i in 0..100u32 {
+        assert_eq!(db.get(0, &i.to_be_bytes())?, None);
+        assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+    }
+    db.shutdown()?;
+    drop(db);
+
+    // Repairing an intact database doesn't remove anything
+    let report = TurboPersistence::repair(path.to_path_buf())?;
+    assert!(report.removed_files.is_empty());
+    Ok(())
+}
+
+#[test]
+fn verify() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..1000u32 {
+        b.put(0, i.to_be_bytes(), vec![1].into())?;
+        b.put(1, i.to_be_bytes(), vec![2; 100].into())?;
+    }
+    b.put(0, 1000u32.to_be_bytes(), vec![1; 65 * 1024 * 1024].into())?;
+    db.commit_write_batch(b)?;
+
+    let report = db.verify(None)?;
+    assert!(report.is_ok(), "{:?}", report.issues);
+    assert_eq!(report.sst_files, 2);
+    assert_eq!(report.entries, 2001);
+    assert_eq!(report.blob_files, 1);
+    let report = db.verify(Some(1))?;
+    assert!(report.is_ok(), "{:?}", report.issues);
+    assert_eq!(report.sst_files, 1);
+    assert_eq!(report.entries, 1000);
+    assert_eq!(report.blob_files, 0);
+    db.shutdown()?;
+    drop(db);
+
+    let files = |ext: &str| -> Result<Vec<_>> {
+        let mut files = fs::read_dir(path)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        files.retain(|path| path.extension().is_some_and(|e| e == ext));
+        Ok(files)
+    };
+    let blob_files = files("blob")?;
+    assert_eq!(blob_files.len(), 1);
+    fs::remove_file(&blob_files[0])?;
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let report = db.verify(None)?;
+    assert!(matches!(
+        &report.issues[..],
+        [VerifyIssue::MissingBlob { .. }]
+    ));
+    db.shutdown()?;
+    drop(db);
+
+    // Damage the index block at the end of the SST files
+    for file in files("sst")? {
+        let mut content = fs::read(&file)?;
+        *content.last_mut().unwrap() ^= 0xff;
+        fs::write(&file, content)?;
+    }
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let report = db.verify(None)?;
+    assert_eq!(report.sst_files, 2);
+    assert!(matches!(
+        &report.issues[..],
+        [VerifyIssue::Corrupted { .. }, VerifyIssue::Corrupted { .. }]
+    ));
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn tombstone_purge() -> Result<()> {
+    /// The number of entries and tombstones in the files created by each source.
+    #[derive(Default)]
+    struct Tombstones {
+        write_batch: Mutex<(usize, usize)>,
+        compaction: Mutex<(usize, usize)>,
+    }
+
+    impl PersistenceEventListener for Tombstones {
+        fn on_sst_file_created(&self, info: &SstFileInfo) {
+            assert!(info.tombstones <= info.entries);
+            let mut counts = match info.source {
+                FileSource::Compaction => self.compaction.lock(),
+                _ => self.write_batch.lock(),
+            };
+            counts.0 += info.entries;
+            counts.1 += info.tombstones;
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let tombstones = Arc::new(Tombstones::default());
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            event_listener: Some(tombstones.clone()),
+            ..Default::default()
+        },
+    )?;
+    for range in [0..1000u32, 1000..2000] {
+        let b = db.write_batch::<_, 1>()?;
+        for i in range {
+            b.put(0, i.to_be_bytes(), vec![1].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    let b = db.write_batch::<_, 1>()?;
+    for i in (0..10u32).chain(1000..1010).chain(5000..5010) {
+        b.delete(0, i.to_be_bytes())?;
+    }
+    db.commit_write_batch(b)?;
+    assert_eq!(*tombstones.write_batch.lock(), (2030, 30));
+
+    let check = || -> Result<()> {
+        for i in 0..2000u32 {
+            let value = db.get(0, &i.to_be_bytes())?;
+            if i % 1000 < 10 {
+                assert!(value.is_none());
+            } else {
+                assert_eq!(value.as_deref(), Some(&[1][..]));
+            }
+        }
+        Ok(())
+    };
+    // Only tombstones for keys that older files might contain are kept
+    db.compact(0.0, 2)?;
+    check()?;
+    assert!(tombstones.compaction.lock().1 <= 20);
+
+    // All versions of the deleted keys are purged
+    db.full_compact()?;
+    check()?;
+    assert_eq!(db.verify(None)?.entries, 1980);
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn event_listener() -> Result<()> {
+    #[derive(Default)]
+    struct Counter {
+        flushes: AtomicUsize,
+        sst_files: AtomicUsize,
+        sst_file_duration: Mutex<Duration>,
+        blob_files: AtomicUsize,
+        compactions: AtomicUsize,
+        cache_misses: AtomicUsize,
+    }
+
+    impl PersistenceEventListener for Counter {
+        fn on_flush(&self, info: &FlushInfo) {
+            assert!(info.sst_files > 0);
+            self.flushes.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_sst_file_created(&self, info: &SstFileInfo) {
+            assert!(info.entries > 0 && info.size > 0);
+            assert_eq!(info.family, 0);
+            self.sst_files.fetch_add(1, Ordering::Relaxed);
+            *self.sst_file_duration.lock() += info.duration;
+        }
+
+        fn on_blob_file_created(&self, _info: &BlobFileInfo) {
+            self.blob_files.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_compaction(&self, info: &CompactionInfo) {
+            assert!(info.removed_files > 0);
+            self.compactions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_cache_miss(&self, _cache: CacheKind) {
+            self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let counter = Arc::new(Counter::default());
+    let options = DbOptions {
+        event_listener: Some(counter.clone()),
+        ..Default::default()
+    };
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+    for _ in 0..2 {
+        let b = db.write_batch::<_, 1>()?;
+        for i in 0..100u32 {
+            b.put(0, i.to_be_bytes(), vec![1].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 1000u32.to_be_bytes(), vec![1; 65 * 1024 * 1024].into())?;
+    db.commit_write_batch(b)?;
+    assert_eq!(counter.flushes.load(Ordering::Relaxed), 3);
+    assert_eq!(counter.sst_files.load(Ordering::Relaxed), 3);
+    assert!(*counter.sst_file_duration.lock() > Duration::ZERO);
+    assert_eq!(counter.blob_files.load(Ordering::Relaxed), 1);
+
+    db.full_compact()?;
+    assert_eq!(counter.compactions.load(Ordering::Relaxed), 1);
+    assert!(counter.sst_files.load(Ordering::Relaxed) > 3);
+
+    assert_eq!(db.get(0, &1u32.to_be_bytes())?.as_deref(), Some(&[1][..]));
+    let cache_misses = counter.cache_misses.load(Ordering::Relaxed);
+    assert!(cache_misses > 0);
+    // The second lookup is served from the caches
+    assert_eq!(db.get(0, &1u32.to_be_bytes())?.as_deref(), Some(&[1][..]));
+    assert_eq!(counter.cache_misses.load(Ordering::Relaxed), cache_misses);
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn cache_capacity() -> Result<()> {
+    #[derive(Default)]
+    struct ValueBlockMisses(AtomicUsize);
+
+    impl PersistenceEventListener for ValueBlockMisses {
+        fn on_cache_miss(&self, cache: CacheKind) {
+            if cache == CacheKind::ValueBlock {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let misses = Arc::new(ValueBlockMisses::default());
+    let options = DbOptions {
+        event_listener: Some(misses.clone()),
+        // Too small to hold any value block
+        cache_capacity: CacheCapacity {
+            value_block: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 1u32.to_be_bytes(), vec![1; 1000].into())?;
+    db.commit_write_batch(b)?;
+
+    let check = |expected_misses: usize| -> Result<()> {
+        let value = db.get(0, &1u32.to_be_bytes())?;
+        assert_eq!(value.as_deref(), Some(&vec![1; 1000][..]));
+        assert_eq!(misses.0.load(Ordering::Relaxed), expected_misses);
+        Ok(())
+    };
+    check(1)?;
+    check(2)?;
+    db.set_cache_capacity(CacheCapacity::default());
+    check(3)?;
+    check(3)?;
+    db.set_cache_capacity(CacheCapacity {
+        value_block: 1,
+        ..Default::default()
+    });
+    check(4)?;
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn row_cache() -> Result<()> {
+    #[derive(Default)]
+    struct ValueBlockMisses(AtomicUsize);
+
+    impl PersistenceEventListener for ValueBlockMisses {
+        fn on_cache_miss(&self, cache: CacheKind) {
+            if cache == CacheKind::ValueBlock {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let misses = Arc::new(ValueBlockMisses::default());
+    let options = DbOptions {
+        event_listener: Some(misses.clone()),
+        // Every lookup in a SST file misses the value block cache
+        cache_capacity: CacheCapacity {
+            value_block: 1,
+            row: 1024 * 1024,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+    let put = |family: usize, value: u8| -> Result<()> {
+        let b = db.write_batch::<_, 2>()?;
+        b.put(family, 1u32.to_be_bytes(), vec![value; 1000].into())?;
+        db.commit_write_batch(b)
+    };
+    let check = |expected: Option<u8>, expected_misses: usize| -> Result<()> {
+        let value = db.get(0, &1u32.to_be_bytes())?;
+        assert_eq!(
+            value.as_deref(),
+            expected.map(|expected| vec![expected; 1000]).as_deref()
+        );
+        assert_eq!(misses.0.load(Ordering::Relaxed), expected_misses);
+        Ok(())
+    };
+
+    put(0, 1)?;
+    check(Some(1), 1)?;
+    check(Some(1), 1)?;
+
+    // Writes to other families keep the cached value
+    put(1, 2)?;
+    check(Some(1), 1)?;
+
+    let snapshot = db.snapshot();
+    put(0, 3)?;
+    check(Some(3), 2)?;
+    check(Some(3), 2)?;
+
+    // Snapshots bypass the row cache
+    let value = snapshot.get(0, &1u32.to_be_bytes())?;
+    assert_eq!(value.as_deref(), Some(&vec![1; 1000][..]));
+    assert_eq!(misses.0.load(Ordering::Relaxed), 3);
+    drop(snapshot);
+
+    let b = db.write_batch::<_, 2>()?;
+    b.delete_range(0, 0u32.to_be_bytes(), 2u32.to_be_bytes())?;
+    db.commit_write_batch(b)?;
+    check(None, 3)?;
+
+    // Compaction doesn't change values
+    put(0, 4)?;
+    check(Some(4), 4)?;
+    db.full_compact()?;
+    check(Some(4), 4)?;
+
+    db.set_cache_capacity(CacheCapacity {
+        value_block: 1,
+        row: 0,
+        ..Default::default()
+    });
+    check(Some(4), 5)?;
+    check(Some(4), 6)?;
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn mapped_reads() -> Result<()> {
+    #[derive(Default)]
+    struct BlockMisses(AtomicUsize);
+
+    impl PersistenceEventListener for BlockMisses {
+        fn on_cache_miss(&self, cache: CacheKind) {
+            if cache != CacheKind::Aqmf {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let misses = Arc::new(BlockMisses::default());
+    let options = || DbOptions {
+        // Family 0 is stored uncompressed, family 1 uses the default compression
+        compression: vec![Compression::None],
+        paranoid_checks: true,
+        event_listener: Some(misses.clone()),
+        mapped_reads: true,
+        ..Default::default()
+    };
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..1000u32 {
+        b.put(0, i.to_be_bytes(), vec![i as u8; 100].into())?;
+        b.put(1, i.to_be_bytes(), vec![i as u8; 100].into())?;
+    }
+    b.put(0, 1000u32.to_be_bytes(), vec![7; 100 * 1024].into())?;
+    db.commit_write_batch(b)?;
+    db.shutdown()?;
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+    for i in 0..1000u32 {
+        let value = db.get(0, &i.to_be_bytes())?;
+        assert_eq!(value.as_deref(), Some(&vec![i as u8; 100][..]));
+    }
+    let value = db.get(0, &1000u32.to_be_bytes())?;
+    assert_eq!(value.as_deref(), Some(&vec![7; 100 * 1024][..]));
+    assert!(db.get(0, &1001u32.to_be_bytes())?.is_none());
+    // Uncompressed blocks are read from the mapping and bypass the caches
+    assert_eq!(misses.0.load(Ordering::Relaxed), 0);
+
+    for i in 0..1000u32 {
+        let value = db.get(1, &i.to_be_bytes())?;
+        assert_eq!(value.as_deref(), Some(&vec![i as u8; 100][..]));
+    }
+    assert!(misses.0.load(Ordering::Relaxed) > 0);
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn write_batch_memory_budget() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let options = DbOptions {
+        write_batch_memory_budget: Some(1024 * 1024),
+        ..Default::default()
+    };
+    let count_sst_files = || -> Result<usize> {
+        let mut count = 0;
+        for entry in fs::read_dir(path)? {
+            if entry?.path().extension().is_some_and(|ext| ext == "sst") {
+                count += 1;
+            }
+        }
+        Ok(count)
+    };
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options)?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..10_000u32 {
+        b.put((i % 2) as usize, i.to_be_bytes(), vec![1; 1000].into())?;
+    }
+    // About 10 MB were written, so the buffers were flushed before the commit
+    assert!(count_sst_files()? >= 9);
+    db.commit_write_batch(b)?;
+
+    for i in 0..10_000u32 {
+        assert_eq!(
+            db.get((i % 2) as usize, &i.to_be_bytes())?.as_deref(),
+            Some(&vec![1; 1000][..])
+        );
+    }
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn file_sizes() -> Result<()> {
+    /// The number of SST files created for each family, by write batches and by compactions.
+    #[derive(Default)]
+    struct Files {
+        counts: Mutex<HashMap<(u32, bool), usize>>,
+    }
+
+    impl PersistenceEventListener for Files {
+        fn on_sst_file_created(&self, info: &SstFileInfo) {
+            let compaction = info.source == FileSource::Compaction;
+            *self
+                .counts
+                .lock()
+                .entry((info.family, compaction))
+                .or_default() += 1;
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let files = Arc::new(Files::default());
+    let small = FileSizes {
+        thread_local_collector_size: 64 * 1024,
+        global_collector_size: 128 * 1024,
+        target_file_size: 256 * 1024,
+    };
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            compression: vec![Compression::None; 2],
+            family_file_sizes: vec![None, Some(small)],
+            event_listener: Some(files.clone()),
+            ..Default::default()
+        },
+    )?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..1024u32 {
+        b.put(0, i.to_be_bytes(), vec![0; 1024].into())?;
+        b.put(1, i.to_be_bytes(), vec![1; 1024].into())?;
+    }
+    db.commit_write_batch(b)?;
+    {
+        let counts = files.counts.lock();
+        assert_eq!(counts[&(0, false)], 1);
+        // Full 64 KiB buffers are written while putting, the rest when committing
+        assert!(counts[&(1, false)] >= 15);
+    }
+
+    db.full_compact()?;
+    {
+        let counts = files.counts.lock();
+        // 1 MiB of values in files of 256 KiB
+        assert!(counts[&(1, true)] >= 4);
+    }
+    for i in 0..1024u32 {
+        assert_eq!(
+            db.get(1, &i.to_be_bytes())?.as_deref(),
+            Some(&[1; 1024][..])
+        );
+    }
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn write_rate_limit() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            compression: vec![Compression::None],
+            write_rate_limit: Some(RateLimit {
+                bytes_per_second: 4 * 1024 * 1024,
+                burst: 1024 * 1024,
+            }),
+            ..Default::default()
+        },
+    )?;
+    let start = Instant::now();
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..3 * 1024u32 {
+        b.put(0, i.to_be_bytes(), vec![i as u8; 1024].into())?;
+    }
+    db.commit_write_batch(b)?;
+    // 3 MiB need at least 0.5s after the burst
+    assert!(start.elapsed() >= Duration::from_millis(450));
+
+    for i in 0..3 * 1024u32 {
+        let value = db.get(0, &i.to_be_bytes())?;
+        assert_eq!(value.as_deref(), Some(&vec![i as u8; 1024][..]));
+    }
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn sync_policy_and_direct_io() -> Result<()> {
+    for sync_policy in [SyncPolicy::PerFile, SyncPolicy::Batched] {
+        let tempdir = tempfile::tempdir()?;
+        let path = tempdir.path();
+        let options = || DbOptions {
+            // Uncompressed, so files are larger than the direct I/O buffer
+            compression: vec![Compression::None],
+            direct_io: true,
+            sync_policy,
+            ..Default::default()
+        };
+
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+        for round in 0..2u32 {
+            let b = db.write_batch::<_, 1>()?;
+            for i in 0..10_000u32 {
+                b.put(
+                    0,
+                    i.to_be_bytes(),
+                    (i + round).to_be_bytes().to_vec().into(),
+                )?;
+            }
+            // Not a multiple of the direct I/O alignment
+            b.put(
+                0,
+                10_000u32.to_be_bytes(),
+                vec![round as u8; 65 * 1024 * 1024 + 7].into(),
+            )?;
+            db.commit_write_batch(b)?;
+        }
+        db.full_compact()?;
+        db.shutdown()?;
+
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+        for i in 0..10_000u32 {
+            let value = db.get(0, &i.to_be_bytes())?;
+            assert_eq!(value.as_deref(), Some(&(i + 1).to_be_bytes()[..]));
+        }
+        let value = db.get(0, &10_000u32.to_be_bytes())?;
+        assert_eq!(value.as_deref(), Some(&vec![1; 65 * 1024 * 1024 + 7][..]));
+        db.shutdown()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn gc_blobs() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let blob = |byte: u8| vec![byte; 65 * 1024 * 1024];
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 1u32.to_be_bytes(), blob(1).into())?;
+    b.put(0, 2u32.to_be_bytes(), blob(2).into())?;
+    db.commit_write_batch(b)?;
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 1u32.to_be_bytes(), blob(3).into())?;
+    b.delete(0, 2u32.to_be_bytes())?;
+    db.commit_write_batch(b)?;
+
+    // The old entries are still in the SST files
+    let stats = db.gc_blobs()?;
+    assert_eq!(stats.live_blob_files, 3);
+    assert_eq!(stats.removed_blob_files, 0);
+
+    let snapshot = db.snapshot();
+    db.full_compact()?;
+    // The snapshot still reads the replaced SST files
+    let stats = db.gc_blobs()?;
+    assert_eq!(stats.live_blob_files, 3);
+    assert_eq!(stats.removed_blob_files, 0);
+    assert_eq!(
+        snapshot.get(0, &2u32.to_be_bytes())?.as_deref(),
+        Some(&blob(2)[..])
+    );
+    drop(snapshot);
+
+    let stats = db.gc_blobs()?;
+    assert_eq!(stats.live_blob_files, 1);
+    assert_eq!(stats.removed_blob_files, 2);
+    assert!(stats.removed_bytes > 0);
+    assert_eq!(
+        db.get(0, &1u32.to_be_bytes())?.as_deref(),
+        Some(&blob(3)[..])
+    );
+    assert!(db.get(0, &2u32.to_be_bytes())?.is_none());
+    db.shutdown()?;
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    assert_eq!(
+        db.get(0, &1u32.to_be_bytes())?.as_deref(),
+        Some(&blob(3)[..])
+    );
+    assert_eq!(db.gc_blobs()?.removed_blob_files, 0);
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn get_reader() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    // Not compressible and not a multiple of the chunk size
+    let mut rng = SmallRng::seed_from_u64(42);
+    let mut blob = vec![0u8; 70 * 1024 * 1024 + 123];
+    rng.fill_bytes(&mut blob);
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 1u32.to_be_bytes(), blob.clone().into())?;
+    b.put(0, 2u32.to_be_bytes(), vec![2; 1000].into())?;
+    db.commit_write_batch(b)?;
+
+    let mut reader = db.get_reader(0, &1u32.to_be_bytes())?.unwrap();
+    assert_eq!(reader.len(), blob.len());
+    // Read in small steps, which cross chunk boundaries
+    let mut value = Vec::new();
+    let mut buf = vec![0; 1000 * 1000];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        value.extend_from_slice(&buf[..n]);
+    }
+    assert!(value == blob);
+    assert!(db.get(0, &1u32.to_be_bytes())?.as_deref() == Some(&blob[..]));
+
+    let mut value = Vec::new();
+    db.get_reader(0, &2u32.to_be_bytes())?
+        .unwrap()
+        .read_to_end(&mut value)?;
+    assert_eq!(value, vec![2; 1000]);
+    assert!(db.get_reader(0, &3u32.to_be_bytes())?.is_none());
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn deduplicate_blobs() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let options = || DbOptions {
+        deduplicate_blobs: true,
+        ..Default::default()
+    };
+    let blob = |byte: u8| vec![byte; 65 * 1024 * 1024];
+    let count_blob_files = || -> Result<usize> {
+        let mut count = 0;
+        for entry in fs::read_dir(path)? {
+            if entry?.path().extension().is_some_and(|ext| ext == "blob") {
+                count += 1;
+            }
+        }
+        Ok(count)
+    };
+
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 1u32.to_be_bytes(), blob(1).into())?;
+    // Same value in the same write batch
+    b.put(0, 2u32.to_be_bytes(), blob(1).into())?;
+    b.put(0, 3u32.to_be_bytes(), blob(3).into())?;
+    db.commit_write_batch(b)?;
+    assert_eq!(count_blob_files()?, 2);
+    db.shutdown()?;
+
+    // The blob index is restored from the blob files
+    let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 4u32.to_be_bytes(), blob(3).into())?;
+    db.commit_write_batch(b)?;
+    assert_eq!(count_blob_files()?, 2);
+    for (key, byte) in [(1u32, 1), (2, 1), (3, 3), (4, 3)] {
+        assert_eq!(
+            db.get(0, &key.to_be_bytes())?.as_deref(),
+            Some(&blob(byte)[..])
+        );
+    }
+
+    // A blob file is only removed when no key references it anymore
+    let b = db.write_batch::<_, 1>()?;
+    b.delete(0, 1u32.to_be_bytes())?;
+    b.delete(0, 3u32.to_be_bytes())?;
+    b.delete(0, 4u32.to_be_bytes())?;
+    db.commit_write_batch(b)?;
+    db.full_compact()?;
+    assert_eq!(db.gc_blobs()?.removed_blob_files, 1);
+    assert_eq!(
+        db.get(0, &2u32.to_be_bytes())?.as_deref(),
+        Some(&blob(1)[..])
+    );
+
+    // The removed blob file is written again
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 5u32.to_be_bytes(), blob(3).into())?;
+    db.commit_write_batch(b)?;
+    assert_eq!(count_blob_files()?, 2);
+    assert_eq!(
+        db.get(0, &5u32.to_be_bytes())?.as_deref(),
+        Some(&blob(3)[..])
+    );
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn concurrent_write_batches() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let first = db.write_batch::<_, 1>()?;
+    let second = db.write_batch::<_, 1>()?;
+    // Exclusive write operations fail while write batches are open
+    assert!(db.full_compact().is_err());
+    for i in 0..1000u32 {
+        first.put(0, i.to_be_bytes(), vec![1].into())?;
+        second.put(0, (i + 500).to_be_bytes(), vec![2].into())?;
+    }
+    // Deletes keys of the first write batch, since it was started before
+    second.delete_range(0, 0u32.to_be_bytes(), 100u32.to_be_bytes())?;
+    std::thread::scope(|scope| -> Result<()> {
+        // Waits for the first write batch to be committed
+        let handle = scope.spawn(|| db.commit_write_batch(second));
+        db.commit_write_batch(first)?;
+        handle.join().unwrap()
+    })?;
+
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..1500u32 {
+            let value = db.get(0, &i.to_be_bytes())?;
+            let expected: Option<&[u8]> = match i {
+                0..100 => None,
+                100..500 => Some(&[1]),
+                _ => Some(&[2]),
+            };
+            assert_eq!(value.as_deref(), expected, "key {i}");
+        }
+        Ok(())
+    };
+    check(&db)?;
+    db.full_compact()?;
+    check(&db)?;
+    db.shutdown()?;
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    check(&db)?;
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn absorb_write_batch() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let first = db.write_batch::<_, 1>()?;
+    let second = db.write_batch::<_, 1>()?;
+    let mut third = db.write_batch::<_, 1>()?;
+    for i in 0..3000u32 {
+        first.put(0, i.to_be_bytes(), vec![1].into())?;
+    }
+    // The write batches are filled in parallel
+    std::thread::scope(|scope| -> Result<()> {
+        let handle = scope.spawn(|| -> Result<()> {
+            for i in 0..1000u32 {
+                second.put(0, i.to_be_bytes(), vec![2].into())?;
+            }
+            Ok(())
+        });
+        for i in 1000..2000u32 {
+            third.put(0, i.to_be_bytes(), vec![3].into())?;
+        }
+        third.delete_range(0, 2000u32.to_be_bytes(), 2100u32.to_be_bytes())?;
+        handle.join().unwrap()
+    })?;
+    // The older write batch is absorbed into the newer one
+    third.absorb(second)?;
+    assert_eq!(third.pending_entries(), 2000);
+    db.commit_write_batch(first)?;
+    db.commit_write_batch(third)?;
+
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..3000u32 {
+            let value = db.get(0, &i.to_be_bytes())?;
+            let expected: Option<&[u8]> = match i {
+                0..1000 => Some(&[2]),
+                1000..2000 => Some(&[3]),
+                2000..2100 => None,
+                _ => Some(&[1]),
+            };
+            assert_eq!(value.as_deref(), expected, "key {i}");
+        }
+        Ok(())
+    };
+    check(&db)?;
+    // Both write batches were committed, so new write batches can be committed
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 3000u32.to_be_bytes(), vec![4].into())?;
+    db.commit_write_batch(b)?;
+    db.shutdown()?;
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    check(&db)?;
+    assert_eq!(
+        db.get(0, &3000u32.to_be_bytes())?.as_deref(),
+        Some(&[4][..])
+    );
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn compact_on_open() -> Result<()> {
+    #[derive(Default)]
+    struct Compactions(Mutex<Vec<Option<u32>>>);
+
+    impl PersistenceEventListener for Compactions {
+        fn on_compaction(&self, info: &CompactionInfo) {
+            self.0.lock().push(info.family);
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for round in 0..10u32 {
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..100u32 {
+            b.put(0, (round * 100 + i).to_be_bytes(), vec![1].into())?;
+        }
+        if round < 2 {
+            b.put(1, round.to_be_bytes(), vec![2].into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    db.shutdown()?;
+
+    let open = || {
+        let compactions = Arc::new(Compactions::default());
+        let db = TurboPersistence::open_with_options(
+            path.to_path_buf(),
+            DbOptions {
+                event_listener: Some(compactions.clone()),
+                compact_on_open: Some(CompactOnOpen {
+                    max_files_per_family: 4,
+                    max_merge_sequence: usize::MAX,
+                }),
+                ..Default::default()
+            },
+        )?;
+        let compactions = std::mem::take(&mut *compactions.0.lock());
+        anyhow::Ok((db, compactions))
+    };
+
+    // Only the family with more files than the limit is compacted
+    let (db, compactions) = open()?;
+    assert_eq!(compactions, vec![Some(0)]);
+    for i in 0..1000u32 {
+        assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+    }
+    assert_eq!(db.get(1, &1u32.to_be_bytes())?.as_deref(), Some(&[2][..]));
+    db.shutdown()?;
+
+    // The compacted family is below the limit now
+    let (db, compactions) = open()?;
+    assert!(compactions.is_empty());
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn lock() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    // Another instance can't open or repair the database while it's open
+    for result in [
+        TurboPersistence::open(path.to_path_buf()).map(|_| ()),
+        TurboPersistence::repair(path.to_path_buf()).map(|_| ()),
+    ] {
+        let error = result.unwrap_err();
+        let locked = error.downcast_ref::<LockedError>().unwrap();
+        assert_eq!(locked.path, path);
+        assert_eq!(locked.pid, Some(std::process::id()));
+    }
+    db.shutdown()?;
+
+    // The lock is released on shutdown
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    drop(db);
+    // and when the database is dropped
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn manifest() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let check = |db: &TurboPersistence| -> Result<()> {
+        for i in 0..100u32 {
+            let value = db.get(0, &i.to_be_bytes())?;
+            assert_eq!(value.as_deref(), Some(&[(i % 2) as u8 + 1][..]));
+        }
+        Ok(())
+    };
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    // The second write batch starts after the reserved sequence numbers of the first one
+    let first = db.write_batch::<_, 1>()?;
+    let second = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        let b = if i % 2 == 0 { &first } else { &second };
+        b.put(0, i.to_be_bytes(), vec![(i % 2) as u8 + 1].into())?;
+    }
+    db.commit_write_batch(first)?;
+    db.commit_write_batch(second)?;
+    check(&db)?;
+    db.shutdown()?;
+
+    // Files of a write batch that was not committed, with sequence numbers below the one in the
+    // CURRENT file, are removed on open
+    fs::copy(path.join("00000001.sst"), path.join("00000002.sst"))?;
+    fs::write(path.join("00000003.blob"), [0; 100])?;
+    // An incomplete record at the end of the manifest is ignored
+    let mut manifest = fs::read(path.join("MANIFEST"))?;
+    manifest.extend_from_slice(&[0, 0, 0, 100, 1, 2, 3]);
+    fs::write(path.join("MANIFEST"), manifest)?;
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    assert!(!path.join("00000002.sst").exists());
+    assert!(!path.join("00000003.blob").exists());
+    check(&db)?;
+    db.shutdown()?;
+
+    // Without a manifest, all files up to the sequence number in the CURRENT file are committed
+    fs::remove_file(path.join("MANIFEST"))?;
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    assert!(path.join("MANIFEST").exists());
+    check(&db)?;
+    let b = db.write_batch::<_, 1>()?;
+    b.put(0, 100u32.to_be_bytes(), vec![1].into())?;
+    db.commit_write_batch(b)?;
+    db.shutdown()?;
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    check(&db)?;
+    assert_eq!(db.get(0, &100u32.to_be_bytes())?.as_deref(), Some(&[1][..]));
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn key_comparator() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    // Family 0 stores numbers in little endian, which don't sort by their bytes
+    let little_endian = || {
+        KeyComparator::new("u32_le", |a, b| {
+            let a = u32::from_le_bytes(a.try_into().unwrap());
+            let b = u32::from_le_bytes(b.try_into().unwrap());
+            a.cmp(&b)
+        })
+    };
+    let options = |key_comparators: Vec<Option<KeyComparator>>| DbOptions {
+        key_comparators,
+        ..Default::default()
+    };
+    fn keys_in(db: &TurboPersistence, range: impl RangeBounds<[u8; 4]>) -> Result<Vec<u32>> {
+        let mut keys = db
+            .range(0, range)?
+            .map(|entry| Ok(u32::from_le_bytes((*entry?.0).try_into().unwrap())))
+            .collect::<Result<Vec<_>>>()?;
+        keys.sort_unstable();
+        Ok(keys)
+    }
+
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        options(vec![Some(little_endian())]),
+    )?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..1000u32 {
+        b.put(0, i.to_le_bytes(), vec![1].into())?;
+    }
+    db.commit_write_batch(b)?;
+    let b = db.write_batch::<_, 2>()?;
+    b.delete_range(0, 30u32.to_le_bytes(), 40u32.to_le_bytes())?;
+    db.commit_write_batch(b)?;
+    // In byte order the range would also contain e. g. 266, which starts with the byte 10
+    assert_eq!(
+        keys_in(&db, 10u32.to_le_bytes()..20u32.to_le_bytes())?,
+        (10..20).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        keys_in(&db, 25u32.to_le_bytes()..45u32.to_le_bytes())?,
+        (25..30).chain(40..45).collect::<Vec<_>>()
+    );
+    // Reverse iteration is ordered by the comparator
+    let backward = db
+        .range_rev(0, 25u32.to_le_bytes()..45u32.to_le_bytes())?
+        .map(|entry| Ok(u32::from_le_bytes((*entry?.0).try_into().unwrap())))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(backward, (25..30).chain(40..45).rev().collect::<Vec<_>>());
+    assert_eq!(db.get(0, &300u32.to_le_bytes())?.as_deref(), Some(&[1][..]));
+    db.shutdown()?;
+
+    // The comparator of a family with data can't be changed
+    for key_comparators in [
+        vec![],
+        vec![Some(KeyComparator::new("other", |a, b| a.cmp(b)))],
+    ] {
+        let error =
+            TurboPersistence::open_with_options(path.to_path_buf(), options(key_comparators))
+                .err()
+                .unwrap();
+        assert!(format!("{error:#}").contains("key comparator u32_le"));
+    }
+
+    // but the one of a family without data can
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        options(vec![
+            Some(little_endian()),
+            Some(KeyComparator::new("other", |a, b| a.cmp(b))),
+        ]),
+    )?;
+    assert_eq!(
+        keys_in(&db, 30u32.to_le_bytes()..=40u32.to_le_bytes())?,
+        vec![40]
+    );
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn prefix_compressed_keys() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    const PREFIX: &[u8] = b"TaskType::some_module::SomeLongTaskFunctionName/";
+    let key = |i: u32| [PREFIX, PREFIX, &i.to_be_bytes()].concat();
+    let sst_size = || -> Result<u64> {
+        let mut size = 0;
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.path().extension().is_some_and(|ext| ext == "sst") {
+                size += entry.metadata()?.len();
+            }
+        }
+        Ok(size)
+    };
+    fn keys(iter: impl Iterator<Item = Result<(ArcSlice<u8>, ArcSlice<u8>)>>) -> Result<Vec<u32>> {
+        iter.map(|entry| {
+            let (key, value) = entry?;
+            let i = u32::from_be_bytes(<[u8; 4]>::try_from(&key[key.len() - 4..])?);
+            assert_eq!(&*value, &[i as u8]);
+            Ok(i)
+        })
+        .collect()
+    }
+
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            compression: vec![Compression::None],
+            ..Default::default()
+        },
+    )?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..10000u32 {
+        b.put(0, key(i), vec![i as u8].into())?;
+    }
+    db.commit_write_batch(b)?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in (0..10000u32).step_by(7) {
+        b.delete(0, key(i))?;
+    }
+    db.commit_write_batch(b)?;
+    db.full_compact()?;
+
+    // Only the restart points store the full key, so the file is much smaller than the keys
+    assert!(sst_size()? < 10000 * key(0).len() as u64 / 2);
+    for i in 0..10000u32 {
+        let value = db.get(0, &key(i))?;
+        if i % 7 == 0 {
+            assert!(value.is_none());
+        } else {
+            assert_eq!(value.as_deref(), Some(&[i as u8][..]));
+        }
+    }
+    assert!(db.get(0, &key(10000))?.is_none());
+    assert!(db.get(0, &PREFIX)?.is_none());
+
+    let mut forward = keys(db.range(0, ..)?)?;
+    forward.sort_unstable();
+    let mut backward = keys(db.range_rev(0, ..)?)?;
+    backward.reverse();
+    assert_eq!(forward.len(), 10000 - 1429);
+    assert_eq!(forward, backward);
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn partitioned_index() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    // Enough keys for multiple AQMF partitions and more key blocks than a single index block
+    // points to
+    const COUNT: u32 = 250000;
+    let key = |i: u32| i.to_be_bytes().repeat(16);
+
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            compression: vec![Compression::None],
+            ..Default::default()
+        },
+    )?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..COUNT {
+        b.put(0, key(i), vec![i as u8].into())?;
+    }
+    db.commit_write_batch(b)?;
+
+    for i in (0..COUNT).step_by(7) {
+        assert_eq!(db.get(0, &key(i))?.as_deref(), Some(&[i as u8][..]));
+    }
+    for i in COUNT..COUNT + 1000 {
+        assert!(db.get(0, &key(i))?.is_none());
+    }
+    assert_eq!(db.range::<&[u8]>(0, ..)?.count(), COUNT as usize);
+
+    let mut cursor = db.cursor(0)?;
+    for _ in 0..1000 {
+        let (key, value) = cursor.next()?.unwrap();
+        assert_eq!(db.get(0, &key)?.as_deref(), Some(&*value));
+    }
+    for i in (0..COUNT).step_by(997) {
+        let (found, value) = cursor.seek(&key(i))?.unwrap();
+        assert_eq!(&*found, &key(i)[..]);
+        assert_eq!(&*value, &[i as u8]);
+    }
+    assert!(cursor.seek_to_last()?.is_some());
+
+    let size = db.approximate_size::<&[u8]>(0, ..)?;
+    let mut sst_size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.path().extension().is_some_and(|ext| ext == "sst") {
+            sst_size += entry.metadata()?.len();
+        }
+    }
+    assert_eq!(size, sst_size);
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn trace() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let trace_path = path.join("trace");
+    let db_path = path.join("db");
+
+    let db = TurboPersistence::open_with_options(
+        db_path,
+        DbOptions {
+            trace: Some(trace_path.clone()),
+            ..Default::default()
+        },
+    )?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..100u32 {
+        b.put(i as usize % 2, i.to_be_bytes(), vec![0; i as usize].into())?;
+    }
+    b.delete(0, 10u32.to_be_bytes())?;
+    db.commit_write_batch(b)?;
+    assert_eq!(
+        db.get(1, &11u32.to_be_bytes())?.as_deref(),
+        Some(&[0; 11][..])
+    );
+    assert!(db.get(0, &10u32.to_be_bytes())?.is_none());
+    let values = db.get_many(0, &[2u32.to_be_bytes(), 3u32.to_be_bytes()])?;
+    assert!(values[0].is_some());
+    assert!(values[1].is_none());
+    // A write batch that is never committed
+    let b = db.write_batch::<_, 2>()?;
+    b.put(0, 1000u32.to_be_bytes(), vec![1].into())?;
+    drop(b);
+    db.shutdown()?;
+
+    let records = read_trace(&trace_path)?;
+    let operations = records
+        .iter()
+        .map(|record| record.operation.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(operations.len(), 1 + 101 + 1 + 4 + 2);
+    assert_eq!(operations[0], TraceOperation::WriteBatch { write_batch: 0 });
+    assert_eq!(
+        operations[12],
+        TraceOperation::Put {
+            write_batch: 0,
+            family: 1,
+            key: 11u32.to_be_bytes().to_vec(),
+            value_size: 11,
+        }
+    );
+    assert_eq!(
+        operations[101],
+        TraceOperation::Delete {
+            write_batch: 0,
+            family: 0,
+            key: 10u32.to_be_bytes().to_vec(),
+        }
+    );
+    assert_eq!(operations[102], TraceOperation::Commit { write_batch: 0 });
+    assert_eq!(
+        operations[103],
+        TraceOperation::Get {
+            family: 1,
+            key: 11u32.to_be_bytes().to_vec(),
+            value_size: Some(11),
+        }
+    );
+    assert_eq!(
+        operations[104],
+        TraceOperation::Get {
+            family: 0,
+            key: 10u32.to_be_bytes().to_vec(),
+            value_size: None,
+        }
+    );
+    assert_eq!(
+        operations[107],
+        TraceOperation::WriteBatch { write_batch: 1 }
+    );
+    assert!(records
+        .windows(2)
+        .all(|records| records[0].timestamp <= records[1].timestamp));
+
+    let db = TurboPersistence::open(path.join("replay"))?;
+    let stats = replay_trace(&db, &records)?;
+    assert_eq!(stats.writes, 102);
+    assert_eq!(stats.commits, 1);
+    assert_eq!(stats.gets, 4);
+    assert_eq!(stats.mismatched_gets, 0);
+    for i in 0..100u32 {
+        let value = db.get(i as usize % 2, &i.to_be_bytes())?;
+        if i == 10 {
+            assert!(value.is_none());
+        } else {
+            assert_eq!(value.map(|value| value.len()), Some(i as usize));
+        }
+    }
+    assert!(db.get(0, &1000u32.to_be_bytes())?.is_none());
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn conditional_writes() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let db = TurboPersistence::open(path.to_path_buf())?;
+
+    assert!(db.put_if_absent(0, 1u32.to_be_bytes(), vec![1].into())?);
+    assert!(!db.put_if_absent(0, 1u32.to_be_bytes(), vec![2].into())?);
+    assert_eq!(db.get(0, &1u32.to_be_bytes())?.as_deref(), Some(&[1][..]));
+
+    assert!(!db.compare_and_swap(0, 1u32.to_be_bytes(), Some(&[2][..]), Some(vec![3].into()))?);
+    assert!(!db.compare_and_swap(0, 1u32.to_be_bytes(), None, Some(vec![3].into()))?);
+    assert_eq!(db.get(0, &1u32.to_be_bytes())?.as_deref(), Some(&[1][..]));
+    assert!(db.compare_and_swap(0, 1u32.to_be_bytes(), Some(&[1][..]), Some(vec![3].into()))?);
+    assert_eq!(db.get(0, &1u32.to_be_bytes())?.as_deref(), Some(&[3][..]));
+    // Deleting the key
+    assert!(db.compare_and_swap(0, 1u32.to_be_bytes(), Some(&[3][..]), None)?);
+    assert!(db.get(0, &1u32.to_be_bytes())?.is_none());
+    assert!(db.put_if_absent(0, 1u32.to_be_bytes(), vec![4].into())?);
+
+    // Only one of the concurrent writes takes effect
+    let written = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for i in 0..8u8 {
+            let db = &db;
+            let written = &written;
+            scope.spawn(move || {
+                if db
+                    .put_if_absent(1, 2u32.to_be_bytes(), vec![i].into())
+                    .unwrap()
+                {
+                    written.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    assert_eq!(written.load(Ordering::Relaxed), 1);
+    // Increments don't get lost
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            let db = &db;
+            scope.spawn(move || {
+                for _ in 0..10 {
+                    loop {
+                        let current = db.get(2, &3u32.to_be_bytes()).unwrap();
+                        let count = current.as_deref().map_or(0, |value| value[0]);
+                        if db
+                            .compare_and_swap(
+                                2,
+                                3u32.to_be_bytes(),
+                                current.as_deref(),
+                                Some(vec![count + 1].into()),
+                            )
+                            .unwrap()
+                        {
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    assert_eq!(db.get(2, &3u32.to_be_bytes())?.as_deref(), Some(&[40][..]));
+    db.shutdown()?;
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    assert_eq!(db.get(0, &1u32.to_be_bytes())?.as_deref(), Some(&[4][..]));
+    assert_eq!(db.get(2, &3u32.to_be_bytes())?.as_deref(), Some(&[40][..]));
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn ingest_sorted() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    {
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        let mut keys = (0..10000u32)
+            .map(|i| i.to_be_bytes().to_vec())
+            .collect::<Vec<_>>();
+        keys.sort_by_key(|key| (hash_key(key), key.clone()));
+        let b = db.write_batch::<Vec<u8>, 2>()?;
+        b.ingest_sorted(
+            0,
+            keys.iter()
+                .map(|key| (key.clone(), key[2..].to_vec().into())),
+        )?;
+        // Unsorted entries are stored correctly as well
+        b.ingest_sorted(
+            1,
+            (0..100u32)
+                .rev()
+                .map(|i| (i.to_be_bytes().to_vec(), vec![1].into())),
+        )?;
+        b.put(1, vec![42], vec![2].into())?;
+        db.commit_write_batch(b)?;
+
+        let entries = db
+            .range::<Vec<u8>>(0, ..)?
+            .map(|entry| entry.map(|(key, _)| key.to_vec()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(entries, keys);
+        db.shutdown()?;
+    }
+    {
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        for i in 0..10000u32 {
+            let key = i.to_be_bytes();
+            assert_eq!(db.get(0, &key)?.as_deref(), Some(&key[2..]));
+        }
+        for i in 0..100u32 {
+            assert_eq!(db.get(1, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+        }
+        assert_eq!(db.get(1, &[42])?.as_deref(), Some(&[2][..]));
+        db.shutdown()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn pending_entries() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let db = TurboPersistence::open(path.to_path_buf())?;
+
+    let b = db.write_batch::<Vec<u8>, 3>()?;
+    assert_eq!(b.pending_entries(), 0);
+    assert_eq!(b.pending_bytes(), 0);
+    (0..100u32)
+        .into_par_iter()
+        .try_for_each(|i| b.put(0, i.to_be_bytes().to_vec(), vec![0; 10].into()))?;
+    b.delete(1, vec![1, 2])?;
+    b.merge(1, vec![3], vec![4; 5].into())?;
+    b.delete_range(2, vec![0], vec![1])?;
+    b.ingest_sorted(
+        2,
+        (0..10u32).map(|i| (i.to_be_bytes().to_vec(), vec![0; 6].into())),
+    )?;
+    assert_eq!(b.pending_entries(), 112);
+    assert_eq!(b.pending_bytes(), 1400 + 8 + 100);
+    assert_eq!(
+        b.pending_families(),
+        [
+            PendingEntries {
+                entries: 100,
+                bytes: 1400,
+            },
+            PendingEntries {
+                entries: 2,
+                bytes: 8,
+            },
+            PendingEntries {
+                entries: 10,
+                bytes: 100,
+            },
+        ]
+    );
+    // Entries of ingest_sorted are written to SST files directly
+    assert_eq!(b.buffered_bytes(), 1400 + 8);
+    db.commit_write_batch(b)?;
+
+    // A reused write batch starts empty
+    let b = db.write_batch::<Vec<u8>, 3>()?;
+    assert_eq!(b.pending_entries(), 0);
+    assert_eq!(b.pending_families(), [PendingEntries::default(); 3]);
+    assert_eq!(b.buffered_bytes(), 0);
+    db.commit_write_batch(b)?;
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn typed_families() -> Result<()> {
+    struct TaskCache;
+    impl Family for TaskCache {
+        const INDEX: usize = 0;
+        type Key = u32;
+    }
+    struct Cells;
+    impl Family for Cells {
+        const INDEX: usize = 1;
+        type Key = (u64, [u8; 2]);
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<Vec<u8>, 2>()?;
+    for i in 0..100u32 {
+        b.put_typed::<TaskCache>(&i, vec![i as u8].into())?;
+        b.put_typed::<Cells>(&(i as u64, [1, 2]), vec![i as u8; 2].into())?;
+    }
+    b.delete_typed::<TaskCache>(&7)?;
+    db.commit_write_batch(b)?;
+
+    for i in 0..100u32 {
+        let value = db.get_typed::<TaskCache>(&i)?;
+        if i == 7 {
+            assert!(value.is_none());
+        } else {
+            assert_eq!(value.as_deref(), Some(&[i as u8][..]));
+        }
+        assert_eq!(
+            db.get_typed::<Cells>(&(i as u64, [1, 2]))?.as_deref(),
+            Some(&[i as u8; 2][..])
+        );
+        assert!(db.get_typed::<Cells>(&(i as u64, [1, 3]))?.is_none());
+    }
+    // The keys are stored as bytes
+    assert_eq!(db.get(0, &5u32.to_be_bytes())?.as_deref(), Some(&[5][..]));
+    let mut key = 5u64.to_be_bytes().to_vec();
+    key.extend_from_slice(&[1, 2]);
+    assert_eq!(db.get(1, &key)?.as_deref(), Some(&[5, 5][..]));
+    let values = db.get_many_typed::<TaskCache>(&[1, 7, 1000])?;
+    assert_eq!(values[0].as_deref(), Some(&[1][..]));
+    assert!(values[1].is_none());
+    assert!(values[2].is_none());
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn cursor() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    // Multiple SST files with overwritten and deleted keys
+    for round in 0..3u32 {
+        let b = db.write_batch::<_, 1>()?;
+        for i in (round..3000).step_by(2) {
+            if i % 7 == 0 {
+                b.delete(0, i.to_be_bytes())?;
+            } else {
+                b.put(0, i.to_be_bytes(), round.to_be_bytes().to_vec().into())?;
+            }
+        }
+        db.commit_write_batch(b)?;
+    }
+    let entries = db
+        .range::<&[u8]>(0, ..)?
+        .map(|entry| entry.map(|(key, value)| (key.to_vec(), value.to_vec())))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(entries.len(), 3000 - 429);
+    let entry = |entry: Option<(ArcSlice<u8>, ArcSlice<u8>)>| {
+        entry.map(|(key, value)| (key.to_vec(), value.to_vec()))
+    };
+
+    let mut cursor = db.cursor(0)?;
+    assert!(cursor.prev()?.is_none());
+    let mut forward = Vec::new();
+    while let Some(e) = entry(cursor.next()?) {
+        forward.push(e);
+    }
+    assert_eq!(forward, entries);
+    let mut backward = Vec::new();
+    while let Some(e) = entry(cursor.prev()?) {
+        backward.push(e);
+    }
+    backward.reverse();
+    assert_eq!(backward, entries);
+
+    for index in [0, 1, 17, 1000, entries.len() - 1] {
+        let (key, _) = &entries[index];
+        assert_eq!(entry(cursor.seek(key)?).as_ref(), Some(&entries[index]));
+        assert_eq!(entry(cursor.next()?).as_ref(), entries.get(index + 1));
+        assert_eq!(entry(cursor.prev()?).as_ref(), Some(&entries[index]));
+        if index > 0 {
+            assert_eq!(entry(cursor.prev()?).as_ref(), Some(&entries[index - 1]));
+        } else {
+            assert!(cursor.prev()?.is_none());
+        }
+    }
+    // Seeking a deleted key moves to the following entry
+    let deleted = 7u32.to_be_bytes();
+    let next = entry(cursor.seek(&deleted)?).unwrap();
+    assert!(entries.contains(&next));
+    assert_ne!(next.0, deleted);
+
+    assert_eq!(entry(cursor.seek_to_first()?).as_ref(), entries.first());
+    assert_eq!(entry(cursor.seek_to_last()?).as_ref(), entries.last());
+    assert!(cursor.next()?.is_none());
+    assert_eq!(entry(cursor.prev()?).as_ref(), entries.last());
+
+    // Writes after the cursor was created are not visible to it
+    let b = db.write_batch::<_, 1>()?;
+    b.delete(0, entries[0].0.clone())?;
+    db.commit_write_batch(b)?;
+    assert_eq!(entry(cursor.seek_to_first()?).as_ref(), entries.first());
+    assert_eq!(
+        entry(db.cursor(0)?.seek_to_first()?).as_ref(),
+        entries.get(1)
+    );
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn approximate_size() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let sst_size = || -> Result<u64> {
+        let mut size = 0;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.path().extension().is_some_and(|ext| ext == "sst") {
+                size += entry.metadata()?.len();
+            }
+        }
+        Ok(size)
+    };
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 2>()?;
+    for i in 0..20000u32 {
+        let prefix = if i % 4 == 0 { b"a" } else { b"b" };
+        b.put(
+            0,
+            [&prefix[..], &i.to_be_bytes()].concat(),
+            vec![0; 100].into(),
+        )?;
+    }
+    b.put(1, b"a".to_vec(), vec![0; 100].into())?;
+    db.commit_write_batch(b)?;
+
+    let total = db.approximate_size::<&[u8]>(0, ..)?;
+    let family_1 = db.approximate_size::<&[u8]>(1, ..)?;
+    assert_eq!(total + family_1, sst_size()?);
+    let a = db.approximate_size(0, &b"a"[..]..&b"b"[..])?;
+    let b = db.approximate_size(0, &b"b"[..]..)?;
+    assert!(a > total / 5 && a < total * 3 / 10, "{a} of {total}");
+    assert!(b > total * 7 / 10 && b < total * 4 / 5, "{b} of {total}");
+    assert_eq!(db.approximate_size(0, &b"c"[..]..)?, 0);
+    assert_eq!(db.approximate_size(2, &b"a"[..]..)?, 0);
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn async_read() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = Arc::new(TurboPersistence::open(path.to_path_buf())?);
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..100u32 {
+        b.put(0, i.to_be_bytes(), i.to_be_bytes().to_vec().into())?;
+    }
+    db.commit_write_batch(b)?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        for i in 0..100u32 {
+            let value = db.get_async(0, i.to_be_bytes()).await?;
+            assert_eq!(value.as_deref(), Some(&i.to_be_bytes()[..]));
+        }
+        let keys = (0..200u32).map(u32::to_be_bytes).collect::<Vec<_>>();
+        let values = db.get_many_async(0, keys).await?;
+        assert_eq!(values[50].as_deref(), Some(&50u32.to_be_bytes()[..]));
+        assert_eq!(values[150], None);
+        anyhow::Ok(())
+    })?;
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn thread_pool() -> Result<()> {
+    #[derive(Default)]
+    struct Threads(Mutex<Vec<Option<String>>>);
+
+    impl PersistenceEventListener for Threads {
+        fn on_sst_file_created(&self, _info: &SstFileInfo) {
+            let name = std::thread::current().name().map(str::to_string);
+            self.0.lock().push(name);
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let threads = Arc::new(Threads::default());
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .thread_name(|i| format!("persistence-{i}"))
+        .build()?;
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            event_listener: Some(threads.clone()),
+            thread_pool: Some(Arc::new(thread_pool)),
+            ..Default::default()
+        },
+    )?;
+    for range in [0..1000u32, 500..1500] {
+        let b = db.write_batch::<_, 1>()?;
+        for i in range {
+            b.put(0, i.to_be_bytes(), i.to_be_bytes().to_vec().into())?;
+        }
+        db.commit_write_batch(b)?;
+    }
+    db.compact(0.0, 2)?;
+
+    // Write batches and the compaction wrote their SST files on the thread pool
+    let threads = std::mem::take(&mut *threads.0.lock());
+    assert!(threads.len() >= 3);
+    for name in threads {
+        assert!(name.is_some_and(|name| name.starts_with("persistence-")));
+    }
+    for i in 0..1500u32 {
+        assert_eq!(
+            db.get(0, &i.to_be_bytes())?.as_deref(),
+            Some(&i.to_be_bytes()[..])
+        );
+    }
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn readahead() -> Result<()> {
+    #[derive(Default)]
+    struct KeyBlockMisses(Mutex<Vec<Option<String>>>);
+
+    impl PersistenceEventListener for KeyBlockMisses {
+        fn on_cache_miss(&self, cache: CacheKind) {
+            if cache == CacheKind::KeyBlock {
+                let name = std::thread::current().name().map(str::to_string);
+                self.0.lock().push(name);
+            }
+        }
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    let b = db.write_batch::<_, 1>()?;
+    for i in 0..50000u32 {
+        b.put(0, i.to_be_bytes(), i.to_le_bytes().to_vec().into())?;
+    }
+    db.commit_write_batch(b)?;
+    db.shutdown()?;
+
+    // Reopen with empty caches
+    let misses = Arc::new(KeyBlockMisses::default());
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .thread_name(|i| format!("readahead-{i}"))
+        .build()?;
+    let db = TurboPersistence::open_with_options(
+        path.to_path_buf(),
+        DbOptions {
+            event_listener: Some(misses.clone()),
+            thread_pool: Some(Arc::new(thread_pool)),
+            ..Default::default()
+        },
+    )?;
+    for reverse in [false, true] {
+        let iter: Box<dyn Iterator<Item = Result<(ArcSlice<u8>, ArcSlice<u8>)>>> = if reverse {
+            Box::new(db.range_rev::<[u8; 4]>(0, ..)?)
+        } else {
+            Box::new(db.range::<[u8; 4]>(0, ..)?)
+        };
+        let mut count = 0;
+        for entry in iter {
+            let (key, value) = entry?;
+            assert_eq!(
+                &*key,
+                &u32::from_le_bytes((*value).try_into()?).to_be_bytes()
+            );
+            count += 1;
+        }
+        assert_eq!(count, 50000);
+    }
+
+    // The key blocks following the first ones were decompressed on the thread pool
+    let start = Instant::now();
+    while !misses.0.lock().iter().any(|name| {
+        name.as_deref()
+            .is_some_and(|name| name.starts_with("readahead-"))
+    }) {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn storage_quota() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+    let sst_size = || -> Result<u64> {
+        let mut size = 0;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.path().extension().and_then(|s| s.to_str()) == Some("sst") {
+                size += entry.metadata()?.len();
+            }
+        }
+        Ok(size)
+    };
+
+    {
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        let b = db.write_batch::<_, 2>()?;
+        for i in 0..1000u32 {
+            b.put(0, i.to_be_bytes(), vec![i as u8; 100].into())?;
+            b.put(1, i.to_be_bytes(), vec![i as u8; 100].into())?;
+        }
+        db.commit_write_batch(b)?;
+        db.shutdown()?;
+    }
+
+    // The files were last read an hour ago
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.path().extension().and_then(|s| s.to_str()) == Some("sst") {
+            fs::File::options()
+                .write(true)
+                .open(entry.path())?
+                .set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+        }
+    }
+
+    let quota = sst_size()? * 3 / 4;
+    let evictions = Arc::new(AtomicUsize::new(0));
+    struct Listener(Arc<AtomicUsize>);
+    impl PersistenceEventListener for Listener {
+        fn on_eviction(&self, info: &EvictionInfo) {
+            assert!(info.removed_files > 0);
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    let options = || DbOptions {
+        storage_quota: Some(quota),
+        event_listener: Some(Arc::new(Listener(evictions.clone()))),
+        ..Default::default()
+    };
+
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+        // Reading family 1 makes family 0 the least recently read data
+        for i in 0..1000u32 {
+            assert!(db.get(1, &i.to_be_bytes())?.is_some());
+        }
+        db.full_compact()?;
+        assert_eq!(evictions.load(Ordering::SeqCst), 1);
+        assert!(sst_size()? <= quota);
+        for i in 0..1000u32 {
+            assert!(db.get(0, &i.to_be_bytes())?.is_none());
+            assert_eq!(
+                db.get(1, &i.to_be_bytes())?.as_deref(),
+                Some(&vec![i as u8; 100][..])
+            );
+        }
+        // The database fits into the quota now
+        db.full_compact()?;
+        assert_eq!(evictions.load(Ordering::SeqCst), 1);
+        db.shutdown()?;
+    }
+
+    {
+        let db = TurboPersistence::open_with_options(path.to_path_buf(), options())?;
+        assert_eq!(db.range::<&[u8]>(0, ..)?.count(), 0);
+        assert_eq!(db.range::<&[u8]>(1, ..)?.count(), 1000);
+        db.shutdown()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,483 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use parking_lot::Mutex;
+
+use crate::{
+    constants::MAX_REPLAY_FAMILIES,
+    key::{write_key_bytes, KeyBase},
+    TurboPersistence, ValueBuffer, WriteBatch,
+};
+
+/// The magic number and version at the start of a trace file.
+const TRACE_MAGIC: &[u8; 4] = b"TPT1";
+
+/// The tag for the start of a write batch.
+const RECORD_TYPE_WRITE_BATCH: u8 = 0;
+/// The tag for the commit of a write batch.
+const RECORD_TYPE_COMMIT: u8 = 1;
+/// The tag for a put record.
+const RECORD_TYPE_PUT: u8 = 2;
+/// The tag for a merge record.
+const RECORD_TYPE_MERGE: u8 = 3;
+/// The tag for a delete record.
+const RECORD_TYPE_DELETE: u8 = 4;
+/// The tag for a delete range record.
+const RECORD_TYPE_DELETE_RANGE: u8 = 5;
+/// The tag for a get record.
+const RECORD_TYPE_GET: u8 = 6;
+
+/// Records the operations on a database into a trace file, so they can be replayed with
+/// [replay_trace] to reproduce performance issues. Keys are recorded, but only the sizes of
+/// values.
+///
+/// The file starts with the magic bytes `TPT1`, followed by records with the following format.
+/// All numbers are LEB128 encoded.
+/// - 1 byte record type
+/// - microseconds since the previous record
+/// - write batch id (except for get records)
+/// - family (except for write batch and commit records)
+/// - key length and key (except for write batch and commit records)
+/// - value size (only for put and merge records)
+/// - end key length and end key (only for delete range records)
+/// - value size + 1, or 0 when the key was not found (only for get records)
+pub(crate) struct TraceRecorder {
+    state: Mutex<TraceState>,
+    /// The id of the next write batch.
+    next_write_batch: AtomicU64,
+}
+
+struct TraceState {
+    writer: BufWriter<File>,
+    /// The time of the previous record.
+    last: Instant,
+    /// A reusable buffer for encoding a record.
+    buf: Vec<u8>,
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_key<K: KeyBase>(buf: &mut Vec<u8>, key: &K) {
+    write_varint(buf, key.len() as u64);
+    write_key_bytes(key, buf);
+}
+
+impl TraceRecorder {
+    /// Creates a new trace file, replacing an existing one.
+    pub(crate) fn create(path: &Path) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path).context("Unable to create trace file")?);
+        writer.write_all(TRACE_MAGIC)?;
+        Ok(Self {
+            state: Mutex::new(TraceState {
+                writer,
+                last: Instant::now(),
+                buf: Vec::new(),
+            }),
+            next_write_batch: AtomicU64::new(0),
+        })
+    }
+
+    /// Appends a record. `write` encodes the type specific part of the record.
+    fn record(&self, ty: u8, write: impl FnOnce(&mut Vec<u8>)) -> Result<()> {
+        let mut state = self.state.lock();
+        let TraceState { writer, last, buf } = &mut *state;
+        let now = Instant::now();
+        buf.clear();
+        buf.push(ty);
+        write_varint(buf, (now - *last).as_micros() as u64);
+        *last = now;
+        write(buf);
+        writer.write_all(buf).context("Unable to write trace file")
+    }
+
+    /// Records the start of a write batch and returns its id.
+    pub(crate) fn write_batch(&self) -> Result<u64> {
+        let id = self.next_write_batch.fetch_add(1, Ordering::Relaxed);
+        self.record(RECORD_TYPE_WRITE_BATCH, |buf| write_varint(buf, id))?;
+        Ok(id)
+    }
+
+    /// Records the commit of a write batch.
+    pub(crate) fn commit(&self, write_batch: u64) -> Result<()> {
+        self.record(RECORD_TYPE_COMMIT, |buf| write_varint(buf, write_batch))
+    }
+
+    /// Records a put or merge operation with the size of the value.
+    pub(crate) fn put<K: KeyBase>(
+        &self,
+        write_batch: u64,
+        merge: bool,
+        family: usize,
+        key: &K,
+        value_size: usize,
+    ) -> Result<()> {
+        let ty = if merge {
+            RECORD_TYPE_MERGE
+        } else {
+            RECORD_TYPE_PUT
+        };
+        self.record(ty, |buf| {
+            write_varint(buf, write_batch);
+            write_varint(buf, family as u64);
+            write_key(buf, key);
+            write_varint(buf, value_size as u64);
+        })
+    }
+
+    /// Records a delete operation.
+    pub(crate) fn delete<K: KeyBase>(
+        &self,
+        write_batch: u64,
+        family: usize,
+        key: &K,
+    ) -> Result<()> {
+        self.record(RECORD_TYPE_DELETE, |buf| {
+            write_varint(buf, write_batch);
+            write_varint(buf, family as u64);
+            write_key(buf, key);
+        })
+    }
+
+    /// Records a delete range operation.
+    pub(crate) fn delete_range<K: KeyBase>(
+        &self,
+        write_batch: u64,
+        family: usize,
+        start: &K,
+        end: &K,
+    ) -> Result<()> {
+        self.record(RECORD_TYPE_DELETE_RANGE, |buf| {
+            write_varint(buf, write_batch);
+            write_varint(buf, family as u64);
+            write_key(buf, start);
+            write_key(buf, end);
+        })
+    }
+
+    /// Records a lookup with the size of the found value.
+    pub(crate) fn get<K: KeyBase>(
+        &self,
+        family: usize,
+        key: &K,
+        value_size: Option<usize>,
+    ) -> Result<()> {
+        self.record(RECORD_TYPE_GET, |buf| {
+            write_varint(buf, family as u64);
+            write_key(buf, key);
+            write_varint(buf, value_size.map_or(0, |size| size as u64 + 1));
+        })
+    }
+
+    /// Writes the buffered records to the trace file.
+    pub(crate) fn flush(&self) -> Result<()> {
+        self.state
+            .lock()
+            .writer
+            .flush()
+            .context("Unable to flush trace file")
+    }
+}
+
+/// An operation read from a trace file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceOperation {
+    WriteBatch {
+        write_batch: u64,
+    },
+    Commit {
+        write_batch: u64,
+    },
+    Put {
+        write_batch: u64,
+        family: usize,
+        key: Vec<u8>,
+        value_size: usize,
+    },
+    Merge {
+        write_batch: u64,
+        family: usize,
+        key: Vec<u8>,
+        operand_size: usize,
+    },
+    Delete {
+        write_batch: u64,
+        family: usize,
+        key: Vec<u8>,
+    },
+    DeleteRange {
+        write_batch: u64,
+        family: usize,
+        start: Vec<u8>,
+        end: Vec<u8>,
+    },
+    Get {
+        family: usize,
+        key: Vec<u8>,
+        value_size: Option<usize>,
+    },
+}
+
+/// A record read from a trace file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// The time since the trace was started.
+    pub timestamp: Duration,
+    pub operation: TraceOperation,
+}
+
+/// Reads all records of a trace file. An incomplete record at the end, e. g. when the process
+/// died while recording, is ignored.
+pub fn read_trace(path: &Path) -> Result<Vec<TraceRecord>> {
+    let content = fs::read(path).context("Unable to read trace file")?;
+    let Some(mut content) = content.strip_prefix(TRACE_MAGIC) else {
+        bail!("Invalid magic number or version of trace file");
+    };
+    let mut records = Vec::new();
+    let mut timestamp = Duration::ZERO;
+    while !content.is_empty() {
+        match read_record(&mut content)? {
+            Some((elapsed, operation)) => {
+                timestamp += elapsed;
+                records.push(TraceRecord {
+                    timestamp,
+                    operation,
+                });
+            }
+            None => break,
+        }
+    }
+    Ok(records)
+}
+
+fn read_varint(content: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = content.split_first()?;
+        *content = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_key(content: &mut &[u8]) -> Option<Vec<u8>> {
+    let len = read_varint(content)? as usize;
+    if content.len() < len {
+        return None;
+    }
+    let (key, rest) = content.split_at(len);
+    *content = rest;
+    Some(key.to_vec())
+}
+
+/// Reads a single record. Returns `None` when the record is incomplete.
+fn read_record(content: &mut &[u8]) -> Result<Option<(Duration, TraceOperation)>> {
+    let Some((&ty, rest)) = content.split_first() else {
+        return Ok(None);
+    };
+    *content = rest;
+    if ty > RECORD_TYPE_GET {
+        bail!("Invalid trace record type {ty}");
+    }
+    let read = |content: &mut &[u8]| -> Option<TraceOperation> {
+        Some(match ty {
+            RECORD_TYPE_WRITE_BATCH => TraceOperation::WriteBatch {
+                write_batch: read_varint(content)?,
+            },
+            RECORD_TYPE_COMMIT => TraceOperation::Commit {
+                write_batch: read_varint(content)?,
+            },
+            RECORD_TYPE_PUT => TraceOperation::Put {
+                write_batch: read_varint(content)?,
+                family: read_varint(content)? as usize,
+                key: read_key(content)?,
+                value_size: read_varint(content)? as usize,
+            },
+            RECORD_TYPE_MERGE => TraceOperation::Merge {
+                write_batch: read_varint(content)?,
+                family: read_varint(content)? as usize,
+                key: read_key(content)?,
+                operand_size: read_varint(content)? as usize,
+            },
+            RECORD_TYPE_DELETE => TraceOperation::Delete {
+                write_batch: read_varint(content)?,
+                family: read_varint(content)? as usize,
+                key: read_key(content)?,
+            },
+            RECORD_TYPE_DELETE_RANGE => TraceOperation::DeleteRange {
+                write_batch: read_varint(content)?,
+                family: read_varint(content)? as usize,
+                start: read_key(content)?,
+                end: read_key(content)?,
+            },
+            _ => TraceOperation::Get {
+                family: read_varint(content)? as usize,
+                key: read_key(content)?,
+                value_size: read_varint(content)?
+                    .checked_sub(1)
+                    .map(|size| size as usize),
+            },
+        })
+    };
+    let Some(elapsed) = read_varint(content) else {
+        return Ok(None);
+    };
+    Ok(read(content).map(|operation| (Duration::from_micros(elapsed), operation)))
+}
+
+/// Statistics about a replayed trace.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayStats {
+    /// The number of replayed write operations.
+    pub writes: u64,
+    /// The number of committed write batches.
+    pub commits: u64,
+    /// The number of replayed lookups.
+    pub gets: u64,
+    /// The number of lookups that found a value when the trace was recorded, but not when it was
+    /// replayed, or the other way around. This is expected when the trace was recorded on a
+    /// database that wasn't empty.
+    pub mismatched_gets: u64,
+    /// The time between the first and the last record of the trace.
+    pub recorded_duration: Duration,
+    /// The time the replay took.
+    pub duration: Duration,
+    /// The time spent in write operations.
+    pub write_duration: Duration,
+    /// The time spent committing write batches.
+    pub commit_duration: Duration,
+    /// The time spent in lookups.
+    pub get_duration: Duration,
+}
+
+/// Fills a buffer with pseudo-random bytes, so replayed values don't compress better than real
+/// values would.
+fn fill_value(buf: &mut Vec<u8>, size: usize, seed: u64) {
+    buf.clear();
+    let mut state = seed | 1;
+    while buf.len() < size {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        buf.extend_from_slice(&state.to_le_bytes());
+    }
+    buf.truncate(size);
+}
+
+/// Replays the records of a trace against a database as fast as possible. Values are not part of
+/// the trace, so pseudo-random values of the recorded sizes are written. Write batches are
+/// started, filled and committed in the recorded order on the current thread. Write batches that
+/// were not committed in the trace are not committed either. Merge operations need a merge
+/// operator for their family.
+pub fn replay_trace(db: &TurboPersistence, records: &[TraceRecord]) -> Result<ReplayStats> {
+    let mut stats = ReplayStats {
+        recorded_duration: records
+            .last()
+            .map_or(Duration::ZERO, |record| record.timestamp),
+        ..Default::default()
+    };
+    let mut write_batches: HashMap<u64, WriteBatch<Vec<u8>, MAX_REPLAY_FAMILIES>> = HashMap::new();
+    let mut value = Vec::new();
+    let replay_start = Instant::now();
+    for (index, record) in records.iter().enumerate() {
+        match &record.operation {
+            TraceOperation::WriteBatch { write_batch } => {
+                write_batches.insert(*write_batch, db.write_batch()?);
+            }
+            TraceOperation::Commit { write_batch } => {
+                let batch = write_batches
+                    .remove(write_batch)
+                    .with_context(|| format!("Write batch {write_batch} was not started"))?;
+                let start = Instant::now();
+                db.commit_write_batch(batch)?;
+                stats.commit_duration += start.elapsed();
+                stats.commits += 1;
+            }
+            TraceOperation::Get {
+                family,
+                key,
+                value_size,
+            } => {
+                let start = Instant::now();
+                let found = db.get(*family, key)?;
+                stats.get_duration += start.elapsed();
+                stats.gets += 1;
+                if found.is_some() != value_size.is_some() {
+                    stats.mismatched_gets += 1;
+                }
+            }
+            operation => {
+                if let TraceOperation::Put { value_size, .. }
+                | TraceOperation::Merge {
+                    operand_size: value_size,
+                    ..
+                } = operation
+                {
+                    fill_value(&mut value, *value_size, index as u64);
+                }
+                let start = Instant::now();
+                replay_write(&write_batches, operation, &value)?;
+                stats.write_duration += start.elapsed();
+                stats.writes += 1;
+            }
+        }
+    }
+    stats.duration = replay_start.elapsed();
+    Ok(stats)
+}
+
+/// Replays a write operation into its write batch. `value` is used as value of put and merge
+/// operations.
+fn replay_write(
+    write_batches: &HashMap<u64, WriteBatch<Vec<u8>, MAX_REPLAY_FAMILIES>>,
+    operation: &TraceOperation,
+    value: &[u8],
+) -> Result<()> {
+    let write_batch = |id: &u64| {
+        write_batches
+            .get(id)
+            .with_context(|| format!("Write batch {id} was not started"))
+    };
+    match operation {
+        TraceOperation::Put {
+            write_batch: id,
+            family,
+            key,
+            ..
+        } => write_batch(id)?.put(*family, key.clone(), ValueBuffer::Borrowed(value)),
+        TraceOperation::Merge {
+            write_batch: id,
+            family,
+            key,
+            ..
+        } => write_batch(id)?.merge(*family, key.clone(), ValueBuffer::Borrowed(value)),
+        TraceOperation::Delete {
+            write_batch: id,
+            family,
+            key,
+        } => write_batch(id)?.delete(*family, key.clone()),
+        TraceOperation::DeleteRange {
+            write_batch: id,
+            family,
+            start,
+            end,
+        } => write_batch(id)?.delete_range(*family, start.clone(), end.clone()),
+        _ => bail!("Not a write operation"),
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::Result;
+
+use crate::{
+    arc_slice::ArcSlice,
+    blob::read_blob_file,
+    encryption::Encryption,
+    lookup_entry::LookupValue,
+    static_sorted_file::{AqmfCache, BlockCache, LookupResult, StaticSortedFile},
+};
+
+/// A problem found by [crate::TurboPersistence::verify].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyIssue {
+    /// The SST file can't be read, e. g. because a block doesn't match its checksum. The
+    /// remaining entries of the file are not verified.
+    Corrupted { sequence_number: u32, error: String },
+    /// The entries of the SST file are not sorted by key hash and key, so the index points to the
+    /// wrong key blocks.
+    UnsortedEntries { sequence_number: u32 },
+    /// Entries of the SST file are outside of the hash range in its header. Lookups skip the file
+    /// for them.
+    OutOfRange { sequence_number: u32, entries: u64 },
+    /// The AQMF filter of the SST file doesn't contain entries of the file. Lookups miss them.
+    FilterMiss { sequence_number: u32, entries: u64 },
+    /// Entries of the SST file can't be found through its index. Lookups miss them.
+    IndexMiss { sequence_number: u32, entries: u64 },
+    /// The SST file references a blob file that doesn't exist.
+    MissingBlob { sequence_number: u32, blob: u32 },
+    /// A referenced blob file can't be read.
+    CorruptedBlob { blob: u32, error: String },
+}
+
+/// The result of [crate::TurboPersistence::verify].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// The number of SST files that were verified.
+    pub sst_files: usize,
+    /// The number of entries in the verified SST files.
+    pub entries: u64,
+    /// The number of blob files referenced by the verified SST files.
+    pub blob_files: usize,
+    /// The problems that were found. Problems of SST files come first, ordered by their sequence
+    /// number, followed by problems of blob files.
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    /// Returns true if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// The result of verifying a single SST file.
+pub(crate) struct SstVerification {
+    pub(crate) entries: u64,
+    pub(crate) issues: Vec<VerifyIssue>,
+    /// The blob files referenced by the file.
+    pub(crate) blob_references: Vec<u32>,
+}
+
+/// Verifies a SST file. All blocks are read with checksum verification and every entry is looked
+/// up through the AQMF filter and the index, like a read would do.
+pub(crate) fn verify_sst_file(
+    path: &Path,
+    sequence_number: u32,
+    encryption: Option<&Encryption>,
+) -> SstVerification {
+    let mut result = SstVerification {
+        entries: 0,
+        issues: Vec::new(),
+        blob_references: Vec::new(),
+    };
+    if let Err(err) = verify_sst_file_internal(path, sequence_number, encryption, &mut result) {
+        result.issues.push(VerifyIssue::Corrupted {
+            sequence_number,
+            error: format!("{err:#}"),
+        });
+    }
+    result
+}
+
+fn verify_sst_file_internal(
+    path: &Path,
+    sequence_number: u32,
+    encryption: Option<&Encryption>,
+    result: &mut SstVerification,
+) -> Result<()> {
+    let sst = Arc::new(StaticSortedFile::open(
+        sequence_number,
+        path.join(format!("{sequence_number:08}.sst")),
+        true,
+        encryption.cloned(),
+        None,
+        false,
+    )?);
+    sst.check_block_layout()?;
+    let family = sst.range()?.family;
+    let aqmf_cache = AqmfCache::with(
+        1,
+        u64::MAX,
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    );
+    let new_cache = || {
+        BlockCache::with(
+            16,
+            16 * 1024 * 1024,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+    };
+    let key_block_cache = new_cache();
+    let value_block_cache = new_cache();
+
+    let mut out_of_range = 0;
+    let mut filter_misses = 0;
+    let mut index_misses = 0;
+    let mut previous: Option<(u64, ArcSlice<u8>)> = None;
+    let mut unsorted = false;
+    for entry in sst.clone().iter(&key_block_cache, &value_block_cache)? {
+        let entry = entry?;
+        result.entries += 1;
+        if let Some((hash, key)) = &previous {
+            if (*hash, &**key) >= (entry.hash, &*entry.key) {
+                unsorted = true;
+            }
+        }
+        if let LookupValue::Blob { sequence_number } = entry.value {
+            result.blob_references.push(sequence_number);
+        }
+        match sst.lookup(
+            family,
+            entry.hash,
+            &&*entry.key,
+            &aqmf_cache,
+            &key_block_cache,
+            &value_block_cache,
+        )? {
+            LookupResult::RangeMiss => out_of_range += 1,
+            LookupResult::QuickFilterMiss => filter_misses += 1,
+            LookupResult::KeyMiss => index_misses += 1,
+            _ => {}
+        }
+        previous = Some((entry.hash, entry.key));
+    }
+
+    if unsorted {
+        result
+            .issues
+            .push(VerifyIssue::UnsortedEntries { sequence_number });
+    }
+    if out_of_range > 0 {
+        result.issues.push(VerifyIssue::OutOfRange {
+            sequence_number,
+            entries: out_of_range,
+        });
+    }
+    if filter_misses > 0 {
+        result.issues.push(VerifyIssue::FilterMiss {
+            sequence_number,
+            entries: filter_misses,
+        });
+    }
+    if index_misses > 0 {
+        result.issues.push(VerifyIssue::IndexMiss {
+            sequence_number,
+            entries: index_misses,
+        });
+    }
+    Ok(())
+}
+
+/// Verifies that the blob files referenced by SST files exist and can be read. `references`
+/// contains the sequence numbers of the SST files and the blob files they reference.
+pub(crate) fn verify_blob_files(
+    path: &Path,
+    references: impl IntoIterator<Item = (u32, u32)>,
+    encryption: Option<&Encryption>,
+    report: &mut VerifyReport,
+) {
+    // The first SST file that references each blob file
+    let mut blob_files = HashMap::new();
+    for (sequence_number, blob) in references {
+        if let Entry::Vacant(entry) = blob_files.entry(blob) {
+            entry.insert(sequence_number);
+        }
+    }
+    let mut blob_files = blob_files.into_iter().collect::<Vec<_>>();
+    blob_files.sort_unstable();
+    report.blob_files = blob_files.len();
+    for (blob, sequence_number) in blob_files {
+        if !path.join(format!("{blob:08}.blob")).exists() {
+            report.issues.push(VerifyIssue::MissingBlob {
+                sequence_number,
+                blob,
+            });
+        } else if let Err(err) = read_blob_file(path, blob, encryption) {
+            report.issues.push(VerifyIssue::CorruptedBlob {
+                blob,
+                error: format!("{err:#}"),
+            });
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+use parking_lot::Mutex;
+
+use crate::{
+    constants::MAX_WAL_FAMILIES,
+    encryption::{self, Encryption},
+    key::StoreKey,
+};
+
+/// The file name of the write-ahead log in the database directory.
+pub const WAL_FILE: &str = "WAL";
+
+/// The tag for a put record.
+const RECORD_TYPE_PUT: u8 = 0;
+/// The tag for a delete record.
+const RECORD_TYPE_DELETE: u8 = 1;
+/// The tag for a delete range record.
+const RECORD_TYPE_DELETE_RANGE: u8 = 2;
+/// The tag for a merge record.
+const RECORD_TYPE_MERGE: u8 = 3;
+/// The tag for a put record with expiration timestamp.
+const RECORD_TYPE_PUT_WITH_EXPIRY: u8 = 4;
+/// The tag for an encrypted record, which contains one of the other records.
+const RECORD_TYPE_ENCRYPTED: u8 = 5;
+/// Flag in the record type of put records whose value length is stored in 8 bytes, because the
+/// value is larger than 4 GiB.
+const LARGE_VALUE_FLAG: u8 = 0x80;
+
+/// A write-ahead log. Every operation of a write batch is appended to it before it's added to the
+/// write batch, so operations that have not been committed yet can be recovered when the process
+/// dies.
+///
+/// Each record has the following format:
+/// - 1 byte record type
+/// - 4 bytes family
+/// - 4 bytes key length
+/// - key
+/// - 4 bytes value length, or 8 bytes when the record type has the large value flag (only for put
+///   and merge records)
+/// - value (only for put and merge records)
+/// - 8 bytes expiration timestamp (only for put records with expiry)
+/// - 4 bytes end key length (only for delete range records)
+/// - end key (only for delete range records)
+///
+/// With encryption every record is stored as an encrypted record:
+/// - 1 byte record type
+/// - 8 bytes length
+/// - nonce and encrypted record, which uses the offset of the record in the log as additional data,
+///   so records can't be reordered or moved to another position
+pub struct Wal {
+    file: Mutex<WalFile>,
+    encryption: Option<Encryption>,
+}
+
+struct WalFile {
+    file: File,
+    /// The length of the log, which is the offset of the next record.
+    len: u64,
+}
+
+/// A record read from the write-ahead log.
+pub enum WalRecord {
+    Put {
+        family: usize,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        expires_at: Option<u64>,
+    },
+    Delete {
+        family: usize,
+        key: Vec<u8>,
+    },
+    DeleteRange {
+        family: usize,
+        start: Vec<u8>,
+        end: Vec<u8>,
+    },
+    Merge {
+        family: usize,
+        key: Vec<u8>,
+        operand: Vec<u8>,
+    },
+}
+
+impl Wal {
+    /// Creates an empty write-ahead log in the database directory, replacing an existing one. With
+    /// an encryption all records are encrypted.
+    pub fn create(db_path: &Path, encryption: Option<Encryption>) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(db_path.join(WAL_FILE))
+            .context("Unable to create write-ahead log")?;
+        Ok(Self {
+            file: Mutex::new(WalFile { file, len: 0 }),
+            encryption,
+        })
+    }
+
+    /// Appends a put operation to the log.
+    pub fn put<K: StoreKey>(&self, family: usize, key: &K, value: &[u8]) -> Result<()> {
+        self.append_with_value(RECORD_TYPE_PUT, family, key, value)
+    }
+
+    /// Appends a put operation with expiration timestamp to the log.
+    pub fn put_with_expiry<K: StoreKey>(
+        &self,
+        family: usize,
+        key: &K,
+        value: &[u8],
+        expires_at: u64,
+    ) -> Result<()> {
+        let mut buf = Vec::with_capacity(25 + key.len() + value.len());
+        write_header(
+            &mut buf,
+            value_record_type(RECORD_TYPE_PUT_WITH_EXPIRY, value),
+            family,
+            key,
+        )?;
+        write_value(&mut buf, value)?;
+        buf.write_u64::<BE>(expires_at)?;
+        self.append(&buf)
+    }
+
+    /// Appends a merge operation to the log.
+    pub fn merge<K: StoreKey>(&self, family: usize, key: &K, operand: &[u8]) -> Result<()> {
+        self.append_with_value(RECORD_TYPE_MERGE, family, key, operand)
+    }
+
+    fn append_with_value<K: StoreKey>(
+        &self,
+        ty: u8,
+        family: usize,
+        key: &K,
+        value: &[u8],
+    ) -> Result<()> {
+        let mut buf = Vec::with_capacity(17 + key.len() + value.len());
+        write_header(&mut buf, value_record_type(ty, value), family, key)?;
+        write_value(&mut buf, value)?;
+        self.append(&buf)
+    }
+
+    /// Appends a delete operation to the log.
+    pub fn delete<K: StoreKey>(&self, family: usize, key: &K) -> Result<()> {
+        let mut buf = Vec::with_capacity(9 + key.len());
+        write_header(&mut buf, RECORD_TYPE_DELETE, family, key)?;
+        self.append(&buf)
+    }
+
+    /// Appends a delete range operation to the log.
+    pub fn delete_range<K: StoreKey>(&self, family: usize, start: &K, end: &K) -> Result<()> {
+        let mut buf = Vec::with_capacity(13 + start.len() + end.len());
+        write_header(&mut buf, RECORD_TYPE_DELETE_RANGE, family, start)?;
+        buf.write_u32::<BE>(end.len() as u32)?;
+        end.write_to(&mut buf);
+        self.append(&buf)
+    }
+
+    /// Writes a record with a single write call. When the process dies while writing only the last
+    /// record can be incomplete.
+    fn append(&self, record: &[u8]) -> Result<()> {
+        let mut file = self.file.lock();
+        let encrypted;
+        let record = match &self.encryption {
+            Some(encryption) => {
+                encrypted = encrypt_record(encryption, file.len, record)?;
+                &encrypted[..]
+            }
+            None => record,
+        };
+        file.file
+            .write_all(record)
+            .context("Unable to write to write-ahead log")?;
+        file.len += record.len() as u64;
+        Ok(())
+    }
+
+    /// Removes all records from the log. Called after the operations have been committed to SST
+    /// files.
+    pub fn truncate(&self) -> Result<()> {
+        let mut file = self.file.lock();
+        file.file
+            .set_len(0)
+            .context("Unable to truncate write-ahead log")?;
+        file.file.sync_all()?;
+        file.len = 0;
+        Ok(())
+    }
+}
+
+fn write_header<K: StoreKey>(buf: &mut Vec<u8>, ty: u8, family: usize, key: &K) -> Result<()> {
+    if family >= MAX_WAL_FAMILIES {
+        bail!(
+            "Family {family} can't be written to the write-ahead log (only {MAX_WAL_FAMILIES} \
+             families are supported)"
+        );
+    }
+    buf.write_u8(ty)?;
+    buf.write_u32::<BE>(family as u32)?;
+    buf.write_u32::<BE>(key.len() as u32)?;
+    key.write_to(buf);
+    Ok(())
+}
+
+/// Returns the record type for a record with the value, which has the large value flag when the
+/// value length doesn't fit into 4 bytes.
+fn value_record_type(ty: u8, value: &[u8]) -> u8 {
+    if value.len() > u32::MAX as usize {
+        ty | LARGE_VALUE_FLAG
+    } else {
+        ty
+    }
+}
+
+/// Writes the length of a value and the value. See [value_record_type].
+fn write_value(buf: &mut Vec<u8>, value: &[u8]) -> Result<()> {
+    if value.len() > u32::MAX as usize {
+        buf.write_u64::<BE>(value.len() as u64)?;
+    } else {
+        buf.write_u32::<BE>(value.len() as u32)?;
+    }
+    buf.extend_from_slice(value);
+    Ok(())
+}
+
+/// Encrypts a record that is written at the offset in the log. See [Wal].
+fn encrypt_record(encryption: &Encryption, offset: u64, record: &[u8]) -> Result<Vec<u8>> {
+    let encrypted = encryption.encrypt_with_aad(record, &offset.to_be_bytes())?;
+    let mut buf = Vec::with_capacity(9 + encrypted.len());
+    buf.write_u8(RECORD_TYPE_ENCRYPTED)?;
+    buf.write_u64::<BE>(encrypted.len() as u64)?;
+    buf.extend_from_slice(&encrypted);
+    Ok(buf)
+}
+
+/// Reads all records from the write-ahead log in the database directory. Returns an empty list
+/// when there is no log. An incomplete record at the end of the log is ignored, as it was never
+/// acknowledged to the caller. With an encryption all records must be encrypted.
+pub fn read_wal(db_path: &Path, encryption: Option<&Encryption>) -> Result<Vec<WalRecord>> {
+    let content = match fs::read(db_path.join(WAL_FILE)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Unable to read write-ahead log"),
+    };
+    let len = content.len();
+    let mut records = Vec::new();
+    let mut content = &content[..];
+    while !content.is_empty() {
+        let offset = (len - content.len()) as u64;
+        match read_record(&mut content, offset, encryption) {
+            Ok(Some(record)) => records.push(record),
+            Ok(None) => break,
+            Err(e) => return Err(e).context("Invalid write-ahead log"),
+        }
+    }
+    Ok(records)
+}
+
+/// Reads a single record at the offset in the log, which is decrypted when it's encrypted. Returns
+/// `None` when the record is incomplete.
+fn read_record(
+    content: &mut &[u8],
+    offset: u64,
+    encryption: Option<&Encryption>,
+) -> Result<Option<WalRecord>> {
+    if content.first() != Some(&RECORD_TYPE_ENCRYPTED) {
+        if encryption.is_some() {
+            bail!("Unencrypted record in the write-ahead log of an encrypted database");
+        }
+        return read_plain_record(content);
+    }
+    let encryption = encryption::required(encryption)?;
+    let mut rest = &content[1..];
+    let Ok(len) = rest.read_u64::<BE>() else {
+        return Ok(None);
+    };
+    let Some(encrypted) = usize::try_from(len).ok().and_then(|len| rest.get(..len)) else {
+        return Ok(None);
+    };
+    let record = encryption.decrypt_with_aad(encrypted, &offset.to_be_bytes())?;
+    *content = &rest[encrypted.len()..];
+    match read_plain_record(&mut &record[..])? {
+        Some(record) => Ok(Some(record)),
+        None => bail!("Incomplete encrypted record"),
+    }
+}
+
+/// Reads a single unencrypted record. Returns `None` when the record is incomplete.
+fn read_plain_record(content: &mut &[u8]) -> Result<Option<WalRecord>> {
+    fn read_bytes(content: &mut &[u8]) -> Option<Vec<u8>> {
+        let len = content.read_u32::<BE>().ok()? as usize;
+        take_bytes(content, len)
+    }
+
+    fn take_bytes(content: &mut &[u8], len: usize) -> Option<Vec<u8>> {
+        if content.len() < len {
+            return None;
+        }
+        let (bytes, rest) = content.split_at(len);
+        *content = rest;
+        Some(bytes.to_vec())
+    }
+
+    let Ok(ty) = content.read_u8() else {
+        return Ok(None);
+    };
+    let large_value = ty & LARGE_VALUE_FLAG != 0;
+    let ty = ty & !LARGE_VALUE_FLAG;
+    let read_value = |content: &mut &[u8]| {
+        if large_value {
+            let len = content.read_u64::<BE>().ok()?;
+            take_bytes(content, usize::try_from(len).ok()?)
+        } else {
+            read_bytes(content)
+        }
+    };
+    let Ok(family) = content.read_u32::<BE>() else {
+        return Ok(None);
+    };
+    let family = family as usize;
+    if family >= MAX_WAL_FAMILIES {
+        bail!("Invalid family {family}");
+    }
+    let Some(key) = read_bytes(content) else {
+        return Ok(None);
+    };
+    match ty {
+        RECORD_TYPE_PUT => {
+            let Some(value) = read_value(content) else {
+                return Ok(None);
+            };
+            Ok(Some(WalRecord::Put {
+                family,
+                key,
+                value,
+                expires_at: None,
+            }))
+        }
+        RECORD_TYPE_PUT_WITH_EXPIRY => {
+            let Some(value) = read_value(content) else {
+                return Ok(None);
+            };
+            let Ok(expires_at) = content.read_u64::<BE>() else {
+                return Ok(None);
+            };
+            Ok(Some(WalRecord::Put {
+                family,
+                key,
+                value,
+                expires_at: Some(expires_at),
+            }))
+        }
+        RECORD_TYPE_MERGE => {
+            let Some(operand) = read_value(content) else {
+                return Ok(None);
+            };
+            Ok(Some(WalRecord::Merge {
+                family,
+                key,
+                operand,
+            }))
+        }
+        RECORD_TYPE_DELETE => Ok(Some(WalRecord::Delete { family, key })),
+        RECORD_TYPE_DELETE_RANGE => {
+            let Some(end) = read_bytes(content) else {
+                return Ok(None);
+            };
+            Ok(Some(WalRecord::DeleteRange {
+                family,
+                start: key,
+                end,
+            }))
+        }
+        _ => bail!("Invalid record type {ty}"),
+    }
+}
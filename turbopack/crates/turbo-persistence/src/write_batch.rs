@@ -1,15 +1,17 @@
 use std::{
     cell::UnsafeCell,
+    collections::HashMap,
     fs::File,
-    io::Write,
-    mem::{replace, swap},
+    mem::{replace, swap, take},
     path::PathBuf,
-    sync::atomic::{AtomicU32, Ordering},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Instant, SystemTime},
 };
 
-use anyhow::{Context, Result};
-use byteorder::{WriteBytesExt, BE};
-use lzzzz::lz4::{self, ACC_LEVEL_DEFAULT};
+use anyhow::{bail, Context, Result};
 use parking_lot::Mutex;
 use rayon::{
     iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator},
@@ -18,8 +20,25 @@ use rayon::{
 use thread_local::ThreadLocal;
 
 use crate::{
-    collector::Collector, collector_entry::CollectorEntry, constants::MAX_MEDIUM_VALUE_SIZE,
-    key::StoreKey, static_sorted_file_builder::StaticSortedFileBuilder, ValueBuffer,
+    blob::{content_hash, write_blob, BlobIndex},
+    collector::Collector,
+    collector_entry::CollectorEntry,
+    compression::Compression,
+    constants::{MAX_MEDIUM_VALUE_SIZE, MAX_SMALL_VALUE_SIZE},
+    encryption::Encryption,
+    event_listener::{BlobFileInfo, FileSource, PersistenceEventListener, SstFileInfo},
+    expiry::to_timestamp,
+    failpoint::{Failpoint, Failpoints},
+    family::Family,
+    file_io::FileWriter,
+    file_sizes::FamilyFileSizes,
+    key::StoreKey,
+    range_tombstone::{write_range_tombstones, RangeTombstone},
+    rate_limiter::RateLimiter,
+    static_sorted_file_builder::StaticSortedFileBuilder,
+    trace::TraceRecorder,
+    wal::Wal,
+    ValueBuffer,
 };
 
 /// The thread local state of a `WriteBatch`.
@@ -29,45 +48,358 @@ struct ThreadLocalState<K: StoreKey + Send, const FAMILIES: usize> {
     /// The list of new SST files that have been created.
     new_sst_files: Vec<(u32, File)>,
     /// The list of new blob files that have been created.
-    new_blob_files: Vec<File>,
+    new_blob_files: Vec<(u32, File)>,
 }
 
 /// The result of a `WriteBatch::finish` operation.
 pub(crate) struct FinishResult {
     pub(crate) sequence_number: u32,
     pub(crate) new_sst_files: Vec<(u32, File)>,
-    pub(crate) new_blob_files: Vec<File>,
+    pub(crate) new_blob_files: Vec<(u32, File)>,
+    pub(crate) new_blob_hashes: HashMap<u128, u32>,
+    pub(crate) new_range_tombstones: Option<(File, Vec<RangeTombstone>)>,
+}
+
+/// The operations of a family that were added to a write batch and are not committed yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PendingEntries {
+    /// The number of puts, deletes and merge operands. Range deletions are not counted.
+    pub entries: u64,
+    /// The total size of their keys and values in bytes.
+    pub bytes: u64,
+}
+
+/// Counts the operations of a family that were added to a write batch.
+#[derive(Default)]
+struct PendingCounters {
+    entries: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl PendingCounters {
+    fn get(&self) -> PendingEntries {
+        PendingEntries {
+            entries: self.entries.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The sequence numbers a write batch uses for new files. The range is unbounded until another
+/// write batch is started, which reserves its own range after the end of this one.
+pub(crate) struct SequenceNumberRange {
+    /// The sequence number the range starts after.
+    start: u32,
+    /// The last used sequence number and the exclusive end of the range.
+    state: Mutex<(u32, u32)>,
+}
+
+impl SequenceNumberRange {
+    /// Creates an unbounded range that starts after `current`.
+    pub(crate) fn new(current: u32) -> Self {
+        Self {
+            start: current,
+            state: Mutex::new((current, u32::MAX)),
+        }
+    }
+
+    /// Returns the last used sequence number.
+    pub(crate) fn current(&self) -> u32 {
+        self.state.lock().0
+    }
+
+    /// Returns the end of the range. The next write batch starts after it.
+    fn end(&self) -> u32 {
+        self.state.lock().1
+    }
+
+    /// Returns the next sequence number of the range, or `None` when all sequence numbers are used.
+    fn next(&self) -> Option<u32> {
+        let mut state = self.state.lock();
+        let (current, end) = &mut *state;
+        // The end is kept free, so the commit can use it for the deletion of range tombstones.
+        if *current + 1 >= *end {
+            return None;
+        }
+        *current += 1;
+        Some(*current)
+    }
+
+    /// Limits the range to `reservation` more sequence numbers and returns its end. The next write
+    /// batch starts after the end.
+    pub(crate) fn reserve(&self, reservation: u32) -> u32 {
+        let mut state = self.state.lock();
+        let (current, end) = &mut *state;
+        *end = (*end).min(current.saturating_add(reservation));
+        *end
+    }
 }
 
 /// A write batch.
 pub struct WriteBatch<K: StoreKey + Send, const FAMILIES: usize> {
     /// The database path
     path: PathBuf,
-    /// The current sequence number counter. Increased for every new SST file or blob file.
-    current_sequence_number: AtomicU32,
+    /// The sequence numbers of new SST files and blob files.
+    sequence_numbers: Arc<SequenceNumberRange>,
+    /// The sequence numbers of write batches that were absorbed into this one. They are used when
+    /// the own range is exhausted, and committed together with it.
+    absorbed_sequence_numbers: Vec<Arc<SequenceNumberRange>>,
+    /// The sequence number when the write batch was started. Range deletions apply to SST files up
+    /// to this sequence number.
+    base_sequence_number: u32,
+    /// The deleted key ranges as (family, start, end).
+    range_tombstones: Mutex<Vec<(u32, Vec<u8>, Vec<u8>)>>,
     /// The thread local state.
     thread_locals: ThreadLocal<UnsafeCell<ThreadLocalState<K, FAMILIES>>>,
+    /// The thread local states of write batches that were absorbed into this one.
+    absorbed_thread_locals: Vec<ThreadLocalState<K, FAMILIES>>,
     /// Collectors are are current unused, but have memory preallocated.
     idle_collectors: Mutex<Vec<Collector<K>>>,
+    /// The write-ahead log that all operations are appended to, when enabled.
+    wal: Option<Arc<Wal>>,
+    /// The compression for each family.
+    compression: Arc<[Compression]>,
+    /// The compression of blob files for each family, when it differs from `compression`.
+    blob_compression: Arc<[Option<Compression>]>,
+    /// The encryption of new files, when enabled.
+    encryption: Option<Encryption>,
+    /// Receives events about new files.
+    event_listener: Option<Arc<dyn PersistenceEventListener>>,
+    /// The maximum size of keys and values buffered in collectors before they are written to SST
+    /// files early.
+    memory_budget: Option<usize>,
+    /// The size of keys and values buffered in the collectors of all threads.
+    buffered_size: AtomicUsize,
+    /// The operations that were added to each family, indexed by family.
+    pending: [PendingCounters; FAMILIES],
+    /// Whether new files are written with direct I/O.
+    direct_io: bool,
+    /// Limits the rate at which new files are written, when enabled.
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// The sizes of the collectors for each family.
+    file_sizes: Arc<FamilyFileSizes>,
+    /// The failures that are injected, with the `failpoints` feature.
+    failpoints: Arc<Failpoints>,
+    /// The content hashes of committed blob files, when blobs are deduplicated.
+    blob_index: Option<Arc<BlobIndex>>,
+    /// The content hashes of the blob files written by this write batch.
+    new_blob_hashes: Mutex<HashMap<u128, u32>>,
+    /// The trace that operations are recorded to and the id of this write batch in it, when
+    /// tracing is enabled.
+    trace: Option<(Arc<TraceRecorder>, u64)>,
+    /// The ids of the write batches that were absorbed into this one in the trace.
+    absorbed_trace_ids: Vec<u64>,
 }
 
 impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
     /// Creates a new write batch for a database.
-    pub(crate) fn new(path: PathBuf, current: u32) -> Self {
+    pub(crate) fn new(
+        path: PathBuf,
+        sequence_numbers: Arc<SequenceNumberRange>,
+        wal: Option<Arc<Wal>>,
+        compression: Arc<[Compression]>,
+        blob_compression: Arc<[Option<Compression>]>,
+        encryption: Option<Encryption>,
+        event_listener: Option<Arc<dyn PersistenceEventListener>>,
+        memory_budget: Option<usize>,
+        direct_io: bool,
+        rate_limiter: Option<Arc<RateLimiter>>,
+        file_sizes: Arc<FamilyFileSizes>,
+        failpoints: Arc<Failpoints>,
+        blob_index: Option<Arc<BlobIndex>>,
+        trace: Option<(Arc<TraceRecorder>, u64)>,
+    ) -> Self {
         assert!(FAMILIES <= u32::MAX as usize);
         Self {
             path,
-            current_sequence_number: AtomicU32::new(current),
+            base_sequence_number: sequence_numbers.current(),
+            sequence_numbers,
+            absorbed_sequence_numbers: Vec::new(),
+            range_tombstones: Mutex::new(Vec::new()),
             thread_locals: ThreadLocal::new(),
+            absorbed_thread_locals: Vec::new(),
             idle_collectors: Mutex::new(Vec::new()),
+            wal,
+            compression,
+            blob_compression,
+            encryption,
+            event_listener,
+            memory_budget,
+            buffered_size: AtomicUsize::new(0),
+            pending: [(); FAMILIES].map(|_| PendingCounters::default()),
+            direct_io,
+            rate_limiter,
+            file_sizes,
+            failpoints,
+            blob_index,
+            new_blob_hashes: Mutex::new(HashMap::new()),
+            trace,
+            absorbed_trace_ids: Vec::new(),
+        }
+    }
+
+    /// Resets the write batch to a new sequence number range. This is called when the WriteBatch
+    /// is reused.
+    pub(crate) fn reset(
+        &mut self,
+        sequence_numbers: Arc<SequenceNumberRange>,
+        trace: Option<(Arc<TraceRecorder>, u64)>,
+    ) {
+        self.trace = trace;
+        self.base_sequence_number = sequence_numbers.current();
+        self.sequence_numbers = sequence_numbers;
+        self.absorbed_sequence_numbers.clear();
+        self.absorbed_trace_ids.clear();
+        *self.buffered_size.get_mut() = 0;
+        self.pending = [(); FAMILIES].map(|_| PendingCounters::default());
+        self.new_blob_hashes.get_mut().clear();
+    }
+
+    /// Returns true if the write batch uses the given sequence number range, either its own or the
+    /// range of an absorbed write batch.
+    pub(crate) fn has_sequence_numbers(&self, sequence_numbers: &Arc<SequenceNumberRange>) -> bool {
+        self.all_sequence_numbers()
+            .any(|own| Arc::ptr_eq(own, sequence_numbers))
+    }
+
+    /// Returns the number of sequence number ranges the write batch uses. They are consecutive in
+    /// the list of open write batches.
+    pub(crate) fn sequence_number_range_count(&self) -> usize {
+        1 + self.absorbed_sequence_numbers.len()
+    }
+
+    /// Returns the own sequence number range and the ranges of absorbed write batches.
+    fn all_sequence_numbers(&self) -> impl Iterator<Item = &Arc<SequenceNumberRange>> {
+        std::iter::once(&self.sequence_numbers).chain(self.absorbed_sequence_numbers.iter())
+    }
+
+    /// Returns the next sequence number for a new file.
+    fn next_sequence_number(&self) -> Result<u32> {
+        match self.all_sequence_numbers().find_map(|range| range.next()) {
+            Some(seq) => Ok(seq),
+            None => bail!(
+                "The write batch used all sequence numbers that were reserved for it when another \
+                 write batch was started"
+            ),
+        }
+    }
+
+    /// Moves the operations of another write batch of the same database into this one, so they
+    /// are committed together. This allows subsystems to fill write batches of their own in
+    /// parallel and to commit them atomically.
+    ///
+    /// The buffered entries, the SST and blob files that were already written, the range deletions
+    /// and the reserved sequence numbers of `other` are moved. The write batches must have been
+    /// started directly after each other, since all write batches that were started in between
+    /// would need to be committed before and after the combined write batch. Range deletions of
+    /// both write batches only delete keys that were committed before the combined write batch.
+    pub fn absorb(&mut self, mut other: WriteBatch<K, FAMILIES>) -> Result<()> {
+        if self.path != other.path {
+            bail!("Only write batches of the same database can be absorbed");
+        }
+        let first = |batch: &Self| batch.base_sequence_number;
+        let last = |batch: &Self| {
+            batch
+                .all_sequence_numbers()
+                .map(|range| range.end())
+                .max()
+                .unwrap()
+        };
+        if last(self) != first(&other) && last(&other) != first(self) {
+            bail!(
+                "Only write batches that were started directly after each other can be absorbed \
+                 (Write batches that were started in between need to be committed before)"
+            );
         }
+        self.base_sequence_number = self.base_sequence_number.min(other.base_sequence_number);
+        self.absorbed_sequence_numbers
+            .push(other.sequence_numbers.clone());
+        self.absorbed_sequence_numbers
+            .append(&mut other.absorbed_sequence_numbers);
+        self.absorbed_sequence_numbers
+            .sort_by_key(|range| range.start);
+        self.absorbed_thread_locals.extend(
+            take(&mut other.thread_locals)
+                .into_iter()
+                .map(UnsafeCell::into_inner),
+        );
+        self.absorbed_thread_locals
+            .append(&mut other.absorbed_thread_locals);
+        self.idle_collectors
+            .get_mut()
+            .append(other.idle_collectors.get_mut());
+        self.range_tombstones
+            .get_mut()
+            .append(other.range_tombstones.get_mut());
+        self.new_blob_hashes
+            .get_mut()
+            .extend(other.new_blob_hashes.get_mut().drain());
+        *self.buffered_size.get_mut() += *other.buffered_size.get_mut();
+        for (counters, absorbed) in self.pending.iter_mut().zip(other.pending.iter_mut()) {
+            *counters.entries.get_mut() += *absorbed.entries.get_mut();
+            *counters.bytes.get_mut() += *absorbed.bytes.get_mut();
+        }
+        if let Some((_, id)) = &other.trace {
+            self.absorbed_trace_ids.push(*id);
+        }
+        self.absorbed_trace_ids
+            .append(&mut other.absorbed_trace_ids);
+        Ok(())
+    }
+
+    /// Records an operation in the trace, when tracing is enabled.
+    fn trace(&self, record: impl FnOnce(&TraceRecorder, u64) -> Result<()>) -> Result<()> {
+        if let Some((trace, id)) = &self.trace {
+            record(trace, *id)?;
+        }
+        Ok(())
+    }
+
+    /// Records the commit of this write batch in the trace, when tracing is enabled.
+    pub(crate) fn trace_commit(&self) -> Result<()> {
+        self.trace(|trace, id| {
+            trace.commit(id)?;
+            for &id in &self.absorbed_trace_ids {
+                trace.commit(id)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Counts an operation with a key and value of the given size.
+    fn track_pending(&self, family: usize, size: usize) {
+        let counters = &self.pending[family];
+        counters.entries.fetch_add(1, Ordering::Relaxed);
+        counters.bytes.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the number of puts, deletes and merge operands that were added to the write batch.
+    /// They become visible when the write batch is committed.
+    pub fn pending_entries(&self) -> u64 {
+        self.pending
+            .iter()
+            .map(|counters| counters.entries.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Returns the total size of the keys and values that were added to the write batch in bytes.
+    pub fn pending_bytes(&self) -> u64 {
+        self.pending
+            .iter()
+            .map(|counters| counters.bytes.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Returns the operations that were added to each family, indexed by family.
+    pub fn pending_families(&self) -> [PendingEntries; FAMILIES] {
+        self.pending.each_ref().map(PendingCounters::get)
     }
 
-    /// Resets the write batch to a new sequence number. This is called when the WriteBatch is
-    /// reused.
-    pub(crate) fn reset(&mut self, current: u32) {
-        self.current_sequence_number
-            .store(current, Ordering::SeqCst);
+    /// Returns the size of the keys and values that are buffered in memory in bytes. They are
+    /// written to SST files when the write batch is committed, the remaining operations were
+    /// already written while the write batch was filled.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_size.load(Ordering::Relaxed)
     }
 
     /// Returns the thread local state for the current thread.
@@ -97,33 +429,217 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
                 .pop()
                 .unwrap_or_else(|| Collector::new())
         });
-        if collector.is_full() {
+        if collector.is_full(
+            self.file_sizes
+                .for_family(family as u32)
+                .thread_local_collector_size,
+        ) {
+            let size = collector.size();
             let sst = self.create_sst_file(family, collector.sorted())?;
             collector.clear();
             state.new_sst_files.push(sst);
+            self.buffered_size.fetch_sub(size, Ordering::Relaxed);
         }
         Ok(collector)
     }
 
+    /// Accounts for an entry that was added to a collector of the current thread. When the
+    /// buffered entries of all threads exceed the memory budget, the largest collectors of the
+    /// current thread are written to SST files until the budget is met again. Collectors of other
+    /// threads can't be accessed, they are flushed when these threads add entries.
+    fn track_buffered_size(
+        &self,
+        state: &mut ThreadLocalState<K, FAMILIES>,
+        size: usize,
+    ) -> Result<()> {
+        self.buffered_size.fetch_add(size, Ordering::Relaxed);
+        let Some(memory_budget) = self.memory_budget else {
+            return Ok(());
+        };
+        while self.buffered_size.load(Ordering::Relaxed) > memory_budget {
+            let Some((family, collector)) = state
+                .collectors
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(family, collector)| Some((family, collector.as_mut()?)))
+                .filter(|(_, collector)| !collector.is_empty())
+                .max_by_key(|(_, collector)| collector.size())
+            else {
+                break;
+            };
+            let size = collector.size();
+            let sst = self.create_sst_file(family, collector.sorted())?;
+            collector.clear();
+            state.new_sst_files.push(sst);
+            self.buffered_size.fetch_sub(size, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
     /// Puts a key-value pair into the write batch.
     pub fn put(&self, family: usize, key: K, value: ValueBuffer<'_>) -> Result<()> {
+        if let Some(wal) = &self.wal {
+            wal.put(family, &key, &value)?;
+        }
+        self.trace(|trace, id| trace.put(id, false, family, &key, value.len()))?;
+        self.put_internal(family, key, value, None)
+    }
+
+    /// Puts a key-value pair into the write batch that expires at the given time. Once expired,
+    /// reads treat the key as deleted and compaction removes the entry.
+    pub fn put_with_expiry(
+        &self,
+        family: usize,
+        key: K,
+        value: ValueBuffer<'_>,
+        expires_at: SystemTime,
+    ) -> Result<()> {
+        let expires_at = to_timestamp(expires_at);
+        if let Some(wal) = &self.wal {
+            wal.put_with_expiry(family, &key, &value, expires_at)?;
+        }
+        self.trace(|trace, id| trace.put(id, false, family, &key, value.len()))?;
+        self.put_internal(family, key, value, Some(expires_at))
+    }
+
+    /// Puts many key-value pairs into the write batch at once. This is a fast path for bulk loads
+    /// like full snapshots. The entries are written to SST files directly instead of being
+    /// buffered in the collectors of the write batch, so the memory usage is limited to a single
+    /// SST file.
+    ///
+    /// The entries should be sorted in database order, i.e. by [crate::hash_key] and then by key,
+    /// as range iteration returns them. Then every SST file covers a distinct range of key hashes
+    /// and sorting the entries for it is linear. Entries in other orders are still stored
+    /// correctly. Each key may only appear once.
+    pub fn ingest_sorted<'l>(
+        &self,
+        family: usize,
+        entries: impl IntoIterator<Item = (K, ValueBuffer<'l>)>,
+    ) -> Result<()> {
+        debug_assert!(family < FAMILIES);
+        let max_size = self
+            .file_sizes
+            .for_family(family as u32)
+            .global_collector_size;
+        let mut collector = self
+            .idle_collectors
+            .lock()
+            .pop()
+            .unwrap_or_else(|| Collector::new());
+        let mut new_sst_files = Vec::new();
+        let mut new_blob_files = Vec::new();
+        for (key, value) in entries {
+            if let Some(wal) = &self.wal {
+                wal.put(family, &key, &value)?;
+            }
+            self.trace(|trace, id| trace.put(id, false, family, &key, value.len()))?;
+            self.track_pending(family, key.len() + value.len());
+            if collector.is_full(max_size) {
+                new_sst_files.push(self.create_sst_file(family, collector.sorted())?);
+                collector.clear();
+            }
+            if value.len() <= MAX_MEDIUM_VALUE_SIZE {
+                collector.put(key, value, None);
+            } else {
+                let (blob, file) = self.create_blob(family, &value)?;
+                collector.put_blob(key, blob, None);
+                new_blob_files.extend(file.map(|file| (blob, file)));
+            }
+        }
+        if !collector.is_empty() {
+            new_sst_files.push(self.create_sst_file(family, collector.sorted())?);
+            collector.clear();
+        }
+        self.idle_collectors.lock().push(collector);
+        let state = self.thread_local_state();
+        state.new_sst_files.append(&mut new_sst_files);
+        state.new_blob_files.append(&mut new_blob_files);
+        Ok(())
+    }
+
+    /// Puts a key-value pair with an optional expiration timestamp into the write batch.
+    pub(crate) fn put_internal(
+        &self,
+        family: usize,
+        key: K,
+        value: ValueBuffer<'_>,
+        expires_at: Option<u64>,
+    ) -> Result<()> {
+        self.track_pending(family, key.len() + value.len());
         let state = self.thread_local_state();
         let collector = self.collector_mut(state, family)?;
+        let size = collector.size();
         if value.len() <= MAX_MEDIUM_VALUE_SIZE {
-            collector.put(key, value);
+            collector.put(key, value, expires_at);
         } else {
-            let (blob, file) = self.create_blob(&value)?;
-            collector.put_blob(key, blob);
-            state.new_blob_files.push(file);
+            let (blob, file) = self.create_blob(family, &value)?;
+            collector.put_blob(key, blob, expires_at);
+            state.new_blob_files.extend(file.map(|file| (blob, file)));
         }
-        Ok(())
+        let size = collector.size() - size;
+        self.track_buffered_size(state, size)
     }
 
     /// Puts a delete operation into the write batch.
     pub fn delete(&self, family: usize, key: K) -> Result<()> {
+        if let Some(wal) = &self.wal {
+            wal.delete(family, &key)?;
+        }
+        self.trace(|trace, id| trace.delete(id, family, &key))?;
+        self.track_pending(family, key.len());
         let state = self.thread_local_state();
         let collector = self.collector_mut(state, family)?;
+        let size = collector.size();
         collector.delete(key);
+        let size = collector.size() - size;
+        self.track_buffered_size(state, size)
+    }
+
+    /// Puts a merge operation into the write batch. The operand is combined with the value of the
+    /// key by the merge operator of the family, which needs to be configured with
+    /// [crate::DbOptions::merge_operators]. This avoids reading the value to update it.
+    ///
+    /// Operands are limited to 64 KiB. As for the other operations, only a single operation per key
+    /// should be added to a write batch.
+    pub fn merge(&self, family: usize, key: K, operand: ValueBuffer<'_>) -> Result<()> {
+        if operand.len() > MAX_SMALL_VALUE_SIZE {
+            bail!(
+                "Merge operand of {} bytes exceeds the maximum size of {MAX_SMALL_VALUE_SIZE} \
+                 bytes",
+                operand.len()
+            );
+        }
+        if let Some(wal) = &self.wal {
+            wal.merge(family, &key, &operand)?;
+        }
+        self.trace(|trace, id| trace.put(id, true, family, &key, operand.len()))?;
+        self.track_pending(family, key.len() + operand.len());
+        let state = self.thread_local_state();
+        let collector = self.collector_mut(state, family)?;
+        let size = collector.size();
+        collector.merge(key, operand);
+        let size = collector.size() - size;
+        self.track_buffered_size(state, size)
+    }
+
+    /// Puts a delete range operation into the write batch. It deletes all keys of the family in
+    /// `start..end`, without reading or enumerating them.
+    ///
+    /// The range only deletes keys that were committed before this write batch, including keys of
+    /// write batches that were started before it. Keys that are put in the same write batch are not
+    /// deleted, independent of the order of the operations.
+    pub fn delete_range(&self, family: usize, start: K, end: K) -> Result<()> {
+        if let Some(wal) = &self.wal {
+            wal.delete_range(family, &start, &end)?;
+        }
+        self.trace(|trace, id| trace.delete_range(id, family, &start, &end))?;
+        let mut start_bytes = Vec::with_capacity(start.len());
+        start.write_to(&mut start_bytes);
+        let mut end_bytes = Vec::with_capacity(end.len());
+        end.write_to(&mut end_bytes);
+        self.range_tombstones
+            .lock()
+            .push((family as u32, start_bytes, end_bytes));
         Ok(())
     }
 
@@ -133,8 +649,13 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
         let mut new_sst_files = Vec::new();
         let mut new_blob_files = Vec::new();
         let mut all_collectors = [(); FAMILIES].map(|_| Vec::new());
-        for cell in self.thread_locals.iter_mut() {
-            let state = cell.get_mut();
+        let mut absorbed_thread_locals = take(&mut self.absorbed_thread_locals);
+        for state in self
+            .thread_locals
+            .iter_mut()
+            .map(UnsafeCell::get_mut)
+            .chain(absorbed_thread_locals.iter_mut())
+        {
             new_sst_files.append(&mut state.new_sst_files);
             new_blob_files.append(&mut state.new_blob_files);
             for (family, global_collector) in all_collectors.iter_mut().enumerate() {
@@ -174,6 +695,10 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
                 .into_par_iter()
                 .enumerate()
                 .for_each(|(family, collectors)| {
+                    let max_size = self
+                        .file_sizes
+                        .for_family(family as u32)
+                        .global_collector_size;
                     let final_collector = collectors.into_par_iter().reduce(
                         || None,
                         |a, b| match (a, b) {
@@ -182,7 +707,7 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
                                     swap(&mut a, &mut b);
                                 }
                                 for entry in b.drain() {
-                                    if a.is_full() {
+                                    if a.is_full(max_size) {
                                         let full_collector = replace(
                                             &mut a,
                                             self.idle_collectors
@@ -222,29 +747,74 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
                 });
         });
         shared_error.into_inner()?;
-        let seq = self.current_sequence_number.load(Ordering::SeqCst);
+        self.failpoints.check(Failpoint::Finish)?;
+        let range_tombstones = take(self.range_tombstones.get_mut());
+        let new_range_tombstones = if range_tombstones.is_empty() {
+            None
+        } else {
+            let seq = self.next_sequence_number()?;
+            Some(write_range_tombstones(
+                &self.path,
+                seq,
+                self.base_sequence_number,
+                range_tombstones,
+            )?)
+        };
+        let seq = self
+            .all_sequence_numbers()
+            .map(|range| range.current())
+            .max()
+            .unwrap();
         new_sst_files.sort_by_key(|(seq, _)| *seq);
         Ok(FinishResult {
             sequence_number: seq,
             new_sst_files,
             new_blob_files,
+            new_blob_hashes: take(self.new_blob_hashes.get_mut()),
+            new_range_tombstones,
         })
     }
 
-    /// Creates a new blob file with the given value.
-    fn create_blob(&self, value: &[u8]) -> Result<(u32, File)> {
-        let seq = self.current_sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
-        let mut buffer = Vec::new();
-        buffer.write_u32::<BE>(value.len() as u32)?;
-        lz4::compress_to_vec(value, &mut buffer, ACC_LEVEL_DEFAULT)
-            .context("Compression of value for blob file failed")?;
+    /// Creates a new blob file with the given value. When blobs are deduplicated and a blob file
+    /// with the same content exists, it's returned without a new file.
+    fn create_blob(&self, family: usize, value: &[u8]) -> Result<(u32, Option<File>)> {
+        let content_hash = self.blob_index.as_ref().map(|_| content_hash(value));
+        if let (Some(blob_index), Some(content_hash)) = (&self.blob_index, content_hash) {
+            let existing = blob_index
+                .get(content_hash)
+                .or_else(|| self.new_blob_hashes.lock().get(&content_hash).copied());
+            if let Some(seq) = existing {
+                return Ok((seq, None));
+            }
+        }
+        let seq = self.next_sequence_number()?;
+        let compression =
+            Compression::for_blob_family(&self.compression, &self.blob_compression, family as u32);
 
         let file = self.path.join(format!("{:08}.blob", seq));
-        let mut file = File::create(&file).context("Unable to create blob file")?;
-        file.write_all(&buffer)
-            .context("Unable to write blob file")?;
-        file.flush().context("Unable to flush blob file")?;
-        Ok((seq, file))
+        let mut writer = FileWriter::create(&file, self.direct_io, self.rate_limiter.as_deref())
+            .context("Unable to create blob file")?;
+        let size = write_blob(
+            value,
+            compression,
+            self.encryption.as_ref(),
+            content_hash,
+            &mut writer,
+        )
+        .context("Unable to write blob file")?;
+        let file = writer.finish().context("Unable to flush blob file")?;
+        self.failpoints.check(Failpoint::AfterBlobWrite)?;
+        if let Some(event_listener) = &self.event_listener {
+            event_listener.on_blob_file_created(&BlobFileInfo {
+                sequence_number: seq,
+                family: family as u32,
+                size,
+            });
+        }
+        if let Some(content_hash) = content_hash {
+            self.new_blob_hashes.lock().insert(content_hash, seq);
+        }
+        Ok((seq, Some(file)))
     }
 
     /// Creates a new SST file with the given collector data.
@@ -254,15 +824,24 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
         collector_data: (&[CollectorEntry<K>], usize, usize),
     ) -> Result<(u32, File)> {
         let (entries, total_key_size, total_value_size) = collector_data;
-        let seq = self.current_sequence_number.fetch_add(1, Ordering::SeqCst) + 1;
+        let seq = self.next_sequence_number()?;
+        let start = Instant::now();
 
-        let builder =
-            StaticSortedFileBuilder::new(family as u32, entries, total_key_size, total_value_size)?;
+        let builder = StaticSortedFileBuilder::new(
+            family as u32,
+            Compression::for_family(&self.compression, family as u32),
+            self.encryption.clone(),
+            (seq, seq),
+            entries,
+            total_key_size,
+            total_value_size,
+        )?;
 
         let path = self.path.join(format!("{:08}.sst", seq));
         let file = builder
-            .write(&path)
+            .write(&path, self.direct_io, self.rate_limiter.as_deref())
             .with_context(|| format!("Unable to write SST file {:08}.sst", seq))?;
+        self.failpoints.check(Failpoint::AfterSstWrite)?;
 
         #[cfg(feature = "verify_sst_content")]
         {
@@ -275,7 +854,8 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
             };
 
             file.sync_all()?;
-            let sst = StaticSortedFile::open(seq, path)?;
+            let sst =
+                StaticSortedFile::open(seq, path, true, self.encryption.clone(), None, false)?;
             let cache1 = AqmfCache::with(
                 10,
                 u64::MAX,
@@ -322,6 +902,59 @@ impl<K: StoreKey + Send + Sync, const FAMILIES: usize> WriteBatch<K, FAMILIES> {
             }
         }
 
+        if let Some(event_listener) = &self.event_listener {
+            event_listener.on_sst_file_created(&SstFileInfo {
+                sequence_number: seq,
+                family: family as u32,
+                entries: entries.len(),
+                tombstones: builder.tombstones(),
+                size: file.metadata()?.len(),
+                source: FileSource::WriteBatch,
+                duration: start.elapsed(),
+            });
+        }
         Ok((seq, file))
     }
 }
+
+/// Writes to families with their own key type. The keys are serialized, so families with different
+/// key types can be written with the same WriteBatch. See [Family].
+impl<const FAMILIES: usize> WriteBatch<Vec<u8>, FAMILIES> {
+    /// Puts a key-value pair into the family `F`.
+    pub fn put_typed<F: Family>(&self, key: &F::Key, value: ValueBuffer<'_>) -> Result<()> {
+        self.put(F::INDEX, serialize_key(key), value)
+    }
+
+    /// Puts a key-value pair into the family `F` that expires at the given time. See
+    /// [WriteBatch::put_with_expiry].
+    pub fn put_typed_with_expiry<F: Family>(
+        &self,
+        key: &F::Key,
+        value: ValueBuffer<'_>,
+        expires_at: SystemTime,
+    ) -> Result<()> {
+        self.put_with_expiry(F::INDEX, serialize_key(key), value, expires_at)
+    }
+
+    /// Deletes a key from the family `F`.
+    pub fn delete_typed<F: Family>(&self, key: &F::Key) -> Result<()> {
+        self.delete(F::INDEX, serialize_key(key))
+    }
+
+    /// Adds a merge operand for a key of the family `F`. See [WriteBatch::merge].
+    pub fn merge_typed<F: Family>(&self, key: &F::Key, operand: ValueBuffer<'_>) -> Result<()> {
+        self.merge(F::INDEX, serialize_key(key), operand)
+    }
+
+    /// Deletes all keys of the family `F` within a key range. See [WriteBatch::delete_range].
+    pub fn delete_range_typed<F: Family>(&self, start: &F::Key, end: &F::Key) -> Result<()> {
+        self.delete_range(F::INDEX, serialize_key(start), serialize_key(end))
+    }
+}
+
+/// Serializes a key of a family with its own key type.
+fn serialize_key(key: &impl StoreKey) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(key.len());
+    key.write_to(&mut buf);
+    buf
+}
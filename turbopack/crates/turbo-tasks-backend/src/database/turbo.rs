@@ -1,11 +1,6 @@
-use std::{
-    path::PathBuf,
-    sync::Arc,
-    thread::{spawn, JoinHandle},
-};
+use std::{path::PathBuf, sync::Arc};
 
 use anyhow::Result;
-use parking_lot::Mutex;
 use turbo_persistence::{ArcSlice, KeyBase, StoreKey, TurboPersistence, ValueBuffer};
 
 use crate::database::{
@@ -18,23 +13,17 @@ const COMPACT_MAX_MERGE_SEQUENCE: usize = 8;
 
 pub struct TurboKeyValueDatabase {
     db: Arc<TurboPersistence>,
-    compact_join_handle: Mutex<Option<JoinHandle<Result<()>>>>,
 }
 
 impl TurboKeyValueDatabase {
     pub fn new(path: PathBuf) -> Result<Self> {
         let db = Arc::new(TurboPersistence::open(path.to_path_buf())?);
-        let mut this = Self {
-            db: db.clone(),
-            compact_join_handle: Mutex::new(None),
-        };
         // start compaction in background if the database is not empty
         if !db.is_empty() {
-            let handle =
-                spawn(move || db.compact(COMPACT_MAX_COVERAGE, COMPACT_MAX_MERGE_SEQUENCE));
-            this.compact_join_handle.get_mut().replace(handle);
+            db.clone()
+                .spawn_compact(COMPACT_MAX_COVERAGE, COMPACT_MAX_MERGE_SEQUENCE)?;
         }
-        Ok(this)
+        Ok(Self { db })
     }
 }
 
@@ -80,24 +69,15 @@ impl KeyValueDatabase for TurboKeyValueDatabase {
     fn write_batch(
         &self,
     ) -> Result<WriteBatch<'_, Self::SerialWriteBatch<'_>, Self::ConcurrentWriteBatch<'_>>> {
-        // Wait for the compaction to finish
-        if let Some(join_handle) = self.compact_join_handle.lock().take() {
-            join_handle.join().unwrap()?;
-        }
-        // Start a new write batch
+        // Start a new write batch, this waits for the background compaction to finish
         Ok(WriteBatch::concurrent(TurboWriteBatch {
             batch: self.db.write_batch()?,
             db: &self.db,
-            compact_join_handle: &self.compact_join_handle,
         }))
     }
 
     fn shutdown(&self) -> Result<()> {
-        // Wait for the compaction to finish
-        if let Some(join_handle) = self.compact_join_handle.lock().take() {
-            join_handle.join().unwrap()?;
-        }
-        // Shutdown the database
+        // Shutdown the database, this waits for the background compaction to finish
         self.db.shutdown()
     }
 }
@@ -105,7 +85,6 @@ impl KeyValueDatabase for TurboKeyValueDatabase {
 pub struct TurboWriteBatch<'a> {
     batch: turbo_persistence::WriteBatch<WriteBuffer<'static>, 5>,
     db: &'a Arc<TurboPersistence>,
-    compact_join_handle: &'a Mutex<Option<JoinHandle<Result<()>>>>,
 }
 
 impl<'a> BaseWriteBatch<'a> for TurboWriteBatch<'a> {
@@ -127,11 +106,9 @@ impl<'a> BaseWriteBatch<'a> for TurboWriteBatch<'a> {
         self.db.commit_write_batch(self.batch)?;
 
         // Start a new compaction in the background
-        let db = self.db.clone();
-        let handle = spawn(move || db.compact(COMPACT_MAX_COVERAGE, COMPACT_MAX_MERGE_SEQUENCE));
-        self.compact_join_handle.lock().replace(handle);
-
-        Ok(())
+        self.db
+            .clone()
+            .spawn_compact(COMPACT_MAX_COVERAGE, COMPACT_MAX_MERGE_SEQUENCE)
     }
 }
 
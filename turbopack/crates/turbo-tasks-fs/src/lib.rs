@@ -19,6 +19,7 @@ mod read_glob;
 mod retry;
 pub mod rope;
 pub mod source_context;
+mod stamp;
 pub mod util;
 pub(crate) mod virtual_fs;
 mod watcher;
@@ -75,6 +76,7 @@ use crate::{
     invalidator_map::WriteContent,
     retry::{retry_blocking, retry_future},
     rope::{Rope, RopeReader},
+    stamp::{FileMetadataStamp, FileStamp},
 };
 
 /// A (somewhat arbitrary) filename limit that we should try to keep output file names below.
@@ -440,6 +442,33 @@ impl DiskFileSystem {
         self.inner.watcher.stop_watching();
     }
 
+    /// Reads the file at `full_path`, returning its content and the stamp of the file that was
+    /// read.
+    async fn read_file(&self, full_path: &Path) -> Result<(FileContent, Option<FileStamp>)> {
+        let _lock = self.inner.lock_path(full_path).await;
+        Ok(
+            match retry_future(|| File::from_path_with_stamp(full_path.to_path_buf()))
+                .concurrency_limited(&self.inner.semaphore)
+                .instrument(tracing::info_span!(
+                    "read file",
+                    path = display(full_path.display())
+                ))
+                .await
+            {
+                Ok((file, stamp)) => (FileContent::new(file), stamp),
+                Err(e)
+                    if e.kind() == ErrorKind::NotFound
+                        || e.kind() == ErrorKind::InvalidFilename =>
+                {
+                    (FileContent::NotFound, None)
+                }
+                Err(e) => {
+                    bail!(anyhow!(e).context(format!("reading file {}", full_path.display())))
+                }
+            },
+        )
+    }
+
     pub async fn to_sys_path(&self, fs_path: Vc<FileSystemPath>) -> Result<PathBuf> {
         // just in case there's a windows unc path prefix we remove it with `dunce`
         let path = self.inner.root_path();
@@ -477,8 +506,35 @@ pub fn path_to_key(path: impl AsRef<Path>) -> String {
     path.as_ref().to_string_lossy().to_string()
 }
 
+/// The content of a file that was read by [DiskFileSystem::read_stamped], together with the stamp
+/// of the version that was read.
+#[turbo_tasks::value(shared)]
+struct StampedFileContent {
+    content: FileContent,
+    stamp: Option<FileStamp>,
+}
+
 #[turbo_tasks::value_impl]
 impl DiskFileSystem {
+    /// Reads the content of a file, given the metadata it had when [FileSystem::read] was called.
+    /// Changes to the file are noticed by [FileSystem::read], which calls this again with new
+    /// metadata.
+    #[turbo_tasks::function(fs)]
+    async fn read_stamped(
+        &self,
+        fs_path: Vc<FileSystemPath>,
+        metadata: FileMetadataStamp,
+    ) -> Result<Vc<StampedFileContent>> {
+        let full_path = self.to_sys_path(fs_path).await?;
+        let (content, stamp) = self.read_file(&full_path).await?;
+        if stamp.is_none_or(|stamp| stamp.metadata() != metadata) {
+            // The file changed since the metadata was read, so the content doesn't belong to the
+            // metadata and must not be restored in a later session.
+            mark_session_dependent();
+        }
+        Ok(StampedFileContent { content, stamp }.cell())
+    }
+
     /// Create a new instance of `DiskFileSystem`.
     /// # Arguments
     ///
@@ -519,29 +575,43 @@ impl Debug for DiskFileSystem {
 
 #[turbo_tasks::value_impl]
 impl FileSystem for DiskFileSystem {
+    /// Reads a file. Only the file's metadata is read at first: the content is read by
+    /// [DiskFileSystem::read_stamped], which is keyed by the file's size and modification time.
+    /// Unlike this task, it isn't session dependent, so after a restart with a persistent cache,
+    /// unchanged files are neither read nor parsed again. When the content was read within the
+    /// racy window of the file's modification, the metadata doesn't identify it, so the file is
+    /// read again and the content hashes are compared. When only the modification time changed,
+    /// the content is read again, but it compares equal to the previous content, which doesn't
+    /// invalidate dependent tasks.
     #[turbo_tasks::function(fs)]
-    async fn read(&self, fs_path: Vc<FileSystemPath>) -> Result<Vc<FileContent>> {
+    async fn read(self: Vc<Self>, fs_path: Vc<FileSystemPath>) -> Result<Vc<FileContent>> {
         mark_session_dependent();
-        let full_path = self.to_sys_path(fs_path).await?;
-        self.inner.register_read_invalidator(&full_path)?;
+        let this = self.await?;
+        let full_path = this.to_sys_path(fs_path).await?;
+        this.inner.register_read_invalidator(&full_path)?;
 
-        let _lock = self.inner.lock_path(&full_path).await;
-        let content = match retry_future(|| File::from_path(full_path.clone()))
-            .concurrency_limited(&self.inner.semaphore)
-            .instrument(tracing::info_span!(
-                "read file",
-                path = display(full_path.display())
-            ))
-            .await
-        {
-            Ok(file) => FileContent::new(file),
-            Err(e) if e.kind() == ErrorKind::NotFound || e.kind() == ErrorKind::InvalidFilename => {
-                FileContent::NotFound
-            }
-            Err(e) => {
-                bail!(anyhow!(e).context(format!("reading file {}", full_path.display())))
+        let metadata = {
+            let _lock = this.inner.lock_path(&full_path).await;
+            match retry_blocking(&full_path, |path| std::fs::metadata(path))
+                .concurrency_limited(&this.inner.semaphore)
+                .await
+            {
+                Ok(meta) if meta.is_file() => FileMetadataStamp::from_metadata(&meta),
+                _ => None,
             }
         };
+
+        let Some(metadata) = metadata else {
+            return Ok(this.read_file(&full_path).await?.0.cell());
+        };
+        let stamped = self.read_stamped(fs_path, metadata).await?;
+        let Some(stamp) = stamped.stamp.filter(|stamp| !stamp.is_fresh(&metadata)) else {
+            return Ok(stamped.content.clone().cell());
+        };
+        let (content, current_stamp) = this.read_file(&full_path).await?;
+        if current_stamp.is_some_and(|current_stamp| stamp.is_unchanged(&current_stamp)) {
+            return Ok(stamped.content.clone().cell());
+        }
         Ok(content.cell())
     }
 
@@ -1846,18 +1916,22 @@ pub struct File {
 }
 
 impl File {
-    /// Reads a [File] from the given path
-    async fn from_path(p: PathBuf) -> io::Result<Self> {
+    /// Reads a [File] from the given path, together with the stamp of the file that was read.
+    async fn from_path_with_stamp(p: PathBuf) -> io::Result<(Self, Option<FileStamp>)> {
         let mut file = fs::File::open(p).await?;
         let metadata = file.metadata().await?;
 
         let mut output = Vec::with_capacity(metadata.len() as usize);
         file.read_to_end(&mut output).await?;
 
-        Ok(File {
-            meta: metadata.into(),
-            content: Rope::from(output),
-        })
+        let stamp = FileStamp::new(&metadata, &output);
+        Ok((
+            File {
+                meta: metadata.into(),
+                content: Rope::from(output),
+            },
+            stamp,
+        ))
     }
 
     /// Creates a [File] from raw bytes.
@@ -0,0 +1,173 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{trace::TraceRawVcs, NonLocalValue, TaskInput};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+/// Files modified less than this long ago are never identified by their metadata, as another write
+/// within the granularity of the file system's timestamps would go unnoticed. This is the same
+/// "racy" check git uses for its index.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Identifies a version of a file on disk without reading it, by its size and modification time.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    TaskInput,
+    TraceRawVcs,
+    NonLocalValue,
+)]
+pub(crate) struct FileMetadataStamp {
+    len: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+}
+
+impl FileMetadataStamp {
+    /// Returns the stamp of the file with the given metadata, or `None` when the file can't be
+    /// reliably identified by it, e.g. because it was modified just now or the platform doesn't
+    /// report modification times.
+    pub(crate) fn from_metadata(meta: &std::fs::Metadata) -> Option<Self> {
+        Self::from_metadata_unchecked(meta).filter(|stamp| !stamp.is_racy_at(SystemTime::now()))
+    }
+
+    /// Like [FileMetadataStamp::from_metadata], but without the check for recent modifications.
+    pub(crate) fn from_metadata_unchecked(meta: &std::fs::Metadata) -> Option<Self> {
+        Self::new(meta.len(), meta.modified().ok()?)
+    }
+
+    fn new(len: u64, mtime: SystemTime) -> Option<Self> {
+        let mtime = mtime.duration_since(UNIX_EPOCH).ok()?;
+        Some(FileMetadataStamp {
+            len,
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+        })
+    }
+
+    /// Whether the file was modified less than [RACY_WINDOW] before `time`, so it might be
+    /// modified again without a change of its metadata.
+    fn is_racy_at(&self, time: SystemTime) -> bool {
+        let mtime = UNIX_EPOCH + Duration::new(self.mtime_secs, self.mtime_nanos);
+        !matches!(time.duration_since(mtime), Ok(age) if age >= RACY_WINDOW)
+    }
+}
+
+/// Identifies the version of a file that was read, by its metadata and the hash of its content.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs, NonLocalValue,
+)]
+pub(crate) struct FileStamp {
+    metadata: FileMetadataStamp,
+    /// The xxh3 hash of the content that was read.
+    content_hash: u64,
+    /// Whether the file was read within the racy window of its modification.
+    racy: bool,
+}
+
+impl FileStamp {
+    /// Returns the stamp of a file that was just read, given its metadata and the content that
+    /// was read.
+    pub(crate) fn new(meta: &std::fs::Metadata, content: &[u8]) -> Option<Self> {
+        Some(Self::new_at(
+            FileMetadataStamp::from_metadata_unchecked(meta)?,
+            content,
+            SystemTime::now(),
+        ))
+    }
+
+    fn new_at(metadata: FileMetadataStamp, content: &[u8], read_time: SystemTime) -> Self {
+        FileStamp {
+            metadata,
+            content_hash: hash_xxh3_hash64(content),
+            racy: metadata.is_racy_at(read_time),
+        }
+    }
+
+    pub(crate) fn metadata(&self) -> FileMetadataStamp {
+        self.metadata
+    }
+
+    /// Whether the content that was read still belongs to a file with the given metadata, without
+    /// reading it again. This is not the case when the file was read within the racy window of
+    /// its modification, as a later write might have kept the metadata. The content hash has to
+    /// be compared then, see [FileStamp::is_unchanged].
+    pub(crate) fn is_fresh(&self, metadata: &FileMetadataStamp) -> bool {
+        !self.racy && self.metadata == *metadata
+    }
+
+    /// Whether the file has the same content as when this stamp was taken, given the stamp of
+    /// reading it again.
+    pub(crate) fn is_unchanged(&self, current: &FileStamp) -> bool {
+        self.metadata == current.metadata && self.content_hash == current.content_hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{FileMetadataStamp, FileStamp, RACY_WINDOW};
+
+    fn mtime() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    fn metadata(len: u64, mtime: SystemTime) -> FileMetadataStamp {
+        FileMetadataStamp::new(len, mtime).unwrap()
+    }
+
+    fn read_later(metadata: FileMetadataStamp, content: &[u8]) -> FileStamp {
+        FileStamp::new_at(metadata, content, mtime() + Duration::from_secs(60))
+    }
+
+    #[test]
+    fn unchanged_stamp() {
+        let stamp = read_later(metadata(5, mtime()), b"hello");
+        assert!(stamp.is_fresh(&metadata(5, mtime())));
+        assert!(stamp.is_unchanged(&read_later(metadata(5, mtime()), b"hello")));
+    }
+
+    #[test]
+    fn changed_len() {
+        let stamp = read_later(metadata(5, mtime()), b"hello");
+        assert!(!stamp.is_fresh(&metadata(6, mtime())));
+        assert!(!stamp.is_unchanged(&read_later(metadata(6, mtime()), b"hello!")));
+    }
+
+    #[test]
+    fn changed_mtime() {
+        let stamp = read_later(metadata(5, mtime()), b"hello");
+        let modified = mtime() + Duration::from_nanos(1);
+        assert!(!stamp.is_fresh(&metadata(5, modified)));
+        assert!(!stamp.is_unchanged(&read_later(metadata(5, modified), b"hello")));
+    }
+
+    #[test]
+    fn racy_window() {
+        assert!(metadata(5, mtime()).is_racy_at(mtime()));
+        assert!(metadata(5, mtime()).is_racy_at(mtime() + RACY_WINDOW / 2));
+        // Modification times in the future are racy too
+        assert!(metadata(5, mtime()).is_racy_at(mtime() - Duration::from_secs(1)));
+        assert!(!metadata(5, mtime()).is_racy_at(mtime() + RACY_WINDOW));
+
+        // The content of a file that was read right after it was modified can't be trusted by its
+        // metadata only, the hash has to be compared.
+        let stamp = FileStamp::new_at(metadata(5, mtime()), b"hello", mtime());
+        assert!(!stamp.is_fresh(&metadata(5, mtime())));
+        assert!(stamp.is_unchanged(&read_later(metadata(5, mtime()), b"hello")));
+        assert!(!stamp.is_unchanged(&read_later(metadata(5, mtime()), b"world")));
+    }
+
+    #[test]
+    fn same_metadata_different_content() {
+        let stamp = read_later(metadata(5, mtime()), b"hello");
+        assert!(!stamp.is_unchanged(&read_later(metadata(5, mtime()), b"world")));
+    }
+}
@@ -50,6 +50,16 @@ impl TaskStatistics {
         self.with_task_type_statistics(function_id, |stats| stats.cache_miss += 1)
     }
 
+    /// Returns the number of cache hits and cache misses, summed over all functions.
+    pub fn total_cache_hits_and_misses(&self) -> (u64, u64) {
+        self.inner.iter().fold((0, 0), |(hits, misses), entry| {
+            (
+                hits + u64::from(entry.cache_hit),
+                misses + u64::from(entry.cache_miss),
+            )
+        })
+    }
+
     fn with_task_type_statistics(
         &self,
         task_function_id: FunctionId,
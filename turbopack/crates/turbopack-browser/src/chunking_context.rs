@@ -13,7 +13,7 @@ use turbopack_core::{
     chunk::{
         availability_info::AvailabilityInfo,
         chunk_group::{make_chunk_group, MakeChunkGroupResult},
-        module_id_strategies::{DevModuleIdStrategy, ModuleIdStrategy},
+        module_id_strategies::{hashed_module_id, DevModuleIdStrategy, ModuleIdStrategy},
         Chunk, ChunkGroupResult, ChunkItem, ChunkType, ChunkableModule, ChunkingConfig,
         ChunkingConfigs, ChunkingContext, EntryChunkGroupResult, EvaluatableAsset,
         EvaluatableAssets, MinifyType, ModuleId, SourceMapsType,
@@ -35,6 +35,7 @@ use crate::ecmascript::{
     chunk::EcmascriptBrowserChunk,
     evaluate::chunk::EcmascriptBrowserEvaluateChunk,
     list::asset::{EcmascriptDevChunkList, EcmascriptDevChunkListSource},
+    runtime::chunk::EcmascriptBrowserRuntimeChunk,
 };
 
 #[turbo_tasks::value]
@@ -70,6 +71,20 @@ pub enum ContentHashing {
         /// due to the high risk of collisions.
         length: u8,
     },
+    /// Like [ContentHashing::Direct], but the file name starts with the readable name of the
+    /// chunk, followed by the content hash.
+    Named {
+        /// The length of the content hash in hex chars.
+        length: u8,
+    },
+    /// Like [ContentHashing::Named], but the name is a hash of the chunk ident, which only depends
+    /// on the paths of the modules in the chunk, so it stays the same when the code changes.
+    HashedName {
+        /// The length of the hash of the chunk ident in hex chars.
+        name_length: u8,
+        /// The length of the content hash in hex chars.
+        length: u8,
+    },
 }
 
 pub struct BrowserChunkingContextBuilder {
@@ -97,6 +112,26 @@ impl BrowserChunkingContextBuilder {
         self
     }
 
+    pub fn simple_tree_shaking(mut self, enable_simple_tree_shaking: bool) -> Self {
+        self.chunking_context.enable_simple_tree_shaking = enable_simple_tree_shaking;
+        self
+    }
+
+    pub fn export_mangling(mut self, enable_export_mangling: bool) -> Self {
+        self.chunking_context.enable_export_mangling = enable_export_mangling;
+        self
+    }
+
+    pub fn scope_hoisting(mut self, enable_scope_hoisting: bool) -> Self {
+        self.chunking_context.enable_scope_hoisting = enable_scope_hoisting;
+        self
+    }
+
+    pub fn json_tree_shaking(mut self, enable_json_tree_shaking: bool) -> Self {
+        self.chunking_context.enable_json_tree_shaking = enable_json_tree_shaking;
+        self
+    }
+
     pub fn asset_base_path(mut self, asset_base_path: ResolvedVc<Option<RcStr>>) -> Self {
         self.chunking_context.asset_base_path = asset_base_path;
         self
@@ -122,11 +157,21 @@ impl BrowserChunkingContextBuilder {
         self
     }
 
+    pub fn separate_runtime_chunk(mut self, separate_runtime_chunk: bool) -> Self {
+        self.chunking_context.separate_runtime_chunk = separate_runtime_chunk;
+        self
+    }
+
     pub fn minify_type(mut self, minify_type: MinifyType) -> Self {
         self.chunking_context.minify_type = minify_type;
         self
     }
 
+    pub fn legacy_output(mut self, legacy_output: bool) -> Self {
+        self.chunking_context.legacy_output = legacy_output;
+        self
+    }
+
     pub fn source_maps(mut self, source_maps: SourceMapsType) -> Self {
         self.chunking_context.source_maps_type = source_maps;
         self
@@ -202,12 +247,22 @@ pub struct BrowserChunkingContext {
     enable_hot_module_replacement: bool,
     /// Enable tracing for this chunking
     enable_tracing: bool,
+    /// Omit side effect free modules of which no export is used
+    enable_simple_tree_shaking: bool,
+    /// Rename exports that are only accessed by known importers to short names
+    enable_export_mangling: bool,
+    /// Merge modules with a single ESM importer into the module factory of their importer
+    enable_scope_hoisting: bool,
+    /// Only include the top level keys of JSON modules that their importers read
+    enable_json_tree_shaking: bool,
     /// The environment chunks will be evaluated in.
     environment: ResolvedVc<Environment>,
     /// The kind of runtime to include in the output.
     runtime_type: RuntimeType,
     /// Whether to minify resulting chunks
     minify_type: MinifyType,
+    /// Whether to compile the chunks, including the runtime, to ES5 for legacy browsers
+    legacy_output: bool,
     /// Whether content hashing is enabled.
     content_hashing: Option<ContentHashing>,
     /// Whether to generate source maps
@@ -216,6 +271,9 @@ pub struct BrowserChunkingContext {
     current_chunk_method: CurrentChunkMethod,
     /// Whether to use manifest chunks for lazy compilation
     manifest_chunks: bool,
+    /// Whether to emit the runtime as a chunk of its own instead of including it in the evaluate
+    /// chunk of each chunk group
+    separate_runtime_chunk: bool,
     /// The module id strategy to use
     module_id_strategy: ResolvedVc<Box<dyn ModuleIdStrategy>>,
     /// The chunking configs
@@ -248,13 +306,19 @@ impl BrowserChunkingContext {
                 asset_base_path: ResolvedVc::cell(None),
                 enable_hot_module_replacement: false,
                 enable_tracing: false,
+                enable_simple_tree_shaking: false,
+                enable_export_mangling: false,
+                enable_scope_hoisting: false,
+                enable_json_tree_shaking: false,
                 environment,
                 runtime_type,
                 minify_type: MinifyType::NoMinify,
+                legacy_output: false,
                 content_hashing: None,
                 source_maps_type: SourceMapsType::Full,
                 current_chunk_method: CurrentChunkMethod::StringLiteral,
                 manifest_chunks: false,
+                separate_runtime_chunk: false,
                 module_id_strategy: ResolvedVc::upcast(DevModuleIdStrategy::new_resolved()),
                 chunking_configs: Default::default(),
             },
@@ -288,7 +352,17 @@ impl BrowserChunkingContext {
 
     /// Returns the minify type.
     pub fn minify_type(&self) -> MinifyType {
-        self.minify_type
+        self.minify_type.clone()
+    }
+
+    /// Returns whether the chunks are compiled to ES5 for legacy browsers.
+    pub fn legacy_output(&self) -> bool {
+        self.legacy_output
+    }
+
+    /// Returns whether the runtime is emitted as a chunk of its own.
+    pub fn separate_runtime_chunk(&self) -> bool {
+        self.separate_runtime_chunk
     }
 }
 
@@ -317,6 +391,19 @@ impl BrowserChunkingContext {
         ))
     }
 
+    #[turbo_tasks::function]
+    fn generate_runtime_chunk(
+        self: Vc<Self>,
+        ident: Vc<AssetIdent>,
+        module_graph: Vc<ModuleGraph>,
+    ) -> Vc<Box<dyn OutputAsset>> {
+        Vc::upcast(EcmascriptBrowserRuntimeChunk::new(
+            self,
+            ident,
+            module_graph,
+        ))
+    }
+
     #[turbo_tasks::function]
     fn generate_chunk_list_register_chunk(
         self: Vc<Self>,
@@ -354,6 +441,29 @@ impl BrowserChunkingContext {
         )
     }
 
+    /// Like [BrowserChunkingContext::generate_chunk], but Ecmascript chunks are named after the
+    /// given name.
+    #[turbo_tasks::function]
+    async fn generate_named_chunk(
+        self: Vc<Self>,
+        chunk: Vc<Box<dyn Chunk>>,
+        name: RcStr,
+    ) -> Result<Vc<Box<dyn OutputAsset>>> {
+        Ok(
+            if let Some(ecmascript_chunk) =
+                Vc::try_resolve_downcast_type::<EcmascriptChunk>(chunk).await?
+            {
+                Vc::upcast(EcmascriptBrowserChunk::new_named(
+                    self,
+                    ecmascript_chunk,
+                    name,
+                ))
+            } else {
+                self.generate_chunk(chunk)
+            },
+        )
+    }
+
     #[turbo_tasks::function]
     pub fn current_chunk_method(&self) -> Vc<CurrentChunkMethod> {
         self.current_chunk_method.cell()
@@ -412,20 +522,25 @@ impl ChunkingContext for BrowserChunkingContext {
                     .await?
             }
             Some(ContentHashing::Direct { length }) => {
-                let Some(asset) = asset else {
-                    bail!("chunk_path requires an asset when content hashing is enabled");
-                };
-                let content = asset.content().await?;
-                if let AssetContent::File(file) = &*content {
-                    let hash = hash_xxh3_hash64(&file.await?);
-                    let length = length as usize;
-                    format!("{hash:0length$x}{extension}").into()
-                } else {
-                    bail!(
-                        "chunk_path requires an asset with file content when content hashing is \
-                         enabled"
-                    );
-                }
+                let hash = content_hash(asset, length).await?;
+                format!("{hash}{extension}").into()
+            }
+            Some(ContentHashing::Named { length }) => {
+                let hash = content_hash(asset, length).await?;
+                let name = ident
+                    .output_name(*self.root_path, extension.clone())
+                    .owned()
+                    .await?;
+                let name = name.strip_suffix(&*extension).unwrap_or(name.as_str());
+                format!("{name}.{hash}{extension}").into()
+            }
+            Some(ContentHashing::HashedName {
+                name_length,
+                length,
+            }) => {
+                let hash = content_hash(asset, length).await?;
+                let name = hashed_module_id(&ident.to_string().await?, name_length as usize);
+                format!("{name}.{hash}{extension}").into()
             }
         };
         Ok(root_path.join(name))
@@ -510,9 +625,29 @@ impl ChunkingContext for BrowserChunkingContext {
         Vc::cell(self.enable_tracing)
     }
 
+    #[turbo_tasks::function]
+    fn is_simple_tree_shaking_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_simple_tree_shaking)
+    }
+
+    #[turbo_tasks::function]
+    fn is_export_mangling_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_export_mangling)
+    }
+
+    #[turbo_tasks::function]
+    fn is_scope_hoisting_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_scope_hoisting)
+    }
+
+    #[turbo_tasks::function]
+    fn is_json_tree_shaking_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_json_tree_shaking)
+    }
+
     #[turbo_tasks::function]
     pub fn minify_type(&self) -> Vc<MinifyType> {
-        self.minify_type.cell()
+        self.minify_type.clone().cell()
     }
 
     #[turbo_tasks::function]
@@ -539,9 +674,31 @@ impl ChunkingContext for BrowserChunkingContext {
             )
             .await?;
 
+            // Async chunk groups can be named by the magic comments of `import()`. Only the chunks
+            // that contain the imported module are named, as the other chunks might be shared with
+            // other chunk groups.
+            let named_entry = match &chunk_group {
+                ChunkGroup::Async(module) => module_graph
+                    .async_chunk_group_options()
+                    .await?
+                    .get(module)
+                    .and_then(|options| options.name.clone())
+                    .map(|name| (*module, name)),
+                _ => None,
+            };
             let mut assets = chunks
                 .iter()
-                .map(|chunk| self.generate_chunk(**chunk).to_resolved())
+                .map(async |&chunk| {
+                    if let Some((module, name)) = &named_entry {
+                        if chunk_contains_module(*chunk, *module).await? {
+                            return self
+                                .generate_named_chunk(*chunk, name.clone())
+                                .to_resolved()
+                                .await;
+                        }
+                    }
+                    self.generate_chunk(*chunk).to_resolved().await
+                })
                 .try_join()
                 .await?;
 
@@ -646,6 +803,16 @@ impl ChunkingContext for BrowserChunkingContext {
                     .await?,
             );
 
+            // The evaluate chunk only registers itself when the runtime is a separate chunk, so
+            // its content doesn't change when the runtime does
+            if this.separate_runtime_chunk {
+                assets.push(
+                    self.generate_runtime_chunk(ident, module_graph)
+                        .to_resolved()
+                        .await?,
+                );
+            }
+
             Ok(ChunkGroupResult {
                 assets: ResolvedVc::cell(assets),
                 availability_info,
@@ -706,3 +873,29 @@ impl ChunkingContext for BrowserChunkingContext {
         })
     }
 }
+
+/// Hashes the final content of a chunk for its file name when content hashing is enabled.
+async fn content_hash(asset: Option<Vc<Box<dyn Asset>>>, length: u8) -> Result<String> {
+    let Some(asset) = asset else {
+        bail!("chunk_path requires an asset when content hashing is enabled");
+    };
+    let content = asset.content().await?;
+    let AssetContent::File(file) = &*content else {
+        bail!("chunk_path requires an asset with file content when content hashing is enabled");
+    };
+    let hash = format!("{:016x}", hash_xxh3_hash64(&file.await?));
+    Ok(hash[..(length as usize).min(hash.len())].to_string())
+}
+
+/// Returns true if the chunk contains the chunk item of the module.
+async fn chunk_contains_module(
+    chunk: Vc<Box<dyn Chunk>>,
+    module: ResolvedVc<Box<dyn Module>>,
+) -> Result<bool> {
+    for chunk_item in chunk.chunk_items().await?.iter() {
+        if chunk_item.module().to_resolved().await? == module {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
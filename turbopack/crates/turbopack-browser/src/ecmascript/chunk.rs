@@ -20,6 +20,8 @@ use crate::{ecmascript::content::EcmascriptBrowserChunkContent, BrowserChunkingC
 pub struct EcmascriptBrowserChunk {
     chunking_context: ResolvedVc<BrowserChunkingContext>,
     chunk: ResolvedVc<EcmascriptChunk>,
+    /// The name of the chunk, e.g. from the magic comments of an `import()`.
+    name: Option<RcStr>,
 }
 
 #[turbo_tasks::value_impl]
@@ -33,6 +35,22 @@ impl EcmascriptBrowserChunk {
         EcmascriptBrowserChunk {
             chunking_context,
             chunk,
+            name: None,
+        }
+        .cell()
+    }
+
+    /// Creates a new [`Vc<EcmascriptDevChunk>`] whose path is based on the given name.
+    #[turbo_tasks::function]
+    pub fn new_named(
+        chunking_context: ResolvedVc<BrowserChunkingContext>,
+        chunk: ResolvedVc<EcmascriptChunk>,
+        name: RcStr,
+    ) -> Vc<Self> {
+        EcmascriptBrowserChunk {
+            chunking_context,
+            chunk,
+            name: Some(name),
         }
         .cell()
     }
@@ -50,7 +68,15 @@ impl EcmascriptBrowserChunk {
 
 impl EcmascriptBrowserChunk {
     fn ident_for_path(&self) -> Vc<AssetIdent> {
-        self.chunk.ident().with_modifier(modifier())
+        let ident = match &self.name {
+            // The ident of the chunk keeps chunks with the same name apart
+            Some(name) => {
+                AssetIdent::from_path(self.chunking_context.root_path().join(name.clone()))
+                    .with_modifier(self.chunk.ident().to_string())
+            }
+            None => self.chunk.ident(),
+        };
+        ident.with_modifier(modifier())
     }
 }
 
@@ -14,7 +14,9 @@ use turbopack_core::{
     source_map::{GenerateSourceMap, OptionStringifiedSourceMap, SourceMapAsset},
     version::{MergeableVersionedContent, Version, VersionedContent, VersionedContentMerger},
 };
-use turbopack_ecmascript::{chunk::EcmascriptChunkContent, minify::minify, utils::StringifyJs};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkContent, es5::compile_to_es5, minify::minify, utils::StringifyJs,
+};
 
 use super::{
     chunk::EcmascriptBrowserChunk, content_entry::EcmascriptBrowserChunkContentEntries,
@@ -127,8 +129,12 @@ impl EcmascriptBrowserChunkContent {
 
         let mut code = code.build();
 
-        if let MinifyType::Minify { mangle } = this.chunking_context.await?.minify_type() {
-            code = minify(&code, source_maps, mangle)?;
+        let chunking_context = this.chunking_context.await?;
+        if chunking_context.legacy_output() {
+            code = compile_to_es5(&code, source_maps)?;
+        }
+        if let MinifyType::Minify(options) = chunking_context.minify_type() {
+            code = minify(&code, source_maps, &options)?;
         }
 
         Ok(code.cell())
@@ -22,18 +22,19 @@ use turbopack_core::{
 };
 use turbopack_ecmascript::{
     chunk::{EcmascriptChunkData, EcmascriptChunkPlaceable},
+    es5::compile_to_es5,
     minify::minify,
     utils::StringifyJs,
 };
-use turbopack_ecmascript_runtime::RuntimeType;
 
 use crate::{
     chunking_context::{CurrentChunkMethod, CURRENT_CHUNK_METHOD_DOCUMENT_CURRENT_SCRIPT_EXPR},
+    ecmascript::runtime::chunk::browser_runtime_code,
     BrowserChunkingContext,
 };
 
 /// An Ecmascript chunk that:
-/// * Contains the Turbopack browser runtime code; and
+/// * Contains the Turbopack browser runtime code, unless the runtime is a separate chunk; and
 /// * Evaluates a list of runtime entries.
 #[turbo_tasks::value(shared)]
 pub(crate) struct EcmascriptBrowserEvaluateChunk {
@@ -76,9 +77,6 @@ impl EcmascriptBrowserEvaluateChunk {
     async fn code(self: Vc<Self>) -> Result<Vc<Code>> {
         let this = self.await?;
         let chunking_context = this.chunking_context.await?;
-        let environment = this.chunking_context.environment();
-
-        let output_root_to_root_path = this.chunking_context.output_root_to_root_path();
         let source_maps = *this
             .chunking_context
             .reference_chunk_source_maps(Vc::upcast(self))
@@ -161,40 +159,20 @@ impl EcmascriptBrowserEvaluateChunk {
             StringifyJs(&params),
         )?;
 
-        match chunking_context.runtime_type() {
-            RuntimeType::Development => {
-                let runtime_code = turbopack_ecmascript_runtime::get_browser_runtime_code(
-                    environment,
-                    chunking_context.chunk_base_path(),
-                    chunking_context.chunk_suffix_path(),
-                    Value::new(chunking_context.runtime_type()),
-                    output_root_to_root_path,
-                    source_maps,
-                );
-                code.push_code(&*runtime_code.await?);
-            }
-            RuntimeType::Production => {
-                let runtime_code = turbopack_ecmascript_runtime::get_browser_runtime_code(
-                    environment,
-                    chunking_context.chunk_base_path(),
-                    chunking_context.chunk_suffix_path(),
-                    Value::new(chunking_context.runtime_type()),
-                    output_root_to_root_path,
-                    source_maps,
-                );
-                code.push_code(&*runtime_code.await?);
-            }
-            #[cfg(feature = "test")]
-            RuntimeType::Dummy => {
-                let runtime_code = turbopack_ecmascript_runtime::get_dummy_runtime_code();
-                code.push_code(&runtime_code);
-            }
+        if !chunking_context.separate_runtime_chunk() {
+            let runtime_code =
+                browser_runtime_code(*this.chunking_context, *this.module_graph, source_maps);
+            code.push_code(&*runtime_code.await?);
         }
 
         let mut code = code.build();
 
-        if let MinifyType::Minify { mangle } = this.chunking_context.await?.minify_type() {
-            code = minify(&code, source_maps, mangle)?;
+        let chunking_context = this.chunking_context.await?;
+        if chunking_context.legacy_output() {
+            code = compile_to_es5(&code, source_maps)?;
+        }
+        if let MinifyType::Minify(options) = chunking_context.minify_type() {
+            code = minify(&code, source_maps, &options)?;
         }
 
         Ok(code.cell())
@@ -18,7 +18,7 @@ use turbopack_core::{
         MergeableVersionedContent, Update, Version, VersionedContent, VersionedContentMerger,
     },
 };
-use turbopack_ecmascript::utils::StringifyJs;
+use turbopack_ecmascript::{es5::compile_to_es5, utils::StringifyJs};
 
 use super::{
     asset::{EcmascriptDevChunkList, EcmascriptDevChunkListSource},
@@ -39,6 +39,7 @@ enum CurrentChunkMethodWithData {
 #[turbo_tasks::value]
 pub(super) struct EcmascriptDevChunkListContent {
     current_chunk_method: CurrentChunkMethodWithData,
+    legacy_output: bool,
     pub(super) chunks_contents: FxIndexMap<String, ResolvedVc<Box<dyn VersionedContent>>>,
     source: EcmascriptDevChunkListSource,
 }
@@ -68,6 +69,7 @@ impl EcmascriptDevChunkListContent {
         };
         Ok(EcmascriptDevChunkListContent {
             current_chunk_method,
+            legacy_output: chunk_list_ref.chunking_context.await?.legacy_output(),
             chunks_contents: chunk_list_ref
                 .chunks
                 .await?
@@ -165,7 +167,12 @@ impl EcmascriptDevChunkListContent {
             StringifyJs(&this.source),
         )?;
 
-        Ok(Code::cell(code.build()))
+        let mut code = code.build();
+        if this.legacy_output {
+            code = compile_to_es5(&code, false)?;
+        }
+
+        Ok(Code::cell(code))
     }
 }
 
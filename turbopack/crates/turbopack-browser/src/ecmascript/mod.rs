@@ -4,6 +4,7 @@ pub(crate) mod content_entry;
 pub(crate) mod evaluate;
 pub(crate) mod list;
 pub(crate) mod merged;
+pub(crate) mod runtime;
 pub(crate) mod update;
 pub(crate) mod version;
 
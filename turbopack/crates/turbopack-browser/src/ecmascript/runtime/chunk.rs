@@ -0,0 +1,223 @@
+use std::io::Write;
+
+use anyhow::Result;
+use turbo_rcstr::RcStr;
+use turbo_tasks::{ResolvedVc, Value, ValueToString, Vc};
+use turbo_tasks_fs::{rope::RopeBuilder, File, FileSystemPath};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    chunk::{ChunkingContext, MinifyType},
+    code_builder::{Code, CodeBuilder},
+    ident::AssetIdent,
+    module::Module,
+    module_graph::{chunk_group_info::ChunkGroup, ModuleGraph},
+    output::{OutputAsset, OutputAssets},
+    source_map::{GenerateSourceMap, OptionStringifiedSourceMap, SourceMapAsset},
+};
+use turbopack_ecmascript::{es5::compile_to_es5, minify::minify};
+use turbopack_ecmascript_runtime::{RuntimeFeatures, RuntimeType};
+use turbopack_wasm::module_asset::WebAssemblyModuleAsset;
+
+use crate::BrowserChunkingContext;
+
+/// An Ecmascript chunk that only contains the Turbopack browser runtime code. It's emitted when
+/// the runtime is a separate chunk, so the evaluate chunks of the chunk groups don't change when
+/// only the runtime does.
+#[turbo_tasks::value(shared)]
+pub(crate) struct EcmascriptBrowserRuntimeChunk {
+    chunking_context: ResolvedVc<BrowserChunkingContext>,
+    ident: ResolvedVc<AssetIdent>,
+    module_graph: ResolvedVc<ModuleGraph>,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptBrowserRuntimeChunk {
+    /// Creates a new [`Vc<EcmascriptBrowserRuntimeChunk>`].
+    #[turbo_tasks::function]
+    pub fn new(
+        chunking_context: ResolvedVc<BrowserChunkingContext>,
+        ident: ResolvedVc<AssetIdent>,
+        module_graph: ResolvedVc<ModuleGraph>,
+    ) -> Vc<Self> {
+        EcmascriptBrowserRuntimeChunk {
+            chunking_context,
+            ident,
+            module_graph,
+        }
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn code(self: Vc<Self>) -> Result<Vc<Code>> {
+        let this = self.await?;
+        let source_maps = *this
+            .chunking_context
+            .reference_chunk_source_maps(Vc::upcast(self))
+            .await?;
+
+        let mut code = CodeBuilder::new(source_maps);
+        let runtime_code =
+            browser_runtime_code(*this.chunking_context, *this.module_graph, source_maps);
+        code.push_code(&*runtime_code.await?);
+        let mut code = code.build();
+
+        let chunking_context = this.chunking_context.await?;
+        if chunking_context.legacy_output() {
+            code = compile_to_es5(&code, source_maps)?;
+        }
+        if let MinifyType::Minify(options) = chunking_context.minify_type() {
+            code = minify(&code, source_maps, &options)?;
+        }
+
+        Ok(code.cell())
+    }
+
+    #[turbo_tasks::function]
+    async fn ident_for_path(&self) -> Result<Vc<AssetIdent>> {
+        let mut ident = self.ident.owned().await?;
+        ident.add_modifier(modifier().to_resolved().await?);
+        Ok(AssetIdent::new(Value::new(ident)))
+    }
+
+    #[turbo_tasks::function]
+    async fn source_map(self: Vc<Self>) -> Result<Vc<SourceMapAsset>> {
+        let this = self.await?;
+        Ok(SourceMapAsset::new(
+            Vc::upcast(*this.chunking_context),
+            self.ident_for_path(),
+            Vc::upcast(self),
+        ))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for EcmascriptBrowserRuntimeChunk {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> Vc<RcStr> {
+        Vc::cell("Ecmascript Browser Runtime Chunk".into())
+    }
+}
+
+#[turbo_tasks::function]
+fn modifier() -> Vc<RcStr> {
+    Vc::cell("ecmascript browser runtime chunk".into())
+}
+
+#[turbo_tasks::value_impl]
+impl OutputAsset for EcmascriptBrowserRuntimeChunk {
+    #[turbo_tasks::function]
+    async fn path(self: Vc<Self>) -> Result<Vc<FileSystemPath>> {
+        let this = self.await?;
+        let ident = self.ident_for_path();
+        Ok(this
+            .chunking_context
+            .chunk_path(Some(Vc::upcast(self)), ident, ".js".into()))
+    }
+
+    #[turbo_tasks::function]
+    async fn references(self: Vc<Self>) -> Result<Vc<OutputAssets>> {
+        let this = self.await?;
+        let mut references = Vec::new();
+
+        let include_source_map = *this
+            .chunking_context
+            .reference_chunk_source_maps(Vc::upcast(self))
+            .await?;
+
+        if include_source_map {
+            references.push(ResolvedVc::upcast(self.source_map().to_resolved().await?));
+        }
+
+        Ok(Vc::cell(references))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptBrowserRuntimeChunk {
+    #[turbo_tasks::function]
+    async fn content(self: Vc<Self>) -> Result<Vc<AssetContent>> {
+        let code = self.code().await?;
+
+        let rope = if code.has_source_map() {
+            let mut rope_builder = RopeBuilder::default();
+            rope_builder.concat(code.source_code());
+            let source_map_path = self.source_map().path().await?;
+            write!(
+                rope_builder,
+                "\n\n//# sourceMappingURL={}",
+                urlencoding::encode(source_map_path.file_name())
+            )?;
+            rope_builder.build()
+        } else {
+            code.source_code().clone()
+        };
+
+        Ok(AssetContent::file(File::from(rope).into()))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for EcmascriptBrowserRuntimeChunk {
+    #[turbo_tasks::function]
+    fn generate_source_map(self: Vc<Self>) -> Vc<OptionStringifiedSourceMap> {
+        self.code().generate_source_map()
+    }
+}
+
+/// The code of the Turbopack browser runtime. It's either part of the evaluate chunks or a
+/// separate runtime chunk.
+#[turbo_tasks::function]
+pub(crate) async fn browser_runtime_code(
+    chunking_context: Vc<BrowserChunkingContext>,
+    module_graph: Vc<ModuleGraph>,
+    source_maps: bool,
+) -> Result<Vc<Code>> {
+    let this = chunking_context.await?;
+    let features = match this.runtime_type() {
+        RuntimeType::Development => RuntimeFeatures::all(),
+        RuntimeType::Production => runtime_features(module_graph),
+        #[cfg(feature = "test")]
+        RuntimeType::Dummy => {
+            return Ok(turbopack_ecmascript_runtime::get_dummy_runtime_code().cell());
+        }
+    };
+    Ok(turbopack_ecmascript_runtime::get_browser_runtime_code(
+        chunking_context.environment(),
+        this.chunk_base_path(),
+        this.chunk_suffix_path(),
+        Value::new(this.runtime_type()),
+        chunking_context.output_root_to_root_path(),
+        source_maps,
+        features,
+    ))
+}
+
+/// Determines which optional runtime features are needed by the modules in the graph. This is
+/// conservative, as the runtime is shared with all chunks that are loaded later on.
+#[turbo_tasks::function]
+async fn runtime_features(module_graph: Vc<ModuleGraph>) -> Result<Vc<RuntimeFeatures>> {
+    let async_loading = module_graph
+        .chunk_group_info()
+        .await?
+        .chunk_groups
+        .iter()
+        .any(|chunk_group| !matches!(chunk_group, ChunkGroup::Entry(_)));
+
+    let async_modules = !module_graph.async_module_info().await?.is_empty();
+
+    // Only WebAssembly modules that are instantiated use the runtime, raw ones just export the path
+    let wasm = module_graph
+        .await?
+        .get_graphs()
+        .await?
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .any(|node| ResolvedVc::try_downcast_type::<WebAssemblyModuleAsset>(node.module).is_some());
+
+    Ok(RuntimeFeatures {
+        async_loading,
+        async_modules,
+        wasm,
+    }
+    .cell())
+}
@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use turbo_tasks::{trace::TraceRawVcs, NonLocalValue, TaskInput};
 use turbopack_cli_utils::issue::IssueSeverityCliOption;
 
+use crate::telemetry::TelemetrySinkOption;
+
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about = None)]
 pub enum Arguments {
@@ -136,6 +138,50 @@ pub struct BuildArguments {
     #[clap(long)]
     pub no_minify: bool,
 
+    /// Mangle property names matching the given regex (e.g. `^_`) when minifying. Only properties
+    /// that are never accessed by name and never reach external modules are mangled, using the
+    /// same names in all chunks.
+    #[clap(long, value_parser)]
+    pub mangle_properties: Option<String>,
+
+    /// Property names that are never mangled by `--mangle-properties`.
+    #[clap(long, value_parser, value_delimiter = ',')]
+    pub mangle_properties_reserved: Vec<String>,
+
+    /// Report the exports of project modules that no other module imports as warnings.
+    #[clap(long)]
+    pub report_unused_exports: bool,
+
+    /// Globs of files, relative to the project directory, that are not checked by
+    /// `--report-unused-exports` (e.g. `src/generated/**`).
+    #[clap(long, value_parser, value_delimiter = ',')]
+    pub unused_exports_allowlist: Vec<String>,
+
+    /// Merge modules that are only imported by a single module into the module factory of their
+    /// importer.
+    #[clap(long)]
+    pub scope_hoisting: bool,
+
+    /// Remove the top level keys of JSON modules that are never read by the modules importing
+    /// them.
+    #[clap(long)]
+    pub json_tree_shaking: bool,
+
+    /// Additionally emit a legacy variant of the browser output, compiled to ES5 (with
+    /// regenerator) for the given browserslist query (e.g. `ie 11`), together with an
+    /// `index.html` that loads the modern chunks as `type="module"` and the legacy chunks as
+    /// `nomodule` scripts. Only used with `--target browser`. Defaults to the `legacy`
+    /// environment of the browserslist config in the project's `package.json`.
+    #[clap(long, value_parser)]
+    pub legacy_browserslist: Option<String>,
+
+    /// Where to send anonymized build telemetry (durations, cache hit ratios and feature usage):
+    /// `disabled`, an `http://` or `https://` endpoint that receives the events as a JSON array,
+    /// or a file path that the events are appended to as JSON lines, optionally prefixed with
+    /// `file:`.
+    #[clap(long, value_parser, env = "TURBOPACK_TELEMETRY", default_value_t)]
+    pub telemetry: TelemetrySinkOption,
+
     /// Drop the `TurboTasks` object upon exit. By default we intentionally leak this memory, as
     /// we're about to exit the process anyways, but that can cause issues with valgrind or other
     /// leak detectors.
@@ -2,7 +2,8 @@ use std::{
     env::current_dir,
     mem::forget,
     path::{PathBuf, MAIN_SEPARATOR},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::Instant,
 };
 
 use anyhow::{bail, Context, Result};
@@ -11,14 +12,15 @@ use tracing::Instrument;
 use turbo_rcstr::RcStr;
 use turbo_tasks::{
     apply_effects, ReadConsistency, ResolvedVc, TransientInstance, TryJoinIterExt, TurboTasks,
-    Value, Vc,
+    TurboTasksApi, Value, Vc,
 };
 use turbo_tasks_backend::{
     noop_backing_storage, BackendOptions, NoopBackingStorage, TurboTasksBackend,
 };
-use turbo_tasks_fs::FileSystem;
+use turbo_tasks_fs::{FileJsonContent, FileSystem, FileSystemPath};
 use turbopack::{
-    css::chunk::CssChunkType, ecmascript::chunk::EcmascriptChunkType,
+    css::chunk::CssChunkType,
+    ecmascript::{chunk::EcmascriptChunkType, unused_exports::report_unused_exports},
     global_module_ids::get_global_module_id_strategy,
 };
 use turbopack_browser::{BrowserChunkingContext, ContentHashing};
@@ -26,9 +28,12 @@ use turbopack_cli_utils::issue::{ConsoleUi, LogOptions};
 use turbopack_core::{
     asset::Asset,
     chunk::{
-        availability_info::AvailabilityInfo, ChunkingConfig, ChunkingContext, EvaluatableAsset,
-        EvaluatableAssets, MangleType, MinifyType, SourceMapsType,
+        availability_info::AvailabilityInfo, module_id_strategies::ModuleIdStrategy,
+        ChunkableModule, ChunkingConfig, ChunkingContext, EvaluatableAsset, EvaluatableAssets,
+        ManglePropertiesOptions, MangleType, MinifyOptions, MinifyType, SourceMapsType,
     },
+    context::AssetContext,
+    diagnostics::{Diagnostic, DiagnosticContextExt},
     environment::{BrowserEnvironment, Environment, ExecutionEnvironment, NodeJsEnvironment},
     ident::AssetIdent,
     issue::{handle_issues, IssueReporter, IssueSeverity},
@@ -45,6 +50,7 @@ use turbopack_core::{
         parse::Request,
     },
 };
+use turbopack_dev_server::html::{DevHtmlAsset, DevHtmlEntry};
 use turbopack_ecmascript_runtime::RuntimeType;
 use turbopack_env::dotenv::load_env;
 use turbopack_node::execution_context::ExecutionContext;
@@ -53,6 +59,7 @@ use turbopack_nodejs::NodeJsChunkingContext;
 use crate::{
     arguments::{BuildArguments, Target},
     contexts::{get_client_asset_context, get_client_compile_time_info, NodeEnv},
+    telemetry::{anonymous_project_id, feature_usage_events, TelemetryEvent, TelemetrySink},
     util::{
         normalize_dirs, normalize_entries, output_fs, project_fs, EntryRequest, NormalizedDirs,
     },
@@ -77,6 +84,11 @@ pub struct TurbopackBuildBuilder {
     source_maps_type: SourceMapsType,
     minify_type: MinifyType,
     target: Target,
+    legacy_browserslist_query: Option<RcStr>,
+    unused_exports_allowlist: Option<Vec<RcStr>>,
+    scope_hoisting: bool,
+    json_tree_shaking: bool,
+    telemetry_sink: Option<Box<dyn TelemetrySink>>,
 }
 
 impl TurbopackBuildBuilder {
@@ -93,10 +105,16 @@ impl TurbopackBuildBuilder {
             show_all: false,
             log_detail: false,
             source_maps_type: SourceMapsType::Full,
-            minify_type: MinifyType::Minify {
+            minify_type: MinifyType::Minify(MinifyOptions {
                 mangle: Some(MangleType::OptimalSize),
-            },
+                mangle_properties: None,
+            }),
             target: Target::Node,
+            legacy_browserslist_query: None,
+            unused_exports_allowlist: None,
+            scope_hoisting: false,
+            json_tree_shaking: false,
+            telemetry_sink: None,
         }
     }
 
@@ -140,51 +158,152 @@ impl TurbopackBuildBuilder {
         self
     }
 
-    pub async fn build(self) -> Result<()> {
-        let task = self.turbo_tasks.spawn_once_task::<(), _>(async move {
-            let build_result_op = build_internal(
-                self.project_dir.clone(),
-                self.root_dir,
-                self.entry_requests.clone(),
-                self.browserslist_query,
-                self.source_maps_type,
-                self.minify_type,
-                self.target,
-            );
-
-            // Await the result to propagate any errors.
-            build_result_op.read_strongly_consistent().await?;
-
-            apply_effects(build_result_op)
-                .instrument(tracing::info_span!("apply effects"))
-                .await?;
+    /// Enables differential output for the browser target: in addition to the regular (modern)
+    /// output, a legacy variant compiled to ES5 for `legacy_browserslist_query` is emitted into
+    /// `dist/legacy`, and a `dist/index.html` selects between both via module/nomodule. When
+    /// `None`, the `legacy` environment of the browserslist config in the project's `package.json`
+    /// is used, if any.
+    pub fn legacy_browserslist_query(mut self, legacy_browserslist_query: Option<RcStr>) -> Self {
+        self.legacy_browserslist_query = legacy_browserslist_query;
+        self
+    }
 
-            let issue_reporter: Vc<Box<dyn IssueReporter>> =
-                Vc::upcast(ConsoleUi::new(TransientInstance::new(LogOptions {
-                    project_dir: PathBuf::from(self.project_dir),
-                    current_dir: current_dir().unwrap(),
-                    show_all: self.show_all,
-                    log_detail: self.log_detail,
-                    log_level: self.log_level,
-                })));
-
-            handle_issues(
-                build_result_op,
-                issue_reporter,
-                IssueSeverity::Error.into(),
-                None,
-                None,
-            )
-            .await?;
+    /// Reports the exports of project modules that no other module imports as issues. Modules
+    /// whose path relative to the project directory matches one of the allowlist globs are not
+    /// reported. Nothing is reported when `None`.
+    pub fn report_unused_exports(mut self, unused_exports_allowlist: Option<Vec<RcStr>>) -> Self {
+        self.unused_exports_allowlist = unused_exports_allowlist;
+        self
+    }
+
+    /// Merges modules with a single ESM importer into the module factory of their importer.
+    pub fn scope_hoisting(mut self, scope_hoisting: bool) -> Self {
+        self.scope_hoisting = scope_hoisting;
+        self
+    }
+
+    /// Removes the top level keys of JSON modules that are never read by their importers.
+    pub fn json_tree_shaking(mut self, json_tree_shaking: bool) -> Self {
+        self.json_tree_shaking = json_tree_shaking;
+        self
+    }
+
+    /// Records anonymized build events (duration, cache hit ratio and feature usage) and sends
+    /// them to the given sink once the build has finished.
+    pub fn telemetry_sink(mut self, telemetry_sink: Option<Box<dyn TelemetrySink>>) -> Self {
+        self.telemetry_sink = telemetry_sink;
+        self
+    }
+
+    pub async fn build(mut self) -> Result<()> {
+        let telemetry_sink = self.telemetry_sink.take();
+        if telemetry_sink.is_some() {
+            self.turbo_tasks.task_statistics().enable();
+        }
+        let start = Instant::now();
+        let build_event = TelemetryEvent::new("build")
+            .with("projectId", anonymous_project_id(&self.project_dir))
+            .with("target", format!("{:?}", self.target).to_lowercase())
+            .with("entries", self.entry_requests.len())
+            .with("minify", matches!(self.minify_type, MinifyType::Minify(_)))
+            .with("sourceMaps", self.source_maps_type != SourceMapsType::None)
+            .with("legacyOutput", self.legacy_browserslist_query.is_some());
+        let feature_events = Arc::new(Mutex::new(Vec::new()));
+        let collect_features = telemetry_sink.is_some();
+
+        let task = self.turbo_tasks.spawn_once_task::<(), _>({
+            let feature_events = feature_events.clone();
+            async move {
+                let build_result_op = build_internal(
+                    self.project_dir.clone(),
+                    self.root_dir,
+                    self.entry_requests.clone(),
+                    self.browserslist_query,
+                    self.source_maps_type,
+                    self.minify_type,
+                    self.target,
+                    self.legacy_browserslist_query,
+                    self.unused_exports_allowlist,
+                    self.scope_hoisting,
+                    self.json_tree_shaking,
+                );
+
+                // Await the result to propagate any errors.
+                build_result_op.read_strongly_consistent().await?;
+
+                apply_effects(build_result_op)
+                    .instrument(tracing::info_span!("apply effects"))
+                    .await?;
+
+                let issue_reporter: Vc<Box<dyn IssueReporter>> =
+                    Vc::upcast(ConsoleUi::new(TransientInstance::new(LogOptions {
+                        project_dir: PathBuf::from(self.project_dir),
+                        current_dir: current_dir().unwrap(),
+                        show_all: self.show_all,
+                        log_detail: self.log_detail,
+                        log_level: self.log_level,
+                    })));
+
+                if collect_features {
+                    let diagnostics = build_result_op.peek_diagnostics().await?;
+                    let mut events = Vec::new();
+                    for diagnostic in &diagnostics.diagnostics {
+                        let diagnostic = diagnostic.into_plain().await?;
+                        events.extend(feature_usage_events(&diagnostic.name, &diagnostic.payload));
+                    }
+                    *feature_events.lock().unwrap() = events;
+                }
+
+                handle_issues(
+                    build_result_op,
+                    issue_reporter,
+                    IssueSeverity::Error.into(),
+                    None,
+                    None,
+                )
+                .await?;
 
-            Ok(Default::default())
+                Ok(Default::default())
+            }
         });
 
-        self.turbo_tasks
+        let result = self
+            .turbo_tasks
             .wait_task_completion(task, ReadConsistency::Strong)
-            .await?;
+            .await;
+
+        if let Some(telemetry_sink) = telemetry_sink {
+            let mut events = vec![build_event
+                .with("durationMs", start.elapsed().as_millis() as u64)
+                .with("success", result.is_ok())];
+            if let Some((hits, misses)) = self
+                .turbo_tasks
+                .task_statistics()
+                .map(|stats| stats.total_cache_hits_and_misses())
+            {
+                let total = hits + misses;
+                events.push(
+                    TelemetryEvent::new("cache")
+                        .with("hits", hits)
+                        .with("misses", misses)
+                        .with(
+                            "hitRatio",
+                            if total == 0 {
+                                0.0
+                            } else {
+                                hits as f64 / total as f64
+                            },
+                        ),
+                );
+            }
+            events.append(&mut feature_events.lock().unwrap());
+            // Telemetry must never fail a build.
+            if let Err(err) = telemetry_sink.send(&events).await {
+                tracing::warn!("Failed to send telemetry: {err:?}");
+            }
+        }
 
-        Ok(())
+        result
     }
 }
 
@@ -197,6 +316,10 @@ async fn build_internal(
     source_maps_type: SourceMapsType,
     minify_type: MinifyType,
     target: Target,
+    legacy_browserslist_query: Option<RcStr>,
+    unused_exports_allowlist: Option<Vec<RcStr>>,
+    scope_hoisting: bool,
+    json_tree_shaking: bool,
 ) -> Result<Vc<()>> {
     let output_fs = output_fs(project_dir.clone());
     let project_fs = project_fs(root_dir.clone());
@@ -252,6 +375,7 @@ async fn build_internal(
         compile_time_info,
         node_env,
         source_maps_type,
+        false,
     );
 
     let entry_requests = (*entry_requests
@@ -276,12 +400,13 @@ async fn build_internal(
         .await?)
         .to_vec();
 
-    let origin = PlainResolveOrigin::new(asset_context, project_fs.root().join("_".into()));
     let project_dir = &project_dir;
-    let entries = async move {
+    let entry_requests = &entry_requests;
+    let resolve_entries = async |asset_context: Vc<Box<dyn AssetContext>>| {
+        let origin = PlainResolveOrigin::new(asset_context, project_fs.root().join("_".into()));
         entry_requests
-            .into_iter()
-            .map(|request_vc| async move {
+            .iter()
+            .map(|&request_vc| async move {
                 let ty = Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined));
                 let request = request_vc.await?;
                 origin
@@ -299,33 +424,42 @@ async fn build_internal(
             })
             .try_join()
             .await
-    }
-    .instrument(tracing::info_span!("resolve entries"))
-    .await?;
+    };
+    let entries = resolve_entries(asset_context)
+        .instrument(tracing::info_span!("resolve entries"))
+        .await?;
 
     let module_graph =
         ModuleGraph::from_modules(Vc::cell(vec![ChunkGroupEntry::Entry(entries.clone())]));
+    if let Some(unused_exports_allowlist) = unused_exports_allowlist {
+        report_unused_exports(module_graph, *project_path, unused_exports_allowlist).await?;
+    }
     let module_id_strategy = ResolvedVc::upcast(
         get_global_module_id_strategy(module_graph)
             .to_resolved()
             .await?,
     );
 
-    let chunking_context: Vc<Box<dyn ChunkingContext>> = match target {
-        Target::Browser => {
+    let browser_chunking_context =
+        async |output_root: ResolvedVc<FileSystemPath>,
+               output_root_to_root_path: RcStr,
+               browserslist_query: RcStr,
+               module_id_strategy: ResolvedVc<Box<dyn ModuleIdStrategy>>,
+               legacy_output: bool|
+               -> Result<Vc<Box<dyn ChunkingContext>>> {
             let mut builder = BrowserChunkingContext::builder(
                 project_path,
-                build_output_root,
-                ResolvedVc::cell(build_output_root_to_root_path),
-                build_output_root,
-                build_output_root,
-                build_output_root,
+                output_root,
+                ResolvedVc::cell(output_root_to_root_path),
+                output_root,
+                output_root,
+                output_root,
                 Environment::new(Value::new(ExecutionEnvironment::Browser(
                     BrowserEnvironment {
                         dom: true,
                         web_worker: false,
                         service_worker: false,
-                        browserslist_query: browserslist_query.clone(),
+                        browserslist_query,
                     }
                     .resolved_cell(),
                 )))
@@ -335,7 +469,8 @@ async fn build_internal(
             )
             .source_maps(source_maps_type)
             .module_id_strategy(module_id_strategy)
-            .minify_type(minify_type);
+            .minify_type(minify_type.clone())
+            .legacy_output(legacy_output);
 
             match *node_env.await? {
                 NodeEnv::Development => {}
@@ -356,11 +491,26 @@ async fn build_internal(
                             ..Default::default()
                         },
                     );
-                    builder = builder.use_content_hashing(ContentHashing::Direct { length: 16 })
+                    builder = builder
+                        .use_content_hashing(ContentHashing::Direct { length: 16 })
+                        .scope_hoisting(scope_hoisting)
+                        .json_tree_shaking(json_tree_shaking)
                 }
             }
 
-            Vc::upcast(builder.build())
+            Ok(Vc::upcast(builder.build()))
+        };
+
+    let chunking_context: Vc<Box<dyn ChunkingContext>> = match target {
+        Target::Browser => {
+            browser_chunking_context(
+                build_output_root,
+                build_output_root_to_root_path,
+                browserslist_query.clone(),
+                module_id_strategy,
+                false,
+            )
+            .await?
         }
         Target::Node => {
             let mut builder = NodeJsChunkingContext::builder(
@@ -379,7 +529,7 @@ async fn build_internal(
             )
             .source_maps(source_maps_type)
             .module_id_strategy(module_id_strategy)
-            .minify_type(minify_type);
+            .minify_type(minify_type.clone());
 
             match *node_env.await? {
                 NodeEnv::Development => {}
@@ -400,6 +550,9 @@ async fn build_internal(
                             ..Default::default()
                         },
                     );
+                    builder = builder
+                        .scope_hoisting(scope_hoisting)
+                        .json_tree_shaking(json_tree_shaking);
                 }
             }
 
@@ -407,18 +560,139 @@ async fn build_internal(
         }
     };
 
-    let entry_chunk_groups = entries
-        .into_iter()
-        .map(|entry_module| async move {
-            Ok(
-                if let Some(ecmascript) =
-                    ResolvedVc::try_sidecast::<Box<dyn EvaluatableAsset>>(entry_module)
-                {
-                    match target {
-                        Target::Browser => {
-                            chunking_context
-                                .evaluated_chunk_group(
-                                    AssetIdent::from_path(
+    let legacy_browserslist_query = match target {
+        Target::Browser => match legacy_browserslist_query {
+            Some(query) => Some(query),
+            None => {
+                project_legacy_browserslist_query(*project_path)
+                    .owned()
+                    .await?
+            }
+        },
+        Target::Node => None,
+    };
+    let entry_chunk_groups = if let Some(legacy_browserslist_query) = legacy_browserslist_query {
+        // Differential output: the same entries are compiled a second time to ES5 for the legacy
+        // browserslist query, and an HTML page loads either the modern or the legacy chunks.
+        let legacy_compile_time_info =
+            get_client_compile_time_info(legacy_browserslist_query.clone(), node_env);
+        let legacy_asset_context = get_client_asset_context(
+            *project_path,
+            execution_context,
+            legacy_compile_time_info,
+            node_env,
+            source_maps_type,
+            true,
+        );
+        let legacy_entries = resolve_entries(legacy_asset_context)
+            .instrument(tracing::info_span!("resolve legacy entries"))
+            .await?;
+
+        let legacy_module_graph =
+            ModuleGraph::from_modules(Vc::cell(vec![ChunkGroupEntry::Entry(
+                legacy_entries.clone(),
+            )]));
+        let legacy_module_id_strategy = ResolvedVc::upcast(
+            get_global_module_id_strategy(legacy_module_graph)
+                .to_resolved()
+                .await?,
+        );
+        let legacy_output_root = build_output_root
+            .join("legacy".into())
+            .to_resolved()
+            .await?;
+        let legacy_output_root_to_root_path = project_path
+            .join("dist/legacy".into())
+            .await?
+            .get_relative_path_to(&*root_path.await?)
+            .context("Project path is in root path")?;
+        let legacy_chunking_context = browser_chunking_context(
+            legacy_output_root,
+            legacy_output_root_to_root_path,
+            legacy_browserslist_query,
+            legacy_module_id_strategy,
+            true,
+        )
+        .await?;
+
+        let html_entries = async |entries: Vec<ResolvedVc<Box<dyn Module>>>,
+                                  module_graph: Vc<ModuleGraph>,
+                                  chunking_context: Vc<Box<dyn ChunkingContext>>|
+               -> Result<Vec<DevHtmlEntry>> {
+            let module_graph = module_graph.to_resolved().await?;
+            let chunking_context = chunking_context.to_resolved().await?;
+            let runtime_entries = EvaluatableAssets::empty().to_resolved().await?;
+            entries
+                .into_iter()
+                .map(|entry_module| {
+                    let chunkable_module =
+                        ResolvedVc::try_sidecast::<Box<dyn ChunkableModule>>(entry_module)
+                            .context(
+                                "Entry module is not chunkable, so it can't be used to bootstrap \
+                                 the application",
+                            )?;
+                    Ok(DevHtmlEntry {
+                        chunkable_module,
+                        module_graph,
+                        chunking_context,
+                        runtime_entries: Some(runtime_entries),
+                    })
+                })
+                .collect()
+        };
+
+        let html = DevHtmlAsset::new(
+            build_output_root
+                .join("index.html".into())
+                .to_resolved()
+                .await?,
+            html_entries(entries, module_graph, chunking_context).await?,
+        )
+        .with_legacy_entries(
+            html_entries(legacy_entries, legacy_module_graph, legacy_chunking_context).await?,
+        )
+        .to_resolved()
+        .await?;
+
+        vec![ResolvedVc::cell(vec![ResolvedVc::upcast(html)])]
+    } else {
+        entries
+            .into_iter()
+            .map(|entry_module| async move {
+                Ok(
+                    if let Some(ecmascript) =
+                        ResolvedVc::try_sidecast::<Box<dyn EvaluatableAsset>>(entry_module)
+                    {
+                        match target {
+                            Target::Browser => {
+                                chunking_context
+                                    .evaluated_chunk_group(
+                                        AssetIdent::from_path(
+                                            build_output_root
+                                                .join(
+                                                    ecmascript
+                                                        .ident()
+                                                        .path()
+                                                        .file_stem()
+                                                        .await?
+                                                        .as_deref()
+                                                        .unwrap()
+                                                        .into(),
+                                                )
+                                                .with_extension("entry.js".into()),
+                                        ),
+                                        ChunkGroup::Entry(
+                                            [ResolvedVc::upcast(ecmascript)].into_iter().collect(),
+                                        ),
+                                        module_graph,
+                                        Value::new(AvailabilityInfo::Root),
+                                    )
+                                    .await?
+                                    .assets
+                            }
+                            Target::Node => ResolvedVc::cell(vec![
+                                chunking_context
+                                    .entry_chunk_group(
                                         build_output_root
                                             .join(
                                                 ecmascript
@@ -431,50 +705,26 @@ async fn build_internal(
                                                     .into(),
                                             )
                                             .with_extension("entry.js".into()),
-                                    ),
-                                    ChunkGroup::Entry(
-                                        [ResolvedVc::upcast(ecmascript)].into_iter().collect(),
-                                    ),
-                                    module_graph,
-                                    Value::new(AvailabilityInfo::Root),
-                                )
-                                .await?
-                                .assets
+                                        EvaluatableAssets::one(*ResolvedVc::upcast(ecmascript)),
+                                        module_graph,
+                                        OutputAssets::empty(),
+                                        Value::new(AvailabilityInfo::Root),
+                                    )
+                                    .await?
+                                    .asset,
+                            ]),
                         }
-                        Target::Node => ResolvedVc::cell(vec![
-                            chunking_context
-                                .entry_chunk_group(
-                                    build_output_root
-                                        .join(
-                                            ecmascript
-                                                .ident()
-                                                .path()
-                                                .file_stem()
-                                                .await?
-                                                .as_deref()
-                                                .unwrap()
-                                                .into(),
-                                        )
-                                        .with_extension("entry.js".into()),
-                                    EvaluatableAssets::one(*ResolvedVc::upcast(ecmascript)),
-                                    module_graph,
-                                    OutputAssets::empty(),
-                                    Value::new(AvailabilityInfo::Root),
-                                )
-                                .await?
-                                .asset,
-                        ]),
-                    }
-                } else {
-                    bail!(
-                        "Entry module is not chunkable, so it can't be used to bootstrap the \
-                         application"
-                    )
-                },
-            )
-        })
-        .try_join()
-        .await?;
+                    } else {
+                        bail!(
+                            "Entry module is not chunkable, so it can't be used to bootstrap the \
+                             application"
+                        )
+                    },
+                )
+            })
+            .try_join()
+            .await?
+    };
 
     let mut chunks: FxHashSet<ResolvedVc<Box<dyn OutputAsset>>> = FxHashSet::default();
     for chunk_group in entry_chunk_groups {
@@ -494,6 +744,36 @@ async fn build_internal(
     Ok(Default::default())
 }
 
+/// Reads the `legacy` environment of the browserslist config in the project's `package.json`, e.g.
+/// `"browserslist": { "production": [...], "legacy": ["ie 11"] }`. When it's set, the browser
+/// output also contains a legacy variant, like with `--legacy-browserslist`.
+#[turbo_tasks::function]
+async fn project_legacy_browserslist_query(
+    project_path: Vc<FileSystemPath>,
+) -> Result<Vc<Option<RcStr>>> {
+    let FileJsonContent::Content(package_json) =
+        &*project_path.join("package.json".into()).read_json().await?
+    else {
+        return Ok(Vc::cell(None));
+    };
+    let query = match package_json
+        .get("browserslist")
+        .and_then(|browserslist| browserslist.get("legacy"))
+    {
+        Some(serde_json::Value::String(query)) => Some(query.as_str().into()),
+        Some(serde_json::Value::Array(queries)) => Some(
+            queries
+                .iter()
+                .filter_map(|query| query.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+                .into(),
+        ),
+        _ => None,
+    };
+    Ok(Vc::cell(query))
+}
+
 pub async fn build(args: &BuildArguments) -> Result<()> {
     let NormalizedDirs {
         project_dir,
@@ -524,11 +804,31 @@ pub async fn build(args: &BuildArguments) -> Result<()> {
         .minify_type(if args.no_minify {
             MinifyType::NoMinify
         } else {
-            MinifyType::Minify {
+            MinifyType::Minify(MinifyOptions {
                 mangle: Some(MangleType::OptimalSize),
-            }
+                mangle_properties: args.mangle_properties.as_ref().map(|regex| {
+                    ManglePropertiesOptions {
+                        reserved: args
+                            .mangle_properties_reserved
+                            .iter()
+                            .map(|name| RcStr::from(name.as_str()))
+                            .collect(),
+                        regex: regex.as_str().into(),
+                    }
+                }),
+            })
         })
         .target(args.common.target.unwrap_or(Target::Node))
+        .legacy_browserslist_query(args.legacy_browserslist.clone().map(RcStr::from))
+        .report_unused_exports(args.report_unused_exports.then(|| {
+            args.unused_exports_allowlist
+                .iter()
+                .map(|glob| RcStr::from(glob.as_str()))
+                .collect()
+        }))
+        .scope_hoisting(args.scope_hoisting)
+        .json_tree_shaking(args.json_tree_shaking)
+        .telemetry_sink(args.telemetry.clone().into_sink())
         .show_all(args.common.show_all);
 
     for entry in normalize_entries(&args.common.entries) {
@@ -114,6 +114,7 @@ pub async fn create_web_entry_source(
         compile_time_info,
         node_env,
         source_maps_type,
+        false,
     );
     let chunking_context = get_client_chunking_context(
         root_path,
@@ -0,0 +1,408 @@
+use std::{
+    fmt,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use turbo_rcstr::RcStr;
+use turbo_tasks::FxIndexMap;
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+
+/// A structured build event. Events are anonymized: they never contain file paths, file
+/// contents or environment values, only durations, counters and the names of used features.
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryEvent {
+    pub name: RcStr,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub payload: FxIndexMap<RcStr, JsonValue>,
+}
+
+impl TelemetryEvent {
+    pub fn new(name: impl Into<RcStr>) -> Self {
+        TelemetryEvent {
+            name: name.into(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64),
+            payload: FxIndexMap::default(),
+        }
+    }
+
+    pub fn with(mut self, key: impl Into<RcStr>, value: impl Into<JsonValue>) -> Self {
+        self.payload.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Returns an identifier for a project that is stable across builds but doesn't reveal its
+/// location. The path is hashed together with a random per-machine salt, so the id can't be
+/// derived from a known project path.
+pub fn anonymous_project_id(project_dir: &str) -> String {
+    let salt_path = config_dir().map(|dir| dir.join("turbopack").join("telemetry-salt"));
+    salted_project_id(&machine_salt(salt_path.as_deref()), project_dir)
+}
+
+fn salted_project_id(salt: &str, project_dir: &str) -> String {
+    encode_hex(hash_xxh3_hash64(format!("{salt}\0{project_dir}").as_str()))
+}
+
+/// Returns a random value that is generated once per machine and stored at `path` in the user's
+/// config directory. When it can't be stored, a new value is used for every build.
+fn machine_salt(path: Option<&Path>) -> String {
+    if let Some(salt) = path
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .filter(|salt| !salt.trim().is_empty())
+    {
+        return salt.trim().to_string();
+    }
+    let salt = format!("{:032x}", rand::random::<u128>());
+    if let Some(path) = path {
+        // Telemetry must never fail a build, so errors are ignored here.
+        let _ = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, &salt));
+    }
+    salt
+}
+
+fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        return std::env::var_os("APPDATA").map(PathBuf::from);
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+}
+
+/// Converts the payload of a diagnostic into `featureUsage` events. Diagnostics can carry
+/// arbitrary payloads, so only feature usage is forwarded: one event per payload entry with the
+/// name of the feature and whether it's enabled or how often it's used. Entries with a name that
+/// could be a path or free text, or with another value, are dropped.
+pub fn feature_usage_events(
+    diagnostic: &str,
+    payload: &FxIndexMap<RcStr, RcStr>,
+) -> Vec<TelemetryEvent> {
+    if !is_feature_name(diagnostic) {
+        return Vec::new();
+    }
+    payload
+        .iter()
+        .filter(|(feature, _)| is_feature_name(feature))
+        .filter_map(|(feature, value)| {
+            let event = TelemetryEvent::new("featureUsage")
+                .with("name", diagnostic)
+                .with("feature", feature.as_str());
+            Some(match value.as_str() {
+                "true" => event.with("enabled", true),
+                "false" => event.with("enabled", false),
+                count => event.with("invocationCount", count.parse::<u64>().ok()?),
+            })
+        })
+        .collect()
+}
+
+/// Whether a name looks like the name of a feature or a package, e.g. `swcPlugins` or
+/// `@next/font`.
+fn is_feature_name(name: &str) -> bool {
+    const MAX_LEN: usize = 64;
+    !name.is_empty()
+        && name.len() <= MAX_LEN
+        && !name.starts_with(['.', '/'])
+        && name.matches('/').count() <= 1
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '/'))
+}
+
+/// Receives the telemetry events of a build. Implement this to forward events to a custom
+/// observability stack.
+pub trait TelemetrySink: Send + Sync {
+    /// Called once at the end of a build with all events recorded during the build.
+    fn send<'a>(&'a self, events: &'a [TelemetryEvent]) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Appends events to a file, one JSON object per line.
+pub struct FileTelemetrySink {
+    path: PathBuf,
+}
+
+impl FileTelemetrySink {
+    pub fn new(path: PathBuf) -> Self {
+        FileTelemetrySink { path }
+    }
+}
+
+impl TelemetrySink for FileTelemetrySink {
+    fn send<'a>(&'a self, events: &'a [TelemetryEvent]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut lines = Vec::new();
+            for event in events {
+                serde_json::to_writer(&mut lines, event)?;
+                lines.push(b'\n');
+            }
+            let path = self.path.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .with_context(|| format!("failed to open telemetry file {path:?}"))?;
+                file.write_all(&lines)
+                    .with_context(|| format!("failed to write telemetry file {path:?}"))
+            })
+            .await?
+        })
+    }
+}
+
+/// Posts events as a JSON array to an HTTP endpoint.
+pub struct HttpTelemetrySink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl HttpTelemetrySink {
+    pub fn new(url: String) -> Self {
+        HttpTelemetrySink {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl TelemetrySink for HttpTelemetrySink {
+    fn send<'a>(&'a self, events: &'a [TelemetryEvent]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(serde_json::to_vec(events)?)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("failed to send telemetry to {}", self.url))?;
+            Ok(())
+        })
+    }
+}
+
+/// Where to send telemetry events, as configured on the command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TelemetrySinkOption {
+    #[default]
+    Disabled,
+    File(PathBuf),
+    Http(String),
+}
+
+impl TelemetrySinkOption {
+    pub fn into_sink(self) -> Option<Box<dyn TelemetrySink>> {
+        match self {
+            TelemetrySinkOption::Disabled => None,
+            TelemetrySinkOption::File(path) => Some(Box::new(FileTelemetrySink::new(path))),
+            TelemetrySinkOption::Http(url) => Some(Box::new(HttpTelemetrySink::new(url))),
+        }
+    }
+}
+
+impl FromStr for TelemetrySinkOption {
+    type Err = String;
+
+    /// Parses `disabled`, an `http://` or `https://` URL, a `file:` prefixed path, or otherwise a
+    /// file path. Values that enable telemetry without a destination, like `1` or `true`, are
+    /// rejected instead of being used as file names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("file:") {
+            if path.is_empty() {
+                return Err("`file:` requires a file path".to_string());
+            }
+            return Ok(TelemetrySinkOption::File(PathBuf::from(path)));
+        }
+        Ok(match s {
+            "" | "disabled" | "off" | "0" | "false" | "no" => TelemetrySinkOption::Disabled,
+            "1" | "true" | "on" | "yes" | "enabled" => {
+                return Err(format!(
+                    "`{s}` doesn't specify where to send telemetry to, use an http:// or https:// \
+                     endpoint or a file path"
+                ))
+            }
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                TelemetrySinkOption::Http(url.to_string())
+            }
+            path => TelemetrySinkOption::File(PathBuf::from(path)),
+        })
+    }
+}
+
+impl fmt::Display for TelemetrySinkOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TelemetrySinkOption::Disabled => write!(f, "disabled"),
+            TelemetrySinkOption::File(path) => write!(f, "file:{}", path.display()),
+            TelemetrySinkOption::Http(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_sink_option() {
+        for disabled in ["", "disabled", "off", "0", "false", "no"] {
+            assert_eq!(
+                disabled.parse::<TelemetrySinkOption>(),
+                Ok(TelemetrySinkOption::Disabled)
+            );
+        }
+        for enabled in ["1", "true", "on", "yes", "enabled"] {
+            assert!(enabled.parse::<TelemetrySinkOption>().is_err());
+        }
+        assert_eq!(
+            "https://telemetry.example.com/events".parse::<TelemetrySinkOption>(),
+            Ok(TelemetrySinkOption::Http(
+                "https://telemetry.example.com/events".to_string()
+            ))
+        );
+        assert_eq!(
+            "http://localhost:8080".parse::<TelemetrySinkOption>(),
+            Ok(TelemetrySinkOption::Http(
+                "http://localhost:8080".to_string()
+            ))
+        );
+        assert_eq!(
+            "telemetry.jsonl".parse::<TelemetrySinkOption>(),
+            Ok(TelemetrySinkOption::File(PathBuf::from("telemetry.jsonl")))
+        );
+        // The prefix allows file names that would be parsed differently otherwise
+        assert_eq!(
+            "file:true".parse::<TelemetrySinkOption>(),
+            Ok(TelemetrySinkOption::File(PathBuf::from("true")))
+        );
+        assert_eq!(
+            "file:http://example.com".parse::<TelemetrySinkOption>(),
+            Ok(TelemetrySinkOption::File(PathBuf::from(
+                "http://example.com"
+            )))
+        );
+        assert!("file:".parse::<TelemetrySinkOption>().is_err());
+    }
+
+    #[test]
+    fn display_sink_option_roundtrip() {
+        for option in [
+            TelemetrySinkOption::Disabled,
+            TelemetrySinkOption::File(PathBuf::from("1")),
+            TelemetrySinkOption::File(PathBuf::from("out/telemetry.jsonl")),
+            TelemetrySinkOption::Http("https://telemetry.example.com".to_string()),
+        ] {
+            assert_eq!(
+                option.to_string().parse::<TelemetrySinkOption>(),
+                Ok(option)
+            );
+        }
+    }
+
+    #[test]
+    fn salted_project_id_hides_path() {
+        let id = salted_project_id("salt", "/home/user/project");
+        assert_eq!(id, salted_project_id("salt", "/home/user/project"));
+        assert_ne!(id, salted_project_id("other salt", "/home/user/project"));
+        assert_ne!(id, salted_project_id("salt", "/home/user/other-project"));
+        assert!(!id.contains("project"));
+    }
+
+    #[test]
+    fn machine_salt_is_stored() {
+        let dir = std::env::temp_dir().join(format!(
+            "turbopack-telemetry-salt-{:x}",
+            rand::random::<u64>()
+        ));
+        let path = dir.join("turbopack").join("telemetry-salt");
+        let salt = machine_salt(Some(&path));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), salt);
+        assert_eq!(machine_salt(Some(&path)), salt);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Without a place to store it, every build uses a new salt
+        assert_ne!(machine_salt(None), machine_salt(None));
+    }
+
+    #[test]
+    fn serialize_event() {
+        let mut event = TelemetryEvent::new("build")
+            .with("entries", 2)
+            .with("minify", true)
+            .with("target", "browser");
+        event.timestamp = 1_700_000_000_000;
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"name":"build","timestamp":1700000000000,"payload":{"entries":2,"minify":true,"target":"browser"}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn file_sink_appends_json_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "turbopack-telemetry-{:x}.jsonl",
+            rand::random::<u64>()
+        ));
+        let sink = FileTelemetrySink::new(path.clone());
+        sink.send(&[TelemetryEvent::new("build")]).await.unwrap();
+        sink.send(&[TelemetryEvent::new("cache").with("hits", 1)])
+            .await
+            .unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let names = content
+            .lines()
+            .map(|line| serde_json::from_str::<JsonValue>(line).unwrap()["name"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![json!("build"), json!("cache")]);
+    }
+
+    #[test]
+    fn feature_usage_events_whitelist_fields() {
+        let payload: FxIndexMap<RcStr, RcStr> = [
+            ("swcPlugins", "true"),
+            ("@next/font", "3"),
+            ("modularizeImports", "false"),
+            ("/home/user/project/src/secret.js", "1"),
+            ("../secret", "1"),
+            ("some free text", "1"),
+            ("emotion", "/home/user/project"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.into(), value.into()))
+        .collect();
+        let payloads = feature_usage_events("EVENT_BUILD_FEATURE_USAGE", &payload)
+            .into_iter()
+            .map(|event| {
+                assert_eq!(&*event.name, "featureUsage");
+                serde_json::to_value(&event.payload).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            payloads,
+            vec![
+                json!({"name": "EVENT_BUILD_FEATURE_USAGE", "feature": "swcPlugins", "enabled": true}),
+                json!({"name": "EVENT_BUILD_FEATURE_USAGE", "feature": "@next/font", "invocationCount": 3}),
+                json!({"name": "EVENT_BUILD_FEATURE_USAGE", "feature": "modularizeImports", "enabled": false}),
+            ]
+        );
+
+        assert!(feature_usage_events("/home/user/project", &payload).is_empty());
+    }
+}
@@ -44,6 +44,8 @@ pub async fn make_chunk_group(
         ChunkLoading::Edge
     );
     let should_trace = *chunking_context.is_tracing_enabled().await?;
+    let simple_tree_shaking = *chunking_context.is_simple_tree_shaking_enabled().await?;
+    let scope_hoisting = *chunking_context.is_scope_hoisting_enabled().await?;
     let batching_config = chunking_context.batching_config();
 
     let ChunkGroupContent {
@@ -57,6 +59,8 @@ pub async fn make_chunk_group(
         availability_info,
         can_split_async,
         should_trace,
+        simple_tree_shaking,
+        scope_hoisting,
         batching_config,
     )
     .await?;
@@ -187,9 +191,21 @@ pub async fn chunk_group_content(
     availability_info: AvailabilityInfo,
     can_split_async: bool,
     should_trace: bool,
+    simple_tree_shaking: bool,
+    scope_hoisting: bool,
     batching_config: Vc<BatchingConfig>,
 ) -> Result<ChunkGroupContent> {
     let module_batches_graph = module_graph.module_batches(batching_config).await?;
+    let export_usage_info = if simple_tree_shaking {
+        Some(module_graph.export_usage_info().await?)
+    } else {
+        None
+    };
+    let merged_modules_info = if scope_hoisting {
+        Some(module_graph.merged_modules_info().await?)
+    } else {
+        None
+    };
 
     type ModuleToChunkableMap = FxHashMap<ModuleOrBatch, ChunkableModuleOrBatch>;
 
@@ -240,6 +256,26 @@ pub async fn chunk_group_content(
                 return Ok(GraphTraversalAction::Exclude);
             }
 
+            // Side effect free modules of which no export is used are omitted, together with the
+            // modules that are only referenced by them. Their importers skip importing them.
+            if let (Some(export_usage_info), ModuleOrBatch::Module(module)) =
+                (&export_usage_info, node)
+            {
+                if parent_info.is_some() && export_usage_info.is_module_unused(module) {
+                    return Ok(GraphTraversalAction::Exclude);
+                }
+            }
+
+            // Merged modules are part of the chunk item of their importer, but the modules they
+            // reference still need to be placed into chunks.
+            if let (Some(merged_modules_info), ModuleOrBatch::Module(module)) =
+                (&merged_modules_info, node)
+            {
+                if parent_info.is_some() && merged_modules_info.is_merged(module) {
+                    return Ok(GraphTraversalAction::Continue);
+                }
+            }
+
             let Some(chunkable_node) = ChunkableModuleOrBatch::from_module_or_batch(node) else {
                 return Ok(GraphTraversalAction::Exclude);
             };
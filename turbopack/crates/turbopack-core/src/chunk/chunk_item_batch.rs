@@ -129,10 +129,23 @@ impl ChunkItemBatchWithAsyncModuleInfo {
         chunking_context: Vc<Box<dyn ChunkingContext>>,
     ) -> Result<Vc<Self>> {
         let async_module_info = module_graph.async_module_info().await?;
+        let merged_modules_info = if *chunking_context.is_scope_hoisting_enabled().await? {
+            Some(module_graph.merged_modules_info().await?)
+        } else {
+            None
+        };
         let batch = batch.await?;
         let chunk_items = batch
             .modules
             .iter()
+            // Merged modules are part of the chunk item of their importer
+            .filter(|module| {
+                merged_modules_info
+                    .as_ref()
+                    .is_none_or(|merged_modules_info| {
+                        !merged_modules_info.is_merged(ResolvedVc::upcast(**module))
+                    })
+            })
             .map(|module| {
                 attach_async_info_to_chunkable_module(
                     *module,
@@ -1,16 +1,18 @@
 use std::{borrow::Cow, collections::BinaryHeap, hash::BuildHasherDefault, mem::take};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use regex::Regex;
 use rustc_hash::FxHasher;
 use smallvec::SmallVec;
 use tracing::{field::Empty, Instrument};
-use turbo_prehash::BuildHasherExt;
-use turbo_tasks::{FxIndexMap, FxIndexSet, ResolvedVc, TryJoinIterExt, Vc};
+use turbo_prehash::{BuildHasherExt, PreHashed};
+use turbo_tasks::{FxIndexMap, FxIndexSet, ResolvedVc, TryJoinIterExt, ValueToString, Vc};
 
 use crate::{
     chunk::{
         chunking::{make_chunk, ChunkItemOrBatchWithInfo, SplitContext},
-        ChunkItemBatchGroup, ChunkItemWithAsyncModuleInfo, ChunkingConfig,
+        ChunkItemBatchGroup, ChunkItemBatchWithAsyncModuleInfo, ChunkItemWithAsyncModuleInfo,
+        ChunkingConfig,
     },
     module_graph::{chunk_group_info::RoaringBitmapWrapper, ModuleGraph},
 };
@@ -33,14 +35,6 @@ pub async fn make_production_chunks(
     async move {
         let chunk_group_info = module_graph.chunk_group_info().await?;
 
-        #[derive(Default)]
-        struct GrouppedChunkItems<'l> {
-            chunk_items: Vec<&'l ChunkItemOrBatchWithInfo>,
-            batch_group: Option<ResolvedVc<ChunkItemBatchGroup>>,
-        }
-
-        let mut grouped_chunk_items = FxIndexMap::<_, GrouppedChunkItems<'_>>::default();
-
         // Helper Vec to keep ReadRefs on batches and allow references into them
         let batch_read_refs = chunk_items
             .iter()
@@ -59,6 +53,23 @@ pub async fn make_production_chunks(
 
         let batch_group_read_refs = batch_groups.iter().try_join().await?;
 
+        let cache_groups = chunking_config
+            .cache_groups
+            .iter()
+            .map(|cache_group| {
+                Regex::new(&cache_group.test).with_context(|| {
+                    format!("invalid test of the chunk cache group {}", cache_group.name)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let vendor_min_chunk_groups = chunking_config.vendor_min_chunk_groups;
+
+        // Chunk items of different partitions are never put into the same chunk, so the chunks of
+        // cache groups and vendor chunks only change when their modules do
+        let mut partition_chunk_items =
+            FxIndexMap::<_, FxIndexMap<_, GrouppedChunkItems<'_>>>::default();
+
         // Put chunk items into `grouped_chunk_items` based on their chunk groups
         for (i, chunk_item) in chunk_items.into_iter().enumerate() {
             let chunk_groups = match chunk_item {
@@ -80,8 +91,27 @@ pub async fn make_production_chunks(
                     batch_read_refs[i].as_ref().unwrap().chunk_groups.as_ref()
                 }
             };
+            let cache_group = if cache_groups.is_empty() {
+                None
+            } else {
+                find_cache_group(chunk_item, batch_read_refs[i].as_deref(), &cache_groups).await?
+            };
+            let partition = if let Some(cache_group) = cache_group {
+                ChunkPartition::CacheGroup(cache_group)
+            } else if vendor_min_chunk_groups != 0
+                && chunk_groups.is_some_and(|chunk_groups| {
+                    chunk_groups.len() >= vendor_min_chunk_groups as u64
+                })
+                && is_vendor(chunk_item, batch_read_refs[i].as_deref()).await?
+            {
+                ChunkPartition::Vendor
+            } else {
+                ChunkPartition::Default
+            };
             let key = BuildHasherDefault::<FxHasher>::default().prehash(chunk_groups);
-            grouped_chunk_items
+            partition_chunk_items
+                .entry(partition)
+                .or_default()
                 .entry(key)
                 .or_default()
                 .chunk_items
@@ -91,456 +121,617 @@ pub async fn make_production_chunks(
         for (i, batch_group) in batch_groups.into_iter().enumerate() {
             let data = &batch_group_read_refs[i].chunk_groups;
             let key = BuildHasherDefault::<FxHasher>::default().prehash(Some(data));
-            grouped_chunk_items.entry(key).or_default().batch_group = Some(batch_group);
+            let mut found = false;
+            for grouped_chunk_items in partition_chunk_items.values_mut() {
+                if let Some(group) = grouped_chunk_items.get_mut(&key) {
+                    group.batch_group = Some(batch_group);
+                    found = true;
+                }
+            }
+            if !found {
+                partition_chunk_items
+                    .entry(ChunkPartition::Default)
+                    .or_default()
+                    .entry(key)
+                    .or_default()
+                    .batch_group = Some(batch_group);
+            }
         }
 
-        let &ChunkingConfig {
-            min_chunk_size,
-            max_chunk_count_per_group,
-            max_merge_chunk_size,
-            ..
-        } = chunking_config;
-
-        if min_chunk_size == 0 && max_chunk_count_per_group == 0 {
-            span.record("chunks", grouped_chunk_items.len());
-            for group in grouped_chunk_items.into_values() {
-                make_chunk(
-                    group.chunk_items,
-                    group.batch_group.into_iter().collect(),
-                    &mut String::new(),
-                    &mut split_context,
-                )
-                .await?;
-            }
-        } else {
-            let mut heap = grouped_chunk_items
-                .into_iter()
-                .map(
-                    |(
-                        key,
-                        GrouppedChunkItems {
+        let mut stats = ChunkingStats::default();
+        for grouped_chunk_items in partition_chunk_items.into_values() {
+            make_cache_group_chunks(
+                grouped_chunk_items,
+                chunking_config,
+                &mut split_context,
+                &mut stats,
+            )
+            .await?;
+        }
+        span.record("chunks_before_limits", stats.chunks_before_limits);
+        span.record("chunks", stats.chunks);
+        span.record("total_size", stats.total_size);
+
+        Ok(())
+    }
+    .instrument(span_outer)
+    .await
+}
+
+/// Creates the chunks for the chunk items of a single partition. The chunk items are grouped by the
+/// chunk groups they are needed in, and small groups are merged according to the chunking config.
+async fn make_cache_group_chunks<'l>(
+    grouped_chunk_items: FxIndexMap<
+        PreHashed<Option<&'l RoaringBitmapWrapper>>,
+        GrouppedChunkItems<'l>,
+    >,
+    chunking_config: &ChunkingConfig,
+    split_context: &mut SplitContext<'_>,
+    stats: &mut ChunkingStats,
+) -> Result<()> {
+    let &ChunkingConfig {
+        min_chunk_size,
+        max_chunk_count_per_group,
+        max_merge_chunk_size,
+        max_chunk_size,
+        ..
+    } = chunking_config;
+
+    if min_chunk_size == 0 && max_chunk_count_per_group == 0 {
+        for group in grouped_chunk_items.into_values() {
+            let size = group
+                .chunk_items
+                .iter()
+                .map(|chunk_item| chunk_item.size())
+                .sum::<usize>();
+            stats.total_size += size;
+            stats.chunks += make_chunks_with_max_size(
+                group.chunk_items,
+                group.batch_group.into_iter().collect(),
+                size,
+                max_chunk_size,
+                split_context,
+            )
+            .await?;
+        }
+    } else {
+        let mut heap = grouped_chunk_items
+            .into_iter()
+            .map(
+                |(
+                    key,
+                    GrouppedChunkItems {
+                        chunk_items,
+                        batch_group,
+                    },
+                )| {
+                    let size = chunk_items
+                        .iter()
+                        .map(|chunk_item| chunk_item.size())
+                        .sum::<usize>();
+                    ChunkCandidate {
+                        size,
+                        chunk_items,
+                        batch_groups: batch_group.into_iter().collect(),
+                        chunk_groups: key.map(Cow::Borrowed),
+                    }
+                },
+            )
+            .collect::<BinaryHeap<_>>();
+
+        stats.chunks_before_limits += heap.len();
+
+        if min_chunk_size != 0 || max_chunk_count_per_group != 0 {
+            let mut chunks_to_merge = BinaryHeap::new();
+            let mut chunks_to_merge_size = 0;
+
+            // Determine chunk to merge
+            loop {
+                if let Some(smallest) = heap.peek() {
+                    let chunk_over_limit =
+                        max_merge_chunk_size != 0 && smallest.size > max_merge_chunk_size;
+                    if chunk_over_limit {
+                        break;
+                    }
+                    let merge_threshold = if min_chunk_size != 0 {
+                        min_chunk_size
+                    } else {
+                        smallest.size
+                    };
+                    let too_many_chunks = max_chunk_count_per_group != 0
+                        && heap.len() + chunks_to_merge_size / merge_threshold + 1
+                            > max_chunk_count_per_group;
+                    let too_small_chunk = min_chunk_size != 0 && smallest.size < min_chunk_size;
+                    if too_many_chunks || too_small_chunk {
+                        let ChunkCandidate {
+                            size,
                             chunk_items,
-                            batch_group,
-                        },
-                    )| {
-                        let size = chunk_items
-                            .iter()
-                            .map(|chunk_item| chunk_item.size())
-                            .sum::<usize>();
-                        ChunkCandidate {
+                            batch_groups,
+                            chunk_groups,
+                        } = heap.pop().unwrap();
+                        chunks_to_merge_size += size;
+                        chunks_to_merge.push(MergeCandidate {
                             size,
                             chunk_items,
-                            batch_groups: batch_group.into_iter().collect(),
-                            chunk_groups: key.map(Cow::Borrowed),
-                        }
-                    },
-                )
-                .collect::<BinaryHeap<_>>();
-
-            span.record("chunks_before_limits", heap.len());
+                            batch_groups,
+                            chunk_groups,
+                        });
+                        continue;
+                    }
+                }
+                break;
+            }
 
-            if min_chunk_size != 0 || max_chunk_count_per_group != 0 {
-                let mut chunks_to_merge = BinaryHeap::new();
-                let mut chunks_to_merge_size = 0;
+            let merge_threshold = if min_chunk_size != 0 {
+                min_chunk_size
+            } else if let Some(smallest) = heap.peek() {
+                smallest.size
+            } else if max_chunk_count_per_group != 0 {
+                chunks_to_merge_size / max_chunk_count_per_group
+            } else {
+                unreachable!();
+            };
 
-                // Determine chunk to merge
-                loop {
-                    if let Some(smallest) = heap.peek() {
-                        let chunk_over_limit =
-                            max_merge_chunk_size != 0 && smallest.size > max_merge_chunk_size;
-                        if chunk_over_limit {
+            while chunks_to_merge.len() > 1 {
+                // Find best candidate
+                let mut selection: Vec<MergeCandidate<'_>> = Vec::new();
+                let mut best_combination = None;
+                while let Some(candidate) = chunks_to_merge.pop() {
+                    // Exist early when no better overlaps are possible
+                    if let Some((_, _, best_overlap, _)) = best_combination.as_ref() {
+                        let candiate_best_possible_value = candidate.chunk_groups_len();
+                        if *best_overlap >= candiate_best_possible_value {
+                            chunks_to_merge.push(candidate);
                             break;
                         }
-                        let merge_threshold = if min_chunk_size != 0 {
-                            min_chunk_size
-                        } else {
-                            smallest.size
-                        };
-                        let too_many_chunks = max_chunk_count_per_group != 0
-                            && heap.len() + chunks_to_merge_size / merge_threshold + 1
-                                > max_chunk_count_per_group;
-                        let too_small_chunk = min_chunk_size != 0 && smallest.size < min_chunk_size;
-                        if too_many_chunks || too_small_chunk {
-                            let ChunkCandidate {
-                                size,
-                                chunk_items,
-                                batch_groups,
-                                chunk_groups,
-                            } = heap.pop().unwrap();
-                            chunks_to_merge_size += size;
-                            chunks_to_merge.push(MergeCandidate {
-                                size,
-                                chunk_items,
-                                batch_groups,
-                                chunk_groups,
-                            });
-                            continue;
-                        }
                     }
-                    break;
-                }
-
-                let merge_threshold = if min_chunk_size != 0 {
-                    min_chunk_size
-                } else if let Some(smallest) = heap.peek() {
-                    smallest.size
-                } else if max_chunk_count_per_group != 0 {
-                    chunks_to_merge_size / max_chunk_count_per_group
-                } else {
-                    unreachable!();
-                };
 
-                while chunks_to_merge.len() > 1 {
-                    // Find best candidate
-                    let mut selection: Vec<MergeCandidate<'_>> = Vec::new();
-                    let mut best_combination = None;
-                    while let Some(candidate) = chunks_to_merge.pop() {
-                        // Exist early when no better overlaps are possible
-                        if let Some((_, _, best_overlap, _)) = best_combination.as_ref() {
-                            let candiate_best_possible_value = candidate.chunk_groups_len();
-                            if *best_overlap >= candiate_best_possible_value {
-                                chunks_to_merge.push(candidate);
-                                break;
-                            }
+                    // Check all combination with the new candidate
+                    for (i, other) in selection.iter().enumerate() {
+                        let overlap = overlap(&candidate.chunk_groups, &other.chunk_groups);
+                        // It need to have at least two chunk groups in common
+                        if overlap <= 1 {
+                            continue;
                         }
+                        // If the candidate is already big enough, avoid shrinking the sharing
+                        if candidate.size > merge_threshold
+                            && overlap != candidate.chunk_groups_len()
+                        {
+                            continue;
+                        }
+                        if other.size > merge_threshold && overlap != other.chunk_groups_len() {
+                            continue;
+                        }
+                        let a_groups = candidate.chunk_groups_len() as i64;
+                        let a_size = candidate.size as i64;
+                        let b_groups = other.chunk_groups_len() as i64;
+                        let b_size = other.size as i64;
+                        let o_groups = overlap as i64;
+                        let groups = a_groups.max(b_groups);
+                        let a_rem = a_groups - o_groups;
+                        let b_rem = b_groups - o_groups;
 
-                        // Check all combination with the new candidate
-                        for (i, other) in selection.iter().enumerate() {
-                            let overlap = overlap(&candidate.chunk_groups, &other.chunk_groups);
-                            // It need to have at least two chunk groups in common
-                            if overlap <= 1 {
-                                continue;
-                            }
-                            // If the candidate is already big enough, avoid shrinking the sharing
-                            if candidate.size > merge_threshold
-                                && overlap != candidate.chunk_groups_len()
-                            {
-                                continue;
-                            }
-                            if other.size > merge_threshold && overlap != other.chunk_groups_len() {
-                                continue;
-                            }
-                            let a_groups = candidate.chunk_groups_len() as i64;
-                            let a_size = candidate.size as i64;
-                            let b_groups = other.chunk_groups_len() as i64;
-                            let b_size = other.size as i64;
-                            let o_groups = overlap as i64;
-                            let groups = a_groups.max(b_groups);
-                            let a_rem = a_groups - o_groups;
-                            let b_rem = b_groups - o_groups;
-
-                            /*
-                                UNMERGED CASE
+                        /*
+                            UNMERGED CASE
 
-                                from the total of `groups` chunk groups
-                                - `a_groups` chunk groups request a `a_size` chunk
-                                - `b_groups` chunk groups request a `b_size` chunk
-                                but there is an overlapy of `o_groups` between them, which request both chunks.
+                            from the total of `groups` chunk groups
+                            - `a_groups` chunk groups request a `a_size` chunk
+                            - `b_groups` chunk groups request a `b_size` chunk
+                            but there is an overlapy of `o_groups` between them, which request both chunks.
 
-                                MERGED CASE
+                            MERGED CASE
 
-                                from the total of `groups` chunk groups
-                                - `a_rem` chunk groups request a `a_size` chunk
-                                - `b_rem` chunk groups request a `b_size` chunk
-                                - `o_groups` chunk groups request the merged chunk of size `(a_size + b_size)`
-                            */
+                            from the total of `groups` chunk groups
+                            - `a_rem` chunk groups request a `a_size` chunk
+                            - `b_rem` chunk groups request a `b_size` chunk
+                            - `o_groups` chunk groups request the merged chunk of size `(a_size + b_size)`
+                        */
 
-                            /*
-                                For our calculations we assume that there is a probability of 2/3 that we request exactly 1 chunk group (`N = 1`)
-                                and a probability of 2/3 that we request 2 chunk groups (`N = 2`).
-                                This is a simplification, but it should be good enough for our purposes.
+                        /*
+                            For our calculations we assume that there is a probability of 2/3 that we request exactly 1 chunk group (`N = 1`)
+                            and a probability of 2/3 that we request 2 chunk groups (`N = 2`).
+                            This is a simplification, but it should be good enough for our purposes.
 
-                                We want to compute the expected request count `e_req` and the expected total requested size `e_size` for the unmerged and merged case.
+                            We want to compute the expected request count `e_req` and the expected total requested size `e_size` for the unmerged and merged case.
 
-                                To compute that we compute the two cases `N = 1` and `N = 2` and combine them
-                                e_size = 2/3 * e_size(N = 1) + 1/3 * e_size(N = 2)
-                                e_req = 2/3 * e_req(N = 1) + 1/3 * e_req(N = 2)
+                            To compute that we compute the two cases `N = 1` and `N = 2` and combine them
+                            e_size = 2/3 * e_size(N = 1) + 1/3 * e_size(N = 2)
+                            e_req = 2/3 * e_req(N = 1) + 1/3 * e_req(N = 2)
 
-                                We combine `e_size` with `e_req` using this formula:
-                                e_cost = e_req * c_req + e_size
+                            We combine `e_size` with `e_req` using this formula:
+                            e_cost = e_req * c_req + e_size
 
-                                The constant `c_req` is the cost of a single request in transferred bytes. We have to choose a good value for that since there is no real value of that.
-                                This way we can compute a cost for both cases (`e_cost_unmerged` and `e_cost_merged`).
+                            The constant `c_req` is the cost of a single request in transferred bytes. We have to choose a good value for that since there is no real value of that.
+                            This way we can compute a cost for both cases (`e_cost_unmerged` and `e_cost_merged`).
 
-                                With both costs we can compute the cost benefit `d` of merging the two chunks:
-                                d = e_cost_unmerged - e_cost_merged
+                            With both costs we can compute the cost benefit `d` of merging the two chunks:
+                            d = e_cost_unmerged - e_cost_merged
 
-                                We can also split the formula into two parts:
-                                d = d_req * c_req + d_size
-                                d_size = e_size_unmerged - e_size_merged
-                                d_req = e_req_unmerged - e_req_merged
+                            We can also split the formula into two parts:
+                            d = d_req * c_req + d_size
+                            d_size = e_size_unmerged - e_size_merged
+                            d_req = e_req_unmerged - e_req_merged
 
-                                And we can split it further for every N:
-                                d_size = 2/3 * d_size(N = 1) + 1/3 * d_size(N = 2)
-                                d_req = 2/3 * d_req(N = 1) + 1/3 * d_req(N = 2)
-                            */
+                            And we can split it further for every N:
+                            d_size = 2/3 * d_size(N = 1) + 1/3 * d_size(N = 2)
+                            d_req = 2/3 * d_req(N = 1) + 1/3 * d_req(N = 2)
+                        */
 
-                            /*
-                                To compute `e_size` and `e_req` we need to determine all cases and there probabilities.
-
-                                UNMERGED CASE (N = 1):
-
-                                case X (p = a_rem/groups): size = b_size, requests = 1
-                                case Y (p = r_rem/groups): size = a_size, requests = 1
-                                case Z (p = o_groups/groups): size = a_size + b_size, requests = 2
-
-                                MERGED CASE (N = 1):
-
-                                case X (p = a_rem/groups): size = b_size, requests = 1
-                                case Y (p = r_rem/groups): size = a_size, requests = 1
-                                case Z (p = o_groups/groups): size = a_size + b_size, requests = 1
-                            */
-
-                            /*
-                                There is no difference in the sizes at all, so that means:
-
-                                d_size(N = 1) = 0
-
-                                The only difference is in case Z in the request count. That case has `p = o_groups/groups`:
-
-                                d_req(N = 1) = o_groups / groups * (2 - 1)
-                                d_req(N = 1) = o_groups / groups
-
-                                d(N = 1) = d_req(N = 1) * c_req + d_size(N = 1)
-                                         = o_groups / groups * c_req
-                            */
-
-                            /*
-                                The N = 2 case is more complicated, since we have to consider all possible combinations of the cases X, Y and Z for the two chunk groups:
-
-                                p_x = a_rem/groups
-                                p_y = r_rem/groups
-                                p_z = o_groups/groups
-
-                                The chunk groups remaining after the first one has been picked
-                                rem_g = groups - 1
-
-                                UNMERGED CASE (N = 2):
-                                case X + X (p = (a_rem/groups) * ((a_rem - 1)/rem_g)): size = b_size, requests = 1
-                                case Y + Y (p = (b_rem/groups) * ((b_rem - 1)/rem_g)): size = a_size, requests = 1
-                                case Z + Z (p = (o_groups/groups) * (o_groups - 1)/rem_g): size = a_size + b_size, requests = 2
-                                case X + Y (p = (a_rem/groups) * (b_rem/rem_g) + (b_rem/groups) * (a_rem/rem_g)): size = a_size + b_size, requests = 2
-                                case X + Z (p = (a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)): size = a_size + b_size, requests = 2
-                                case Y + Z (p = (b_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (b_rem/rem_g)): size = a_size + b_size, requests = 2
-
-                                MERGED CASE (N = 2):
-                                case X + X (p = (a_rem/groups) * ((a_rem - 1)/rem_g)): size = b_size, requests = 1
-                                case Y + Y (p = (b_rem/groups) * ((b_rem - 1)/rem_g)): size = a_size, requests = 1
-                                case Z + Z (p = (o_groups/groups) * (o_groups - 1)/rem_g): size = (a_size + b_size), requests = 1
-                                case X + Y (p = (a_rem/groups) * (b_rem/rem_g) + (b_rem/groups) * (a_rem/rem_g)): size = a_size + b_size, requests = 2
-                                case X + Z (p = (a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)): size = b_size + (a_size + b_size), requests = 3
-                                case Y + Z (p = (b_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (b_rem/rem_g)): size = a_size + (a_size + b_size), requests = 3
-
-                                Request count is different in these cases: Z + Z (better), X + Z (worse), Y + Z (worse)
-                                Requests size is different (worse) in these cases: X + Z, Y + Z
-
-                                d_req_z_z = ((o_groups/groups) * (o_groups - 1)/rem_g) * (2 - 1)
-                                          = o_groups * (o_groups - 1) / (groups * rem_g)
-                                d_req_x_z = ((a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)) * (2 - 3)
-                                          = -2 * o_groups * a_rem / (groups * rem_g)
-                                d_req_y_z = ((b_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (b_rem/rem_g)) * (2 - 3)
-                                          = -2 * o_groups * b_rem / (groups * rem_g)
-
-                                d_req(N = 2) = o_groups * (o_groups - 1 - 2 * a_rem - 2 * b_rem) / (groups * rem_g)
-                                             = o_groups * (o_groups - 1 - 2 * (a_groups - o_groups) - 2 * (b_groups - o_groups)) / (groups * rem_g)
-                                             = o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g)
-
-                                d_size_x_z = ((a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)) * (a_size + b_size - (b_size + (a_size + b_size)))
-                                           = (2 * a_rem * o_groups / groups / rem_g)) * (-b_size)
-                                           = -2 * a_rem * b_size * o_groups / (groups * rem_g)
-                                d_size_y_z = -2 * b_rem * a_size * o_groups / (groups * rem_g)
+                        /*
+                            To compute `e_size` and `e_req` we need to determine all cases and there probabilities.
+
+                            UNMERGED CASE (N = 1):
+
+                            case X (p = a_rem/groups): size = b_size, requests = 1
+                            case Y (p = r_rem/groups): size = a_size, requests = 1
+                            case Z (p = o_groups/groups): size = a_size + b_size, requests = 2
+
+                            MERGED CASE (N = 1):
+
+                            case X (p = a_rem/groups): size = b_size, requests = 1
+                            case Y (p = r_rem/groups): size = a_size, requests = 1
+                            case Z (p = o_groups/groups): size = a_size + b_size, requests = 1
+                        */
+
+                        /*
+                            There is no difference in the sizes at all, so that means:
+
+                            d_size(N = 1) = 0
+
+                            The only difference is in case Z in the request count. That case has `p = o_groups/groups`:
+
+                            d_req(N = 1) = o_groups / groups * (2 - 1)
+                            d_req(N = 1) = o_groups / groups
+
+                            d(N = 1) = d_req(N = 1) * c_req + d_size(N = 1)
+                                     = o_groups / groups * c_req
+                        */
+
+                        /*
+                            The N = 2 case is more complicated, since we have to consider all possible combinations of the cases X, Y and Z for the two chunk groups:
+
+                            p_x = a_rem/groups
+                            p_y = r_rem/groups
+                            p_z = o_groups/groups
+
+                            The chunk groups remaining after the first one has been picked
+                            rem_g = groups - 1
+
+                            UNMERGED CASE (N = 2):
+                            case X + X (p = (a_rem/groups) * ((a_rem - 1)/rem_g)): size = b_size, requests = 1
+                            case Y + Y (p = (b_rem/groups) * ((b_rem - 1)/rem_g)): size = a_size, requests = 1
+                            case Z + Z (p = (o_groups/groups) * (o_groups - 1)/rem_g): size = a_size + b_size, requests = 2
+                            case X + Y (p = (a_rem/groups) * (b_rem/rem_g) + (b_rem/groups) * (a_rem/rem_g)): size = a_size + b_size, requests = 2
+                            case X + Z (p = (a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)): size = a_size + b_size, requests = 2
+                            case Y + Z (p = (b_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (b_rem/rem_g)): size = a_size + b_size, requests = 2
+
+                            MERGED CASE (N = 2):
+                            case X + X (p = (a_rem/groups) * ((a_rem - 1)/rem_g)): size = b_size, requests = 1
+                            case Y + Y (p = (b_rem/groups) * ((b_rem - 1)/rem_g)): size = a_size, requests = 1
+                            case Z + Z (p = (o_groups/groups) * (o_groups - 1)/rem_g): size = (a_size + b_size), requests = 1
+                            case X + Y (p = (a_rem/groups) * (b_rem/rem_g) + (b_rem/groups) * (a_rem/rem_g)): size = a_size + b_size, requests = 2
+                            case X + Z (p = (a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)): size = b_size + (a_size + b_size), requests = 3
+                            case Y + Z (p = (b_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (b_rem/rem_g)): size = a_size + (a_size + b_size), requests = 3
+
+                            Request count is different in these cases: Z + Z (better), X + Z (worse), Y + Z (worse)
+                            Requests size is different (worse) in these cases: X + Z, Y + Z
+
+                            d_req_z_z = ((o_groups/groups) * (o_groups - 1)/rem_g) * (2 - 1)
+                                      = o_groups * (o_groups - 1) / (groups * rem_g)
+                            d_req_x_z = ((a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)) * (2 - 3)
+                                      = -2 * o_groups * a_rem / (groups * rem_g)
+                            d_req_y_z = ((b_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (b_rem/rem_g)) * (2 - 3)
+                                      = -2 * o_groups * b_rem / (groups * rem_g)
+
+                            d_req(N = 2) = o_groups * (o_groups - 1 - 2 * a_rem - 2 * b_rem) / (groups * rem_g)
+                                         = o_groups * (o_groups - 1 - 2 * (a_groups - o_groups) - 2 * (b_groups - o_groups)) / (groups * rem_g)
+                                         = o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g)
 
-                                d_size(N = 2) = -2 * (a_rem * b_size + b_rem * a_size) * o_groups / (groups * rem_g)
-
-
-                                d(N = 2) = d_req(N = 2) * c_req + d_size(N = 2)
-                                         = o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g) * c_req + 2 * (a_rem * b_size + b_rem * a_size) * o_groups) / (groups * rem_g)
-                                         = ((o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) * c_req - 2 * (a_rem * b_size + b_rem * a_size) * o_groups)) / (groups * rem_g)
-                            */
-
-                            /*
-                                d  = 2/3 * d(N = 1) + 1/3 * d(N = 2)
-                                3d = 2 * o_groups / groups * c_req + (o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1)) * c_req - 2 * (a_rem * b_size + b_rem * a_size) * o_groups) / (groups * rem_g)
-                                   = c_req * (2 * o_groups / groups + o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g)) - 2 * (a_rem * b_size + b_rem * a_size) * o_groups / (groups * rem_g)
-                                   = c_req * (o_groups / groups) * (2 + (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / rem_g) - 2 * (a_rem * b_size + b_rem * a_size) * o_groups / (groups * rem_g)
-
-                                We pull out some factors:
-                                3d = (c_req * (2 * rem_g + (5 * o_groups - 2 * a_groups - 2 * b_groups - 1)) - 2 * (a_rem * b_size + b_rem * a_size)) * o_groups / (rem_g * groups)
-                            */
-
-                            /*
-                               Note that d_size < 0. So we can make a quick check if d_req is positive.
-
-                               c_req * (o_groups / groups + o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g)) > 0
-                               o_groups + o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / rem_g > 0
-                               o_groups + o_groups * 5 * o_groups / rem_g - o_groups * (2 * a_groups + 2 * b_groups + 1) / rem_g > 0
-                               o_groups * rem_g + o_groups * 5 * o_groups - o_groups * (2 * a_groups + 2 * b_groups + 1) > 0
-                               o_groups * rem_g + o_groups * 5 * o_groups > o_groups * (2 * a_groups + 2 * b_groups + 1)
-                               rem_g + 5 * o_groups > 2 * a_groups + 2 * b_groups + 1
-                               rem_g + 5 * o_groups > 2 * (a_rem + o_groups) + 2 * (b_rem + o_groups) + 1
-                               rem_g + 5 * o_groups > 2 * a_rem + 2 * b_rem + 4 * o_groups + 1
-                               rem_g + o_groups > 2 * a_rem + 2 * b_rem + 1
-                               rem_g + o_groups > 2 * (a_rem + b_rem) + 1
-                               groups - 1 + o_groups > 2 * (a_rem + b_rem) + 1
-                               groups + o_groups > 2 * (a_rem + b_rem) + 2
-                            */
-
-                            // It need to have some request count benefit
-                            if groups + o_groups <= 2 * (a_rem + b_rem) + 2 {
-                                continue;
-                            }
-                            let rem_g = groups - 1;
-                            let c_req = 200000;
-                            // d3 = 3 * d
-                            let pre_d3 = c_req
-                                * (2 * rem_g + (5 * o_groups - 2 * a_groups - 2 * b_groups - 1))
-                                - 2 * (a_rem * b_size + b_rem * a_size);
-                            // It need to have some runtime benefit of merging the chunks
-                            if pre_d3 < 0 {
-                                continue;
-                            }
-                            let d3 = pre_d3 * o_groups / (rem_g * groups);
-                            let value = d3;
+                            d_size_x_z = ((a_rem/groups) * (o_groups/rem_g) + (o_groups/groups) * (a_rem/rem_g)) * (a_size + b_size - (b_size + (a_size + b_size)))
+                                       = (2 * a_rem * o_groups / groups / rem_g)) * (-b_size)
+                                       = -2 * a_rem * b_size * o_groups / (groups * rem_g)
+                            d_size_y_z = -2 * b_rem * a_size * o_groups / (groups * rem_g)
 
-                            if let Some((best_i1, best_i2, best_overlap, best_value)) =
-                                best_combination.as_mut()
-                            {
-                                if (overlap.cmp(best_overlap)).then_with(|| value.cmp(best_value))
-                                    == std::cmp::Ordering::Greater
-                                {
-                                    *best_i1 = i;
-                                    *best_i2 = selection.len();
-                                    *best_overlap = overlap;
-                                    *best_value = value;
-                                }
-                            } else {
-                                best_combination = Some((i, selection.len(), overlap, value));
-                            }
+                            d_size(N = 2) = -2 * (a_rem * b_size + b_rem * a_size) * o_groups / (groups * rem_g)
+
+
+                            d(N = 2) = d_req(N = 2) * c_req + d_size(N = 2)
+                                     = o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g) * c_req + 2 * (a_rem * b_size + b_rem * a_size) * o_groups) / (groups * rem_g)
+                                     = ((o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) * c_req - 2 * (a_rem * b_size + b_rem * a_size) * o_groups)) / (groups * rem_g)
+                        */
+
+                        /*
+                            d  = 2/3 * d(N = 1) + 1/3 * d(N = 2)
+                            3d = 2 * o_groups / groups * c_req + (o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1)) * c_req - 2 * (a_rem * b_size + b_rem * a_size) * o_groups) / (groups * rem_g)
+                               = c_req * (2 * o_groups / groups + o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g)) - 2 * (a_rem * b_size + b_rem * a_size) * o_groups / (groups * rem_g)
+                               = c_req * (o_groups / groups) * (2 + (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / rem_g) - 2 * (a_rem * b_size + b_rem * a_size) * o_groups / (groups * rem_g)
+
+                            We pull out some factors:
+                            3d = (c_req * (2 * rem_g + (5 * o_groups - 2 * a_groups - 2 * b_groups - 1)) - 2 * (a_rem * b_size + b_rem * a_size)) * o_groups / (rem_g * groups)
+                        */
+
+                        /*
+                           Note that d_size < 0. So we can make a quick check if d_req is positive.
+
+                           c_req * (o_groups / groups + o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / (groups * rem_g)) > 0
+                           o_groups + o_groups * (5 * o_groups - 2 * a_groups - 2 * b_groups - 1) / rem_g > 0
+                           o_groups + o_groups * 5 * o_groups / rem_g - o_groups * (2 * a_groups + 2 * b_groups + 1) / rem_g > 0
+                           o_groups * rem_g + o_groups * 5 * o_groups - o_groups * (2 * a_groups + 2 * b_groups + 1) > 0
+                           o_groups * rem_g + o_groups * 5 * o_groups > o_groups * (2 * a_groups + 2 * b_groups + 1)
+                           rem_g + 5 * o_groups > 2 * a_groups + 2 * b_groups + 1
+                           rem_g + 5 * o_groups > 2 * (a_rem + o_groups) + 2 * (b_rem + o_groups) + 1
+                           rem_g + 5 * o_groups > 2 * a_rem + 2 * b_rem + 4 * o_groups + 1
+                           rem_g + o_groups > 2 * a_rem + 2 * b_rem + 1
+                           rem_g + o_groups > 2 * (a_rem + b_rem) + 1
+                           groups - 1 + o_groups > 2 * (a_rem + b_rem) + 1
+                           groups + o_groups > 2 * (a_rem + b_rem) + 2
+                        */
+
+                        // It need to have some request count benefit
+                        if groups + o_groups <= 2 * (a_rem + b_rem) + 2 {
+                            continue;
                         }
-                        selection.push(candidate);
-                    }
-
-                    let best_overlap = if let Some((best_i1, best_i2, best_overlap, _)) =
-                        best_combination.as_ref()
-                    {
-                        let other = selection.swap_remove(*best_i2);
-                        let mut candidate = selection.swap_remove(*best_i1);
-                        // Merge other into candidate
-                        let MergeCandidate {
-                            size,
-                            chunk_items,
-                            mut batch_groups,
-                            chunk_groups,
-                        } = other;
-                        candidate.size += size;
-                        candidate.chunk_items.extend(chunk_items);
-                        if batch_groups.len() + candidate.batch_groups.len() > 16 {
-                            let mut set = take(&mut candidate.batch_groups)
-                                .into_iter()
-                                .collect::<FxIndexSet<_>>();
-                            set.extend(batch_groups);
-                            candidate.batch_groups = set.into_iter().collect();
-                        } else {
-                            batch_groups.retain(|batch_group| {
-                                !candidate.batch_groups.contains(batch_group)
-                            });
-                            candidate.batch_groups.extend(batch_groups);
+                        let rem_g = groups - 1;
+                        let c_req = 200000;
+                        // d3 = 3 * d
+                        let pre_d3 = c_req
+                            * (2 * rem_g + (5 * o_groups - 2 * a_groups - 2 * b_groups - 1))
+                            - 2 * (a_rem * b_size + b_rem * a_size);
+                        // It need to have some runtime benefit of merging the chunks
+                        if pre_d3 < 0 {
+                            continue;
                         }
-                        candidate.chunk_groups =
-                            merge_chunk_groups(&candidate.chunk_groups, &chunk_groups);
+                        let d3 = pre_d3 * o_groups / (rem_g * groups);
+                        let value = d3;
 
-                        // Merged candidate is pushed back into the queue
-                        chunks_to_merge.push(candidate);
-
-                        *best_overlap
-                    } else {
-                        u64::MAX
-                    };
-                    for unused in selection {
-                        // Candiates from selection that are already big enough move into the
-                        // heap again when no more merges are expected.
-                        // Since we can only merge into big enough candates when overlap ==
-                        // chunk_groups_len we can use that as condition.
-                        if unused.size > merge_threshold && unused.chunk_groups_len() > best_overlap
+                        if let Some((best_i1, best_i2, best_overlap, best_value)) =
+                            best_combination.as_mut()
                         {
-                            heap.push(ChunkCandidate {
-                                size: unused.size,
-                                chunk_items: unused.chunk_items,
-                                batch_groups: unused.batch_groups,
-                                chunk_groups: unused.chunk_groups,
-                            });
+                            if (overlap.cmp(best_overlap)).then_with(|| value.cmp(best_value))
+                                == std::cmp::Ordering::Greater
+                            {
+                                *best_i1 = i;
+                                *best_i2 = selection.len();
+                                *best_overlap = overlap;
+                                *best_value = value;
+                            }
                         } else {
-                            chunks_to_merge.push(unused);
+                            best_combination = Some((i, selection.len(), overlap, value));
                         }
                     }
-                    if best_combination.is_none() {
-                        // No merges possible
-                        break;
-                    }
+                    selection.push(candidate);
                 }
 
-                let mut remainer_size = 0;
-                let mut remainer_chunk_items = Vec::new();
-                let mut remainer_batch_groups = FxIndexSet::default();
-                for MergeCandidate {
-                    size,
-                    chunk_items,
-                    batch_groups,
-                    chunk_groups,
-                } in chunks_to_merge.into_iter()
+                let best_overlap = if let Some((best_i1, best_i2, best_overlap, _)) =
+                    best_combination.as_ref()
                 {
-                    if size > merge_threshold {
+                    let other = selection.swap_remove(*best_i2);
+                    let mut candidate = selection.swap_remove(*best_i1);
+                    // Merge other into candidate
+                    let MergeCandidate {
+                        size,
+                        chunk_items,
+                        mut batch_groups,
+                        chunk_groups,
+                    } = other;
+                    candidate.size += size;
+                    candidate.chunk_items.extend(chunk_items);
+                    if batch_groups.len() + candidate.batch_groups.len() > 16 {
+                        let mut set = take(&mut candidate.batch_groups)
+                            .into_iter()
+                            .collect::<FxIndexSet<_>>();
+                        set.extend(batch_groups);
+                        candidate.batch_groups = set.into_iter().collect();
+                    } else {
+                        batch_groups
+                            .retain(|batch_group| !candidate.batch_groups.contains(batch_group));
+                        candidate.batch_groups.extend(batch_groups);
+                    }
+                    candidate.chunk_groups =
+                        merge_chunk_groups(&candidate.chunk_groups, &chunk_groups);
+
+                    // Merged candidate is pushed back into the queue
+                    chunks_to_merge.push(candidate);
+
+                    *best_overlap
+                } else {
+                    u64::MAX
+                };
+                for unused in selection {
+                    // Candiates from selection that are already big enough move into the
+                    // heap again when no more merges are expected.
+                    // Since we can only merge into big enough candates when overlap ==
+                    // chunk_groups_len we can use that as condition.
+                    if unused.size > merge_threshold && unused.chunk_groups_len() > best_overlap {
                         heap.push(ChunkCandidate {
-                            size,
-                            chunk_items,
-                            batch_groups,
-                            chunk_groups,
+                            size: unused.size,
+                            chunk_items: unused.chunk_items,
+                            batch_groups: unused.batch_groups,
+                            chunk_groups: unused.chunk_groups,
                         });
                     } else {
-                        remainer_size += size;
-                        remainer_chunk_items.extend(chunk_items);
-                        remainer_batch_groups.extend(batch_groups);
+                        chunks_to_merge.push(unused);
                     }
                 }
+                if best_combination.is_none() {
+                    // No merges possible
+                    break;
+                }
+            }
 
-                // Left-over chunks are merged together forming the remainer chunk, which includes
-                // all modules that are not sharable
-                if !remainer_chunk_items.is_empty() {
+            let mut remainer_size = 0;
+            let mut remainer_chunk_items = Vec::new();
+            let mut remainer_batch_groups = FxIndexSet::default();
+            for MergeCandidate {
+                size,
+                chunk_items,
+                batch_groups,
+                chunk_groups,
+            } in chunks_to_merge.into_iter()
+            {
+                if size > merge_threshold {
                     heap.push(ChunkCandidate {
-                        size: remainer_size,
-                        chunk_items: remainer_chunk_items,
-                        batch_groups: remainer_batch_groups.into_iter().collect(),
-                        chunk_groups: None,
+                        size,
+                        chunk_items,
+                        batch_groups,
+                        chunk_groups,
                     });
+                } else {
+                    remainer_size += size;
+                    remainer_chunk_items.extend(chunk_items);
+                    remainer_batch_groups.extend(batch_groups);
                 }
             }
 
-            span.record("chunks", heap.len());
+            // Left-over chunks are merged together forming the remainer chunk, which includes
+            // all modules that are not sharable
+            if !remainer_chunk_items.is_empty() {
+                heap.push(ChunkCandidate {
+                    size: remainer_size,
+                    chunk_items: remainer_chunk_items,
+                    batch_groups: remainer_batch_groups.into_iter().collect(),
+                    chunk_groups: None,
+                });
+            }
+        }
 
-            let mut total_size = 0;
-            for ChunkCandidate {
+        for ChunkCandidate {
+            chunk_items,
+            batch_groups,
+            size,
+            ..
+        } in heap.into_iter()
+        {
+            stats.total_size += size;
+            stats.chunks += make_chunks_with_max_size(
                 chunk_items,
-                batch_groups,
+                batch_groups.into_vec(),
                 size,
-                ..
-            } in heap.into_iter()
-            {
-                total_size += size;
-                make_chunk(
-                    chunk_items,
-                    batch_groups.into_vec(),
-                    &mut String::new(),
-                    &mut split_context,
-                )
-                .await?;
+                max_chunk_size,
+                split_context,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a chunk with the chunk items, or multiple chunks when they are bigger than
+/// `max_chunk_size`. The chunk items are kept in order, so the same chunk items are split the same
+/// way. Returns the number of created chunks.
+async fn make_chunks_with_max_size<'l>(
+    chunk_items: Vec<&'l ChunkItemOrBatchWithInfo>,
+    batch_groups: Vec<ResolvedVc<ChunkItemBatchGroup>>,
+    size: usize,
+    max_chunk_size: usize,
+    split_context: &mut SplitContext<'_>,
+) -> Result<usize> {
+    if max_chunk_size == 0 || size <= max_chunk_size || chunk_items.len() <= 1 {
+        make_chunk(chunk_items, batch_groups, &mut String::new(), split_context).await?;
+        return Ok(1);
+    }
+
+    let mut chunks = 0;
+    let mut current = Vec::new();
+    let mut current_size = 0;
+    for chunk_item in chunk_items {
+        let item_size = chunk_item.size();
+        if !current.is_empty() && current_size + item_size > max_chunk_size {
+            // Batch groups are only used to compute the content of the chunk items together, so
+            // each part can use them
+            make_chunk(
+                take(&mut current),
+                batch_groups.clone(),
+                &mut String::new(),
+                split_context,
+            )
+            .await?;
+            chunks += 1;
+            current_size = 0;
+        }
+        current.push(chunk_item);
+        current_size += item_size;
+    }
+    if !current.is_empty() {
+        make_chunk(current, batch_groups, &mut String::new(), split_context).await?;
+        chunks += 1;
+    }
+    Ok(chunks)
+}
+
+/// Chunk items are split into partitions that are chunked independently.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum ChunkPartition {
+    /// Chunk items of modules that match the cache group with this index.
+    CacheGroup(usize),
+    /// Chunk items of modules in `node_modules` that are needed by many chunk groups.
+    Vendor,
+    /// All other chunk items.
+    Default,
+}
+
+#[derive(Default)]
+struct GrouppedChunkItems<'l> {
+    chunk_items: Vec<&'l ChunkItemOrBatchWithInfo>,
+    batch_group: Option<ResolvedVc<ChunkItemBatchGroup>>,
+}
+
+#[derive(Default)]
+struct ChunkingStats {
+    chunks_before_limits: usize,
+    chunks: usize,
+    total_size: usize,
+}
+
+/// Returns the index of the first cache group that the chunk item belongs to. A batch belongs to
+/// a cache group when any of its chunk items does.
+async fn find_cache_group(
+    chunk_item: &ChunkItemOrBatchWithInfo,
+    batch: Option<&ChunkItemBatchWithAsyncModuleInfo>,
+    cache_groups: &[Regex],
+) -> Result<Option<usize>> {
+    let matching_cache_group =
+        |ident: &str| cache_groups.iter().position(|test| test.is_match(ident));
+    match (chunk_item, batch) {
+        (ChunkItemOrBatchWithInfo::ChunkItem { asset_ident, .. }, _) => {
+            Ok(matching_cache_group(asset_ident))
+        }
+        (ChunkItemOrBatchWithInfo::Batch { .. }, Some(batch)) => {
+            for item in batch.chunk_items.iter() {
+                let ident = item.chunk_item.asset_ident().to_string().await?;
+                if let Some(cache_group) = matching_cache_group(&ident) {
+                    return Ok(Some(cache_group));
+                }
             }
-            span.record("total_size", total_size);
+            Ok(None)
         }
+        (ChunkItemOrBatchWithInfo::Batch { .. }, None) => Ok(None),
+    }
+}
 
-        Ok(())
+/// Returns true if the chunk item is a module in `node_modules`. A batch is only a vendor batch
+/// when all of its chunk items are.
+async fn is_vendor(
+    chunk_item: &ChunkItemOrBatchWithInfo,
+    batch: Option<&ChunkItemBatchWithAsyncModuleInfo>,
+) -> Result<bool> {
+    let is_node_modules = |ident: &str| ident.contains("/node_modules/");
+    match (chunk_item, batch) {
+        (ChunkItemOrBatchWithInfo::ChunkItem { asset_ident, .. }, _) => {
+            Ok(is_node_modules(asset_ident))
+        }
+        (ChunkItemOrBatchWithInfo::Batch { .. }, Some(batch)) => {
+            for item in batch.chunk_items.iter() {
+                let ident = item.chunk_item.asset_ident().to_string().await?;
+                if !is_node_modules(&ident) {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (ChunkItemOrBatchWithInfo::Batch { .. }, None) => Ok(false),
     }
-    .instrument(span_outer)
-    .await
 }
 
 struct ChunkCandidate<'l> {
@@ -37,20 +37,70 @@ pub enum MangleType {
     Deterministic,
 }
 
+/// Options for mangling property names. The properties are renamed based on an analysis of the
+/// module graph, consistently across all chunks. Only properties that are never accessed by name
+/// and never reach code outside of the module graph are renamed. Property mangling must be opted
+/// into explicitly, and only applies when identifiers are mangled too, see [MinifyOptions::mangle].
+#[derive(
+    Debug,
+    Default,
+    TaskInput,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+    DeterministicHash,
+    NonLocalValue,
+)]
+pub struct ManglePropertiesOptions {
+    /// Property names that are never mangled.
+    pub reserved: Vec<RcStr>,
+    /// Only properties matching this regex are mangled, e.g. `^_`.
+    pub regex: RcStr,
+}
+
+#[derive(
+    Debug,
+    TaskInput,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+    DeterministicHash,
+    NonLocalValue,
+)]
+pub struct MinifyOptions {
+    /// How to mangle identifiers. Identifiers are kept as they are when `None`.
+    pub mangle: Option<MangleType>,
+    /// Opt-in mangling of property names.
+    pub mangle_properties: Option<ManglePropertiesOptions>,
+}
+
+impl Default for MinifyOptions {
+    fn default() -> Self {
+        Self {
+            mangle: Some(MangleType::OptimalSize),
+            mangle_properties: None,
+        }
+    }
+}
+
 #[turbo_tasks::value(shared)]
-#[derive(Debug, TaskInput, Clone, Copy, Hash, DeterministicHash)]
+#[derive(Debug, TaskInput, Clone, Hash, DeterministicHash)]
 pub enum MinifyType {
-    // TODO instead of adding a new property here,
-    // refactor that to Minify(MinifyOptions) to allow defaults on MinifyOptions
-    Minify { mangle: Option<MangleType> },
+    Minify(MinifyOptions),
     NoMinify,
 }
 
 impl Default for MinifyType {
     fn default() -> Self {
-        Self::Minify {
-            mangle: Some(MangleType::OptimalSize),
-        }
+        Self::Minify(Default::default())
     }
 }
 
@@ -124,10 +174,32 @@ pub struct ChunkingConfig {
     /// This makes sure that code in big chunks is not duplicated in multiple chunks.
     pub max_merge_chunk_size: usize,
 
+    /// Splits chunks bigger than this size into multiple chunks. 0 means no limit.
+    pub max_chunk_size: usize,
+
+    /// Chunk items of modules that match a cache group are put into chunks of their own, which
+    /// are not merged with chunks of other modules. This keeps e.g. chunks of rarely changing
+    /// modules cacheable for longer.
+    pub cache_groups: Vec<ChunkCacheGroup>,
+
+    /// Chunk items of modules in `node_modules` that are needed by at least this number of chunk
+    /// groups are put into vendor chunks, which are not merged with chunks of application code.
+    /// 0 disables vendor chunks.
+    pub vendor_min_chunk_groups: usize,
+
     #[allow(dead_code)]
     pub placeholder_for_future_extensions: (),
 }
 
+/// A group of modules that is chunked separately from other modules, see
+/// [ChunkingConfig::cache_groups].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
+pub struct ChunkCacheGroup {
+    pub name: RcStr,
+    /// A regular expression that is matched against the idents of the modules.
+    pub test: RcStr,
+}
+
 #[turbo_tasks::value(transparent)]
 pub struct ChunkingConfigs(FxHashMap<ResolvedVc<Box<dyn ChunkType>>, ChunkingConfig>);
 
@@ -197,6 +269,28 @@ pub trait ChunkingContext {
         Vc::cell(false)
     }
 
+    /// Whether side effect free modules of which no export is used are omitted from chunks.
+    fn is_simple_tree_shaking_enabled(self: Vc<Self>) -> Vc<bool> {
+        Vc::cell(false)
+    }
+
+    /// Whether exports that are only accessed by name from known importers are renamed to short
+    /// names.
+    fn is_export_mangling_enabled(self: Vc<Self>) -> Vc<bool> {
+        Vc::cell(false)
+    }
+
+    /// Whether modules that are only imported by a single ESM module are merged into the module
+    /// factory of their importer instead of being separate modules (scope hoisting).
+    fn is_scope_hoisting_enabled(self: Vc<Self>) -> Vc<bool> {
+        Vc::cell(false)
+    }
+
+    /// Whether JSON modules only include the top level keys that their importers read.
+    fn is_json_tree_shaking_enabled(self: Vc<Self>) -> Vc<bool> {
+        Vc::cell(false)
+    }
+
     fn minify_type(self: Vc<Self>) -> Vc<MinifyType> {
         MinifyType::NoMinify.cell()
     }
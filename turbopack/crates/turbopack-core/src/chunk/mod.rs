@@ -28,8 +28,9 @@ pub use self::{
         ChunkItemOrBatchWithAsyncModuleInfo,
     },
     chunking_context::{
-        ChunkGroupResult, ChunkGroupType, ChunkingConfig, ChunkingConfigs, ChunkingContext,
-        ChunkingContextExt, EntryChunkGroupResult, MangleType, MinifyType, SourceMapsType,
+        ChunkCacheGroup, ChunkGroupResult, ChunkGroupType, ChunkingConfig, ChunkingConfigs,
+        ChunkingContext, ChunkingContextExt, EntryChunkGroupResult, ManglePropertiesOptions,
+        MangleType, MinifyOptions, MinifyType, SourceMapsType,
     },
     data::{ChunkData, ChunkDataOption, ChunksData},
     evaluate::{EvaluatableAsset, EvaluatableAssetExt, EvaluatableAssets},
@@ -39,6 +40,8 @@ use crate::{
     ident::AssetIdent,
     module::Module,
     module_graph::{
+        async_chunk_group_options::AsyncChunkGroupOptions,
+        export_usage::ExportUsage,
         module_batch::{ChunkableModuleOrBatch, ModuleBatchGroup},
         ModuleGraph,
     },
@@ -269,6 +272,18 @@ pub trait ChunkableModuleReference: ModuleReference + ValueToString {
     fn chunking_type(self: Vc<Self>) -> Vc<ChunkingTypeOption> {
         Vc::cell(Some(ChunkingType::default()))
     }
+
+    /// Which exports of the referenced modules are used by this reference. Defaults to all
+    /// exports, which is always correct but prevents removing unused exports.
+    fn export_usage(self: Vc<Self>) -> Vc<ExportUsage> {
+        ExportUsage::all()
+    }
+
+    /// Options for the chunk group that is loaded by this reference when its chunking type is
+    /// [ChunkingType::Async].
+    fn async_chunk_group_options(self: Vc<Self>) -> Vc<AsyncChunkGroupOptions> {
+        AsyncChunkGroupOptions::none()
+    }
 }
 
 #[derive(Default)]
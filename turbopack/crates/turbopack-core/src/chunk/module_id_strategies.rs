@@ -1,5 +1,6 @@
 use anyhow::{bail, Result};
 use rustc_hash::FxHashMap;
+use turbo_rcstr::RcStr;
 use turbo_tasks::{ResolvedVc, ValueToString, Vc};
 use turbo_tasks_hash::hash_xxh3_hash64;
 
@@ -80,3 +81,80 @@ impl ModuleIdStrategy for GlobalModuleIdStrategy {
         .cell())
     }
 }
+
+/// Derives the module ids from hashes of the module idents, shortened to a fixed length. Unlike
+/// [GlobalModuleIdStrategy], the id of a module doesn't depend on the other modules, so adding or
+/// removing modules doesn't change the ids of unrelated modules, which keeps long-term caching
+/// of their chunks intact.
+#[turbo_tasks::value(shared)]
+pub struct HashedModuleIdStrategy {
+    /// The number of hex digits of the hash that are used as module id.
+    pub hash_length: u8,
+    /// The ids of the modules whose shortened hash was already used by another module. They use
+    /// longer ids instead, see [hashed_module_id].
+    pub collision_ids: FxHashMap<ResolvedVc<AssetIdent>, RcStr>,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleIdStrategy for HashedModuleIdStrategy {
+    #[turbo_tasks::function]
+    async fn get_module_id(&self, ident: ResolvedVc<AssetIdent>) -> Result<Vc<ModuleId>> {
+        if let Some(module_id) = self.collision_ids.get(&ident) {
+            return Ok(ModuleId::String(module_id.clone()).cell());
+        }
+        Ok(ModuleId::String(
+            hashed_module_id(&ident.to_string().await?, self.hash_length as usize).into(),
+        )
+        .cell())
+    }
+}
+
+/// The hash of a module ident as hex string with `length` digits, so a longer id starts with the
+/// shorter ids of the same ident. The hash has 16 digits, so lengths above that add a numeric
+/// suffix to the full hash instead, e.g. `-1` for a length of 17.
+pub fn hashed_module_id(ident: &str, length: usize) -> String {
+    let hash = format!("{:016x}", hash_xxh3_hash64(ident));
+    if length > hash.len() {
+        format!("{hash}-{}", length - hash.len())
+    } else {
+        hash[..length.max(1)].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hashed_module_id;
+
+    #[test]
+    fn hashed_module_id_length() {
+        assert_eq!(hashed_module_id("[project]/a.js", 8).len(), 8);
+        assert_eq!(hashed_module_id("[project]/a.js", 16).len(), 16);
+        // At least one digit
+        assert_eq!(hashed_module_id("[project]/a.js", 0).len(), 1);
+        assert!(hashed_module_id("[project]/a.js", 16)
+            .chars()
+            .all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn hashed_module_id_is_stable() {
+        assert_eq!(
+            hashed_module_id("[project]/a.js", 8),
+            hashed_module_id("[project]/a.js", 8)
+        );
+        assert_ne!(
+            hashed_module_id("[project]/a.js", 16),
+            hashed_module_id("[project]/b.js", 16)
+        );
+    }
+
+    #[test]
+    fn hashed_module_id_extends_shorter_ids() {
+        let full = hashed_module_id("[project]/a.js", 16);
+        for length in 1..16 {
+            assert!(full.starts_with(&hashed_module_id("[project]/a.js", length)));
+        }
+        assert_eq!(hashed_module_id("[project]/a.js", 17), format!("{full}-1"));
+        assert_eq!(hashed_module_id("[project]/a.js", 20), format!("{full}-4"));
+    }
+}
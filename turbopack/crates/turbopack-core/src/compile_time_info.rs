@@ -312,6 +312,32 @@ impl CompileTimeInfo {
     pub fn environment(&self) -> Vc<Environment> {
         *self.environment
     }
+
+    /// The compile time info for the modules of a web worker that is started from this
+    /// environment. `window` and `document` are not available in web workers, so checks for them
+    /// are folded.
+    #[turbo_tasks::function]
+    pub async fn for_web_worker(self: Vc<Self>) -> Result<Vc<Self>> {
+        let this = self.await?;
+        let environment = this.environment.web_worker().to_resolved().await?;
+        if environment == this.environment {
+            return Ok(self);
+        }
+        let mut free_var_references = this.free_var_references.owned().await?;
+        free_var_references.extend(
+            free_var_references!(
+                typeof window = "undefined",
+                typeof document = "undefined",
+            )
+            .0,
+        );
+        Ok(CompileTimeInfo {
+            environment,
+            defines: this.defines,
+            free_var_references: ResolvedVc::cell(free_var_references),
+        }
+        .cell())
+    }
 }
 
 pub struct CompileTimeInfoBuilder {
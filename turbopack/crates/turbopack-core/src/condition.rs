@@ -2,7 +2,7 @@ use anyhow::Result;
 use futures::{stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{trace::TraceRawVcs, NonLocalValue, ResolvedVc};
-use turbo_tasks_fs::FileSystemPath;
+use turbo_tasks_fs::{glob::Glob, FileSystemPath};
 
 #[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq, NonLocalValue)]
 pub enum ContextCondition {
@@ -11,6 +11,13 @@ pub enum ContextCondition {
     Not(Box<ContextCondition>),
     InDirectory(String),
     InPath(ResolvedVc<FileSystemPath>),
+    /// Matches if the last segment of the path matches the glob.
+    NameGlob(ResolvedVc<Glob>),
+    /// Matches if the path relative to `base` matches the glob.
+    PathGlob {
+        base: ResolvedVc<FileSystemPath>,
+        glob: ResolvedVc<Glob>,
+    },
 }
 
 impl ContextCondition {
@@ -59,6 +66,19 @@ impl ContextCondition {
                 || path.path.contains(&format!("/{dir}/"))
                 || path.path.ends_with(&format!("/{dir}"))
                 || path.path == *dir),
+            ContextCondition::NameGlob(glob) => {
+                let name = path
+                    .path
+                    .rsplit_once('/')
+                    .map_or(path.path.as_str(), |(_, name)| name);
+                Ok(glob.await?.execute(name))
+            }
+            ContextCondition::PathGlob { base, glob } => {
+                Ok(match base.await?.get_relative_path_to(path) {
+                    Some(relative_path) => glob.await?.execute(&relative_path),
+                    None => glob.await?.execute(&path.path),
+                })
+            }
         }
     }
 }
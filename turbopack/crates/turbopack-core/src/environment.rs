@@ -219,6 +219,28 @@ impl Environment {
             ExecutionEnvironment::Custom(_) => todo!(),
         }
     }
+
+    /// The environment of a web worker that is started from this environment. Web workers don't
+    /// have access to the DOM.
+    #[turbo_tasks::function]
+    pub async fn web_worker(self: Vc<Self>) -> Result<Vc<Self>> {
+        let ExecutionEnvironment::Browser(browser_env) = self.await?.execution else {
+            return Ok(self);
+        };
+        let browser_env = browser_env.await?;
+        if browser_env.web_worker {
+            return Ok(self);
+        }
+        Ok(Environment::new(Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: false,
+                web_worker: true,
+                service_worker: false,
+                browserslist_query: browser_env.browserslist_query.clone(),
+            }
+            .resolved_cell(),
+        ))))
+    }
 }
 
 pub enum NodeEnvironmentType {
@@ -35,6 +35,20 @@ pub trait Module: Asset {
     fn style_type(self: Vc<Self>) -> Vc<OptionStyleType> {
         Vc::cell(None)
     }
+
+    /// Signifies that evaluating the module has no side effects, e.g. because its package is marked
+    /// with `"sideEffects": false`. Such a module can be omitted when none of its exports are
+    /// used.
+    fn is_side_effect_free(self: Vc<Self>) -> Vc<bool> {
+        Vc::cell(false)
+    }
+
+    /// Signifies that the module can be merged into the module factory of the module that imports
+    /// it (scope hoisting), e.g. because it's an ESM module with statically known exports. Modules
+    /// are only merged into other mergeable modules.
+    fn is_mergeable(self: Vc<Self>) -> Vc<bool> {
+        Vc::cell(false)
+    }
 }
 
 #[turbo_tasks::value(transparent)]
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexMap, ResolvedVc, TryFlatJoinIterExt, TryJoinIterExt, Vc};
+
+use crate::{
+    chunk::{ChunkableModuleReference, ChunkingType},
+    module::Module,
+    module_graph::ModuleGraph,
+};
+
+/// Options for the chunk group that is loaded by an async reference, e.g. from the magic comments
+/// of an `import()`.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Default, Hash)]
+pub struct AsyncChunkGroupOptions {
+    /// The name of the chunk that contains the referenced module.
+    pub name: Option<RcStr>,
+    /// Emit a `<link rel="prefetch">` for the chunks of the chunk group when the importing module
+    /// is evaluated.
+    pub prefetch: bool,
+    /// Emit a `<link rel="preload">` for the chunks of the chunk group when the importing module
+    /// is evaluated.
+    pub preload: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl AsyncChunkGroupOptions {
+    #[turbo_tasks::function]
+    pub fn none() -> Vc<Self> {
+        AsyncChunkGroupOptions::default().cell()
+    }
+}
+
+impl AsyncChunkGroupOptions {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && !self.prefetch && !self.preload
+    }
+
+    /// Merges the options of another reference to the same module. The first name wins, and
+    /// hints are emitted when any of the references asks for them.
+    fn merge(&mut self, other: &AsyncChunkGroupOptions) {
+        if self.name.is_none() {
+            self.name.clone_from(&other.name);
+        }
+        self.prefetch |= other.prefetch;
+        self.preload |= other.preload;
+    }
+}
+
+/// The [AsyncChunkGroupOptions] of the async chunk groups in a [ModuleGraph], by the module that
+/// the chunk group is created for. Modules without options are omitted.
+#[turbo_tasks::value(transparent)]
+pub struct AsyncChunkGroupOptionsInfo(
+    FxIndexMap<ResolvedVc<Box<dyn Module>>, AsyncChunkGroupOptions>,
+);
+
+#[turbo_tasks::function]
+pub async fn compute_async_chunk_group_options(
+    graph: Vc<ModuleGraph>,
+) -> Result<Vc<AsyncChunkGroupOptionsInfo>> {
+    let graphs = graph.await?.get_graphs().await?;
+    let references = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|node| async_references_with_options(*node.module))
+        .try_join()
+        .await?;
+
+    let mut info = FxIndexMap::<_, AsyncChunkGroupOptions>::default();
+    for references in &references {
+        for (options, targets) in references.iter() {
+            for &target in targets {
+                info.entry(target).or_default().merge(options);
+            }
+        }
+    }
+
+    Ok(Vc::cell(info))
+}
+
+#[turbo_tasks::value(transparent)]
+struct AsyncReferencesWithOptions(Vec<(AsyncChunkGroupOptions, Vec<ResolvedVc<Box<dyn Module>>>)>);
+
+/// The async references of a module that have [AsyncChunkGroupOptions], together with the
+/// modules they reference.
+#[turbo_tasks::function]
+async fn async_references_with_options(
+    module: Vc<Box<dyn Module>>,
+) -> Result<Vc<AsyncReferencesWithOptions>> {
+    let references = module
+        .references()
+        .await?
+        .iter()
+        .map(|reference| async {
+            let Some(reference) =
+                ResolvedVc::try_downcast::<Box<dyn ChunkableModuleReference>>(*reference)
+            else {
+                return Ok(None);
+            };
+            if !matches!(*reference.chunking_type().await?, Some(ChunkingType::Async)) {
+                return Ok(None);
+            }
+            let options = reference.async_chunk_group_options().owned().await?;
+            if options.is_empty() {
+                return Ok(None);
+            }
+            let targets = reference
+                .resolve_reference()
+                .resolve()
+                .await?
+                .primary_modules()
+                .owned()
+                .await?;
+            Ok(Some((options, targets)))
+        })
+        .try_flat_join()
+        .await?;
+    Ok(Vc::cell(references))
+}
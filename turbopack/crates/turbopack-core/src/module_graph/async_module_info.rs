@@ -1,11 +1,13 @@
 use anyhow::Result;
-use rustc_hash::FxHashSet;
-use turbo_tasks::{ResolvedVc, TryJoinIterExt, Vc};
+use petgraph::visit::EdgeRef;
+use rustc_hash::{FxHashMap, FxHashSet};
+use turbo_tasks::{ResolvedVc, TryJoinIterExt, ValueToString, Vc};
+use turbo_tasks_fs::FileSystemPath;
 
 use crate::{
-    chunk::ChunkingType,
+    issue::{Issue, IssueExt, IssueSeverity, IssueStage, OptionStyledString, StyledString},
     module::{Module, Modules},
-    module_graph::{GraphTraversalAction, ModuleGraph, SingleModuleGraph},
+    module_graph::{ModuleGraph, SingleModuleGraph},
 };
 
 #[turbo_tasks::value(transparent)]
@@ -67,63 +69,127 @@ async fn compute_async_module_info_single(
         .collect::<FxHashSet<_>>();
 
     // To determine which modules are async, we need to propagate the self-async flag to all
-    // importers, which is done using a postorder traversal of the graph.
-    //
-    // This however doesn't cover cycles of async modules, which are handled by determining all
-    // strongly-connected components, and then marking all the whole SCC as async if one of the
-    // modules in the SCC is async.
+    // importers. This is done with a worklist over the reversed edges, which also covers cycles:
+    // every module of a cycle that imports an async module becomes async, and so do all the
+    // importers of that cycle.
+    let mut importers = FxHashMap::<_, Vec<_>>::default();
+    for edge in graph.graph.edge_references() {
+        if !edge.weight().is_inherit_async() {
+            continue;
+        }
+        let importer = graph.graph.node_weight(edge.source()).unwrap().module();
+        let imported = graph.graph.node_weight(edge.target()).unwrap().module();
+        importers.entry(imported).or_default().push(importer);
+    }
 
     let mut async_modules = self_async_modules;
-    graph.traverse_edges_from_entries_topological(
-        graph.entry_modules(),
-        &mut (),
-        |_, _, _| Ok(GraphTraversalAction::Continue),
-        |parent_info, module, _| {
-            let Some((parent_module, chunking_type)) = parent_info else {
-                // An entry module
-                return;
-            };
-            let module = module.module();
-            let parent_module = parent_module.module;
-
-            // edges.push((parent_module, module, async_modules.contains(&module)));
-            match chunking_type {
-                ChunkingType::ParallelInheritAsync
-                | ChunkingType::Shared {
-                    inherit_async: true,
-                    ..
-                } => {
-                    if async_modules.contains(&module) {
-                        async_modules.insert(parent_module);
-                    }
-                }
-                ChunkingType::Parallel
-                | ChunkingType::Async
-                | ChunkingType::Isolated { .. }
-                | ChunkingType::Traced
-                | ChunkingType::Shared {
-                    inherit_async: false,
-                    ..
-                } => {
-                    // Nothing to propagate
-                }
+    let mut queue = async_modules.iter().copied().collect::<Vec<_>>();
+    while let Some(module) = queue.pop() {
+        for &importer in importers.get(&module).into_iter().flatten() {
+            if async_modules.insert(importer) {
+                queue.push(importer);
             }
-        },
-    )?;
+        }
+    }
+
+    Ok(Vc::cell(async_modules))
+}
+
+/// The cycles of modules (over edges that inherit the async flag) that contain a module with a
+/// top-level `await`.
+#[turbo_tasks::value(transparent)]
+pub struct AsyncModuleCycles(Vec<Vec<ResolvedVc<Box<dyn Module>>>>);
+
+#[turbo_tasks::function(operation)]
+pub async fn compute_async_module_cycles(
+    graph: ResolvedVc<ModuleGraph>,
+) -> Result<Vc<AsyncModuleCycles>> {
+    let graph = graph.await?;
+
+    let mut cycles = Vec::new();
+    graph
+        .traverse_cycles(
+            |ty| ty.is_inherit_async(),
+            |cycle| cycles.push(cycle.iter().map(|node| node.module).collect::<Vec<_>>()),
+        )
+        .await?;
 
-    graph.traverse_cycles(
-        |ty| ty.is_inherit_async(),
-        |cycle| {
-            if cycle
+    let cycles = cycles
+        .into_iter()
+        .map(async |cycle| {
+            let is_self_async = cycle
                 .iter()
-                .any(|node| async_modules.contains(&node.module))
-            {
-                for &node in cycle {
-                    async_modules.insert(node.module);
-                }
-            }
-        },
-    );
+                .map(async |module| Ok(*module.is_self_async().await?))
+                .try_join()
+                .await?;
+            Ok(is_self_async.into_iter().any(|v| v).then_some(cycle))
+        })
+        .try_join()
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
 
-    Ok(Vc::cell(async_modules))
+    Ok(Vc::cell(cycles))
+}
+
+/// A cycle of modules that contains a module with a top-level `await`. The modules of the cycle
+/// can't wait for each other, so some of them observe the bindings of the others before they are
+/// evaluated.
+#[turbo_tasks::value(shared)]
+pub struct AsyncModuleCycleIssue {
+    pub modules: Vec<ResolvedVc<Box<dyn Module>>>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for AsyncModuleCycleIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> Vc<IssueSeverity> {
+        IssueSeverity::Warning.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn stage(&self) -> Vc<IssueStage> {
+        IssueStage::Analysis.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn file_path(&self) -> Vc<FileSystemPath> {
+        self.modules[0].ident().path()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<StyledString> {
+        StyledString::Text("Top-level await in a module cycle".into()).cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn description(&self) -> Result<Vc<OptionStyledString>> {
+        let modules = self
+            .modules
+            .iter()
+            .map(async |module| Ok(module.ident().to_string().owned().await?))
+            .try_join()
+            .await?;
+        let mut lines: Vec<StyledString> = vec![StyledString::Text(
+            "These modules import each other, and at least one of them uses top-level await. A \
+             module of the cycle can't wait for the others to finish evaluating, so it might \
+             access their exports before they are initialized:"
+                .into(),
+        )];
+        lines.extend(modules.into_iter().map(StyledString::Code));
+        Ok(Vc::cell(Some(StyledString::Stack(lines).resolved_cell())))
+    }
+}
+
+/// Emits an [AsyncModuleCycleIssue] for every cycle in [AsyncModuleCycles].
+pub async fn emit_async_module_cycle_issues(cycles: Vc<AsyncModuleCycles>) -> Result<()> {
+    for cycle in cycles.await?.iter() {
+        AsyncModuleCycleIssue {
+            modules: cycle.clone(),
+        }
+        .resolved_cell()
+        .emit();
+    }
+    Ok(())
 }
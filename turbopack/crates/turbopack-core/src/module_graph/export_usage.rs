@@ -0,0 +1,1107 @@
+use std::{
+    collections::{hash_map::Entry, BTreeMap},
+    hash::Hash,
+};
+
+use anyhow::Result;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use roaring::RoaringBitmap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{
+    trace::TraceRawVcs, FxIndexMap, FxIndexSet, NonLocalValue, ResolvedVc, TryFlatJoinIterExt,
+    TryJoinIterExt, ValueToString, Vc,
+};
+
+use crate::{
+    module::Module,
+    module_graph::{chunk_group_info::RoaringBitmapWrapper, ModuleGraph, SingleModuleGraph},
+    reference::primary_chunkable_referenced_modules_with_export_usage,
+};
+
+/// How a reference uses the exports of the module it references.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Hash)]
+pub enum ExportUsage {
+    /// The module is only evaluated, e.g. by `import "./module"`.
+    Evaluation,
+    /// A single export is used, e.g. by `import { foo } from "./module"`.
+    Named(RcStr),
+    /// Only these properties of the default export are read, e.g. by `data.foo` after
+    /// `import data from "./data.json"`.
+    DefaultExportProperties(Vec<RcStr>),
+    /// An export is re-exported under a (possibly different) name, e.g. by
+    /// `export { foo as bar } from "./module"`. The export is only used when the re-export is.
+    ReExport { imported: RcStr, exported: RcStr },
+    /// The namespace object is re-exported under a name, e.g. by `export * as ns from "./module"`.
+    /// All exports are used when the re-export is.
+    ReExportNamespace(RcStr),
+    /// All exports except `default` are re-exported by `export * from "./module"`. An export is
+    /// only used when it's used on the re-exporting module.
+    ReExportAll,
+    /// The namespace object is only used to read these exports, e.g. by
+    /// `(await import("./module")).foo` or `require("./module").foo`. The exports are read by
+    /// name at runtime.
+    NamespaceProperties(Vec<RcStr>),
+    /// All exports might be used, e.g. by a namespace import or by a CommonJS module.
+    All,
+}
+
+#[turbo_tasks::value_impl]
+impl ExportUsage {
+    #[turbo_tasks::function]
+    pub fn all() -> Vc<Self> {
+        ExportUsage::All.cell()
+    }
+
+    #[turbo_tasks::function]
+    pub fn evaluation() -> Vc<Self> {
+        ExportUsage::Evaluation.cell()
+    }
+
+    #[turbo_tasks::function]
+    pub fn named(name: RcStr) -> Vc<Self> {
+        ExportUsage::Named(name).cell()
+    }
+}
+
+/// The exports of a module that are used by the modules that reference it.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub enum ModuleExportUsageInfo {
+    /// The module is only evaluated for its side effects, none of its exports are used.
+    Evaluation,
+    /// Only these exports are used.
+    Exports(FxHashSet<RcStr>),
+    /// All exports might be used.
+    All,
+}
+
+impl ModuleExportUsageInfo {
+    pub fn is_export_used(&self, export: &RcStr) -> bool {
+        match self {
+            ModuleExportUsageInfo::Evaluation => false,
+            ModuleExportUsageInfo::Exports(exports) => exports.contains(export),
+            ModuleExportUsageInfo::All => true,
+        }
+    }
+
+    /// Marks an export as used. Returns true if it wasn't used before.
+    fn add_export(&mut self, export: &RcStr) -> bool {
+        match self {
+            ModuleExportUsageInfo::Evaluation => {
+                *self = ModuleExportUsageInfo::Exports(FxHashSet::from_iter([export.clone()]));
+                true
+            }
+            ModuleExportUsageInfo::Exports(exports) => exports.insert(export.clone()),
+            ModuleExportUsageInfo::All => false,
+        }
+    }
+
+    /// Marks all exports as used. Returns true if they weren't all used before.
+    fn add_all(&mut self) -> bool {
+        if *self == ModuleExportUsageInfo::All {
+            return false;
+        }
+        *self = ModuleExportUsageInfo::All;
+        true
+    }
+
+    /// Marks the exports used by `other` as used too. Returns true if any of them wasn't used
+    /// before.
+    fn merge(&mut self, other: &ModuleExportUsageInfo) -> bool {
+        match other {
+            ModuleExportUsageInfo::Evaluation => false,
+            ModuleExportUsageInfo::Exports(exports) => {
+                let mut changed = false;
+                for export in exports {
+                    changed |= self.add_export(export);
+                }
+                changed
+            }
+            ModuleExportUsageInfo::All => self.add_all(),
+        }
+    }
+}
+
+/// The export usages that are known from the references within a single [SingleModuleGraph].
+/// Re-exports are only recorded here and are resolved in [compute_export_usage_info], since a
+/// re-exporting module and its importers might be in different graphs.
+///
+/// This is computed for each graph on its own, so only the partial results of the graphs that
+/// changed have to be recomputed before they are merged again.
+#[turbo_tasks::value]
+#[derive(Default)]
+pub struct PartialExportUsageInfo {
+    /// The used exports of the entries and the directly referenced modules.
+    used_exports: FxHashMap<ResolvedVc<Box<dyn Module>>, ModuleExportUsageInfo>,
+    /// The re-exports of each module, which pass the usage of the module on to their targets.
+    reexports:
+        FxHashMap<ResolvedVc<Box<dyn Module>>, Vec<(ResolvedVc<Box<dyn Module>>, ExportUsage)>>,
+    /// Modules whose exports are read from their namespace object by name.
+    namespace_accessed: FxHashSet<ResolvedVc<Box<dyn Module>>>,
+    /// The properties that are read from the default export of each module, or `None` when the
+    /// default export is used as a whole.
+    default_export_properties: FxHashMap<ResolvedVc<Box<dyn Module>>, Option<FxHashSet<RcStr>>>,
+}
+
+impl PartialExportUsageInfo {
+    /// Records the export usages of the references of a module.
+    fn add_references(
+        &mut self,
+        source: ResolvedVc<Box<dyn Module>>,
+        references: &[(ExportUsage, Vec<ResolvedVc<Box<dyn Module>>>)],
+    ) {
+        for (usage, targets) in references.iter() {
+            for &target in targets {
+                let used = self
+                    .used_exports
+                    .entry(target)
+                    .or_insert(ModuleExportUsageInfo::Evaluation);
+                if uses_whole_default_export(usage) {
+                    self.default_export_properties.insert(target, None);
+                }
+                match usage {
+                    ExportUsage::Evaluation => {}
+                    ExportUsage::Named(name) => {
+                        used.add_export(name);
+                    }
+                    ExportUsage::DefaultExportProperties(properties) => {
+                        used.add_export(&"default".into());
+                        if let Some(used_properties) = self
+                            .default_export_properties
+                            .entry(target)
+                            .or_insert_with(|| Some(FxHashSet::default()))
+                        {
+                            used_properties.extend(properties.iter().cloned());
+                        }
+                    }
+                    ExportUsage::NamespaceProperties(names) => {
+                        for name in names {
+                            used.add_export(name);
+                        }
+                        self.namespace_accessed.insert(target);
+                    }
+                    ExportUsage::All => {
+                        used.add_all();
+                    }
+                    ExportUsage::ReExport { .. }
+                    | ExportUsage::ReExportNamespace(_)
+                    | ExportUsage::ReExportAll => {
+                        self.reexports
+                            .entry(source)
+                            .or_default()
+                            .push((target, usage.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Merges the export usages that were collected from other modules of the same graph.
+    fn merge(mut self, other: Self) -> Self {
+        for (module, used) in other.used_exports {
+            match self.used_exports.entry(module) {
+                Entry::Occupied(mut e) => {
+                    e.get_mut().merge(&used);
+                }
+                Entry::Vacant(e) => {
+                    e.insert(used);
+                }
+            }
+        }
+        for (source, reexports) in other.reexports {
+            self.reexports.entry(source).or_default().extend(reexports);
+        }
+        self.namespace_accessed.extend(other.namespace_accessed);
+        for (module, properties) in other.default_export_properties {
+            match (self.default_export_properties.entry(module), properties) {
+                (Entry::Vacant(e), properties) => {
+                    e.insert(properties);
+                }
+                (Entry::Occupied(mut e), Some(properties)) => {
+                    if let Some(used_properties) = e.get_mut() {
+                        used_properties.extend(properties);
+                    }
+                }
+                (Entry::Occupied(mut e), None) => {
+                    e.insert(None);
+                }
+            }
+        }
+        self
+    }
+}
+
+/// The used exports of a module, as indices into the export names of an [ExportUsageInfo]. Apps
+/// have a lot of modules that use the same few export names, so storing each name only once and
+/// the sets as bitmaps is much more compact than sets of names.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
+enum UsedExportIds {
+    Evaluation,
+    Exports(RoaringBitmapWrapper),
+    All,
+}
+
+impl UsedExportIds {
+    /// Converts the used exports of a module into ids, adding the names that are not known yet to
+    /// `names`.
+    fn intern(used: &ModuleExportUsageInfo, names: &mut FxIndexSet<RcStr>) -> Self {
+        match used {
+            ModuleExportUsageInfo::Evaluation => UsedExportIds::Evaluation,
+            ModuleExportUsageInfo::Exports(exports) => {
+                UsedExportIds::Exports(RoaringBitmapWrapper(
+                    exports
+                        .iter()
+                        .map(|export| export_id(names, export))
+                        .collect(),
+                ))
+            }
+            ModuleExportUsageInfo::All => UsedExportIds::All,
+        }
+    }
+
+    fn contains(&self, id: Option<u32>) -> bool {
+        match self {
+            UsedExportIds::Evaluation => false,
+            UsedExportIds::Exports(ids) => id.is_some_and(|id| ids.contains(id)),
+            UsedExportIds::All => true,
+        }
+    }
+
+    /// Marks an export as used. Returns true if it wasn't used before.
+    fn add_export(&mut self, id: u32) -> bool {
+        match self {
+            UsedExportIds::Evaluation => {
+                *self =
+                    UsedExportIds::Exports(RoaringBitmapWrapper(RoaringBitmap::from_iter([id])));
+                true
+            }
+            UsedExportIds::Exports(ids) => ids.insert(id),
+            UsedExportIds::All => false,
+        }
+    }
+
+    /// Marks all exports as used. Returns true if they weren't all used before.
+    fn add_all(&mut self) -> bool {
+        if *self == UsedExportIds::All {
+            return false;
+        }
+        *self = UsedExportIds::All;
+        true
+    }
+
+    /// Marks the exports used by `other` as used too. Returns true if any of them wasn't used
+    /// before.
+    fn merge(&mut self, other: &UsedExportIds) -> bool {
+        match other {
+            UsedExportIds::Evaluation => false,
+            UsedExportIds::Exports(other_ids) => match self {
+                UsedExportIds::Evaluation => {
+                    *self = UsedExportIds::Exports(other_ids.clone());
+                    !other_ids.is_empty()
+                }
+                UsedExportIds::Exports(ids) => {
+                    let len = ids.len();
+                    **ids |= &**other_ids;
+                    ids.len() != len
+                }
+                UsedExportIds::All => false,
+            },
+            UsedExportIds::All => self.add_all(),
+        }
+    }
+}
+
+/// Returns the id of an export name, adding it to `names` if it's not known yet.
+fn export_id(names: &mut FxIndexSet<RcStr>, name: &RcStr) -> u32 {
+    let index = match names.get_index_of(name) {
+        Some(index) => index,
+        None => names.insert_full(name.clone()).0,
+    };
+    index as u32
+}
+
+/// Sorts the export names and updates the ids of the used exports accordingly. The ids depend on
+/// the order in which the partial results are merged otherwise, so the same usage could result in
+/// a different [ExportUsageInfo], which would invalidate all tasks that read it, also those that
+/// are restored from the persistent cache.
+fn sort_export_names<M>(
+    names: &mut FxIndexSet<RcStr>,
+    used_exports: &mut FxHashMap<M, UsedExportIds>,
+) {
+    let mut order = (0..names.len()).collect::<Vec<_>>();
+    order.sort_unstable_by(|a, b| names[*a].cmp(&names[*b]));
+    let mut ids = vec![0; names.len()];
+    for (id, index) in order.into_iter().enumerate() {
+        ids[index] = id as u32;
+    }
+    names.sort_unstable();
+    for used in used_exports.values_mut() {
+        if let UsedExportIds::Exports(used_ids) = used {
+            *used_ids = RoaringBitmapWrapper(used_ids.iter().map(|id| ids[id as usize]).collect());
+        }
+    }
+}
+
+/// Which exports of each module in a [ModuleGraph] are used.
+#[turbo_tasks::value]
+pub struct ExportUsageInfo {
+    /// The names of all used exports, which [UsedExportIds] refer to by index.
+    export_names: FxIndexSet<RcStr>,
+    used_exports: FxHashMap<ResolvedVc<Box<dyn Module>>, UsedExportIds>,
+    /// Side effect free modules of which no export is used, so they can be omitted.
+    unused_modules: FxHashSet<ResolvedVc<Box<dyn Module>>>,
+    /// Modules that are re-exported by `export *` or `export * as ns`, which copies their exports
+    /// by name.
+    star_reexported: FxHashSet<ResolvedVc<Box<dyn Module>>>,
+    /// Modules whose exports are read from their namespace object by name, e.g. after a dynamic
+    /// import.
+    namespace_accessed: FxHashSet<ResolvedVc<Box<dyn Module>>>,
+    /// The properties that are read from the default export of modules whose default export is
+    /// only used to read properties.
+    default_export_properties: FxHashMap<ResolvedVc<Box<dyn Module>>, FxHashSet<RcStr>>,
+}
+
+impl ExportUsageInfo {
+    /// Returns the used exports of a module. Modules that are not part of the graph are treated as
+    /// if all their exports are used.
+    pub fn used_exports(&self, module: ResolvedVc<Box<dyn Module>>) -> ModuleExportUsageInfo {
+        match self.used_export_ids(module) {
+            UsedExportIds::Evaluation => ModuleExportUsageInfo::Evaluation,
+            UsedExportIds::Exports(ids) => ModuleExportUsageInfo::Exports(
+                ids.iter()
+                    .map(|id| self.export_names[id as usize].clone())
+                    .collect(),
+            ),
+            UsedExportIds::All => ModuleExportUsageInfo::All,
+        }
+    }
+
+    /// Returns true if all exports of the module might be used. This is cheaper than checking the
+    /// result of [ExportUsageInfo::used_exports].
+    pub fn are_all_exports_used(&self, module: ResolvedVc<Box<dyn Module>>) -> bool {
+        *self.used_export_ids(module) == UsedExportIds::All
+    }
+
+    fn used_export_ids(&self, module: ResolvedVc<Box<dyn Module>>) -> &UsedExportIds {
+        self.used_exports
+            .get(&module)
+            .unwrap_or(&UsedExportIds::All)
+    }
+
+    fn export_used(&self, module: ResolvedVc<Box<dyn Module>>, export: &str) -> bool {
+        self.used_export_ids(module).contains(
+            self.export_names
+                .get_index_of(export)
+                .map(|index| index as u32),
+        )
+    }
+
+    /// Returns true if the module is side effect free and none of its exports are used, so it
+    /// doesn't need to be evaluated at all.
+    pub fn is_module_unused(&self, module: ResolvedVc<Box<dyn Module>>) -> bool {
+        self.unused_modules.contains(&module)
+    }
+
+    /// Returns true if the module is re-exported by `export *` or `export * as ns`. The names of
+    /// its exports are then visible to modules that don't reference it directly.
+    pub fn is_star_reexported(&self, module: ResolvedVc<Box<dyn Module>>) -> bool {
+        self.star_reexported.contains(&module)
+    }
+
+    /// Returns true if exports of the module are read from its namespace object by name, so they
+    /// can't be renamed.
+    pub fn is_namespace_accessed(&self, module: ResolvedVc<Box<dyn Module>>) -> bool {
+        self.namespace_accessed.contains(&module)
+    }
+
+    /// Returns the properties that are read from the default export of the module, when the
+    /// default export is only used to read properties with static names.
+    pub fn default_export_properties(
+        &self,
+        module: ResolvedVc<Box<dyn Module>>,
+    ) -> Option<&FxHashSet<RcStr>> {
+        self.default_export_properties.get(&module)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ExportUsageInfo {
+    #[turbo_tasks::function]
+    pub fn is_export_used(&self, module: ResolvedVc<Box<dyn Module>>, export: RcStr) -> Vc<bool> {
+        Vc::cell(self.export_used(module, &export))
+    }
+
+    /// Returns for each of the exports whether it's used, in the same order. Prefer this over
+    /// calling [ExportUsageInfo::is_export_used] for every export of a module.
+    #[turbo_tasks::function]
+    pub fn are_exports_used(
+        &self,
+        module: ResolvedVc<Box<dyn Module>>,
+        exports: Vec<RcStr>,
+    ) -> Vc<Vec<bool>> {
+        Vc::cell(
+            exports
+                .iter()
+                .map(|export| self.export_used(module, export))
+                .collect(),
+        )
+    }
+
+    /// Returns the used exports of a module in a cell of its own. Tasks that read it only depend
+    /// on the usage of that module instead of the usage of all modules in the graph.
+    #[turbo_tasks::function]
+    pub fn module_used_exports(
+        &self,
+        module: ResolvedVc<Box<dyn Module>>,
+    ) -> Vc<ModuleExportUsageInfo> {
+        self.used_exports(module).cell()
+    }
+}
+
+/// Collects the export usages of the references of the modules in a single graph.
+#[turbo_tasks::function]
+pub async fn compute_partial_export_usage_info(
+    graph: Vc<SingleModuleGraph>,
+) -> Result<Vc<PartialExportUsageInfo>> {
+    let graph = graph.await?;
+    let references = graph
+        .iter_nodes()
+        .map(async |node| {
+            let source = node.module;
+            let references =
+                primary_chunkable_referenced_modules_with_export_usage(*source).await?;
+            Ok((source, references))
+        })
+        .try_join()
+        .await?;
+
+    // The usages are collected on the rayon thread pool, as this is on the critical path of
+    // chunking and large graphs have a lot of references.
+    let mut info = references
+        .par_iter()
+        .fold(
+            PartialExportUsageInfo::default,
+            |mut info, (source, references)| {
+                info.add_references(*source, references);
+                info
+            },
+        )
+        .reduce(
+            PartialExportUsageInfo::default,
+            PartialExportUsageInfo::merge,
+        );
+    for entry in graph.entry_modules() {
+        info.used_exports.insert(entry, ModuleExportUsageInfo::All);
+    }
+
+    Ok(info.cell())
+}
+
+/// Returns true if the usage might use the default export as a whole and not only read some of
+/// its properties.
+fn uses_whole_default_export(usage: &ExportUsage) -> bool {
+    match usage {
+        ExportUsage::Evaluation
+        | ExportUsage::ReExportAll
+        | ExportUsage::DefaultExportProperties(_) => false,
+        ExportUsage::Named(name) | ExportUsage::ReExport { imported: name, .. } => {
+            name == "default"
+        }
+        ExportUsage::NamespaceProperties(names) => names.iter().any(|name| name == "default"),
+        ExportUsage::ReExportNamespace(_) | ExportUsage::All => true,
+    }
+}
+
+/// Computes which exports of the modules in the graph are used, from the partial export usages of
+/// its graphs, see [merge_export_usage].
+#[turbo_tasks::function]
+pub async fn compute_export_usage_info(graph: Vc<ModuleGraph>) -> Result<Vc<ExportUsageInfo>> {
+    let async_modules = graph.async_module_info().await?;
+    let graph = graph.await?;
+    let partials = graph
+        .graphs
+        .iter()
+        .map(|graph| compute_partial_export_usage_info(**graph))
+        .try_join()
+        .await?;
+
+    let MergedExportUsage {
+        export_names,
+        used_exports,
+        star_reexported,
+        namespace_accessed,
+        default_export_properties,
+    } = merge_export_usage(partials.iter().map(|partial| PartialExportUsage {
+        used_exports: &partial.used_exports,
+        reexports: &partial.reexports,
+        namespace_accessed: &partial.namespace_accessed,
+        default_export_properties: &partial.default_export_properties,
+    }));
+
+    // Async modules are awaited by their importers, so they have to be kept too.
+    let unused_modules = used_exports
+        .iter()
+        .filter(|(module, used)| {
+            **used == UsedExportIds::Evaluation
+                && !star_reexported.contains(*module)
+                && !async_modules.contains(*module)
+        })
+        .map(async |(module, _)| {
+            Ok(if *module.is_side_effect_free().await? {
+                Some(*module)
+            } else {
+                None
+            })
+        })
+        .try_flat_join()
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(ExportUsageInfo {
+        export_names,
+        used_exports,
+        unused_modules,
+        star_reexported,
+        namespace_accessed,
+        default_export_properties: default_export_properties
+            .into_iter()
+            .filter_map(|(module, properties)| Some((module, properties?)))
+            .collect(),
+    }
+    .cell())
+}
+
+/// The fields of a [PartialExportUsageInfo] that [merge_export_usage] reads, generic over the
+/// module type so the merging can be tested without modules.
+struct PartialExportUsage<'a, M> {
+    used_exports: &'a FxHashMap<M, ModuleExportUsageInfo>,
+    reexports: &'a FxHashMap<M, Vec<(M, ExportUsage)>>,
+    namespace_accessed: &'a FxHashSet<M>,
+    default_export_properties: &'a FxHashMap<M, Option<FxHashSet<RcStr>>>,
+}
+
+/// The export usage of all graphs, see [ExportUsageInfo].
+#[derive(Debug, PartialEq, Eq)]
+struct MergedExportUsage<M: Eq + Hash> {
+    export_names: FxIndexSet<RcStr>,
+    used_exports: FxHashMap<M, UsedExportIds>,
+    star_reexported: FxHashSet<M>,
+    namespace_accessed: FxHashSet<M>,
+    default_export_properties: FxHashMap<M, Option<FxHashSet<RcStr>>>,
+}
+
+/// Merges the partial export usages of the graphs and follows the re-exports. The result doesn't
+/// depend on the order of the partial results.
+fn merge_export_usage<'a, M: Copy + Eq + Hash + 'a>(
+    partials: impl IntoIterator<Item = PartialExportUsage<'a, M>>,
+) -> MergedExportUsage<M> {
+    let mut export_names = FxIndexSet::default();
+    let mut used_exports = FxHashMap::<_, UsedExportIds>::default();
+    let mut reexports = FxHashMap::<_, Vec<_>>::default();
+    // The generated code of `export *` and `export * as ns` refers to the target module even when
+    // none of its exports are used.
+    let mut star_reexported = FxHashSet::default();
+    let mut namespace_accessed = FxHashSet::default();
+    let mut default_export_properties = FxHashMap::<_, Option<FxHashSet<_>>>::default();
+    for partial in partials {
+        namespace_accessed.extend(partial.namespace_accessed.iter().copied());
+        for (module, properties) in partial.default_export_properties {
+            match (default_export_properties.entry(*module), properties) {
+                (Entry::Vacant(e), _) => {
+                    e.insert(properties.clone());
+                }
+                (Entry::Occupied(mut e), Some(properties)) => {
+                    if let Some(used_properties) = e.get_mut() {
+                        used_properties.extend(properties.iter().cloned());
+                    }
+                }
+                (Entry::Occupied(mut e), None) => {
+                    e.insert(None);
+                }
+            }
+        }
+        for (module, used) in partial.used_exports {
+            let used = UsedExportIds::intern(used, &mut export_names);
+            match used_exports.entry(*module) {
+                Entry::Occupied(mut e) => {
+                    e.get_mut().merge(&used);
+                }
+                Entry::Vacant(e) => {
+                    e.insert(used);
+                }
+            }
+        }
+        for (module, module_reexports) in partial.reexports {
+            star_reexported.extend(
+                module_reexports
+                    .iter()
+                    .filter(|(_, usage)| {
+                        matches!(
+                            usage,
+                            ExportUsage::ReExportAll | ExportUsage::ReExportNamespace(_)
+                        )
+                    })
+                    .map(|(target, _)| *target),
+            );
+            reexports
+                .entry(*module)
+                .or_default()
+                .extend(module_reexports.iter().cloned());
+        }
+    }
+
+    // Modules whose used exports changed, so their re-exports need to be updated. Initially that
+    // are all re-exporting modules.
+    let mut queue = reexports.keys().copied().collect::<Vec<_>>();
+
+    // Follow re-export chains until the used exports don't change anymore. Exports are only ever
+    // added, so this terminates for cyclic re-exports too.
+    while let Some(module) = queue.pop() {
+        let Some(module_reexports) = reexports.get(&module) else {
+            continue;
+        };
+        let used = used_exports
+            .get(&module)
+            .cloned()
+            .unwrap_or(UsedExportIds::Evaluation);
+        for (target, usage) in module_reexports {
+            let target_used = match used_exports.entry(*target) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => e.insert(UsedExportIds::Evaluation),
+            };
+            let changed = match (&used, usage) {
+                (UsedExportIds::Evaluation, _) => false,
+                (UsedExportIds::All, ExportUsage::ReExport { imported, .. }) => {
+                    target_used.add_export(export_id(&mut export_names, imported))
+                }
+                (UsedExportIds::All, _) => target_used.add_all(),
+                (UsedExportIds::Exports(ids), ExportUsage::ReExport { imported, exported }) => {
+                    export_names
+                        .get_index_of(exported)
+                        .is_some_and(|id| ids.contains(id as u32))
+                        && target_used.add_export(export_id(&mut export_names, imported))
+                }
+                (UsedExportIds::Exports(ids), ExportUsage::ReExportNamespace(name)) => {
+                    export_names
+                        .get_index_of(name)
+                        .is_some_and(|id| ids.contains(id as u32))
+                        && target_used.add_all()
+                }
+                (UsedExportIds::Exports(ids), ExportUsage::ReExportAll) => {
+                    // Exports that the module declares itself are added too, which is
+                    // conservative
+                    let mut ids = ids.clone();
+                    if let Some(default) = export_names.get_index_of("default") {
+                        ids.remove(default as u32);
+                    }
+                    target_used.merge(&UsedExportIds::Exports(ids))
+                }
+                (UsedExportIds::Exports(_), _) => false,
+            };
+            if changed {
+                queue.push(*target);
+            }
+        }
+    }
+
+    sort_export_names(&mut export_names, &mut used_exports);
+
+    MergedExportUsage {
+        export_names,
+        used_exports,
+        star_reexported,
+        namespace_accessed,
+        default_export_properties,
+    }
+}
+
+/// The export usage of a module in the JSON dump of [export_usage_json].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModuleExportUsageJson {
+    /// All exports might be used, e.g. because of a namespace import.
+    all_exports_used: bool,
+    /// The used exports, sorted by name. Empty when all exports are used.
+    used_exports: Vec<RcStr>,
+    /// The module is side effect free and none of its exports are used, so it's omitted.
+    unused: bool,
+    star_reexported: bool,
+    namespace_accessed: bool,
+    /// The references to the module, which decide which exports are used.
+    references: Vec<ExportUsageReferenceJson>,
+}
+
+#[derive(Serialize)]
+struct ExportUsageReferenceJson {
+    /// The ident of the referencing module.
+    from: RcStr,
+    #[serde(flatten)]
+    usage: ExportUsageJson,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum ExportUsageJson {
+    Evaluation,
+    Named { export: RcStr },
+    DefaultExportProperties { properties: Vec<RcStr> },
+    ReExport { imported: RcStr, exported: RcStr },
+    ReExportNamespace { exported: RcStr },
+    ReExportAll,
+    NamespaceProperties { exports: Vec<RcStr> },
+    All,
+}
+
+impl From<&ExportUsage> for ExportUsageJson {
+    fn from(usage: &ExportUsage) -> Self {
+        match usage {
+            ExportUsage::Evaluation => ExportUsageJson::Evaluation,
+            ExportUsage::Named(export) => ExportUsageJson::Named {
+                export: export.clone(),
+            },
+            ExportUsage::DefaultExportProperties(properties) => {
+                ExportUsageJson::DefaultExportProperties {
+                    properties: properties.clone(),
+                }
+            }
+            ExportUsage::ReExport { imported, exported } => ExportUsageJson::ReExport {
+                imported: imported.clone(),
+                exported: exported.clone(),
+            },
+            ExportUsage::ReExportNamespace(exported) => ExportUsageJson::ReExportNamespace {
+                exported: exported.clone(),
+            },
+            ExportUsage::ReExportAll => ExportUsageJson::ReExportAll,
+            ExportUsage::NamespaceProperties(exports) => ExportUsageJson::NamespaceProperties {
+                exports: exports.clone(),
+            },
+            ExportUsage::All => ExportUsageJson::All,
+        }
+    }
+}
+
+/// Collects the export usage of the modules in a graph for the JSON dumps, together with the
+/// ident of each module.
+async fn modules_export_usage_json(
+    graph: Vc<ModuleGraph>,
+) -> Result<Vec<(ResolvedVc<Box<dyn Module>>, RcStr, ModuleExportUsageJson)>> {
+    let export_usage_info = graph.export_usage_info().await?;
+    let graphs = graph.await?.get_graphs().await?;
+    let modules = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|node| node.module)
+        .collect::<FxIndexSet<_>>();
+
+    let modules = modules
+        .into_iter()
+        .map(async |module| {
+            let ident = module.ident().to_string().owned().await?;
+            let references =
+                primary_chunkable_referenced_modules_with_export_usage(*module).await?;
+            Ok((module, ident, references))
+        })
+        .try_join()
+        .await?;
+
+    let mut json = modules
+        .iter()
+        .map(|(module, ident, _)| {
+            let (all_exports_used, mut used_exports) = match export_usage_info.used_exports(*module)
+            {
+                ModuleExportUsageInfo::Evaluation => (false, vec![]),
+                ModuleExportUsageInfo::Exports(exports) => {
+                    (false, exports.into_iter().collect::<Vec<_>>())
+                }
+                ModuleExportUsageInfo::All => (true, vec![]),
+            };
+            used_exports.sort();
+            (
+                *module,
+                (
+                    ident.clone(),
+                    ModuleExportUsageJson {
+                        all_exports_used,
+                        used_exports,
+                        unused: export_usage_info.is_module_unused(*module),
+                        star_reexported: export_usage_info.is_star_reexported(*module),
+                        namespace_accessed: export_usage_info.is_namespace_accessed(*module),
+                        references: vec![],
+                    },
+                ),
+            )
+        })
+        .collect::<FxIndexMap<_, _>>();
+
+    for (_, ident, references) in &modules {
+        for (usage, targets) in references.iter() {
+            for target in targets {
+                if let Some((_, target_json)) = json.get_mut(target) {
+                    target_json.references.push(ExportUsageReferenceJson {
+                        from: ident.clone(),
+                        usage: usage.into(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(json
+        .into_iter()
+        .map(|(module, (ident, json))| (module, ident, json))
+        .collect())
+}
+
+/// Serializes the export usage of the modules in a graph to JSON, keyed by module ident. For each
+/// module it lists the used exports and the references that use them together with the kind of
+/// the reference, so tooling can explain why an export is retained.
+#[turbo_tasks::function]
+pub async fn export_usage_json(graph: Vc<ModuleGraph>) -> Result<Vc<RcStr>> {
+    let json = modules_export_usage_json(graph)
+        .await?
+        .into_iter()
+        .map(|(_, ident, json)| (ident, json))
+        .collect::<BTreeMap<_, _>>();
+    Ok(Vc::cell(serde_json::to_string_pretty(&json)?.into()))
+}
+
+/// The export usage of the modules that are created from the same source file in the JSON dump of
+/// [merged_export_usage_json].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MergedExportUsageJson {
+    /// The layers in which all exports might be used.
+    all_exports_used_in: Vec<Option<RcStr>>,
+    /// The layers in which each export is used, by export name.
+    used_exports: BTreeMap<RcStr, Vec<Option<RcStr>>>,
+    /// The export usage of each module, including the references that use it.
+    modules: Vec<LayerExportUsageJson>,
+}
+
+#[derive(Serialize)]
+struct LayerExportUsageJson {
+    ident: RcStr,
+    /// The layer of the module, e.g. the server or the client.
+    layer: Option<RcStr>,
+    #[serde(flatten)]
+    usage: ModuleExportUsageJson,
+}
+
+/// Serializes the export usage of the modules in a graph to JSON, keyed by the path of their
+/// source file. The modules that are created from the same file for different layers, e.g. for
+/// the server and the client, are merged, and each used export lists the layers that use it.
+#[turbo_tasks::function]
+pub async fn merged_export_usage_json(graph: Vc<ModuleGraph>) -> Result<Vc<RcStr>> {
+    let modules = modules_export_usage_json(graph)
+        .await?
+        .into_iter()
+        .map(async |(module, ident, usage)| {
+            let module_ident = module.ident().await?;
+            let path = module_ident.path.to_string().owned().await?;
+            let layer = match module_ident.layer {
+                Some(layer) => Some(layer.owned().await?),
+                None => None,
+            };
+            Ok((
+                path,
+                LayerExportUsageJson {
+                    ident,
+                    layer,
+                    usage,
+                },
+            ))
+        })
+        .try_join()
+        .await?;
+
+    let mut json = BTreeMap::<_, MergedExportUsageJson>::new();
+    for (path, module) in modules {
+        let merged = json.entry(path).or_insert_with(|| MergedExportUsageJson {
+            all_exports_used_in: vec![],
+            used_exports: BTreeMap::new(),
+            modules: vec![],
+        });
+        if module.usage.all_exports_used {
+            merged.all_exports_used_in.push(module.layer.clone());
+        }
+        for export in &module.usage.used_exports {
+            merged
+                .used_exports
+                .entry(export.clone())
+                .or_default()
+                .push(module.layer.clone());
+        }
+        merged.modules.push(module);
+    }
+    Ok(Vc::cell(serde_json::to_string_pretty(&json)?.into()))
+}
+
+/// The used exports of the source files in a [ModuleGraph], merged over all modules that are
+/// created from the same file, e.g. for the server and the client.
+#[turbo_tasks::value]
+pub struct MergedExportUsageInfo {
+    used_exports: FxHashMap<RcStr, ModuleExportUsageInfo>,
+}
+
+impl MergedExportUsageInfo {
+    /// Returns the exports of a source file that are used by any of its modules, by the string
+    /// representation of the path of the file. Files that are not part of the graph are treated
+    /// as if all their exports are used.
+    pub fn used_exports(&self, path: &str) -> &ModuleExportUsageInfo {
+        self.used_exports
+            .get(path)
+            .unwrap_or(&ModuleExportUsageInfo::All)
+    }
+}
+
+#[turbo_tasks::function]
+pub async fn compute_merged_export_usage_info(
+    graph: Vc<ModuleGraph>,
+) -> Result<Vc<MergedExportUsageInfo>> {
+    let export_usage_info = graph.export_usage_info().await?;
+    let graphs = graph.await?.get_graphs().await?;
+    let modules = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|node| node.module)
+        .collect::<FxIndexSet<_>>()
+        .into_iter()
+        .map(async |module| {
+            let path = module.ident().path().to_string().owned().await?;
+            Ok((path, module))
+        })
+        .try_join()
+        .await?;
+
+    let mut used_exports = FxHashMap::<_, ModuleExportUsageInfo>::default();
+    for (path, module) in modules {
+        let used = export_usage_info.used_exports(module);
+        match used_exports.entry(path) {
+            Entry::Occupied(mut e) => {
+                e.get_mut().merge(&used);
+            }
+            Entry::Vacant(e) => {
+                e.insert(used);
+            }
+        }
+    }
+
+    Ok(MergedExportUsageInfo { used_exports }.cell())
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::{FxHashMap, FxHashSet};
+    use turbo_rcstr::RcStr;
+
+    use super::{
+        merge_export_usage, ExportUsage, MergedExportUsage, ModuleExportUsageInfo,
+        PartialExportUsage, UsedExportIds,
+    };
+
+    #[derive(Default)]
+    struct Partial {
+        used_exports: FxHashMap<u32, ModuleExportUsageInfo>,
+        reexports: FxHashMap<u32, Vec<(u32, ExportUsage)>>,
+        namespace_accessed: FxHashSet<u32>,
+        default_export_properties: FxHashMap<u32, Option<FxHashSet<RcStr>>>,
+    }
+
+    fn exports(names: &[&str]) -> ModuleExportUsageInfo {
+        ModuleExportUsageInfo::Exports(names.iter().map(|name| (*name).into()).collect())
+    }
+
+    fn merge(partials: &[&Partial]) -> MergedExportUsage<u32> {
+        merge_export_usage(partials.iter().map(|partial| PartialExportUsage {
+            used_exports: &partial.used_exports,
+            reexports: &partial.reexports,
+            namespace_accessed: &partial.namespace_accessed,
+            default_export_properties: &partial.default_export_properties,
+        }))
+    }
+
+    fn used_names(merged: &MergedExportUsage<u32>, module: u32) -> Vec<&str> {
+        match &merged.used_exports[&module] {
+            UsedExportIds::Exports(ids) => ids
+                .iter()
+                .map(|id| &*merged.export_names[id as usize])
+                .collect(),
+            used => panic!("expected used exports, got {used:?}"),
+        }
+    }
+
+    #[test]
+    fn merge_order_independent() {
+        // 2 re-exports `inner` of 3 as `alpha` and everything of 4, 6 re-exports 7 as `ns`
+        let a = Partial {
+            used_exports: FxHashMap::from_iter([
+                (2, exports(&["zeta", "alpha"])),
+                (3, ModuleExportUsageInfo::Evaluation),
+            ]),
+            reexports: FxHashMap::from_iter([(
+                2,
+                vec![
+                    (
+                        3,
+                        ExportUsage::ReExport {
+                            imported: "inner".into(),
+                            exported: "alpha".into(),
+                        },
+                    ),
+                    (4, ExportUsage::ReExportAll),
+                ],
+            )]),
+            namespace_accessed: FxHashSet::from_iter([2]),
+            default_export_properties: FxHashMap::from_iter([(
+                5,
+                Some(FxHashSet::from_iter(["x".into()])),
+            )]),
+        };
+        let b = Partial {
+            used_exports: FxHashMap::from_iter([
+                (5, exports(&["default"])),
+                (2, exports(&["beta"])),
+                (4, ModuleExportUsageInfo::Evaluation),
+            ]),
+            default_export_properties: FxHashMap::from_iter([(5, None)]),
+            ..Default::default()
+        };
+        let c = Partial {
+            used_exports: FxHashMap::from_iter([(6, ModuleExportUsageInfo::All)]),
+            reexports: FxHashMap::from_iter([(
+                6,
+                vec![(7, ExportUsage::ReExportNamespace("ns".into()))],
+            )]),
+            ..Default::default()
+        };
+
+        let merged = merge(&[&a, &b, &c]);
+        for order in [
+            [&a, &c, &b],
+            [&b, &a, &c],
+            [&b, &c, &a],
+            [&c, &a, &b],
+            [&c, &b, &a],
+        ] {
+            let other = merge(&order);
+            assert_eq!(other, merged);
+            // Index sets compare equal regardless of their order
+            assert!(other.export_names.iter().eq(merged.export_names.iter()));
+        }
+
+        assert!(merged
+            .export_names
+            .iter()
+            .eq(["alpha", "beta", "default", "inner", "zeta"].iter()));
+        assert_eq!(used_names(&merged, 2), ["alpha", "beta", "zeta"]);
+        assert_eq!(used_names(&merged, 3), ["inner"]);
+        assert_eq!(used_names(&merged, 4), ["alpha", "beta", "zeta"]);
+        assert_eq!(merged.used_exports[&7], UsedExportIds::All);
+        assert_eq!(merged.star_reexported, FxHashSet::from_iter([4, 7]));
+        assert_eq!(merged.default_export_properties[&5], None);
+    }
+}
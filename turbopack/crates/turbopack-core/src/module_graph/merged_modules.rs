@@ -0,0 +1,155 @@
+use anyhow::Result;
+use rustc_hash::{FxHashMap, FxHashSet};
+use turbo_tasks::{FxIndexSet, ResolvedVc, TryFlatJoinIterExt, TryJoinIterExt, Vc};
+
+use crate::{
+    module::Module,
+    module_graph::{export_usage::ExportUsage, ModuleGraph},
+    reference::primary_chunkable_referenced_modules_with_export_usage,
+};
+
+/// Modules that are merged into the module factory of their only importer (scope hoisting), so
+/// they don't need to be registered and instantiated as separate modules at runtime.
+#[turbo_tasks::value]
+pub struct MergedModulesInfo {
+    /// The module each merged module is merged into.
+    merged_into: FxHashMap<ResolvedVc<Box<dyn Module>>, ResolvedVc<Box<dyn Module>>>,
+    /// The modules that are merged into a module, in the order they are referenced.
+    merged_modules: FxHashMap<ResolvedVc<Box<dyn Module>>, Vec<ResolvedVc<Box<dyn Module>>>>,
+}
+
+impl MergedModulesInfo {
+    /// Returns true if the module is merged into its importer and must not be placed into chunks
+    /// on its own.
+    pub fn is_merged(&self, module: ResolvedVc<Box<dyn Module>>) -> bool {
+        self.merged_into.contains_key(&module)
+    }
+
+    /// Returns the module the module is merged into.
+    pub fn merged_into(
+        &self,
+        module: ResolvedVc<Box<dyn Module>>,
+    ) -> Option<ResolvedVc<Box<dyn Module>>> {
+        self.merged_into.get(&module).copied()
+    }
+
+    /// Returns the modules that are directly merged into the module. These can have merged modules
+    /// themselves.
+    pub fn merged_modules(
+        &self,
+        module: ResolvedVc<Box<dyn Module>>,
+    ) -> &[ResolvedVc<Box<dyn Module>>] {
+        self.merged_modules
+            .get(&module)
+            .map(|modules| &modules[..])
+            .unwrap_or_default()
+    }
+}
+
+/// Finds the modules that can be merged into their importer.
+///
+/// A module is merged when all its references come from a single mergeable module and only access
+/// its exports by name, so the importer can evaluate it in place and doesn't need a namespace
+/// object that's shared with other modules. Chunk group entries, async modules and modules that
+/// are re-exported by `export *` stay separate modules.
+#[turbo_tasks::function]
+pub async fn compute_merged_modules_info(graph: Vc<ModuleGraph>) -> Result<Vc<MergedModulesInfo>> {
+    let export_usage_info = graph.export_usage_info().await?;
+    let async_modules = graph.async_module_info().await?;
+    let chunk_group_info = graph.chunk_group_info().await?;
+    let graphs = graph.await?.get_graphs().await?;
+
+    let references = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(async |node| {
+            let source = node.module;
+            let references =
+                primary_chunkable_referenced_modules_with_export_usage(*source).await?;
+            Ok((source, references))
+        })
+        .try_join()
+        .await?;
+
+    let mut importers = FxHashMap::<_, FxIndexSet<_>>::default();
+    // Modules that are referenced in a way that needs the whole namespace object
+    let mut namespace_referenced = FxHashSet::default();
+    for (source, references) in &references {
+        for (usage, targets) in references.iter() {
+            for &target in targets {
+                importers.entry(target).or_default().insert(*source);
+                if matches!(
+                    usage,
+                    ExportUsage::All
+                        | ExportUsage::ReExportNamespace(_)
+                        | ExportUsage::ReExportAll
+                        | ExportUsage::NamespaceProperties(_)
+                ) {
+                    namespace_referenced.insert(target);
+                }
+            }
+        }
+    }
+
+    let chunk_group_entries = chunk_group_info
+        .chunk_groups
+        .iter()
+        .flat_map(|chunk_group| chunk_group.entries())
+        .collect::<FxHashSet<_>>();
+
+    let candidates = importers
+        .iter()
+        .filter_map(|(&module, importers)| {
+            if importers.len() != 1 {
+                return None;
+            }
+            let importer = importers[0];
+            let mergeable = importer != module
+                && !namespace_referenced.contains(&module)
+                && !chunk_group_entries.contains(&module)
+                && !async_modules.contains(&module)
+                && !async_modules.contains(&importer)
+                && !export_usage_info.is_star_reexported(module)
+                && !export_usage_info.is_module_unused(module)
+                && !export_usage_info.are_all_exports_used(module);
+            mergeable.then_some((module, importer))
+        })
+        .collect::<Vec<_>>();
+
+    let merged_into = candidates
+        .into_iter()
+        .map(async |(module, importer)| {
+            Ok(
+                if *module.is_mergeable().await? && *importer.is_mergeable().await? {
+                    Some((module, importer))
+                } else {
+                    None
+                },
+            )
+        })
+        .try_flat_join()
+        .await?
+        .into_iter()
+        .collect::<FxHashMap<_, _>>();
+
+    // Collect the merged modules in reference order, so the generated code is deterministic
+    let mut merged_modules = FxHashMap::<_, Vec<_>>::default();
+    for (source, references) in &references {
+        for (_, targets) in references.iter() {
+            for target in targets {
+                if merged_into.get(target) == Some(source) {
+                    let modules = merged_modules.entry(*source).or_default();
+                    if !modules.contains(target) {
+                        modules.push(*target);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(MergedModulesInfo {
+        merged_into,
+        merged_modules,
+    }
+    .cell())
+}
@@ -24,8 +24,19 @@ use crate::{
     issue::Issue,
     module::Module,
     module_graph::{
-        async_module_info::{compute_async_module_info, AsyncModulesInfo},
+        async_chunk_group_options::{
+            compute_async_chunk_group_options, AsyncChunkGroupOptionsInfo,
+        },
+        async_module_info::{
+            compute_async_module_cycles, compute_async_module_info, emit_async_module_cycle_issues,
+            AsyncModulesInfo,
+        },
         chunk_group_info::{compute_chunk_group_info, ChunkGroupEntry, ChunkGroupInfo},
+        export_usage::{
+            compute_export_usage_info, compute_merged_export_usage_info, ExportUsageInfo,
+            MergedExportUsageInfo,
+        },
+        merged_modules::{compute_merged_modules_info, MergedModulesInfo},
         module_batches::{compute_module_batches, ModuleBatchesGraph},
         style_groups::{compute_style_groups, StyleGroups, StyleGroupsConfig},
         traced_di_graph::{iter_neighbors_rev, TracedDiGraph},
@@ -33,8 +44,11 @@ use crate::{
     reference::primary_chunkable_referenced_modules,
 };
 
+pub mod async_chunk_group_options;
 pub mod async_module_info;
 pub mod chunk_group_info;
+pub mod export_usage;
+pub mod merged_modules;
 pub mod module_batch;
 pub(crate) mod module_batches;
 pub(crate) mod style_groups;
@@ -767,12 +781,47 @@ impl ModuleGraph {
             let result_op = compute_async_module_info(self.to_resolved().await?);
             let result_vc = result_op.resolve_strongly_consistent().await?;
             let _issues = result_op.take_collectibles::<Box<dyn Issue>>();
+
+            // Only the cycle warnings themselves are reported, not the issues of the modules.
+            let cycles_op = compute_async_module_cycles(self.to_resolved().await?);
+            let cycles_vc = cycles_op.resolve_strongly_consistent().await?;
+            let _issues = cycles_op.take_collectibles::<Box<dyn Issue>>();
+            emit_async_module_cycle_issues(*cycles_vc).await?;
+
             anyhow::Ok(*result_vc)
         }
         .instrument(tracing::info_span!("compute async module info"))
         .await
     }
 
+    /// The options of the async chunk groups in the graph, e.g. from the magic comments of
+    /// `import()`.
+    #[turbo_tasks::function]
+    pub fn async_chunk_group_options(self: Vc<Self>) -> Vc<AsyncChunkGroupOptionsInfo> {
+        compute_async_chunk_group_options(self)
+    }
+
+    /// Which exports of each module in the graph are used, following re-exports to the modules
+    /// that declare the exports.
+    #[turbo_tasks::function]
+    pub fn export_usage_info(self: Vc<Self>) -> Vc<ExportUsageInfo> {
+        compute_export_usage_info(self)
+    }
+
+    /// Which exports of each source file are used by any of the modules that are created from it,
+    /// e.g. for the server and the client.
+    #[turbo_tasks::function]
+    pub fn merged_export_usage_info(self: Vc<Self>) -> Vc<MergedExportUsageInfo> {
+        compute_merged_export_usage_info(self)
+    }
+
+    /// Which modules are merged into the module factory of their only importer when scope
+    /// hoisting is enabled.
+    #[turbo_tasks::function]
+    pub fn merged_modules_info(self: Vc<Self>) -> Vc<MergedModulesInfo> {
+        compute_merged_modules_info(self)
+    }
+
     #[turbo_tasks::function]
     pub async fn referenced_async_modules(
         self: Vc<Self>,
@@ -10,6 +10,7 @@ use turbo_tasks::{
 use crate::{
     chunk::{ChunkableModuleReference, ChunkingType, ChunkingTypeOption},
     module::{Module, Modules},
+    module_graph::export_usage::ExportUsage,
     output::{OutputAsset, OutputAssets},
     raw_module::RawModule,
     resolve::{ModuleResolveResult, RequestKey},
@@ -309,6 +310,45 @@ pub async fn primary_chunkable_referenced_modules(
     Ok(Vc::cell(modules))
 }
 
+#[turbo_tasks::value(transparent)]
+pub struct ModulesWithExportUsage(Vec<(ExportUsage, ModulesVec)>);
+
+/// Aggregates all primary [Module]s referenced by an [Module] via [ChunkableModuleReference]s,
+/// together with the [ExportUsage] of the reference. These are the same references as in
+/// [primary_chunkable_referenced_modules].
+///
+/// [Module]: crate::module::Module
+#[turbo_tasks::function]
+pub async fn primary_chunkable_referenced_modules_with_export_usage(
+    module: Vc<Box<dyn Module>>,
+) -> Result<Vc<ModulesWithExportUsage>> {
+    let modules = module
+        .references()
+        .await?
+        .iter()
+        .map(|reference| async {
+            if let Some(reference) =
+                ResolvedVc::try_downcast::<Box<dyn ChunkableModuleReference>>(*reference)
+            {
+                if reference.chunking_type().await?.is_some() {
+                    let resolved = reference
+                        .resolve_reference()
+                        .resolve()
+                        .await?
+                        .primary_modules()
+                        .owned()
+                        .await?;
+                    let export_usage = reference.export_usage().owned().await?;
+                    return Ok(Some((export_usage, resolved)));
+                }
+            }
+            Ok(None)
+        })
+        .try_flat_join()
+        .await?;
+    Ok(Vc::cell(modules))
+}
+
 /// Walks the asset graph from multiple assets and collect all referenced
 /// assets.
 #[turbo_tasks::function]
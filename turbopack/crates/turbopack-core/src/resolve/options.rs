@@ -659,6 +659,34 @@ impl ResolveOptions {
         Ok(resolve_options.into())
     }
 
+    /// Returns a new [Vc<ResolveOptions>] with the given conditions set when resolving the
+    /// `exports` and `imports` fields of packages. Conditions prefixed with `!` are unset
+    /// instead, e.g. `!browser`.
+    #[turbo_tasks::function]
+    pub async fn with_conditions(self: Vc<Self>, conditions: Vec<RcStr>) -> Result<Vc<Self>> {
+        let mut resolve_options = self.owned().await?;
+        let apply = |resolution_conditions: &mut ResolutionConditions| {
+            for condition in &conditions {
+                if let Some(condition) = condition.strip_prefix('!') {
+                    resolution_conditions.insert(condition.into(), ConditionValue::Unset);
+                } else {
+                    resolution_conditions.insert(condition.clone(), ConditionValue::Set);
+                }
+            }
+        };
+        for into_package in resolve_options.into_package.iter_mut() {
+            if let ResolveIntoPackage::ExportsField { conditions, .. } = into_package {
+                apply(conditions);
+            }
+        }
+        for in_package in resolve_options.in_package.iter_mut() {
+            if let ResolveInPackage::ImportsField { conditions, .. } = in_package {
+                apply(conditions);
+            }
+        }
+        Ok(resolve_options.cell())
+    }
+
     /// Overrides the fully_specified flag for resolving
     #[turbo_tasks::function]
     pub async fn with_fully_specified(self: Vc<Self>, fully_specified: bool) -> Result<Vc<Self>> {
@@ -565,12 +565,17 @@ impl SourceMap {
                     })
                 });
 
+                // For index maps, only look at the section covering the position. Its source ids
+                // are local to that section, and flattening the whole index just for a lookup
+                // would re-encode every section of a (potentially huge) chunk.
+                let section = map.map.regular_map_at(line, column);
+
                 if let Token::Synthetic(SyntheticToken {
                     guessed_original_file,
                     ..
                 }) = &mut token
                 {
-                    if let DecodedMap::Regular(map) = &map.map.0 {
+                    if let Some((map, ..)) = &section {
                         if map.get_source_count() == 1 {
                             let source = map.sources().next().unwrap();
                             *guessed_original_file = Some(source.to_string());
@@ -579,8 +584,8 @@ impl SourceMap {
                 }
 
                 if need_source_content && content.is_none() {
-                    if let Some(map) = map.map.as_regular_source_map() {
-                        content = tok.and_then(|tok| {
+                    if let Some((map, line, column)) = &section {
+                        content = map.lookup_token(*line, *column).and_then(|tok| {
                             let src_id = tok.get_src_id();
 
                             let name = map.get_source(src_id);
@@ -731,6 +736,39 @@ impl CrateMapWrapper {
             _ => None,
         }
     }
+
+    /// Returns the regular map covering the given generated position, together with the
+    /// position translated into that map's coordinates.
+    ///
+    /// Unlike [CrateMapWrapper::as_regular_source_map], this never flattens an index map. It
+    /// descends into the (possibly nested) section that contains the position instead.
+    pub fn regular_map_at(&self, line: u32, column: u32) -> Option<(&RegularMap, u32, u32)> {
+        match &self.0 {
+            DecodedMap::Regular(m) => Some((m, line, column)),
+            DecodedMap::Index(m) => {
+                let pos = SourcePos { line, column };
+                // Sections are ordered by offset, so the last one starting at or before the
+                // position is the one covering it.
+                let section = m
+                    .sections()
+                    .take_while(|section| {
+                        let (line, column) = section.get_offset();
+                        SourcePos { line, column } <= pos
+                    })
+                    .last()?;
+                let (offset_line, offset_column) = section.get_offset();
+                // The section's column offset only applies to its first line.
+                let column = if line == offset_line {
+                    column - offset_column
+                } else {
+                    column
+                };
+                CrateMapWrapper::ref_cast(section.get_sourcemap()?)
+                    .regular_map_at(line - offset_line, column)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Deref for CrateMapWrapper {
@@ -32,7 +32,7 @@ pub fn add_default_ignore_list(map: &mut sourcemap::SourceMap) {
 #[derive(Serialize, Deserialize)]
 struct SourceMapSectionOffsetJson {
     line: u32,
-    offset: u32,
+    column: u32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -58,7 +58,9 @@ struct SourceMapJson {
     sources_content: Option<Vec<Option<String>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     names: Option<Vec<String>>,
-    mappings: String,
+    // Index maps (the ones with `sections`) have no `mappings` of their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mappings: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     ignore_list: Option<Vec<u32>>,
 
@@ -116,6 +118,10 @@ pub async fn resolve_source_map_sources(
     }
 
     async fn resolve_map(map: &mut SourceMapJson, origin: Vc<FileSystemPath>) -> Result<()> {
+        for section in map.sections.iter_mut().flatten() {
+            Box::pin(resolve_map(&mut section.map, origin)).await?;
+        }
+
         if let Some(sources) = &mut map.sources {
             let mut contents = if let Some(mut contents) = map.sources_content.take() {
                 contents.resize(sources.len(), None);
@@ -145,9 +151,6 @@ pub async fn resolve_source_map_sources(
     };
 
     resolve_map(&mut map, origin).await?;
-    for section in map.sections.iter_mut().flatten() {
-        resolve_map(&mut section.map, origin).await?;
-    }
 
     let map = Rope::from(serde_json::to_vec(&map)?);
     Ok(Some(map))
@@ -184,13 +187,18 @@ pub async fn fileify_source_map(
         anyhow::Ok(())
     };
 
-    for src in map.sources.iter_mut().flatten() {
-        transform_source(src).await?;
-    }
-    for section in map.sections.iter_mut().flatten() {
-        for src in section.map.sources.iter_mut().flatten() {
+    // Sections can be nested, so walk the whole tree of maps instead of only the first level.
+    let mut maps = vec![&mut map];
+    while let Some(map) = maps.pop() {
+        for src in map.sources.iter_mut().flatten() {
             transform_source(src).await?;
         }
+        maps.extend(
+            map.sections
+                .iter_mut()
+                .flatten()
+                .map(|section| &mut section.map),
+        );
     }
 
     let map = Rope::from(serde_json::to_vec(&map)?);
@@ -19,9 +19,10 @@ use turbopack_core::{
 use crate::{
     chunk::{CssChunkItem, CssChunkItemContent, CssChunkPlaceable, CssChunkType, CssImport},
     code_gen::CodeGenerateable,
+    module_asset::unused_css_module_classes,
     process::{
         finalize_css, parse_css, process_css_with_placeholder, CssWithPlaceholderResult,
-        FinalCssResult, ParseCss, ParseCssResult, ProcessCss,
+        FinalCssResult, ParseCss, ParseCssResult, ProcessCss, UnusedCssModuleClasses,
     },
     references::{
         compose::CssModuleComposeReference, import::ImportAssetReference, url::ReferencedAsset,
@@ -100,10 +101,16 @@ impl ProcessCss for CssModuleAsset {
         self: Vc<Self>,
         chunking_context: Vc<Box<dyn ChunkingContext>>,
         minify_type: MinifyType,
+        unused_classes: Vc<UnusedCssModuleClasses>,
     ) -> Vc<FinalCssResult> {
         let process_result = self.get_css_with_placeholder();
 
-        finalize_css(process_result, chunking_context, minify_type)
+        finalize_css(
+            process_result,
+            chunking_context,
+            minify_type,
+            unused_classes,
+        )
     }
 }
 
@@ -305,9 +312,20 @@ impl CssChunkItem for CssModuleChunkItem {
             }
         }
 
+        let unused_classes =
+            unused_css_module_classes(*self.module_graph, *chunking_context).await?;
+        let unused_classes = match unused_classes.get(&ResolvedVc::upcast(self.module)) {
+            Some(unused_classes) => **unused_classes,
+            None => UnusedCssModuleClasses::empty(),
+        };
+
         let result = self
             .module
-            .finalize_css(*chunking_context, self.module.await?.minify_type)
+            .finalize_css(
+                *chunking_context,
+                self.module.await?.minify_type.clone(),
+                unused_classes,
+            )
             .await?;
 
         if let FinalCssResult::Ok {
@@ -3,9 +3,10 @@ use std::{fmt::Write, sync::Arc};
 use anyhow::{bail, Context, Result};
 use indoc::formatdoc;
 use lightningcss::css_modules::CssModuleReference;
+use rustc_hash::{FxHashMap, FxHashSet};
 use swc_core::common::{BytePos, FileName, LineCol, SourceMap};
 use turbo_rcstr::RcStr;
-use turbo_tasks::{FxIndexMap, ResolvedVc, Value, ValueToString, Vc};
+use turbo_tasks::{FxIndexMap, ResolvedVc, TryFlatJoinIterExt, Value, ValueToString, Vc};
 use turbo_tasks_fs::{rope::Rope, FileSystemPath};
 use turbopack_core::{
     asset::{Asset, AssetContent},
@@ -14,7 +15,7 @@ use turbopack_core::{
     ident::AssetIdent,
     issue::{Issue, IssueExt, IssueSeverity, IssueStage, OptionStyledString, StyledString},
     module::Module,
-    module_graph::ModuleGraph,
+    module_graph::{export_usage::ModuleExportUsageInfo, ModuleGraph},
     reference::{ModuleReference, ModuleReferences},
     reference_type::{CssReferenceSubType, ReferenceType},
     resolve::{origin::ResolveOrigin, parse::Request},
@@ -31,7 +32,7 @@ use turbopack_ecmascript::{
 };
 
 use crate::{
-    process::{CssWithPlaceholderResult, ProcessCss},
+    process::{CssWithPlaceholderResult, ProcessCss, UnusedCssModuleClasses},
     references::{compose::CssModuleComposeReference, internal::InternalCssAssetReference},
 };
 
@@ -243,6 +244,98 @@ impl ModuleCssAsset {
 
         Ok(Vc::cell(references))
     }
+
+    /// Returns the original names of the local classes that are neither imported nor composed by
+    /// an imported class.
+    #[turbo_tasks::function]
+    async fn unused_classes(
+        self: ResolvedVc<Self>,
+        module_graph: Vc<ModuleGraph>,
+    ) -> Result<Vc<UnusedCssModuleClasses>> {
+        let used_exports = module_graph
+            .export_usage_info()
+            .module_used_exports(Vc::upcast(*self))
+            .await?;
+        let classes = self.classes().await?;
+
+        let used_local_names = classes
+            .iter()
+            .filter(|(export_name, _)| is_class_used(&used_exports, export_name))
+            .flat_map(|(_, class_names)| class_names)
+            .filter_map(|class_name| match class_name {
+                ModuleCssClass::Local { name } => Some(name.as_str()),
+                ModuleCssClass::Global { .. } | ModuleCssClass::Import { .. } => None,
+            })
+            .collect::<FxHashSet<_>>();
+
+        // The first class of an export is the local name of the class itself
+        let unused_classes = classes
+            .iter()
+            .filter(|(_, class_names)| match class_names.first() {
+                Some(ModuleCssClass::Local { name }) => !used_local_names.contains(name.as_str()),
+                _ => false,
+            })
+            .map(|(export_name, _)| RcStr::from(export_name.as_str()))
+            .collect();
+
+        Ok(Vc::cell(unused_classes))
+    }
+}
+
+/// Returns true if the class is part of the exports used by the importers of a CSS module. The
+/// default export is the object with all classes.
+fn is_class_used(used_exports: &ModuleExportUsageInfo, export_name: &str) -> bool {
+    used_exports.is_export_used(&"default".into())
+        || used_exports.is_export_used(&export_name.into())
+}
+
+/// The unused classes of the CSS modules in a module graph, by the module that contains their
+/// styles.
+#[turbo_tasks::value(transparent)]
+pub(crate) struct UnusedCssModuleClassesMap(
+    FxHashMap<ResolvedVc<Box<dyn Module>>, ResolvedVc<UnusedCssModuleClasses>>,
+);
+
+/// Finds the classes of the CSS modules in a module graph that are never used, so their style
+/// rules can be omitted from the emitted CSS. This is only done when simple tree shaking is
+/// enabled.
+#[turbo_tasks::function]
+pub(crate) async fn unused_css_module_classes(
+    module_graph: ResolvedVc<ModuleGraph>,
+    chunking_context: Vc<Box<dyn ChunkingContext>>,
+) -> Result<Vc<UnusedCssModuleClassesMap>> {
+    if !*chunking_context.is_simple_tree_shaking_enabled().await? {
+        return Ok(Vc::cell(Default::default()));
+    }
+
+    let graphs = module_graph.await?.get_graphs().await?;
+    let css_modules = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .filter_map(|node| ResolvedVc::try_downcast_type::<ModuleCssAsset>(node.module))
+        .collect::<FxHashSet<_>>();
+
+    let unused_classes = css_modules
+        .into_iter()
+        .map(async |module| {
+            let inner = *module
+                .inner(Value::new(ReferenceType::Css(
+                    CssReferenceSubType::Internal,
+                )))
+                .try_into_module()
+                .await?;
+            Ok(match inner {
+                Some(inner) => Some((
+                    inner,
+                    module.unused_classes(*module_graph).to_resolved().await?,
+                )),
+                None => None,
+            })
+        })
+        .try_flat_join()
+        .await?;
+
+    Ok(Vc::cell(unused_classes.into_iter().collect()))
 }
 
 #[turbo_tasks::value_impl]
@@ -322,9 +415,29 @@ impl EcmascriptChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
     async fn content(&self) -> Result<Vc<EcmascriptChunkItemContent>> {
         let classes = self.module.classes().await?;
+        let used_exports = if *self
+            .chunking_context
+            .is_simple_tree_shaking_enabled()
+            .await?
+        {
+            Some(
+                self.module_graph
+                    .export_usage_info()
+                    .module_used_exports(Vc::upcast(*self.module))
+                    .await?,
+            )
+        } else {
+            None
+        };
 
         let mut code = format!("{TURBOPACK_EXPORT_VALUE}({{\n");
         for (export_name, class_names) in &*classes {
+            if let Some(used_exports) = &used_exports {
+                if !is_class_used(used_exports, export_name) {
+                    continue;
+                }
+            }
+
             let mut exported_class_names = Vec::with_capacity(class_names.len());
 
             for class_name in class_names {
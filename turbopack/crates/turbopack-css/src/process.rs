@@ -1,15 +1,20 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    convert::Infallible,
+    sync::{Arc, RwLock},
+};
 
 use anyhow::{bail, Context, Result};
 use lightningcss::{
     css_modules::{CssModuleExport, CssModuleExports, Pattern, Segment},
+    rules::{CssRule, CssRuleList},
+    selector::Selector,
     stylesheet::{ParserOptions, PrinterOptions, StyleSheet, ToCssResult},
     targets::{Features, Targets},
     values::url::Url,
     visit_types,
-    visitor::Visit,
+    visitor::{Visit, Visitor},
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use smallvec::smallvec;
 use swc_core::base::sourcemap::SourceMapBuilder;
 use tracing::Instrument;
@@ -84,7 +89,7 @@ impl StyleSheetLike<'_, '_> {
         };
 
         let result = ss.to_css(PrinterOptions {
-            minify: matches!(minify_type, MinifyType::Minify { .. }),
+            minify: matches!(minify_type, MinifyType::Minify(_)),
             source_map: srcmap.as_mut(),
             targets,
             analyze_dependencies: None,
@@ -171,6 +176,20 @@ impl PartialEq for FinalCssResult {
     }
 }
 
+/// The original names of the local classes of a CSS module that are never used, so the style
+/// rules that require them can be removed.
+#[turbo_tasks::value(transparent)]
+#[derive(Default)]
+pub struct UnusedCssModuleClasses(FxHashSet<RcStr>);
+
+#[turbo_tasks::value_impl]
+impl UnusedCssModuleClasses {
+    #[turbo_tasks::function]
+    pub fn empty() -> Vc<Self> {
+        Vc::cell(Default::default())
+    }
+}
+
 #[turbo_tasks::function]
 pub async fn process_css_with_placeholder(
     parse_result: ResolvedVc<ParseCssResult>,
@@ -222,6 +241,7 @@ pub async fn finalize_css(
     result: Vc<CssWithPlaceholderResult>,
     chunking_context: Vc<Box<dyn ChunkingContext>>,
     minify_type: MinifyType,
+    unused_classes: Vc<UnusedCssModuleClasses>,
 ) -> Result<Vc<FinalCssResult>> {
     let result = result.await?;
     match &*result {
@@ -254,6 +274,11 @@ pub async fn finalize_css(
 
             replace_url_references(&mut stylesheet, &url_map);
 
+            let unused_classes = unused_classes.await?;
+            if !unused_classes.is_empty() {
+                remove_unused_classes(&mut stylesheet, &unused_classes);
+            }
+
             let code = code.await?;
             let code = match &*code {
                 FileContent::Content(v) => v.content().to_str()?,
@@ -286,6 +311,7 @@ pub trait ProcessCss: ParseCss {
         self: Vc<Self>,
         chunking_context: Vc<Box<dyn ChunkingContext>>,
         minify_type: MinifyType,
+        unused_classes: Vc<UnusedCssModuleClasses>,
     ) -> Result<Vc<FinalCssResult>>;
 }
 
@@ -572,6 +598,54 @@ impl lightningcss::visitor::Visitor<'_> for CssValidator {
     }
 }
 
+/// Removes the selectors that require one of the unused classes, since no element has that class.
+/// Style rules without any selector left are removed together with their nested rules.
+fn remove_unused_classes(ss: &mut StyleSheetLike<'static, 'static>, classes: &FxHashSet<RcStr>) {
+    let mut remover = UnusedClassRemover { classes };
+    ss.0.visit(&mut remover).unwrap();
+}
+
+struct UnusedClassRemover<'a> {
+    classes: &'a FxHashSet<RcStr>,
+}
+
+impl UnusedClassRemover<'_> {
+    /// Only the compound selectors are checked. Classes in pseudo classes like `:not()` or
+    /// `:is()` don't have to be present on the element.
+    fn requires_unused_class(&self, selector: &Selector) -> bool {
+        selector.iter_raw_match_order().any(|component| {
+            matches!(
+                component,
+                parcel_selectors::parser::Component::Class(class)
+                    if self.classes.contains(&*class.0)
+            )
+        })
+    }
+}
+
+impl<'i> Visitor<'i> for UnusedClassRemover<'_> {
+    type Error = Infallible;
+
+    fn visit_types(&self) -> lightningcss::visitor::VisitTypes {
+        visit_types!(RULES)
+    }
+
+    fn visit_rule_list(&mut self, rules: &mut CssRuleList<'i>) -> Result<(), Self::Error> {
+        rules.0.retain_mut(|rule| {
+            let CssRule::Style(style) = rule else {
+                return true;
+            };
+            style
+                .selectors
+                .0
+                .retain(|selector| !self.requires_unused_class(selector));
+            !style.selectors.0.is_empty()
+        });
+
+        rules.visit_children(self)
+    }
+}
+
 fn generate_css_source_map(source_map: &parcel_sourcemap::SourceMap) -> Result<Rope> {
     let mut builder = SourceMapBuilder::new(None);
 
@@ -32,11 +32,16 @@ pub struct DevHtmlEntry {
 /// The HTML entry point of the dev server.
 ///
 /// Generates an HTML page that includes the ES and CSS chunks.
+///
+/// When legacy entries are present, the page uses differential loading: the chunks of `entries`
+/// are loaded as `type="module"` scripts, which only modern browsers execute, and the chunks of
+/// `legacy_entries` are loaded as `nomodule` scripts, which only legacy browsers execute.
 #[turbo_tasks::value(shared)]
 #[derive(Clone)]
 pub struct DevHtmlAsset {
     path: ResolvedVc<FileSystemPath>,
     entries: Vec<DevHtmlEntry>,
+    legacy_entries: Vec<DevHtmlEntry>,
     body: Option<RcStr>,
 }
 
@@ -53,8 +58,12 @@ impl OutputAsset for DevHtmlAsset {
     }
 
     #[turbo_tasks::function]
-    fn references(self: Vc<Self>) -> Vc<OutputAssets> {
-        self.chunks()
+    async fn references(self: Vc<Self>) -> Result<Vc<OutputAssets>> {
+        let chunks = self.chunks().await?;
+        let legacy_chunks = self.legacy_chunks().await?;
+        Ok(Vc::cell(
+            chunks.iter().chain(legacy_chunks.iter()).copied().collect(),
+        ))
     }
 }
 
@@ -77,6 +86,7 @@ impl DevHtmlAsset {
         DevHtmlAsset {
             path,
             entries,
+            legacy_entries: vec![],
             body: None,
         }
         .cell()
@@ -91,6 +101,7 @@ impl DevHtmlAsset {
         DevHtmlAsset {
             path,
             entries,
+            legacy_entries: vec![],
             body: Some(body),
         }
         .cell()
@@ -112,6 +123,18 @@ impl DevHtmlAsset {
         html.body = Some(body);
         Ok(html.cell())
     }
+
+    /// Adds entries that are only loaded by browsers without ES module support. These are
+    /// usually the same modules as the regular entries, compiled for an older target.
+    #[turbo_tasks::function]
+    pub async fn with_legacy_entries(
+        self: Vc<Self>,
+        legacy_entries: Vec<DevHtmlEntry>,
+    ) -> Result<Vc<Self>> {
+        let mut html: DevHtmlAsset = self.owned().await?;
+        html.legacy_entries = legacy_entries;
+        Ok(html.cell())
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -120,82 +143,107 @@ impl DevHtmlAsset {
     async fn html_content(self: Vc<Self>) -> Result<Vc<DevHtmlAssetContent>> {
         let this = self.await?;
         let context_path = this.path.parent().await?;
-        let mut chunk_paths = vec![];
-        for chunk in &*self.chunks().await? {
-            let chunk_path = &*chunk.path().await?;
-            if let Some(relative_path) = context_path.get_path_to(chunk_path) {
-                chunk_paths.push(format!("/{relative_path}").into());
+        let relative_paths = async |chunks: Vc<OutputAssets>| -> Result<Vec<RcStr>> {
+            let mut chunk_paths = vec![];
+            for chunk in &*chunks.await? {
+                let chunk_path = &*chunk.path().await?;
+                if let Some(relative_path) = context_path.get_path_to(chunk_path) {
+                    chunk_paths.push(format!("/{relative_path}").into());
+                }
             }
-        }
+            Ok(chunk_paths)
+        };
 
-        Ok(DevHtmlAssetContent::new(chunk_paths, this.body.clone()))
+        Ok(DevHtmlAssetContent::new(
+            relative_paths(self.chunks()).await?,
+            relative_paths(self.legacy_chunks()).await?,
+            this.body.clone(),
+        ))
     }
 
     #[turbo_tasks::function]
     async fn chunks(&self) -> Result<Vc<OutputAssets>> {
-        let all_assets = self
-            .entries
-            .iter()
-            .map(|entry| async move {
-                let &DevHtmlEntry {
-                    chunkable_module,
-                    chunking_context,
-                    module_graph,
-                    runtime_entries,
-                } = entry;
-
-                let assets = if let Some(runtime_entries) = runtime_entries {
-                    let runtime_entries =
-                        if let Some(evaluatable) = ResolvedVc::try_downcast(chunkable_module) {
-                            runtime_entries
-                                .with_entry(*evaluatable)
-                                .to_resolved()
-                                .await?
-                        } else {
-                            runtime_entries
-                        };
-                    chunking_context.evaluated_chunk_group_assets(
-                        chunkable_module.ident(),
-                        ChunkGroup::Entry(
-                            runtime_entries
-                                .await?
-                                .iter()
-                                .map(|v| ResolvedVc::upcast(*v))
-                                .collect(),
-                        ),
-                        *module_graph,
-                        Value::new(AvailabilityInfo::Root),
-                    )
-                } else {
-                    chunking_context.root_chunk_group_assets(
-                        chunkable_module.ident(),
-                        ChunkGroup::Entry(vec![ResolvedVc::upcast(chunkable_module)]),
-                        *module_graph,
-                    )
-                };
-
-                assets.await
-            })
-            .try_join()
-            .await?
-            .iter()
-            .flatten()
-            .copied()
-            .collect();
-
-        Ok(Vc::cell(all_assets))
+        Ok(Vc::cell(entries_chunks(&self.entries).await?))
+    }
+
+    #[turbo_tasks::function]
+    async fn legacy_chunks(&self) -> Result<Vc<OutputAssets>> {
+        Ok(Vc::cell(entries_chunks(&self.legacy_entries).await?))
     }
 }
 
+async fn entries_chunks(entries: &[DevHtmlEntry]) -> Result<Vec<ResolvedVc<Box<dyn OutputAsset>>>> {
+    let all_assets = entries
+        .iter()
+        .map(|entry| async move {
+            let &DevHtmlEntry {
+                chunkable_module,
+                chunking_context,
+                module_graph,
+                runtime_entries,
+            } = entry;
+
+            let assets = if let Some(runtime_entries) = runtime_entries {
+                let runtime_entries =
+                    if let Some(evaluatable) = ResolvedVc::try_downcast(chunkable_module) {
+                        runtime_entries
+                            .with_entry(*evaluatable)
+                            .to_resolved()
+                            .await?
+                    } else {
+                        runtime_entries
+                    };
+                chunking_context.evaluated_chunk_group_assets(
+                    chunkable_module.ident(),
+                    ChunkGroup::Entry(
+                        runtime_entries
+                            .await?
+                            .iter()
+                            .map(|v| ResolvedVc::upcast(*v))
+                            .collect(),
+                    ),
+                    *module_graph,
+                    Value::new(AvailabilityInfo::Root),
+                )
+            } else {
+                chunking_context.root_chunk_group_assets(
+                    chunkable_module.ident(),
+                    ChunkGroup::Entry(vec![ResolvedVc::upcast(chunkable_module)]),
+                    *module_graph,
+                )
+            };
+
+            assets.await
+        })
+        .try_join()
+        .await?
+        .iter()
+        .flatten()
+        .copied()
+        .collect();
+
+    Ok(all_assets)
+}
+
 #[turbo_tasks::value(operation)]
 struct DevHtmlAssetContent {
     chunk_paths: Vec<RcStr>,
+    legacy_chunk_paths: Vec<RcStr>,
     body: Option<RcStr>,
 }
 
 impl DevHtmlAssetContent {
-    fn new(chunk_paths: Vec<RcStr>, body: Option<RcStr>) -> Vc<Self> {
-        DevHtmlAssetContent { chunk_paths, body }.cell()
+    fn new(
+        chunk_paths: Vec<RcStr>,
+        legacy_chunk_paths: Vec<RcStr>,
+        body: Option<RcStr>,
+    ) -> Vc<Self> {
+        DevHtmlAssetContent {
+            chunk_paths,
+            legacy_chunk_paths,
+            body,
+        }
+        .cell()
     }
 }
 
@@ -203,33 +251,11 @@ impl DevHtmlAssetContent {
 impl DevHtmlAssetContent {
     #[turbo_tasks::function]
     async fn content(&self) -> Result<Vc<AssetContent>> {
-        let mut scripts = Vec::new();
-        let mut stylesheets = Vec::new();
-
-        for relative_path in &*self.chunk_paths {
-            if relative_path.ends_with(".js") {
-                scripts.push(format!("<script src=\"{}\"></script>", relative_path));
-            } else if relative_path.ends_with(".css") {
-                stylesheets.push(format!(
-                    "<link data-turbopack rel=\"stylesheet\" href=\"{}\">",
-                    relative_path
-                ));
-            } else {
-                anyhow::bail!("chunk with unknown asset type: {}", relative_path)
-            }
-        }
-
-        let body = match &self.body {
-            Some(body) => body.as_str(),
-            None => "",
-        };
-
-        let html: RcStr = format!(
-            "<!DOCTYPE html>\n<html>\n<head>\n{}\n</head>\n<body>\n{}\n{}\n</body>\n</html>",
-            stylesheets.join("\n"),
-            body,
-            scripts.join("\n"),
-        )
+        let html: RcStr = render_html(
+            &self.chunk_paths,
+            &self.legacy_chunk_paths,
+            self.body.as_deref(),
+        )?
         .into();
 
         Ok(AssetContent::file(
@@ -257,6 +283,61 @@ impl VersionedContent for DevHtmlAssetContent {
     }
 }
 
+/// Renders the HTML page that loads the chunks. Without legacy chunks, the scripts are classic
+/// scripts, otherwise the modern ones are `type="module"` scripts and the legacy ones `nomodule`
+/// scripts.
+fn render_html(
+    chunk_paths: &[RcStr],
+    legacy_chunk_paths: &[RcStr],
+    body: Option<&str>,
+) -> Result<String> {
+    let mut scripts = Vec::new();
+    let mut stylesheets = Vec::new();
+
+    // With differential loading, modern browsers only execute `type="module"` scripts and legacy
+    // browsers only execute `nomodule` scripts.
+    let script_attribute = if legacy_chunk_paths.is_empty() {
+        ""
+    } else {
+        " type=\"module\""
+    };
+
+    for relative_path in chunk_paths {
+        if relative_path.ends_with(".js") {
+            scripts.push(format!(
+                "<script{} src=\"{}\"></script>",
+                script_attribute, relative_path
+            ));
+        } else if relative_path.ends_with(".css") {
+            stylesheets.push(format!(
+                "<link data-turbopack rel=\"stylesheet\" href=\"{}\">",
+                relative_path
+            ));
+        } else {
+            anyhow::bail!("chunk with unknown asset type: {}", relative_path)
+        }
+    }
+
+    for relative_path in legacy_chunk_paths {
+        if relative_path.ends_with(".js") {
+            scripts.push(format!(
+                "<script nomodule src=\"{}\"></script>",
+                relative_path
+            ));
+        } else if !relative_path.ends_with(".css") {
+            // Stylesheets are shared with the modern entries, so legacy ones are skipped.
+            anyhow::bail!("chunk with unknown asset type: {}", relative_path)
+        }
+    }
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n{}\n</head>\n<body>\n{}\n{}\n</body>\n</html>",
+        stylesheets.join("\n"),
+        body.unwrap_or_default(),
+        scripts.join("\n"),
+    ))
+}
+
 #[turbo_tasks::value(operation)]
 struct DevHtmlAssetVersion {
     content: ReadRef<DevHtmlAssetContent>,
@@ -270,6 +351,9 @@ impl Version for DevHtmlAssetVersion {
         for relative_path in &*self.content.chunk_paths {
             hasher.write_ref(relative_path);
         }
+        for relative_path in &*self.content.legacy_chunk_paths {
+            hasher.write_ref(relative_path);
+        }
         if let Some(body) = &self.content.body {
             hasher.write_ref(body);
         }
@@ -278,3 +362,48 @@ impl Version for DevHtmlAssetVersion {
         Vc::cell(hex_hash.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use turbo_rcstr::RcStr;
+
+    use super::render_html;
+
+    fn paths(paths: &[&str]) -> Vec<RcStr> {
+        paths.iter().map(|&path| path.into()).collect()
+    }
+
+    #[test]
+    fn classic_scripts() {
+        assert_eq!(
+            render_html(&paths(&["/main.js", "/main.css"]), &[], Some("<div></div>")).unwrap(),
+            "<!DOCTYPE html>\n<html>\n<head>\n<link data-turbopack rel=\"stylesheet\" \
+             href=\"/main.css\">\n</head>\n<body>\n<div></div>\n<script \
+             src=\"/main.js\"></script>\n</body>\n</html>"
+        );
+    }
+
+    #[test]
+    fn module_and_nomodule_scripts() {
+        assert_eq!(
+            render_html(
+                &paths(&["/main.js", "/runtime.js", "/main.css"]),
+                &paths(&["/legacy/main.js", "/legacy/runtime.js", "/legacy/main.css"]),
+                None,
+            )
+            .unwrap(),
+            "<!DOCTYPE html>\n<html>\n<head>\n<link data-turbopack rel=\"stylesheet\" \
+             href=\"/main.css\">\n</head>\n<body>\n\n<script type=\"module\" \
+             src=\"/main.js\"></script>\n<script type=\"module\" \
+             src=\"/runtime.js\"></script>\n<script nomodule \
+             src=\"/legacy/main.js\"></script>\n<script nomodule \
+             src=\"/legacy/runtime.js\"></script>\n</body>\n</html>"
+        );
+    }
+
+    #[test]
+    fn unknown_asset_type() {
+        assert!(render_html(&paths(&["/main.wasm"]), &[], None).is_err());
+        assert!(render_html(&paths(&["/main.js"]), &paths(&["/legacy/main.wasm"]), None).is_err());
+    }
+}
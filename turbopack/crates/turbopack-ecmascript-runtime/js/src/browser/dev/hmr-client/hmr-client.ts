@@ -71,6 +71,52 @@ export function connect({
       subscribeToChunkUpdate(chunkPath, sendMessage, callback);
     }
   }
+
+  forwardChunkUpdatesToWorkers(sendMessage);
+}
+
+/**
+ * Web workers can't connect to the update server. They ask the page to
+ * subscribe to their chunk lists instead, and the updates are forwarded to
+ * them.
+ */
+function forwardChunkUpdatesToWorkers(sendMessage: SendMessage) {
+  if (typeof BroadcastChannel === "undefined") {
+    return;
+  }
+
+  const channel = new BroadcastChannel("__turbopack_worker_hmr__");
+  const clientId = Math.random().toString(36).slice(2);
+  const subscribed: Set<ChunkListPath> = new Set();
+
+  channel.onmessage = ({ data }: MessageEvent<WorkerHmrMessage>) => {
+    switch (data.type) {
+      case "turbopack-worker-subscribe": {
+        const { chunkListPath } = data;
+        if (subscribed.has(chunkListPath)) {
+          return;
+        }
+        subscribed.add(chunkListPath);
+        subscribeToChunkUpdate(chunkListPath, sendMessage, (update) => {
+          channel.postMessage({
+            type: "turbopack-worker-update",
+            clientId,
+            chunkListPath,
+            update,
+          } satisfies WorkerHmrMessage);
+        });
+        break;
+      }
+      case "turbopack-worker-restart":
+        location.reload();
+        break;
+    }
+  };
+
+  // Workers that were started before the page connected subscribe again.
+  channel.postMessage({
+    type: "turbopack-worker-ready",
+  } satisfies WorkerHmrMessage);
 }
 
 type UpdateCallbackSet = {
@@ -3,6 +3,11 @@
 
 declare var augmentContext: ((context: unknown) => unknown);
 
+// Injected by rust code. The helpers of disabled features are dropped by the minifier.
+declare var RUNTIME_FEATURE_ASYNC_LOADING: boolean;
+declare var RUNTIME_FEATURE_ASYNC_MODULES: boolean;
+declare var RUNTIME_FEATURE_WASM: boolean;
+
 const moduleCache: ModuleCache<Module> = {};
 
 /**
@@ -104,7 +109,7 @@ function instantiateModule(id: ModuleId, source: SourceInfo): Module {
     moduleFactory.call(
       module.exports,
       augmentContext({
-        a: asyncModule.bind(null, module),
+        a: RUNTIME_FEATURE_ASYNC_MODULES ? asyncModule.bind(null, module) : undefined,
         e: module.exports,
         r: commonJsRequire.bind(null, module),
         t: runtimeRequire,
@@ -117,15 +122,16 @@ function instantiateModule(id: ModuleId, source: SourceInfo): Module {
         m: module,
         c: moduleCache,
         M: moduleFactories,
-        l: loadChunk.bind(null, sourceInfo),
-        L: loadChunkByUrl.bind(null, sourceInfo),
-        w: loadWebAssembly.bind(null, sourceInfo),
-        u: loadWebAssemblyModule.bind(null, sourceInfo),
+        l: RUNTIME_FEATURE_ASYNC_LOADING ? loadChunk.bind(null, sourceInfo) : undefined,
+        L: RUNTIME_FEATURE_ASYNC_LOADING ? loadChunkByUrl.bind(null, sourceInfo) : undefined,
+        h: RUNTIME_FEATURE_ASYNC_LOADING ? emitLinkHint : undefined,
+        w: RUNTIME_FEATURE_WASM ? loadWebAssembly.bind(null, sourceInfo) : undefined,
+        u: RUNTIME_FEATURE_WASM ? loadWebAssemblyModule.bind(null, sourceInfo) : undefined,
         g: globalThis,
         P: resolveAbsolutePath,
         U: relativeURL,
         R: createResolvePathFromModule(r),
-        b: getWorkerBlobURL,
+        b: RUNTIME_FEATURE_ASYNC_LOADING ? getWorkerBlobURL : undefined,
         d: typeof module.id === "string" ? module.id.replace(/(^|\/)\/+$/, "") : module.id
       })
     );
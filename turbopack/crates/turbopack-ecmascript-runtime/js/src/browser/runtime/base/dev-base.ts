@@ -37,6 +37,7 @@ type RefreshHelpers = RefreshRuntimeGlobals["$RefreshHelpers$"];
 interface TurbopackDevBaseContext extends TurbopackBaseContext<Module> {
   k: RefreshContext;
   R: ResolvePathFromModule;
+  h: EmitLinkHint;
 }
 
 interface TurbopackDevContext extends TurbopackDevBaseContext {}
@@ -216,6 +217,7 @@ function instantiateModule(id: ModuleId, source: SourceInfo): Module {
           M: moduleFactories,
           l: loadChunk.bind(null, sourceInfo),
           L: loadChunkByUrl.bind(null, sourceInfo),
+          h: emitLinkHint,
           w: loadWebAssembly.bind(null, sourceInfo),
           u: loadWebAssemblyModule.bind(null, sourceInfo),
           g: globalThis,
@@ -15,6 +15,29 @@ declare var TURBOPACK_CHUNK_UPDATE_LISTENERS:
   | ChunkUpdateProvider
   | [ChunkListPath, UpdateCallback][]
   | undefined;
+
+/**
+ * Messages on the `__turbopack_worker_hmr__` broadcast channel. Web workers
+ * can't connect to the update server, so the HMR client of the page subscribes
+ * to the chunk lists of the workers and forwards their updates.
+ */
+type WorkerHmrMessage =
+  | {
+      type: "turbopack-worker-ready";
+    }
+  | {
+      type: "turbopack-worker-subscribe";
+      chunkListPath: ChunkListPath;
+    }
+  | {
+      type: "turbopack-worker-update";
+      clientId: string;
+      chunkListPath: ChunkListPath;
+      update: ServerMessage;
+    }
+  | {
+      type: "turbopack-worker-restart";
+    };
 // This is used by the Next.js integration test suite to notify it when HMR
 // updates have been completed.
 declare var __NEXT_HMR_CB: undefined | null | (() => void);
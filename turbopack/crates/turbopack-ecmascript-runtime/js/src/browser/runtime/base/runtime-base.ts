@@ -75,6 +75,7 @@ type SourceInfo =
 interface RuntimeBackend {
   registerChunk: (chunkPath: ChunkPath, params?: RuntimeParams) => void;
   loadChunk: (chunkUrl: ChunkUrl, source: SourceInfo) => Promise<void>;
+  emitLinkHint?: (rel: LinkHintRel, chunkUrl: ChunkUrl) => void;
 }
 
 interface DevRuntimeBackend {
@@ -224,6 +225,14 @@ async function loadChunkByUrl(source: SourceInfo, chunkUrl: ChunkUrl) {
   }
 }
 
+/**
+ * Emits a `<link>` hint for a chunk of an async import, so the browser fetches
+ * it before it's loaded.
+ */
+function emitLinkHint(rel: LinkHintRel, chunkData: ChunkData) {
+  BACKEND.emitLinkHint?.(rel, getChunkRelativeUrl(getChunkPath(chunkData)));
+}
+
 async function loadChunkPath(
   source: SourceInfo,
   chunkPath: ChunkPath
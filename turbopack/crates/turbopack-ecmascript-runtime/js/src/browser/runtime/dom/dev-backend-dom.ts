@@ -13,12 +13,18 @@
 /// <reference path="../../../shared/require-type.d.ts" />
 
 let DEV_BACKEND: DevRuntimeBackend;
+let workerHmrChannel: BroadcastChannel | undefined;
 
 (() => {
   DEV_BACKEND = {
     unloadChunk(chunkUrl) {
       deleteResolver(chunkUrl);
 
+      // Web workers have no elements to remove.
+      if (typeof document === "undefined") {
+        return;
+      }
+
       // TODO(PACK-2140): remove this once all filenames are guaranteed to be escaped.
       const decodedChunkUrl = decodeURI(chunkUrl);
 
@@ -102,7 +108,15 @@ let DEV_BACKEND: DevRuntimeBackend;
       });
     },
 
-    restart: () => self.location.reload(),
+    restart: () => {
+      if (workerHmrChannel) {
+        workerHmrChannel.postMessage({
+          type: "turbopack-worker-restart",
+        } satisfies WorkerHmrMessage);
+      } else {
+        self.location.reload();
+      }
+    },
   };
 
   function deleteResolver(chunkUrl: ChunkUrl) {
@@ -110,6 +124,59 @@ let DEV_BACKEND: DevRuntimeBackend;
   }
 })();
 
+// Web workers can't connect to the update server. The HMR client of the page
+// subscribes to the chunk lists of the worker instead and forwards the updates.
+if (
+  typeof importScripts === "function" &&
+  typeof BroadcastChannel !== "undefined"
+) {
+  const channel = new BroadcastChannel("__turbopack_worker_hmr__");
+  const callbacks: Map<ChunkListPath, UpdateCallback> = new Map();
+  // Every open page forwards the updates, only the ones of a single page are
+  // applied.
+  let clientId: string | undefined;
+
+  const subscribe = (chunkListPath: ChunkListPath) => {
+    channel.postMessage({
+      type: "turbopack-worker-subscribe",
+      chunkListPath,
+    } satisfies WorkerHmrMessage);
+  };
+
+  channel.onmessage = ({ data }: MessageEvent<WorkerHmrMessage>) => {
+    switch (data.type) {
+      case "turbopack-worker-ready":
+        // A page (re)connected, it doesn't know about the chunk lists yet.
+        clientId = undefined;
+        for (const chunkListPath of callbacks.keys()) {
+          subscribe(chunkListPath);
+        }
+        break;
+      case "turbopack-worker-update":
+        clientId ??= data.clientId;
+        if (data.clientId === clientId) {
+          callbacks.get(data.chunkListPath)?.(data.update);
+        }
+        break;
+    }
+  };
+
+  const queued = globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS;
+  globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS = {
+    push: ([chunkListPath, callback]: [ChunkListPath, UpdateCallback]) => {
+      callbacks.set(chunkListPath, callback);
+      subscribe(chunkListPath);
+    },
+  };
+  if (Array.isArray(queued)) {
+    for (const registration of queued) {
+      globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS.push(registration);
+    }
+  }
+
+  workerHmrChannel = channel;
+}
+
 function _eval({ code, url, map }: EcmascriptModuleEntry): ModuleFactory {
   code += `\n\n//# sourceURL=${encodeURI(
     location.origin + CHUNK_BASE_PATH + url + CHUNK_SUFFIX_PATH
@@ -95,6 +95,34 @@ const chunkResolvers: Map<ChunkUrl, ChunkResolver> = new Map();
     loadChunk(chunkUrl, source) {
       return doLoadChunk(chunkUrl, source);
     },
+
+    /**
+     * Adds a `<link rel="prefetch">` or `<link rel="preload">` for the given
+     * chunk, unless it's already loading.
+     */
+    emitLinkHint(rel, chunkUrl) {
+      if (typeof importScripts === "function") {
+        // There is no document in web workers
+        return;
+      }
+
+      const resolver = chunkResolvers.get(chunkUrl);
+      if (resolver?.loadingStarted) {
+        return;
+      }
+
+      if (document.querySelector(`link[rel=${rel}][href="${chunkUrl}"]`)) {
+        return;
+      }
+
+      const link = document.createElement("link");
+      link.rel = rel;
+      link.href = chunkUrl;
+      if (rel === "preload") {
+        link.as = isCss(chunkUrl) ? "style" : "script";
+      }
+      document.head.appendChild(link);
+    },
   };
 
   function getOrCreateResolver(chunkUrl: ChunkUrl): ChunkResolver {
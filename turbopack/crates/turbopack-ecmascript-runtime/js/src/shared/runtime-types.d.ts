@@ -43,6 +43,8 @@ type DynamicExport = (object: Record<string, any>) => void;
 
 type LoadChunk = (chunkPath: ChunkPath) => Promise<any> | undefined;
 type LoadChunkByUrl = (chunkUrl: ChunkUrl) => Promise<any> | undefined;
+type LinkHintRel = "prefetch" | "preload";
+type EmitLinkHint = (rel: LinkHintRel, chunkData: ChunkData) => void;
 type LoadWebAssembly = (
   wasmChunkPath: ChunkPath,
   imports: WebAssembly.Imports
@@ -108,7 +108,7 @@ function ensureDynamicExports(module: Module, exports: Exports) {
           return Reflect.get(target, prop);
         }
         for (const obj of reexportedObjects!) {
-          const value = Reflect.get(obj, prop);
+          const value = Reflect.get(reexportedExports(obj), prop);
           if (value !== undefined) return value;
         }
         return undefined;
@@ -116,7 +116,7 @@ function ensureDynamicExports(module: Module, exports: Exports) {
       ownKeys(target) {
         const keys = Reflect.ownKeys(target);
         for (const obj of reexportedObjects!) {
-          for (const key of Reflect.ownKeys(obj)) {
+          for (const key of Reflect.ownKeys(reexportedExports(obj))) {
             if (key !== "default" && !keys.includes(key)) keys.push(key);
           }
         }
@@ -126,6 +126,14 @@ function ensureDynamicExports(module: Module, exports: Exports) {
   }
 }
 
+/**
+ * Async modules are imported as a promise. Like ESM bindings, their exports
+ * can be re-exported before the module finished evaluating.
+ */
+function reexportedExports(obj: Record<string | symbol, any>) {
+  return isAsyncModuleExt(obj) ? obj[turbopackExports] : obj;
+}
+
 /**
  * Dynamically exports properties from an object
  */
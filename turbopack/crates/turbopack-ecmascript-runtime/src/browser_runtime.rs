@@ -11,9 +11,13 @@ use turbopack_core::{
 };
 use turbopack_ecmascript::utils::StringifyJs;
 
-use crate::{asset_context::get_runtime_asset_context, embed_js::embed_static_code, RuntimeType};
+use crate::{
+    asset_context::get_runtime_asset_context, embed_js::embed_static_code, RuntimeFeatures,
+    RuntimeType,
+};
 
-/// Returns the code for the ECMAScript runtime.
+/// Returns the code for the ECMAScript runtime. `features` only affects production runtimes,
+/// development runtimes always support everything.
 #[turbo_tasks::function]
 pub async fn get_browser_runtime_code(
     environment: Vc<Environment>,
@@ -22,6 +26,7 @@ pub async fn get_browser_runtime_code(
     runtime_type: Value<RuntimeType>,
     output_root_to_root_path: Vc<RcStr>,
     generate_source_map: bool,
+    features: Vc<RuntimeFeatures>,
 ) -> Result<Vc<Code>> {
     let asset_context = get_runtime_asset_context(environment).await?;
 
@@ -105,6 +110,25 @@ pub async fn get_browser_runtime_code(
         StringifyJs(chunk_base_path),
     )?;
 
+    if matches!(*runtime_type, RuntimeType::Production) {
+        let RuntimeFeatures {
+            async_loading,
+            async_modules,
+            wasm,
+        } = *features.await?;
+        writedoc!(
+            code,
+            r#"
+                const RUNTIME_FEATURE_ASYNC_LOADING = {};
+                const RUNTIME_FEATURE_ASYNC_MODULES = {};
+                const RUNTIME_FEATURE_WASM = {};
+            "#,
+            async_loading,
+            async_modules,
+            wasm,
+        )?;
+    }
+
     code.push_code(&*shared_runtime_utils_code.await?);
     for runtime_code in runtime_base_code {
         code.push_code(
@@ -7,6 +7,7 @@ pub(crate) mod browser_runtime;
 pub(crate) mod dummy_runtime;
 pub(crate) mod embed_js;
 pub(crate) mod nodejs_runtime;
+pub(crate) mod runtime_features;
 pub(crate) mod runtime_type;
 
 pub use browser_runtime::get_browser_runtime_code;
@@ -14,6 +15,7 @@ pub use browser_runtime::get_browser_runtime_code;
 pub use dummy_runtime::get_dummy_runtime_code;
 pub use embed_js::{embed_file, embed_file_path, embed_fs};
 pub use nodejs_runtime::get_nodejs_runtime_code;
+pub use runtime_features::RuntimeFeatures;
 pub use runtime_type::RuntimeType;
 
 pub fn register() {
@@ -0,0 +1,28 @@
+use turbo_tasks::Vc;
+
+/// Optional parts of the browser runtime. Production runtimes only keep the helpers for enabled
+/// features, as the minifier drops the code of disabled ones.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy, Hash)]
+pub struct RuntimeFeatures {
+    /// Loading chunks on demand, e.g. for `import()` or web workers.
+    pub async_loading: bool,
+    /// Modules with top level await, or modules depending on them.
+    pub async_modules: bool,
+    /// Instantiating WebAssembly modules.
+    pub wasm: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl RuntimeFeatures {
+    /// All features, for runtimes that can't know which modules they'll load, e.g. with HMR.
+    #[turbo_tasks::function]
+    pub fn all() -> Vc<Self> {
+        RuntimeFeatures {
+            async_loading: true,
+            async_modules: true,
+            wasm: true,
+        }
+        .cell()
+    }
+}
@@ -0,0 +1,201 @@
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use swc_core::ecma::{ast::*, atoms::Atom};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{trace::TraceRawVcs, FxIndexMap, NonLocalValue};
+
+/// The value of a `const enum` member.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
+pub enum ConstEnumValue {
+    /// A finite number.
+    Number(f64),
+    String(RcStr),
+}
+
+// Numbers are always finite, so they are never NaN
+impl Eq for ConstEnumValue {}
+
+/// The members of a `const enum` with statically known values, by name.
+pub type ConstEnumMembers = FxIndexMap<RcStr, ConstEnumValue>;
+
+/// The `const enum`s exported by a module, by export name.
+#[turbo_tasks::value(transparent)]
+pub struct ExportedConstEnums(FxIndexMap<RcStr, ConstEnumMembers>);
+
+/// Collects the `const enum`s exported by a module, by export name.
+///
+/// This needs to run before the TypeScript transform, which erases the `const` modifier of the
+/// enums.
+pub fn collect_exported_const_enums(program: &Program) -> FxIndexMap<RcStr, ConstEnumMembers> {
+    let Program::Module(module) = program else {
+        return Default::default();
+    };
+
+    let mut local = FxHashMap::<Id, ConstEnumMembers>::default();
+    let mut exported = FxIndexMap::<RcStr, ConstEnumMembers>::default();
+    for item in &module.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                decl: Decl::TsEnum(decl),
+                ..
+            })) if decl.is_const => {
+                // Enum declarations with the same name are merged
+                exported
+                    .entry(decl.id.sym.as_str().into())
+                    .or_default()
+                    .extend(const_enum_members(decl));
+            }
+            ModuleItem::Stmt(Stmt::Decl(Decl::TsEnum(decl))) if decl.is_const => {
+                local
+                    .entry(decl.id.to_id())
+                    .or_default()
+                    .extend(const_enum_members(decl));
+            }
+            _ => {}
+        }
+    }
+
+    if !local.is_empty() {
+        for item in &module.body {
+            let ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+                specifiers,
+                src: None,
+                ..
+            })) = item
+            else {
+                continue;
+            };
+            for specifier in specifiers {
+                let ExportSpecifier::Named(ExportNamedSpecifier {
+                    orig: ModuleExportName::Ident(orig),
+                    exported: exported_name,
+                    ..
+                }) = specifier
+                else {
+                    continue;
+                };
+                let Some(members) = local.get(&orig.to_id()) else {
+                    continue;
+                };
+                let exported_name = match exported_name {
+                    Some(ModuleExportName::Ident(ident)) => ident.sym.as_str(),
+                    Some(ModuleExportName::Str(str)) => str.value.as_str(),
+                    None => orig.sym.as_str(),
+                };
+                exported.insert(exported_name.into(), members.clone());
+            }
+        }
+    }
+
+    exported
+}
+
+/// Computes the values of the members of a `const enum`. Members that aren't initialized with a
+/// constant expression are omitted.
+fn const_enum_members(decl: &TsEnumDecl) -> ConstEnumMembers {
+    let mut members = ConstEnumMembers::default();
+    // Members without an initializer continue the numbering of the previous member
+    let mut next = Some(0.0);
+    for member in &decl.members {
+        let name: RcStr = match &member.id {
+            TsEnumMemberId::Ident(ident) => ident.sym.as_str().into(),
+            TsEnumMemberId::Str(str) => str.value.as_str().into(),
+        };
+        let value = match &member.init {
+            Some(init) => eval_member_init(init, &decl.id.sym, &members),
+            None => next.map(ConstEnumValue::Number),
+        };
+        next = match &value {
+            Some(ConstEnumValue::Number(n)) => Some(n + 1.0),
+            _ => None,
+        };
+        if let Some(value) = value {
+            members.insert(name, value);
+        }
+    }
+    members
+}
+
+/// Evaluates the constant expression of a member initializer, which might refer to the previous
+/// members of the enum.
+fn eval_member_init(
+    expr: &Expr,
+    enum_name: &Atom,
+    members: &ConstEnumMembers,
+) -> Option<ConstEnumValue> {
+    use ConstEnumValue::{Number, String};
+
+    let value = match expr {
+        Expr::Lit(Lit::Num(num)) => Number(num.value),
+        Expr::Lit(Lit::Str(str)) => String(str.value.as_str().into()),
+        Expr::Paren(ParenExpr { expr, .. }) => return eval_member_init(expr, enum_name, members),
+        Expr::Ident(ident) => return members.get(ident.sym.as_str()).cloned(),
+        Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(prop),
+            ..
+        }) if matches!(&**obj, Expr::Ident(obj) if obj.sym == *enum_name) => {
+            return members.get(prop.sym.as_str()).cloned()
+        }
+        Expr::Unary(UnaryExpr { op, arg, .. }) => {
+            let Number(arg) = eval_member_init(arg, enum_name, members)? else {
+                return None;
+            };
+            match op {
+                UnaryOp::Minus => Number(-arg),
+                UnaryOp::Plus => Number(arg),
+                UnaryOp::Tilde => Number(!to_int32(arg) as f64),
+                _ => return None,
+            }
+        }
+        Expr::Bin(BinExpr {
+            op, left, right, ..
+        }) => {
+            let left = eval_member_init(left, enum_name, members)?;
+            let right = eval_member_init(right, enum_name, members)?;
+            match (left, right) {
+                (String(left), String(right)) if *op == BinaryOp::Add => {
+                    String(format!("{left}{right}").into())
+                }
+                (Number(left), Number(right)) => Number(match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Mod => left % right,
+                    BinaryOp::Exp => left.powf(right),
+                    BinaryOp::BitOr => (to_int32(left) | to_int32(right)) as f64,
+                    BinaryOp::BitAnd => (to_int32(left) & to_int32(right)) as f64,
+                    BinaryOp::BitXor => (to_int32(left) ^ to_int32(right)) as f64,
+                    BinaryOp::LShift => to_int32(left).wrapping_shl(to_uint32(right)) as f64,
+                    BinaryOp::RShift => to_int32(left).wrapping_shr(to_uint32(right)) as f64,
+                    BinaryOp::ZeroFillRShift => {
+                        to_uint32(left).wrapping_shr(to_uint32(right)) as f64
+                    }
+                    _ => return None,
+                }),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    match value {
+        Number(n) if !n.is_finite() => None,
+        value => Some(value),
+    }
+}
+
+/// The `ToUint32` abstract operation of JavaScript.
+fn to_uint32(n: f64) -> u32 {
+    if n.is_finite() {
+        n.trunc().rem_euclid(4294967296.0) as u32
+    } else {
+        0
+    }
+}
+
+/// The `ToInt32` abstract operation of JavaScript.
+fn to_int32(n: f64) -> i32 {
+    to_uint32(n) as i32
+}
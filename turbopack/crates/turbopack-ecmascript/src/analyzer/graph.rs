@@ -16,12 +16,12 @@ use swc_core::{
     },
 };
 use turbo_rcstr::RcStr;
-use turbo_tasks::ResolvedVc;
+use turbo_tasks::{FxIndexMap, ResolvedVc};
 use turbopack_core::source::Source;
 
 use super::{
-    is_unresolved_id, ConstantNumber, ConstantValue, ImportMap, JsValue, ObjectPart,
-    WellKnownFunctionKind,
+    const_enums::ConstEnumMembers, is_unresolved_id, ConstantNumber, ConstantValue, ImportMap,
+    JsValue, ObjectPart, WellKnownFunctionKind,
 };
 use crate::{
     analyzer::{is_unresolved, WellKnownObjectKind},
@@ -39,6 +39,14 @@ impl EffectsBlock {
     pub fn is_empty(&self) -> bool {
         self.effects.is_empty()
     }
+
+    /// Collects the ESM references of the imported bindings that are used by the effects of the
+    /// block, including the effects of nested blocks.
+    pub fn collect_imported_bindings(&self, references: &mut FxHashSet<usize>) {
+        for effect in &self.effects {
+            effect.collect_imported_bindings(references);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -74,6 +82,27 @@ pub enum ConditionalKind {
 }
 
 impl ConditionalKind {
+    /// The blocks of the conditional.
+    pub fn blocks(&self) -> Vec<&EffectsBlock> {
+        match self {
+            ConditionalKind::If { then: block }
+            | ConditionalKind::Else { r#else: block }
+            | ConditionalKind::And { expr: block }
+            | ConditionalKind::Or { expr: block }
+            | ConditionalKind::NullishCoalescing { expr: block }
+            | ConditionalKind::Labeled { body: block } => vec![&**block],
+            ConditionalKind::IfElse { then, r#else }
+            | ConditionalKind::Ternary { then, r#else } => {
+                vec![&**then, &**r#else]
+            }
+            ConditionalKind::IfElseMultiple { then, r#else } => then
+                .iter()
+                .chain(r#else.iter())
+                .map(|block| &**block)
+                .collect(),
+        }
+    }
+
     /// Normalizes all contained values.
     pub fn normalize(&mut self) {
         match self {
@@ -154,6 +183,9 @@ pub enum Effect {
         ast_path: Vec<AstParentKind>,
         span: Span,
         in_try: bool,
+        /// Whether the innermost enclosing function is `async`, or `None` at the top level of
+        /// the module.
+        in_async_function: Option<bool>,
         new: bool,
     },
     /// A function call or a new call of a property of an object.
@@ -178,6 +210,9 @@ pub enum Effect {
     ImportedBinding {
         esm_reference_index: usize,
         export: Option<RcStr>,
+        /// The property and the path of a static member access on a named import, e.g. `A` in
+        /// `E.A`. It's inlined when the binding is a `const enum`.
+        member: Option<(RcStr, Vec<AstParentKind>)>,
         ast_path: Vec<AstParentKind>,
         span: Span,
         in_try: bool,
@@ -207,6 +242,32 @@ pub enum Effect {
 }
 
 impl Effect {
+    /// Collects the ESM references of the imported bindings that are used by the effect,
+    /// including the effects of nested blocks and closures.
+    pub fn collect_imported_bindings(&self, references: &mut FxHashSet<usize>) {
+        match self {
+            Effect::ImportedBinding {
+                esm_reference_index,
+                ..
+            } => {
+                references.insert(*esm_reference_index);
+            }
+            Effect::Conditional { kind, .. } => {
+                for block in kind.blocks() {
+                    block.collect_imported_bindings(references);
+                }
+            }
+            Effect::Call { args, .. } | Effect::MemberCall { args, .. } => {
+                for arg in args {
+                    if let EffectArg::Closure(_, block) = arg {
+                        block.collect_imported_bindings(references);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Normalizes all contained values.
     pub fn normalize(&mut self) {
         match self {
@@ -304,6 +365,8 @@ pub struct EvalContext {
     pub(crate) top_level_mark: Mark,
     pub(crate) imports: ImportMap,
     pub(crate) force_free_values: Arc<FxHashSet<Id>>,
+    /// The `const enum`s exported by the module, by export name.
+    pub(crate) const_enums: Arc<FxIndexMap<RcStr, ConstEnumMembers>>,
 }
 
 impl EvalContext {
@@ -323,9 +386,20 @@ impl EvalContext {
             top_level_mark,
             imports: ImportMap::analyze(module, source, comments),
             force_free_values,
+            const_enums: Default::default(),
         }
     }
 
+    /// Sets the `const enum`s exported by the module. These have to be collected from the
+    /// program before the TypeScript transform.
+    pub(crate) fn with_const_enums(
+        mut self,
+        const_enums: Arc<FxIndexMap<RcStr, ConstEnumMembers>>,
+    ) -> Self {
+        self.const_enums = const_enums;
+        self
+    }
+
     pub fn is_esm(&self, specified_type: SpecifiedModuleType) -> bool {
         self.imports.is_esm(specified_type)
     }
@@ -805,6 +879,20 @@ pub fn is_in_try(ast_path: &AstNodePath<AstParentNodeRef<'_>>) -> bool {
         .unwrap_or(false)
 }
 
+pub fn is_in_async_function(ast_path: &AstNodePath<AstParentNodeRef<'_>>) -> Option<bool> {
+    ast_path.iter().rev().find_map(|ast_ref| match ast_ref {
+        AstParentNodeRef::ArrowExpr(arrow, ArrowExprField::Body) => Some(arrow.is_async),
+        AstParentNodeRef::Function(function, FunctionField::Body) => Some(function.is_async),
+        AstParentNodeRef::Constructor(_, ConstructorField::Body)
+        | AstParentNodeRef::GetterProp(_, GetterPropField::Body)
+        | AstParentNodeRef::SetterProp(_, SetterPropField::Body)
+        | AstParentNodeRef::StaticBlock(_, StaticBlockField::Body)
+        | AstParentNodeRef::ClassProp(_, ClassPropField::Value)
+        | AstParentNodeRef::PrivateProp(_, PrivatePropField::Value) => Some(false),
+        _ => None,
+    })
+}
+
 enum CallOrNewExpr<'ast> {
     Call(&'ast CallExpr),
     New(&'ast NewExpr),
@@ -1143,6 +1231,7 @@ impl Analyzer<'_> {
                     ast_path: as_parent_path(ast_path),
                     span,
                     in_try: is_in_try(ast_path),
+                    in_async_function: is_in_async_function(ast_path),
                     new,
                 });
             }
@@ -1177,6 +1266,7 @@ impl Analyzer<'_> {
                         ast_path: as_parent_path(ast_path),
                         span,
                         in_try: is_in_try(ast_path),
+                        in_async_function: is_in_async_function(ast_path),
                         new,
                     });
                 }
@@ -1191,6 +1281,7 @@ impl Analyzer<'_> {
                 ast_path: as_parent_path(ast_path),
                 span,
                 in_try: is_in_try(ast_path),
+                in_async_function: is_in_async_function(ast_path),
                 new,
             }),
         }
@@ -1221,6 +1312,30 @@ impl Analyzer<'_> {
         });
     }
 
+    /// Returns the property and the path of the member expression when the identifier at
+    /// `ast_path` is the object of a static member access that isn't assigned to.
+    fn imported_binding_member(
+        &self,
+        ast_path: &AstNodePath<AstParentNodeRef<'_>>,
+    ) -> Option<(RcStr, Vec<AstParentKind>)> {
+        let Some(AstParentNodeRef::MemberExpr(member, MemberExprField::Obj)) =
+            ast_path.get(ast_path.len() - 2)
+        else {
+            return None;
+        };
+        if matches!(
+            ast_path.get(ast_path.len() - 3),
+            Some(AstParentNodeRef::SimpleAssignTarget(
+                _,
+                SimpleAssignTargetField::Member
+            ))
+        ) {
+            return None;
+        }
+        let prop = self.eval_context.eval_member_prop(&member.prop)?;
+        Some((prop.as_str()?.into(), as_parent_path_skip(ast_path, 1)))
+    }
+
     fn take_return_values(&mut self) -> Box<JsValue> {
         let values = self.cur_fn_return_values.take().unwrap();
 
@@ -1886,6 +2001,7 @@ impl VisitAstPath for Analyzer<'_> {
                                 self.add_effect(Effect::ImportedBinding {
                                     esm_reference_index,
                                     export: Some(prop_str.into()),
+                                    member: None,
                                     ast_path: as_parent_path_skip(ast_path, 1),
                                     span: member.span(),
                                     in_try: is_in_try(ast_path),
@@ -1897,9 +2013,16 @@ impl VisitAstPath for Analyzer<'_> {
                 }
             }
 
+            let member = if export.is_some() {
+                self.imported_binding_member(ast_path)
+            } else {
+                None
+            };
+
             self.add_effect(Effect::ImportedBinding {
                 esm_reference_index,
                 export,
+                member,
                 ast_path: as_parent_path(ast_path),
                 span: ident.span(),
                 in_try: is_in_try(ast_path),
@@ -1,4 +1,7 @@
-use std::{collections::BTreeMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
 
 use once_cell::sync::Lazy;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -15,7 +18,10 @@ use turbo_rcstr::RcStr;
 use turbo_tasks::{FxIndexMap, FxIndexSet, ResolvedVc};
 use turbopack_core::{issue::IssueSource, source::Source};
 
-use super::{top_level_await::has_top_level_await, JsValue, ModuleValue};
+use super::{
+    property_reads::binding_property_reads, top_level_await::has_top_level_await, JsValue,
+    ModuleValue,
+};
 use crate::{
     analyzer::{ConstantValue, ObjectPart},
     tree_shake::{find_turbopack_part_id_in_asserts, PartId},
@@ -180,6 +186,10 @@ pub(crate) struct ImportMap {
     /// as a whole.
     full_star_imports: FxHashSet<Atom>,
 
+    /// The properties that are read from default imports by the index of the reference, for
+    /// default imports that are only used to read properties.
+    default_import_properties: FxHashMap<usize, BTreeSet<RcStr>>,
+
     pub(crate) exports: FxHashMap<RcStr, Id>,
 }
 
@@ -200,11 +210,35 @@ pub struct ImportAttributes {
     /// const b = import(/* turbopackIgnore: true */ "b");
     /// ```
     pub ignore: bool,
+    /// The name of the chunks that are loaded by this import expression.
+    ///
+    /// This is set by using either a `webpackChunkName` or `turbopackChunkName` comment.
+    ///
+    /// Example:
+    /// ```js
+    /// const editor = import(/* turbopackChunkName: "editor" */ "./editor");
+    /// ```
+    pub chunk_name: Option<RcStr>,
+    /// Should the chunks of this import expression be prefetched? If so, a `<link rel="prefetch">`
+    /// is emitted for them when the importing module is evaluated.
+    ///
+    /// This is set by using either a `webpackPrefetch` or `turbopackPrefetch` comment.
+    pub prefetch: bool,
+    /// Should the chunks of this import expression be preloaded? If so, a `<link rel="preload">`
+    /// is emitted for them when the importing module is evaluated.
+    ///
+    /// This is set by using either a `webpackPreload` or `turbopackPreload` comment.
+    pub preload: bool,
 }
 
 impl ImportAttributes {
     pub const fn empty() -> Self {
-        ImportAttributes { ignore: false }
+        ImportAttributes {
+            ignore: false,
+            chunk_name: None,
+            prefetch: false,
+            preload: false,
+        }
     }
 
     pub fn empty_ref() -> &'static Self {
@@ -344,9 +378,27 @@ impl ImportMap {
         };
         m.visit_with(&mut analyzer);
 
+        let default_imports = data
+            .imports
+            .iter()
+            .filter(|(_, (_, sym))| &**sym == "default")
+            .map(|(id, (i, _))| (id.clone(), *i))
+            .collect::<FxHashMap<_, _>>();
+        data.default_import_properties = binding_property_reads(m, &default_imports);
+
         data
     }
 
+    /// Returns the properties that are read from the default import of a reference, when the
+    /// default import is only used to read properties, e.g. `data.foo` after
+    /// `import data from "./data.json"`.
+    pub(crate) fn default_import_properties(
+        &self,
+        esm_reference_index: usize,
+    ) -> Option<&BTreeSet<RcStr>> {
+        self.default_import_properties.get(&esm_reference_index)
+    }
+
     pub(crate) fn should_import_all(&self, esm_reference_index: usize) -> bool {
         let r = &self.references[esm_reference_index];
 
@@ -687,6 +739,7 @@ impl Visit for Analyzer<'_> {
     /// We are checking for the following cases:
     /// - import(/* webpackIgnore: true */ "a")
     /// - require(/* webpackIgnore: true */ "a")
+    /// - import(/* webpackChunkName: "a", webpackPrefetch: true */ "a")
     ///
     /// We can do this by checking if any of the comment spans are between the
     /// callee and the first argument.
@@ -704,16 +757,10 @@ impl Visit for Analyzer<'_> {
                 _ => None,
             };
 
-            // we are interested here in the last comment with a valid directive
-            let ignore_directive = parse_ignore_directive(comments, n.args.first());
+            let attributes = parse_directives(comments, n.args.first());
 
-            if let Some((callee_span, ignore_directive)) = callee_span.zip(ignore_directive) {
-                self.data.attributes.insert(
-                    callee_span.lo,
-                    ImportAttributes {
-                        ignore: ignore_directive,
-                    },
-                );
+            if let Some((callee_span, attributes)) = callee_span.zip(attributes) {
+                self.data.attributes.insert(callee_span.lo, attributes);
             };
         }
 
@@ -728,15 +775,10 @@ impl Visit for Analyzer<'_> {
                 _ => None,
             };
 
-            let ignore_directive = parse_ignore_directive(comments, n.args.iter().flatten().next());
+            let attributes = parse_directives(comments, n.args.iter().flatten().next());
 
-            if let Some((callee_span, ignore_directive)) = callee_span.zip(ignore_directive) {
-                self.data.attributes.insert(
-                    callee_span.lo,
-                    ImportAttributes {
-                        ignore: ignore_directive,
-                    },
-                );
+            if let Some((callee_span, attributes)) = callee_span.zip(attributes) {
+                self.data.attributes.insert(callee_span.lo, attributes);
             };
         }
 
@@ -744,24 +786,55 @@ impl Visit for Analyzer<'_> {
     }
 }
 
-fn parse_ignore_directive(comments: &dyn Comments, value: Option<&ExprOrSpread>) -> Option<bool> {
-    // we are interested here in the last comment with a valid directive
-    value
-        .map(|arg| arg.span_lo())
-        .and_then(|comment_pos| comments.get_leading(comment_pos))
-        .iter()
-        .flatten()
-        .rev()
-        .filter_map(|comment| {
-            let (directive, value) = comment.text.trim().split_once(':')?;
+/// Parses the magic comments in front of the first argument. A comment can contain multiple
+/// comma-separated directives, and when a directive is repeated, the last one wins.
+fn parse_directives(
+    comments: &dyn Comments,
+    value: Option<&ExprOrSpread>,
+) -> Option<ImportAttributes> {
+    let comments = comments.get_leading(value?.span_lo())?;
+
+    let mut attributes = ImportAttributes::empty();
+    let mut found = false;
+    for comment in comments.iter() {
+        for directive in comment.text.split(',') {
+            let Some((directive, value)) = directive.trim().split_once(':') else {
+                continue;
+            };
             // support whitespace between the colon
             match (directive.trim(), value.trim()) {
-                ("webpackIgnore" | "turbopackIgnore", "true") => Some(true),
-                ("webpackIgnore" | "turbopackIgnore", "false") => Some(false),
-                _ => None, // ignore anything else
+                ("webpackIgnore" | "turbopackIgnore", "true") => attributes.ignore = true,
+                ("webpackIgnore" | "turbopackIgnore", "false") => attributes.ignore = false,
+                ("webpackPrefetch" | "turbopackPrefetch", "true") => attributes.prefetch = true,
+                ("webpackPrefetch" | "turbopackPrefetch", "false") => attributes.prefetch = false,
+                ("webpackPreload" | "turbopackPreload", "true") => attributes.preload = true,
+                ("webpackPreload" | "turbopackPreload", "false") => attributes.preload = false,
+                ("webpackChunkName" | "turbopackChunkName", name) => {
+                    let Some(name) = parse_quoted(name) else {
+                        continue;
+                    };
+                    attributes.chunk_name = Some(name.into());
+                }
+                _ => continue, // ignore anything else
             }
-        })
-        .next()
+            found = true;
+        }
+    }
+
+    found.then_some(attributes)
+}
+
+/// Strips the quotes from a string in a magic comment, e.g. `"editor"` or `'editor'`.
+fn parse_quoted(value: &str) -> Option<&str> {
+    let name = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .or_else(|| {
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+        })?;
+    (!name.is_empty()).then_some(name)
 }
 
 pub(crate) fn orig_name(n: &ModuleExportName) -> Atom {
@@ -37,10 +37,14 @@ use crate::{
 };
 
 pub mod builtin;
+pub mod const_enums;
 pub mod es_regex;
 pub mod graph;
 pub mod imports;
 pub mod linker;
+pub(crate) mod namespace_reads;
+pub(crate) mod property_reads;
+pub(crate) mod side_effects;
 pub mod top_level_await;
 pub mod well_known;
 
@@ -0,0 +1,234 @@
+use std::collections::BTreeSet;
+
+use rustc_hash::FxHashMap;
+use swc_core::{
+    common::Span,
+    ecma::{
+        ast::*,
+        visit::{Visit, VisitWith},
+    },
+};
+use turbo_rcstr::RcStr;
+
+use super::property_reads::{binding_property_reads, static_prop_name};
+
+/// Finds the `import()` calls whose namespace object is only used to read specific exports, e.g.
+/// `(await import("./module")).foo`, `const { foo } = await import("./module")`,
+/// `const m = await import("./module"); m.foo` or `import("./module").then((m) => m.foo)`.
+///
+/// Returns the read exports by the span of the call. Calls whose namespace object escapes, e.g.
+/// by being passed to a function or returned from the `then` callback, are not included.
+pub(crate) fn dynamic_import_accessed_exports(program: &Program) -> FxHashMap<Span, Vec<RcStr>> {
+    accessed_exports(
+        program,
+        NamespaceFinder {
+            namespace_call: as_awaited_import_call,
+            then_callbacks: true,
+            accessed: Default::default(),
+            bindings: Default::default(),
+        },
+    )
+}
+
+/// Finds the `require()` calls whose result is only used to read specific properties, e.g.
+/// `require("./module").foo`, `const { foo } = require("./module")` or
+/// `const m = require("./module"); m.foo`.
+///
+/// Returns the read properties by the span of the call. Calls whose result escapes are not
+/// included.
+pub(crate) fn require_accessed_exports(program: &Program) -> FxHashMap<Span, Vec<RcStr>> {
+    accessed_exports(
+        program,
+        NamespaceFinder {
+            namespace_call: as_require_call,
+            then_callbacks: false,
+            accessed: Default::default(),
+            bindings: Default::default(),
+        },
+    )
+}
+
+fn accessed_exports(program: &Program, mut finder: NamespaceFinder) -> FxHashMap<Span, Vec<RcStr>> {
+    program.visit_with(&mut finder);
+
+    let mut accessed = finder.accessed;
+    let mut reads = binding_property_reads(program, &finder.bindings);
+    // The namespace objects of escaped bindings might be used in any way
+    for span in finder.bindings.values() {
+        match reads.remove(span) {
+            Some(exports) => accessed.entry(*span).or_default().extend(exports),
+            None => {
+                accessed.remove(span);
+            }
+        }
+    }
+
+    accessed
+        .into_iter()
+        .filter(|(_, exports)| !exports.is_empty())
+        .map(|(span, exports)| (span, exports.into_iter().collect()))
+        .collect()
+}
+
+struct NamespaceFinder {
+    /// Returns the call that evaluates to a namespace object, ignoring parentheses.
+    namespace_call: fn(&Expr) -> Option<&CallExpr>,
+    /// Whether the namespace object is a Promise whose `then` callbacks can read from it.
+    then_callbacks: bool,
+    /// The exports that are read from the namespace object, by the span of the call.
+    accessed: FxHashMap<Span, BTreeSet<RcStr>>,
+    /// Bindings that hold a namespace object, with the span of the call.
+    bindings: FxHashMap<Id, Span>,
+}
+
+impl NamespaceFinder {
+    fn add_accessed(&mut self, span: Span, exports: impl IntoIterator<Item = RcStr>) {
+        if !span.is_dummy() {
+            self.accessed.entry(span).or_default().extend(exports);
+        }
+    }
+
+    /// Adds a pattern that the namespace object is assigned to.
+    fn add_pat(&mut self, span: Span, pat: &Pat) {
+        match pat {
+            Pat::Object(pat) => {
+                if let Some(exports) = object_pat_exports(pat) {
+                    self.add_accessed(span, exports);
+                }
+            }
+            Pat::Ident(ident) if !span.is_dummy() => {
+                self.bindings.insert(ident.to_id(), span);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Visit for NamespaceFinder {
+    fn visit_member_expr(&mut self, expr: &MemberExpr) {
+        // `(await import("./module")).foo` or `require("./module").foo`
+        if let (Some(call), Some(export)) = (
+            (self.namespace_call)(&expr.obj),
+            static_prop_name(&expr.prop),
+        ) {
+            self.add_accessed(call.span, [export]);
+        }
+        expr.visit_children_with(self);
+    }
+
+    fn visit_var_declarator(&mut self, decl: &VarDeclarator) {
+        // `const { foo } = await import("./module")` or `const m = require("./module")`
+        if let Some(call) = decl.init.as_deref().and_then(self.namespace_call) {
+            self.add_pat(call.span, &decl.name);
+        }
+        decl.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        // `import("./module").then((m) => m.foo)`
+        if self.then_callbacks {
+            if let Some((import_call, param)) = as_then_call(call) {
+                self.add_pat(import_call.span, param);
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+fn as_import_call(expr: &Expr) -> Option<&CallExpr> {
+    match expr {
+        Expr::Paren(ParenExpr { expr, .. }) => as_import_call(expr),
+        Expr::Call(
+            call @ CallExpr {
+                callee: Callee::Import(_),
+                ..
+            },
+        ) => Some(call),
+        _ => None,
+    }
+}
+
+fn as_awaited_import_call(expr: &Expr) -> Option<&CallExpr> {
+    match expr {
+        Expr::Paren(ParenExpr { expr, .. }) => as_awaited_import_call(expr),
+        Expr::Await(AwaitExpr { arg, .. }) => as_import_call(arg),
+        _ => None,
+    }
+}
+
+/// Matches `require()` calls with a single argument by name. Only the calls that are analyzed as
+/// calls of the actual `require` function create references, so calls of a shadowed `require`
+/// are never looked up.
+fn as_require_call(expr: &Expr) -> Option<&CallExpr> {
+    match expr {
+        Expr::Paren(ParenExpr { expr, .. }) => as_require_call(expr),
+        Expr::Call(
+            call @ CallExpr {
+                callee: Callee::Expr(callee),
+                args,
+                ..
+            },
+        ) if args.len() == 1
+            && matches!(&**callee, Expr::Ident(ident) if &*ident.sym == "require") =>
+        {
+            Some(call)
+        }
+        _ => None,
+    }
+}
+
+/// Matches `import(...).then(callback)` and returns the `import()` call and the only parameter of
+/// the callback.
+fn as_then_call(call: &CallExpr) -> Option<(&CallExpr, &Pat)> {
+    let Callee::Expr(callee) = &call.callee else {
+        return None;
+    };
+    let Expr::Member(MemberExpr {
+        obj,
+        prop: MemberProp::Ident(prop),
+        ..
+    }) = &**callee
+    else {
+        return None;
+    };
+    match (&*prop.sym, call.args.first()) {
+        (
+            "then",
+            Some(ExprOrSpread {
+                spread: None,
+                expr: callback,
+            }),
+        ) => Some((as_import_call(obj)?, single_arrow_param(callback)?)),
+        _ => None,
+    }
+}
+
+/// Returns the parameter of an arrow function with a single parameter. Other functions could
+/// access the namespace object through `arguments`.
+fn single_arrow_param(expr: &Expr) -> Option<&Pat> {
+    match expr {
+        Expr::Paren(ParenExpr { expr, .. }) => single_arrow_param(expr),
+        Expr::Arrow(ArrowExpr { params, .. }) => match &params[..] {
+            [param] => Some(param),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the exports that an object pattern reads, or `None` when it has a rest element or
+/// computed keys.
+fn object_pat_exports(pat: &ObjectPat) -> Option<Vec<RcStr>> {
+    pat.props
+        .iter()
+        .map(|prop| match prop {
+            ObjectPatProp::KeyValue(KeyValuePatProp { key, .. }) => match key {
+                PropName::Ident(ident) => Some(ident.sym.as_str().into()),
+                PropName::Str(str) => Some(str.value.as_str().into()),
+                _ => None,
+            },
+            ObjectPatProp::Assign(AssignPatProp { key, .. }) => Some(key.id.sym.as_str().into()),
+            ObjectPatProp::Rest(_) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,264 @@
+use std::{collections::BTreeSet, hash::Hash};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use swc_core::ecma::{
+    ast::*,
+    visit::{Visit, VisitWith},
+};
+use turbo_rcstr::RcStr;
+
+/// Collects the properties that are read from each of the `bindings`, e.g. `foo` for `ns.foo`,
+/// grouped by the key of the binding. Keys of bindings that are used in any other way, e.g. by
+/// being passed to a function, are not included, since any property might be read from them.
+pub(crate) fn binding_property_reads<K: Copy + Eq + Hash>(
+    program: &Program,
+    bindings: &FxHashMap<Id, K>,
+) -> FxHashMap<K, BTreeSet<RcStr>> {
+    if bindings.is_empty() {
+        return Default::default();
+    }
+    let mut reads = PropertyReads {
+        bindings,
+        reads: FxHashMap::default(),
+        escaped: FxHashSet::default(),
+    };
+    program.visit_with(&mut reads);
+    let PropertyReads {
+        mut reads, escaped, ..
+    } = reads;
+    reads.retain(|key, _| !escaped.contains(key));
+    reads
+}
+
+struct PropertyReads<'a, K> {
+    bindings: &'a FxHashMap<Id, K>,
+    reads: FxHashMap<K, BTreeSet<RcStr>>,
+    escaped: FxHashSet<K>,
+}
+
+impl<K: Copy + Eq + Hash> Visit for PropertyReads<'_, K> {
+    fn visit_member_expr(&mut self, expr: &MemberExpr) {
+        if let Expr::Ident(obj) = &*expr.obj {
+            if let Some(&key) = self.bindings.get(&obj.to_id()) {
+                match static_prop_name(&expr.prop) {
+                    Some(name) => {
+                        self.reads.entry(key).or_default().insert(name);
+                    }
+                    None => {
+                        self.escaped.insert(key);
+                    }
+                }
+                expr.prop.visit_with(self);
+                return;
+            }
+        }
+        expr.visit_children_with(self);
+    }
+
+    // Imports, declarations and reassignments of a binding don't read from it
+    fn visit_import_decl(&mut self, _: &ImportDecl) {}
+
+    fn visit_binding_ident(&mut self, _: &BindingIdent) {}
+
+    fn visit_ident(&mut self, ident: &Ident) {
+        if let Some(&key) = self.bindings.get(&ident.to_id()) {
+            self.escaped.insert(key);
+        }
+    }
+}
+
+/// Returns the name of a property that is accessed with a static name, e.g. `foo` for `obj.foo`
+/// or `obj["foo"]`.
+pub(crate) fn static_prop_name(prop: &MemberProp) -> Option<RcStr> {
+    match prop {
+        MemberProp::Ident(ident) => Some(ident.sym.as_str().into()),
+        MemberProp::Computed(ComputedPropName { expr, .. }) => match &**expr {
+            Expr::Lit(Lit::Str(str)) => Some(str.value.as_str().into()),
+            _ => None,
+        },
+        MemberProp::PrivateName(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use rustc_hash::FxHashMap;
+    use swc_core::{
+        common::{sync::Lrc, FileName, Mark, SourceMap, GLOBALS},
+        ecma::{
+            ast::{EsVersion, ImportSpecifier, ModuleDecl, ModuleItem, Program},
+            parser::parse_file_as_program,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+    };
+    use turbo_rcstr::RcStr;
+
+    use super::binding_property_reads;
+
+    /// Returns the properties read from the `data` import of the code, or `None` if the binding
+    /// escapes.
+    fn data_reads(code: &str) -> Option<BTreeSet<RcStr>> {
+        GLOBALS.set(&Default::default(), || {
+            let cm = Lrc::new(SourceMap::default());
+            let fm = cm.new_source_file(FileName::Anon.into(), code.to_string());
+            let mut program = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .unwrap();
+            program.visit_mut_with(&mut resolver(Mark::new(), Mark::new(), false));
+
+            let Program::Module(module) = &program else {
+                panic!("expected a module");
+            };
+            let mut bindings = FxHashMap::default();
+            for item in &module.body {
+                if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+                    for specifier in &import.specifiers {
+                        if let ImportSpecifier::Default(specifier) = specifier {
+                            if &*specifier.local.sym == "data" {
+                                bindings.insert(specifier.local.to_id(), ());
+                            }
+                        }
+                    }
+                }
+            }
+            assert_eq!(bindings.len(), 1, "expected a `data` default import");
+
+            binding_property_reads(&program, &bindings).remove(&())
+        })
+    }
+
+    fn names(names: &[&str]) -> Option<BTreeSet<RcStr>> {
+        Some(names.iter().map(|&name| name.into()).collect())
+    }
+
+    #[test]
+    fn static_property_reads() {
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                console.log(data.foo, data["bar"], data.foo.nested);
+                "#
+            ),
+            names(&["bar", "foo"])
+        );
+    }
+
+    #[test]
+    fn object_keys_escapes() {
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                console.log(data.foo, Object.keys(data));
+                "#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn computed_key_escapes() {
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                export function get(key) {
+                    return data[key];
+                }
+                "#
+            ),
+            None
+        );
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                console.log(data[`foo`]);
+                "#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn spread_escapes() {
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                export const copy = { ...data };
+                "#
+            ),
+            None
+        );
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                const { foo, ...rest } = data;
+                "#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn passed_binding_escapes() {
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                console.log(data.foo);
+                export default data;
+                "#
+            ),
+            None
+        );
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                send(data.foo, data);
+                "#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn shadowed_bindings_are_ignored() {
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                function format(data) {
+                    return JSON.stringify(data);
+                }
+                console.log(format(data.foo));
+                "#
+            ),
+            names(&["foo"])
+        );
+    }
+
+    #[test]
+    fn unused_binding() {
+        assert_eq!(
+            data_reads(
+                r#"
+                import data from "./data.json";
+                "#
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use swc_core::{
+    common::{comments::Comments, BytePos, Mark, Spanned, SyntaxContext},
+    ecma::{
+        ast::*,
+        utils::{ExprCtx, ExprExt},
+    },
+};
+
+/// Decides whether top level code is free of side effects. Expressions are pure when swc can prove
+/// it or when they are annotated with `/*#__PURE__*/`.
+///
+/// Needs to be created and used within `GLOBALS`.
+pub(crate) struct SideEffectsChecker<'a> {
+    comments: &'a dyn Comments,
+    expr_ctx: ExprCtx,
+}
+
+impl<'a> SideEffectsChecker<'a> {
+    pub fn new(comments: &'a dyn Comments, unresolved_mark: Mark) -> Self {
+        Self {
+            comments,
+            expr_ctx: ExprCtx {
+                unresolved_ctxt: SyntaxContext::empty().apply_mark(unresolved_mark),
+                is_unresolved_ref_safe: false,
+                in_strict: true,
+                remaining_depth: 4,
+            },
+        }
+    }
+
+    pub fn is_pure_expr(&self, e: &Expr) -> bool {
+        e.span().lo == BytePos::PURE
+            || self.comments.has_flag(e.span().lo, "PURE")
+            || !e.may_have_side_effects(self.expr_ctx)
+    }
+
+    pub fn is_pure_class(&self, c: &Class) -> bool {
+        c.super_class.as_deref().is_none_or(|e| e.is_ident())
+            && c.body.iter().all(|member| match member {
+                ClassMember::Method(ClassMethod { key, .. }) => !key.is_computed(),
+                ClassMember::ClassProp(ClassProp {
+                    key,
+                    is_static,
+                    value,
+                    ..
+                }) => {
+                    !key.is_computed()
+                        && (!is_static || value.as_deref().is_none_or(|e| self.is_pure_expr(e)))
+                }
+                ClassMember::PrivateProp(PrivateProp {
+                    is_static, value, ..
+                }) => !is_static || value.as_deref().is_none_or(|e| self.is_pure_expr(e)),
+                ClassMember::PrivateMethod(_)
+                | ClassMember::Constructor(_)
+                | ClassMember::Empty(_) => true,
+                _ => false,
+            })
+    }
+
+    pub fn is_pure_decl(&self, decl: &Decl) -> bool {
+        match decl {
+            Decl::Fn(_) | Decl::TsInterface(_) | Decl::TsTypeAlias(_) => true,
+            Decl::Class(c) => self.is_pure_class(&c.class),
+            Decl::Var(v) => v.decls.iter().all(|d| {
+                d.name.is_ident() && d.init.as_deref().is_none_or(|e| self.is_pure_expr(e))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Top level statements that only declare bindings or evaluate pure expressions. Directives
+    /// like `"use strict"` are not pure, since they change how the module is evaluated.
+    pub fn is_pure_stmt(&self, stmt: &Stmt) -> bool {
+        match stmt {
+            Stmt::Decl(decl) => self.is_pure_decl(decl),
+            Stmt::Expr(ExprStmt { expr, .. }) => {
+                !matches!(&**expr, Expr::Lit(Lit::Str(_))) && self.is_pure_expr(expr)
+            }
+            Stmt::Empty(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Imports and re-exports are never pure, since they evaluate other modules.
+    pub fn is_pure_module_item(&self, item: &ModuleItem) -> bool {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. }))
+            | ModuleItem::Stmt(Stmt::Decl(decl)) => self.is_pure_decl(decl),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport { src: None, .. })) => true,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                decl,
+                ..
+            })) => match decl {
+                DefaultDecl::Class(c) => self.is_pure_class(&c.class),
+                DefaultDecl::Fn(_) | DefaultDecl::TsInterfaceDecl(_) => true,
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(ExportDefaultExpr {
+                expr,
+                ..
+            }))
+            | ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) => self.is_pure_expr(expr),
+            ModuleItem::Stmt(Stmt::Empty(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Detects whether evaluating a module has no side effects. That's the case when it has no imports
+/// or re-exports and its top level only declares functions, classes and variables whose
+/// initializers are free of side effects.
+pub(crate) fn has_pure_top_level(
+    p: &Program,
+    comments: &dyn Comments,
+    unresolved_mark: Mark,
+) -> bool {
+    let Program::Module(m) = p else {
+        // CommonJs modules assign their exports
+        return false;
+    };
+    let checker = SideEffectsChecker::new(comments, unresolved_mark);
+    m.body.iter().all(|item| checker.is_pure_module_item(item))
+}
@@ -7,6 +7,7 @@ use turbopack_core::{
         ChunkData, ChunkItem, ChunkType, ChunkingContext, ChunkingContextExt, ChunksData,
         ModuleChunkItemIdExt,
     },
+    environment::ChunkLoading,
     ident::AssetIdent,
     module::Module,
     module_graph::{
@@ -21,7 +22,7 @@ use crate::{
         data::EcmascriptChunkData, EcmascriptChunkItem, EcmascriptChunkItemContent,
         EcmascriptChunkPlaceable, EcmascriptChunkType,
     },
-    runtime_functions::{TURBOPACK_EXPORT_VALUE, TURBOPACK_LOAD},
+    runtime_functions::{TURBOPACK_EXPORT_VALUE, TURBOPACK_LINK_HINT, TURBOPACK_LOAD},
     utils::{StringifyJs, StringifyModuleId},
 };
 
@@ -60,6 +61,28 @@ impl AsyncLoaderChunkItem {
         ))
     }
 
+    /// The kinds of `<link>` hints to emit for the chunks, from the magic comments of the imports
+    /// of the module. They are only supported when chunks are loaded in the browser.
+    #[turbo_tasks::function]
+    async fn link_hints(&self) -> Result<Vc<Vec<RcStr>>> {
+        let chunk_loading = self.chunking_context.environment().chunk_loading().await?;
+        if !matches!(*chunk_loading, ChunkLoading::Dom) {
+            return Ok(Vc::cell(vec![]));
+        }
+        let options = self.module_graph.async_chunk_group_options().await?;
+        let inner_module = ResolvedVc::upcast(self.module.await?.inner);
+        let mut hints = vec![];
+        if let Some(options) = options.get(&inner_module) {
+            if options.prefetch {
+                hints.push("prefetch".into());
+            }
+            if options.preload {
+                hints.push("preload".into());
+            }
+        }
+        Ok(Vc::cell(hints))
+    }
+
     #[turbo_tasks::function]
     async fn chunks_data(self: Vc<Self>) -> Result<Vc<ChunksData>> {
         let this = self.await?;
@@ -97,7 +120,7 @@ impl EcmascriptChunkItem for AsyncLoaderChunkItem {
             .map(|chunk_data| EcmascriptChunkData::new(chunk_data))
             .collect();
 
-        let code = match (id, chunks_data.is_empty()) {
+        let mut code = match (id, chunks_data.is_empty()) {
             (Some(id), true) => {
                 formatdoc! {
                     r#"
@@ -144,6 +167,19 @@ impl EcmascriptChunkItem for AsyncLoaderChunkItem {
             }
         };
 
+        if !chunks_data.is_empty() {
+            let hints = self.link_hints().await?;
+            for rel in hints.iter() {
+                code = formatdoc! {
+                    r#"
+                        {chunks:#}.forEach((chunk) => {TURBOPACK_LINK_HINT}({rel}, chunk));
+                        {code}"#,
+                    chunks = StringifyJs(&chunks_data),
+                    rel = StringifyJs(rel),
+                };
+            }
+        }
+
         Ok(EcmascriptChunkItemContent {
             inner_code: code.into(),
             ..Default::default()
@@ -15,7 +15,10 @@ use turbopack_core::{
 };
 
 use crate::{
-    references::async_module::{AsyncModuleOptions, OptionAsyncModuleOptions},
+    references::{
+        async_module::{AsyncModuleOptions, OptionAsyncModuleOptions},
+        esm::{scope_hoisting::merged_exports_ident, MergedModuleContent},
+    },
     utils::FormatIter,
     EcmascriptModuleContent, EcmascriptOptions,
 };
@@ -27,6 +30,9 @@ pub struct EcmascriptChunkItemContent {
     pub source_map: Option<Rope>,
     pub options: EcmascriptChunkItemOptions,
     pub rewrite_source_path: Option<ResolvedVc<FileSystemPath>>,
+    /// The modules that are merged into this module (scope hoisting). The functions that evaluate
+    /// them are declared next to the module factory.
+    pub merged_modules: Vec<MergedModuleContent>,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -56,6 +62,7 @@ impl EcmascriptChunkItemContent {
             },
             inner_code: content.inner_code.clone(),
             source_map: content.source_map.clone(),
+            merged_modules: content.merged_modules.clone(),
             options: if content.is_esm {
                 EcmascriptChunkItemOptions {
                     strict: true,
@@ -110,6 +117,30 @@ impl EcmascriptChunkItemContent {
         }
         let mut code = CodeBuilder::default();
         let args = FormatIter(|| args.iter().copied().intersperse(", "));
+
+        // Merged modules are evaluated by functions that are declared in a scope around the module
+        // factory, so they can't access the top level bindings of the module
+        if !self.merged_modules.is_empty() {
+            code += "(() => {\n";
+            for merged_module in &self.merged_modules {
+                let content = merged_module.content.await?;
+                writeln!(
+                    code,
+                    "function {}(__turbopack_context__) {{\n\"use strict\";\n\nvar {{ g: global, \
+                     __dirname }} = __turbopack_context__;\n{{",
+                    merged_module.name
+                )?;
+                let source_map = if let Some(rewrite_source_path) = self.rewrite_source_path {
+                    fileify_source_map(content.source_map.as_ref(), *rewrite_source_path).await?
+                } else {
+                    content.source_map.clone()
+                };
+                code.push_source(&content.inner_code, source_map);
+                writeln!(code, "}}\nreturn {};\n}}", merged_exports_ident())?;
+            }
+            code += "return ";
+        }
+
         if self.options.this {
             code += "(function(__turbopack_context__) {\n";
         } else {
@@ -149,6 +180,9 @@ impl EcmascriptChunkItemContent {
         }
 
         code += "})";
+        if !self.merged_modules.is_empty() {
+            code += ";\n})()";
+        }
         Ok(code.build().cell())
     }
 }
@@ -101,6 +101,7 @@ pub enum CodeGen {
     // AMD occurs very rarely and makes the enum much bigger
     AmdDefineWithDependenciesCodeGen(Box<AmdDefineWithDependenciesCodeGen>),
     CjsRequireCacheAccess(CjsRequireCacheAccess),
+    ConstEnumMemberCodeGen(ConstEnumMemberCodeGen),
     ConstantConditionCodeGen(ConstantConditionCodeGen),
     ConstantValueCodeGen(ConstantValueCodeGen),
     DynamicExpression(DynamicExpression),
@@ -129,6 +130,7 @@ impl CodeGen {
         match self {
             Self::AmdDefineWithDependenciesCodeGen(v) => v.code_generation(g, ctx).await,
             Self::CjsRequireCacheAccess(v) => v.code_generation(g, ctx).await,
+            Self::ConstEnumMemberCodeGen(v) => v.code_generation(g, ctx).await,
             Self::ConstantConditionCodeGen(v) => v.code_generation(g, ctx).await,
             Self::ConstantValueCodeGen(v) => v.code_generation(g, ctx).await,
             Self::DynamicExpression(v) => v.code_generation(g, ctx).await,
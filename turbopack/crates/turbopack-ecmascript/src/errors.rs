@@ -19,3 +19,9 @@ pub mod failed_to_analyse {
         pub const NEW_WORKER: &str = "TP1203";
     }
 }
+
+pub mod interop {
+    pub mod ecmascript {
+        pub const REQUIRE_ASYNC_MODULE: &str = "TP1300";
+    }
+}
@@ -0,0 +1,310 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use swc_core::{
+    base::try_with_handler,
+    common::{
+        comments::{Comments, SingleThreadedComments},
+        FileName, FilePathMapping, Mark, SourceMap as SwcSourceMap, SyntaxContext, DUMMY_SP,
+        GLOBALS,
+    },
+    ecma::{
+        ast::{EsVersion, Expr, Ident, UnaryExpr, UnaryOp},
+        parser::{lexer::Lexer, Parser, StringInput, Syntax},
+        preset_env,
+        transforms::base::{
+            assumptions::Assumptions,
+            feature::FeatureFlag,
+            fixer::fixer,
+            helpers::{inject_helpers, Helpers, HELPERS},
+            hygiene::hygiene,
+            resolver,
+        },
+        visit::{VisitMut, VisitMutWith},
+    },
+    quote,
+};
+use tracing::{instrument, Level};
+use turbopack_core::code_builder::{Code, CodeBuilder};
+
+use crate::{minify::print_program, parse::generate_js_source_map};
+
+/// Compiles the code of a whole chunk to ES5 for legacy `nomodule` output.
+///
+/// The modules of legacy chunks are already compiled to ES5 (see
+/// [crate::EcmascriptInputTransform::PresetEnvEs5]), but the chunk wrappers, module factories and
+/// the runtime are generated as modern code. Helpers are inlined, as chunks are classic scripts.
+/// References to `globalThis`, which legacy browsers don't have, are replaced by a fallback to
+/// `self` or `window`.
+#[instrument(level = Level::INFO, skip_all)]
+pub fn compile_to_es5(code: &Code, source_maps: bool) -> Result<Code> {
+    let source_maps = source_maps
+        .then(|| code.generate_source_map_ref())
+        .transpose()?;
+
+    let cm = Arc::new(SwcSourceMap::new(FilePathMapping::empty()));
+    let (src, mut src_map_buf) = {
+        let fm = cm.new_source_file(
+            FileName::Anon.into(),
+            code.source_code().to_str()?.into_owned(),
+        );
+
+        let lexer = Lexer::new(
+            Syntax::default(),
+            EsVersion::latest(),
+            StringInput::from(&*fm),
+            None,
+        );
+        let mut parser = Parser::new_from(lexer);
+
+        let program = try_with_handler(cm.clone(), Default::default(), |handler| {
+            GLOBALS.set(&Default::default(), || {
+                HELPERS.set(&Helpers::new(false), || {
+                    let program = match parser.parse_program() {
+                        Ok(program) => program,
+                        Err(err) => {
+                            err.into_diagnostic(handler).emit();
+                            bail!(
+                                "failed to parse source code\n{}",
+                                code.source_code().to_str()?
+                            )
+                        }
+                    };
+                    let comments = SingleThreadedComments::default();
+                    let unresolved_mark = Mark::new();
+                    let top_level_mark = Mark::new();
+
+                    let mut program = program.apply((
+                        resolver(unresolved_mark, top_level_mark, false),
+                        preset_env::preset_env::<&'_ dyn Comments>(
+                            top_level_mark,
+                            Some(&comments),
+                            preset_env::Config {
+                                force_all_transforms: true,
+                                mode: None, // Don't insert core-js polyfills
+                                ..Default::default()
+                            },
+                            Assumptions::default(),
+                            &mut FeatureFlag::empty(),
+                        ),
+                        inject_helpers(unresolved_mark),
+                    ));
+                    program.visit_mut_with(&mut GlobalThisFallback {
+                        unresolved_ctxt: SyntaxContext::empty().apply_mark(unresolved_mark),
+                    });
+
+                    Ok(program.apply((hygiene(), fixer(Some(&comments as &dyn Comments)))))
+                })
+            })
+        })
+        .map_err(|e| e.to_pretty_error())?;
+
+        print_program(cm.clone(), program, source_maps.is_some(), false)?
+    };
+
+    let mut builder = CodeBuilder::new(source_maps.is_some());
+    if let Some(original_map) = source_maps.as_ref() {
+        src_map_buf.shrink_to_fit();
+        builder.push_source(
+            &src.into(),
+            Some(generate_js_source_map(cm, src_map_buf, Some(original_map))?),
+        );
+    } else {
+        builder.push_source(&src.into(), None);
+    }
+    Ok(builder.build())
+}
+
+/// Replaces references to the global `globalThis` with
+/// `typeof globalThis !== "undefined" ? globalThis : typeof self !== "undefined" ? self : window`.
+/// `typeof globalThis` is left as is, as it doesn't throw.
+struct GlobalThisFallback {
+    unresolved_ctxt: SyntaxContext,
+}
+
+impl GlobalThisFallback {
+    fn is_global_this(&self, expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Ident(Ident { sym, ctxt, .. })
+                if &**sym == "globalThis" && *ctxt == self.unresolved_ctxt
+        )
+    }
+}
+
+impl VisitMut for GlobalThisFallback {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Unary(UnaryExpr {
+                op: UnaryOp::TypeOf,
+                arg,
+                ..
+            }) if self.is_global_this(arg) => {}
+            _ if self.is_global_this(expr) => {
+                let global = |sym: &str| Ident::new(sym.into(), DUMMY_SP, self.unresolved_ctxt);
+                *expr = quote!(
+                    "(typeof $global_this !== \"undefined\" ? $global_this : typeof $self_ !== \
+                     \"undefined\" ? $self_ : $window)" as Expr,
+                    global_this: Ident = global("globalThis"),
+                    self_: Ident = global("self"),
+                    window: Ident = global("window"),
+                );
+            }
+            _ => expr.visit_mut_children_with(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, FilePathMapping, SourceMap as SwcSourceMap},
+        ecma::{
+            ast::{
+                ArrowExpr, Class, EsVersion, Expr, Ident, Tpl, UnaryExpr, UnaryOp, VarDecl,
+                VarDeclKind,
+            },
+            parser::{lexer::Lexer, Parser, StringInput, Syntax},
+            visit::{Visit, VisitWith},
+        },
+    };
+    use turbopack_core::code_builder::CodeBuilder;
+
+    use super::compile_to_es5;
+
+    /// Collects the syntax that ES5 doesn't support, and references to `globalThis` that are not
+    /// guarded by `typeof`.
+    #[derive(Default)]
+    struct Es5Violations(Vec<&'static str>);
+
+    impl Visit for Es5Violations {
+        fn visit_arrow_expr(&mut self, node: &ArrowExpr) {
+            self.0.push("arrow function");
+            node.visit_children_with(self);
+        }
+
+        fn visit_class(&mut self, node: &Class) {
+            self.0.push("class");
+            node.visit_children_with(self);
+        }
+
+        fn visit_var_decl(&mut self, node: &VarDecl) {
+            if node.kind != VarDeclKind::Var {
+                self.0.push("let or const");
+            }
+            node.visit_children_with(self);
+        }
+
+        fn visit_tpl(&mut self, node: &Tpl) {
+            self.0.push("template literal");
+            node.visit_children_with(self);
+        }
+
+        fn visit_expr(&mut self, node: &Expr) {
+            match node {
+                Expr::Unary(UnaryExpr {
+                    op: UnaryOp::TypeOf,
+                    arg,
+                    ..
+                }) if matches!(&**arg, Expr::Ident(Ident { sym, .. }) if &**sym == "globalThis") => {
+                }
+                Expr::Ident(Ident { sym, .. }) if &**sym == "globalThis" => {
+                    self.0.push("globalThis");
+                }
+                _ => node.visit_children_with(self),
+            }
+        }
+    }
+
+    fn es5_violations(code: &str) -> Vec<&'static str> {
+        let cm = SwcSourceMap::new(FilePathMapping::empty());
+        let fm = cm.new_source_file(FileName::Anon.into(), code.to_string());
+        let lexer = Lexer::new(
+            Syntax::default(),
+            EsVersion::Es5,
+            StringInput::from(&*fm),
+            None,
+        );
+        let program = Parser::new_from(lexer)
+            .parse_script()
+            .unwrap_or_else(|err| panic!("{err:?} in\n{code}"));
+        let mut violations = Es5Violations::default();
+        program.visit_with(&mut violations);
+        violations.0
+    }
+
+    fn compile(code: &str) -> String {
+        let mut builder = CodeBuilder::new(false);
+        builder.push_source(&code.into(), None);
+        let code = compile_to_es5(&builder.build(), false).unwrap();
+        code.source_code().to_str().unwrap().into_owned()
+    }
+
+    #[test]
+    fn chunk() {
+        let chunk = r#"
+            (globalThis.TURBOPACK = globalThis.TURBOPACK || []).push(["output/chunk.js", {
+            "[project]/input/index.js [client] (ecmascript)": ((__turbopack_context__) => {
+            "use strict";
+            var { r: __turbopack_require__, e: exports } = __turbopack_context__;
+            {
+            const value = __turbopack_require__("[project]/input/value.js [client] (ecmascript)");
+            exports.value = `value: ${value}`;
+            }}),
+            }]);
+        "#;
+        assert!(!es5_violations(chunk).is_empty());
+
+        let compiled = compile(chunk);
+        assert_eq!(es5_violations(&compiled), Vec::<&str>::new(), "{compiled}");
+        assert!(compiled.contains(
+            "typeof globalThis !== \"undefined\" ? globalThis : typeof self !== \"undefined\" ? \
+             self : window"
+        ));
+    }
+
+    #[test]
+    fn runtime() {
+        let runtime = r#"
+            (() => {
+            if (!Array.isArray(globalThis.TURBOPACK)) {
+                return;
+            }
+            class Registry {
+                chunks = new Map();
+                async load(chunk) {
+                    await Promise.resolve();
+                    this.chunks.set(chunk, true);
+                }
+            }
+            globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS ??= [];
+            const chunksToRegister = globalThis.TURBOPACK;
+            globalThis.TURBOPACK = { push: (chunk) => new Registry().load(chunk) };
+            chunksToRegister.forEach((chunk) => globalThis.TURBOPACK.push(chunk));
+            })();
+        "#;
+        assert!(!es5_violations(runtime).is_empty());
+
+        let compiled = compile(runtime);
+        assert_eq!(es5_violations(&compiled), Vec::<&str>::new(), "{compiled}");
+    }
+
+    #[test]
+    fn typeof_global_this() {
+        let compiled =
+            compile("var global = typeof globalThis === \"object\" ? globalThis : undefined;");
+        assert_eq!(es5_violations(&compiled), Vec::<&str>::new(), "{compiled}");
+        assert!(compiled.contains("typeof globalThis === \"object\""));
+        assert_eq!(
+            compiled.matches("typeof globalThis").count(),
+            2,
+            "{compiled}"
+        );
+    }
+
+    #[test]
+    fn local_global_this() {
+        let compiled = compile("(function (globalThis) { globalThis.value = 1; })(self);");
+        assert!(!compiled.contains("typeof"), "{compiled}");
+    }
+}
@@ -14,11 +14,13 @@ pub mod async_chunk;
 pub mod chunk;
 pub mod code_gen;
 mod errors;
+pub mod es5;
 pub mod magic_identifier;
 pub mod manifest;
 pub mod minify;
 pub mod parse;
 mod path_visitor;
+pub mod property_mangling;
 pub mod references;
 pub mod runtime_functions;
 pub mod side_effect_optimization;
@@ -29,6 +31,7 @@ pub mod text;
 pub(crate) mod transform;
 pub mod tree_shake;
 pub mod typescript;
+pub mod unused_exports;
 pub mod utils;
 pub mod webpack;
 pub mod worker_chunk;
@@ -92,11 +95,18 @@ pub use turbopack_resolve::ecmascript as resolve;
 
 use self::chunk::{EcmascriptChunkItemContent, EcmascriptChunkType, EcmascriptExports};
 use crate::{
+    analyzer::const_enums::ExportedConstEnums,
     chunk::{placeable::is_marked_as_side_effect_free, EcmascriptChunkPlaceable},
     code_gen::CodeGens,
     parse::generate_js_source_map,
+    property_mangling::property_mangling_code_generation,
     references::{
-        analyse_ecmascript_module, async_module::OptionAsyncModule, esm::base::EsmAssetReferences,
+        analyse_ecmascript_module,
+        async_module::OptionAsyncModule,
+        esm::{
+            base::EsmAssetReferences, export_mangling::export_mangling,
+            scope_hoisting::merged_module_contents, MergedModuleContent,
+        },
     },
     transform::remove_shebang,
 };
@@ -161,6 +171,9 @@ pub struct EcmascriptOptions {
     /// parsing fails. This is useful to keep the module graph structure intact when syntax errors
     /// are temporarily introduced.
     pub keep_last_successful_parse: bool,
+    /// If true, exports that are not used anywhere in the module graph are still emitted when
+    /// simple tree shaking is enabled. This is useful for debugging.
+    pub keep_unused_exports: bool,
 }
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -429,6 +442,7 @@ impl EcmascriptAnalyzable for EcmascriptModuleAsset {
 
         Ok(EcmascriptModuleContent::new(
             EcmascriptModuleContentOptions {
+                module: ResolvedVc::upcast(self.to_resolved().await?),
                 parsed,
                 ident: self.ident().to_resolved().await?,
                 specified_module_type: module_type_result.module_type,
@@ -442,6 +456,7 @@ impl EcmascriptAnalyzable for EcmascriptModuleAsset {
                 original_source_map: analyze_ref.source_map,
                 exports: analyze_ref.exports,
                 async_module_info,
+                keep_unused_exports: self.await?.options.await?.keep_unused_exports,
             },
         ))
     }
@@ -554,6 +569,17 @@ impl EcmascriptModuleAsset {
     pub fn parse(&self) -> Vc<ParseResult> {
         parse(*self.source, Value::new(self.ty), *self.transforms)
     }
+
+    /// The `const enum`s exported by the module, whose members are inlined into the importing
+    /// modules.
+    #[turbo_tasks::function]
+    pub async fn exported_const_enums(self: Vc<Self>) -> Result<Vc<ExportedConstEnums>> {
+        let parsed = self.failsafe_parse().await?;
+        let ParseResult::Ok { eval_context, .. } = &*parsed else {
+            return Ok(Vc::cell(Default::default()));
+        };
+        Ok(Vc::cell((*eval_context.const_enums).clone()))
+    }
 }
 
 impl EcmascriptModuleAsset {
@@ -620,6 +646,30 @@ impl Module for EcmascriptModuleAsset {
             Ok(Vc::cell(false))
         }
     }
+
+    #[turbo_tasks::function]
+    async fn is_side_effect_free(self: Vc<Self>) -> Result<Vc<bool>> {
+        let side_effect_free_packages = self.asset_context().side_effect_free_packages();
+        if *self
+            .is_marked_as_side_effect_free(side_effect_free_packages)
+            .await?
+        {
+            return Ok(Vc::cell(true));
+        }
+        Ok(Vc::cell(self.analyze().await?.has_pure_top_level))
+    }
+
+    #[turbo_tasks::function]
+    async fn is_mergeable(self: Vc<Self>) -> Result<Vc<bool>> {
+        // The importer accesses the exports of a merged module by name, so they need to be known
+        // statically
+        let EcmascriptExports::EsmExports(exports) = *self.analyze().await?.exports.await? else {
+            return Ok(Vc::cell(false));
+        };
+        Ok(Vc::cell(
+            exports.expand_exports().await?.dynamic_exports.is_empty(),
+        ))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -781,10 +831,13 @@ pub struct EcmascriptModuleContent {
     pub source_map: Option<Rope>,
     pub is_esm: bool,
     // pub refresh: bool,
+    /// The modules that are merged into this module (scope hoisting).
+    pub merged_modules: Vec<MergedModuleContent>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TaskInput, TraceRawVcs)]
 pub struct EcmascriptModuleContentOptions {
+    module: ResolvedVc<Box<dyn Module>>,
     parsed: ResolvedVc<ParseResult>,
     ident: ResolvedVc<AssetIdent>,
     specified_module_type: SpecifiedModuleType,
@@ -798,6 +851,7 @@ pub struct EcmascriptModuleContentOptions {
     original_source_map: ResolvedVc<OptionStringifiedSourceMap>,
     exports: ResolvedVc<EcmascriptExports>,
     async_module_info: Option<ResolvedVc<AsyncModuleInfo>>,
+    keep_unused_exports: bool,
 }
 
 #[turbo_tasks::value_impl]
@@ -806,6 +860,7 @@ impl EcmascriptModuleContent {
     #[turbo_tasks::function]
     pub async fn new(input: EcmascriptModuleContentOptions) -> Result<Vc<Self>> {
         let EcmascriptModuleContentOptions {
+            module,
             parsed,
             ident,
             specified_module_type,
@@ -819,38 +874,80 @@ impl EcmascriptModuleContent {
             original_source_map,
             exports,
             async_module_info,
+            keep_unused_exports,
         } = input;
 
+        // Whether this module is merged into its importer, so its exports are returned to the
+        // importer instead of being defined on the module
+        let merged = *chunking_context.is_scope_hoisting_enabled().await?
+            && module_graph.merged_modules_info().await?.is_merged(module);
+
         let (esm_code_gens, additional_code_gens, code_gens) = async {
             let additional_code_gens = [
-                if let Some(async_module) = &*async_module.await? {
+                // The exports, including star re-exports, are bound before the async dependencies
+                // are awaited. Like ESM bindings, they are available to modules in a cycle while
+                // this module is still evaluating.
+                if let EcmascriptExports::EsmExports(exports) = *exports.await? {
+                    let used_exports = if !keep_unused_exports
+                        && *chunking_context.is_simple_tree_shaking_enabled().await?
+                    {
+                        Some(
+                            module_graph
+                                .export_usage_info()
+                                .module_used_exports(*module)
+                                .await?,
+                        )
+                    } else {
+                        None
+                    };
+                    let export_mangling =
+                        match ResolvedVc::try_sidecast::<Box<dyn EcmascriptChunkPlaceable>>(module)
+                        {
+                            Some(module) => {
+                                match *export_mangling(*module_graph, *chunking_context, *module)
+                                    .await?
+                                {
+                                    Some(mangling) => Some(mangling.await?),
+                                    None => None,
+                                }
+                            }
+                            None => None,
+                        };
                     Some(
-                        async_module
+                        exports
                             .code_generation(
-                                async_module_info.map(|info| *info),
-                                *references,
+                                *module_graph,
                                 *chunking_context,
+                                Some(*parsed),
+                                used_exports.as_deref(),
+                                export_mangling.as_deref(),
+                                merged,
                             )
                             .await?,
                     )
                 } else {
                     None
                 },
-                if let EcmascriptExports::EsmExports(exports) = *exports.await? {
+                if let Some(async_module) = &*async_module.await? {
                     Some(
-                        exports
-                            .code_generation(*module_graph, *chunking_context, Some(*parsed))
+                        async_module
+                            .code_generation(
+                                async_module_info.map(|info| *info),
+                                *references,
+                                *chunking_context,
+                            )
                             .await?,
                     )
                 } else {
                     None
                 },
+                property_mangling_code_generation(*module_graph, *chunking_context).await?,
             ];
 
             let esm_code_gens = esm_references
                 .await?
                 .iter()
-                .map(|r| r.code_generation(*chunking_context))
+                .map(|r| r.code_generation(*module_graph, *chunking_context))
                 .try_join()
                 .await?;
             let code_gens = code_generation
@@ -870,6 +967,10 @@ impl EcmascriptModuleContent {
             .chain(additional_code_gens.iter().flatten())
             .chain(code_gens.iter());
 
+        let merged_modules = merged_module_contents(*module_graph, *chunking_context, *module)
+            .owned()
+            .await?;
+
         gen_content_with_code_gens(
             parsed,
             *ident,
@@ -877,6 +978,7 @@ impl EcmascriptModuleContent {
             code_gens,
             generate_source_map,
             original_source_map,
+            merged_modules,
         )
         .instrument(tracing::info_span!("gen content with code gens"))
         .await
@@ -897,6 +999,7 @@ impl EcmascriptModuleContent {
             &[],
             generate_source_map,
             OptionStringifiedSourceMap::none().to_resolved().await?,
+            vec![],
         )
         .await
     }
@@ -909,6 +1012,7 @@ async fn gen_content_with_code_gens(
     code_gens: impl IntoIterator<Item = &CodeGeneration>,
     generate_source_map: bool,
     original_source_map: ResolvedVc<OptionStringifiedSourceMap>,
+    merged_modules: Vec<MergedModuleContent>,
 ) -> Result<Vc<EcmascriptModuleContent>> {
     let parsed = parsed.final_read_hint().await?;
 
@@ -1008,6 +1112,7 @@ async fn gen_content_with_code_gens(
                 inner_code: bytes.into(),
                 source_map,
                 is_esm: eval_context.is_esm(specified_module_type),
+                merged_modules,
             }
             .cell())
         }
@@ -1024,6 +1129,7 @@ async fn gen_content_with_code_gens(
             .into(),
             source_map: None,
             is_esm: false,
+            merged_modules: vec![],
         }
         .cell()),
         ParseResult::NotFound => Ok(EcmascriptModuleContent {
@@ -1035,6 +1141,7 @@ async fn gen_content_with_code_gens(
             .into(),
             source_map: None,
             is_esm: false,
+            merged_modules: vec![],
         }
         .cell()),
     }
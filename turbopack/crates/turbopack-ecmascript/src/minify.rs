@@ -14,7 +14,7 @@ use swc_core::{
             text_writer::{self, JsWriter, WriteJs},
             Emitter,
         },
-        minifier::option::{CompressOptions, ExtraOptions, MangleOptions, MinifyOptions},
+        minifier::option::{self, CompressOptions, ExtraOptions, MangleOptions},
         parser::{lexer::Lexer, Parser, StringInput, Syntax},
         transforms::base::{
             fixer::paren_remover,
@@ -24,14 +24,19 @@ use swc_core::{
 };
 use tracing::{instrument, Level};
 use turbopack_core::{
-    chunk::MangleType,
+    chunk::{MangleType, MinifyOptions},
     code_builder::{Code, CodeBuilder},
 };
 
 use crate::parse::generate_js_source_map;
 
 #[instrument(level = Level::INFO, skip_all)]
-pub fn minify(code: &Code, source_maps: bool, mangle: Option<MangleType>) -> Result<Code> {
+pub fn minify(code: &Code, source_maps: bool, options: &MinifyOptions) -> Result<Code> {
+    // Properties are mangled consistently across all chunks when generating the code of the
+    // modules, see [crate::property_mangling]. Mangling them per chunk would give a property
+    // different names in different chunks.
+    let MinifyOptions { mangle, .. } = options;
+
     let source_maps = source_maps
         .then(|| code.generate_source_map_ref())
         .transpose()?;
@@ -80,7 +85,7 @@ pub fn minify(code: &Code, source_maps: bool, mangle: Option<MangleType>) -> Res
                     cm.clone(),
                     Some(&comments),
                     None,
-                    &MinifyOptions {
+                    &option::MinifyOptions {
                         compress: Some(CompressOptions {
                             // Only run 2 passes, this is a tradeoff between performance and
                             // compression size. Default is 3 passes.
@@ -124,7 +129,7 @@ pub fn minify(code: &Code, source_maps: bool, mangle: Option<MangleType>) -> Res
         })
         .map_err(|e| e.to_pretty_error())?;
 
-        print_program(cm.clone(), program, source_maps.is_some())?
+        print_program(cm.clone(), program, source_maps.is_some(), true)?
     };
 
     let mut builder = CodeBuilder::new(source_maps.is_some());
@@ -141,10 +146,11 @@ pub fn minify(code: &Code, source_maps: bool, mangle: Option<MangleType>) -> Res
 }
 
 // From https://github.com/swc-project/swc/blob/11efd4e7c5e8081f8af141099d3459c3534c1e1d/crates/swc/src/lib.rs#L523-L560
-fn print_program(
+pub(crate) fn print_program(
     cm: Arc<SwcSourceMap>,
     program: Program,
     source_maps: bool,
+    minify: bool,
 ) -> Result<(String, Vec<(BytePos, LineCol)>)> {
     let mut src_map_buf = vec![];
 
@@ -159,7 +165,7 @@ fn print_program(
             )))) as Box<dyn WriteJs>;
 
             let mut emitter = Emitter {
-                cfg: swc_core::ecma::codegen::Config::default().with_minify(true),
+                cfg: swc_core::ecma::codegen::Config::default().with_minify(minify),
                 comments: None,
                 cm: cm.clone(),
                 wr,
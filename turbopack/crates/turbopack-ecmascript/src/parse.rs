@@ -38,7 +38,7 @@ use turbopack_swc_utils::emitter::IssueEmitter;
 
 use super::EcmascriptModuleAssetType;
 use crate::{
-    analyzer::graph::EvalContext,
+    analyzer::{const_enums::collect_exported_const_enums, graph::EvalContext},
     swc_comments::ImmutableComments,
     transform::{EcmascriptInputTransforms, TransformContext},
     EcmascriptInputTransform,
@@ -364,6 +364,11 @@ async fn parse_file_content(
             } else {
                 FxHashSet::default()
             };
+            let const_enums = if is_typescript {
+                collect_exported_const_enums(&parsed_program)
+            } else {
+                Default::default()
+            };
 
             let transform_context = TransformContext {
                 comments: &comments,
@@ -415,7 +420,8 @@ async fn parse_file_content(
                 Arc::new(var_with_ts_declare),
                 Some(&comments),
                 Some(source),
-            );
+            )
+            .with_const_enums(Arc::new(const_enums));
 
             Ok::<ParseResult, anyhow::Error>(ParseResult::Ok {
                 program: parsed_program,
@@ -0,0 +1,542 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use rustc_hash::FxHashSet;
+use swc_core::ecma::{
+    ast::{
+        AssignPat, AssignPatProp, ExportDecl, Expr, Ident, IdentName, ImportNamedSpecifier,
+        JSXAttrName, KeyValuePatProp, KeyValueProp, MemberProp, ModuleExportName, ObjectPatProp,
+        Pat, Program, Prop, PropName, Str, SuperProp, Tpl,
+    },
+    utils::find_pat_ids,
+    visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexMap, FxIndexSet, ResolvedVc, TryFlatJoinIterExt, TryJoinIterExt, Vc};
+use turbopack_core::{
+    chunk::{ChunkingContext, ManglePropertiesOptions, MinifyOptions, MinifyType},
+    module::Module,
+    module_graph::ModuleGraph,
+};
+
+use crate::{
+    chunk::EcmascriptChunkPlaceable,
+    code_gen::CodeGeneration,
+    create_visitor,
+    parse::ParseResult,
+    references::esm::export_mangling::mangled_name,
+    runtime_functions::TUBROPACK_RUNTIME_FUNCTION_SHORTCUTS,
+    side_effect_optimization::{
+        facade::module::EcmascriptModuleFacadeModule, locals::module::EcmascriptModuleLocalsModule,
+    },
+    tree_shake::asset::EcmascriptModulePartAsset,
+    EcmascriptModuleAsset,
+};
+
+/// Short names for property names, by their original names.
+#[turbo_tasks::value(transparent)]
+pub struct PropertyMangling(FxIndexMap<RcStr, RcStr>);
+
+/// Computes short names for the properties matching [ManglePropertiesOptions::regex] that are only
+/// accessed by bundled modules, so every module renames them in the same way regardless of the
+/// chunk it ends up in.
+///
+/// A property keeps its name when it's accessed by name anywhere, e.g. with a string key or as an
+/// import or export name, or when it's accessed by a module that might pass objects to code
+/// outside of the module graph. These are modules that reference modules that aren't analyzed
+/// (e.g. externals, JSON or CSS modules), and modules whose exports are all used, like entries.
+#[turbo_tasks::function]
+pub async fn property_mangling(
+    module_graph: Vc<ModuleGraph>,
+    options: ManglePropertiesOptions,
+) -> Result<Vc<PropertyMangling>> {
+    let regex = Regex::new(&options.regex)
+        .with_context(|| format!("invalid property mangling regex {}", options.regex))?;
+
+    let export_usage_info = module_graph.export_usage_info().await?;
+    let graphs = module_graph.await?.get_graphs().await?;
+    let modules = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|node| node.module)
+        .collect::<FxIndexSet<_>>();
+    let module_names = modules
+        .into_iter()
+        .map(async |module| {
+            let Some(source_module) = analyzed_module(module).await? else {
+                return Ok(None);
+            };
+            let names = property_names(*source_module).await?;
+            let escapes = export_usage_info.are_all_exports_used(module)
+                || references_unanalyzed_module(module).await?;
+            Ok(Some((names, escapes)))
+        })
+        .try_flat_join()
+        .await?;
+
+    let mut all_names = FxHashSet::default();
+    let mut kept = options.reserved.iter().cloned().collect::<FxHashSet<_>>();
+    kept.extend(
+        TUBROPACK_RUNTIME_FUNCTION_SHORTCUTS
+            .iter()
+            .map(|(_, shortcut)| RcStr::from(shortcut.shortcut)),
+    );
+    let mut candidates = FxIndexSet::default();
+    for (names, escapes) in &module_names {
+        all_names.extend(names.accessed.iter().cloned());
+        all_names.extend(names.by_name.iter().cloned());
+        kept.extend(names.by_name.iter().cloned());
+        if *escapes {
+            kept.extend(names.accessed.iter().cloned());
+        } else {
+            candidates.extend(
+                names
+                    .accessed
+                    .iter()
+                    .filter(|name| regex.is_match(name))
+                    .cloned(),
+            );
+        }
+    }
+    candidates.retain(|name| !kept.contains(name));
+    // Sorted, so the short names are stable as long as the set of properties doesn't change
+    candidates.sort();
+
+    // The short names start with an underscore so they can't shadow builtin properties, and names
+    // that are used anywhere are skipped
+    let mut index = 0;
+    let mangling = candidates
+        .into_iter()
+        .map(|name| loop {
+            let short_name: RcStr = format!("_{}", mangled_name(index)).into();
+            index += 1;
+            if !all_names.contains(&short_name) && !kept.contains(&short_name) {
+                break (name, short_name);
+            }
+        })
+        .collect();
+    Ok(Vc::cell(mangling))
+}
+
+/// Returns the code generation that renames the properties of a module when property mangling is
+/// enabled, see [MinifyOptions::mangle_properties].
+pub async fn property_mangling_code_generation(
+    module_graph: Vc<ModuleGraph>,
+    chunking_context: Vc<Box<dyn ChunkingContext>>,
+) -> Result<Option<CodeGeneration>> {
+    let MinifyType::Minify(MinifyOptions {
+        mangle: Some(_),
+        mangle_properties: Some(options),
+    }) = &*chunking_context.minify_type().await?
+    else {
+        return Ok(None);
+    };
+    let mangling = property_mangling(module_graph, options.clone()).await?;
+    if mangling.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(CodeGeneration::visitors(vec![create_visitor!(
+        visit_mut_program(program: &mut Program) {
+            program.visit_mut_with(&mut PropertyRenamer {
+                mangling: &mangling,
+            });
+        }
+    )])))
+}
+
+/// Returns the module whose source contains the code of an analyzed module, or `None` for modules
+/// whose property accesses are unknown.
+async fn analyzed_module(
+    module: ResolvedVc<Box<dyn Module>>,
+) -> Result<Option<ResolvedVc<EcmascriptModuleAsset>>> {
+    if let Some(module) = ResolvedVc::try_downcast_type::<EcmascriptModuleAsset>(module) {
+        return Ok(Some(module));
+    }
+    if let Some(part) = ResolvedVc::try_downcast_type::<EcmascriptModulePartAsset>(module) {
+        return Ok(Some(part.await?.full_module));
+    }
+    if let Some(locals) = ResolvedVc::try_downcast_type::<EcmascriptModuleLocalsModule>(module) {
+        return Ok(Some(locals.await?.module));
+    }
+    if let Some(facade) = ResolvedVc::try_downcast_type::<EcmascriptModuleFacadeModule>(module) {
+        return Ok(ResolvedVc::try_downcast_type::<EcmascriptModuleAsset>(
+            facade.await?.module,
+        ));
+    }
+    Ok(None)
+}
+
+/// Returns true if the module references an ecmascript module that isn't analyzed, so objects
+/// might be passed to code whose property accesses are unknown.
+async fn references_unanalyzed_module(module: ResolvedVc<Box<dyn Module>>) -> Result<bool> {
+    let targets = module
+        .references()
+        .await?
+        .iter()
+        .map(|reference| async move {
+            reference
+                .resolve_reference()
+                .resolve()
+                .await?
+                .primary_modules()
+                .owned()
+                .await
+        })
+        .try_join()
+        .await?;
+    for target in targets.into_iter().flatten() {
+        if ResolvedVc::try_sidecast::<Box<dyn EcmascriptChunkPlaceable>>(target).is_some()
+            && analyzed_module(target).await?.is_none()
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[turbo_tasks::value]
+struct PropertyNames {
+    /// Property names that are accessed with a static name, e.g. `a.b` or `{ b: 1 }`.
+    accessed: FxIndexSet<RcStr>,
+    /// Strings that might be used to access properties by name, e.g. `a["b"]` or `"b" in a`, and
+    /// the names of imports and exports, which are accessed by name by the generated code.
+    by_name: FxIndexSet<RcStr>,
+}
+
+#[turbo_tasks::function]
+async fn property_names(module: Vc<EcmascriptModuleAsset>) -> Result<Vc<PropertyNames>> {
+    let mut collector = PropertyNameCollector::default();
+    if let ParseResult::Ok { program, .. } = &*module.parse().await? {
+        program.visit_with(&mut collector);
+    }
+    let PropertyNameCollector { accessed, by_name } = collector;
+    Ok(PropertyNames { accessed, by_name }.cell())
+}
+
+#[derive(Default)]
+struct PropertyNameCollector {
+    accessed: FxIndexSet<RcStr>,
+    by_name: FxIndexSet<RcStr>,
+}
+
+impl Visit for PropertyNameCollector {
+    fn visit_member_prop(&mut self, prop: &MemberProp) {
+        if let MemberProp::Ident(name) = prop {
+            self.accessed.insert(name.sym.as_str().into());
+        }
+        prop.visit_children_with(self);
+    }
+
+    fn visit_super_prop(&mut self, prop: &SuperProp) {
+        if let SuperProp::Ident(name) = prop {
+            self.accessed.insert(name.sym.as_str().into());
+        }
+        prop.visit_children_with(self);
+    }
+
+    fn visit_prop_name(&mut self, name: &PropName) {
+        if let PropName::Ident(name) = name {
+            self.accessed.insert(name.sym.as_str().into());
+        }
+        name.visit_children_with(self);
+    }
+
+    fn visit_prop(&mut self, prop: &Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            self.accessed.insert(ident.sym.as_str().into());
+        }
+        prop.visit_children_with(self);
+    }
+
+    fn visit_object_pat_prop(&mut self, prop: &ObjectPatProp) {
+        if let ObjectPatProp::Assign(AssignPatProp { key, .. }) = prop {
+            self.accessed.insert(key.sym.as_str().into());
+        }
+        prop.visit_children_with(self);
+    }
+
+    fn visit_str(&mut self, str: &Str) {
+        self.by_name.insert(str.value.as_str().into());
+    }
+
+    fn visit_tpl(&mut self, tpl: &Tpl) {
+        if tpl.exprs.is_empty() {
+            for quasi in &tpl.quasis {
+                if let Some(cooked) = &quasi.cooked {
+                    self.by_name.insert(cooked.as_str().into());
+                }
+            }
+        }
+        tpl.visit_children_with(self);
+    }
+
+    fn visit_jsx_attr_name(&mut self, name: &JSXAttrName) {
+        // JSX attributes become the keys of the props object
+        if let JSXAttrName::Ident(name) = name {
+            self.by_name.insert(name.sym.as_str().into());
+        }
+        name.visit_children_with(self);
+    }
+
+    fn visit_import_named_specifier(&mut self, specifier: &ImportNamedSpecifier) {
+        if specifier.imported.is_none() {
+            self.by_name.insert(specifier.local.sym.as_str().into());
+        }
+        specifier.visit_children_with(self);
+    }
+
+    fn visit_module_export_name(&mut self, name: &ModuleExportName) {
+        if let ModuleExportName::Ident(ident) = name {
+            self.by_name.insert(ident.sym.as_str().into());
+        }
+        name.visit_children_with(self);
+    }
+
+    fn visit_export_decl(&mut self, decl: &ExportDecl) {
+        for ident in find_pat_ids::<_, Ident>(&decl.decl) {
+            self.by_name.insert(ident.sym.as_str().into());
+        }
+        decl.visit_children_with(self);
+    }
+}
+
+/// Renames the properties of a module according to a [PropertyMangling].
+struct PropertyRenamer<'a> {
+    mangling: &'a FxIndexMap<RcStr, RcStr>,
+}
+
+impl PropertyRenamer<'_> {
+    fn rename(&self, name: &mut IdentName) {
+        if let Some(short_name) = self.mangling.get(name.sym.as_str()) {
+            name.sym = short_name.as_str().into();
+        }
+    }
+}
+
+impl VisitMut for PropertyRenamer<'_> {
+    fn visit_mut_member_prop(&mut self, prop: &mut MemberProp) {
+        if let MemberProp::Ident(name) = prop {
+            self.rename(name);
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_super_prop(&mut self, prop: &mut SuperProp) {
+        if let SuperProp::Ident(name) = prop {
+            self.rename(name);
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_prop_name(&mut self, name: &mut PropName) {
+        if let PropName::Ident(name) = name {
+            self.rename(name);
+        }
+        name.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        // `{ a }` becomes `{ b: a }`
+        if let Prop::Shorthand(ident) = prop {
+            if let Some(short_name) = self.mangling.get(ident.sym.as_str()) {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(IdentName::new(short_name.as_str().into(), ident.span)),
+                    value: Box::new(Expr::Ident(ident.clone())),
+                });
+            }
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_object_pat_prop(&mut self, prop: &mut ObjectPatProp) {
+        // `{ a = 1 } = c` becomes `{ b: a = 1 } = c`
+        if let ObjectPatProp::Assign(AssignPatProp { key, value, span }) = prop {
+            if let Some(short_name) = self.mangling.get(key.sym.as_str()) {
+                let binding = Box::new(Pat::Ident(key.clone()));
+                *prop = ObjectPatProp::KeyValue(KeyValuePatProp {
+                    key: PropName::Ident(IdentName::new(short_name.as_str().into(), key.span)),
+                    value: match value.take() {
+                        Some(default) => Box::new(Pat::Assign(AssignPat {
+                            span: *span,
+                            left: binding,
+                            right: default,
+                        })),
+                        None => binding,
+                    },
+                });
+            }
+        }
+        prop.visit_mut_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use swc_core::{
+        common::{sync::Lrc, FileName, SourceMap, GLOBALS},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter, Node},
+            parser::{parse_file_as_program, EsSyntax, Syntax},
+            visit::{VisitMutWith, VisitWith},
+        },
+    };
+    use turbo_rcstr::RcStr;
+    use turbo_tasks::FxIndexMap;
+
+    use super::{PropertyNameCollector, PropertyRenamer};
+
+    fn parse(cm: &Lrc<SourceMap>, code: &str) -> Program {
+        let fm = cm.new_source_file(FileName::Anon.into(), code.to_string());
+        parse_file_as_program(
+            &fm,
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap()
+    }
+
+    fn print(cm: &Lrc<SourceMap>, program: &Program) -> String {
+        let mut buf = vec![];
+        {
+            let mut emitter = Emitter {
+                cfg: Default::default(),
+                cm: cm.clone(),
+                comments: None,
+                wr: Box::new(JsWriter::new(cm.clone(), "\n", &mut buf, None)),
+            };
+            program.emit_with(&mut emitter).unwrap();
+        }
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Returns the property names that are accessed statically and by name by the code.
+    fn property_names(code: &str) -> (BTreeSet<RcStr>, BTreeSet<RcStr>) {
+        GLOBALS.set(&Default::default(), || {
+            let cm = Lrc::new(SourceMap::default());
+            let mut collector = PropertyNameCollector::default();
+            parse(&cm, code).visit_with(&mut collector);
+            (
+                collector.accessed.into_iter().collect(),
+                collector.by_name.into_iter().collect(),
+            )
+        })
+    }
+
+    /// Renames the properties of the code and compares the result to the expected code.
+    fn assert_renamed(code: &str, mangling: &[(&str, &str)], expected: &str) {
+        GLOBALS.set(&Default::default(), || {
+            let cm = Lrc::new(SourceMap::default());
+            let mangling = mangling
+                .iter()
+                .map(|&(name, short_name)| (name.into(), short_name.into()))
+                .collect::<FxIndexMap<RcStr, RcStr>>();
+            let mut program = parse(&cm, code);
+            program.visit_mut_with(&mut PropertyRenamer {
+                mangling: &mangling,
+            });
+            assert_eq!(print(&cm, &program), print(&cm, &parse(&cm, expected)));
+        })
+    }
+
+    fn names(names: &[&str]) -> BTreeSet<RcStr> {
+        names.iter().map(|&name| name.into()).collect()
+    }
+
+    #[test]
+    fn member_and_object_properties() {
+        assert_renamed(
+            r#"
+            const shape = { _width: 2, _height: 3, other: 4 };
+            console.log(shape._width * shape._height, shape.other);
+            "#,
+            &[("_width", "_a"), ("_height", "_b")],
+            r#"
+            const shape = { _a: 2, _b: 3, other: 4 };
+            console.log(shape._a * shape._b, shape.other);
+            "#,
+        );
+    }
+
+    #[test]
+    fn shorthand_properties() {
+        assert_renamed(
+            r#"
+            const _x = 1, _y = 2;
+            const point = { _x, _y };
+            "#,
+            &[("_x", "_a")],
+            r#"
+            const _x = 1, _y = 2;
+            const point = { _a: _x, _y };
+            "#,
+        );
+    }
+
+    #[test]
+    fn destructuring_defaults() {
+        assert_renamed(
+            r#"
+            function sum({ _x, _y = 10 }) {
+                return _x + _y;
+            }
+            const { _z = 5, _w: w = 6 } = {};
+            "#,
+            &[("_x", "_a"), ("_y", "_b"), ("_z", "_c"), ("_w", "_d")],
+            r#"
+            function sum({ _a: _x, _b: _y = 10 }) {
+                return _x + _y;
+            }
+            const { _c: _z = 5, _d: w = 6 } = {};
+            "#,
+        );
+    }
+
+    #[test]
+    fn string_keys() {
+        let (accessed, by_name) = property_names(
+            r#"
+            const config = { _mode: "dev", _level: 1, _renamed: true };
+            console.log(config["_mode"], "_level" in config, config[`_other`], config._renamed);
+            "#,
+        );
+        assert_eq!(accessed, names(&["_level", "_mode", "_renamed", "log"]));
+        assert_eq!(by_name, names(&["_level", "_mode", "_other", "dev"]));
+
+        // String keys are never renamed, so the names they access have to be kept
+        assert_renamed(
+            r#"const config = { "_mode": 1, [`_mode`]: 2 }; config["_mode"];"#,
+            &[("_mode", "_a")],
+            r#"const config = { "_mode": 1, [`_mode`]: 2 }; config["_mode"];"#,
+        );
+    }
+
+    #[test]
+    fn import_and_export_names() {
+        let (accessed, by_name) = property_names(
+            r#"
+            import { _imported, _other as local } from "./a";
+            export const _exported = local._read;
+            export { _imported as _reexported };
+            "#,
+        );
+        assert_eq!(accessed, names(&["_read"]));
+        assert_eq!(
+            by_name,
+            names(&["./a", "_exported", "_imported", "_other", "_reexported"])
+        );
+    }
+
+    #[test]
+    fn jsx_attributes() {
+        let (_, by_name) = property_names(r#"<div _prop={1} />"#);
+        assert_eq!(by_name, names(&["_prop"]));
+    }
+}
@@ -2,7 +2,13 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use swc_core::{
     common::util::take::Take,
-    ecma::ast::{CallExpr, Expr, ExprOrSpread, Lit},
+    ecma::{
+        ast::{CallExpr, Expr, ExprOrSpread, Lit},
+        visit::{
+            fields::{AwaitExprField, ExprField, ParenExprField},
+            AstParentKind,
+        },
+    },
     quote,
 };
 use turbo_rcstr::RcStr;
@@ -11,8 +17,9 @@ use turbo_tasks::{
 };
 use turbopack_core::{
     chunk::{ChunkableModuleReference, ChunkingContext},
-    issue::IssueSource,
-    module_graph::ModuleGraph,
+    ident::AssetIdent,
+    issue::{analyze::AnalyzeIssue, IssueExt, IssueSeverity, IssueSource, StyledString},
+    module_graph::{export_usage::ExportUsage, ModuleGraph},
     reference::ModuleReference,
     resolve::{origin::ResolveOrigin, parse::Request, ModuleResolveResult},
 };
@@ -20,7 +27,7 @@ use turbopack_resolve::ecmascript::cjs_resolve;
 
 use crate::{
     code_gen::{CodeGen, CodeGeneration, IntoCodeGenReference},
-    create_visitor,
+    create_visitor, errors,
     references::{
         pattern_mapping::{PatternMapping, ResolveType},
         AstPath,
@@ -88,6 +95,11 @@ pub struct CjsRequireAssetReference {
     pub request: ResolvedVc<Request>,
     pub issue_source: IssueSource,
     pub in_try: bool,
+    /// Whether `await` can be used at the position of the `require()` call, i.e. inside of an
+    /// async function or at the top level of a module that uses top level await.
+    pub in_async_context: bool,
+    /// Which properties are read from the result of the `require()` call.
+    pub export_usage: ExportUsage,
 }
 
 impl CjsRequireAssetReference {
@@ -96,12 +108,16 @@ impl CjsRequireAssetReference {
         request: ResolvedVc<Request>,
         issue_source: IssueSource,
         in_try: bool,
+        in_async_context: bool,
+        export_usage: ExportUsage,
     ) -> Self {
         CjsRequireAssetReference {
             origin,
             request,
             issue_source,
             in_try,
+            in_async_context,
+            export_usage,
         }
     }
 }
@@ -130,7 +146,12 @@ impl ValueToString for CjsRequireAssetReference {
 }
 
 #[turbo_tasks::value_impl]
-impl ChunkableModuleReference for CjsRequireAssetReference {}
+impl ChunkableModuleReference for CjsRequireAssetReference {
+    #[turbo_tasks::function]
+    fn export_usage(&self) -> Vc<ExportUsage> {
+        self.export_usage.clone().cell()
+    }
+}
 
 impl IntoCodeGenReference for CjsRequireAssetReference {
     fn into_code_gen_reference(
@@ -157,11 +178,45 @@ pub struct CjsRequireAssetReferenceCodeGen {
 impl CjsRequireAssetReferenceCodeGen {
     pub async fn code_generation(
         &self,
-        _module_graph: Vc<ModuleGraph>,
+        module_graph: Vc<ModuleGraph>,
         chunking_context: Vc<Box<dyn ChunkingContext>>,
     ) -> Result<CodeGeneration> {
         let reference = self.reference.await?;
 
+        // An async module (one that uses top level await, directly or through its imports)
+        // exposes a Promise of its exports as `module.exports`. That works when the result of
+        // `require()` is awaited, otherwise the caller silently receives the Promise.
+        let await_result = if is_awaited(&self.path)
+            || !*requires_async_module(module_graph, *self.reference).await?
+        {
+            false
+        } else if reference.in_async_context {
+            true
+        } else {
+            AnalyzeIssue::new(
+                IssueSeverity::Warning,
+                AssetIdent::from_path(reference.origin.origin_path()),
+                Vc::cell("require() of an async module".into()),
+                StyledString::Text(
+                    format!(
+                        "The module {} uses top level await (directly or through its imports), so \
+                         require() returns a Promise of its exports instead of the exports. Use \
+                         `await import()` instead, or await the result of require() in an async \
+                         function.",
+                        reference.request.to_string().await?
+                    )
+                    .into(),
+                )
+                .cell(),
+                Some(errors::interop::ecmascript::REQUIRE_ASYNC_MODULE.into()),
+                Some(reference.issue_source.clone()),
+            )
+            .to_resolved()
+            .await?
+            .emit();
+            false
+        };
+
         let pm = PatternMapping::resolve_request(
             *reference.request,
             *reference.origin,
@@ -178,6 +233,12 @@ impl CjsRequireAssetReferenceCodeGen {
                 match args.into_iter().next() {
                     Some(ExprOrSpread { spread: None, expr: key_expr }) => {
                         *expr = pm.create_require(*key_expr);
+                        if await_result {
+                            *expr = quote!(
+                                "(await $expr)" as Expr,
+                                expr: Expr = expr.take()
+                            );
+                        }
                         return;
                     }
                     Some(ExprOrSpread { spread: Some(_), expr: _ }) => {
@@ -200,6 +261,42 @@ impl CjsRequireAssetReferenceCodeGen {
     }
 }
 
+/// Whether any of the modules a `require()` call resolves to is async.
+#[turbo_tasks::function]
+async fn requires_async_module(
+    module_graph: Vc<ModuleGraph>,
+    reference: Vc<CjsRequireAssetReference>,
+) -> Result<Vc<bool>> {
+    let async_module_info = module_graph.async_module_info();
+    for &module in reference
+        .resolve_reference()
+        .primary_modules()
+        .await?
+        .iter()
+    {
+        if *async_module_info.is_async(*module).await? {
+            return Ok(Vc::cell(true));
+        }
+    }
+    Ok(Vc::cell(false))
+}
+
+/// Whether the expression at `path` is the operand of an `await` expression, ignoring
+/// parentheses.
+fn is_awaited(path: &[AstParentKind]) -> bool {
+    let mut parents = path.iter().rev().skip_while(|kind| {
+        matches!(
+            kind,
+            AstParentKind::Expr(ExprField::Call | ExprField::Paren)
+                | AstParentKind::ParenExpr(ParenExprField::Expr)
+        )
+    });
+    matches!(
+        parents.next(),
+        Some(AstParentKind::AwaitExpr(AwaitExprField::Arg))
+    )
+}
+
 #[turbo_tasks::value]
 #[derive(Hash, Debug)]
 pub struct CjsRequireResolveAssetReference {
@@ -0,0 +1,64 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::ast::{Expr, Lit, Number, UnaryExpr, UnaryOp},
+};
+use turbo_tasks::{debug::ValueDebugFormat, trace::TraceRawVcs, NonLocalValue, Vc};
+use turbopack_core::{chunk::ChunkingContext, module_graph::ModuleGraph};
+
+use super::AstPath;
+use crate::{
+    analyzer::const_enums::ConstEnumValue,
+    code_gen::{CodeGen, CodeGeneration},
+    create_visitor,
+};
+
+/// Replaces a member access on an imported `const enum` with the value of the member.
+#[derive(PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, ValueDebugFormat, NonLocalValue)]
+pub struct ConstEnumMemberCodeGen {
+    value: ConstEnumValue,
+    path: AstPath,
+}
+
+impl ConstEnumMemberCodeGen {
+    pub fn new(value: ConstEnumValue, path: AstPath) -> Self {
+        ConstEnumMemberCodeGen { value, path }
+    }
+
+    pub async fn code_generation(
+        &self,
+        _module_graph: Vc<ModuleGraph>,
+        _chunking_context: Vc<Box<dyn ChunkingContext>>,
+    ) -> Result<CodeGeneration> {
+        let value = self.value.clone();
+
+        let visitor = create_visitor!(self.path, visit_mut_expr(expr: &mut Expr) {
+            *expr = match value {
+                ConstEnumValue::Number(n) if n.is_sign_negative() => Expr::Unary(UnaryExpr {
+                    span: DUMMY_SP,
+                    op: UnaryOp::Minus,
+                    arg: Box::new(number_lit(-n)),
+                }),
+                ConstEnumValue::Number(n) => number_lit(n),
+                ConstEnumValue::String(ref s) => s.to_string().into(),
+            };
+        });
+
+        Ok(CodeGeneration::visitors(vec![visitor]))
+    }
+}
+
+fn number_lit(value: f64) -> Expr {
+    Expr::Lit(Lit::Num(Number {
+        span: DUMMY_SP,
+        value,
+        raw: None,
+    }))
+}
+
+impl From<ConstEnumMemberCodeGen> for CodeGen {
+    fn from(val: ConstEnumMemberCodeGen) -> Self {
+        CodeGen::ConstEnumMemberCodeGen(val)
+    }
+}
@@ -7,7 +7,7 @@ use swc_core::{
 };
 use turbo_rcstr::RcStr;
 use turbo_tasks::{ResolvedVc, Value, ValueToString, Vc};
-use turbo_tasks_fs::FileSystemPath;
+use turbo_tasks_fs::{glob::Glob, FileSystemPath};
 use turbopack_core::{
     chunk::{
         ChunkableModuleReference, ChunkingContext, ChunkingType, ChunkingTypeOption,
@@ -18,6 +18,7 @@ use turbopack_core::{
         OptionStyledString, StyledString,
     },
     module::Module,
+    module_graph::{export_usage::ExportUsage, ModuleGraph},
     reference::ModuleReference,
     reference_type::{EcmaScriptModulesReferenceSubType, ImportWithType},
     resolve::{
@@ -28,16 +29,20 @@ use turbopack_core::{
 };
 use turbopack_resolve::ecmascript::esm_resolve;
 
-use super::export::{all_known_export_names, is_export_missing};
+use super::{
+    export::{all_known_export_names, is_export_missing},
+    scope_hoisting::merged_module_function_name,
+};
 use crate::{
-    analyzer::imports::ImportAnnotations,
-    chunk::EcmascriptChunkPlaceable,
+    analyzer::{const_enums::ExportedConstEnums, imports::ImportAnnotations},
+    chunk::{placeable::is_marked_as_side_effect_free, EcmascriptChunkPlaceable},
     code_gen::CodeGeneration,
     magic_identifier,
     references::util::{request_to_string, throw_module_not_found_expr},
     runtime_functions::{TURBOPACK_EXTERNAL_IMPORT, TURBOPACK_EXTERNAL_REQUIRE, TURBOPACK_IMPORT},
     tree_shake::{asset::EcmascriptModulePartAsset, TURBOPACK_PART_IMPORT_SOURCE},
     utils::module_id_to_lit,
+    EcmascriptModuleAsset,
 };
 
 #[turbo_tasks::value]
@@ -115,6 +120,7 @@ pub struct EsmAssetReference {
     pub annotations: ImportAnnotations,
     pub issue_source: IssueSource,
     pub export_name: Option<ModulePart>,
+    pub export_usage: ExportUsage,
     pub import_externals: bool,
 }
 
@@ -126,6 +132,20 @@ impl EsmAssetReference {
             *self.origin
         }
     }
+
+    /// Resolves the referenced module without the part. Selecting the part might follow the
+    /// reexports of the module and analyze the importing module again, so this has to be used
+    /// while analyzing the importing module.
+    async fn resolve_without_part(&self) -> Result<Vc<ModuleResolveResult>> {
+        esm_resolve(
+            self.get_origin(),
+            *self.request,
+            Value::new(EcmaScriptModulesReferenceSubType::Import),
+            true,
+            None,
+        )
+        .await
+    }
 }
 
 impl EsmAssetReference {
@@ -135,6 +155,7 @@ impl EsmAssetReference {
         issue_source: IssueSource,
         annotations: Value<ImportAnnotations>,
         export_name: Option<ModulePart>,
+        export_usage: ExportUsage,
         import_externals: bool,
     ) -> Self {
         EsmAssetReference {
@@ -143,6 +164,7 @@ impl EsmAssetReference {
             issue_source,
             annotations: annotations.into_value(),
             export_name,
+            export_usage,
             import_externals,
         }
     }
@@ -154,6 +176,45 @@ impl EsmAssetReference {
     pub(crate) fn get_referenced_asset(self: Vc<Self>) -> Vc<ReferencedAsset> {
         ReferencedAsset::from_resolve_result(self.resolve_reference())
     }
+
+    /// The `const enum`s exported by the referenced module.
+    #[turbo_tasks::function]
+    pub(crate) async fn referenced_const_enums(&self) -> Result<Vc<ExportedConstEnums>> {
+        if self.annotations.module_type().is_some() {
+            return Ok(Vc::cell(Default::default()));
+        }
+
+        let result = self.resolve_without_part().await?;
+        let Some(module) = *result.first_module().await? else {
+            return Ok(Vc::cell(Default::default()));
+        };
+        let Some(module) = ResolvedVc::try_downcast_type::<EcmascriptModuleAsset>(module) else {
+            return Ok(Vc::cell(Default::default()));
+        };
+        Ok(module.exported_const_enums())
+    }
+
+    /// Whether the referenced modules are marked as side effect free by their `package.json` or
+    /// the side effect free packages of the project.
+    #[turbo_tasks::function]
+    pub(crate) async fn is_referenced_module_side_effect_free(
+        &self,
+        side_effect_free_packages: Vc<Glob>,
+    ) -> Result<Vc<bool>> {
+        let result = self.resolve_without_part().await?;
+        let modules = result.primary_modules().await?;
+        if modules.is_empty() {
+            return Ok(Vc::cell(false));
+        }
+        for module in modules.iter() {
+            if !*is_marked_as_side_effect_free(module.ident().path(), side_effect_free_packages)
+                .await?
+            {
+                return Ok(Vc::cell(false));
+            }
+        }
+        Ok(Vc::cell(true))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -246,11 +307,17 @@ impl ChunkableModuleReference for EsmAssetReference {
             },
         ))
     }
+
+    #[turbo_tasks::function]
+    fn export_usage(&self) -> Vc<ExportUsage> {
+        self.export_usage.clone().cell()
+    }
 }
 
 impl EsmAssetReference {
     pub async fn code_generation(
         self: Vc<Self>,
+        module_graph: Vc<ModuleGraph>,
         chunking_context: Vc<Box<dyn ChunkingContext>>,
     ) -> Result<CodeGeneration> {
         let this = &*self.await?;
@@ -280,6 +347,49 @@ impl EsmAssetReference {
                     ReferencedAsset::Unresolvable => {
                         unreachable!()
                     }
+                    // The module is omitted from the chunks when it's side effect free and none of
+                    // its exports are used
+                    ReferencedAsset::Some(asset)
+                        if *chunking_context.is_simple_tree_shaking_enabled().await?
+                            && module_graph
+                                .export_usage_info()
+                                .await?
+                                .is_module_unused(ResolvedVc::upcast(*asset)) =>
+                    {
+                        None
+                    }
+                    // The module is merged into this module, so it's evaluated by a function that
+                    // is declared next to the module factory instead of being imported
+                    ReferencedAsset::Some(asset)
+                        if *chunking_context.is_scope_hoisting_enabled().await?
+                            && module_graph
+                                .merged_modules_info()
+                                .await?
+                                .is_merged(ResolvedVc::upcast(*asset)) =>
+                    {
+                        let function_name =
+                            merged_module_function_name(Vc::upcast(**asset), chunking_context)
+                                .await?;
+                        Some((
+                            function_name.clone().into(),
+                            var_decl_with_span(
+                                quote!(
+                                    "var $name = $merged_module(__turbopack_context__);" as Stmt,
+                                    name = Ident::new(
+                                        ident.clone().into(),
+                                        DUMMY_SP,
+                                        Default::default()
+                                    ),
+                                    merged_module = Ident::new(
+                                        function_name.into(),
+                                        DUMMY_SP,
+                                        Default::default()
+                                    ),
+                                ),
+                                span,
+                            ),
+                        ))
+                    }
                     ReferencedAsset::Some(asset) => {
                         let id = asset.chunk_item_id(Vc::upcast(chunking_context)).await?;
                         let name = ident;
@@ -18,7 +18,10 @@ use super::EsmAssetReference;
 use crate::{
     code_gen::{CodeGen, CodeGeneration},
     create_visitor,
-    references::{esm::base::ReferencedAsset, AstPath},
+    references::{
+        esm::{base::ReferencedAsset, export_mangling::mangled_export_name},
+        AstPath,
+    },
 };
 
 #[derive(Hash, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TraceRawVcs, NonLocalValue)]
@@ -43,13 +46,18 @@ impl EsmBinding {
 
     pub async fn code_generation(
         &self,
-        _module_graph: Vc<ModuleGraph>,
+        module_graph: Vc<ModuleGraph>,
         chunking_context: Vc<Box<dyn ChunkingContext>>,
     ) -> Result<CodeGeneration> {
         let mut visitors = vec![];
 
-        let export = self.export.clone();
         let imported_module = self.reference.get_referenced_asset();
+        let export = match (&self.export, &*imported_module.await?) {
+            (Some(export), ReferencedAsset::Some(asset)) => {
+                Some(mangled_export_name(module_graph, chunking_context, *asset, export).await?)
+            }
+            (export, _) => export.clone(),
+        };
 
         enum ImportedIdent {
             Module(String),
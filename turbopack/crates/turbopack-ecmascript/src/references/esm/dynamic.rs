@@ -1,9 +1,11 @@
+use std::borrow::Cow;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use swc_core::{
     common::{util::take::Take, DUMMY_SP},
-    ecma::ast::{CallExpr, Callee, Expr, ExprOrSpread, Lit},
-    quote_expr,
+    ecma::ast::{CallExpr, Callee, Expr, ExprOrSpread, Lit, Stmt},
+    quote, quote_expr,
 };
 use turbo_rcstr::RcStr;
 use turbo_tasks::{
@@ -14,7 +16,9 @@ use turbopack_core::{
     chunk::{ChunkableModuleReference, ChunkingContext, ChunkingType, ChunkingTypeOption},
     environment::ChunkLoading,
     issue::IssueSource,
-    module_graph::ModuleGraph,
+    module_graph::{
+        async_chunk_group_options::AsyncChunkGroupOptions, export_usage::ExportUsage, ModuleGraph,
+    },
     reference::ModuleReference,
     reference_type::EcmaScriptModulesReferenceSubType,
     resolve::{
@@ -25,10 +29,10 @@ use turbopack_core::{
 };
 use turbopack_resolve::ecmascript::esm_resolve;
 
-use super::super::pattern_mapping::{PatternMapping, ResolveType};
+use super::super::pattern_mapping::{PatternMapping, ResolveType, SinglePatternMapping};
 use crate::{
     analyzer::imports::ImportAnnotations,
-    code_gen::{CodeGen, CodeGeneration, IntoCodeGenReference},
+    code_gen::{CodeGen, CodeGeneration, CodeGenerationHoistedStmt, IntoCodeGenReference},
     create_visitor,
     references::AstPath,
 };
@@ -42,6 +46,10 @@ pub struct EsmAsyncAssetReference {
     pub issue_source: IssueSource,
     pub in_try: bool,
     pub import_externals: bool,
+    /// Which exports are read from the namespace object the import resolves to.
+    pub export_usage: ExportUsage,
+    /// The chunk name and link hints from the magic comments of the import.
+    pub chunk_group_options: AsyncChunkGroupOptions,
 }
 
 impl EsmAsyncAssetReference {
@@ -62,6 +70,8 @@ impl EsmAsyncAssetReference {
         annotations: Value<ImportAnnotations>,
         in_try: bool,
         import_externals: bool,
+        export_usage: ExportUsage,
+        chunk_group_options: AsyncChunkGroupOptions,
     ) -> Self {
         EsmAsyncAssetReference {
             origin,
@@ -70,6 +80,8 @@ impl EsmAsyncAssetReference {
             annotations: annotations.into_value(),
             in_try,
             import_externals,
+            export_usage,
+            chunk_group_options,
         }
     }
 }
@@ -105,6 +117,16 @@ impl ChunkableModuleReference for EsmAsyncAssetReference {
     fn chunking_type(&self) -> Vc<ChunkingTypeOption> {
         Vc::cell(Some(ChunkingType::Async))
     }
+
+    #[turbo_tasks::function]
+    fn export_usage(&self) -> Vc<ExportUsage> {
+        self.export_usage.clone().cell()
+    }
+
+    #[turbo_tasks::function]
+    fn async_chunk_group_options(&self) -> Vc<AsyncChunkGroupOptions> {
+        self.chunk_group_options.clone().cell()
+    }
 }
 
 impl IntoCodeGenReference for EsmAsyncAssetReference {
@@ -155,6 +177,21 @@ impl EsmAsyncAssetReferenceCodeGen {
 
         let import_externals = reference.import_externals;
 
+        // The async loader emits the link hints for the chunks of the chunk group when it's
+        // evaluated, so it's required eagerly when the importing module is evaluated.
+        let mut hoisted_stmts = vec![];
+        let options = &reference.chunk_group_options;
+        if options.prefetch || options.preload {
+            if let PatternMapping::Single(loader @ SinglePatternMapping::ModuleLoader(id)) = &*pm {
+                hoisted_stmts.push(CodeGenerationHoistedStmt::new(
+                    format!("link hints {id}").into(),
+                    quote!("$require;" as Stmt,
+                        require: Expr = loader.create_require(Cow::Owned(Expr::dummy()))
+                    ),
+                ));
+            }
+        }
+
         let visitor = create_visitor!(self.path, visit_mut_expr(expr: &mut Expr) {
             let old_expr = expr.take();
             let message = if let Expr::Call(CallExpr { args, ..}) = old_expr {
@@ -189,6 +226,6 @@ impl EsmAsyncAssetReferenceCodeGen {
             });
         });
 
-        Ok(CodeGeneration::visitors(vec![visitor]))
+        Ok(CodeGeneration::new(vec![visitor], hoisted_stmts, vec![]))
     }
 }
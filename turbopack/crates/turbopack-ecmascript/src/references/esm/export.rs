@@ -1,13 +1,17 @@
 use std::{borrow::Cow, collections::BTreeMap, ops::ControlFlow};
 
 use anyhow::Result;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
 use swc_core::{
     common::DUMMY_SP,
-    ecma::ast::{
-        AssignTarget, ComputedPropName, Expr, ExprStmt, Ident, KeyValueProp, Lit, MemberExpr,
-        MemberProp, ObjectLit, Prop, PropName, PropOrSpread, SimpleAssignTarget, Stmt, Str,
+    ecma::{
+        ast::{
+            AssignTarget, ComputedPropName, Decl, Expr, ExprStmt, Id, Ident, KeyValueProp, Lit,
+            MemberExpr, MemberProp, ModuleItem, ObjectLit, Program, Prop, PropName, PropOrSpread,
+            SimpleAssignTarget, Stmt, Str,
+        },
+        visit::{Visit, VisitWith},
     },
     quote, quote_expr,
 };
@@ -22,17 +26,22 @@ use turbopack_core::{
     ident::AssetIdent,
     issue::{analyze::AnalyzeIssue, IssueExt, IssueSeverity, StyledString},
     module::Module,
-    module_graph::ModuleGraph,
+    module_graph::{export_usage::ModuleExportUsageInfo, ModuleGraph},
     reference::ModuleReference,
 };
 
-use super::base::ReferencedAsset;
+use super::{
+    base::ReferencedAsset, export_mangling::mangled_export_name,
+    scope_hoisting::merged_exports_ident,
+};
 use crate::{
+    analyzer::side_effects::SideEffectsChecker,
     chunk::{EcmascriptChunkPlaceable, EcmascriptExports},
     code_gen::{CodeGeneration, CodeGenerationHoistedStmt},
-    magic_identifier,
+    create_visitor, magic_identifier,
     parse::ParseResult,
     runtime_functions::{TURBOPACK_DYNAMIC, TURBOPACK_ESM},
+    utils::pure_call,
 };
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
@@ -491,11 +500,19 @@ impl EsmExports {
 }
 
 impl EsmExports {
+    /// Generates the getters of the exports. When `used_exports` is given, unused exports are
+    /// omitted and their declarations are removed if they are side effect free and not referenced
+    /// otherwise. When `export_mangling` is given, the getters use the short names of the exports.
+    /// When `merged` is true, the module is merged into its importer and the getters are defined on
+    /// a local exports object instead of the exports of the module.
     pub async fn code_generation(
         self: Vc<Self>,
-        _module_graph: Vc<ModuleGraph>,
+        module_graph: Vc<ModuleGraph>,
         chunking_context: Vc<Box<dyn ChunkingContext>>,
         parsed: Option<Vc<ParseResult>>,
+        used_exports: Option<&ModuleExportUsageInfo>,
+        export_mangling: Option<&FxIndexMap<RcStr, RcStr>>,
+        merged: bool,
     ) -> Result<CodeGeneration> {
         let expanded = self.expand_exports().await?;
         let parsed = if let Some(parsed) = parsed {
@@ -518,10 +535,32 @@ impl EsmExports {
         }
 
         let mut props = Vec::new();
+        // Locals of unused exports, which can be removed when nothing else refers to them
+        let mut unused_locals = FxHashSet::default();
+        let mut used_locals = FxHashSet::default();
         for (exported, local) in &expanded.exports {
-            let expr = match local {
-                EsmExport::Error => Some(quote!(
-                    "(() => { throw new Error(\"Failed binding. See build errors!\"); })" as Expr,
+            let is_used =
+                used_exports.is_none_or(|used_exports| used_exports.is_export_used(exported));
+            if let (EsmExport::LocalBinding(name, _), Some(ParseResult::Ok { eval_context, .. })) =
+                (local, parsed.as_deref())
+            {
+                if let Some(id) = eval_context.imports.exports.get(name) {
+                    if is_used {
+                        used_locals.insert(id.clone());
+                    } else {
+                        unused_locals.insert(id.clone());
+                    }
+                }
+            }
+            if !is_used {
+                continue;
+            }
+            let accessors = match local {
+                EsmExport::Error => Some((
+                    quote!(
+                        "() => { throw new Error(\"Failed binding. See build errors!\"); }" as Expr,
+                    ),
+                    None,
                 )),
                 EsmExport::LocalBinding(name, mutable) => {
                     let local = if name == "default" {
@@ -541,21 +580,35 @@ impl EsmExports {
                         .unwrap_or_default();
 
                     if *mutable {
-                        Some(quote!(
-                            "([() => $local, ($new) => $local = $new])" as Expr,
-                            local = Ident::new(local.into(), DUMMY_SP, ctxt),
-                            new = Ident::new(format!("new_{name}").into(), DUMMY_SP, ctxt),
+                        Some((
+                            quote!(
+                                "() => $local" as Expr,
+                                local = Ident::new(local.clone().into(), DUMMY_SP, ctxt),
+                            ),
+                            Some(quote!(
+                                "($new) => $local = $new" as Expr,
+                                local = Ident::new(local.into(), DUMMY_SP, ctxt),
+                                new = Ident::new(format!("new_{name}").into(), DUMMY_SP, ctxt),
+                            )),
                         ))
                     } else {
-                        Some(quote!(
-                            "(() => $local)" as Expr,
-                            local = Ident::new((name as &str).into(), DUMMY_SP, ctxt)
+                        Some((
+                            quote!(
+                                "() => $local" as Expr,
+                                local = Ident::new((name as &str).into(), DUMMY_SP, ctxt)
+                            ),
+                            None,
                         ))
                     }
                 }
                 EsmExport::ImportedBinding(esm_ref, name, mutable) => {
                     let referenced_asset =
                         ReferencedAsset::from_resolve_result(esm_ref.resolve_reference()).await?;
+                    let name = if let ReferencedAsset::Some(asset) = &*referenced_asset {
+                        mangled_export_name(module_graph, chunking_context, *asset, name).await?
+                    } else {
+                        name.clone()
+                    };
                     referenced_asset.get_ident(
                         chunking_context
                     ).await?.map(|ident| {
@@ -570,28 +623,25 @@ impl EsmExports {
                                 span: DUMMY_SP,
                                 expr: Box::new(Expr::Lit(Lit::Str(Str {
                                     span: DUMMY_SP,
-                                    value: (name as &str).into(),
+                                    value: name.as_str().into(),
                                     raw: None,
                                 }))),
                             }),
                         };
-                        if *mutable {
-                            quote!(
-                                "([() => $expr, ($new) => $lhs = $new])" as Expr,
-                                expr: Expr = Expr::Member(expr.clone()),
-                                lhs: AssignTarget = AssignTarget::Simple(SimpleAssignTarget::Member(expr)),
-                                new = Ident::new(
-                                    format!("new_{name}").into(),
-                                    DUMMY_SP,
-                                    Default::default()
-                                ),
-                            )
-                        } else {
-                            quote!(
-                                "(() => $expr)" as Expr,
-                                expr: Expr = Expr::Member(expr),
-                            )
-                        }
+                        let getter = quote!(
+                            "() => $expr" as Expr,
+                            expr: Expr = Expr::Member(expr.clone()),
+                        );
+                        let setter = mutable.then(|| quote!(
+                            "($new) => $lhs = $new" as Expr,
+                            lhs: AssignTarget = AssignTarget::Simple(SimpleAssignTarget::Member(expr)),
+                            new = Ident::new(
+                                format!("new_{name}").into(),
+                                DUMMY_SP,
+                                Default::default()
+                            ),
+                        ));
+                        (getter, setter)
                     })
                 }
                 EsmExport::ImportedNamespace(esm_ref) => {
@@ -601,14 +651,44 @@ impl EsmExports {
                         .get_ident(chunking_context)
                         .await?
                         .map(|ident| {
-                            quote!(
-                                "(() => $imported)" as Expr,
-                                imported = Ident::new(ident.into(), DUMMY_SP, Default::default())
+                            (
+                                quote!(
+                                    "() => $imported" as Expr,
+                                    imported =
+                                        Ident::new(ident.into(), DUMMY_SP, Default::default())
+                                ),
+                                None,
                             )
                         })
                 }
             };
-            if let Some(expr) = expr {
+            if let Some((getter, setter)) = accessors {
+                let expr = if merged {
+                    // A property descriptor for `Object.defineProperties`
+                    match setter {
+                        Some(setter) => quote!(
+                            "({ get: $getter, set: $setter, enumerable: true })" as Expr,
+                            getter: Expr = getter,
+                            setter: Expr = setter,
+                        ),
+                        None => quote!(
+                            "({ get: $getter, enumerable: true })" as Expr,
+                            getter: Expr = getter,
+                        ),
+                    }
+                } else {
+                    match setter {
+                        Some(setter) => quote!(
+                            "([$getter, $setter])" as Expr,
+                            getter: Expr = getter,
+                            setter: Expr = setter,
+                        ),
+                        None => quote!("($getter)" as Expr, getter: Expr = getter),
+                    }
+                };
+                let exported = export_mangling
+                    .and_then(|export_mangling| export_mangling.get(exported))
+                    .unwrap_or(exported);
                 props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
                     key: PropName::Str(Str {
                         span: DUMMY_SP,
@@ -632,8 +712,36 @@ impl EsmExports {
             None
         };
 
+        let mut visitors = Vec::new();
+        unused_locals.retain(|id| !used_locals.contains(id));
+        // When the module is only imported for its side effects, none of its bindings are used and
+        // only the statements with side effects need to be kept
+        let evaluation_only = matches!(used_exports, Some(ModuleExportUsageInfo::Evaluation));
+        if !unused_locals.is_empty() || evaluation_only {
+            if let Some(ParseResult::Ok {
+                comments,
+                eval_context,
+                ..
+            }) = parsed.as_deref()
+            {
+                let comments = comments.clone();
+                let unresolved_mark = eval_context.unresolved_mark;
+                visitors.push(create_visitor!(visit_mut_program(program: &mut Program) {
+                    if let Program::Module(module) = program {
+                        let checker = SideEffectsChecker::new(&*comments, unresolved_mark);
+                        remove_unused_declarations(
+                            module,
+                            &unused_locals,
+                            evaluation_only,
+                            &checker,
+                        );
+                    }
+                }));
+            }
+        }
+
         Ok(CodeGeneration::new(
-            vec![],
+            visitors,
             [dynamic_stmt
                 .clone()
                 .map(|stmt| CodeGenerationHoistedStmt::new("__turbopack_dynamic__".into(), stmt))]
@@ -642,11 +750,99 @@ impl EsmExports {
             .collect(),
             vec![CodeGenerationHoistedStmt::new(
                 "__turbopack_esm__".into(),
-                quote!("$turbopack_esm($getters);" as Stmt,
-                    turbopack_esm: Expr = TURBOPACK_ESM.into(),
-                    getters: Expr = getters.clone()
-                ),
+                if merged {
+                    // The exports object is only needed when the exports of the merged module are
+                    // used
+                    quote!("var $exports = $init;" as Stmt,
+                        exports = Ident::new(merged_exports_ident().into(), DUMMY_SP, Default::default()),
+                        init: Expr = pure_call(quote!(
+                            "Object.defineProperties({}, $getters)" as Expr,
+                            getters: Expr = getters.clone()
+                        ))
+                    )
+                } else {
+                    quote!("$turbopack_esm($getters);" as Stmt,
+                        turbopack_esm: Expr = TURBOPACK_ESM.into(),
+                        getters: Expr = getters.clone()
+                    )
+                },
             )],
         ))
     }
 }
+
+/// Removes the side effect free top level declarations that only declare `unused` bindings, as
+/// long as no other top level item refers to them. Repeats until nothing changes, since removing a
+/// declaration can make another one unreferenced.
+///
+/// With `evaluation_only` every binding is unused, and side effect free expression statements are
+/// removed as well.
+fn remove_unused_declarations(
+    module: &mut swc_core::ecma::ast::Module,
+    unused: &FxHashSet<Id>,
+    evaluation_only: bool,
+    checker: &SideEffectsChecker,
+) {
+    loop {
+        let referenced_ids = module
+            .body
+            .iter()
+            .map(|item| {
+                let mut ids = FxHashSet::default();
+                item.visit_with(&mut IdCollector(&mut ids));
+                ids
+            })
+            .collect::<Vec<_>>();
+        let mut references = FxHashMap::<&Id, usize>::default();
+        for id in referenced_ids.iter().flatten() {
+            *references.entry(id).or_default() += 1;
+        }
+
+        let len = module.body.len();
+        let mut index = 0;
+        module.body.retain(|item| {
+            let item_index = index;
+            index += 1;
+            let ModuleItem::Stmt(stmt) = item else {
+                return true;
+            };
+            let decl = match stmt {
+                Stmt::Decl(decl) => decl,
+                Stmt::Expr(_) | Stmt::Empty(_) => {
+                    return !evaluation_only || !checker.is_pure_stmt(stmt)
+                }
+                _ => return true,
+            };
+            let declared = match decl {
+                Decl::Fn(f) => vec![f.ident.to_id()],
+                Decl::Class(c) => vec![c.ident.to_id()],
+                Decl::Var(v) => v
+                    .decls
+                    .iter()
+                    .filter_map(|d| d.name.as_ident().map(|i| i.to_id()))
+                    .collect(),
+                _ => return true,
+            };
+            let removable = !declared.is_empty()
+                && declared.iter().all(|id| {
+                    (evaluation_only || unused.contains(id))
+                        // Only referenced by the declaration itself
+                        && references.get(id).copied() == Some(1)
+                        && referenced_ids[item_index].contains(id)
+                })
+                && checker.is_pure_decl(decl);
+            !removable
+        });
+        if module.body.len() == len {
+            break;
+        }
+    }
+}
+
+struct IdCollector<'a>(&'a mut FxHashSet<Id>);
+
+impl Visit for IdCollector<'_> {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.0.insert(ident.to_id());
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexMap, ResolvedVc, Vc};
+use turbopack_core::{
+    chunk::ChunkingContext,
+    module_graph::{export_usage::ModuleExportUsageInfo, ModuleGraph},
+};
+
+use crate::chunk::{EcmascriptChunkPlaceable, EcmascriptExports};
+
+/// Short names for the exports of a module, by their original names.
+#[turbo_tasks::value(transparent)]
+pub struct ExportMangling(FxIndexMap<RcStr, RcStr>);
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionExportMangling(Option<ResolvedVc<ExportMangling>>);
+
+/// Computes short names for the exports of a module when export mangling is enabled.
+///
+/// Exports can only be renamed when every access by name is known, which is the case when all
+/// importers use specific exports ([ModuleExportUsageInfo::Exports]). Modules that are
+/// re-exported by `export *` or whose namespace object is read by name, and modules with exports
+/// that are copied at runtime from modules that couldn't be analyzed, keep their names.
+#[turbo_tasks::function]
+pub async fn export_mangling(
+    module_graph: Vc<ModuleGraph>,
+    chunking_context: Vc<Box<dyn ChunkingContext>>,
+    module: ResolvedVc<Box<dyn EcmascriptChunkPlaceable>>,
+) -> Result<Vc<OptionExportMangling>> {
+    if !*chunking_context.is_export_mangling_enabled().await? {
+        return Ok(Vc::cell(None));
+    }
+    let export_usage_info = module_graph.export_usage_info().await?;
+    let module_as_module = ResolvedVc::upcast(module);
+    if !matches!(
+        export_usage_info.used_exports(module_as_module),
+        ModuleExportUsageInfo::Exports(_)
+    ) || export_usage_info.is_star_reexported(module_as_module)
+        || export_usage_info.is_namespace_accessed(module_as_module)
+    {
+        return Ok(Vc::cell(None));
+    }
+    let EcmascriptExports::EsmExports(exports) = &*module.get_exports().await? else {
+        return Ok(Vc::cell(None));
+    };
+    let expanded = exports.expand_exports().await?;
+    if !expanded.dynamic_exports.is_empty() {
+        return Ok(Vc::cell(None));
+    }
+
+    // All exports are renamed, unused ones included, so the short names can't collide with an
+    // original name. The exports are sorted, so the names are stable as long as the exports of
+    // the module don't change.
+    let mangling = expanded
+        .exports
+        .keys()
+        .enumerate()
+        .map(|(index, export)| (export.clone(), mangled_name(index)))
+        .collect();
+    Ok(Vc::cell(Some(ResolvedVc::cell(mangling))))
+}
+
+/// Returns the name an export of a module is accessed with, which is the original name unless
+/// the exports of the module are mangled.
+pub async fn mangled_export_name(
+    module_graph: Vc<ModuleGraph>,
+    chunking_context: Vc<Box<dyn ChunkingContext>>,
+    module: ResolvedVc<Box<dyn EcmascriptChunkPlaceable>>,
+    export: &RcStr,
+) -> Result<RcStr> {
+    Ok(
+        match *export_mangling(module_graph, chunking_context, *module).await? {
+            Some(mangling) => mangling.await?.get(export).cloned(),
+            None => None,
+        }
+        .unwrap_or_else(|| export.clone()),
+    )
+}
+
+/// Returns `a`..`z`, `A`..`Z`, `aa`, `ab`, ... for consecutive indices.
+pub(crate) fn mangled_name(mut index: usize) -> RcStr {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let mut name = String::new();
+    loop {
+        name.push(CHARS[index % CHARS.len()] as char);
+        index /= CHARS.len();
+        if index == 0 {
+            break;
+        }
+        index -= 1;
+    }
+    name.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mangled_name;
+
+    #[test]
+    fn mangled_names_are_unique() {
+        assert_eq!(mangled_name(0), "a");
+        assert_eq!(mangled_name(51), "Z");
+        assert_eq!(mangled_name(52), "aa");
+        assert_eq!(mangled_name(53), "ba");
+        let names = (0..10_000)
+            .map(mangled_name)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(names.len(), 10_000);
+    }
+}
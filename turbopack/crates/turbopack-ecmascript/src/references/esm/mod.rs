@@ -2,9 +2,11 @@ pub(crate) mod base;
 pub(crate) mod binding;
 pub(crate) mod dynamic;
 pub(crate) mod export;
+pub(crate) mod export_mangling;
 pub(crate) mod meta;
 pub(crate) mod module_id;
 pub(crate) mod module_item;
+pub(crate) mod scope_hoisting;
 pub(crate) mod url;
 
 pub use self::{
@@ -14,5 +16,6 @@ pub use self::{
     export::{EsmExport, EsmExports, FoundExportType},
     meta::{ImportMetaBinding, ImportMetaRef},
     module_item::EsmModuleItem,
+    scope_hoisting::MergedModuleContent,
     url::{UrlAssetReference, UrlRewriteBehavior},
 };
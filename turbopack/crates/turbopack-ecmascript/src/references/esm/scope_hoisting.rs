@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{trace::TraceRawVcs, NonLocalValue, ResolvedVc, Vc};
+use turbopack_core::{
+    chunk::{ChunkableModule, ChunkingContext, ModuleChunkItemIdExt},
+    module::Module,
+    module_graph::ModuleGraph,
+};
+
+use crate::{magic_identifier, EcmascriptModuleAsset, EcmascriptModuleContent};
+
+/// The variable that holds the exports of a merged module. The function that evaluates the merged
+/// module returns it to the importer.
+pub(crate) fn merged_exports_ident() -> String {
+    magic_identifier::mangle("merged exports")
+}
+
+/// The name of the function that evaluates a merged module. It's declared next to the module
+/// factory of the importer.
+pub(crate) async fn merged_module_function_name(
+    module: Vc<Box<dyn ChunkableModule>>,
+    chunking_context: Vc<Box<dyn ChunkingContext>>,
+) -> Result<String> {
+    let id = module.chunk_item_id(chunking_context).await?;
+    Ok(magic_identifier::mangle(&format!("merged module {}", id)))
+}
+
+/// The content of a module that is merged into the module factory of its importer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
+pub struct MergedModuleContent {
+    /// The name of the function that evaluates the module and returns its exports.
+    pub name: RcStr,
+    pub content: ResolvedVc<EcmascriptModuleContent>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct MergedModuleContents(Vec<MergedModuleContent>);
+
+/// Returns the contents of the modules that are merged into a module when scope hoisting is
+/// enabled. The modules that are merged into those are included too, since all of them are
+/// declared next to the module factory of the module.
+#[turbo_tasks::function]
+pub async fn merged_module_contents(
+    module_graph: ResolvedVc<ModuleGraph>,
+    chunking_context: ResolvedVc<Box<dyn ChunkingContext>>,
+    module: ResolvedVc<Box<dyn Module>>,
+) -> Result<Vc<MergedModuleContents>> {
+    if !*chunking_context.is_scope_hoisting_enabled().await? {
+        return Ok(Vc::cell(vec![]));
+    }
+    let merged_modules_info = module_graph.merged_modules_info().await?;
+    let mut contents = vec![];
+    for &merged_module in merged_modules_info.merged_modules(module) {
+        let merged_module = ResolvedVc::try_downcast_type::<EcmascriptModuleAsset>(merged_module)
+            .context("Only ecmascript modules can be merged into their importer")?;
+        let name =
+            merged_module_function_name(Vc::upcast(*merged_module), *chunking_context).await?;
+        let content = merged_module
+            .module_content(*module_graph, *chunking_context, None)
+            .to_resolved()
+            .await?;
+        contents.extend(content.await?.merged_modules.iter().cloned());
+        contents.push(MergedModuleContent {
+            name: name.into(),
+            content,
+        });
+    }
+    Ok(Vc::cell(contents))
+}
@@ -114,6 +114,7 @@ impl CachedExternalModule {
             inner_code: code.build(),
             source_map: None,
             is_esm: self.external_type != CachedExternalType::CommonJs,
+            merged_modules: vec![],
         }
         .cell())
     }
@@ -1,6 +1,7 @@
 pub mod amd;
 pub mod async_module;
 pub mod cjs;
+pub mod const_enum;
 pub mod constant_condition;
 pub mod constant_value;
 pub mod dynamic_expression;
@@ -18,9 +19,17 @@ pub mod unreachable;
 pub mod util;
 pub mod worker;
 
-use std::{borrow::Cow, collections::BTreeMap, future::Future, mem::take, ops::Deref, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    future::Future,
+    mem::take,
+    ops::Deref,
+    sync::Arc,
+};
 
 use anyhow::{bail, Result};
+use const_enum::ConstEnumMemberCodeGen;
 use constant_condition::{ConstantConditionCodeGen, ConstantConditionValue};
 use constant_value::ConstantValueCodeGen;
 use either::Either;
@@ -56,16 +65,18 @@ use turbo_tasks::{
     trace::TraceRawVcs, FxIndexMap, FxIndexSet, NonLocalValue, ReadRef, ResolvedVc, TaskInput,
     TryJoinIterExt, Upcast, Value, ValueToString, Vc,
 };
-use turbo_tasks_fs::{rope::Rope, FileSystemPath};
+use turbo_tasks_fs::{glob::Glob, rope::Rope, FileSystemPath};
 use turbopack_core::{
     compile_time_info::{
         CompileTimeInfo, DefineableNameSegment, FreeVarReference, FreeVarReferences,
         FreeVarReferencesIndividual,
     },
+    context::AssetContext,
     environment::Rendering,
     error::PrettyPrintError,
     issue::{analyze::AnalyzeIssue, IssueExt, IssueSeverity, IssueSource, StyledString},
     module::Module,
+    module_graph::{async_chunk_group_options::AsyncChunkGroupOptions, export_usage::ExportUsage},
     reference::{ModuleReference, ModuleReferences, SourceMapReference},
     reference_type::{CommonJsReferenceSubType, ReferenceType},
     resolve::{
@@ -126,8 +137,10 @@ use crate::{
     analyzer::{
         builtin::early_replace_builtin,
         graph::{ConditionalKind, EffectArg, EvalContext, VarGraph},
-        imports::{ImportAnnotations, ImportAttributes, ImportedSymbol, Reexport},
+        imports::{ImportAnnotations, ImportAttributes, ImportMap, ImportedSymbol, Reexport},
+        namespace_reads::{dynamic_import_accessed_exports, require_accessed_exports},
         parse_require_context,
+        side_effects::has_pure_top_level,
         top_level_await::has_top_level_await,
         ConstantNumber, ConstantString, JsValueUrlKind, RequireContextValue,
     },
@@ -172,6 +185,8 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub exports: ResolvedVc<EcmascriptExports>,
     pub async_module: ResolvedVc<OptionAsyncModule>,
     pub has_side_effect_free_directive: bool,
+    /// `true` when the module has no imports and its top level is free of side effects.
+    pub has_pure_top_level: bool,
     /// `true` when the analysis was successful.
     pub successful: bool,
     pub source_map: ResolvedVc<OptionStringifiedSourceMap>,
@@ -225,6 +240,7 @@ pub struct AnalyzeEcmascriptModuleResultBuilder {
     successful: bool,
     source_map: Option<ResolvedVc<OptionStringifiedSourceMap>>,
     has_side_effect_free_directive: bool,
+    has_pure_top_level: bool,
 }
 
 impl AnalyzeEcmascriptModuleResultBuilder {
@@ -243,6 +259,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             successful: false,
             source_map: None,
             has_side_effect_free_directive: false,
+            has_pure_top_level: false,
         }
     }
 
@@ -280,6 +297,14 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.esm_evaluation_references.insert(idx);
     }
 
+    /// Removes an ESM reference from the analysis result, e.g. when its bindings are only used
+    /// in eliminated code.
+    pub fn remove_esm_reference(&mut self, idx: usize) {
+        self.esm_references.remove(&idx);
+        self.esm_local_references.remove(&idx);
+        self.esm_evaluation_references.remove(&idx);
+    }
+
     /// Adds a codegen to the analysis result.
     pub fn add_code_gen<C>(&mut self, code_gen: C)
     where
@@ -308,6 +333,11 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.has_side_effect_free_directive = value;
     }
 
+    /// Set whether this module has no imports and its top level is free of side effects.
+    pub fn set_has_pure_top_level(&mut self, value: bool) {
+        self.has_pure_top_level = value;
+    }
+
     /// Sets whether the analysis was successful.
     pub fn set_successful(&mut self, successful: bool) {
         self.successful = successful;
@@ -423,6 +453,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
                 exports: self.exports.resolved_cell(),
                 async_module: self.async_module,
                 has_side_effect_free_directive: self.has_side_effect_free_directive,
+                has_pure_top_level: self.has_pure_top_level,
                 successful: self.successful,
                 source_map,
             },
@@ -449,11 +480,21 @@ struct AnalysisState<'a> {
     // There can be many references to import.meta, but only the first should hoist
     // the object allocation.
     first_import_meta: bool,
+    /// Whether the module body is wrapped in an async function because it uses top level await.
+    top_level_await: bool,
     tree_shaking_mode: Option<TreeShakingMode>,
     import_externals: bool,
     ignore_dynamic_requests: bool,
     url_rewrite_behavior: Option<UrlRewriteBehavior>,
     free_var_references: ReadRef<FreeVarReferencesIndividual>,
+    /// The exports that are read from the namespace objects of `import()` calls, by the span of
+    /// the call.
+    dynamic_import_exports: FxHashMap<Span, Vec<RcStr>>,
+    /// The properties that are read from the results of `require()` calls, by the span of the
+    /// call.
+    require_exports: FxHashMap<Span, Vec<RcStr>>,
+    /// The imports of the module, which also contain the magic comments of the `import()` calls.
+    imports: &'a ImportMap,
 }
 
 impl AnalysisState<'_> {
@@ -535,6 +576,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
         EcmascriptModuleAssetType::TypescriptDeclaration => true,
         EcmascriptModuleAssetType::Ecmascript => false,
     };
+    let is_typescript = !matches!(&*ty, EcmascriptModuleAssetType::Ecmascript);
 
     let parsed = if let Some(part) = part {
         let parsed = parse(*source, ty, *transforms);
@@ -624,6 +666,9 @@ pub(crate) async fn analyse_ecmascript_module_internal(
         _ => false,
     });
     analysis.set_has_side_effect_free_directive(has_side_effect_free_directive);
+    analysis.set_has_pure_top_level(GLOBALS.set(globals, || {
+        has_pure_top_level(program, &**comments, eval_context.unresolved_mark)
+    }));
 
     let compile_time_info = compile_time_info_for_module_type(
         *raw_module.compile_time_info,
@@ -745,6 +790,10 @@ pub(crate) async fn analyse_ecmascript_module_internal(
     let span = tracing::info_span!("esm import references");
     let import_references = async {
         let mut import_references = Vec::with_capacity(eval_context.imports.references().len());
+        let mut reexports = FxHashMap::<usize, Vec<&Reexport>>::default();
+        for (i, reexport) in eval_context.imports.reexports() {
+            reexports.entry(i).or_default().push(reexport);
+        }
         for (i, r) in eval_context.imports.references().enumerate() {
             let mut should_add_evaluation = false;
             let reference = EsmAssetReference::new(
@@ -791,6 +840,11 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                         None
                     }
                 },
+                export_usage(
+                    &r.imported_symbol,
+                    reexports.get(&i).map_or(&[][..], |r| &r[..]),
+                    eval_context.imports.default_import_properties(i),
+                ),
                 import_externals,
             )
             .resolved_cell();
@@ -973,7 +1027,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
         .await?;
     }
     let span = tracing::info_span!("async module handling");
-    async {
+    let top_level_await = async {
         let top_level_await_span =
             set_handler_and_globals(&handler, globals, || has_top_level_await(program));
         let has_top_level_await = top_level_await_span.is_some();
@@ -999,11 +1053,16 @@ pub(crate) async fn analyse_ecmascript_module_internal(
             .await?
             .emit();
         }
-        anyhow::Ok(())
+        anyhow::Ok(has_top_level_await && eval_context.is_esm(specified_type))
     }
     .instrument(span)
     .await?;
 
+    // The ESM references with imported bindings that are used in live code and in branches
+    // eliminated by compile-time conditions
+    let mut used_imported_bindings = FxHashSet::default();
+    let mut eliminated_imported_bindings = FxHashSet::default();
+
     let span = tracing::info_span!("effects processing");
     async {
         let effects = take(&mut var_graph.effects);
@@ -1017,6 +1076,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
             fun_args_values: Default::default(),
             var_cache: Default::default(),
             first_import_meta: true,
+            top_level_await,
             tree_shaking_mode: options.tree_shaking_mode,
             import_externals: options.import_externals,
             ignore_dynamic_requests: options.ignore_dynamic_requests,
@@ -1026,6 +1086,9 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                 .free_var_references
                 .individual()
                 .await?,
+            dynamic_import_exports: dynamic_import_accessed_exports(program),
+            require_exports: require_accessed_exports(program),
+            imports: &eval_context.imports,
         };
 
         enum Action {
@@ -1080,6 +1143,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
 
                     macro_rules! inactive {
                         ($block:ident) => {
+                            $block.collect_imported_bindings(&mut eliminated_imported_bindings);
                             analysis.add_code_gen(Unreachable::new($block.range.clone()));
                         };
                     }
@@ -1227,6 +1291,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                     ast_path,
                     span,
                     in_try,
+                    in_async_function,
                     new,
                 } => {
                     if let Some(ignored) = &ignore_effect_span {
@@ -1249,6 +1314,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                         &add_effects,
                         &mut analysis,
                         in_try,
+                        in_async_function.unwrap_or(analysis_state.top_level_await),
                         new,
                     )
                     .await?;
@@ -1326,6 +1392,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                         &add_effects,
                         &mut analysis,
                         in_try,
+                        false,
                         new,
                     )
                     .await?;
@@ -1366,6 +1433,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                 Effect::ImportedBinding {
                     esm_reference_index,
                     export,
+                    member,
                     ast_path,
                     span: _,
                     in_try: _,
@@ -1374,6 +1442,25 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                         continue;
                     };
 
+                    // Only TypeScript modules can refer to `const enum`s of other modules
+                    if let (true, Some(export), Some((prop, member_ast_path))) =
+                        (is_typescript, &export, member)
+                    {
+                        let const_enums = r.referenced_const_enums().await?;
+                        if let Some(value) = const_enums
+                            .get(export)
+                            .and_then(|members| members.get(&prop))
+                        {
+                            analysis.add_code_gen(ConstEnumMemberCodeGen::new(
+                                value.clone(),
+                                member_ast_path.into(),
+                            ));
+                            continue;
+                        }
+                    }
+
+                    used_imported_bindings.insert(esm_reference_index);
+
                     if let Some("__turbopack_module_id__") = export.as_deref() {
                         analysis.add_reference_code_gen(
                             EsmModuleIdAssetReference::new(*r),
@@ -1397,6 +1484,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                                                 r_ref.issue_source.clone(),
                                                 Value::new(r_ref.annotations.clone()),
                                                 Some(ModulePart::export(export.clone())),
+                                                ExportUsage::Named(export.clone()),
                                                 r_ref.import_externals,
                                             )
                                             .resolved_cell()
@@ -1447,6 +1535,16 @@ pub(crate) async fn analyse_ecmascript_module_internal(
     .instrument(span)
     .await?;
 
+    remove_eliminated_imports(
+        &mut analysis,
+        &eval_context.imports,
+        &import_references,
+        &eliminated_imported_bindings,
+        &used_imported_bindings,
+        raw_module.asset_context.side_effect_free_packages(),
+    )
+    .await?;
+
     analysis.set_successful(true);
 
     collector.emit().await?;
@@ -1462,6 +1560,55 @@ pub(crate) async fn analyse_ecmascript_module_internal(
         .await
 }
 
+/// Removes the static imports whose bindings are only used in branches that were eliminated by
+/// compile-time conditions, e.g. `process.env.NODE_ENV` checks, so they don't keep side effect free
+/// modules in the module graph.
+async fn remove_eliminated_imports(
+    analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
+    imports: &ImportMap,
+    import_references: &[ResolvedVc<EsmAssetReference>],
+    eliminated_imported_bindings: &FxHashSet<usize>,
+    used_imported_bindings: &FxHashSet<usize>,
+    side_effect_free_packages: Vc<Glob>,
+) -> Result<()> {
+    if eliminated_imported_bindings.is_empty() {
+        return Ok(());
+    }
+
+    // The references of the imports of a module, including the one for the evaluation of the
+    // module, can only be removed together
+    let mut references_by_module = FxIndexMap::<_, Vec<usize>>::default();
+    for (i, r) in imports.references().enumerate() {
+        references_by_module
+            .entry((&r.module_path, &r.annotations))
+            .or_default()
+            .push(i);
+    }
+
+    for references in references_by_module.values() {
+        if !references
+            .iter()
+            .any(|i| eliminated_imported_bindings.contains(i))
+            || references.iter().any(|i| {
+                used_imported_bindings.contains(i) || analysis.esm_reexport_references.contains(i)
+            })
+        {
+            continue;
+        }
+        if !*import_references[references[0]]
+            .is_referenced_module_side_effect_free(side_effect_free_packages)
+            .await?
+        {
+            continue;
+        }
+        for &i in references {
+            analysis.remove_esm_reference(i);
+        }
+    }
+
+    Ok(())
+}
+
 #[turbo_tasks::function]
 async fn compile_time_info_for_module_type(
     compile_time_info: Vc<CompileTimeInfo>,
@@ -1532,6 +1679,7 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
     add_effects: &G,
     analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
     in_try: bool,
+    in_async_context: bool,
     new: bool,
 ) -> Result<()> {
     let &AnalysisState {
@@ -1672,6 +1820,7 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
                     add_effects,
                     analysis,
                     in_try,
+                    in_async_context,
                     new,
                 ))
                 .await?;
@@ -1718,6 +1867,16 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
                         return Ok(());
                     }
                 }
+                let export_usage = match state.dynamic_import_exports.get(&span) {
+                    Some(exports) => ExportUsage::NamespaceProperties(exports.clone()),
+                    None => ExportUsage::All,
+                };
+                let attributes = state.imports.get_attributes(span);
+                let chunk_group_options = AsyncChunkGroupOptions {
+                    name: attributes.chunk_name.clone(),
+                    prefetch: attributes.prefetch,
+                    preload: attributes.preload,
+                };
                 analysis.add_reference_code_gen(
                     EsmAsyncAssetReference::new(
                         origin,
@@ -1726,6 +1885,8 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
                         Value::new(import_annotations),
                         in_try,
                         state.import_externals,
+                        export_usage,
+                        chunk_group_options,
                     ),
                     ast_path.to_vec().into(),
                 );
@@ -1758,12 +1919,18 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
                         return Ok(());
                     }
                 }
+                let export_usage = match state.require_exports.get(&span) {
+                    Some(exports) => ExportUsage::NamespaceProperties(exports.clone()),
+                    None => ExportUsage::All,
+                };
                 analysis.add_reference_code_gen(
                     CjsRequireAssetReference::new(
                         origin,
                         Request::parse(Value::new(pat)).to_resolved().await?,
                         issue_source(source, span),
                         in_try,
+                        in_async_context,
+                        export_usage,
                     ),
                     ast_path.to_vec().into(),
                 );
@@ -2386,6 +2553,36 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
     Ok(())
 }
 
+/// Returns how an import or re-export uses the exports of the referenced module. A reference is
+/// shared by all re-exports of the same name in a statement, which are treated as a direct use of
+/// the name. `default_properties` are the properties read from a default import that is only used
+/// to read properties.
+fn export_usage(
+    imported_symbol: &ImportedSymbol,
+    reexports: &[&Reexport],
+    default_properties: Option<&BTreeSet<RcStr>>,
+) -> ExportUsage {
+    match (imported_symbol, reexports) {
+        (ImportedSymbol::Part(_) | ImportedSymbol::PartEvaluation(_), _) => ExportUsage::All,
+        (_, [Reexport::Star]) => ExportUsage::ReExportAll,
+        (_, [Reexport::Namespace { exported }]) => {
+            ExportUsage::ReExportNamespace(exported.as_str().into())
+        }
+        (_, [Reexport::Named { imported, exported }]) => ExportUsage::ReExport {
+            imported: imported.as_str().into(),
+            exported: exported.as_str().into(),
+        },
+        (ImportedSymbol::ModuleEvaluation, _) => ExportUsage::Evaluation,
+        (ImportedSymbol::Symbol(name), _) => match default_properties {
+            Some(properties) if name == "default" => {
+                ExportUsage::DefaultExportProperties(properties.iter().cloned().collect())
+            }
+            _ => ExportUsage::Named(name.as_str().into()),
+        },
+        (ImportedSymbol::Exports, _) => ExportUsage::All,
+    }
+}
+
 async fn handle_member(
     ast_path: &[AstParentKind],
     link_obj: impl Future<Output = Result<JsValue>> + Send + Sync,
@@ -2574,6 +2771,7 @@ async fn handle_free_var_reference(
                             }
                             None => None,
                         },
+                        export.clone().map_or(ExportUsage::All, ExportUsage::Named),
                         state.import_externals,
                     )
                     .resolved_cell())
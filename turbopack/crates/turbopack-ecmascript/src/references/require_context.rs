@@ -47,7 +47,7 @@ use crate::{
         AstPath,
     },
     runtime_functions::{TURBOPACK_EXPORT_VALUE, TURBOPACK_MODULE_CONTEXT, TURBOPACK_REQUIRE},
-    utils::module_id_to_lit,
+    utils::{module_id_to_lit, pure_call},
     EcmascriptChunkPlaceable,
 };
 
@@ -327,12 +327,17 @@ impl RequireContextAssetReferenceCodeGen {
 
         visitors.push(create_visitor!(self.path, visit_mut_expr(expr: &mut Expr) {
             if let Expr::Call(_) = expr {
-                *expr = quote!(
-                    "$turbopack_module_context($turbopack_require($id))" as Expr,
-                    turbopack_module_context: Expr = TURBOPACK_MODULE_CONTEXT.into(),
+                // The context map module only declares the map, so neither call has side effects
+                let require = pure_call(quote!(
+                    "$turbopack_require($id)" as Expr,
                     turbopack_require: Expr = TURBOPACK_REQUIRE.into(),
                     id: Expr = module_id_to_lit(&module_id)
-                );
+                ));
+                *expr = pure_call(quote!(
+                    "$turbopack_module_context($require)" as Expr,
+                    turbopack_module_context: Expr = TURBOPACK_MODULE_CONTEXT.into(),
+                    require: Expr = require,
+                ));
             }
         }));
 
@@ -80,7 +80,14 @@ impl EcmascriptChunkItem for EcmascriptModuleFacadeChunkItem {
                 )
                 .await?,
             exports
-                .code_generation(*self.module_graph, *chunking_context, None)
+                .code_generation(
+                    *self.module_graph,
+                    *chunking_context,
+                    None,
+                    None,
+                    None,
+                    false,
+                )
                 .await?,
         ];
         let code_gens = esm_code_gens.iter().chain(additional_code_gens.iter());
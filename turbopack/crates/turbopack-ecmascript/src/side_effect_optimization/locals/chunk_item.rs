@@ -55,6 +55,7 @@ impl EcmascriptChunkItem for EcmascriptModuleLocalsChunkItem {
             .await?;
 
         let content = EcmascriptModuleContent::new(EcmascriptModuleContentOptions {
+            module: ResolvedVc::upcast(self.module),
             parsed,
             ident: self.module.ident().to_resolved().await?,
             specified_module_type: module_type_result.module_type,
@@ -68,6 +69,7 @@ impl EcmascriptChunkItem for EcmascriptModuleLocalsChunkItem {
             original_source_map: analyze_result.source_map,
             exports,
             async_module_info,
+            keep_unused_exports: original_module.await?.options.await?.keep_unused_exports,
         });
 
         Ok(EcmascriptChunkItemContent::new(
@@ -32,6 +32,9 @@ pub enum EcmascriptInputTransform {
     CommonJs,
     Plugin(ResolvedVc<TransformPlugin>),
     PresetEnv(ResolvedVc<Environment>),
+    /// Compiles all syntax down to ES5, including generators and async functions (with
+    /// regenerator), regardless of the target environment. Used for legacy `nomodule` output.
+    PresetEnvEs5,
     React {
         #[serde(default)]
         development: bool,
@@ -131,8 +134,10 @@ impl EcmascriptInputTransform {
         } = ctx;
         match self {
             EcmascriptInputTransform::GlobalTypeofs { window_value } => {
+                // `document` is defined exactly when `window` is
                 let mut typeofs: FxHashMap<Atom, Atom> = Default::default();
                 typeofs.insert(Atom::from("window"), Atom::from(&**window_value));
+                typeofs.insert(Atom::from("document"), Atom::from(&**window_value));
 
                 program.mutate(inline_globals2(
                     Default::default(),
@@ -227,41 +232,20 @@ impl EcmascriptInputTransform {
             }
             EcmascriptInputTransform::PresetEnv(env) => {
                 let versions = env.runtime_versions().await?;
-                let config = swc_core::ecma::preset_env::Config {
+                let config = preset_env::Config {
                     targets: Some(Targets::Versions(*versions)),
                     mode: None, // Don't insert core-js polyfills
                     ..Default::default()
                 };
-
-                let module_program = std::mem::replace(program, Program::Module(Module::dummy()));
-
-                let module_program = if let Program::Script(Script {
-                    span,
-                    mut body,
-                    shebang,
-                }) = module_program
-                {
-                    Program::Module(Module {
-                        span,
-                        body: body.drain(..).map(ModuleItem::Stmt).collect(),
-                        shebang,
-                    })
-                } else {
-                    module_program
+                apply_preset_env(program, config, comments, top_level_mark, unresolved_mark);
+            }
+            EcmascriptInputTransform::PresetEnvEs5 => {
+                let config = preset_env::Config {
+                    force_all_transforms: true,
+                    mode: None, // Don't insert core-js polyfills
+                    ..Default::default()
                 };
-
-                // Explicit type annotation to ensure that we don't duplicate transforms in the
-                // final binary
-                *program = module_program.apply((
-                    preset_env::preset_env::<&'_ dyn Comments>(
-                        top_level_mark,
-                        Some(&comments),
-                        config,
-                        Assumptions::default(),
-                        &mut FeatureFlag::empty(),
-                    ),
-                    inject_helpers(unresolved_mark),
-                ));
+                apply_preset_env(program, config, comments, top_level_mark, unresolved_mark);
             }
             EcmascriptInputTransform::TypeScript {
                 // TODO(WEB-1213)
@@ -295,6 +279,45 @@ impl EcmascriptInputTransform {
     }
 }
 
+/// Applies preset-env with the given config. Scripts are converted to modules, so the helpers can
+/// be imported.
+fn apply_preset_env(
+    program: &mut Program,
+    config: preset_env::Config,
+    comments: &SwcComments,
+    top_level_mark: Mark,
+    unresolved_mark: Mark,
+) {
+    let module_program = std::mem::replace(program, Program::Module(Module::dummy()));
+
+    let module_program = if let Program::Script(Script {
+        span,
+        mut body,
+        shebang,
+    }) = module_program
+    {
+        Program::Module(Module {
+            span,
+            body: body.drain(..).map(ModuleItem::Stmt).collect(),
+            shebang,
+        })
+    } else {
+        module_program
+    };
+
+    // Explicit type annotation to ensure that we don't duplicate transforms in the final binary
+    *program = module_program.apply((
+        preset_env::preset_env::<&'_ dyn Comments>(
+            top_level_mark,
+            Some(&comments),
+            config,
+            Assumptions::default(),
+            &mut FeatureFlag::empty(),
+        ),
+        inject_helpers(unresolved_mark),
+    ));
+}
+
 pub fn remove_shebang(program: &mut Program) {
     match program {
         Program::Module(m) => {
@@ -64,6 +64,7 @@ impl EcmascriptChunkItem for EcmascriptModulePartChunkItem {
             .await?;
 
         let content = EcmascriptModuleContent::new(EcmascriptModuleContentOptions {
+            module: ResolvedVc::upcast(self.module),
             parsed,
             ident: self.module.ident().to_resolved().await?,
             specified_module_type: module_type_result.module_type,
@@ -77,6 +78,7 @@ impl EcmascriptChunkItem for EcmascriptModulePartChunkItem {
             original_source_map: analyze_ref.source_map,
             exports: analyze_ref.exports,
             async_module_info,
+            keep_unused_exports: module.full_module.await?.options.await?.keep_unused_exports,
         });
 
         Ok(EcmascriptChunkItemContent::new(
@@ -208,6 +210,7 @@ impl EcmascriptChunkItem for SideEffectsModuleChunkItem {
                 },
                 ..Default::default()
             },
+            merged_modules: vec![],
             placeholder_for_future_extensions: (),
         }
         .cell())
@@ -0,0 +1,234 @@
+use anyhow::Result;
+use rustc_hash::{FxHashMap, FxHashSet};
+use swc_core::{
+    common::{Span, Spanned},
+    ecma::{
+        ast::{Decl, ExportSpecifier, Ident, ModuleDecl, ModuleExportName, ModuleItem, Program},
+        utils::find_pat_ids,
+    },
+};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexSet, ResolvedVc, TryJoinIterExt, ValueToString, Vc};
+use turbo_tasks_fs::{glob::Glob, FileSystemPath};
+use turbopack_core::{
+    issue::{
+        Issue, IssueExt, IssueSeverity, IssueSource, IssueStage, OptionIssueSource,
+        OptionStyledString, StyledString,
+    },
+    module::Module,
+    module_graph::ModuleGraph,
+};
+
+use crate::{
+    chunk::{EcmascriptChunkPlaceable, EcmascriptExports},
+    parse::ParseResult,
+    EcmascriptModuleAsset,
+};
+
+/// Reports the exports of the modules in the project that no other module imports as issues, so
+/// they can be removed. The usage is merged over all modules that are created from the same file,
+/// so an export that is only used on the server or only on the client is not reported. Entry
+/// modules, modules in `node_modules` and modules whose path relative to `project_path` matches
+/// one of the `allowlist` globs are not reported.
+#[turbo_tasks::function]
+pub async fn report_unused_exports(
+    module_graph: Vc<ModuleGraph>,
+    project_path: Vc<FileSystemPath>,
+    allowlist: Vec<RcStr>,
+) -> Result<Vc<()>> {
+    let export_usage_info = module_graph.merged_export_usage_info().await?;
+    let project_path = project_path.await?;
+    let allowlist = if allowlist.is_empty() {
+        None
+    } else {
+        Some(Glob::alternatives(allowlist.into_iter().map(Glob::new).collect()).await?)
+    };
+
+    let graphs = module_graph.await?.get_graphs().await?;
+    let entries = graphs
+        .iter()
+        .flat_map(|graph| graph.entry_modules())
+        .collect::<FxHashSet<_>>();
+    let modules = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|node| node.module)
+        .filter(|module| !entries.contains(module))
+        .filter_map(ResolvedVc::try_downcast_type::<EcmascriptModuleAsset>)
+        .collect::<FxIndexSet<_>>();
+
+    // Report each file only once, even when it's part of several layers
+    let mut paths = FxHashSet::default();
+    let modules = modules
+        .into_iter()
+        .map(async |module| {
+            let path = module.ident().path().to_string().owned().await?;
+            Ok((path, module))
+        })
+        .try_join()
+        .await?
+        .into_iter()
+        .filter(|(path, _)| paths.insert(path.clone()))
+        .collect::<Vec<_>>();
+
+    modules
+        .into_iter()
+        .map(async |(path_str, module)| {
+            let path = module.ident().path().await?;
+            let Some(relative_path) = project_path.get_path_to(&path) else {
+                return Ok(());
+            };
+            if relative_path.split('/').any(|part| part == "node_modules")
+                || allowlist
+                    .as_ref()
+                    .is_some_and(|allowlist| allowlist.execute(relative_path))
+            {
+                return Ok(());
+            }
+
+            let EcmascriptExports::EsmExports(exports) = *module.get_exports().await? else {
+                return Ok(());
+            };
+            let used_exports = export_usage_info.used_exports(&path_str);
+            let unused_exports = exports
+                .await?
+                .exports
+                .keys()
+                .filter(|export| !used_exports.is_export_used(export))
+                .cloned()
+                .collect::<Vec<_>>();
+            if unused_exports.is_empty() {
+                return Ok(());
+            }
+
+            let spans = match &*module.parse().await? {
+                ParseResult::Ok { program, .. } => export_spans(program),
+                _ => Default::default(),
+            };
+            let source = module.await?.source;
+            for export in unused_exports {
+                let source = match spans.get(&export) {
+                    Some(span) => {
+                        IssueSource::from_swc_offsets(source, span.lo.to_u32(), span.hi.to_u32())
+                    }
+                    None => IssueSource::from_source_only(source),
+                };
+                UnusedExportIssue { export, source }.resolved_cell().emit();
+            }
+            anyhow::Ok(())
+        })
+        .try_join()
+        .await?;
+
+    Ok(Default::default())
+}
+
+/// Finds the spans of the export declarations of a module by exported name.
+fn export_spans(program: &Program) -> FxHashMap<RcStr, Span> {
+    let Program::Module(module) = program else {
+        return Default::default();
+    };
+
+    let mut spans = FxHashMap::default();
+    for item in &module.body {
+        let ModuleItem::ModuleDecl(decl) = item else {
+            continue;
+        };
+        match decl {
+            ModuleDecl::ExportDecl(export) => match &export.decl {
+                Decl::Class(class) => {
+                    spans.insert(class.ident.sym.as_str().into(), class.ident.span);
+                }
+                Decl::Fn(function) => {
+                    spans.insert(function.ident.sym.as_str().into(), function.ident.span);
+                }
+                Decl::Var(var) => {
+                    for ident in find_pat_ids::<_, Ident>(&**var) {
+                        spans.insert(ident.sym.as_str().into(), ident.span);
+                    }
+                }
+                _ => {}
+            },
+            ModuleDecl::ExportNamed(export) => {
+                for specifier in &export.specifiers {
+                    let exported = match specifier {
+                        ExportSpecifier::Named(specifier) => {
+                            specifier.exported.as_ref().unwrap_or(&specifier.orig)
+                        }
+                        ExportSpecifier::Namespace(specifier) => &specifier.name,
+                        ExportSpecifier::Default(specifier) => {
+                            spans.insert(
+                                specifier.exported.sym.as_str().into(),
+                                specifier.exported.span,
+                            );
+                            continue;
+                        }
+                    };
+                    let name = match exported {
+                        ModuleExportName::Ident(ident) => ident.sym.as_str(),
+                        ModuleExportName::Str(str) => str.value.as_str(),
+                    };
+                    spans.insert(name.into(), exported.span());
+                }
+            }
+            ModuleDecl::ExportDefaultDecl(export) => {
+                spans.insert("default".into(), export.span);
+            }
+            ModuleDecl::ExportDefaultExpr(export) => {
+                spans.insert("default".into(), export.span);
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+#[turbo_tasks::value(shared)]
+pub struct UnusedExportIssue {
+    export: RcStr,
+    source: IssueSource,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UnusedExportIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> Vc<IssueSeverity> {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<StyledString> {
+        StyledString::Line(vec![
+            StyledString::Text("Export ".into()),
+            StyledString::Code(self.export.clone()),
+            StyledString::Text(" is never imported".into()),
+        ])
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    fn stage(&self) -> Vc<IssueStage> {
+        IssueStage::Analysis.into()
+    }
+
+    #[turbo_tasks::function]
+    fn file_path(&self) -> Vc<FileSystemPath> {
+        self.source.file_path()
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Vc<OptionStyledString> {
+        Vc::cell(Some(
+            StyledString::Text(
+                "No module in the module graph imports this export, so it can probably be removed."
+                    .into(),
+            )
+            .resolved_cell(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> Vc<OptionIssueSource> {
+        Vc::cell(Some(self.source.clone()))
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use swc_core::{
-    common::DUMMY_SP,
+    common::{BytePos, Span, DUMMY_SP},
     ecma::{
         ast::{Expr, Lit, Str},
         visit::AstParentKind,
@@ -56,6 +56,15 @@ pub fn js_value_to_pattern(value: &JsValue) -> Pattern {
     result
 }
 
+/// Annotates a call generated by turbopack with `/*#__PURE__*/`, so minifiers can drop it when its
+/// result is unused.
+pub fn pure_call(mut expr: Expr) -> Expr {
+    if let Expr::Call(call) = &mut expr {
+        call.span = Span::new(BytePos::PURE, BytePos::PURE);
+    }
+    expr
+}
+
 const JS_MAX_SAFE_INTEGER: u64 = (1u64 << 53) - 1;
 
 pub fn module_id_to_lit(module_id: &ModuleId) -> Expr {
@@ -9,7 +9,7 @@ use turbopack_core::{
     },
     ident::AssetIdent,
     module::Module,
-    module_graph::ModuleGraph,
+    module_graph::{export_usage::ExportUsage, ModuleGraph},
     reference::{ModuleReference, ModuleReferences},
     resolve::ModuleResolveResult,
 };
@@ -107,6 +107,13 @@ impl ChunkableModuleReference for WorkerModuleReference {
             merge_tag: None,
         }))
     }
+
+    /// The worker entry is only evaluated. It communicates with the main thread through messages,
+    /// so none of its exports are used.
+    #[turbo_tasks::function]
+    fn export_usage(self: Vc<Self>) -> Vc<ExportUsage> {
+        ExportUsage::evaluation()
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -3,15 +3,17 @@
 //! JSON assets are parsed to ensure they contain valid JSON.
 //!
 //! When imported from ES modules, they produce a module that exports the
-//! JSON value as an object.
+//! JSON value as an object. With JSON tree shaking enabled, only the top level
+//! keys that importers read are included.
 
 #![feature(min_specialization)]
 #![feature(arbitrary_self_types)]
 #![feature(arbitrary_self_types_pointers)]
 
-use std::fmt::Write;
+use std::{borrow::Cow, fmt::Write};
 
 use anyhow::{bail, Error, Result};
+use rustc_hash::FxHashSet;
 use turbo_rcstr::RcStr;
 use turbo_tasks::{ResolvedVc, ValueToString, Vc};
 use turbo_tasks_fs::{glob::Glob, FileContent, FileJsonContent};
@@ -20,7 +22,7 @@ use turbopack_core::{
     chunk::{ChunkItem, ChunkType, ChunkableModule, ChunkingContext},
     ident::AssetIdent,
     module::Module,
-    module_graph::ModuleGraph,
+    module_graph::{export_usage::ModuleExportUsageInfo, ModuleGraph},
     source::Source,
 };
 use turbopack_ecmascript::{
@@ -55,6 +57,11 @@ impl Module for JsonModuleAsset {
     fn ident(&self) -> Vc<AssetIdent> {
         self.source.ident().with_modifier(modifier())
     }
+
+    #[turbo_tasks::function]
+    fn is_side_effect_free(&self) -> Vc<bool> {
+        Vc::cell(true)
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -70,11 +77,12 @@ impl ChunkableModule for JsonModuleAsset {
     #[turbo_tasks::function]
     fn as_chunk_item(
         self: ResolvedVc<Self>,
-        _module_graph: Vc<ModuleGraph>,
+        module_graph: ResolvedVc<ModuleGraph>,
         chunking_context: ResolvedVc<Box<dyn ChunkingContext>>,
     ) -> Vc<Box<dyn turbopack_core::chunk::ChunkItem>> {
         Vc::upcast(JsonChunkItem::cell(JsonChunkItem {
             module: self,
+            module_graph,
             chunking_context,
         }))
     }
@@ -96,6 +104,7 @@ impl EcmascriptChunkPlaceable for JsonModuleAsset {
 #[turbo_tasks::value]
 struct JsonChunkItem {
     module: ResolvedVc<JsonModuleAsset>,
+    module_graph: ResolvedVc<ModuleGraph>,
     chunking_context: ResolvedVc<Box<dyn ChunkingContext>>,
 }
 
@@ -134,6 +143,23 @@ impl EcmascriptChunkItem for JsonChunkItem {
         let data = content.parse_json().await?;
         match &*data {
             FileJsonContent::Content(data) => {
+                let used_keys = if *self.chunking_context.is_json_tree_shaking_enabled().await? {
+                    used_keys(*self.module_graph, ResolvedVc::upcast(self.module)).await?
+                } else {
+                    None
+                };
+                let data = match (data, used_keys) {
+                    (serde_json::Value::Object(object), Some(used_keys)) => {
+                        Cow::Owned(serde_json::Value::Object(
+                            object
+                                .iter()
+                                .filter(|(key, _)| used_keys.contains(key.as_str()))
+                                .map(|(key, value)| (key.clone(), value.clone()))
+                                .collect(),
+                        ))
+                    }
+                    (data, _) => Cow::Borrowed(data),
+                };
                 let js_str_content = serde_json::to_string(&data.to_string())?;
                 let inner_code = format!("{TURBOPACK_EXPORT_VALUE}(JSON.parse({js_str_content}));");
 
@@ -164,6 +190,27 @@ impl EcmascriptChunkItem for JsonChunkItem {
     }
 }
 
+/// Returns the top level keys that importers read from the JSON value, either by named imports or
+/// by reading properties of the default import. Returns `None` when the whole value might be used.
+async fn used_keys(
+    module_graph: Vc<ModuleGraph>,
+    module: ResolvedVc<Box<dyn Module>>,
+) -> Result<Option<FxHashSet<RcStr>>> {
+    let export_usage_info = module_graph.export_usage_info().await?;
+    let mut keys = match export_usage_info.used_exports(module) {
+        ModuleExportUsageInfo::Evaluation => FxHashSet::default(),
+        ModuleExportUsageInfo::Exports(exports) => exports,
+        ModuleExportUsageInfo::All => return Ok(None),
+    };
+    if keys.remove("default") {
+        let Some(properties) = export_usage_info.default_export_properties(module) else {
+            return Ok(None);
+        };
+        keys.extend(properties.iter().cloned());
+    }
+    Ok(Some(keys))
+}
+
 pub fn register() {
     turbo_tasks::register();
     turbo_tasks_fs::register();
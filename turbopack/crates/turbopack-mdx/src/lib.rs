@@ -4,6 +4,10 @@
 
 use anyhow::Result;
 use mdxjs::{compile, MdxParseOptions, Options};
+pub use metadata::{
+    mdx_module_metadata, mdx_modules_metadata, MdxFrontmatter, MdxFrontmatterKind,
+    MdxModuleMetadata, MdxModulesMetadata,
+};
 use turbo_rcstr::RcStr;
 use turbo_tasks::{ResolvedVc, ValueDefault, Vc};
 use turbo_tasks_fs::{rope::Rope, File, FileContent, FileSystemPath};
@@ -19,6 +23,8 @@ use turbopack_core::{
     source_transform::SourceTransform,
 };
 
+mod metadata;
+
 #[turbo_tasks::function]
 fn modifier() -> Vc<RcStr> {
     Vc::cell("mdx".into())
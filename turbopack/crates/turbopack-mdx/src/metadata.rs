@@ -0,0 +1,545 @@
+use anyhow::Result;
+use serde_json::{Map, Number, Value};
+use swc_core::{
+    common::{FileName, SourceMap},
+    ecma::{
+        ast::{
+            Decl, EsVersion, ExportSpecifier, Expr, Lit, ModuleDecl, ModuleExportName, ModuleItem,
+            ObjectPatProp, Pat, Prop, PropName, PropOrSpread, Stmt, UnaryOp, VarDecl, VarDeclKind,
+        },
+        parser::{parse_file_as_module, EsSyntax, Syntax},
+    },
+};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexMap, FxIndexSet, ResolvedVc, TryFlatJoinIterExt, Vc};
+use turbo_tasks_fs::FileContent;
+use turbopack_core::{module::Module, module_graph::ModuleGraph, source::Source};
+use turbopack_ecmascript::EcmascriptModuleAsset;
+
+use crate::{transform_process_operation, MdxTransformedAsset};
+
+/// The syntax of an MDX frontmatter block, determined by its fence.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy)]
+pub enum MdxFrontmatterKind {
+    /// Fenced by `---`.
+    Yaml,
+    /// Fenced by `+++`.
+    Toml,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct MdxFrontmatter {
+    pub kind: MdxFrontmatterKind,
+    /// The content between the fences. It is not parsed, as neither YAML nor TOML are
+    /// understood by the bundler itself.
+    pub value: RcStr,
+}
+
+/// Metadata of an MDX module that is extracted statically, i.e. without executing the module.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Default)]
+pub struct MdxModuleMetadata {
+    pub frontmatter: Option<MdxFrontmatter>,
+    /// All named exports of the module, in declaration order. `export const` declarations whose
+    /// initializer is a literal (strings, numbers, booleans, `null`, arrays and plain objects of
+    /// those) map to the JSON serialization of that value, e.g. `export const metadata = {...}`.
+    /// All other exports map to `None`, including re-exports of other modules and bindings of
+    /// destructuring patterns.
+    pub exports: FxIndexMap<RcStr, Option<RcStr>>,
+    /// The specifiers of `export * from "..."` declarations. The names they export are only known
+    /// after resolving the modules, so they are not part of `exports`.
+    pub star_exports: Vec<RcStr>,
+}
+
+#[turbo_tasks::value_impl]
+impl MdxModuleMetadata {
+    #[turbo_tasks::function]
+    pub fn empty() -> Vc<Self> {
+        Self::default().cell()
+    }
+}
+
+/// Returns the statically extracted metadata of an MDX module, given the source produced by
+/// [crate::MdxTransform]. Returns empty metadata for any other source.
+#[turbo_tasks::function]
+pub async fn mdx_module_metadata(
+    source: ResolvedVc<Box<dyn Source>>,
+) -> Result<Vc<MdxModuleMetadata>> {
+    let Some(asset) = ResolvedVc::try_downcast_type::<MdxTransformedAsset>(source) else {
+        return Ok(MdxModuleMetadata::empty());
+    };
+    Ok(asset.metadata())
+}
+
+/// The metadata of the MDX modules of a module graph, e.g. to collect the metadata of routes or to
+/// list content pages, in the order of the modules in the graph.
+#[turbo_tasks::value(transparent)]
+pub struct MdxModulesMetadata(
+    FxIndexMap<ResolvedVc<Box<dyn Module>>, ResolvedVc<MdxModuleMetadata>>,
+);
+
+/// Returns the statically extracted metadata of all MDX modules in a module graph. These are the
+/// ecmascript modules whose source was produced by [crate::MdxTransform].
+#[turbo_tasks::function]
+pub async fn mdx_modules_metadata(graph: Vc<ModuleGraph>) -> Result<Vc<MdxModulesMetadata>> {
+    let mut modules = FxIndexSet::default();
+    for graph in graph.await?.get_graphs().await? {
+        modules.extend(graph.iter_nodes().map(|node| node.module));
+    }
+    let metadata = modules
+        .into_iter()
+        .map(async |module| {
+            let Some(ecmascript) = ResolvedVc::try_downcast_type::<EcmascriptModuleAsset>(module)
+            else {
+                return Ok(None);
+            };
+            let source = ecmascript.source().to_resolved().await?;
+            let Some(asset) = ResolvedVc::try_downcast_type::<MdxTransformedAsset>(source) else {
+                return Ok(None);
+            };
+            Ok(Some((module, asset.metadata().to_resolved().await?)))
+        })
+        .try_flat_join()
+        .await?;
+    Ok(Vc::cell(metadata.into_iter().collect()))
+}
+
+#[turbo_tasks::value_impl]
+impl MdxTransformedAsset {
+    #[turbo_tasks::function]
+    pub(crate) async fn metadata(self: ResolvedVc<Self>) -> Result<Vc<MdxModuleMetadata>> {
+        let this = self.await?;
+
+        let frontmatter = match &*this.source.content().file_content().await? {
+            FileContent::Content(file) => extract_frontmatter(&file.content().to_str()?),
+            FileContent::NotFound => None,
+        };
+
+        // Exports are read from the compiled module rather than the MDX source, as mdxjs already
+        // takes care of separating ESM from markdown content.
+        let compiled = transform_process_operation(self).connect().await?;
+        let (exports, star_exports) = match &*compiled.content.file_content().await? {
+            FileContent::Content(file) => extract_exports(
+                this.source.ident().path().await?.to_string(),
+                file.content().to_str()?.into_owned(),
+            ),
+            FileContent::NotFound => Default::default(),
+        };
+
+        Ok(MdxModuleMetadata {
+            frontmatter,
+            exports,
+            star_exports,
+        }
+        .cell())
+    }
+}
+
+/// Extracts a frontmatter block, which must start on the very first line of the file.
+fn extract_frontmatter(source: &str) -> Option<MdxFrontmatter> {
+    let (fence, kind) = if source.starts_with("---") {
+        ("---", MdxFrontmatterKind::Yaml)
+    } else if source.starts_with("+++") {
+        ("+++", MdxFrontmatterKind::Toml)
+    } else {
+        return None;
+    };
+
+    let mut lines = source.split_inclusive('\n');
+    if lines.next()?.trim_end() != fence {
+        return None;
+    }
+
+    let mut value = String::new();
+    for line in lines {
+        if line.trim_end() == fence {
+            return Some(MdxFrontmatter {
+                kind,
+                value: value.into(),
+            });
+        }
+        value.push_str(line);
+    }
+
+    // An unterminated fence is not frontmatter.
+    None
+}
+
+/// Extracts the named exports of a module and the specifiers of its `export * from` declarations.
+fn extract_exports(
+    file_name: String,
+    code: String,
+) -> (FxIndexMap<RcStr, Option<RcStr>>, Vec<RcStr>) {
+    let mut exports = FxIndexMap::default();
+    let mut star_exports = Vec::new();
+
+    let cm = SourceMap::default();
+    let fm = cm.new_source_file(FileName::Custom(file_name).into(), code);
+    let Ok(module) = parse_file_as_module(
+        &fm,
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        }),
+        EsVersion::latest(),
+        None,
+        &mut vec![],
+    ) else {
+        return (exports, star_exports);
+    };
+
+    // Static values of top-level `const` declarations, which are also used for bindings that are
+    // exported with `export { name }`.
+    let mut local_values = FxIndexMap::default();
+    for item in &module.body {
+        let var = match item {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => var,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => match &export.decl {
+                Decl::Var(var) => var,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        for (name, value) in static_declarations(var) {
+            local_values.insert(name, value);
+        }
+    }
+
+    for item in &module.body {
+        let ModuleItem::ModuleDecl(decl) = item else {
+            continue;
+        };
+        match decl {
+            ModuleDecl::ExportDecl(export) => match &export.decl {
+                Decl::Var(var) => {
+                    for declarator in &var.decls {
+                        for_each_binding(&declarator.name, &mut |name| {
+                            let value = local_values.get(&name).cloned().flatten();
+                            exports.insert(name, value);
+                        });
+                    }
+                }
+                Decl::Fn(f) => {
+                    exports.insert(f.ident.sym.as_str().into(), None);
+                }
+                Decl::Class(c) => {
+                    exports.insert(c.ident.sym.as_str().into(), None);
+                }
+                _ => {}
+            },
+            ModuleDecl::ExportNamed(named) => {
+                for specifier in &named.specifiers {
+                    match specifier {
+                        ExportSpecifier::Named(specifier) => {
+                            let orig = export_name(&specifier.orig);
+                            let exported = specifier
+                                .exported
+                                .as_ref()
+                                .map_or_else(|| orig.clone(), export_name);
+                            // Values of re-exported modules are not known
+                            let value = if named.src.is_none() {
+                                local_values.get(&orig).cloned().flatten()
+                            } else {
+                                None
+                            };
+                            exports.insert(exported, value);
+                        }
+                        ExportSpecifier::Namespace(specifier) => {
+                            exports.insert(export_name(&specifier.name), None);
+                        }
+                        ExportSpecifier::Default(specifier) => {
+                            exports.insert(specifier.exported.sym.as_str().into(), None);
+                        }
+                    }
+                }
+            }
+            ModuleDecl::ExportAll(export) => {
+                star_exports.push(export.src.value.as_str().into());
+            }
+            ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_) => {
+                exports.insert("default".into(), None);
+            }
+            _ => {}
+        }
+    }
+
+    (exports, star_exports)
+}
+
+/// Returns the bindings of a variable declaration with the JSON serialization of their value, when
+/// it is a `const` declaration of a single identifier with a literal initializer.
+fn static_declarations(var: &VarDecl) -> Vec<(RcStr, Option<RcStr>)> {
+    let mut declarations = Vec::new();
+    for declarator in &var.decls {
+        let value = declarator
+            .init
+            .as_deref()
+            .filter(|_| var.kind == VarDeclKind::Const && declarator.name.is_ident())
+            .and_then(static_value)
+            .map(|value| RcStr::from(value.to_string()));
+        for_each_binding(&declarator.name, &mut |name| {
+            declarations.push((name, value.clone()));
+        });
+    }
+    declarations
+}
+
+/// Calls `f` with the names of all bindings of a pattern, e.g. `a`, `c` and `d` for
+/// `{ a, b: [c, ...d] }`.
+fn for_each_binding(pat: &Pat, f: &mut impl FnMut(RcStr)) {
+    match pat {
+        Pat::Ident(ident) => f(ident.id.sym.as_str().into()),
+        Pat::Array(array) => {
+            for elem in array.elems.iter().flatten() {
+                for_each_binding(elem, f);
+            }
+        }
+        Pat::Object(object) => {
+            for prop in &object.props {
+                match prop {
+                    ObjectPatProp::KeyValue(kv) => for_each_binding(&kv.value, f),
+                    ObjectPatProp::Assign(assign) => f(assign.key.sym.as_str().into()),
+                    ObjectPatProp::Rest(rest) => for_each_binding(&rest.arg, f),
+                }
+            }
+        }
+        Pat::Rest(rest) => for_each_binding(&rest.arg, f),
+        Pat::Assign(assign) => for_each_binding(&assign.left, f),
+        Pat::Invalid(_) | Pat::Expr(_) => {}
+    }
+}
+
+fn export_name(name: &ModuleExportName) -> RcStr {
+    match name {
+        ModuleExportName::Ident(ident) => ident.sym.as_str().into(),
+        ModuleExportName::Str(s) => s.value.as_str().into(),
+    }
+}
+
+/// Evaluates literal expressions to JSON. Returns `None` for anything that would require
+/// executing code.
+fn static_value(expr: &Expr) -> Option<Value> {
+    Some(match expr {
+        Expr::Lit(Lit::Str(s)) => Value::String(s.value.to_string()),
+        Expr::Lit(Lit::Num(n)) => Value::Number(Number::from_f64(n.value)?),
+        Expr::Lit(Lit::Bool(b)) => Value::Bool(b.value),
+        Expr::Lit(Lit::Null(_)) => Value::Null,
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => {
+            Value::String(tpl.quasis.first()?.cooked.as_ref()?.to_string())
+        }
+        Expr::Unary(unary) if unary.op == UnaryOp::Minus => match static_value(&unary.arg)? {
+            Value::Number(n) => Value::Number(Number::from_f64(-n.as_f64()?)?),
+            _ => return None,
+        },
+        Expr::Paren(paren) => static_value(&paren.expr)?,
+        Expr::Array(array) => Value::Array(
+            array
+                .elems
+                .iter()
+                .map(|elem| match elem {
+                    None => Some(Value::Null),
+                    Some(elem) if elem.spread.is_none() => static_value(&elem.expr),
+                    Some(_) => None,
+                })
+                .collect::<Option<_>>()?,
+        ),
+        Expr::Object(object) => {
+            let mut map = Map::new();
+            for prop in &object.props {
+                let PropOrSpread::Prop(prop) = prop else {
+                    return None;
+                };
+                let Prop::KeyValue(kv) = &**prop else {
+                    return None;
+                };
+                let key = match &kv.key {
+                    PropName::Ident(ident) => ident.sym.to_string(),
+                    PropName::Str(s) => s.value.to_string(),
+                    PropName::Num(n) => n.value.to_string(),
+                    _ => return None,
+                };
+                map.insert(key, static_value(&kv.value)?);
+            }
+            Value::Object(map)
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::ecma::parser::parse_file_as_expr;
+
+    use super::*;
+
+    fn exports(code: &str) -> (Vec<(String, Option<String>)>, Vec<String>) {
+        let (exports, star_exports) = extract_exports("test.js".into(), code.into());
+        let exports = exports
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.map(|value| value.to_string())))
+            .collect();
+        let star_exports = star_exports.iter().map(|s| s.to_string()).collect();
+        (exports, star_exports)
+    }
+
+    fn expected(exports: &[(&str, Option<&str>)]) -> Vec<(String, Option<String>)> {
+        exports
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.map(str::to_string)))
+            .collect()
+    }
+
+    fn value(code: &str) -> Option<Value> {
+        let cm = SourceMap::default();
+        let fm = cm.new_source_file(FileName::Anon.into(), code.into());
+        let expr = parse_file_as_expr(
+            &fm,
+            Syntax::Es(Default::default()),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap();
+        static_value(&expr)
+    }
+
+    #[test]
+    fn frontmatter() {
+        let yaml = extract_frontmatter("---\ntitle: Hello\ntags: [a]\n---\n# Hello\n").unwrap();
+        assert!(matches!(yaml.kind, MdxFrontmatterKind::Yaml));
+        assert_eq!(&*yaml.value, "title: Hello\ntags: [a]\n");
+
+        let toml = extract_frontmatter("+++\r\ntitle = \"Hello\"\r\n+++\r\n").unwrap();
+        assert!(matches!(toml.kind, MdxFrontmatterKind::Toml));
+        assert_eq!(&*toml.value, "title = \"Hello\"\r\n");
+
+        let empty = extract_frontmatter("---\n---\n").unwrap();
+        assert_eq!(&*empty.value, "");
+
+        // Frontmatter has to start on the first line and has to be terminated
+        assert!(extract_frontmatter("\n---\ntitle: Hello\n---\n").is_none());
+        assert!(extract_frontmatter("---\ntitle: Hello\n").is_none());
+        assert!(extract_frontmatter("--- title\n---\n").is_none());
+        assert!(extract_frontmatter("# Hello\n").is_none());
+    }
+
+    #[test]
+    fn static_values() {
+        assert_eq!(value(r#""a""#), Some(Value::from("a")));
+        assert_eq!(value("`a`"), Some(Value::from("a")));
+        assert_eq!(value("-1.5"), Some(Value::from(-1.5)));
+        assert_eq!(value("(true)"), Some(Value::from(true)));
+        assert_eq!(value("null"), Some(Value::Null));
+        assert_eq!(
+            value(r#"{ title: "a", "tags": ["b", 1, , null], 2: { nested: false } }"#),
+            Some(serde_json::json!({
+                "title": "a",
+                "tags": ["b", 1.0, null, null],
+                "2": { "nested": false },
+            }))
+        );
+
+        // Anything that would need to execute code
+        assert_eq!(value("`a${b}`"), None);
+        assert_eq!(value("-\"a\""), None);
+        assert_eq!(value("a"), None);
+        assert_eq!(value("f()"), None);
+        assert_eq!(value("[...a]"), None);
+        assert_eq!(value("{ ...a }"), None);
+        assert_eq!(value("{ [a]: 1 }"), None);
+        assert_eq!(value("{ a }"), None);
+        assert_eq!(value("{ a() {} }"), None);
+        assert_eq!(value("{ a: b }"), None);
+    }
+
+    #[test]
+    fn declared_exports() {
+        let (exports, star_exports) = exports(
+            r#"
+            export const metadata = { title: "Hello" };
+            export let mutable = 1;
+            export const computed = title.toUpperCase();
+            export function MDXContent() {}
+            export class Component {}
+            export default MDXContent;
+            "#,
+        );
+        assert_eq!(
+            exports,
+            expected(&[
+                ("metadata", Some(r#"{"title":"Hello"}"#)),
+                ("mutable", None),
+                ("computed", None),
+                ("MDXContent", None),
+                ("Component", None),
+                ("default", None),
+            ])
+        );
+        assert!(star_exports.is_empty());
+    }
+
+    #[test]
+    fn destructured_exports() {
+        let (exports, _) = exports(
+            r#"
+            export const { a, b: { c }, d = 1, ...e } = data;
+            export const [f, , [g], ...h] = list;
+            export const { i } = { i: 1 };
+            "#,
+        );
+        assert_eq!(
+            exports,
+            expected(&[
+                ("a", None),
+                ("c", None),
+                ("d", None),
+                ("e", None),
+                ("f", None),
+                ("g", None),
+                ("h", None),
+                ("i", None),
+            ])
+        );
+    }
+
+    #[test]
+    fn export_lists() {
+        let (exports, star_exports) = exports(
+            r#"
+            const metadata = { title: "Hello" };
+            const count = 1;
+            let mutable = 1;
+            function helper() {}
+            export { metadata, count as total, mutable, helper, metadata as "string name" };
+            export { x, y as z, default as w } from "./other";
+            export * as ns from "./namespace";
+            export * from "./all";
+            export * from "./more";
+            "#,
+        );
+        assert_eq!(
+            exports,
+            expected(&[
+                ("metadata", Some(r#"{"title":"Hello"}"#)),
+                ("total", Some("1.0")),
+                ("mutable", None),
+                ("helper", None),
+                ("string name", Some(r#"{"title":"Hello"}"#)),
+                ("x", None),
+                ("z", None),
+                ("w", None),
+                ("ns", None),
+            ])
+        );
+        assert_eq!(star_exports, vec!["./all", "./more"]);
+    }
+
+    #[test]
+    fn invalid_module() {
+        let (exports, star_exports) = exports("export const metadata = {");
+        assert!(exports.is_empty());
+        assert!(star_exports.is_empty());
+    }
+}
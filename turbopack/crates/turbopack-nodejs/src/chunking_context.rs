@@ -56,6 +56,26 @@ impl NodeJsChunkingContextBuilder {
         self
     }
 
+    pub fn simple_tree_shaking(mut self, enable_simple_tree_shaking: bool) -> Self {
+        self.chunking_context.enable_simple_tree_shaking = enable_simple_tree_shaking;
+        self
+    }
+
+    pub fn export_mangling(mut self, enable_export_mangling: bool) -> Self {
+        self.chunking_context.enable_export_mangling = enable_export_mangling;
+        self
+    }
+
+    pub fn scope_hoisting(mut self, enable_scope_hoisting: bool) -> Self {
+        self.chunking_context.enable_scope_hoisting = enable_scope_hoisting;
+        self
+    }
+
+    pub fn json_tree_shaking(mut self, enable_json_tree_shaking: bool) -> Self {
+        self.chunking_context.enable_json_tree_shaking = enable_json_tree_shaking;
+        self
+    }
+
     pub fn runtime_type(mut self, runtime_type: RuntimeType) -> Self {
         self.chunking_context.runtime_type = runtime_type;
         self
@@ -119,6 +139,14 @@ pub struct NodeJsChunkingContext {
     runtime_type: RuntimeType,
     /// Enable tracing for this chunking
     enable_file_tracing: bool,
+    /// Omit side effect free modules of which no export is used
+    enable_simple_tree_shaking: bool,
+    /// Rename exports that are only accessed by known importers to short names
+    enable_export_mangling: bool,
+    /// Merge modules with a single ESM importer into the module factory of their importer
+    enable_scope_hoisting: bool,
+    /// Only include the top level keys of JSON modules that their importers read
+    enable_json_tree_shaking: bool,
     /// Whether to minify resulting chunks
     minify_type: MinifyType,
     /// Whether to generate source maps
@@ -155,6 +183,10 @@ impl NodeJsChunkingContext {
                 asset_root_path,
                 asset_prefix: ResolvedVc::cell(None),
                 enable_file_tracing: false,
+                enable_simple_tree_shaking: false,
+                enable_export_mangling: false,
+                enable_scope_hoisting: false,
+                enable_json_tree_shaking: false,
                 environment,
                 runtime_type,
                 minify_type: MinifyType::NoMinify,
@@ -179,7 +211,7 @@ impl NodeJsChunkingContext {
 
     /// Returns the minify type.
     pub fn minify_type(&self) -> MinifyType {
-        self.minify_type
+        self.minify_type.clone()
     }
 }
 
@@ -248,9 +280,29 @@ impl ChunkingContext for NodeJsChunkingContext {
         Vc::cell(self.enable_file_tracing)
     }
 
+    #[turbo_tasks::function]
+    fn is_simple_tree_shaking_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_simple_tree_shaking)
+    }
+
+    #[turbo_tasks::function]
+    fn is_export_mangling_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_export_mangling)
+    }
+
+    #[turbo_tasks::function]
+    fn is_scope_hoisting_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_scope_hoisting)
+    }
+
+    #[turbo_tasks::function]
+    fn is_json_tree_shaking_enabled(&self) -> Vc<bool> {
+        Vc::cell(self.enable_json_tree_shaking)
+    }
+
     #[turbo_tasks::function]
     pub fn minify_type(&self) -> Vc<MinifyType> {
-        self.minify_type.cell()
+        self.minify_type.clone().cell()
     }
 
     #[turbo_tasks::function]
@@ -77,8 +77,8 @@ impl EcmascriptBuildNodeChunkContent {
 
         let mut code = code.build();
 
-        if let MinifyType::Minify { mangle } = this.chunking_context.await?.minify_type() {
-            code = minify(&code, source_maps, mangle)?;
+        if let MinifyType::Minify(options) = this.chunking_context.await?.minify_type() {
+            code = minify(&code, source_maps, &options)?;
         }
 
         Ok(code.cell())
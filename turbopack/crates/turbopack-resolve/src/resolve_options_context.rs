@@ -74,6 +74,11 @@ pub struct ResolveOptionsContext {
     /// context paths. The first matching is used.
     pub rules: Vec<(ContextCondition, ResolvedVc<ResolveOptionsContext>)>,
     #[serde(default)]
+    /// A list of rules to override resolve conditions for requests issued by certain modules.
+    /// The condition is matched against the path of the issuing module (not its directory).
+    /// Conditions are set, or unset when prefixed with `!`. The first matching is used.
+    pub issuer_conditions: Vec<(ContextCondition, Vec<RcStr>)>,
+    #[serde(default)]
     /// Plugins which get applied before and after resolving.
     pub after_resolve_plugins: Vec<ResolvedVc<Box<dyn AfterResolvePlugin>>>,
     pub before_resolve_plugins: Vec<ResolvedVc<Box<dyn BeforeResolvePlugin>>>,
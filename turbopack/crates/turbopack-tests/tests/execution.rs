@@ -30,7 +30,7 @@ use turbopack::{
     ModuleAssetContext,
 };
 use turbopack_core::{
-    chunk::ChunkingConfig,
+    chunk::{ChunkingConfig, MinifyType},
     compile_time_defines,
     compile_time_info::CompileTimeInfo,
     condition::ContextCondition,
@@ -242,6 +242,9 @@ async fn run_inner_operation(
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct TestOptions {
     tree_shaking_mode: Option<TreeShakingMode>,
+    #[serde(default)]
+    scope_hoisting: bool,
+    minify_type: Option<MinifyType>,
 }
 
 #[turbo_tasks::value]
@@ -431,6 +434,8 @@ async fn run_test_operation(prepared_test: ResolvedVc<PreparedTest>) -> Result<V
             ..Default::default()
         },
     )
+    .scope_hoisting(options.scope_hoisting)
+    .minify_type(options.minify_type.clone().unwrap_or(MinifyType::NoMinify))
     .build();
 
     let jest_entry_source = FileSource::new(jest_entry_path);
@@ -0,0 +1,4 @@
+await Promise.resolve();
+
+export const x = "x";
+export default "default";
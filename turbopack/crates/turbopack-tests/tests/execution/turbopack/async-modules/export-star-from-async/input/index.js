@@ -0,0 +1,19 @@
+import * as ns from "./reexport.js";
+import { x, y } from "./reexport.js";
+
+it("should re-export all exports of an async module", () => {
+  expect(x).toBe("x");
+  expect(y).toBe("y");
+});
+
+it("should re-export all exports of an async module in the namespace", () => {
+  expect(ns.x).toBe("x");
+  expect(ns.y).toBe("y");
+});
+
+it("should re-export all exports of an async module with import()", async () => {
+  const { x, y, default: def } = await import("./reexport.js");
+  expect(x).toBe("x");
+  expect(y).toBe("y");
+  expect(def).toBeUndefined();
+});
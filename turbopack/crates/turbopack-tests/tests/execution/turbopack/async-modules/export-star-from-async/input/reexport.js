@@ -0,0 +1,2 @@
+export * from "./async.js";
+export * from "./sync.js";
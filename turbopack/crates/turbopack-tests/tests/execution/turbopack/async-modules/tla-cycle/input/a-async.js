@@ -0,0 +1,7 @@
+import { b } from "./b-sync.js";
+
+await Promise.resolve();
+
+export function a() {
+  return "a" + b();
+}
@@ -0,0 +1,9 @@
+import { a } from "./a-async.js";
+
+export function b() {
+  return "b";
+}
+
+export function callA() {
+  return a();
+}
@@ -0,0 +1,10 @@
+import { a } from "./a-async.js";
+import { callA } from "./b-sync.js";
+
+// index.js -> a-async.js -> b-sync.js (-> back to a-async.js)
+// The cycle is reported as a warning, see the issues directory.
+
+it("should evaluate modules in a cycle with top level await", () => {
+  expect(a()).toBe("ab");
+  expect(callA()).toBe("ab");
+});
@@ -0,0 +1,6 @@
+import { order } from "./order.js";
+import "./d-sync.js";
+
+order.push("a");
+
+export * from "./b-sync.js";
@@ -0,0 +1,5 @@
+import { order } from "./order.js";
+
+order.push("b");
+
+export { value } from "./c-async.js";
@@ -0,0 +1,7 @@
+import { order } from "./order.js";
+
+order.push("c:start");
+await Promise.resolve();
+order.push("c:end");
+
+export const value = "value";
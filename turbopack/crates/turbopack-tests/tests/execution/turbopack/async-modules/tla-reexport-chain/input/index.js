@@ -0,0 +1,13 @@
+import { value } from "./a-sync.js";
+import { order } from "./order.js";
+
+// index.js -> a-sync.js -> d-sync.js
+//                       \-> b-sync.js -> c-async.js
+
+it("should re-export bindings through a chain of modules with top level await", () => {
+  expect(value).toBe("value");
+});
+
+it("should evaluate importers after their async dependencies", () => {
+  expect(order).toEqual(["d", "c:start", "c:end", "b", "a"]);
+});
@@ -0,0 +1,3 @@
+export function area(shape) {
+  return shape._width * shape._height;
+}
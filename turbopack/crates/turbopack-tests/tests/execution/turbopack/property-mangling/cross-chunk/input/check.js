@@ -0,0 +1,10 @@
+import { createShape } from "./shape.js";
+
+export async function computeArea() {
+  const { area } = await import("./area.js");
+  return area(createShape());
+}
+
+export function shapeKeys() {
+  return Object.keys(createShape());
+}
@@ -0,0 +1,12 @@
+import { computeArea, shapeKeys } from "./check.js";
+
+it("should rename properties in the same way in all chunks", async () => {
+  expect(await computeArea()).toBe(6);
+});
+
+it("should rename properties that are only accessed by bundled modules", () => {
+  const keys = shapeKeys();
+  expect(keys).toHaveLength(2);
+  expect(keys.some((key) => /^_width$/.test(key))).toBe(false);
+  expect(keys.some((key) => /^_height$/.test(key))).toBe(false);
+});
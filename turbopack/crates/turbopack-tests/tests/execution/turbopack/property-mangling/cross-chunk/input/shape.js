@@ -0,0 +1,3 @@
+export function createShape() {
+  return { _width: 2, _height: 3 };
+}
@@ -0,0 +1,5 @@
+import def from "esm-external/package";
+
+export function createExternalOptions() {
+  return { _external: def };
+}
@@ -0,0 +1,22 @@
+import { createExternalOptions } from "./external.js";
+import { createInternalOptions } from "./internal.js";
+
+export function createEntryOptions() {
+  return { _entry: 1 };
+}
+
+it("should keep properties of modules that reference external modules", () => {
+  const keys = Object.keys(createExternalOptions());
+  expect(keys.some((key) => /^_external$/.test(key))).toBe(true);
+});
+
+it("should keep properties of entry modules", () => {
+  const keys = Object.keys(createEntryOptions());
+  expect(keys.some((key) => /^_entry$/.test(key))).toBe(true);
+});
+
+it("should rename properties of other modules", () => {
+  const keys = Object.keys(createInternalOptions());
+  expect(keys).toHaveLength(1);
+  expect(keys.some((key) => /^_internal$/.test(key))).toBe(false);
+});
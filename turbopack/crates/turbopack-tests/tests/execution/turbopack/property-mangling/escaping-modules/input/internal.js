@@ -0,0 +1,3 @@
+export function createInternalOptions() {
+  return { _internal: 1 };
+}
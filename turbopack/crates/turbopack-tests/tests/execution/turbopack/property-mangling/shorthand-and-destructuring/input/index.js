@@ -0,0 +1,15 @@
+import { createOptions, createPoint, sum, withDefault } from "./point.js";
+
+it("should rename shorthand properties", () => {
+  const point = createPoint(1, 2);
+  expect(Object.keys(point).some((key) => /^_[xy]$/.test(key))).toBe(false);
+  expect(sum(point)).toBe(3);
+});
+
+it("should rename destructured properties with defaults", () => {
+  expect(sum(createPoint(1))).toBe(11);
+  expect(withDefault()).toBe(5);
+  expect(withDefault(createOptions(7))).toBe(7);
+  const keys = Object.keys(createOptions(7));
+  expect(keys.some((key) => /^_z$/.test(key))).toBe(false);
+});
@@ -0,0 +1,15 @@
+export function createPoint(_x, _y) {
+  return { _x, _y };
+}
+
+export function sum({ _x, _y = 10 }) {
+  return _x + _y;
+}
+
+export function withDefault({ _z = 5 } = {}) {
+  return _z;
+}
+
+export function createOptions(_z) {
+  return { _z };
+}
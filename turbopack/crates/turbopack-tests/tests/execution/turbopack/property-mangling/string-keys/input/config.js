@@ -0,0 +1,8 @@
+export function createConfig() {
+  return { _mode: "dev", _level: 1, _renamed: true };
+}
+
+export function readConfig(config) {
+  const level = "_level" in config && config._level;
+  return [config["_mode"], level, config._renamed];
+}
@@ -0,0 +1,13 @@
+import { createConfig, readConfig } from "./config.js";
+
+it("should keep properties that are accessed by name", () => {
+  const keys = Object.keys(createConfig());
+  expect(keys).toContain("_mode");
+  expect(keys).toContain("_level");
+  expect(readConfig(createConfig())).toEqual(["dev", 1, true]);
+});
+
+it("should rename other properties", () => {
+  const keys = Object.keys(createConfig());
+  expect(keys.some((key) => /^_renamed$/.test(key))).toBe(false);
+});
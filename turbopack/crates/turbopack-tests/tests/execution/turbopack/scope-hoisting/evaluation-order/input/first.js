@@ -0,0 +1,4 @@
+import { log } from "./log";
+import "./first-dependency";
+
+log.push("first");
@@ -0,0 +1,15 @@
+import { log } from "./log";
+import "./first";
+import "./second";
+
+log.push("index");
+
+it("should evaluate merged modules in import order", () => {
+  expect(log).toEqual([
+    "first dependency",
+    "first",
+    "second dependency",
+    "second",
+    "index",
+  ]);
+});
@@ -0,0 +1,3 @@
+import { log } from "./log";
+
+log.push("second dependency");
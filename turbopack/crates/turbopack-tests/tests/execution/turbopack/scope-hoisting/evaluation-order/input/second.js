@@ -0,0 +1,4 @@
+import { log } from "./log";
+import "./second-dependency";
+
+log.push("second");
@@ -0,0 +1,16 @@
+import { count, increment, reset, value, replace } from "./reexports";
+
+it("should observe updates of mutable bindings of merged modules", () => {
+  expect(count).toBe(0);
+  increment();
+  increment();
+  expect(count).toBe(2);
+  reset();
+  expect(count).toBe(0);
+});
+
+it("should observe updates of renamed bindings of merged modules", () => {
+  expect(value).toBe("initial");
+  replace();
+  expect(value).toBe("replaced");
+});
@@ -0,0 +1,2 @@
+export { count, increment, reset } from "./counter";
+export { value, replace } from "./value";
@@ -0,0 +1,7 @@
+let current = "initial";
+
+export function replace() {
+  current = "replaced";
+}
+
+export { current as value };
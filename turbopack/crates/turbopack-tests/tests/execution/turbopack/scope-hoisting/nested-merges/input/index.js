@@ -0,0 +1,9 @@
+import { describeTree } from "./root";
+
+it("should evaluate modules that are merged into merged modules", () => {
+  expect(describeTree()).toEqual({
+    root: "root",
+    left: { name: "left", leaf: "left leaf" },
+    right: { name: "right", leaf: "right leaf" },
+  });
+});
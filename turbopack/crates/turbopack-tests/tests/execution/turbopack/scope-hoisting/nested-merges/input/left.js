@@ -0,0 +1,5 @@
+import { leaf } from "./left-leaf";
+
+export function left() {
+  return { name: "left", leaf };
+}
@@ -0,0 +1 @@
+export const leaf = "right leaf";
@@ -0,0 +1,5 @@
+import { leaf } from "./right-leaf";
+
+export function right() {
+  return { name: "right", leaf };
+}
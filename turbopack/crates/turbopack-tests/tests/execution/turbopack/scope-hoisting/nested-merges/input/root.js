@@ -0,0 +1,6 @@
+import { left } from "./left";
+import { right } from "./right";
+
+export function describeTree() {
+  return { root: "root", left: left(), right: right() };
+}
@@ -0,0 +1,5 @@
+import { b, fromC } from "./b";
+
+export const a = "a";
+export const fromB = b;
+export { fromC };
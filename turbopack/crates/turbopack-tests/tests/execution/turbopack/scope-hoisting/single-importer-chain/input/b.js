@@ -0,0 +1,4 @@
+import { c } from "./c";
+
+export const b = "b";
+export const fromC = c;
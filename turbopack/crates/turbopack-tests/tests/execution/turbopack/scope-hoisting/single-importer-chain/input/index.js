@@ -0,0 +1,7 @@
+import { a, fromB, fromC } from "./a";
+
+it("should evaluate a chain of merged modules", () => {
+  expect(a).toBe("a");
+  expect(fromB).toBe("b");
+  expect(fromC).toBe("c");
+});
@@ -25,7 +25,7 @@ use turbopack::{
     ecmascript::{EcmascriptInputTransform, TreeShakingMode},
     module_options::{
         CssOptionsContext, EcmascriptOptionsContext, JsxTransformOptions, ModuleOptionsContext,
-        ModuleRule, ModuleRuleEffect, RuleCondition,
+        ModuleRule, ModuleRuleEffect, RuleCondition, TypeofWindow,
     },
     ModuleAssetContext,
 };
@@ -96,6 +96,8 @@ struct SnapshotOptions {
     environment: SnapshotEnvironment,
     #[serde(default)]
     tree_shaking_mode: Option<TreeShakingMode>,
+    #[serde(default)]
+    typeof_window: Option<TypeofWindow>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -122,6 +124,7 @@ impl Default for SnapshotOptions {
             runtime_type: default_runtime_type(),
             environment: Default::default(),
             tree_shaking_mode: Default::default(),
+            typeof_window: Default::default(),
         }
     }
 }
@@ -297,6 +300,7 @@ async fn run_test_operation(resource: RcStr) -> Result<Vc<FileSystemPath>> {
                     ..Default::default()
                 })),
                 ignore_dynamic_requests: true,
+                enable_typeof_window_inlining: options.typeof_window.clone(),
                 ..Default::default()
             },
             css: CssOptionsContext {
@@ -0,0 +1,4 @@
+// Inlined as "object"
+console.log("typeof window", typeof window);
+
+new Worker(new URL("./worker.js", import.meta.url));
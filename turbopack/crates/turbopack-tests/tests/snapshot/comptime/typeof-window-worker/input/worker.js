@@ -0,0 +1,3 @@
+// Web workers have no window and document, inlined as "undefined"
+console.log("typeof window", typeof window);
+console.log("typeof document", typeof document);
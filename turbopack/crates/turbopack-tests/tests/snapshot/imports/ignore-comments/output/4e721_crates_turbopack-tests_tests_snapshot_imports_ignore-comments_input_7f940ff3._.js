@@ -11,7 +11,7 @@ module.exports = "turbopack";
 var { g: global, __dirname } = __turbopack_context__;
 {
 __turbopack_context__.v("/static/vercel.5cd99b11.cjs");}}),
-"[project]/turbopack/crates/turbopack-tests/tests/snapshot/imports/ignore-comments/input/vercel.cjs [test] (ecmascript, worker loader)": ((__turbopack_context__) => {
+"[project]/turbopack/crates/turbopack-tests/tests/snapshot/imports/ignore-comments/input/vercel.cjs [test-worker] (ecmascript, worker loader)": ((__turbopack_context__) => {
 
 var { g: global, __dirname } = __turbopack_context__;
 {
@@ -41,7 +41,7 @@ const __TURBOPACK__import$2e$meta__ = {
 __turbopack_context__.r("[project]/turbopack/crates/turbopack-tests/tests/snapshot/imports/ignore-comments/input/vercel.mjs [test] (ecmascript, async loader)")(__turbopack_context__.i).then(console.log);
 __turbopack_context__.r("[project]/turbopack/crates/turbopack-tests/tests/snapshot/imports/ignore-comments/input/vercel.mjs [test] (ecmascript, async loader)")(__turbopack_context__.i).then(console.log);
 console.log(__turbopack_context__.r("[project]/turbopack/crates/turbopack-tests/tests/snapshot/imports/ignore-comments/input/vercel.cjs [test] (ecmascript)"));
-new Worker(__turbopack_context__.r("[project]/turbopack/crates/turbopack-tests/tests/snapshot/imports/ignore-comments/input/vercel.cjs [test] (ecmascript, worker loader)"));
+new Worker(__turbopack_context__.r("[project]/turbopack/crates/turbopack-tests/tests/snapshot/imports/ignore-comments/input/vercel.cjs [test-worker] (ecmascript, worker loader)"));
 // turbopack shouldn't attempt to bundle these, and they should be preserved in the output
 import(/* webpackIgnore: true */ "./ignore.mjs");
 import(/* turbopackIgnore: true */ "./ignore.mjs");
@@ -55,7 +55,7 @@ function ensureDynamicExports(module, exports) {
                     return Reflect.get(target, prop);
                 }
                 for (const obj of reexportedObjects){
-                    const value = Reflect.get(obj, prop);
+                    const value = Reflect.get(reexportedExports(obj), prop);
                     if (value !== undefined) return value;
                 }
                 return undefined;
@@ -63,7 +63,7 @@ function ensureDynamicExports(module, exports) {
             ownKeys (target) {
                 const keys = Reflect.ownKeys(target);
                 for (const obj of reexportedObjects){
-                    for (const key of Reflect.ownKeys(obj)){
+                    for (const key of Reflect.ownKeys(reexportedExports(obj))){
                         if (key !== "default" && !keys.includes(key)) keys.push(key);
                     }
                 }
@@ -72,6 +72,12 @@ function ensureDynamicExports(module, exports) {
         });
     }
 }
+/**
+ * Async modules are imported as a promise. Like ESM bindings, their exports
+ * can be re-exported before the module finished evaluating.
+ */ function reexportedExports(obj) {
+    return isAsyncModuleExt(obj) ? obj[turbopackExports] : obj;
+}
 /**
  * Dynamically exports properties from an object
  */ function dynamicExport(module, exports, object) {
@@ -66,7 +66,7 @@ function ensureDynamicExports(module, exports) {
                     return Reflect.get(target, prop);
                 }
                 for (const obj of reexportedObjects){
-                    const value = Reflect.get(obj, prop);
+                    const value = Reflect.get(reexportedExports(obj), prop);
                     if (value !== undefined) return value;
                 }
                 return undefined;
@@ -74,7 +74,7 @@ function ensureDynamicExports(module, exports) {
             ownKeys (target) {
                 const keys = Reflect.ownKeys(target);
                 for (const obj of reexportedObjects){
-                    for (const key of Reflect.ownKeys(obj)){
+                    for (const key of Reflect.ownKeys(reexportedExports(obj))){
                         if (key !== "default" && !keys.includes(key)) keys.push(key);
                     }
                 }
@@ -83,6 +83,12 @@ function ensureDynamicExports(module, exports) {
         });
     }
 }
+/**
+ * Async modules are imported as a promise. Like ESM bindings, their exports
+ * can be re-exported before the module finished evaluating.
+ */ function reexportedExports(obj) {
+    return isAsyncModuleExt(obj) ? obj[turbopackExports] : obj;
+}
 /**
  * Dynamically exports properties from an object
  */ function dynamicExport(module, exports, object) {
@@ -1592,10 +1598,15 @@ async function loadWebAssemblyModule(_source, wasmChunkPath) {
 /// <reference path="./runtime-backend-dom.ts" />
 /// <reference path="../../../shared/require-type.d.ts" />
 let DEV_BACKEND;
+let workerHmrChannel;
 (()=>{
     DEV_BACKEND = {
         unloadChunk (chunkUrl) {
             deleteResolver(chunkUrl);
+            // Web workers have no elements to remove.
+            if (typeof document === "undefined") {
+                return;
+            }
             // TODO(PACK-2140): remove this once all filenames are guaranteed to be escaped.
             const decodedChunkUrl = decodeURI(chunkUrl);
             if (isCss(chunkUrl)) {
@@ -1659,12 +1670,65 @@ let DEV_BACKEND;
                 previousLinks[0].parentElement.insertBefore(link, previousLinks[0].nextSibling);
             });
         },
-        restart: ()=>self.location.reload()
+        restart: ()=>{
+            if (workerHmrChannel) {
+                workerHmrChannel.postMessage({
+                    type: "turbopack-worker-restart"
+                });
+            } else {
+                self.location.reload();
+            }
+        }
     };
     function deleteResolver(chunkUrl) {
         chunkResolvers.delete(chunkUrl);
     }
 })();
+// Web workers can't connect to the update server. The HMR client of the page
+// subscribes to the chunk lists of the worker instead and forwards the updates.
+if (typeof importScripts === "function" && typeof BroadcastChannel !== "undefined") {
+    const channel = new BroadcastChannel("__turbopack_worker_hmr__");
+    const callbacks = new Map();
+    // Every open page forwards the updates, only the ones of a single page are
+    // applied.
+    let clientId;
+    const subscribe = (chunkListPath)=>{
+        channel.postMessage({
+            type: "turbopack-worker-subscribe",
+            chunkListPath
+        });
+    };
+    channel.onmessage = ({ data })=>{
+        switch(data.type){
+            case "turbopack-worker-ready":
+                // A page (re)connected, it doesn't know about the chunk lists yet.
+                clientId = undefined;
+                for (const chunkListPath of callbacks.keys()){
+                    subscribe(chunkListPath);
+                }
+                break;
+            case "turbopack-worker-update":
+                clientId ??= data.clientId;
+                if (data.clientId === clientId) {
+                    callbacks.get(data.chunkListPath)?.(data.update);
+                }
+                break;
+        }
+    };
+    const queued = globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS;
+    globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS = {
+        push: ([chunkListPath, callback])=>{
+            callbacks.set(chunkListPath, callback);
+            subscribe(chunkListPath);
+        }
+    };
+    if (Array.isArray(queued)) {
+        for (const registration of queued){
+            globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS.push(registration);
+        }
+    }
+    workerHmrChannel = channel;
+}
 function _eval({ code, url, map }) {
     code += `\n\n//# sourceURL=${encodeURI(location.origin + CHUNK_BASE_PATH + url + CHUNK_SUFFIX_PATH)}`;
     if (map) {
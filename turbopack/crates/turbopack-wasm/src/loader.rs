@@ -12,11 +12,18 @@ use crate::{analysis::analyze, source::WebAssemblySource};
 
 /// Create a javascript loader to instantiate the WebAssembly module with the
 /// necessary imports and exports to be processed by [turbopack_ecmascript].
+///
+/// When `used_exports` is given, only these exports are exported by the loader.
 #[turbo_tasks::function]
 pub(crate) async fn instantiating_loader_source(
     source: Vc<WebAssemblySource>,
+    used_exports: Option<Vc<Vec<RcStr>>>,
 ) -> Result<Vc<Box<dyn Source>>> {
     let analysis = analyze(source).await?;
+    let exports = match used_exports {
+        Some(used_exports) => used_exports.await?.iter().map(|e| e.to_string()).collect(),
+        None => analysis.exports.clone(),
+    };
 
     let mut code = String::new();
 
@@ -49,7 +56,7 @@ pub(crate) async fn instantiating_loader_source(
             export {{ {exports} }};
         "#,
         imports = imports_obj,
-        exports = analysis.exports.join(", "),
+        exports = exports.join(", "),
     )?;
 
     let code: RcStr = code.into();
@@ -11,7 +11,7 @@ use turbopack_core::{
     context::AssetContext,
     ident::AssetIdent,
     module::{Module, OptionModule},
-    module_graph::ModuleGraph,
+    module_graph::{export_usage::ModuleExportUsageInfo, ModuleGraph},
     output::OutputAssets,
     reference::{ModuleReferences, SingleChunkableModuleReference},
     reference_type::ReferenceType,
@@ -27,6 +27,7 @@ use turbopack_ecmascript::{
 };
 
 use crate::{
+    analysis::analyze,
     loader::{compiling_loader_source, instantiating_loader_source},
     output_asset::WebAssemblyAsset,
     raw::RawWebAssemblyModuleAsset,
@@ -65,26 +66,39 @@ impl WebAssemblyModuleAsset {
         WebAssemblyAsset::new(*self.source, chunking_context)
     }
 
+    #[turbo_tasks::function]
+    async fn is_compiling(&self) -> Result<Vc<bool>> {
+        Ok(Vc::cell(&*self.source.ident().query().await? == "?module"))
+    }
+
     #[turbo_tasks::function]
     async fn loader_as_module(self: Vc<Self>) -> Result<Vc<Box<dyn Module>>> {
         let this = self.await?;
-        let query = &*this.source.ident().query().await?;
 
-        let loader_source = if query == "?module" {
+        let loader_source = if *self.is_compiling().await? {
             compiling_loader_source(*this.source)
         } else {
-            instantiating_loader_source(*this.source)
+            instantiating_loader_source(*this.source, None)
         };
 
-        let module = this.asset_context.process(
+        Ok(self.process_loader(loader_source))
+    }
+
+    #[turbo_tasks::function]
+    async fn process_loader(
+        &self,
+        loader_source: Vc<Box<dyn Source>>,
+    ) -> Result<Vc<Box<dyn Module>>> {
+        let module = self.asset_context.process(
             loader_source,
             Value::new(ReferenceType::Internal(ResolvedVc::cell(fxindexmap! {
-                "WASM_PATH".into() => ResolvedVc::upcast(RawWebAssemblyModuleAsset::new(*this.source, *this.asset_context).to_resolved().await?),
+                "WASM_PATH".into() => ResolvedVc::upcast(RawWebAssemblyModuleAsset::new(*self.source, *self.asset_context).to_resolved().await?),
             }))),
         ).module();
 
         Ok(module)
     }
+
     #[turbo_tasks::function]
     async fn loader_as_resolve_origin(self: Vc<Self>) -> Result<Vc<Box<dyn ResolveOrigin>>> {
         let module = self.loader_as_module();
@@ -99,15 +113,44 @@ impl WebAssemblyModuleAsset {
 
     #[turbo_tasks::function]
     async fn loader(self: Vc<Self>) -> Result<Vc<Box<dyn EcmascriptChunkPlaceable>>> {
-        let module = self.loader_as_module();
+        loader_as_placeable(self.loader_as_module()).await
+    }
 
-        let Some(esm_asset) =
-            Vc::try_resolve_sidecast::<Box<dyn EcmascriptChunkPlaceable>>(module).await?
-        else {
-            bail!("WASM loader was not processed into an EcmascriptModuleAsset");
+    /// A loader that only exports the exports of the WebAssembly module which are used in the
+    /// module graph, so the glue code for unused exports is omitted.
+    ///
+    /// It has the same references as [WebAssemblyModuleAsset::loader], which is the loader that is
+    /// part of the module graph.
+    #[turbo_tasks::function]
+    async fn pruned_loader(
+        self: Vc<Self>,
+        module_graph: Vc<ModuleGraph>,
+    ) -> Result<Vc<Box<dyn EcmascriptChunkPlaceable>>> {
+        if *self.is_compiling().await? {
+            return Ok(self.loader());
+        }
+        let this = self.await?;
+        let used_exports = module_graph
+            .export_usage_info()
+            .module_used_exports(Vc::upcast(self))
+            .await?;
+        let analysis = analyze(*this.source).await?;
+        let exports: Vec<RcStr> = match &*used_exports {
+            ModuleExportUsageInfo::All => return Ok(self.loader()),
+            ModuleExportUsageInfo::Evaluation => vec![],
+            ModuleExportUsageInfo::Exports(used_exports) => analysis
+                .exports
+                .iter()
+                .filter(|export| used_exports.contains(export.as_str()))
+                .map(|export| export.as_str().into())
+                .collect(),
         };
+        if exports.len() == analysis.exports.len() {
+            return Ok(self.loader());
+        }
 
-        Ok(esm_asset)
+        let loader_source = instantiating_loader_source(*this.source, Some(Vc::cell(exports)));
+        loader_as_placeable(self.process_loader(loader_source)).await
     }
 
     #[turbo_tasks::function]
@@ -123,6 +166,18 @@ impl WebAssemblyModuleAsset {
     }
 }
 
+async fn loader_as_placeable(
+    module: Vc<Box<dyn Module>>,
+) -> Result<Vc<Box<dyn EcmascriptChunkPlaceable>>> {
+    let Some(esm_asset) =
+        Vc::try_resolve_sidecast::<Box<dyn EcmascriptChunkPlaceable>>(module).await?
+    else {
+        bail!("WASM loader was not processed into an EcmascriptModuleAsset");
+    };
+
+    Ok(esm_asset)
+}
+
 #[turbo_tasks::value_impl]
 impl Module for WebAssemblyModuleAsset {
     #[turbo_tasks::function]
@@ -252,7 +307,16 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         &self,
         async_module_info: Option<Vc<AsyncModuleInfo>>,
     ) -> Result<Vc<EcmascriptChunkItemContent>> {
-        let loader_asset = self.module.loader();
+        // The glue code of unused exports is omitted
+        let loader_asset = if *self
+            .chunking_context
+            .is_simple_tree_shaking_enabled()
+            .await?
+        {
+            self.module.pruned_loader(*self.module_graph)
+        } else {
+            self.module.loader()
+        };
         let item =
             loader_asset.as_chunk_item(*self.module_graph, Vc::upcast(*self.chunking_context));
 
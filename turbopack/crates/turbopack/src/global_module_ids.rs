@@ -1,49 +1,58 @@
 use anyhow::{Context, Result};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use smallvec::SmallVec;
 use tracing::Instrument;
 use turbo_rcstr::RcStr;
-use turbo_tasks::{ReadRef, ResolvedVc, TryJoinIterExt, ValueToString, Vc};
+use turbo_tasks::{
+    FxIndexMap, ReadRef, ResolvedVc, TryFlatJoinIterExt, TryJoinIterExt, ValueToString, Vc,
+};
+use turbo_tasks_fs::{File, FileContent, FileJsonContent, FileSystemPath};
 use turbo_tasks_hash::hash_xxh3_hash64;
 use turbopack_core::{
-    chunk::{module_id_strategies::GlobalModuleIdStrategy, ChunkableModule, ChunkingType},
+    chunk::{
+        module_id_strategies::{hashed_module_id, GlobalModuleIdStrategy, HashedModuleIdStrategy},
+        ChunkableModule, ChunkingType,
+    },
     ident::AssetIdent,
     module::Module,
     module_graph::ModuleGraph,
 };
 use turbopack_ecmascript::async_chunk::module::AsyncLoaderModule;
 
+/// Returns the idents of all modules in the graph, and additionally of all the modules that are
+/// inserted by chunking (i.e. async loaders).
+async fn module_idents(module_graph: &ModuleGraph) -> Result<Vec<Vc<AssetIdent>>> {
+    let graphs = module_graph.graphs.iter().try_join().await?;
+
+    let mut idents = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|m| m.module.ident())
+        .collect::<Vec<_>>();
+
+    module_graph
+        .traverse_all_edges_unordered(|parent, current| {
+            if let (_, &ChunkingType::Async) = parent {
+                let module = ResolvedVc::try_sidecast::<Box<dyn ChunkableModule>>(current.module)
+                    .context("expected chunkable module for async reference")?;
+                idents.push(AsyncLoaderModule::asset_ident_for(*module));
+            }
+            Ok(())
+        })
+        .await?;
+
+    Ok(idents)
+}
+
 #[turbo_tasks::function]
 pub async fn get_global_module_id_strategy(
     module_graph: ResolvedVc<ModuleGraph>,
 ) -> Result<Vc<GlobalModuleIdStrategy>> {
     let span = tracing::info_span!("compute module id map");
     async move {
-        let module_graph = module_graph.await?;
-        let graphs = module_graph.graphs.iter().try_join().await?;
-
-        // All modules in the graph
-        let module_idents = graphs
-            .iter()
-            .flat_map(|graph| graph.iter_nodes())
-            .map(|m| m.module.ident());
-
-        // And additionally, all the modules that are inserted by chunking (i.e. async loaders)
-        let mut async_idents = vec![];
-        module_graph
-            .traverse_all_edges_unordered(|parent, current| {
-                if let (_, &ChunkingType::Async) = parent {
-                    let module =
-                        ResolvedVc::try_sidecast::<Box<dyn ChunkableModule>>(current.module)
-                            .context("expected chunkable module for async reference")?;
-                    async_idents.push(AsyncLoaderModule::asset_ident_for(*module));
-                }
-                Ok(())
-            })
-            .await?;
-
-        let mut module_id_map = module_idents
-            .chain(async_idents.into_iter())
+        let mut module_id_map = module_idents(&*module_graph.await?)
+            .await?
+            .into_iter()
             .map(|ident| async move {
                 let ident = ident.to_resolved().await?;
                 let ident_str = ident.to_string().await?;
@@ -69,6 +78,137 @@ pub async fn get_global_module_id_strategy(
     .await
 }
 
+/// The ids that the [HashedModuleIdStrategy] assigned to the modules, by module ident.
+#[turbo_tasks::value(transparent)]
+pub struct HashedModuleIds(FxIndexMap<RcStr, RcStr>);
+
+#[turbo_tasks::value_impl]
+impl HashedModuleIds {
+    /// Reads the ids that were written by [HashedModuleIds::write], e.g. by a previous build. A
+    /// missing or invalid file has no ids.
+    #[turbo_tasks::function]
+    pub async fn read(path: Vc<FileSystemPath>) -> Result<Vc<Self>> {
+        let FileJsonContent::Content(json) = &*path.read_json().await? else {
+            return Ok(Vc::cell(Default::default()));
+        };
+        Ok(Vc::cell(
+            serde_json::from_value(json.clone()).unwrap_or_default(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn write(self: Vc<Self>, path: Vc<FileSystemPath>) -> Result<()> {
+        let json = serde_json::to_string_pretty(&*self.await?)?;
+        path.write(FileContent::Content(File::from(json)).cell())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Assigns the ids of the [HashedModuleIdStrategy] to the modules of the graph. Modules keep their
+/// ids of `previous_ids`, so on a collision, only the id of the new module is extended.
+#[turbo_tasks::function]
+pub async fn get_hashed_module_ids(
+    module_graph: ResolvedVc<ModuleGraph>,
+    hash_length: u8,
+    previous_ids: Vc<HashedModuleIds>,
+) -> Result<Vc<HashedModuleIds>> {
+    let span = tracing::info_span!("compute hashed module ids");
+    async move {
+        let idents = module_idents(&*module_graph.await?)
+            .await?
+            .into_iter()
+            .map(|ident| async move { Ok(ident.to_string().owned().await?) })
+            .try_join()
+            .await?;
+
+        Ok(Vc::cell(assign_hashed_module_ids(
+            idents,
+            hash_length.clamp(1, 16) as usize,
+            &*previous_ids.await?,
+        )))
+    }
+    .instrument(span)
+    .await
+}
+
+/// Returns a [HashedModuleIdStrategy] with the ids that [get_hashed_module_ids] assigned to the
+/// modules of the graph.
+#[turbo_tasks::function]
+pub async fn get_hashed_module_id_strategy(
+    module_graph: ResolvedVc<ModuleGraph>,
+    hash_length: u8,
+    ids: Vc<HashedModuleIds>,
+) -> Result<Vc<HashedModuleIdStrategy>> {
+    let hash_length = hash_length.clamp(1, 16);
+    let ids = &ids.await?;
+    let collision_ids = module_idents(&*module_graph.await?)
+        .await?
+        .into_iter()
+        .map(|ident| async move {
+            let ident = ident.to_resolved().await?;
+            let ident_str = ident.to_string().await?;
+            Ok(ids.get(&*ident_str).and_then(|id| {
+                (**id != hashed_module_id(&ident_str, hash_length as usize))
+                    .then(|| (ident, id.clone()))
+            }))
+        })
+        .try_flat_join()
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(HashedModuleIdStrategy {
+        hash_length,
+        collision_ids,
+    }
+    .cell())
+}
+
+/// Assigns an id to every module ident: the first `hash_length` digits of the hash of the ident,
+/// or a longer prefix of it when that id is already used by another module (see
+/// [hashed_module_id]).
+///
+/// Modules keep the ids of `previous_ids` that are still valid for the hash length, so a collision
+/// is settled by extending the id of the module that is new, and adding a module never changes the
+/// ids of the existing modules. Without previous ids, the modules are processed in the order of
+/// their idents.
+fn assign_hashed_module_ids(
+    idents: impl IntoIterator<Item = RcStr>,
+    hash_length: usize,
+    previous_ids: &FxIndexMap<RcStr, RcStr>,
+) -> FxIndexMap<RcStr, RcStr> {
+    let mut idents = idents.into_iter().collect::<Vec<_>>();
+    idents.sort();
+    idents.dedup();
+
+    let mut ids = FxIndexMap::default();
+    let mut used_ids = FxHashSet::default();
+    for ident in &idents {
+        let Some(id) = previous_ids.get(ident) else {
+            continue;
+        };
+        let is_valid = id.len() >= hash_length
+            && (hash_length..=id.len()).any(|length| **id == hashed_module_id(ident, length));
+        if is_valid && used_ids.insert(id.clone()) {
+            ids.insert(ident.clone(), id.clone());
+        }
+    }
+    for ident in idents {
+        if ids.contains_key(&ident) {
+            continue;
+        }
+        let id = (hash_length..)
+            .map(|length| RcStr::from(hashed_module_id(&ident, length)))
+            .find(|id| !used_ids.contains(id))
+            .unwrap();
+        used_ids.insert(id.clone());
+        ids.insert(ident, id);
+    }
+    ids.sort_keys();
+    ids
+}
+
 const JS_MAX_SAFE_INTEGER: u64 = (1u64 << 53) - 1;
 
 /// Shorten hashes and handle any collisions.
@@ -135,3 +275,87 @@ fn finalize_module_ids(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashSet;
+    use turbo_rcstr::RcStr;
+    use turbo_tasks::FxIndexMap;
+    use turbopack_core::chunk::module_id_strategies::hashed_module_id;
+
+    use super::assign_hashed_module_ids;
+
+    fn idents(range: std::ops::Range<usize>) -> Vec<RcStr> {
+        range
+            .map(|i| format!("[project]/src/module{i}.js").into())
+            .collect()
+    }
+
+    fn assert_unique(ids: &FxIndexMap<RcStr, RcStr>) {
+        assert_eq!(ids.values().collect::<FxHashSet<_>>().len(), ids.len());
+    }
+
+    #[test]
+    fn without_collisions() {
+        let ids = assign_hashed_module_ids(idents(0..10), 16, &Default::default());
+        assert_eq!(ids.len(), 10);
+        for (ident, id) in &ids {
+            assert_eq!(**id, hashed_module_id(ident, 16));
+        }
+    }
+
+    #[test]
+    fn collisions_extend_ids() {
+        // There are only 16 ids with a single digit
+        let ids = assign_hashed_module_ids(idents(0..40), 1, &Default::default());
+        assert_eq!(ids.len(), 40);
+        assert_unique(&ids);
+        for (ident, id) in &ids {
+            assert!(id.starts_with(&hashed_module_id(ident, 1)));
+        }
+        // The module with the smallest ident keeps the shortened hash
+        let mut idents = idents(0..40);
+        idents.sort();
+        assert_eq!(*ids[&idents[0]], hashed_module_id(&idents[0], 1));
+    }
+
+    #[test]
+    fn collisions_are_deterministic() {
+        let mut reversed = idents(0..40);
+        reversed.reverse();
+        assert_eq!(
+            assign_hashed_module_ids(idents(0..40), 1, &Default::default()),
+            assign_hashed_module_ids(reversed, 1, &Default::default())
+        );
+    }
+
+    #[test]
+    fn new_modules_dont_change_existing_ids() {
+        let previous_ids = assign_hashed_module_ids(idents(20..40), 1, &Default::default());
+        // Modules whose idents sort before the existing ones collide with them
+        let ids = assign_hashed_module_ids(idents(0..40), 1, &previous_ids);
+        assert_unique(&ids);
+        for (ident, id) in &previous_ids {
+            assert_eq!(&ids[ident], id);
+        }
+    }
+
+    #[test]
+    fn removed_modules_dont_change_existing_ids() {
+        let previous_ids = assign_hashed_module_ids(idents(0..40), 1, &Default::default());
+        let ids = assign_hashed_module_ids(idents(10..40), 1, &previous_ids);
+        assert_eq!(ids.len(), 30);
+        for (ident, id) in &ids {
+            assert_eq!(&previous_ids[ident], id);
+        }
+    }
+
+    #[test]
+    fn previous_ids_of_another_hash_length() {
+        let previous_ids = assign_hashed_module_ids(idents(0..10), 4, &Default::default());
+        let ids = assign_hashed_module_ids(idents(0..10), 8, &previous_ids);
+        for (ident, id) in &ids {
+            assert_eq!(**id, hashed_module_id(ident, 8));
+        }
+    }
+}
@@ -240,7 +240,8 @@ async fn apply_module_type(
                     .module_options_context()
                     .await?
                     .css
-                    .minify_type,
+                    .minify_type
+                    .clone(),
                 css_import_context,
             )
             .to_resolved()
@@ -414,6 +415,36 @@ impl ModuleAssetContext {
             *this.layer,
         ))
     }
+
+    /// The context for the modules of a web worker that is started from a module of this context.
+    /// They are compiled for an environment without DOM, in a separate layer, and `typeof window`
+    /// is not inlined as `"object"` in them.
+    #[turbo_tasks::function]
+    pub async fn web_worker_context(self: Vc<Self>) -> Result<Vc<ModuleAssetContext>> {
+        let this = self.await?;
+        let compile_time_info = this
+            .compile_time_info
+            .for_web_worker()
+            .to_resolved()
+            .await?;
+        if compile_time_info == this.compile_time_info {
+            return Ok(self);
+        }
+        let layer = format!("{}-worker", this.layer.await?);
+        Ok(Self::cell(ModuleAssetContext {
+            transitions: this.transitions,
+            compile_time_info,
+            module_options_context: this
+                .module_options_context
+                .for_web_worker()
+                .to_resolved()
+                .await?,
+            resolve_options_context: this.resolve_options_context,
+            layer: ResolvedVc::cell(layer.into()),
+            transition: None,
+            replace_externals: this.replace_externals,
+        }))
+    }
 }
 
 impl ModuleAssetContext {
@@ -431,6 +462,10 @@ impl ModuleAssetContext {
                 .await?
             {
                 transition.process(*source, self, reference_type)
+            } else if matches!(*reference_type, ReferenceType::Worker(_)) {
+                self.web_worker_context()
+                    .process_default(source, reference_type)
+                    .await?
             } else {
                 self.process_default(source, reference_type).await?
             },
@@ -709,11 +744,24 @@ impl AssetContext for ModuleAssetContext {
         } else {
             self
         };
+        let resolve_options_context = module_asset_context.await?.resolve_options_context;
         // TODO move `apply_commonjs/esm_resolve_options` etc. to here
-        Ok(resolve_options(
+        let resolve_options = resolve_options(
             origin_path.parent().resolve().await?,
-            *module_asset_context.await?.resolve_options_context,
-        ))
+            *resolve_options_context,
+        );
+
+        let issuer_conditions = &resolve_options_context.await?.issuer_conditions;
+        if !issuer_conditions.is_empty() {
+            let origin_path = &*origin_path.await?;
+            for (condition, conditions) in issuer_conditions {
+                if condition.matches(origin_path).await? {
+                    return Ok(resolve_options.with_conditions(conditions.clone()));
+                }
+            }
+        }
+
+        Ok(resolve_options)
     }
 
     #[turbo_tasks::function]
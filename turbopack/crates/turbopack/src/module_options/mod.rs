@@ -130,6 +130,7 @@ impl ModuleOptions {
                     esm_url_rewrite_behavior,
                     ref enable_typeof_window_inlining,
                     source_maps: ecmascript_source_maps,
+                    keep_unused_exports,
                     ..
                 },
             enable_mdx,
@@ -143,6 +144,7 @@ impl ModuleOptions {
             ref enable_postcss_transform,
             ref enable_webpack_loaders,
             preset_env_versions,
+            preset_env_es5,
             ref module_rules,
             execution_context,
             tree_shaking_mode,
@@ -177,11 +179,14 @@ impl ModuleOptions {
             refresh,
             extract_source_map: matches!(ecmascript_source_maps, SourceMapsType::Full),
             keep_last_successful_parse,
+            keep_unused_exports,
             ..Default::default()
         };
         let ecmascript_options_vc = ecmascript_options.resolved_cell();
 
-        if let Some(env) = preset_env_versions {
+        if preset_env_es5 {
+            transforms.push(EcmascriptInputTransform::PresetEnvEs5);
+        } else if let Some(env) = preset_env_versions {
             transforms.push(EcmascriptInputTransform::PresetEnv(env));
         }
 
@@ -1,6 +1,9 @@
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use turbo_rcstr::RcStr;
-use turbo_tasks::{trace::TraceRawVcs, FxIndexMap, NonLocalValue, ResolvedVc, ValueDefault, Vc};
+use turbo_tasks::{
+    trace::TraceRawVcs, FxIndexMap, NonLocalValue, ResolvedVc, TryJoinIterExt, ValueDefault, Vc,
+};
 use turbo_tasks_fs::FileSystemPath;
 use turbopack_core::{
     chunk::{MinifyType, SourceMapsType},
@@ -46,7 +49,8 @@ pub enum DecoratorsKind {
     Ecma,
 }
 
-/// The types when replacing `typeof window` with a constant.
+/// The types when replacing `typeof window` and `typeof document` with a constant. Both are
+/// defined in a browser with DOM and both are missing elsewhere.
 #[derive(Clone, PartialEq, Eq, Debug, TraceRawVcs, Serialize, Deserialize, NonLocalValue)]
 pub enum TypeofWindow {
     Object,
@@ -124,6 +128,10 @@ pub struct ModuleOptionsContext {
     pub enable_mdx_rs: Option<ResolvedVc<MdxTransformOptions>>,
 
     pub preset_env_versions: Option<ResolvedVc<Environment>>,
+    /// Compiles all code to ES5, including generators and async functions (with regenerator),
+    /// instead of only the syntax that [Self::preset_env_versions] don't support. Used for legacy
+    /// `nomodule` output.
+    pub preset_env_es5: bool,
     pub execution_context: Option<ResolvedVc<ExecutionContext>>,
     pub side_effect_free_packages: Vec<RcStr>,
     pub tree_shaking_mode: Option<TreeShakingMode>,
@@ -169,6 +177,9 @@ pub struct EcmascriptOptionsContext {
     pub ignore_dynamic_requests: bool,
     /// Specifies how Source Maps are handled.
     pub source_maps: SourceMapsType,
+    /// Keep exports that are unused in the module graph when simple tree shaking is enabled. This
+    /// is useful for debugging.
+    pub keep_unused_exports: bool,
 
     pub placeholder_for_future_extensions: (),
 }
@@ -192,6 +203,47 @@ pub struct CssOptionsContext {
     pub placeholder_for_future_extensions: (),
 }
 
+#[turbo_tasks::value_impl]
+impl ModuleOptionsContext {
+    /// The module options for the modules of a web worker that is started from a module of this
+    /// context. Web workers have no `window` and `document`, so their `typeof` is inlined as
+    /// `"undefined"` instead, also in the contexts of the [ModuleOptionsContext::rules].
+    #[turbo_tasks::function]
+    pub async fn for_web_worker(self: Vc<Self>) -> Result<Vc<Self>> {
+        let this = self.await?;
+        let enable_typeof_window_inlining = this
+            .ecmascript
+            .enable_typeof_window_inlining
+            .as_ref()
+            .map(|_| TypeofWindow::Undefined);
+        let rules = this
+            .rules
+            .iter()
+            .map(|(condition, context)| async move {
+                Ok((
+                    condition.clone(),
+                    context.for_web_worker().to_resolved().await?,
+                ))
+            })
+            .try_join()
+            .await?;
+        if enable_typeof_window_inlining == this.ecmascript.enable_typeof_window_inlining
+            && rules == this.rules
+        {
+            return Ok(self);
+        }
+        Ok(ModuleOptionsContext {
+            ecmascript: EcmascriptOptionsContext {
+                enable_typeof_window_inlining,
+                ..this.ecmascript.clone()
+            },
+            rules,
+            ..(*this).clone()
+        }
+        .cell())
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl ValueDefault for ModuleOptionsContext {
     #[turbo_tasks::function]